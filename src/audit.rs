@@ -0,0 +1,19 @@
+//! # Audit Trail
+//!
+//! Structured, queryable record of security-relevant events.
+//!
+//! - [`event`] — the [`AuditEvent`](event::AuditEvent) value object and the
+//!   built-in action names (uploads, login/logout, CSRF failures)
+//! - [`store`] — the [`AuditStore`](store::AuditStore) port and
+//!   [`AuditQuery`](store::AuditQuery) filters (actor, entity, time range)
+//! - [`db_audit_store`] — [`AuditStore`](store::AuditStore) over the
+//!   [`Db`](crate::db::port::Db) port
+//! - [`context`] — request extractor that emits events with the current actor
+//!
+//! Recording is best-effort: a failing store is logged and never turns a
+//! successful request into an error.
+
+pub mod context;
+pub mod db_audit_store;
+pub mod event;
+pub mod store;
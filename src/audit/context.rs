@@ -0,0 +1,200 @@
+//! # Audit Context Extractor
+//!
+//! [`AuditContext`] is an Axum extractor that picks up the optional
+//! `Arc<dyn AuditStore>` extension and the optional [`CurrentUser`] request
+//! extension, so handlers can emit events without extra plumbing.
+//!
+//! When no store is registered, emitting is a no-op.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use axum::{routing::post, Extension, Router};
+//! use wzs_web::audit::context::AuditContext;
+//! use wzs_web::audit::event::AuditEvent;
+//! use wzs_web::audit::store::AuditStore;
+//!
+//! async fn logout(audit: AuditContext) -> &'static str {
+//!     if let Some(subject) = audit.actor() {
+//!         audit.emit(AuditEvent::logout(subject)).await;
+//!     }
+//!     "bye"
+//! }
+//!
+//! fn router(store: Arc<dyn AuditStore>) -> Router {
+//!     Router::new()
+//!         .route("/logout", post(logout))
+//!         .layer(Extension(store))
+//! }
+//! ```
+
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::{extract::FromRequestParts, http::request::Parts};
+use tracing::warn;
+
+use crate::audit::event::AuditEvent;
+use crate::audit::store::AuditStore;
+use crate::auth::CurrentUser;
+
+/// Per-request handle for emitting audit events.
+#[derive(Clone, Default)]
+pub struct AuditContext {
+    store: Option<Arc<dyn AuditStore>>,
+    actor: Option<String>,
+    path: String,
+}
+
+impl AuditContext {
+    /// Creates a context explicitly (outside of extraction, e.g. in tests).
+    pub fn new(store: Option<Arc<dyn AuditStore>>, actor: Option<String>) -> Self {
+        Self {
+            store,
+            actor,
+            path: String::new(),
+        }
+    }
+
+    /// Subject of the authenticated principal, if any.
+    pub fn actor(&self) -> Option<&str> {
+        self.actor.as_deref()
+    }
+
+    /// Request path the context was extracted from.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Records `event`, filling in the actor when the event has none.
+    ///
+    /// The store is blocking (it may write to the database), so the record
+    /// runs on the blocking thread pool. Store errors are logged and
+    /// swallowed.
+    pub async fn emit(&self, mut event: AuditEvent) {
+        let Some(store) = self.store.clone() else {
+            return;
+        };
+        if event.actor.is_none() {
+            event.actor = self.actor.clone();
+        }
+        let action = event.action.clone();
+        match tokio::task::spawn_blocking(move || store.record(&event)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("audit record failed ({action}): {e:#}"),
+            Err(e) => warn!("audit record task failed ({action}): {e}"),
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for AuditContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self {
+            store: parts.extensions.get::<Arc<dyn AuditStore>>().cloned(),
+            actor: parts
+                .extensions
+                .get::<CurrentUser>()
+                .map(|u| u.subject.clone()),
+            path: parts.uri.path().to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use anyhow::{bail, Result};
+    use axum::{body::Body, http::Request, routing::get, Extension, Router};
+    use tower::ServiceExt;
+
+    use crate::audit::store::AuditQuery;
+
+    #[derive(Default)]
+    struct MemoryStore {
+        events: Mutex<Vec<AuditEvent>>,
+        fail: bool,
+    }
+
+    impl AuditStore for MemoryStore {
+        fn record(&self, event: &AuditEvent) -> Result<()> {
+            if self.fail {
+                bail!("down");
+            }
+            self.events.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+
+        fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>> {
+            Ok(self
+                .events
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|e| query.matches(e))
+                .cloned()
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn emit_without_store_is_noop() {
+        AuditContext::default().emit(AuditEvent::new("x")).await;
+    }
+
+    #[tokio::test]
+    async fn emit_fills_missing_actor_only() {
+        let store = Arc::new(MemoryStore::default());
+        let ctx = AuditContext::new(Some(store.clone()), Some("7".into()));
+
+        ctx.emit(AuditEvent::new("a")).await;
+        ctx.emit(AuditEvent::login("9", true)).await;
+
+        let events = store.events.lock().unwrap();
+        assert_eq!(events[0].actor.as_deref(), Some("7"));
+        assert_eq!(events[1].actor.as_deref(), Some("9"));
+    }
+
+    #[tokio::test]
+    async fn emit_swallows_store_errors() {
+        let store = Arc::new(MemoryStore {
+            fail: true,
+            ..Default::default()
+        });
+        AuditContext::new(Some(store), None)
+            .emit(AuditEvent::new("a"))
+            .await;
+    }
+
+    #[tokio::test]
+    async fn extractor_reads_store_user_and_path() {
+        async fn handler(audit: AuditContext) -> String {
+            audit
+                .emit(AuditEvent::new("ping").detail(audit.path().to_string()))
+                .await;
+            audit.actor().unwrap_or("-").to_string()
+        }
+
+        let store = Arc::new(MemoryStore::default());
+        let app = Router::new()
+            .route("/ping", get(handler))
+            .layer(Extension(store.clone() as Arc<dyn AuditStore>))
+            .layer(Extension(CurrentUser::new("42")));
+
+        let resp = app
+            .oneshot(Request::get("/ping").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"42");
+
+        let events = store.query(&AuditQuery::by_actor("42")).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].detail.as_deref(), Some("/ping"));
+    }
+}
@@ -0,0 +1,278 @@
+//! # Database Audit Store
+//!
+//! [`AuditStore`] implementation over the [`Db`] port.
+//!
+//! Expected schema (MySQL):
+//!
+//! ```sql
+//! CREATE TABLE audit_events (
+//!     id          BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+//!     occurred_at DATETIME     NOT NULL,
+//!     action      VARCHAR(64)  NOT NULL,
+//!     actor       VARCHAR(255) NULL,
+//!     entity_type VARCHAR(64)  NULL,
+//!     entity_id   VARCHAR(255) NULL,
+//!     outcome     VARCHAR(16)  NOT NULL,
+//!     detail      TEXT         NULL,
+//!     INDEX idx_audit_actor (actor, occurred_at),
+//!     INDEX idx_audit_entity (entity_type, entity_id, occurred_at),
+//!     INDEX idx_audit_occurred_at (occurred_at)
+//! );
+//! ```
+
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::audit::event::{AuditEvent, AuditOutcome};
+use crate::audit::store::{AuditQuery, AuditStore};
+use crate::db::port::{Db, Param, Row};
+use crate::db::repository::ident;
+
+const COLUMNS: &str = "occurred_at, action, actor, entity_type, entity_id, outcome, detail";
+
+/// [`AuditStore`] persisted in a SQL table.
+#[derive(Clone)]
+pub struct DbAuditStore {
+    db: Arc<dyn Db>,
+    table: String,
+}
+
+impl DbAuditStore {
+    /// Default table name.
+    pub const DEFAULT_TABLE: &'static str = "audit_events";
+
+    /// Creates a store backed by the default table.
+    pub fn new(db: Arc<dyn Db>) -> Self {
+        Self {
+            db,
+            table: Self::DEFAULT_TABLE.into(),
+        }
+    }
+
+    /// Creates a store backed by a custom table.
+    ///
+    /// # Errors
+    /// Returns an error if `table` is not a plain SQL identifier.
+    pub fn with_table(db: Arc<dyn Db>, table: impl Into<String>) -> Result<Self> {
+        let table = table.into();
+        ident(&table)?;
+        Ok(Self { db, table })
+    }
+}
+
+impl AuditStore for DbAuditStore {
+    fn record(&self, event: &AuditEvent) -> Result<()> {
+        let sql = format!(
+            "INSERT INTO {} ({COLUMNS}) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            self.table
+        );
+        self.db.exec(
+            &sql,
+            &[
+                Param::DateTime(event.occurred_at),
+                Param::Str(&event.action),
+                event.actor.as_deref().into(),
+                event.entity_type.as_deref().into(),
+                event.entity_id.as_deref().into(),
+                Param::Str(event.outcome.as_str()),
+                event.detail.as_deref().into(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>> {
+        let mut conds: Vec<&str> = Vec::new();
+        let mut params: Vec<Param> = Vec::new();
+
+        if let Some(v) = &query.actor {
+            conds.push("actor = ?");
+            params.push(Param::Str(v));
+        }
+        if let Some(v) = &query.entity_type {
+            conds.push("entity_type = ?");
+            params.push(Param::Str(v));
+        }
+        if let Some(v) = &query.entity_id {
+            conds.push("entity_id = ?");
+            params.push(Param::Str(v));
+        }
+        if let Some(v) = &query.action {
+            conds.push("action = ?");
+            params.push(Param::Str(v));
+        }
+        if let Some(v) = query.from {
+            conds.push("occurred_at >= ?");
+            params.push(Param::DateTime(v));
+        }
+        if let Some(v) = query.to {
+            conds.push("occurred_at < ?");
+            params.push(Param::DateTime(v));
+        }
+
+        let mut sql = format!("SELECT {COLUMNS} FROM {}", self.table);
+        if !conds.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conds.join(" AND "));
+        }
+        sql.push_str(" ORDER BY occurred_at DESC, id DESC");
+        if let Some(limit) = query.limit {
+            sql.push_str(" LIMIT ?");
+            params.push(Param::U64(limit));
+        }
+
+        self.db
+            .fetch_all(&sql, &params)?
+            .iter()
+            .map(row_to_event)
+            .collect()
+    }
+}
+
+fn row_to_event(row: &Row) -> Result<AuditEvent> {
+    Ok(AuditEvent {
+        occurred_at: row.get_datetime("occurred_at")?,
+        action: row.get_string("action")?,
+        actor: row.get_string_opt("actor")?,
+        entity_type: row.get_string_opt("entity_type")?,
+        entity_id: row.get_string_opt("entity_id")?,
+        outcome: AuditOutcome::parse(&row.get_string("outcome")?)?,
+        detail: row.get_string_opt("detail")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use chrono::NaiveDate;
+
+    use crate::db::port::Value;
+
+    #[derive(Default)]
+    struct RecordingDb {
+        calls: Mutex<Vec<(String, Vec<String>)>>,
+        rows: Vec<Row>,
+    }
+
+    impl RecordingDb {
+        fn record(&self, sql: &str, params: &[Param]) {
+            self.calls.lock().unwrap().push((
+                sql.to_string(),
+                params.iter().map(|p| format!("{p:?}")).collect(),
+            ));
+        }
+    }
+
+    impl Db for RecordingDb {
+        fn fetch_one(&self, sql: &str, params: &[Param]) -> Result<Option<Row>> {
+            self.record(sql, params);
+            Ok(None)
+        }
+
+        fn fetch_all(&self, sql: &str, params: &[Param]) -> Result<Vec<Row>> {
+            self.record(sql, params);
+            Ok(self.rows.clone())
+        }
+
+        fn exec(&self, sql: &str, params: &[Param]) -> Result<u64> {
+            self.record(sql, params);
+            Ok(1)
+        }
+
+        fn exec_returning_last_insert_id(&self, sql: &str, params: &[Param]) -> Result<u64> {
+            self.record(sql, params);
+            Ok(1)
+        }
+    }
+
+    fn at(day: u32) -> chrono::NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 3, day)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn record_inserts_all_columns_with_nulls() {
+        let db = Arc::new(RecordingDb::default());
+        let store = DbAuditStore::new(db.clone());
+
+        store
+            .record(&AuditEvent::csrf_rejected("/upload").at(at(1)))
+            .unwrap();
+
+        let calls = db.calls.lock().unwrap();
+        assert_eq!(
+            calls[0].0,
+            "INSERT INTO audit_events (occurred_at, action, actor, entity_type, entity_id, outcome, detail) VALUES (?, ?, ?, ?, ?, ?, ?)"
+        );
+        assert_eq!(calls[0].1[1], "Str(\"csrf.rejected\")");
+        assert_eq!(calls[0].1[2], "Null");
+        assert_eq!(calls[0].1[5], "Str(\"failure\")");
+        assert_eq!(calls[0].1[6], "Str(\"/upload\")");
+    }
+
+    #[test]
+    fn query_without_filters_selects_newest_first() {
+        let db = Arc::new(RecordingDb::default());
+        DbAuditStore::new(db.clone())
+            .query(&AuditQuery::default())
+            .unwrap();
+
+        let calls = db.calls.lock().unwrap();
+        assert!(calls[0]
+            .0
+            .ends_with("FROM audit_events ORDER BY occurred_at DESC, id DESC"));
+        assert!(calls[0].1.is_empty());
+    }
+
+    #[test]
+    fn query_combines_filters_and_limit() {
+        let db = Arc::new(RecordingDb::default());
+        let q = AuditQuery::by_entity("upload", "a.png")
+            .action("upload")
+            .between(at(1), at(5))
+            .limit(10);
+        DbAuditStore::new(db.clone()).query(&q).unwrap();
+
+        let calls = db.calls.lock().unwrap();
+        assert!(calls[0].0.contains(
+            "WHERE entity_type = ? AND entity_id = ? AND action = ? AND occurred_at >= ? AND occurred_at < ?"
+        ));
+        assert!(calls[0].0.ends_with("LIMIT ?"));
+        assert_eq!(calls[0].1.len(), 6);
+        assert_eq!(calls[0].1[5], "U64(10)");
+    }
+
+    #[test]
+    fn query_maps_rows_to_events() {
+        let mut row = Row::default();
+        row.insert("occurred_at", Value::DateTime(at(2)));
+        row.insert("action", Value::Str("auth.login".into()));
+        row.insert("actor", Value::Str("42".into()));
+        row.insert("entity_type", Value::Null);
+        row.insert("entity_id", Value::Null);
+        row.insert("outcome", Value::Str("failure".into()));
+        row.insert("detail", Value::Null);
+
+        let db = Arc::new(RecordingDb {
+            rows: vec![row],
+            ..Default::default()
+        });
+        let events = DbAuditStore::new(db)
+            .query(&AuditQuery::by_actor("42"))
+            .unwrap();
+
+        assert_eq!(events, vec![AuditEvent::login("42", false).at(at(2))]);
+    }
+
+    #[test]
+    fn rejects_unsafe_table_names() {
+        let db: Arc<dyn Db> = Arc::new(RecordingDb::default());
+        assert!(DbAuditStore::with_table(db.clone(), "audit; DROP TABLE x").is_err());
+        assert!(DbAuditStore::with_table(db, "security_audit").is_ok());
+    }
+}
@@ -0,0 +1,195 @@
+//! # Audit Events
+//!
+//! [`AuditEvent`] describes *who* did *what* to *which entity*, *when*, and
+//! whether it succeeded.
+//!
+//! # Example
+//! ```rust
+//! use wzs_web::audit::event::{actions, AuditEvent, AuditOutcome};
+//!
+//! let event = AuditEvent::new(actions::UPLOAD)
+//!     .actor("42")
+//!     .entity("upload", "images/a.png")
+//!     .detail("bytes=1024");
+//!
+//! assert_eq!(event.action, "upload");
+//! assert_eq!(event.outcome, AuditOutcome::Success);
+//! ```
+
+use anyhow::{bail, Result};
+use chrono::{NaiveDateTime, Utc};
+
+/// Built-in action names emitted by this crate.
+pub mod actions {
+    /// A file was uploaded (or an upload attempt failed).
+    pub const UPLOAD: &str = "upload";
+    /// A login attempt.
+    pub const LOGIN: &str = "auth.login";
+    /// A logout.
+    pub const LOGOUT: &str = "auth.logout";
+    /// A request was rejected by CSRF validation.
+    pub const CSRF_REJECTED: &str = "csrf.rejected";
+    /// A tracked email was opened.
+    pub const EMAIL_OPEN: &str = "email.open";
+    /// A tracked link in an email was clicked.
+    pub const EMAIL_CLICK: &str = "email.click";
+}
+
+/// Result of an audited action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+impl AuditOutcome {
+    /// Returns the stored representation (`"success"` / `"failure"`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditOutcome::Success => "success",
+            AuditOutcome::Failure => "failure",
+        }
+    }
+
+    /// Parses the stored representation.
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "success" => Ok(AuditOutcome::Success),
+            "failure" => Ok(AuditOutcome::Failure),
+            other => bail!("unknown audit outcome: {other}"),
+        }
+    }
+}
+
+/// A single audit trail entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditEvent {
+    /// When the event happened (UTC).
+    pub occurred_at: NaiveDateTime,
+    /// Action name (see [`actions`]).
+    pub action: String,
+    /// Principal that performed the action (usually the JWT subject).
+    pub actor: Option<String>,
+    /// Kind of the affected entity (e.g. `"upload"`).
+    pub entity_type: Option<String>,
+    /// Identifier of the affected entity (e.g. a storage key).
+    pub entity_id: Option<String>,
+    /// Whether the action succeeded.
+    pub outcome: AuditOutcome,
+    /// Free-form detail (error message, request path, ...).
+    pub detail: Option<String>,
+}
+
+impl AuditEvent {
+    /// Creates a successful event for `action`, timestamped now (UTC).
+    pub fn new(action: impl Into<String>) -> Self {
+        Self {
+            occurred_at: Utc::now().naive_utc(),
+            action: action.into(),
+            actor: None,
+            entity_type: None,
+            entity_id: None,
+            outcome: AuditOutcome::Success,
+            detail: None,
+        }
+    }
+
+    /// Sets the actor.
+    pub fn actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into());
+        self
+    }
+
+    /// Sets the affected entity.
+    pub fn entity(mut self, entity_type: impl Into<String>, entity_id: impl Into<String>) -> Self {
+        self.entity_type = Some(entity_type.into());
+        self.entity_id = Some(entity_id.into());
+        self
+    }
+
+    /// Marks the event as failed.
+    pub fn failed(mut self) -> Self {
+        self.outcome = AuditOutcome::Failure;
+        self
+    }
+
+    /// Sets the free-form detail.
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Overrides the timestamp.
+    pub fn at(mut self, occurred_at: NaiveDateTime) -> Self {
+        self.occurred_at = occurred_at;
+        self
+    }
+
+    /// Login attempt for `subject` (success or failure).
+    pub fn login(subject: impl Into<String>, success: bool) -> Self {
+        let ev = Self::new(actions::LOGIN).actor(subject);
+        if success {
+            ev
+        } else {
+            ev.failed()
+        }
+    }
+
+    /// Logout of `subject`.
+    pub fn logout(subject: impl Into<String>) -> Self {
+        Self::new(actions::LOGOUT).actor(subject)
+    }
+
+    /// CSRF rejection on `path`.
+    pub fn csrf_rejected(path: impl Into<String>) -> Self {
+        Self::new(actions::CSRF_REJECTED).failed().detail(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn builder_sets_all_fields() {
+        let at = NaiveDate::from_ymd_opt(2025, 1, 2)
+            .unwrap()
+            .and_hms_opt(3, 4, 5)
+            .unwrap();
+        let ev = AuditEvent::new(actions::UPLOAD)
+            .actor("7")
+            .entity("upload", "a.png")
+            .failed()
+            .detail("disk full")
+            .at(at);
+
+        assert_eq!(ev.occurred_at, at);
+        assert_eq!(ev.action, "upload");
+        assert_eq!(ev.actor.as_deref(), Some("7"));
+        assert_eq!(ev.entity_type.as_deref(), Some("upload"));
+        assert_eq!(ev.entity_id.as_deref(), Some("a.png"));
+        assert_eq!(ev.outcome, AuditOutcome::Failure);
+        assert_eq!(ev.detail.as_deref(), Some("disk full"));
+    }
+
+    #[test]
+    fn login_logout_and_csrf_constructors() {
+        assert_eq!(AuditEvent::login("1", true).outcome, AuditOutcome::Success);
+        assert_eq!(AuditEvent::login("1", false).outcome, AuditOutcome::Failure);
+        assert_eq!(AuditEvent::logout("1").action, actions::LOGOUT);
+
+        let csrf = AuditEvent::csrf_rejected("/upload");
+        assert_eq!(csrf.action, actions::CSRF_REJECTED);
+        assert_eq!(csrf.outcome, AuditOutcome::Failure);
+        assert_eq!(csrf.detail.as_deref(), Some("/upload"));
+    }
+
+    #[test]
+    fn outcome_roundtrips_through_str() {
+        for o in [AuditOutcome::Success, AuditOutcome::Failure] {
+            assert_eq!(AuditOutcome::parse(o.as_str()).unwrap(), o);
+        }
+        assert!(AuditOutcome::parse("maybe").is_err());
+    }
+}
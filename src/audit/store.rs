@@ -0,0 +1,142 @@
+//! # Audit Store Port
+//!
+//! [`AuditStore`] persists [`AuditEvent`]s and answers [`AuditQuery`]s.
+//!
+//! # Example
+//! ```rust
+//! use chrono::NaiveDate;
+//! use wzs_web::audit::store::AuditQuery;
+//!
+//! let from = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+//! let to = NaiveDate::from_ymd_opt(2025, 2, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+//!
+//! let q = AuditQuery::by_actor("42").between(from, to).limit(50);
+//! assert_eq!(q.actor.as_deref(), Some("42"));
+//! ```
+
+use anyhow::Result;
+use chrono::NaiveDateTime;
+
+use crate::audit::event::AuditEvent;
+
+/// Port for persisting and querying audit events.
+pub trait AuditStore: Send + Sync {
+    /// Persists one event.
+    fn record(&self, event: &AuditEvent) -> Result<()>;
+
+    /// Returns events matching `query`, newest first.
+    fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>>;
+}
+
+/// Filters for [`AuditStore::query`]. All set filters are combined with AND.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AuditQuery {
+    /// Only events by this actor.
+    pub actor: Option<String>,
+    /// Only events on entities of this type.
+    pub entity_type: Option<String>,
+    /// Only events on this entity id (usually combined with `entity_type`).
+    pub entity_id: Option<String>,
+    /// Only events with this action.
+    pub action: Option<String>,
+    /// Inclusive lower bound on `occurred_at`.
+    pub from: Option<NaiveDateTime>,
+    /// Exclusive upper bound on `occurred_at`.
+    pub to: Option<NaiveDateTime>,
+    /// Maximum number of events to return.
+    pub limit: Option<u64>,
+}
+
+impl AuditQuery {
+    /// Events performed by `actor`.
+    pub fn by_actor(actor: impl Into<String>) -> Self {
+        Self {
+            actor: Some(actor.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Events affecting one entity.
+    pub fn by_entity(entity_type: impl Into<String>, entity_id: impl Into<String>) -> Self {
+        Self {
+            entity_type: Some(entity_type.into()),
+            entity_id: Some(entity_id.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Restricts to one action.
+    pub fn action(mut self, action: impl Into<String>) -> Self {
+        self.action = Some(action.into());
+        self
+    }
+
+    /// Restricts to `[from, to)`.
+    pub fn between(mut self, from: NaiveDateTime, to: NaiveDateTime) -> Self {
+        self.from = Some(from);
+        self.to = Some(to);
+        self
+    }
+
+    /// Caps the number of results.
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Returns `true` if `event` satisfies every filter (ignores `limit`).
+    ///
+    /// Useful for in-memory stores.
+    pub fn matches(&self, event: &AuditEvent) -> bool {
+        fn eq(filter: &Option<String>, value: &Option<String>) -> bool {
+            filter.is_none() || filter == value
+        }
+
+        eq(&self.actor, &event.actor)
+            && eq(&self.entity_type, &event.entity_type)
+            && eq(&self.entity_id, &event.entity_id)
+            && self.action.as_ref().is_none_or(|a| *a == event.action)
+            && self.from.is_none_or(|f| event.occurred_at >= f)
+            && self.to.is_none_or(|t| event.occurred_at < t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 1, day)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn by_actor_matches_only_that_actor() {
+        let q = AuditQuery::by_actor("1");
+        assert!(q.matches(&AuditEvent::login("1", true)));
+        assert!(!q.matches(&AuditEvent::login("2", true)));
+        assert!(!q.matches(&AuditEvent::csrf_rejected("/x")));
+    }
+
+    #[test]
+    fn by_entity_and_action_filters_combine() {
+        let ev = AuditEvent::new("upload").entity("upload", "a.png");
+        assert!(AuditQuery::by_entity("upload", "a.png").matches(&ev));
+        assert!(!AuditQuery::by_entity("upload", "b.png").matches(&ev));
+        assert!(!AuditQuery::by_entity("upload", "a.png")
+            .action("auth.login")
+            .matches(&ev));
+    }
+
+    #[test]
+    fn time_range_is_half_open() {
+        let q = AuditQuery::default().between(at(2), at(4));
+        assert!(!q.matches(&AuditEvent::new("x").at(at(1))));
+        assert!(q.matches(&AuditEvent::new("x").at(at(2))));
+        assert!(q.matches(&AuditEvent::new("x").at(at(3))));
+        assert!(!q.matches(&AuditEvent::new("x").at(at(4))));
+    }
+}
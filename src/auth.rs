@@ -1,4 +1,13 @@
+pub mod authorize;
 pub mod jwt;
+pub mod oauth;
+pub mod otp;
+pub mod password;
+pub mod password_policy;
 pub mod principal;
+pub mod refresh;
+pub mod revocation;
+pub mod session;
+pub mod throttle;
 
 pub use principal::CurrentUser;
@@ -0,0 +1,311 @@
+//! # Role and scope checks
+//!
+//! Common authorization checks on the roles and scopes carried by
+//! [`CurrentUser`] (see [`CurrentUser::from_claims`]), so applications do
+//! not re-implement them per project. What a role *means* stays with the
+//! application.
+//!
+//! | Where | Use |
+//! |-------|-----|
+//! | Axum route group | [`authorize`] middleware with a [`Require`] state |
+//! | Axum handler | [`RequireRole<R>`] extractor |
+//! | GraphQL field | `#[graphql(guard = "Require::role(\"admin\")")]` |
+//!
+//! Axum checks read the [`CurrentUser`] request extension inserted by the
+//! application's authentication; GraphQL checks read
+//! [`RequestContext`](crate::graphql::context::RequestContext). A missing
+//! principal is `401` / `UNAUTHENTICATED`, a missing role or scope `403` /
+//! `FORBIDDEN`.
+//!
+//! # Example
+//! ```rust,no_run
+//! use axum::{middleware::from_fn_with_state, routing::get, Router};
+//! use wzs_web::auth::authorize::{authorize, Require, RequireRole, Role};
+//!
+//! struct Editor;
+//!
+//! impl Role for Editor {
+//!     const NAME: &'static str = "editor";
+//! }
+//!
+//! async fn edit(RequireRole(user, ..): RequireRole<Editor>) -> String {
+//!     format!("editing as {}", user.subject)
+//! }
+//!
+//! let admin: Router = Router::new()
+//!     .route("/admin/stats", get(|| async { "stats" }))
+//!     .layer(from_fn_with_state(
+//!         Require::role("admin").scope("stats:read"),
+//!         authorize,
+//!     ));
+//! let app: Router = Router::new().route("/edit", get(edit)).merge(admin);
+//! ```
+
+use std::marker::PhantomData;
+
+use async_graphql::{ErrorExtensions, Guard};
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::{request::Parts, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::auth::CurrentUser;
+use crate::graphql::context::RequestContext;
+use crate::web::problem::Problem;
+
+/// Why an authorization check failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthzError {
+    /// No authenticated principal.
+    Unauthenticated,
+    /// Authenticated, but a role or scope is missing.
+    Forbidden,
+}
+
+impl AuthzError {
+    /// HTTP status (`401` / `403`).
+    pub fn status(self) -> StatusCode {
+        match self {
+            Self::Unauthenticated => StatusCode::UNAUTHORIZED,
+            Self::Forbidden => StatusCode::FORBIDDEN,
+        }
+    }
+
+    /// GraphQL error code (`UNAUTHENTICATED` / `FORBIDDEN`).
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::Unauthenticated => "UNAUTHENTICATED",
+            Self::Forbidden => "FORBIDDEN",
+        }
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            Self::Unauthenticated => "authentication required",
+            Self::Forbidden => "permission denied",
+        }
+    }
+}
+
+impl IntoResponse for AuthzError {
+    fn into_response(self) -> Response {
+        Problem::new(self.status())
+            .detail(self.message())
+            .into_response()
+    }
+}
+
+impl From<AuthzError> for async_graphql::Error {
+    fn from(e: AuthzError) -> Self {
+        async_graphql::Error::new(e.message()).extend_with(|_, ext| ext.set("code", e.code()))
+    }
+}
+
+/// Roles and scopes a principal must have.
+///
+/// The principal needs **one of** the listed roles (when any are listed)
+/// and **all** of the listed scopes. An empty requirement only needs an
+/// authenticated principal.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Require {
+    roles: Vec<String>,
+    scopes: Vec<String>,
+}
+
+impl Require {
+    /// Any authenticated principal.
+    pub fn authenticated() -> Self {
+        Self::default()
+    }
+
+    /// Principals with `role`.
+    pub fn role(role: impl Into<String>) -> Self {
+        Self::any_role([role])
+    }
+
+    /// Principals with at least one of `roles`.
+    pub fn any_role<I, R>(roles: I) -> Self
+    where
+        I: IntoIterator<Item = R>,
+        R: Into<String>,
+    {
+        Self {
+            roles: roles.into_iter().map(Into::into).collect(),
+            scopes: Vec::new(),
+        }
+    }
+
+    /// Additionally requires `scope`.
+    pub fn scope(mut self, scope: impl Into<String>) -> Self {
+        self.scopes.push(scope.into());
+        self
+    }
+
+    /// Checks `user` against the requirement.
+    pub fn check(&self, user: Option<&CurrentUser>) -> Result<(), AuthzError> {
+        let user = user.ok_or(AuthzError::Unauthenticated)?;
+        let role_ok = self.roles.is_empty() || self.roles.iter().any(|r| user.has_role(r));
+        let scopes_ok = self.scopes.iter().all(|s| user.has_scope(s));
+        if role_ok && scopes_ok {
+            Ok(())
+        } else {
+            Err(AuthzError::Forbidden)
+        }
+    }
+}
+
+impl Guard for Require {
+    async fn check(&self, ctx: &async_graphql::Context<'_>) -> async_graphql::Result<()> {
+        Require::check(self, RequestContext::of(ctx).user()).map_err(Into::into)
+    }
+}
+
+/// Middleware rejecting requests whose [`CurrentUser`] does not meet the
+/// [`Require`] state.
+///
+/// Mount with `from_fn_with_state(Require::role("admin"), authorize)`,
+/// inside the layer that authenticates the request.
+pub async fn authorize(State(require): State<Require>, req: Request, next: Next) -> Response {
+    match require.check(req.extensions().get::<CurrentUser>()) {
+        Ok(()) => next.run(req).await,
+        Err(e) => e.into_response(),
+    }
+}
+
+/// A role name usable with [`RequireRole`].
+pub trait Role: Send + Sync + 'static {
+    const NAME: &'static str;
+}
+
+/// Extractor yielding the [`CurrentUser`] if it has role `R`, else
+/// rejecting with [`AuthzError`].
+pub struct RequireRole<R: Role>(pub CurrentUser, pub PhantomData<R>);
+
+impl<R, S> FromRequestParts<S> for RequireRole<R>
+where
+    R: Role,
+    S: Send + Sync,
+{
+    type Rejection = AuthzError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        match parts.extensions.get::<CurrentUser>() {
+            Some(user) if user.has_role(R::NAME) => Ok(Self(user.clone(), PhantomData)),
+            Some(_) => Err(AuthzError::Forbidden),
+            None => Err(AuthzError::Unauthenticated),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+    use axum::{body::Body, middleware::from_fn_with_state, routing::get, Extension, Router};
+    use tower::ServiceExt;
+
+    struct Admin;
+
+    impl Role for Admin {
+        const NAME: &'static str = "admin";
+    }
+
+    async fn status(app: Router, user: Option<CurrentUser>) -> StatusCode {
+        let app = match user {
+            Some(user) => app.layer(Extension(user)),
+            None => app,
+        };
+        app.oneshot(axum::http::Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[test]
+    fn roles_are_any_of_and_scopes_all_of() {
+        let require = Require::any_role(["admin", "editor"]).scope("a").scope("b");
+        let editor = CurrentUser::new("1").with_roles(["editor"]);
+
+        assert_eq!(require.check(None), Err(AuthzError::Unauthenticated));
+        assert_eq!(require.check(Some(&editor)), Err(AuthzError::Forbidden));
+        assert_eq!(
+            require.check(Some(&editor.clone().with_scopes(["a"]))),
+            Err(AuthzError::Forbidden)
+        );
+        assert_eq!(require.check(Some(&editor.with_scopes(["b", "a"]))), Ok(()));
+        assert_eq!(
+            Require::authenticated().check(Some(&CurrentUser::new("2"))),
+            Ok(())
+        );
+    }
+
+    #[tokio::test]
+    async fn middleware_maps_failures_to_401_and_403() {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(from_fn_with_state(Require::role("admin"), authorize));
+
+        assert_eq!(status(app.clone(), None).await, StatusCode::UNAUTHORIZED);
+        let user = CurrentUser::new("1");
+        assert_eq!(
+            status(app.clone(), Some(user.clone())).await,
+            StatusCode::FORBIDDEN
+        );
+        let admin = user.with_roles(["admin"]);
+        assert_eq!(status(app, Some(admin)).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn require_role_extractor() {
+        let app = Router::new().route(
+            "/",
+            get(|RequireRole(user, ..): RequireRole<Admin>| async move { user.subject }),
+        );
+        assert_eq!(status(app.clone(), None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            status(app.clone(), Some(CurrentUser::new("1"))).await,
+            StatusCode::FORBIDDEN
+        );
+        let admin = CurrentUser::new("1").with_roles(["admin"]);
+        assert_eq!(status(app, Some(admin)).await, StatusCode::OK);
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        #[graphql(guard = "Require::role(\"admin\")")]
+        async fn secret(&self) -> &str {
+            "42"
+        }
+    }
+
+    #[tokio::test]
+    async fn graphql_guard_sets_error_codes() {
+        let schema = Schema::new(Query, EmptyMutation, EmptySubscription);
+        let run = |user: Option<CurrentUser>| {
+            let schema = schema.clone();
+            async move {
+                let ctx = RequestContext {
+                    current_user: user,
+                    ..Default::default()
+                };
+                let req = async_graphql::Request::new("{ secret }").data(ctx);
+                schema.execute(req).await
+            }
+        };
+
+        let res = run(None).await;
+        let code = res.errors[0].extensions.as_ref().unwrap().get("code");
+        assert_eq!(code, Some(&async_graphql::Value::from("UNAUTHENTICATED")));
+
+        let res = run(Some(CurrentUser::new("1"))).await;
+        let code = res.errors[0].extensions.as_ref().unwrap().get("code");
+        assert_eq!(code, Some(&async_graphql::Value::from("FORBIDDEN")));
+
+        let res = run(Some(CurrentUser::new("1").with_roles(["admin"]))).await;
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+    }
+}
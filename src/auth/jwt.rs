@@ -12,17 +12,30 @@
 //!
 //! ## Provided functions
 //! - [`create_jwt`] — Create a signed JWT token
+//! - [`create_jwt_with_ttl`] — Create a signed JWT token with a custom lifetime
+//! - [`JwtBuilder`] — Create a JWT with a custom TTL, registered claims
+//!   (`iss`, `aud`, `iat`, `nbf`) and extra application claims
 //! - [`decode_jwt`] — Validate and decode a JWT token
+//! - [`decode_jwt_with`] — Same, checking issuer / audience ([`JwtValidation`])
+//!
+//! Refresh tokens are handled by [`refresh`](crate::auth::refresh);
+//! revoking access tokens by their `jti` by
+//! [`revocation`](crate::auth::revocation).
 
-use chrono::{Duration, Utc};
+use anyhow::Context as _;
+use chrono::{DateTime, Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
 
 /// JWT claims stored inside the token payload.
 ///
 /// ## Fields
 /// - `sub`: Subject (user ID)
 /// - `exp`: Expiration time (UNIX timestamp, seconds)
+/// - `iss`, `aud`, `iat`, `nbf`, `jti`: optional registered claims
+/// - `extra`: any other claims (see [`JwtBuilder::claim`])
 ///
 /// This struct is serialized into the JWT payload.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -31,6 +44,218 @@ pub struct Claims {
     pub sub: String,
     /// Expiration timestamp (UTC, seconds since UNIX epoch)
     pub exp: usize,
+    /// Issuer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    /// Audience
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    /// Issued-at timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<usize>,
+    /// Not-before timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<usize>,
+    /// Token ID, used to revoke the token before it expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    /// Application-specific claims
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Claims {
+    /// Deserializes the extra claim `name`; `Ok(None)` if it is absent.
+    pub fn claim<T: DeserializeOwned>(&self, name: &str) -> anyhow::Result<Option<T>> {
+        self.extra
+            .get(name)
+            .map(|v| serde_json::from_value(v.clone()))
+            .transpose()
+            .with_context(|| format!("claim `{name}`"))
+    }
+}
+
+/// Builder for JWTs with custom lifetime and claims.
+///
+/// Tokens carry `sub`, `exp`, `iat` and a random `jti`; `iss`, `aud`,
+/// `nbf` and extra claims are added when set.
+///
+/// ## Example
+/// ```
+/// use chrono::Duration;
+/// use wzs_web::auth::jwt::{decode_jwt_with, JwtBuilder, JwtValidation};
+///
+/// let token = JwtBuilder::new("42")
+///     .ttl(Duration::hours(1))
+///     .issuer("https://auth.example.com")
+///     .audience("api")
+///     .claim("roles", ["admin"])
+///     .sign("test-secret")
+///     .unwrap();
+///
+/// let validation = JwtValidation::new()
+///     .issuer("https://auth.example.com")
+///     .audience("api");
+/// let claims = decode_jwt_with(&token, "test-secret", &validation).unwrap();
+/// assert_eq!(claims.claim::<Vec<String>>("roles").unwrap().unwrap(), ["admin"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct JwtBuilder {
+    sub: String,
+    ttl: Duration,
+    iss: Option<String>,
+    aud: Option<String>,
+    nbf: Option<DateTime<Utc>>,
+    jti: String,
+    extra: Map<String, Value>,
+    error: Option<String>,
+}
+
+/// Registered claim names that [`JwtBuilder::claim`] must not override.
+const REGISTERED_CLAIMS: [&str; 7] = ["sub", "exp", "iss", "aud", "iat", "nbf", "jti"];
+
+impl JwtBuilder {
+    /// Starts a token for `sub` with the default 48-hour lifetime.
+    pub fn new(sub: impl Into<String>) -> Self {
+        Self {
+            sub: sub.into(),
+            ttl: Duration::hours(48),
+            iss: None,
+            aud: None,
+            nbf: None,
+            jti: Uuid::new_v4().to_string(),
+            extra: Map::new(),
+            error: None,
+        }
+    }
+
+    /// Sets the lifetime.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets the `iss` claim.
+    pub fn issuer(mut self, iss: impl Into<String>) -> Self {
+        self.iss = Some(iss.into());
+        self
+    }
+
+    /// Sets the `aud` claim.
+    pub fn audience(mut self, aud: impl Into<String>) -> Self {
+        self.aud = Some(aud.into());
+        self
+    }
+
+    /// Sets the `nbf` claim (the token is rejected before this time).
+    pub fn not_before(mut self, nbf: DateTime<Utc>) -> Self {
+        self.nbf = Some(nbf);
+        self
+    }
+
+    /// Sets the `jti` claim (default: a random UUID).
+    pub fn jwt_id(mut self, jti: impl Into<String>) -> Self {
+        self.jti = jti.into();
+        self
+    }
+
+    /// Adds an application claim.
+    ///
+    /// Serialization failures and registered claim names are reported by
+    /// [`sign`](Self::sign).
+    pub fn claim(mut self, name: impl Into<String>, value: impl Serialize) -> Self {
+        let name = name.into();
+        if REGISTERED_CLAIMS.contains(&name.as_str()) {
+            self.error
+                .get_or_insert(format!("`{name}` is a registered claim"));
+            return self;
+        }
+        match serde_json::to_value(value) {
+            Ok(v) => {
+                self.extra.insert(name, v);
+            }
+            Err(e) => {
+                self.error.get_or_insert(format!("claim `{name}`: {e}"));
+            }
+        }
+        self
+    }
+
+    /// Signs the token with `secret` (HS256).
+    pub fn sign(&self, secret: &str) -> anyhow::Result<String> {
+        if let Some(e) = &self.error {
+            anyhow::bail!("{e}");
+        }
+        let now = Utc::now();
+        let expiration = now
+            .checked_add_signed(self.ttl)
+            .ok_or_else(|| anyhow::anyhow!("invalid token lifetime"))?;
+
+        let claims = Claims {
+            sub: self.sub.clone(),
+            exp: expiration.timestamp() as usize,
+            iss: self.iss.clone(),
+            aud: self.aud.clone(),
+            iat: Some(now.timestamp() as usize),
+            nbf: self.nbf.map(|t| t.timestamp() as usize),
+            jti: Some(self.jti.clone()),
+            extra: self.extra.clone(),
+        };
+
+        Ok(encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(secret.as_bytes()),
+        )?)
+    }
+}
+
+/// Checks applied by [`decode_jwt_with`] in addition to signature, `exp`
+/// and `nbf`.
+///
+/// Tokens carrying an `aud` claim are only accepted when an audience is
+/// configured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JwtValidation {
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+    /// Clock skew tolerance for `exp` / `nbf`, in seconds (default: 60).
+    pub leeway: u64,
+}
+
+impl Default for JwtValidation {
+    fn default() -> Self {
+        Self {
+            issuer: None,
+            audience: None,
+            leeway: 60,
+        }
+    }
+}
+
+impl JwtValidation {
+    /// Signature and time checks only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires `iss` to equal `issuer`.
+    pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Requires `aud` to equal `audience`.
+    pub fn audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = Some(audience.into());
+        self
+    }
+
+    /// Sets the clock skew tolerance.
+    pub fn leeway(mut self, leeway: std::time::Duration) -> Self {
+        self.leeway = leeway.as_secs();
+        self
+    }
 }
 
 /// Creates a signed JWT for the given user ID.
@@ -55,23 +280,21 @@ pub struct Claims {
 /// assert!(!token.is_empty());
 /// ```
 pub fn create_jwt(id: u64, secret: &str) -> anyhow::Result<String> {
-    let expiration = Utc::now()
-        .checked_add_signed(Duration::hours(48))
-        .expect("invalid timestamp")
-        .timestamp() as usize;
-
-    let claims = Claims {
-        sub: id.to_string(),
-        exp: expiration,
-    };
-
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )?;
-
-    Ok(token)
+    create_jwt_with_ttl(&id.to_string(), secret, Duration::hours(48))
+}
+
+/// Creates a signed JWT for `sub` that expires after `ttl`.
+///
+/// ## Example
+/// ```
+/// use chrono::Duration;
+/// use wzs_web::auth::jwt::{create_jwt_with_ttl, decode_jwt};
+///
+/// let token = create_jwt_with_ttl("42", "test-secret", Duration::minutes(15)).unwrap();
+/// assert_eq!(decode_jwt(&token, "test-secret").unwrap().sub, "42");
+/// ```
+pub fn create_jwt_with_ttl(sub: &str, secret: &str, ttl: Duration) -> anyhow::Result<String> {
+    JwtBuilder::new(sub).ttl(ttl).sign(secret)
 }
 
 /// Decodes and validates a JWT token.
@@ -87,7 +310,8 @@ pub fn create_jwt(id: u64, secret: &str) -> anyhow::Result<String> {
 /// Returns an error if:
 /// - The token is malformed
 /// - Signature does not match
-/// - Token is expired
+/// - Token is expired or not yet valid (`nbf`)
+/// - Token carries an `aud` claim (use [`decode_jwt_with`])
 ///
 /// ## Example
 /// ```
@@ -100,11 +324,30 @@ pub fn create_jwt(id: u64, secret: &str) -> anyhow::Result<String> {
 /// assert_eq!(claims.sub, "1");
 /// ```
 pub fn decode_jwt(token: &str, secret: &str) -> anyhow::Result<Claims> {
-    let decoded = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::default(),
-    )?;
+    decode_jwt_with(token, secret, &JwtValidation::default())
+}
+
+/// Decodes and validates a JWT token, also checking issuer and audience.
+///
+/// ## Errors
+/// Same as [`decode_jwt`], plus an `iss` / `aud` mismatch with `validation`.
+pub fn decode_jwt_with(
+    token: &str,
+    secret: &str,
+    validation: &JwtValidation,
+) -> anyhow::Result<Claims> {
+    let mut v = Validation::default();
+    v.validate_nbf = true;
+    v.leeway = validation.leeway;
+    if let Some(iss) = &validation.issuer {
+        v.set_issuer(&[iss]);
+        v.set_required_spec_claims(&["exp", "iss"]);
+    }
+    if let Some(aud) = &validation.audience {
+        v.set_audience(&[aud]);
+    }
+
+    let decoded = decode::<Claims>(token, &DecodingKey::from_secret(secret.as_bytes()), &v)?;
 
     Ok(decoded.claims)
 }
@@ -147,4 +390,79 @@ mod tests {
         let result = decode_jwt("not-a-valid-token", SECRET);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn builder_sets_registered_and_extra_claims() {
+        let token = JwtBuilder::new("9")
+            .ttl(Duration::minutes(10))
+            .issuer("iss-a")
+            .audience("aud-a")
+            .claim("tenant", "acme")
+            .claim("level", 3)
+            .sign(SECRET)
+            .unwrap();
+
+        let validation = JwtValidation::new().issuer("iss-a").audience("aud-a");
+        let claims = decode_jwt_with(&token, SECRET, &validation).unwrap();
+        let now = Utc::now().timestamp() as usize;
+        assert_eq!(claims.sub, "9");
+        assert!(claims.exp <= now + 600 && claims.exp > now + 590);
+        assert!(claims.iat.unwrap() <= now);
+        assert!(claims.jti.is_some());
+        assert_eq!(
+            claims.claim::<String>("tenant").unwrap().as_deref(),
+            Some("acme")
+        );
+        assert_eq!(claims.claim::<u32>("level").unwrap(), Some(3));
+        assert_eq!(claims.claim::<u32>("missing").unwrap(), None);
+        assert!(claims.claim::<u32>("tenant").is_err());
+    }
+
+    #[test]
+    fn validation_checks_issuer_audience_and_not_before() {
+        let token = JwtBuilder::new("1")
+            .issuer("iss-a")
+            .audience("aud-a")
+            .sign(SECRET)
+            .unwrap();
+        assert!(decode_jwt(&token, SECRET).is_err(), "aud requires opt-in");
+        assert!(decode_jwt_with(&token, SECRET, &JwtValidation::new().audience("aud-b")).is_err());
+        assert!(decode_jwt_with(
+            &token,
+            SECRET,
+            &JwtValidation::new().issuer("iss-b").audience("aud-a")
+        )
+        .is_err());
+
+        let plain = create_jwt(1, SECRET).unwrap();
+        assert!(decode_jwt_with(&plain, SECRET, &JwtValidation::new().issuer("iss-a")).is_err());
+
+        let later = JwtBuilder::new("1")
+            .not_before(Utc::now() + Duration::hours(1))
+            .sign(SECRET)
+            .unwrap();
+        assert!(decode_jwt(&later, SECRET).is_err());
+    }
+
+    #[test]
+    fn every_token_gets_its_own_jti() {
+        let a = decode_jwt(&create_jwt(1, SECRET).unwrap(), SECRET).unwrap();
+        let b = decode_jwt(&create_jwt(1, SECRET).unwrap(), SECRET).unwrap();
+        assert_ne!(a.jti, b.jti);
+
+        let token = JwtBuilder::new("1").jwt_id("fixed").sign(SECRET).unwrap();
+        assert_eq!(
+            decode_jwt(&token, SECRET).unwrap().jti.as_deref(),
+            Some("fixed")
+        );
+    }
+
+    #[test]
+    fn builder_rejects_registered_claim_names() {
+        let err = JwtBuilder::new("1")
+            .claim("exp", 0)
+            .sign(SECRET)
+            .unwrap_err();
+        assert!(err.to_string().contains("registered claim"));
+    }
 }
@@ -0,0 +1,3 @@
+pub mod flow;
+pub mod http;
+pub mod provider;
@@ -0,0 +1,863 @@
+//! # OAuth2 / OIDC Login Flow
+//!
+//! Authorization-code login with PKCE against the providers of an [`OAuth`]:
+//!
+//! 1. `GET /auth/{provider}/login?return_to=/account` ([`oauth_login_handler`])
+//!    redirects to the provider. The pending login (state, PKCE verifier,
+//!    nonce, return path) travels in a short-lived cookie signed with a key
+//!    derived from the CSRF secret, so no server-side storage is needed.
+//! 2. The provider redirects back to the registered `redirect_uri`. The
+//!    application's handler takes an [`OAuthCallback`], which checks the
+//!    cookie and `state`, exchanges the code and reads the identity from the
+//!    ID token and/or the userinfo endpoint.
+//! 3. The handler signs the user in (session, JWT, …), clears the cookie and
+//!    redirects to [`return_to`](OAuthIdentity::return_to).
+//!
+//! The ID token is read without checking its signature: it comes straight
+//! from the token endpoint over TLS, which OIDC Core (3.1.3.7) allows for
+//! confidential clients. `iss`, `aud`, `exp` and `nonce` are checked.
+//!
+//! Failures map to [`OAuthError`]; details are logged, not shown.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use axum::{
+//!     http::header::{LOCATION, SET_COOKIE},
+//!     response::IntoResponse,
+//!     routing::get,
+//!     Extension, Router,
+//! };
+//! use wzs_web::auth::oauth::flow::{oauth_login_handler, OAuth, OAuthCallback};
+//! use wzs_web::auth::oauth::provider::OAuthProvider;
+//! use wzs_web::config::csrf::CsrfConfig;
+//!
+//! async fn callback(login: OAuthCallback) -> impl IntoResponse {
+//!     let user = login.identity.current_user(); // e.g. "google:1078…"
+//!     // ... look up or create the local account, start a session ...
+//!     (
+//!         axum::http::StatusCode::SEE_OTHER,
+//!         [
+//!             (LOCATION, login.identity.return_to.clone()),
+//!             (SET_COOKIE, login.clear_cookie.clone()),
+//!         ],
+//!     )
+//! }
+//!
+//! let csrf = CsrfConfig::from_env();
+//! let oauth = OAuth::new(csrf.secret).secure(true).provider(
+//!     OAuthProvider::google().client("id", "secret", "https://app.example.com/auth/callback"),
+//! );
+//!
+//! let app: Router = Router::new()
+//!     .route("/auth/{provider}/login", get(oauth_login_handler))
+//!     .route("/auth/callback", get(callback))
+//!     .layer(Extension(Arc::new(oauth)));
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{FromRequestParts, Path, Query},
+    http::{
+        header::{LOCATION, SET_COOKIE},
+        request::Parts,
+        StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Extension,
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use tracing::warn;
+use url::Url;
+
+use crate::auth::oauth::http::{HttpsClient, OAuthHttp};
+use crate::auth::oauth::provider::OAuthProvider;
+use crate::auth::CurrentUser;
+use crate::web::problem::Problem;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Why a login failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OAuthError {
+    /// No provider with that name.
+    UnknownProvider,
+    /// Missing, tampered or expired state cookie, or `state` mismatch.
+    InvalidState,
+    /// The user or provider declined (`error=access_denied`, …).
+    Denied,
+    /// The code could not be exchanged for tokens.
+    Exchange,
+    /// The tokens or userinfo did not yield a valid identity.
+    InvalidIdentity,
+    /// No [`OAuth`] extension on the request.
+    NotConfigured,
+}
+
+impl OAuthError {
+    /// HTTP status of the rejection.
+    pub fn status(self) -> StatusCode {
+        match self {
+            Self::UnknownProvider => StatusCode::NOT_FOUND,
+            Self::InvalidState => StatusCode::BAD_REQUEST,
+            Self::Denied => StatusCode::UNAUTHORIZED,
+            Self::Exchange | Self::InvalidIdentity => StatusCode::BAD_GATEWAY,
+            Self::NotConfigured => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            Self::UnknownProvider => "unknown login provider",
+            Self::InvalidState => "login expired or invalid, please try again",
+            Self::Denied => "login was cancelled",
+            Self::Exchange | Self::InvalidIdentity => "login provider error",
+            Self::NotConfigured => "OAuth is not configured",
+        }
+    }
+}
+
+impl IntoResponse for OAuthError {
+    fn into_response(self) -> Response {
+        Problem::new(self.status())
+            .detail(self.message())
+            .into_response()
+    }
+}
+
+/// Login started by [`OAuth::begin`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuthorizationRequest {
+    /// Provider URL to redirect the browser to.
+    pub url: String,
+    /// `Set-Cookie` value carrying the pending login.
+    pub set_cookie: String,
+}
+
+/// Identity established by a completed login.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OAuthIdentity {
+    /// Provider name.
+    pub provider: String,
+    /// The provider's `sub` for the user.
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub name: Option<String>,
+    /// All ID token and userinfo claims (userinfo wins).
+    pub claims: Value,
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    /// Local path to continue at (from `return_to`, default `/`).
+    pub return_to: String,
+}
+
+impl OAuthIdentity {
+    /// [`CurrentUser`] with subject `<provider>:<sub>`, without roles.
+    ///
+    /// Applications usually map this to a local account instead.
+    pub fn current_user(&self) -> CurrentUser {
+        CurrentUser::new(format!("{}:{}", self.provider, self.subject))
+    }
+}
+
+/// Query of the provider's redirect back to the application.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct CallbackParams {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Pending login kept in the state cookie.
+#[derive(Serialize, Deserialize)]
+struct Pending {
+    /// Provider name.
+    p: String,
+    /// `state`.
+    s: String,
+    /// PKCE verifier.
+    v: String,
+    /// OIDC nonce.
+    n: String,
+    /// Return path.
+    r: String,
+    /// Expiry (unix seconds).
+    e: i64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    id_token: Option<String>,
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+/// OAuth2 / OIDC login configuration; share it as `Extension<Arc<OAuth>>`.
+#[derive(Clone)]
+pub struct OAuth {
+    providers: HashMap<String, OAuthProvider>,
+    http: Arc<dyn OAuthHttp>,
+    key: [u8; 32],
+    cookie_name: String,
+    secure: bool,
+    ttl: Duration,
+}
+
+impl OAuth {
+    /// Signs pending logins with a key derived from `csrf_secret`; cookie
+    /// `oauth_state`, valid for 10 minutes, not `Secure`.
+    pub fn new(csrf_secret: [u8; 32]) -> Self {
+        let mut mac = HmacSha256::new_from_slice(&csrf_secret).expect("HMAC key");
+        mac.update(b"wzs-web oauth state");
+        Self {
+            providers: HashMap::new(),
+            http: Arc::new(HttpsClient::new()),
+            key: mac.finalize().into_bytes().into(),
+            cookie_name: "oauth_state".into(),
+            secure: false,
+            ttl: Duration::from_secs(600),
+        }
+    }
+
+    /// Registers a provider under its name.
+    pub fn provider(mut self, provider: OAuthProvider) -> Self {
+        self.providers.insert(provider.name.clone(), provider);
+        self
+    }
+
+    /// Replaces the HTTP client.
+    pub fn http(mut self, http: Arc<dyn OAuthHttp>) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Sets the state cookie name.
+    pub fn cookie_name(mut self, name: impl Into<String>) -> Self {
+        self.cookie_name = name.into();
+        self
+    }
+
+    /// Sets the `Secure` cookie attribute.
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Sets how long a started login may take.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Starts a login with `provider`; `return_to` must be a local path.
+    pub fn begin(
+        &self,
+        provider: &str,
+        return_to: Option<&str>,
+    ) -> Result<AuthorizationRequest, OAuthError> {
+        let p = self
+            .providers
+            .get(provider)
+            .ok_or(OAuthError::UnknownProvider)?;
+        let pending = Pending {
+            p: p.name.clone(),
+            s: random_token(),
+            v: random_token(),
+            n: random_token(),
+            r: local_path(return_to),
+            e: chrono::Utc::now().timestamp() + self.ttl.as_secs() as i64,
+        };
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(pending.v.as_bytes()));
+
+        let mut params: Vec<(&str, &str)> = vec![
+            ("response_type", "code"),
+            ("client_id", &p.client_id),
+            ("redirect_uri", &p.redirect_uri),
+            ("state", &pending.s),
+            ("code_challenge", &challenge),
+            ("code_challenge_method", "S256"),
+        ];
+        let scope = p.scopes.join(" ");
+        if !scope.is_empty() {
+            params.push(("scope", &scope));
+        }
+        if p.scopes.iter().any(|s| s == "openid") {
+            params.push(("nonce", &pending.n));
+        }
+        params.extend(p.params.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+        let url = Url::parse_with_params(&p.authorization_endpoint, &params).map_err(|e| {
+            warn!("invalid authorization endpoint for {}: {e}", p.name);
+            OAuthError::UnknownProvider
+        })?;
+
+        let value = self.sign(&serde_json::to_vec(&pending).expect("pending login serializes"));
+        Ok(AuthorizationRequest {
+            url: url.into(),
+            set_cookie: self.cookie(value, self.ttl.as_secs() as i64),
+        })
+    }
+
+    /// Completes a login from the state cookie value and the callback query.
+    pub async fn complete(
+        &self,
+        cookie: Option<&str>,
+        params: &CallbackParams,
+    ) -> Result<OAuthIdentity, OAuthError> {
+        let pending = cookie
+            .and_then(|c| self.verify(c))
+            .and_then(|json| serde_json::from_slice::<Pending>(&json).ok())
+            .filter(|p| p.e > chrono::Utc::now().timestamp())
+            .ok_or(OAuthError::InvalidState)?;
+        let state_ok = params
+            .state
+            .as_deref()
+            .is_some_and(|s| bool::from(s.as_bytes().ct_eq(pending.s.as_bytes())));
+        if !state_ok {
+            return Err(OAuthError::InvalidState);
+        }
+        if let Some(error) = &params.error {
+            warn!("OAuth login with {} declined: {error}", pending.p);
+            return Err(OAuthError::Denied);
+        }
+        let code = params.code.as_deref().ok_or(OAuthError::InvalidState)?;
+        let provider = self
+            .providers
+            .get(&pending.p)
+            .ok_or(OAuthError::UnknownProvider)?;
+
+        let tokens = self.exchange(provider, code, &pending.v).await?;
+        self.identity(provider, &pending, tokens).await
+    }
+
+    /// `Set-Cookie` value removing the state cookie.
+    pub fn clear_cookie(&self) -> String {
+        self.cookie(String::new(), 0)
+    }
+
+    async fn exchange(
+        &self,
+        p: &OAuthProvider,
+        code: &str,
+        verifier: &str,
+    ) -> Result<TokenResponse, OAuthError> {
+        let form = [
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", p.redirect_uri.as_str()),
+            ("client_id", p.client_id.as_str()),
+            ("client_secret", p.client_secret.as_str()),
+            ("code_verifier", verifier),
+        ];
+        let res = self
+            .http
+            .post_form(&p.token_endpoint, &form)
+            .await
+            .map_err(|e| {
+                warn!("OAuth token request to {} failed: {e:#}", p.name);
+                OAuthError::Exchange
+            })?;
+        if !res.is_success() {
+            warn!(
+                "OAuth token exchange with {} returned {}: {}",
+                p.name,
+                res.status,
+                String::from_utf8_lossy(&res.body)
+            );
+            return Err(OAuthError::Exchange);
+        }
+        res.json().map_err(|e| {
+            warn!("OAuth token response from {}: {e:#}", p.name);
+            OAuthError::Exchange
+        })
+    }
+
+    async fn identity(
+        &self,
+        p: &OAuthProvider,
+        pending: &Pending,
+        tokens: TokenResponse,
+    ) -> Result<OAuthIdentity, OAuthError> {
+        let invalid = |why: &str| {
+            warn!("OAuth identity from {} rejected: {why}", p.name);
+            OAuthError::InvalidIdentity
+        };
+
+        let mut claims = serde_json::Map::new();
+        if let Some(id_token) = &tokens.id_token {
+            let id = id_token_claims(id_token).ok_or_else(|| invalid("malformed ID token"))?;
+            check_id_token(&id, p, &pending.n).map_err(invalid)?;
+            claims.extend(id);
+        }
+        if let Some(url) = &p.userinfo_endpoint {
+            let res = self
+                .http
+                .get(url, Some(&tokens.access_token))
+                .await
+                .map_err(|e| invalid(&format!("userinfo request failed: {e:#}")))?;
+            if !res.is_success() {
+                return Err(invalid(&format!("userinfo returned {}", res.status)));
+            }
+            let info: serde_json::Map<String, Value> = res
+                .json()
+                .map_err(|e| invalid(&format!("userinfo: {e:#}")))?;
+            let sub_differs = matches!(
+                (claims.get("sub"), info.get("sub")),
+                (Some(a), Some(b)) if a != b
+            );
+            if sub_differs {
+                return Err(invalid("userinfo sub differs from ID token"));
+            }
+            claims.extend(info);
+        }
+
+        let subject = match claims.get("sub") {
+            Some(Value::String(s)) if !s.is_empty() => s.clone(),
+            Some(Value::Number(n)) => n.to_string(),
+            _ => return Err(invalid("no subject")),
+        };
+        let text = |k: &str| claims.get(k).and_then(Value::as_str).map(str::to_string);
+        Ok(OAuthIdentity {
+            provider: p.name.clone(),
+            subject,
+            email: text("email"),
+            email_verified: matches!(claims.get("email_verified"), Some(Value::Bool(true)))
+                || text("email_verified").as_deref() == Some("true"),
+            name: text("name"),
+            claims: Value::Object(claims),
+            access_token: tokens.access_token,
+            refresh_token: tokens.refresh_token,
+            return_to: pending.r.clone(),
+        })
+    }
+
+    fn sign(&self, payload: &[u8]) -> String {
+        let data = URL_SAFE_NO_PAD.encode(payload);
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC key");
+        mac.update(data.as_bytes());
+        format!(
+            "{data}.{}",
+            URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+        )
+    }
+
+    fn verify(&self, value: &str) -> Option<Vec<u8>> {
+        let (data, sig) = value.rsplit_once('.')?;
+        let sig = URL_SAFE_NO_PAD.decode(sig).ok()?;
+        let mut mac = HmacSha256::new_from_slice(&self.key).ok()?;
+        mac.update(data.as_bytes());
+        mac.verify_slice(&sig).ok()?;
+        URL_SAFE_NO_PAD.decode(data).ok()
+    }
+
+    fn cookie(&self, value: String, max_age: i64) -> String {
+        let cookie = Cookie::build((self.cookie_name.clone(), value))
+            .path("/")
+            .http_only(true)
+            .secure(self.secure)
+            // The callback is a cross-site top-level navigation.
+            .same_site(SameSite::Lax);
+        format!("{}; Max-Age={max_age}", cookie.build())
+    }
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// `return_to` if it is a local path, else `/` (no open redirects).
+fn local_path(return_to: Option<&str>) -> String {
+    match return_to {
+        Some(p) if p.starts_with('/') && !p.starts_with("//") && !p.contains('\\') => p.into(),
+        _ => "/".into(),
+    }
+}
+
+fn id_token_claims(token: &str) -> Option<serde_json::Map<String, Value>> {
+    let payload = token.split('.').nth(1)?;
+    let json = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+    serde_json::from_slice(&json).ok()
+}
+
+fn check_id_token(
+    claims: &serde_json::Map<String, Value>,
+    p: &OAuthProvider,
+    nonce: &str,
+) -> Result<(), &'static str> {
+    if let Some(issuer) = &p.issuer {
+        let iss = claims
+            .get("iss")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if iss.trim_end_matches('/') != issuer.trim_end_matches('/') {
+            return Err("issuer mismatch");
+        }
+    }
+    let aud_ok = match claims.get("aud") {
+        Some(Value::String(a)) => *a == p.client_id,
+        Some(Value::Array(a)) => a.iter().any(|v| v.as_str() == Some(&p.client_id)),
+        _ => false,
+    };
+    if !aud_ok {
+        return Err("audience mismatch");
+    }
+    match claims.get("exp").and_then(Value::as_i64) {
+        Some(exp) if exp > chrono::Utc::now().timestamp() => {}
+        _ => return Err("expired"),
+    }
+    match claims.get("nonce").and_then(Value::as_str) {
+        Some(n) if n == nonce => Ok(()),
+        _ => Err("nonce mismatch"),
+    }
+}
+
+/// Query of [`oauth_login_handler`].
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct LoginQuery {
+    pub return_to: Option<String>,
+}
+
+/// `GET /auth/{provider}/login`: redirects to the provider.
+pub async fn oauth_login_handler(
+    Extension(oauth): Extension<Arc<OAuth>>,
+    Path(provider): Path<String>,
+    Query(q): Query<LoginQuery>,
+) -> Response {
+    match oauth.begin(&provider, q.return_to.as_deref()) {
+        Ok(req) => (
+            StatusCode::SEE_OTHER,
+            [(LOCATION, req.url), (SET_COOKIE, req.set_cookie)],
+        )
+            .into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Extractor completing a login on the provider's redirect.
+///
+/// Needs an `Extension<Arc<OAuth>>`; rejects with [`OAuthError`].
+#[derive(Clone, Debug)]
+pub struct OAuthCallback {
+    pub identity: OAuthIdentity,
+    /// `Set-Cookie` value removing the state cookie; send it with the
+    /// response.
+    pub clear_cookie: String,
+}
+
+impl<S> FromRequestParts<S> for OAuthCallback
+where
+    S: Send + Sync,
+{
+    type Rejection = OAuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let oauth = parts
+            .extensions
+            .get::<Arc<OAuth>>()
+            .cloned()
+            .ok_or(OAuthError::NotConfigured)?;
+        let params = Query::<CallbackParams>::try_from_uri(&parts.uri)
+            .map(|q| q.0)
+            .map_err(|_| OAuthError::InvalidState)?;
+        let jar = CookieJar::from_headers(&parts.headers);
+        let cookie = jar.get(&oauth.cookie_name).map(|c| c.value().to_string());
+
+        let identity = oauth.complete(cookie.as_deref(), &params).await?;
+        Ok(Self {
+            identity,
+            clear_cookie: oauth.clear_cookie(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    use crate::auth::oauth::http::HttpResponse;
+
+    /// Fake provider: fixed token and userinfo responses, records requests.
+    struct FakeProvider {
+        id_claims: Mutex<Value>,
+        userinfo: Value,
+        token_status: u16,
+        forms: Mutex<Vec<Vec<(String, String)>>>,
+    }
+
+    impl FakeProvider {
+        fn new(id_claims: Value, userinfo: Value) -> Self {
+            Self {
+                id_claims: Mutex::new(id_claims),
+                userinfo,
+                token_status: 200,
+                forms: Mutex::default(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl OAuthHttp for FakeProvider {
+        async fn post_form(&self, url: &str, form: &[(&str, &str)]) -> Result<HttpResponse> {
+            assert_eq!(url, "https://idp.example.com/token");
+            self.forms.lock().unwrap().push(
+                form.iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            );
+            let payload =
+                URL_SAFE_NO_PAD.encode(self.id_claims.lock().unwrap().to_string().as_bytes());
+            let body = json!({
+                "access_token": "at-1",
+                "refresh_token": "rt-1",
+                "id_token": format!("eyJhbGciOiJSUzI1NiJ9.{payload}.sig"),
+            });
+            Ok(HttpResponse {
+                status: self.token_status,
+                body: body.to_string().into_bytes(),
+            })
+        }
+
+        async fn get(&self, url: &str, bearer: Option<&str>) -> Result<HttpResponse> {
+            assert_eq!(url, "https://idp.example.com/userinfo");
+            assert_eq!(bearer, Some("at-1"));
+            Ok(HttpResponse {
+                status: 200,
+                body: self.userinfo.to_string().into_bytes(),
+            })
+        }
+    }
+
+    fn provider() -> OAuthProvider {
+        OAuthProvider::new(
+            "idp",
+            "https://idp.example.com/authorize",
+            "https://idp.example.com/token",
+        )
+        .userinfo_endpoint("https://idp.example.com/userinfo")
+        .issuer("https://idp.example.com")
+        .client(
+            "client-1",
+            "s3cret",
+            "https://app.example.com/auth/callback",
+        )
+        .param("prompt", "select_account")
+    }
+
+    fn oauth(http: Arc<FakeProvider>) -> OAuth {
+        OAuth::new([7u8; 32]).provider(provider()).http(http)
+    }
+
+    fn id_claims(nonce: &str) -> Value {
+        json!({
+            "iss": "https://idp.example.com",
+            "aud": ["client-1"],
+            "sub": "u-42",
+            "exp": chrono::Utc::now().timestamp() + 300,
+            "nonce": nonce,
+            "email": "a@example.com",
+            "email_verified": true,
+        })
+    }
+
+    fn query(url: &str, key: &str) -> String {
+        Url::parse(url)
+            .unwrap()
+            .query_pairs()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.into_owned())
+            .unwrap()
+    }
+
+    fn cookie_value(set_cookie: &str) -> String {
+        let first = set_cookie.split(';').next().unwrap();
+        first.split_once('=').unwrap().1.to_string()
+    }
+
+    fn callback(state: &str) -> CallbackParams {
+        CallbackParams {
+            code: Some("code-1".into()),
+            state: Some(state.into()),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn begin_builds_a_pkce_authorization_url() {
+        let oauth = OAuth::new([7u8; 32]).provider(provider());
+        let req = oauth.begin("idp", Some("/account?tab=1")).unwrap();
+
+        assert!(req.url.starts_with("https://idp.example.com/authorize?"));
+        assert_eq!(query(&req.url, "client_id"), "client-1");
+        assert_eq!(query(&req.url, "scope"), "openid email profile");
+        assert_eq!(query(&req.url, "code_challenge_method"), "S256");
+        assert_eq!(query(&req.url, "prompt"), "select_account");
+        assert_eq!(query(&req.url, "code_challenge").len(), 43);
+        assert!(req.set_cookie.starts_with("oauth_state="));
+        assert!(req.set_cookie.contains("HttpOnly"));
+        assert!(req.set_cookie.contains("SameSite=Lax"));
+        assert!(req.set_cookie.contains("Max-Age=600"));
+
+        assert_eq!(oauth.begin("nope", None), Err(OAuthError::UnknownProvider));
+        assert_eq!(local_path(Some("//evil.com")), "/");
+        assert_eq!(local_path(Some("https://evil.com")), "/");
+    }
+
+    #[tokio::test]
+    async fn complete_exchanges_the_code_and_merges_userinfo() {
+        let http = Arc::new(FakeProvider::new(
+            Value::Null,
+            json!({ "sub": "u-42", "name": "Alice" }),
+        ));
+        let oauth = oauth(http.clone());
+        let req = oauth.begin("idp", Some("/account")).unwrap();
+        *http.id_claims.lock().unwrap() = id_claims(&query(&req.url, "nonce"));
+
+        let cookie = cookie_value(&req.set_cookie);
+        let state = query(&req.url, "state");
+        let id = oauth
+            .complete(Some(&cookie), &callback(&state))
+            .await
+            .unwrap();
+
+        assert_eq!(id.subject, "u-42");
+        assert_eq!(id.email.as_deref(), Some("a@example.com"));
+        assert!(id.email_verified);
+        assert_eq!(id.name.as_deref(), Some("Alice"));
+        assert_eq!(id.return_to, "/account");
+        assert_eq!(id.refresh_token.as_deref(), Some("rt-1"));
+        assert_eq!(id.current_user().subject, "idp:u-42");
+
+        // The PKCE verifier matches the challenge sent earlier.
+        let forms = http.forms.lock().unwrap();
+        let verifier = &forms[0]
+            .iter()
+            .find(|(k, _)| k == "code_verifier")
+            .unwrap()
+            .1;
+        assert_eq!(
+            URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())),
+            query(&req.url, "code_challenge")
+        );
+    }
+
+    #[tokio::test]
+    async fn complete_rejects_bad_state_and_identities() {
+        let http = Arc::new(FakeProvider::new(Value::Null, json!({ "sub": "u-42" })));
+        let oauth = oauth(http.clone());
+        let req = oauth.begin("idp", None).unwrap();
+        let cookie = cookie_value(&req.set_cookie);
+        let state = query(&req.url, "state");
+        let nonce = query(&req.url, "nonce");
+
+        let err = |r: Result<OAuthIdentity, OAuthError>| r.unwrap_err();
+        assert_eq!(
+            err(oauth.complete(None, &callback(&state)).await),
+            OAuthError::InvalidState
+        );
+        assert_eq!(
+            err(oauth.complete(Some(&cookie), &callback("other")).await),
+            OAuthError::InvalidState
+        );
+        let tampered = cookie.replacen('e', "f", 1);
+        assert_eq!(
+            err(oauth.complete(Some(&tampered), &callback(&state)).await),
+            OAuthError::InvalidState
+        );
+        let denied = CallbackParams {
+            error: Some("access_denied".into()),
+            ..callback(&state)
+        };
+        assert_eq!(
+            err(oauth.complete(Some(&cookie), &denied).await),
+            OAuthError::Denied
+        );
+
+        *http.id_claims.lock().unwrap() = id_claims("wrong-nonce");
+        assert_eq!(
+            err(oauth.complete(Some(&cookie), &callback(&state)).await),
+            OAuthError::InvalidIdentity
+        );
+        let mut claims = id_claims(&nonce);
+        claims["aud"] = json!("someone-else");
+        *http.id_claims.lock().unwrap() = claims;
+        assert_eq!(
+            err(oauth.complete(Some(&cookie), &callback(&state)).await),
+            OAuthError::InvalidIdentity
+        );
+
+        // A login signed with another secret is not accepted.
+        let other = OAuth::new([8u8; 32]).provider(provider()).http(http);
+        assert_eq!(
+            err(other.complete(Some(&cookie), &callback(&state)).await),
+            OAuthError::InvalidState
+        );
+    }
+
+    #[tokio::test]
+    async fn login_route_and_callback_extractor() {
+        let http = Arc::new(FakeProvider::new(Value::Null, json!({ "sub": "u-42" })));
+        let oauth = Arc::new(oauth(http.clone()));
+        let app = Router::new()
+            .route("/auth/{provider}/login", get(oauth_login_handler))
+            .route(
+                "/auth/callback",
+                get(|login: OAuthCallback| async move { login.identity.current_user().subject }),
+            )
+            .layer(Extension(oauth));
+
+        let res = app
+            .clone()
+            .oneshot(
+                Request::get("/auth/idp/login?return_to=/x")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        let location = res.headers()[LOCATION].to_str().unwrap().to_string();
+        let set_cookie = res.headers()[SET_COOKIE].to_str().unwrap().to_string();
+        *http.id_claims.lock().unwrap() = id_claims(&query(&location, "nonce"));
+
+        let uri = format!("/auth/callback?code=c&state={}", query(&location, "state"));
+        let cookie = format!("oauth_state={}", cookie_value(&set_cookie));
+        let res = app
+            .clone()
+            .oneshot(
+                Request::get(uri.as_str())
+                    .header("cookie", cookie)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = app
+            .oneshot(Request::get(uri.as_str()).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
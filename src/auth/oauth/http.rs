@@ -0,0 +1,374 @@
+//! # OAuth HTTP Client
+//!
+//! The OAuth flow talks to providers through [`OAuthHttp`] (token exchange,
+//! userinfo, OIDC discovery), so tests and applications with their own HTTP
+//! stack can substitute it.
+//!
+//! [`HttpsClient`] is the built-in implementation: one HTTP/1.1 request per
+//! connection over rustls with the Mozilla root store, bounded by a timeout
+//! and a response size limit. Plain `http://` is only allowed for loopback
+//! hosts (`localhost`, `127.0.0.0/8`, `::1`; local mock providers).
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use wzs_web::auth::oauth::http::{HttpsClient, OAuthHttp};
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let http = HttpsClient::new().timeout(Duration::from_secs(5));
+//! let res = http
+//!     .get("https://accounts.google.com/.well-known/openid-configuration", None)
+//!     .await?;
+//! assert!(res.is_success());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::{self, pki_types::ServerName, ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+use url::Url;
+
+/// Status and body of an HTTP response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// Whether the status is `2xx`.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Deserializes the body as JSON.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_slice(&self.body).context("invalid JSON response")
+    }
+}
+
+/// HTTP requests made by the OAuth flow.
+#[async_trait]
+pub trait OAuthHttp: Send + Sync {
+    /// `POST url` with an `application/x-www-form-urlencoded` body.
+    async fn post_form(&self, url: &str, form: &[(&str, &str)]) -> Result<HttpResponse>;
+
+    /// `GET url`, with `Authorization: Bearer <bearer>` when given.
+    ///
+    /// Implementations must refuse a `bearer` containing control characters
+    /// rather than send it.
+    async fn get(&self, url: &str, bearer: Option<&str>) -> Result<HttpResponse>;
+}
+
+/// Minimal HTTPS client for [`OAuthHttp`].
+#[derive(Clone)]
+pub struct HttpsClient {
+    tls: TlsConnector,
+    timeout: Duration,
+    max_body: usize,
+}
+
+impl Default for HttpsClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpsClient {
+    /// Client trusting the Mozilla root store, with a 10-second timeout and
+    /// a 1 MiB response limit.
+    pub fn new() -> Self {
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .expect("ring supports the default protocol versions")
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Self {
+            tls: TlsConnector::from(Arc::new(config)),
+            timeout: Duration::from_secs(10),
+            max_body: 1024 * 1024,
+        }
+    }
+
+    /// Sets the timeout for a whole request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the largest accepted response (headers and body).
+    pub fn max_body(mut self, bytes: usize) -> Self {
+        self.max_body = bytes;
+        self
+    }
+
+    async fn send(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &str,
+        body: &[u8],
+    ) -> Result<HttpResponse> {
+        let url = Url::parse(url).with_context(|| format!("invalid URL: {url}"))?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| anyhow!("URL without host: {url}"))?;
+        // IPv6 literals come bracketed; connect and TLS want the bare address.
+        let addr = host.trim_start_matches('[').trim_end_matches(']');
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| anyhow!("URL without port: {url}"))?;
+        let target = match url.query() {
+            Some(q) => format!("{}?{q}", url.path()),
+            None => url.path().to_string(),
+        };
+        let authority = match url.port() {
+            Some(p) => format!("{host}:{p}"),
+            None => host.to_string(),
+        };
+        let head = format!(
+            "{method} {target} HTTP/1.1\r\nHost: {authority}\r\nUser-Agent: wzs-web\r\n\
+             Accept: application/json\r\nConnection: close\r\nContent-Length: {}\r\n{headers}\r\n",
+            body.len()
+        );
+
+        let use_tls = match url.scheme() {
+            "https" => true,
+            "http" if is_loopback(addr) => false,
+            scheme => bail!("refusing {scheme}:// request to {host}"),
+        };
+
+        let exchange = async {
+            let tcp = TcpStream::connect((addr, port))
+                .await
+                .with_context(|| format!("connect {authority}"))?;
+            if !use_tls {
+                return round_trip(tcp, head.as_bytes(), body, self.max_body).await;
+            }
+            let name = ServerName::try_from(addr.to_string())
+                .with_context(|| format!("invalid server name: {host}"))?;
+            let tls = self.tls.connect(name, tcp).await.context("TLS handshake")?;
+            round_trip(tls, head.as_bytes(), body, self.max_body).await
+        };
+        let raw = tokio::time::timeout(self.timeout, exchange)
+            .await
+            .map_err(|_| anyhow!("{method} {url} timed out"))??;
+        parse_response(&raw)
+    }
+}
+
+#[async_trait]
+impl OAuthHttp for HttpsClient {
+    async fn post_form(&self, url: &str, form: &[(&str, &str)]) -> Result<HttpResponse> {
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(form)
+            .finish();
+        let headers = "Content-Type: application/x-www-form-urlencoded\r\n";
+        self.send("POST", url, headers, body.as_bytes()).await
+    }
+
+    async fn get(&self, url: &str, bearer: Option<&str>) -> Result<HttpResponse> {
+        if bearer.is_some_and(|t| t.chars().any(char::is_control)) {
+            bail!("bearer token contains control characters");
+        }
+        let headers = bearer
+            .map(|t| format!("Authorization: Bearer {t}\r\n"))
+            .unwrap_or_default();
+        self.send("GET", url, &headers, &[]).await
+    }
+}
+
+/// `localhost` or a loopback IP address (without IPv6 brackets).
+fn is_loopback(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+async fn round_trip<S>(mut stream: S, head: &[u8], body: &[u8], max: usize) -> Result<Vec<u8>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(head).await?;
+    stream.write_all(body).await?;
+    stream.flush().await?;
+
+    let mut raw = Vec::new();
+    (&mut stream)
+        .take(max as u64 + 1)
+        .read_to_end(&mut raw)
+        .await?;
+    if raw.len() > max {
+        bail!("response larger than {max} bytes");
+    }
+    Ok(raw)
+}
+
+/// Parses a complete `Connection: close` response.
+fn parse_response(raw: &[u8]) -> Result<HttpResponse> {
+    let split = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| anyhow!("malformed HTTP response"))?;
+    let head = std::str::from_utf8(&raw[..split]).context("non-UTF-8 response headers")?;
+    let rest = &raw[split + 4..];
+
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|l| l.split_whitespace().nth(1))
+        .and_then(|s| s.parse::<u16>().ok())
+        .ok_or_else(|| anyhow!("malformed HTTP status line"))?;
+
+    let mut chunked = false;
+    let mut length = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = value.to_ascii_lowercase().contains("chunked");
+        } else if name.eq_ignore_ascii_case("content-length") {
+            length = value.parse::<usize>().ok();
+        }
+    }
+
+    let body = match (chunked, length) {
+        (true, _) => decode_chunked(rest)?,
+        (false, Some(n)) if n <= rest.len() => rest[..n].to_vec(),
+        (false, Some(_)) => bail!("truncated HTTP response body"),
+        (false, None) => rest.to_vec(),
+    };
+    Ok(HttpResponse { status, body })
+}
+
+fn decode_chunked(mut data: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    loop {
+        let eol = data
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or_else(|| anyhow!("malformed chunk header"))?;
+        let size_line = std::str::from_utf8(&data[..eol])?;
+        let size_hex = size_line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size_hex, 16).context("invalid chunk size")?;
+        data = &data[eol + 2..];
+        if size == 0 {
+            return Ok(out);
+        }
+        if data.len() < size + 2 {
+            bail!("truncated chunk");
+        }
+        out.extend_from_slice(&data[..size]);
+        data = &data[size + 2..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn parses_content_length_and_chunked_bodies() {
+        let res =
+            parse_response(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}trailing").unwrap();
+        assert_eq!(
+            res,
+            HttpResponse {
+                status: 200,
+                body: b"{}".to_vec()
+            }
+        );
+        assert!(res.is_success());
+
+        let raw = b"HTTP/1.1 400 Bad Request\r\nTransfer-Encoding: chunked\r\n\r\n\
+                    4\r\n{\"a\"\r\n3;x=y\r\n:1}\r\n0\r\n\r\n";
+        let res = parse_response(raw).unwrap();
+        assert_eq!(res.status, 400);
+        assert_eq!(res.json::<serde_json::Value>().unwrap()["a"], 1);
+
+        assert!(parse_response(b"garbage").is_err());
+        assert!(parse_response(b"HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\nshort").is_err());
+    }
+
+    #[tokio::test]
+    async fn posts_forms_to_loopback_servers() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut sock, _) = listener.accept().await.unwrap();
+            let mut req = String::new();
+            let mut buf = [0u8; 1024];
+            while !req.ends_with("authorization_code") {
+                let n = sock.read(&mut buf).await.unwrap();
+                req.push_str(&String::from_utf8_lossy(&buf[..n]));
+            }
+            sock.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\n{\"ok\":true}")
+                .await
+                .unwrap();
+            req
+        });
+
+        let http = HttpsClient::new();
+        let res = http
+            .post_form(
+                &format!("http://127.0.0.1:{port}/token?x=1"),
+                &[("code", "a b"), ("grant_type", "authorization_code")],
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.json::<serde_json::Value>().unwrap()["ok"], true);
+
+        let req = server.await.unwrap();
+        assert!(req.starts_with("POST /token?x=1 HTTP/1.1\r\n"), "{req}");
+        assert!(req.contains(&format!("Host: 127.0.0.1:{port}\r\n")));
+        assert!(
+            req.ends_with("code=a+b&grant_type=authorization_code"),
+            "{req}"
+        );
+    }
+
+    #[test]
+    fn loopback_hosts_are_parsed_as_addresses() {
+        for host in ["localhost", "LOCALHOST", "127.0.0.1", "127.1.2.3", "::1"] {
+            assert!(is_loopback(host), "{host}");
+        }
+        for host in ["example.com", "10.0.0.1", "::2", "127.0.0.1.example.com"] {
+            assert!(!is_loopback(host), "{host}");
+        }
+    }
+
+    #[tokio::test]
+    async fn refuses_bearer_tokens_with_control_characters() {
+        for token in ["t\r\nX-Injected: 1", "t\n", "t\0"] {
+            let err = HttpsClient::new()
+                .get("http://127.0.0.1:9/userinfo", Some(token))
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("control characters"), "{err:#}");
+        }
+    }
+
+    #[tokio::test]
+    async fn refuses_plain_http_to_remote_hosts() {
+        let err = HttpsClient::new()
+            .get("http://example.com/userinfo", Some("t"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("refusing http://"), "{err:#}");
+    }
+}
@@ -0,0 +1,232 @@
+//! # OAuth Providers
+//!
+//! [`OAuthProvider`] holds the endpoints and client credentials of one
+//! identity provider:
+//!
+//! | Constructor | Provider |
+//! |-------------|----------|
+//! | [`OAuthProvider::google`] | Google (OIDC, fixed endpoints) |
+//! | [`OAuthProvider::discover`] | any OIDC issuer, via `/.well-known/openid-configuration` |
+//! | [`OAuthProvider::new`] | plain OAuth2 / OIDC with explicit endpoints |
+//!
+//! # Example
+//! ```rust,no_run
+//! use wzs_web::auth::oauth::http::HttpsClient;
+//! use wzs_web::auth::oauth::provider::OAuthProvider;
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let google = OAuthProvider::google()
+//!     .client("client-id", "client-secret", "https://app.example.com/auth/callback")
+//!     .param("hd", "example.com");
+//!
+//! let keycloak = OAuthProvider::discover(
+//!     &HttpsClient::new(),
+//!     "keycloak",
+//!     "https://sso.example.com/realms/main",
+//! )
+//! .await?
+//! .client("app", "secret", "https://app.example.com/auth/callback");
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::auth::oauth::http::OAuthHttp;
+
+/// Endpoints and client credentials of an identity provider.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OAuthProvider {
+    /// Short name used in routes and state (e.g. `"google"`).
+    pub name: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    /// Fetched with the access token when set.
+    pub userinfo_endpoint: Option<String>,
+    /// Expected `iss` of ID tokens; not checked when `None`.
+    pub issuer: Option<String>,
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_uri: String,
+    /// Requested scopes (default `openid email profile`).
+    pub scopes: Vec<String>,
+    /// Extra authorization request parameters (`prompt`, `hd`, …).
+    pub params: Vec<(String, String)>,
+}
+
+#[derive(Deserialize)]
+struct Discovery {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    #[serde(default)]
+    userinfo_endpoint: Option<String>,
+}
+
+impl OAuthProvider {
+    /// Provider with explicit endpoints; set credentials with
+    /// [`client`](Self::client).
+    pub fn new(
+        name: impl Into<String>,
+        authorization_endpoint: impl Into<String>,
+        token_endpoint: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            authorization_endpoint: authorization_endpoint.into(),
+            token_endpoint: token_endpoint.into(),
+            userinfo_endpoint: None,
+            issuer: None,
+            client_id: String::new(),
+            client_secret: String::new(),
+            redirect_uri: String::new(),
+            scopes: vec!["openid".into(), "email".into(), "profile".into()],
+            params: Vec::new(),
+        }
+    }
+
+    /// Google, named `"google"`.
+    pub fn google() -> Self {
+        Self::new(
+            "google",
+            "https://accounts.google.com/o/oauth2/v2/auth",
+            "https://oauth2.googleapis.com/token",
+        )
+        .userinfo_endpoint("https://openidconnect.googleapis.com/v1/userinfo")
+        .issuer("https://accounts.google.com")
+    }
+
+    /// Reads the endpoints of an OIDC `issuer` from its discovery document.
+    ///
+    /// # Errors
+    /// When the document cannot be fetched or names another issuer.
+    pub async fn discover(
+        http: &dyn OAuthHttp,
+        name: impl Into<String>,
+        issuer: &str,
+    ) -> Result<Self> {
+        let issuer = issuer.trim_end_matches('/');
+        let url = format!("{issuer}/.well-known/openid-configuration");
+        let res = http.get(&url, None).await?;
+        if !res.is_success() {
+            bail!("OIDC discovery failed: {url} returned {}", res.status);
+        }
+        let doc: Discovery = res
+            .json()
+            .with_context(|| format!("OIDC discovery {url}"))?;
+        if doc.issuer.trim_end_matches('/') != issuer {
+            bail!(
+                "OIDC discovery: issuer mismatch ({} != {issuer})",
+                doc.issuer
+            );
+        }
+        let mut provider =
+            Self::new(name, doc.authorization_endpoint, doc.token_endpoint).issuer(doc.issuer);
+        provider.userinfo_endpoint = doc.userinfo_endpoint;
+        Ok(provider)
+    }
+
+    /// Sets the client credentials and the registered redirect URI.
+    pub fn client(
+        mut self,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+        redirect_uri: impl Into<String>,
+    ) -> Self {
+        self.client_id = client_id.into();
+        self.client_secret = client_secret.into();
+        self.redirect_uri = redirect_uri.into();
+        self
+    }
+
+    /// Sets the userinfo endpoint.
+    pub fn userinfo_endpoint(mut self, url: impl Into<String>) -> Self {
+        self.userinfo_endpoint = Some(url.into());
+        self
+    }
+
+    /// Sets the expected ID token issuer.
+    pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Replaces the requested scopes.
+    pub fn scopes<I, S>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Adds an authorization request parameter.
+    pub fn param(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.params.push((key.into(), value.into()));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::oauth::http::HttpResponse;
+    use async_trait::async_trait;
+
+    struct StaticHttp(String);
+
+    #[async_trait]
+    impl OAuthHttp for StaticHttp {
+        async fn post_form(&self, _: &str, _: &[(&str, &str)]) -> Result<HttpResponse> {
+            unreachable!()
+        }
+
+        async fn get(&self, url: &str, _: Option<&str>) -> Result<HttpResponse> {
+            assert_eq!(
+                url,
+                "https://sso.example.com/.well-known/openid-configuration"
+            );
+            Ok(HttpResponse {
+                status: 200,
+                body: self.0.as_bytes().to_vec(),
+            })
+        }
+    }
+
+    #[test]
+    fn google_has_fixed_endpoints() {
+        let p = OAuthProvider::google().client("id", "secret", "https://app/cb");
+        assert_eq!(p.name, "google");
+        assert_eq!(p.issuer.as_deref(), Some("https://accounts.google.com"));
+        assert!(p.userinfo_endpoint.is_some());
+        assert_eq!(p.scopes, ["openid", "email", "profile"]);
+        assert_eq!(p.redirect_uri, "https://app/cb");
+    }
+
+    #[tokio::test]
+    async fn discover_reads_the_configuration_document() {
+        let doc = r#"{
+            "issuer": "https://sso.example.com",
+            "authorization_endpoint": "https://sso.example.com/auth",
+            "token_endpoint": "https://sso.example.com/token",
+            "jwks_uri": "https://sso.example.com/certs"
+        }"#;
+        let p = OAuthProvider::discover(&StaticHttp(doc.into()), "sso", "https://sso.example.com/")
+            .await
+            .unwrap();
+        assert_eq!(p.token_endpoint, "https://sso.example.com/token");
+        assert_eq!(p.userinfo_endpoint, None);
+
+        let wrong = doc.replace(
+            "\"issuer\": \"https://sso.example.com\"",
+            "\"issuer\": \"https://evil\"",
+        );
+        assert!(
+            OAuthProvider::discover(&StaticHttp(wrong), "sso", "https://sso.example.com")
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,455 @@
+//! # One-time codes
+//!
+//! Short numeric codes for sign-in and verification flows (email verification,
+//! passwordless sign-in, phone confirmation).
+//!
+//! ## Design principles
+//! - Codes are stored only as an HMAC digest bound to purpose and subject
+//! - Verification compares digests in constant time
+//! - Codes expire after a TTL and are single use
+//! - Attempts are limited per purpose and subject via [`Throttle`]; each one
+//!   is reserved before the code is compared and released on a match
+//! - Delivery is channel-agnostic: email, SMS, etc. implement [`CodeSender`]
+//!
+//! ## Provided types
+//! - [`OtpService`] — issues and verifies codes
+//! - [`OtpStore`] / [`MemoryOtpStore`] — storage port and in-process store
+//! - [`CodeSender`] / [`EmailCodeSender`] — delivery port and email channel
+//!
+//! ## Example
+//! ```
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//! use wzs_web::auth::otp::{MemoryOtpStore, OtpService, OtpVerdict};
+//! use wzs_web::auth::throttle::MemoryThrottle;
+//!
+//! let otp = OtpService::new(
+//!     b"server-secret".to_vec(),
+//!     Arc::new(MemoryOtpStore::default()),
+//!     Arc::new(MemoryThrottle::new(5, Duration::from_secs(900))),
+//! )
+//! .digits(6);
+//!
+//! let code = otp.issue("verify_email", "user@example.com").unwrap();
+//! assert_eq!(code.len(), 6);
+//!
+//! let verdict = otp.verify("verify_email", "user@example.com", &code).unwrap();
+//! assert_eq!(verdict, OtpVerdict::Valid);
+//!
+//! // Single use.
+//! let verdict = otp.verify("verify_email", "user@example.com", &code).unwrap();
+//! assert_eq!(verdict, OtpVerdict::Invalid);
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use lettre::message::Mailbox;
+use rand::Rng;
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+
+use crate::auth::throttle::Throttle;
+use crate::notification::email::{Email, EmailBody};
+use crate::notification::email_sender::EmailSender;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// A stored code: its digest and expiry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtpRecord {
+    /// HMAC-SHA256 of purpose, subject and code.
+    pub digest: Vec<u8>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Storage for outstanding codes, keyed by purpose and subject.
+///
+/// Issuing a new code replaces the previous one for the same key.
+pub trait OtpStore: Send + Sync {
+    fn put(&self, key: &str, record: OtpRecord) -> Result<()>;
+
+    fn get(&self, key: &str) -> Result<Option<OtpRecord>>;
+
+    fn remove(&self, key: &str) -> Result<()>;
+}
+
+/// In-process [`OtpStore`].
+#[derive(Default)]
+pub struct MemoryOtpStore {
+    records: Mutex<HashMap<String, OtpRecord>>,
+}
+
+impl OtpStore for MemoryOtpStore {
+    fn put(&self, key: &str, record: OtpRecord) -> Result<()> {
+        self.records.lock().unwrap().insert(key.to_string(), record);
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Result<Option<OtpRecord>> {
+        Ok(self.records.lock().unwrap().get(key).cloned())
+    }
+
+    fn remove(&self, key: &str) -> Result<()> {
+        self.records.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+/// Result of [`OtpService::verify`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtpVerdict {
+    /// The code matched and has been consumed.
+    Valid,
+    /// No outstanding code, or the code did not match.
+    Invalid,
+    /// The code matched but has expired; it has been discarded.
+    Expired,
+    /// Too many failed attempts; the code was not checked.
+    Throttled,
+}
+
+/// Delivers a code over one channel (email, SMS, ...).
+#[async_trait]
+pub trait CodeSender: Send + Sync {
+    /// Sends `code` for `purpose` to `to` (an address or phone number).
+    async fn send_code(&self, to: &str, purpose: &str, code: &str, ttl: Duration) -> Result<()>;
+}
+
+/// Issues and verifies one-time codes.
+#[derive(Clone)]
+pub struct OtpService {
+    secret: Vec<u8>,
+    store: Arc<dyn OtpStore>,
+    throttle: Arc<dyn Throttle>,
+    digits: u32,
+    ttl: Duration,
+}
+
+impl OtpService {
+    /// Creates a service issuing 6-digit codes valid for 10 minutes.
+    pub fn new(secret: Vec<u8>, store: Arc<dyn OtpStore>, throttle: Arc<dyn Throttle>) -> Self {
+        Self {
+            secret,
+            store,
+            throttle,
+            digits: 6,
+            ttl: Duration::minutes(10),
+        }
+    }
+
+    /// Sets the code length (clamped to 4..=9 digits).
+    pub fn digits(mut self, digits: u32) -> Self {
+        self.digits = digits.clamp(4, 9);
+        self
+    }
+
+    /// Sets how long a code stays valid.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Generates and stores a code for `subject`, replacing any previous one.
+    ///
+    /// # Errors
+    /// Returns an error if `purpose` or `subject` is empty, or the store fails.
+    pub fn issue(&self, purpose: &str, subject: &str) -> Result<String> {
+        if purpose.is_empty() || subject.is_empty() {
+            bail!("otp purpose and subject must not be empty");
+        }
+        let code = format!(
+            "{:0width$}",
+            rand::rng().random_range(0..10u32.pow(self.digits)),
+            width = self.digits as usize
+        );
+        self.store.put(
+            &key(purpose, subject),
+            OtpRecord {
+                digest: self.digest(purpose, subject, &code),
+                expires_at: Utc::now() + self.ttl,
+            },
+        )?;
+        Ok(code)
+    }
+
+    /// Issues a code and delivers it to `to` over `channel`.
+    ///
+    /// # Errors
+    /// Returns an error if issuing or delivery fails.
+    pub async fn deliver(
+        &self,
+        channel: &dyn CodeSender,
+        purpose: &str,
+        subject: &str,
+        to: &str,
+    ) -> Result<()> {
+        let code = self.issue(purpose, subject)?;
+        channel.send_code(to, purpose, &code, self.ttl).await
+    }
+
+    /// Checks `code` for `subject`; a valid code is consumed.
+    ///
+    /// # Errors
+    /// Returns an error only if the store fails.
+    pub fn verify(&self, purpose: &str, subject: &str, code: &str) -> Result<OtpVerdict> {
+        let key = key(purpose, subject);
+        // Reserve the attempt first so concurrent guesses share the limit.
+        if !self.throttle.try_acquire(&key) {
+            return Ok(OtpVerdict::Throttled);
+        }
+        let Some(record) = self.store.get(&key)? else {
+            return Ok(OtpVerdict::Invalid);
+        };
+
+        let digest = self.digest(purpose, subject, code.trim());
+        if record.digest.as_slice().ct_eq(&digest).unwrap_u8() != 1 {
+            return Ok(OtpVerdict::Invalid);
+        }
+
+        self.throttle.reset(&key);
+        self.store.remove(&key)?;
+        if record.expires_at <= Utc::now() {
+            return Ok(OtpVerdict::Expired);
+        }
+        Ok(OtpVerdict::Valid)
+    }
+
+    fn digest(&self, purpose: &str, subject: &str, code: &str) -> Vec<u8> {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts any key length");
+        mac.update(purpose.as_bytes());
+        mac.update(b"\0");
+        mac.update(subject.as_bytes());
+        mac.update(b"\0");
+        mac.update(code.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+fn key(purpose: &str, subject: &str) -> String {
+    format!("otp:{purpose}:{subject}")
+}
+
+/// [`CodeSender`] that emails the code as plain text.
+#[derive(Clone)]
+pub struct EmailCodeSender {
+    sender: Arc<dyn EmailSender>,
+    subject: String,
+}
+
+impl EmailCodeSender {
+    /// Sends codes through `sender` with a default subject line.
+    pub fn new(sender: Arc<dyn EmailSender>) -> Self {
+        Self {
+            sender,
+            subject: "Your verification code".into(),
+        }
+    }
+
+    /// Overrides the subject line.
+    pub fn subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = subject.into();
+        self
+    }
+}
+
+#[async_trait]
+impl CodeSender for EmailCodeSender {
+    async fn send_code(&self, to: &str, _purpose: &str, code: &str, ttl: Duration) -> Result<()> {
+        let to: Mailbox = to.parse()?;
+        let text = format!(
+            "Your code is {code}\n\nIt expires in {} minutes. If you did not request it, ignore this email.\n",
+            ttl.num_minutes().max(1)
+        );
+        self.sender
+            .send(Email {
+                subject: self.subject.clone(),
+                body: EmailBody::Text(text),
+                to: vec![to],
+                cc: vec![],
+                bcc: vec![],
+                headers: vec![],
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::throttle::MemoryThrottle;
+
+    fn service(max_attempts: u32) -> OtpService {
+        OtpService::new(
+            b"secret".to_vec(),
+            Arc::new(MemoryOtpStore::default()),
+            Arc::new(MemoryThrottle::new(
+                max_attempts,
+                std::time::Duration::from_secs(60),
+            )),
+        )
+    }
+
+    #[test]
+    fn issued_code_is_numeric_and_single_use() {
+        let otp = service(5).digits(8);
+        let code = otp.issue("sign_in", "u1").unwrap();
+        assert_eq!(code.len(), 8);
+        assert!(code.chars().all(|c| c.is_ascii_digit()));
+
+        assert_eq!(
+            otp.verify("sign_in", "u1", &code).unwrap(),
+            OtpVerdict::Valid
+        );
+        assert_eq!(
+            otp.verify("sign_in", "u1", &code).unwrap(),
+            OtpVerdict::Invalid
+        );
+    }
+
+    #[test]
+    fn code_is_bound_to_purpose_and_subject() {
+        let otp = service(5);
+        let code = otp.issue("sign_in", "u1").unwrap();
+        assert_eq!(
+            otp.verify("verify_email", "u1", &code).unwrap(),
+            OtpVerdict::Invalid
+        );
+        assert_eq!(
+            otp.verify("sign_in", "u2", &code).unwrap(),
+            OtpVerdict::Invalid
+        );
+        assert_eq!(
+            otp.verify("sign_in", "u1", &code).unwrap(),
+            OtpVerdict::Valid
+        );
+    }
+
+    #[test]
+    fn expired_code_is_rejected_and_consumed() {
+        let otp = service(5).ttl(Duration::zero());
+        let code = otp.issue("sign_in", "u1").unwrap();
+        assert_eq!(
+            otp.verify("sign_in", "u1", &code).unwrap(),
+            OtpVerdict::Expired
+        );
+        assert_eq!(
+            otp.verify("sign_in", "u1", &code).unwrap(),
+            OtpVerdict::Invalid
+        );
+    }
+
+    #[test]
+    fn failed_attempts_are_throttled() {
+        let otp = service(2);
+        let code = otp.issue("sign_in", "u1").unwrap();
+        let wrong = if code == "000000" { "000001" } else { "000000" };
+
+        assert_eq!(
+            otp.verify("sign_in", "u1", wrong).unwrap(),
+            OtpVerdict::Invalid
+        );
+        assert_eq!(
+            otp.verify("sign_in", "u1", wrong).unwrap(),
+            OtpVerdict::Invalid
+        );
+        assert_eq!(
+            otp.verify("sign_in", "u1", &code).unwrap(),
+            OtpVerdict::Throttled
+        );
+    }
+
+    #[test]
+    fn concurrent_guesses_share_the_attempt_limit() {
+        let otp = Arc::new(service(3));
+        let code = otp.issue("sign_in", "u1").unwrap();
+        let wrong = if code == "000000" { "000001" } else { "000000" };
+
+        let verdicts: Vec<_> = (0..10)
+            .map(|_| {
+                let otp = otp.clone();
+                std::thread::spawn(move || otp.verify("sign_in", "u1", wrong).unwrap())
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect();
+        let invalid = verdicts
+            .iter()
+            .filter(|v| **v == OtpVerdict::Invalid)
+            .count();
+        assert_eq!(invalid, 3);
+        assert_eq!(
+            otp.verify("sign_in", "u1", &code).unwrap(),
+            OtpVerdict::Throttled
+        );
+    }
+
+    #[test]
+    fn success_clears_reserved_attempts() {
+        let otp = service(2);
+        let code = otp.issue("sign_in", "u1").unwrap();
+        let wrong = if code == "000000" { "000001" } else { "000000" };
+
+        otp.verify("sign_in", "u1", wrong).unwrap();
+        assert_eq!(
+            otp.verify("sign_in", "u1", &code).unwrap(),
+            OtpVerdict::Valid
+        );
+        let code = otp.issue("sign_in", "u1").unwrap();
+        otp.verify("sign_in", "u1", wrong).unwrap();
+        assert_eq!(
+            otp.verify("sign_in", "u1", &code).unwrap(),
+            OtpVerdict::Valid
+        );
+    }
+
+    #[test]
+    fn issue_rejects_empty_subject() {
+        assert!(service(5).issue("sign_in", "").is_err());
+    }
+
+    #[derive(Default)]
+    struct RecordingSender {
+        sent: Mutex<Vec<Email>>,
+    }
+
+    #[async_trait]
+    impl EmailSender for RecordingSender {
+        async fn send(&self, email: Email) -> Result<()> {
+            self.sent.lock().unwrap().push(email);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn deliver_emails_a_verifiable_code() {
+        let otp = service(5);
+        let mail = Arc::new(RecordingSender::default());
+        let channel = EmailCodeSender::new(mail.clone()).subject("Sign-in code");
+
+        otp.deliver(&channel, "sign_in", "u1", "user@example.com")
+            .await
+            .unwrap();
+
+        let sent = mail.sent.lock().unwrap();
+        assert_eq!(sent[0].subject, "Sign-in code");
+        let EmailBody::Text(text) = &sent[0].body else {
+            panic!("expected text body");
+        };
+        let code: String = text
+            .split_whitespace()
+            .find(|w| w.chars().all(|c| c.is_ascii_digit()) && w.len() == 6)
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            otp.verify("sign_in", "u1", &code).unwrap(),
+            OtpVerdict::Valid
+        );
+    }
+}
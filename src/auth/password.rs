@@ -0,0 +1,455 @@
+//! # Password hashing
+//!
+//! Argon2id password hashes in the PHC string format shared with other
+//! Argon2 implementations:
+//!
+//! ```text
+//! $argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>
+//! ```
+//!
+//! ## Design principles
+//! - Parameters are stored in each hash, so they can be raised without
+//!   invalidating existing passwords
+//! - [`verify_and_rehash`] returns a new hash when a stored one was made
+//!   with weaker parameters than the configured ones; save it to upgrade
+//!   users as they sign in
+//! - Verification compares hashes in constant time
+//! - `m` is capped at [`MAX_MEMORY_KIB`], so a stored hash cannot make
+//!   verification allocate more memory than that
+//! - Hashing is CPU and memory bound; call it via
+//!   `tokio::task::spawn_blocking` from async handlers
+//!
+//! Only Argon2id hashes are recognized; bcrypt and other legacy formats are
+//! reported as errors.
+//!
+//! ## Provided items
+//! - [`hash_password`] / [`verify_password`] — default parameters
+//! - [`Argon2Params`] — memory, iterations and parallelism (OWASP defaults,
+//!   overridable from the environment)
+//! - [`verify_and_rehash`] / [`needs_rehash`] — parameter upgrades
+//!
+//! ## Example
+//! ```
+//! use wzs_web::auth::password::{
+//!     hash_password_with, verify_and_rehash, verify_password, Argon2Params, PasswordVerdict,
+//! };
+//!
+//! let old = Argon2Params { memory_kib: 64, iterations: 1, ..Default::default() };
+//! let hash = hash_password_with("correct horse", &old).unwrap();
+//! assert!(verify_password("correct horse", &hash).unwrap());
+//! assert!(!verify_password("wrong", &hash).unwrap());
+//!
+//! let current = Argon2Params { memory_kib: 128, ..old };
+//! match verify_and_rehash("correct horse", &hash, &current).unwrap() {
+//!     PasswordVerdict::Rehash(upgraded) => assert!(upgraded.contains("m=128")),
+//!     other => panic!("expected a rehash, got {other:?}"),
+//! }
+//! ```
+
+mod argon2;
+
+use std::env;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use base64::Engine;
+use rand::RngCore;
+use subtle::ConstantTimeEq;
+
+/// Salt length for new hashes, in bytes.
+const SALT_LEN: usize = 16;
+
+/// Upper bound on `m` (256 MiB), for configured parameters and stored
+/// hashes alike: verifying allocates `m` KiB up front, so a tampered or
+/// imported hash must not be able to request more.
+pub const MAX_MEMORY_KIB: u32 = 256 * 1024;
+
+/// Upper bound on `t` accepted from stored hashes.
+const MAX_ITERATIONS: u32 = 64;
+
+/// Upper bound on `p` accepted from stored hashes.
+const MAX_PARALLELISM: u32 = 64;
+
+/// Argon2id cost parameters.
+///
+/// Defaults follow the OWASP recommendation (19 MiB, 2 iterations, 1 lane).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Argon2Params {
+    /// Memory size in KiB (`m`).
+    pub memory_kib: u32,
+    /// Number of passes (`t`).
+    pub iterations: u32,
+    /// Number of lanes (`p`).
+    pub parallelism: u32,
+    /// Hash length in bytes.
+    pub output_len: usize,
+}
+
+impl Default for Argon2Params {
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+            output_len: 32,
+        }
+    }
+}
+
+impl Argon2Params {
+    /// Reads `PASSWORD_MEMORY_KIB`, `PASSWORD_ITERATIONS` and
+    /// `PASSWORD_PARALLELISM`, falling back to the defaults.
+    pub fn from_env() -> Self {
+        Self::from_env_with(|k| env::var(k).ok())
+    }
+
+    /// Same as [`from_env`](Self::from_env) with a custom provider.
+    ///
+    /// Unparsable values fall back to the defaults.
+    pub fn from_env_with<F>(get: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let d = Self::default();
+        let num = |k: &str, default: u32| {
+            get(k)
+                .and_then(|s| s.trim().parse::<u32>().ok())
+                .unwrap_or(default)
+        };
+        Self {
+            memory_kib: num("PASSWORD_MEMORY_KIB", d.memory_kib),
+            iterations: num("PASSWORD_ITERATIONS", d.iterations),
+            parallelism: num("PASSWORD_PARALLELISM", d.parallelism),
+            output_len: d.output_len,
+        }
+    }
+
+    /// Checks the parameters are usable.
+    ///
+    /// # Errors
+    /// Returns an error if a value is zero, memory is below `8 * p` KiB, the
+    /// hash is shorter than 4 bytes, or a value exceeds the supported range.
+    pub fn validate(&self) -> Result<()> {
+        if self.iterations == 0 || self.iterations > MAX_ITERATIONS {
+            bail!("argon2 iterations must be 1..={MAX_ITERATIONS}");
+        }
+        if self.parallelism == 0 || self.parallelism > MAX_PARALLELISM {
+            bail!("argon2 parallelism must be 1..={MAX_PARALLELISM}");
+        }
+        if self.memory_kib < 8 * self.parallelism || self.memory_kib > MAX_MEMORY_KIB {
+            bail!(
+                "argon2 memory must be {}..={MAX_MEMORY_KIB} KiB",
+                8 * self.parallelism
+            );
+        }
+        if !(4..=1024).contains(&self.output_len) {
+            bail!("argon2 output length must be 4..=1024 bytes");
+        }
+        Ok(())
+    }
+
+    /// Whether any parameter is below the one in `other` (a hash made with
+    /// these should be upgraded to `other`).
+    pub fn is_weaker_than(&self, other: &Argon2Params) -> bool {
+        self.memory_kib < other.memory_kib
+            || self.iterations < other.iterations
+            || self.parallelism < other.parallelism
+            || self.output_len < other.output_len
+    }
+}
+
+/// Outcome of [`verify_and_rehash`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PasswordVerdict {
+    /// The password does not match.
+    Invalid,
+    /// The password matches and the hash is current.
+    Valid,
+    /// The password matches; store this hash made with the current
+    /// parameters.
+    Rehash(String),
+}
+
+impl PasswordVerdict {
+    /// Whether the password matched.
+    pub fn is_valid(&self) -> bool {
+        !matches!(self, Self::Invalid)
+    }
+}
+
+/// Hashes `password` with the default [`Argon2Params`].
+///
+/// # Errors
+/// Never fails with the default parameters; kept fallible for symmetry with
+/// [`hash_password_with`].
+pub fn hash_password(password: &str) -> Result<String> {
+    hash_password_with(password, &Argon2Params::default())
+}
+
+/// Hashes `password` with `params` and a random salt.
+///
+/// # Errors
+/// Returns an error if `params` are invalid ([`Argon2Params::validate`]).
+pub fn hash_password_with(password: &str, params: &Argon2Params) -> Result<String> {
+    params.validate()?;
+    let mut salt = [0u8; SALT_LEN];
+    rand::rng().fill_bytes(&mut salt);
+    let hash = argon2::argon2id(password.as_bytes(), &salt, &[], &[], params);
+    Ok(format!(
+        "$argon2id$v={}$m={},t={},p={}${}${}",
+        argon2::VERSION,
+        params.memory_kib,
+        params.iterations,
+        params.parallelism,
+        STANDARD_NO_PAD.encode(salt),
+        STANDARD_NO_PAD.encode(hash),
+    ))
+}
+
+/// Checks `password` against a stored hash.
+///
+/// # Errors
+/// Returns an error if `hash` is not a supported Argon2id PHC string.
+pub fn verify_password(password: &str, hash: &str) -> Result<bool> {
+    let stored = PhcHash::parse(hash)?;
+    Ok(stored.matches(password))
+}
+
+/// Checks `password` and, when it matches a hash made with parameters
+/// weaker than `current` in any field, returns a fresh hash to store.
+/// Hashes at least as strong as `current` (e.g. after the configured cost
+/// was lowered) are kept.
+///
+/// # Errors
+/// Returns an error if `hash` cannot be parsed or `current` is invalid.
+pub fn verify_and_rehash(
+    password: &str,
+    hash: &str,
+    current: &Argon2Params,
+) -> Result<PasswordVerdict> {
+    let stored = PhcHash::parse(hash)?;
+    if !stored.matches(password) {
+        return Ok(PasswordVerdict::Invalid);
+    }
+    if stored.params.is_weaker_than(current) {
+        Ok(PasswordVerdict::Rehash(hash_password_with(
+            password, current,
+        )?))
+    } else {
+        Ok(PasswordVerdict::Valid)
+    }
+}
+
+/// Whether `hash` was made with parameters weaker than `current` in any
+/// field (or cannot be parsed).
+pub fn needs_rehash(hash: &str, current: &Argon2Params) -> bool {
+    PhcHash::parse(hash).map_or(true, |h| h.params.is_weaker_than(current))
+}
+
+/// A parsed `$argon2id$...` string.
+struct PhcHash {
+    params: Argon2Params,
+    salt: Vec<u8>,
+    hash: Vec<u8>,
+}
+
+impl PhcHash {
+    fn parse(s: &str) -> Result<Self> {
+        let mut parts = s.split('$');
+        if parts.next() != Some("") {
+            bail!("not a PHC hash string");
+        }
+        match parts.next() {
+            Some("argon2id") => {}
+            Some(alg) => bail!("unsupported password hash algorithm: {alg}"),
+            None => bail!("not a PHC hash string"),
+        }
+        let version = parts
+            .next()
+            .and_then(|v| v.strip_prefix("v="))
+            .ok_or_else(|| anyhow!("missing argon2 version"))?;
+        if version != argon2::VERSION.to_string() {
+            bail!("unsupported argon2 version: {version}");
+        }
+
+        let mut params = Argon2Params::default();
+        let (mut m, mut t, mut p) = (None, None, None);
+        for kv in parts
+            .next()
+            .ok_or_else(|| anyhow!("missing argon2 parameters"))?
+            .split(',')
+        {
+            let (k, v) = kv
+                .split_once('=')
+                .ok_or_else(|| anyhow!("malformed argon2 parameter: {kv}"))?;
+            let v: u32 = v.parse().with_context(|| format!("argon2 parameter {k}"))?;
+            match k {
+                "m" => m = Some(v),
+                "t" => t = Some(v),
+                "p" => p = Some(v),
+                _ => bail!("unknown argon2 parameter: {k}"),
+            }
+        }
+        params.memory_kib = m.ok_or_else(|| anyhow!("missing argon2 parameter m"))?;
+        params.iterations = t.ok_or_else(|| anyhow!("missing argon2 parameter t"))?;
+        params.parallelism = p.ok_or_else(|| anyhow!("missing argon2 parameter p"))?;
+
+        let salt = STANDARD_NO_PAD
+            .decode(parts.next().ok_or_else(|| anyhow!("missing salt"))?)
+            .context("argon2 salt")?;
+        let hash = STANDARD_NO_PAD
+            .decode(parts.next().ok_or_else(|| anyhow!("missing hash"))?)
+            .context("argon2 hash")?;
+        if parts.next().is_some() {
+            bail!("trailing data in PHC hash string");
+        }
+        if salt.len() < 8 {
+            bail!("argon2 salt is too short");
+        }
+        params.output_len = hash.len();
+        params.validate()?;
+
+        Ok(Self { params, salt, hash })
+    }
+
+    fn matches(&self, password: &str) -> bool {
+        let computed = argon2::argon2id(password.as_bytes(), &self.salt, &[], &[], &self.params);
+        computed.ct_eq(&self.hash).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cheap() -> Argon2Params {
+        Argon2Params {
+            memory_kib: 32,
+            iterations: 1,
+            parallelism: 1,
+            output_len: 32,
+        }
+    }
+
+    #[test]
+    fn hashes_and_verifies() {
+        let hash = hash_password_with("s3cret", &cheap()).unwrap();
+        assert!(hash.starts_with("$argon2id$v=19$m=32,t=1,p=1$"));
+        assert!(verify_password("s3cret", &hash).unwrap());
+        assert!(!verify_password("s3cret ", &hash).unwrap());
+
+        let again = hash_password_with("s3cret", &cheap()).unwrap();
+        assert_ne!(hash, again, "salts are random");
+    }
+
+    #[test]
+    fn verifies_hashes_from_other_implementations() {
+        // From the reference implementation's test suite (src/test.c).
+        let hash = "$argon2id$v=19$m=65536,t=2,p=1$c29tZXNhbHQ$\
+                    CTFhFdXPJO1aFaMaO6Mm5c8y7cJHAph8ArZWb2GRPPc";
+        assert!(verify_password("password", hash).unwrap());
+    }
+
+    #[test]
+    fn rehashes_when_parameters_change() {
+        let hash = hash_password_with("pw", &cheap()).unwrap();
+        assert_eq!(
+            verify_and_rehash("pw", &hash, &cheap()).unwrap(),
+            PasswordVerdict::Valid
+        );
+        assert!(!needs_rehash(&hash, &cheap()));
+
+        let stronger = Argon2Params {
+            memory_kib: 64,
+            iterations: 2,
+            ..cheap()
+        };
+        assert!(needs_rehash(&hash, &stronger));
+        let PasswordVerdict::Rehash(upgraded) = verify_and_rehash("pw", &hash, &stronger).unwrap()
+        else {
+            panic!("expected rehash");
+        };
+        assert!(upgraded.contains("m=64,t=2,p=1"));
+        assert!(verify_password("pw", &upgraded).unwrap());
+
+        let verdict = verify_and_rehash("nope", &hash, &stronger).unwrap();
+        assert_eq!(verdict, PasswordVerdict::Invalid);
+        assert!(!verdict.is_valid());
+    }
+
+    #[test]
+    fn keeps_hashes_stronger_than_current() {
+        let strong = Argon2Params {
+            memory_kib: 64,
+            iterations: 2,
+            ..cheap()
+        };
+        let hash = hash_password_with("pw", &strong).unwrap();
+
+        // Lowering the configured cost never downgrades stored hashes.
+        assert!(!needs_rehash(&hash, &cheap()));
+        assert_eq!(
+            verify_and_rehash("pw", &hash, &cheap()).unwrap(),
+            PasswordVerdict::Valid
+        );
+
+        // Any weaker field still triggers an upgrade.
+        let mixed = Argon2Params {
+            memory_kib: 32,
+            iterations: 3,
+            ..cheap()
+        };
+        assert!(needs_rehash(&hash, &mixed));
+        let longer = Argon2Params {
+            output_len: 64,
+            ..strong
+        };
+        assert!(needs_rehash(&hash, &longer));
+        assert!(!needs_rehash(&hash, &strong));
+    }
+
+    #[test]
+    fn rejects_unsupported_or_hostile_hashes() {
+        assert!(verify_password("pw", "$2b$12$abcdefghijklmnopqrstuv").is_err());
+        assert!(verify_password("pw", "plaintext").is_err());
+        assert!(verify_password("pw", "$argon2id$v=16$m=32,t=1,p=1$c29tZXNhbHQ$AAAAAAAA").is_err());
+        assert!(verify_password(
+            "pw",
+            "$argon2id$v=19$m=99999999,t=1,p=1$c29tZXNhbHQ$AAAAAAAA"
+        )
+        .is_err());
+        let oversized = format!(
+            "$argon2id$v=19$m={},t=1,p=1$c29tZXNhbHQ$AAAAAAAA",
+            MAX_MEMORY_KIB + 1
+        );
+        let err = verify_password("pw", &oversized).unwrap_err();
+        assert!(err.to_string().contains("argon2 memory"), "{err}");
+        assert!(needs_rehash("garbage", &cheap()));
+    }
+
+    #[test]
+    fn params_from_env_and_validation() {
+        let p = Argon2Params::from_env_with(|k| match k {
+            "PASSWORD_MEMORY_KIB" => Some("65536".into()),
+            "PASSWORD_ITERATIONS" => Some("x".into()),
+            _ => None,
+        });
+        assert_eq!(p.memory_kib, 65536);
+        assert_eq!(p.iterations, 2);
+        assert!(p.validate().is_ok());
+
+        let bad = Argon2Params {
+            memory_kib: 8,
+            parallelism: 2,
+            ..cheap()
+        };
+        assert!(bad.validate().is_err());
+        assert!(hash_password_with("pw", &bad).is_err());
+
+        let huge = Argon2Params {
+            memory_kib: 4 * 1024 * 1024,
+            ..cheap()
+        };
+        assert!(huge.validate().is_err());
+    }
+}
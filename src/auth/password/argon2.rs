@@ -0,0 +1,449 @@
+//! Argon2id (RFC 9106, version 0x13) and the BLAKE2b hash it is built on.
+//!
+//! Single-threaded: lanes are filled one after another, which gives the
+//! same output as a parallel implementation.
+
+use super::Argon2Params;
+
+/// Argon2 version 1.3.
+pub(super) const VERSION: u32 = 0x13;
+
+/// Argon2 type identifier for Argon2id.
+const TYPE_ID: u64 = 2;
+
+/// 64-bit words per 1 KiB block.
+const QWORDS: usize = 128;
+
+/// Slices per pass.
+const SYNC_POINTS: u32 = 4;
+
+type Block = [u64; QWORDS];
+
+/// Computes an Argon2id tag of `params.output_len` bytes.
+///
+/// Callers validate the parameters ([`Argon2Params::validate`]).
+pub(super) fn argon2id(
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    associated: &[u8],
+    params: &Argon2Params,
+) -> Vec<u8> {
+    let Argon2Params {
+        memory_kib,
+        iterations,
+        parallelism,
+        output_len: out_len,
+    } = *params;
+    let lanes = parallelism as usize;
+    let segment = (memory_kib / (SYNC_POINTS * parallelism)) as usize;
+    let lane_len = segment * SYNC_POINTS as usize;
+    let total = lane_len * lanes;
+
+    let mut h0_input = Vec::with_capacity(64 + password.len() + salt.len());
+    for v in [
+        parallelism,
+        out_len as u32,
+        memory_kib,
+        iterations,
+        VERSION,
+        TYPE_ID as u32,
+    ] {
+        h0_input.extend_from_slice(&v.to_le_bytes());
+    }
+    for part in [password, salt, secret, associated] {
+        h0_input.extend_from_slice(&(part.len() as u32).to_le_bytes());
+        h0_input.extend_from_slice(part);
+    }
+    let h0 = blake2b(64, &h0_input);
+
+    let mut memory: Vec<Block> = vec![[0; QWORDS]; total];
+    for lane in 0..lanes {
+        for j in 0..2u32 {
+            let mut seed = h0.clone();
+            seed.extend_from_slice(&j.to_le_bytes());
+            seed.extend_from_slice(&(lane as u32).to_le_bytes());
+            memory[lane * lane_len + j as usize] = block_from_bytes(&hash_long(1024, &seed));
+        }
+    }
+
+    let ctx = Fill {
+        lanes,
+        segment,
+        lane_len,
+        total: total as u64,
+        iterations: iterations as u64,
+    };
+    for pass in 0..iterations as u64 {
+        for slice in 0..SYNC_POINTS as usize {
+            for lane in 0..lanes {
+                ctx.segment(&mut memory, pass, slice, lane);
+            }
+        }
+    }
+
+    let mut last = memory[lane_len - 1];
+    for lane in 1..lanes {
+        xor_into(&mut last, &memory[lane * lane_len + lane_len - 1]);
+    }
+    let bytes: Vec<u8> = last.iter().flat_map(|w| w.to_le_bytes()).collect();
+    hash_long(out_len, &bytes)
+}
+
+struct Fill {
+    lanes: usize,
+    segment: usize,
+    lane_len: usize,
+    total: u64,
+    iterations: u64,
+}
+
+impl Fill {
+    fn segment(&self, memory: &mut [Block], pass: u64, slice: usize, lane: usize) {
+        let independent = pass == 0 && slice < (SYNC_POINTS / 2) as usize;
+        let mut input: Block = [0; QWORDS];
+        let mut addresses: Block = [0; QWORDS];
+        if independent {
+            input[0] = pass;
+            input[1] = lane as u64;
+            input[2] = slice as u64;
+            input[3] = self.total;
+            input[4] = self.iterations;
+            input[5] = TYPE_ID;
+        }
+
+        let start = if pass == 0 && slice == 0 {
+            if independent {
+                next_addresses(&mut input, &mut addresses);
+            }
+            2
+        } else {
+            0
+        };
+
+        for index in start..self.segment {
+            let column = slice * self.segment + index;
+            let current = lane * self.lane_len + column;
+            let previous = if column == 0 {
+                current + self.lane_len - 1
+            } else {
+                current - 1
+            };
+
+            let pseudo_rand = if independent {
+                if index % QWORDS == 0 {
+                    next_addresses(&mut input, &mut addresses);
+                }
+                addresses[index % QWORDS]
+            } else {
+                memory[previous][0]
+            };
+
+            let ref_lane = if pass == 0 && slice == 0 {
+                lane
+            } else {
+                ((pseudo_rand >> 32) % self.lanes as u64) as usize
+            };
+            let ref_index = self.reference_index(
+                pass,
+                slice,
+                index,
+                pseudo_rand & 0xffff_ffff,
+                ref_lane == lane,
+            );
+
+            let reference = memory[ref_lane * self.lane_len + ref_index];
+            let prev = memory[previous];
+            let out = &mut memory[current];
+            if pass == 0 {
+                *out = compress(&prev, &reference);
+            } else {
+                let old = *out;
+                *out = compress(&prev, &reference);
+                xor_into(out, &old);
+            }
+        }
+    }
+
+    /// Column of the reference block within its lane (RFC 9106, 3.4.2).
+    fn reference_index(
+        &self,
+        pass: u64,
+        slice: usize,
+        index: usize,
+        j1: u64,
+        same_lane: bool,
+    ) -> usize {
+        let area = if pass == 0 {
+            if slice == 0 {
+                index - 1
+            } else if same_lane {
+                slice * self.segment + index - 1
+            } else {
+                slice * self.segment - usize::from(index == 0)
+            }
+        } else if same_lane {
+            self.lane_len - self.segment + index - 1
+        } else {
+            self.lane_len - self.segment - usize::from(index == 0)
+        } as u64;
+
+        let x = (j1 * j1) >> 32;
+        let relative = area - 1 - ((area * x) >> 32);
+        let start = if pass == 0 || slice == SYNC_POINTS as usize - 1 {
+            0
+        } else {
+            (slice + 1) * self.segment
+        } as u64;
+        ((start + relative) % self.lane_len as u64) as usize
+    }
+}
+
+fn next_addresses(input: &mut Block, addresses: &mut Block) {
+    let zero: Block = [0; QWORDS];
+    input[6] += 1;
+    *addresses = compress(&zero, &compress(&zero, input));
+}
+
+fn xor_into(dst: &mut Block, src: &Block) {
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d ^= s;
+    }
+}
+
+fn block_from_bytes(bytes: &[u8]) -> Block {
+    let mut block = [0; QWORDS];
+    for (w, chunk) in block.iter_mut().zip(bytes.chunks_exact(8)) {
+        *w = u64::from_le_bytes(chunk.try_into().expect("8-byte chunk"));
+    }
+    block
+}
+
+/// Compression function G (RFC 9106, 3.5).
+fn compress(x: &Block, y: &Block) -> Block {
+    let mut r = *x;
+    xor_into(&mut r, y);
+    let mut q = r;
+    for row in 0..8 {
+        let mut idx = [0usize; 16];
+        for (k, i) in idx.iter_mut().enumerate() {
+            *i = row * 16 + k;
+        }
+        permute(&mut q, &idx);
+    }
+    for col in 0..8 {
+        let mut idx = [0usize; 16];
+        for (k, i) in idx.iter_mut().enumerate() {
+            *i = 2 * col + (k / 2) * 16 + (k % 2);
+        }
+        permute(&mut q, &idx);
+    }
+    xor_into(&mut q, &r);
+    q
+}
+
+/// Permutation P over the 16 words at `idx`.
+fn permute(b: &mut Block, idx: &[usize; 16]) {
+    let mut v = [0u64; 16];
+    for (slot, &i) in v.iter_mut().zip(idx) {
+        *slot = b[i];
+    }
+    gb(&mut v, 0, 4, 8, 12);
+    gb(&mut v, 1, 5, 9, 13);
+    gb(&mut v, 2, 6, 10, 14);
+    gb(&mut v, 3, 7, 11, 15);
+    gb(&mut v, 0, 5, 10, 15);
+    gb(&mut v, 1, 6, 11, 12);
+    gb(&mut v, 2, 7, 8, 13);
+    gb(&mut v, 3, 4, 9, 14);
+    for (slot, &i) in v.iter().zip(idx) {
+        b[i] = *slot;
+    }
+}
+
+fn gb(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize) {
+    let fma = |x: u64, y: u64| {
+        x.wrapping_add(y).wrapping_add(
+            2u64.wrapping_mul(x & 0xffff_ffff)
+                .wrapping_mul(y & 0xffff_ffff),
+        )
+    };
+    v[a] = fma(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = fma(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = fma(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = fma(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// Variable-length hash H' (RFC 9106, 3.3).
+fn hash_long(out_len: usize, input: &[u8]) -> Vec<u8> {
+    let mut prefixed = (out_len as u32).to_le_bytes().to_vec();
+    prefixed.extend_from_slice(input);
+    if out_len <= 64 {
+        return blake2b(out_len, &prefixed);
+    }
+    let r = out_len.div_ceil(32) - 2;
+    let mut out = Vec::with_capacity(out_len);
+    let mut v = blake2b(64, &prefixed);
+    out.extend_from_slice(&v[..32]);
+    for _ in 1..r {
+        v = blake2b(64, &v);
+        out.extend_from_slice(&v[..32]);
+    }
+    out.extend_from_slice(&blake2b(out_len - 32 * r, &v));
+    out
+}
+
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// Unkeyed BLAKE2b with an `out_len`-byte digest (1..=64).
+fn blake2b(out_len: usize, input: &[u8]) -> Vec<u8> {
+    let mut h = BLAKE2B_IV;
+    h[0] ^= 0x0101_0000 ^ out_len as u64;
+
+    let blocks = input.len().div_ceil(128).max(1);
+    for i in 0..blocks {
+        let chunk = &input[i * 128..input.len().min((i + 1) * 128)];
+        let mut block = [0u8; 128];
+        block[..chunk.len()].copy_from_slice(chunk);
+        let last = i == blocks - 1;
+        let counter = if last {
+            input.len() as u128
+        } else {
+            ((i + 1) * 128) as u128
+        };
+        blake2b_compress(&mut h, &block, counter, last);
+    }
+
+    h.iter()
+        .flat_map(|w| w.to_le_bytes())
+        .take(out_len)
+        .collect()
+}
+
+fn blake2b_compress(h: &mut [u64; 8], block: &[u8; 128], counter: u128, last: bool) {
+    let mut m = [0u64; 16];
+    for (w, chunk) in m.iter_mut().zip(block.chunks_exact(8)) {
+        *w = u64::from_le_bytes(chunk.try_into().expect("8-byte chunk"));
+    }
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&BLAKE2B_IV);
+    v[12] ^= counter as u64;
+    v[13] ^= (counter >> 64) as u64;
+    if last {
+        v[14] = !v[14];
+    }
+
+    let mut g = |a: usize, b: usize, c: usize, d: usize, x: u64, y: u64| {
+        v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+        v[d] = (v[d] ^ v[a]).rotate_right(32);
+        v[c] = v[c].wrapping_add(v[d]);
+        v[b] = (v[b] ^ v[c]).rotate_right(24);
+        v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+        v[d] = (v[d] ^ v[a]).rotate_right(16);
+        v[c] = v[c].wrapping_add(v[d]);
+        v[b] = (v[b] ^ v[c]).rotate_right(63);
+    };
+    for round in 0..12 {
+        let s = &SIGMA[round % 10];
+        g(0, 4, 8, 12, m[s[0]], m[s[1]]);
+        g(1, 5, 9, 13, m[s[2]], m[s[3]]);
+        g(2, 6, 10, 14, m[s[4]], m[s[5]]);
+        g(3, 7, 11, 15, m[s[6]], m[s[7]]);
+        g(0, 5, 10, 15, m[s[8]], m[s[9]]);
+        g(1, 6, 11, 12, m[s[10]], m[s[11]]);
+        g(2, 7, 8, 13, m[s[12]], m[s[13]]);
+        g(3, 4, 9, 14, m[s[14]], m[s[15]]);
+    }
+    for i in 0..8 {
+        h[i] ^= v[i] ^ v[i + 8];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn blake2b_matches_reference_digests() {
+        assert_eq!(
+            hex(&blake2b(64, b"")),
+            "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419\
+             d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce"
+        );
+        assert_eq!(
+            hex(&blake2b(64, b"abc")),
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+             7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        );
+    }
+
+    #[test]
+    fn argon2id_matches_rfc_9106_test_vector() {
+        let params = Argon2Params {
+            memory_kib: 32,
+            iterations: 3,
+            parallelism: 4,
+            output_len: 32,
+        };
+        let tag = argon2id(&[1; 32], &[2; 16], &[3; 8], &[4; 12], &params);
+        assert_eq!(
+            hex(&tag),
+            "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659"
+        );
+    }
+
+    #[test]
+    fn argon2id_matches_reference_implementation_vectors() {
+        // From the phc-winner-argon2 test suite ("password" / "somesalt").
+        for (parallelism, expected) in [
+            (
+                1,
+                "9dfeb910e80bad0311fee20f9c0e2b12c17987b4cac90c2ef54d5b3021c68bfe",
+            ),
+            (
+                2,
+                "6d093c501fd5999645e0ea3bf620d7b8be7fd2db59c20d9fff9539da2bf57037",
+            ),
+        ] {
+            let params = Argon2Params {
+                memory_kib: 256,
+                iterations: 2,
+                parallelism,
+                output_len: 32,
+            };
+            let tag = argon2id(b"password", b"somesalt", &[], &[], &params);
+            assert_eq!(hex(&tag), expected, "p={parallelism}");
+        }
+    }
+}
@@ -0,0 +1,414 @@
+//! # Password policy
+//!
+//! Rules a new password must satisfy, checked by registration and password
+//! reset handlers before hashing with [`auth::password`](crate::auth::password):
+//!
+//! - length between [`min_length`](PasswordPolicy::min_length) and
+//!   [`max_length`](PasswordPolicy::max_length) characters
+//! - an [estimated strength](strength::estimate_strength) score of at least
+//!   [`min_score`](PasswordPolicy::min_score)
+//! - not derived from the account's identifiers (username, email, name)
+//! - not found in known breaches, when a [`BreachCheck`] is configured
+//!   ([`PwnedPasswords`](breach::PwnedPasswords) with the `breach-check`
+//!   feature)
+//!
+//! [`PasswordPolicy::check`] runs the local rules and returns the strength
+//! for display; [`password_strength_handler`] exposes it to the frontend for
+//! live feedback. [`PasswordPolicy::enforce`] additionally consults the
+//! breach list and fails with a [`PasswordRejection`] (`422` with the
+//! violation codes).
+//!
+//! ## Example
+//! ```
+//! use wzs_web::auth::password_policy::{PasswordPolicy, PasswordViolation};
+//!
+//! let policy = PasswordPolicy::default();
+//! let report = policy.check("alice2024", &["alice@example.com"]);
+//! assert!(report.violations.contains(&PasswordViolation::SimilarToIdentifier));
+//! assert!(report.strength.score < 2);
+//!
+//! assert!(policy.check("plum-orbit-lantern-97", &["alice@example.com"]).is_ok());
+//! ```
+
+pub mod breach;
+pub mod strength;
+
+use std::env;
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use thiserror::Error;
+
+pub use breach::BreachCheck;
+pub use strength::{estimate_strength, PasswordStrength};
+
+/// A rule a password breaks.
+#[derive(Clone, Debug, PartialEq, Eq, Error, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum PasswordViolation {
+    #[error("must be at least {min} characters")]
+    TooShort { min: usize },
+    #[error("must be at most {max} characters")]
+    TooLong { max: usize },
+    #[error("is too easy to guess")]
+    TooWeak { score: u8, min_score: u8 },
+    #[error("must not contain your name, username or email address")]
+    SimilarToIdentifier,
+    #[error("appears in a known data breach")]
+    Breached { count: u64 },
+}
+
+/// Result of [`PasswordPolicy::check`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PasswordReport {
+    #[serde(flatten)]
+    pub strength: PasswordStrength,
+    pub violations: Vec<PasswordViolation>,
+}
+
+impl PasswordReport {
+    /// Whether no rule is broken.
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Error of [`PasswordPolicy::enforce`]: `422 Unprocessable Entity` with the
+/// violations as JSON.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error("password rejected")]
+pub struct PasswordRejection(pub Vec<PasswordViolation>);
+
+impl IntoResponse for PasswordRejection {
+    fn into_response(self) -> Response {
+        let messages: Vec<String> = self.0.iter().map(|v| format!("Password {v}")).collect();
+        let body = json!({
+            "ok": false,
+            "error": self.to_string(),
+            "violations": self.0,
+            "messages": messages,
+        });
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response()
+    }
+}
+
+/// Password rules.
+///
+/// Cloning is cheap; the breach checker is shared.
+#[derive(Clone)]
+pub struct PasswordPolicy {
+    min_length: usize,
+    max_length: usize,
+    min_score: u8,
+    reject_identifiers: bool,
+    breach: Option<Arc<dyn BreachCheck>>,
+}
+
+impl Default for PasswordPolicy {
+    /// 10 to 128 characters, score 3, identifiers rejected, no breach check.
+    fn default() -> Self {
+        Self {
+            min_length: 10,
+            max_length: 128,
+            min_score: 3,
+            reject_identifiers: true,
+            breach: None,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Reads `PASSWORD_MIN_LENGTH`, `PASSWORD_MAX_LENGTH` and
+    /// `PASSWORD_MIN_SCORE`, falling back to the defaults.
+    pub fn from_env() -> Self {
+        Self::from_env_with(|k| env::var(k).ok())
+    }
+
+    /// Same as [`from_env`](Self::from_env) with a custom provider.
+    ///
+    /// Unparsable values fall back to the defaults.
+    pub fn from_env_with<F>(get: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let d = Self::default();
+        let num = |k: &str, default: usize| {
+            get(k)
+                .and_then(|s| s.trim().parse::<usize>().ok())
+                .unwrap_or(default)
+        };
+        d.clone()
+            .min_length(num("PASSWORD_MIN_LENGTH", d.min_length))
+            .max_length(num("PASSWORD_MAX_LENGTH", d.max_length))
+            .min_score(num("PASSWORD_MIN_SCORE", d.min_score.into()).min(4) as u8)
+    }
+
+    /// Minimum length in characters.
+    pub fn min_length(mut self, n: usize) -> Self {
+        self.min_length = n;
+        self
+    }
+
+    /// Maximum length in characters (bounds hashing cost).
+    pub fn max_length(mut self, n: usize) -> Self {
+        self.max_length = n;
+        self
+    }
+
+    /// Minimum [`PasswordStrength::score`] (0–4).
+    pub fn min_score(mut self, score: u8) -> Self {
+        self.min_score = score.min(4);
+        self
+    }
+
+    /// Whether passwords containing an identifier are rejected.
+    pub fn reject_identifiers(mut self, enabled: bool) -> Self {
+        self.reject_identifiers = enabled;
+        self
+    }
+
+    /// Rejects passwords found by `check` in known breaches.
+    pub fn breach_check(mut self, check: Arc<dyn BreachCheck>) -> Self {
+        self.breach = Some(check);
+        self
+    }
+
+    /// Checks the local rules (everything but the breach list).
+    ///
+    /// `identifiers` are the account's username, email, name, …; they also
+    /// lower the strength estimate.
+    pub fn check(&self, password: &str, identifiers: &[&str]) -> PasswordReport {
+        let strength = estimate_strength(password, identifiers);
+        let mut violations = Vec::new();
+        let len = password.chars().count();
+        if len < self.min_length {
+            violations.push(PasswordViolation::TooShort {
+                min: self.min_length,
+            });
+        }
+        if len > self.max_length {
+            violations.push(PasswordViolation::TooLong {
+                max: self.max_length,
+            });
+        }
+        if self.reject_identifiers && similar_to_identifier(password, identifiers) {
+            violations.push(PasswordViolation::SimilarToIdentifier);
+        }
+        if strength.score < self.min_score {
+            violations.push(PasswordViolation::TooWeak {
+                score: strength.score,
+                min_score: self.min_score,
+            });
+        }
+        PasswordReport {
+            strength,
+            violations,
+        }
+    }
+
+    /// Checks all rules, including the breach list.
+    ///
+    /// A failing breach lookup is logged and ignored, so an outage of the
+    /// external service does not block sign-ups.
+    ///
+    /// # Errors
+    /// Returns the broken rules.
+    pub async fn enforce(
+        &self,
+        password: &str,
+        identifiers: &[&str],
+    ) -> Result<PasswordStrength, PasswordRejection> {
+        let PasswordReport {
+            strength,
+            mut violations,
+        } = self.check(password, identifiers);
+        if let Some(breach) = &self.breach {
+            match breach.breach_count(password).await {
+                Ok(0) => {}
+                Ok(count) => violations.push(PasswordViolation::Breached { count }),
+                Err(e) => tracing::warn!(error = %e, "password breach check failed"),
+            }
+        }
+        if violations.is_empty() {
+            Ok(strength)
+        } else {
+            Err(PasswordRejection(violations))
+        }
+    }
+}
+
+/// Whether `password` contains an identifier token of 4+ characters (also
+/// reversed or in l33t spelling), or is contained in one.
+fn similar_to_identifier(password: &str, identifiers: &[&str]) -> bool {
+    let lower = password.to_lowercase();
+    let leet: String = lower
+        .chars()
+        .map(|c| match c {
+            '4' | '@' => 'a',
+            '3' => 'e',
+            '1' | '!' => 'i',
+            '0' => 'o',
+            '5' | '$' => 's',
+            '7' => 't',
+            c => c,
+        })
+        .collect();
+    let reversed: String = lower.chars().rev().collect();
+    strength::identifier_tokens(identifiers)
+        .iter()
+        .filter(|t| t.chars().count() >= 4)
+        .any(|t| {
+            [&lower, &leet, &reversed]
+                .iter()
+                .any(|p| p.contains(t.as_str()))
+                || (lower.chars().count() >= 4 && t.contains(lower.as_str()))
+        })
+}
+
+/// Body of [`password_strength_handler`].
+#[derive(Clone, Debug, Deserialize)]
+pub struct StrengthRequest {
+    pub password: String,
+    /// Identifiers entered so far (username, email, name).
+    #[serde(default)]
+    pub identifiers: Vec<String>,
+}
+
+/// `POST` endpoint returning the [`PasswordReport`] of a candidate password
+/// (local rules only), for strength meters.
+///
+/// Mount with the policy as state:
+/// `.route("/password/strength", post(password_strength_handler)).with_state(policy)`.
+pub async fn password_strength_handler(
+    State(policy): State<PasswordPolicy>,
+    Json(req): Json<StrengthRequest>,
+) -> Json<PasswordReport> {
+    let identifiers: Vec<&str> = req.identifiers.iter().map(String::as_str).collect();
+    Json(policy.check(&req.password, &identifiers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use axum::{body::Body, extract::Request, routing::post, Router};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    struct Breached(u64);
+
+    #[async_trait]
+    impl BreachCheck for Breached {
+        async fn breach_count(&self, _: &str) -> Result<u64> {
+            if self.0 == u64::MAX {
+                anyhow::bail!("service down");
+            }
+            Ok(self.0)
+        }
+    }
+
+    #[test]
+    fn check_reports_every_broken_rule() {
+        let policy = PasswordPolicy::default().max_length(16);
+        let report = policy.check("Al1ce", &["alice"]);
+        assert_eq!(
+            report.violations,
+            [
+                PasswordViolation::TooShort { min: 10 },
+                PasswordViolation::SimilarToIdentifier,
+                PasswordViolation::TooWeak {
+                    score: report.strength.score,
+                    min_score: 3
+                },
+            ]
+        );
+
+        let long = "x".repeat(17);
+        assert!(policy
+            .check(&long, &[])
+            .violations
+            .contains(&PasswordViolation::TooLong { max: 16 }));
+        assert!(policy.check("vT9#qL2!mZr8", &["alice"]).is_ok());
+    }
+
+    #[test]
+    fn identifier_similarity() {
+        let ids = ["Bob.Martin@example.com", "bobm"];
+        assert!(similar_to_identifier("martin-rocks-99", &ids));
+        assert!(similar_to_identifier("nitram99", &["martin"]));
+        assert!(similar_to_identifier("M4rt1n!", &ids));
+        assert!(!similar_to_identifier("vT9#qL2!mZr8", &ids));
+        assert!(!PasswordPolicy::default()
+            .reject_identifiers(false)
+            .check("martin-rocks-99-xyz", &ids)
+            .violations
+            .contains(&PasswordViolation::SimilarToIdentifier));
+    }
+
+    #[test]
+    fn from_env_overrides_defaults() {
+        let policy = PasswordPolicy::from_env_with(|k| match k {
+            "PASSWORD_MIN_LENGTH" => Some("14".into()),
+            "PASSWORD_MIN_SCORE" => Some("9".into()),
+            "PASSWORD_MAX_LENGTH" => Some("x".into()),
+            _ => None,
+        });
+        assert_eq!(
+            (policy.min_length, policy.max_length, policy.min_score),
+            (14, 128, 4)
+        );
+    }
+
+    #[tokio::test]
+    async fn enforce_consults_the_breach_list() {
+        let pw = "plum-orbit-lantern-97";
+        let policy = PasswordPolicy::default().breach_check(Arc::new(Breached(3)));
+        assert_eq!(
+            policy.enforce(pw, &[]).await.unwrap_err(),
+            PasswordRejection(vec![PasswordViolation::Breached { count: 3 }])
+        );
+
+        let down = PasswordPolicy::default().breach_check(Arc::new(Breached(u64::MAX)));
+        assert_eq!(down.enforce(pw, &[]).await.unwrap().score, 4);
+
+        let res = PasswordRejection(vec![PasswordViolation::TooShort { min: 10 }]).into_response();
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["violations"][0]["code"], "too_short");
+        assert_eq!(json["violations"][0]["min"], 10);
+        assert_eq!(
+            json["messages"][0],
+            "Password must be at least 10 characters"
+        );
+    }
+
+    #[tokio::test]
+    async fn strength_endpoint_returns_the_report() {
+        let app = Router::new()
+            .route("/password/strength", post(password_strength_handler))
+            .with_state(PasswordPolicy::default());
+        let req = Request::post("/password/strength")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"password":"password","identifiers":["alice"]}"#,
+            ))
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["score"], 0);
+        assert_eq!(json["warning"], "This is a commonly used password.");
+        assert_eq!(json["violations"][0]["code"], "too_short");
+    }
+}
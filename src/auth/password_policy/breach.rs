@@ -0,0 +1,129 @@
+//! Breached-password lookups.
+//!
+//! [`BreachCheck`] is the port consulted by
+//! [`PasswordPolicy::enforce`](super::PasswordPolicy::enforce). With the
+//! `breach-check` feature, [`PwnedPasswords`] implements it over the Have I
+//! Been Pwned range API using k-anonymity: only the first five hex digits of
+//! the password's SHA-1 leave the process, and the match is done locally.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+#[cfg(feature = "breach-check")]
+use std::sync::Arc;
+
+#[cfg(feature = "breach-check")]
+use anyhow::bail;
+#[cfg(feature = "breach-check")]
+use sha1::{Digest, Sha1};
+
+#[cfg(feature = "breach-check")]
+use crate::auth::oauth::http::{HttpsClient, OAuthHttp};
+
+/// Looks up how often a password appears in known breaches.
+#[async_trait]
+pub trait BreachCheck: Send + Sync {
+    /// Number of breaches `password` appears in (0 if none).
+    async fn breach_count(&self, password: &str) -> Result<u64>;
+}
+
+/// [`BreachCheck`] over the Pwned Passwords range API.
+#[cfg(feature = "breach-check")]
+#[derive(Clone)]
+pub struct PwnedPasswords {
+    http: Arc<dyn OAuthHttp>,
+    base_url: String,
+}
+
+#[cfg(feature = "breach-check")]
+impl Default for PwnedPasswords {
+    fn default() -> Self {
+        Self::new(Arc::new(HttpsClient::new()))
+    }
+}
+
+#[cfg(feature = "breach-check")]
+impl PwnedPasswords {
+    /// Default API endpoint.
+    pub const DEFAULT_BASE_URL: &'static str = "https://api.pwnedpasswords.com";
+
+    /// Creates a checker sending requests through `http`.
+    pub fn new(http: Arc<dyn OAuthHttp>) -> Self {
+        Self {
+            http,
+            base_url: Self::DEFAULT_BASE_URL.into(),
+        }
+    }
+
+    /// Uses another endpoint (mirror, local mock).
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = url.into().trim_end_matches('/').to_string();
+        self
+    }
+}
+
+#[cfg(feature = "breach-check")]
+#[async_trait]
+impl BreachCheck for PwnedPasswords {
+    async fn breach_count(&self, password: &str) -> Result<u64> {
+        let hash: String = Sha1::digest(password.as_bytes())
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect();
+        let (prefix, suffix) = hash.split_at(5);
+        let res = self
+            .http
+            .get(&format!("{}/range/{prefix}", self.base_url), None)
+            .await?;
+        if !res.is_success() {
+            bail!("pwned passwords lookup failed with status {}", res.status);
+        }
+        let body = String::from_utf8_lossy(&res.body);
+        Ok(body
+            .lines()
+            .filter_map(|line| line.trim().split_once(':'))
+            .find(|(s, _)| s.eq_ignore_ascii_case(suffix))
+            .and_then(|(_, count)| count.trim().parse().ok())
+            .unwrap_or(0))
+    }
+}
+
+#[cfg(all(test, feature = "breach-check"))]
+mod tests {
+    use super::*;
+
+    use std::sync::Mutex;
+
+    use crate::auth::oauth::http::HttpResponse;
+
+    #[derive(Default)]
+    struct Fake(Mutex<Vec<String>>);
+
+    #[async_trait]
+    impl OAuthHttp for Fake {
+        async fn post_form(&self, _: &str, _: &[(&str, &str)]) -> Result<HttpResponse> {
+            unreachable!()
+        }
+
+        async fn get(&self, url: &str, _: Option<&str>) -> Result<HttpResponse> {
+            self.0.lock().unwrap().push(url.to_string());
+            // SHA-1("password") = 5BAA6 1E4C9B93F3F0682250B6CF8331B7EE68FD8
+            Ok(HttpResponse {
+                status: 200,
+                body: b"0018A45C4D1DEF81644B54AB7F969B88D65:1\r\n\
+                        1E4C9B93F3F0682250B6CF8331B7EE68FD8:9659365\r\n"
+                    .to_vec(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_only_the_hash_prefix() {
+        let http = Arc::new(Fake::default());
+        let pwned = PwnedPasswords::new(http.clone()).base_url("https://mock/");
+
+        assert_eq!(pwned.breach_count("password").await.unwrap(), 9_659_365);
+        assert_eq!(pwned.breach_count("not in the list").await.unwrap(), 0);
+        assert_eq!(http.0.lock().unwrap()[0], "https://mock/range/5BAA6");
+    }
+}
@@ -0,0 +1,501 @@
+//! zxcvbn-style password strength estimation.
+//!
+//! The password is split into the cheapest sequence of patterns an attacker
+//! would try — common passwords, the user's own identifiers (both also
+//! reversed and in l33t spelling), repeats, sequences, keyboard rows and
+//! years — with any rest guessed by brute force. The estimated number of
+//! guesses maps to a score from 0 (trivial) to 4 (very strong), using the
+//! zxcvbn thresholds.
+
+use serde::Serialize;
+
+/// Only this many characters are analysed; the rest count as brute force.
+const MAX_ANALYSED: usize = 100;
+
+/// Guesses per brute-forced character.
+const BRUTEFORCE_CARDINALITY: f64 = 10.0;
+
+/// Lower bound of guesses for a multi-character pattern.
+const MIN_PATTERN_GUESSES: f64 = 50.0;
+
+/// `log10` guesses thresholds for scores 1 to 4.
+const SCORE_THRESHOLDS: [f64; 4] = [3.0, 6.0, 8.0, 10.0];
+
+/// Most common passwords, by rank.
+const COMMON_PASSWORDS: &[&str] = &[
+    "123456",
+    "password",
+    "12345678",
+    "qwerty",
+    "123456789",
+    "12345",
+    "1234",
+    "111111",
+    "1234567",
+    "dragon",
+    "123123",
+    "baseball",
+    "abc123",
+    "football",
+    "monkey",
+    "letmein",
+    "696969",
+    "shadow",
+    "master",
+    "666666",
+    "qwertyuiop",
+    "123321",
+    "mustang",
+    "1234567890",
+    "michael",
+    "654321",
+    "superman",
+    "1qaz2wsx",
+    "7777777",
+    "121212",
+    "000000",
+    "qazwsx",
+    "123qwe",
+    "killer",
+    "trustno1",
+    "jordan",
+    "jennifer",
+    "zxcvbnm",
+    "asdfgh",
+    "hunter",
+    "buster",
+    "soccer",
+    "harley",
+    "batman",
+    "andrew",
+    "tigger",
+    "sunshine",
+    "iloveyou",
+    "2000",
+    "charlie",
+    "robert",
+    "thomas",
+    "hockey",
+    "ranger",
+    "daniel",
+    "starwars",
+    "klaster",
+    "112233",
+    "george",
+    "computer",
+    "michelle",
+    "jessica",
+    "pepper",
+    "1111",
+    "zxcvbn",
+    "555555",
+    "11111111",
+    "131313",
+    "freedom",
+    "777777",
+    "pass",
+    "maggie",
+    "159753",
+    "aaaaaa",
+    "ginger",
+    "princess",
+    "joshua",
+    "cheese",
+    "amanda",
+    "summer",
+    "love",
+    "ashley",
+    "nicole",
+    "chelsea",
+    "biteme",
+    "matthew",
+    "access",
+    "yankees",
+    "987654321",
+    "dallas",
+    "austin",
+    "thunder",
+    "taylor",
+    "matrix",
+    "welcome",
+    "admin",
+    "login",
+    "passw0rd",
+    "secret",
+    "changeme",
+    "qwerty123",
+    "password1",
+    "hello",
+    "whatever",
+    "flower",
+    "hottie",
+    "loveme",
+    "zaq1zaq1",
+    "password123",
+];
+
+/// Keyboard rows checked for straight runs.
+const KEYBOARD_ROWS: &[&str] = &["qwertyuiop", "asdfghjkl", "zxcvbnm", "1234567890"];
+
+/// Result of [`estimate_strength`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PasswordStrength {
+    /// 0 (too guessable) to 4 (very unguessable).
+    pub score: u8,
+    /// Estimated guesses needed, as a power of ten.
+    pub guesses_log10: f64,
+    /// Why the password is weak, if it is.
+    pub warning: Option<String>,
+    /// How to make it stronger.
+    pub suggestions: Vec<String>,
+}
+
+/// Kind of a recognized pattern.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Pattern {
+    Common,
+    Identifier,
+    Repeat,
+    Sequence,
+    Keyboard,
+    Year,
+}
+
+impl Pattern {
+    fn warning(self) -> &'static str {
+        match self {
+            Self::Common => "This is a commonly used password.",
+            Self::Identifier => "Passwords based on your name or email are easy to guess.",
+            Self::Repeat => "Repeated characters like \"aaa\" are easy to guess.",
+            Self::Sequence => "Sequences like \"abc\" or \"6543\" are easy to guess.",
+            Self::Keyboard => "Straight rows of keys are easy to guess.",
+            Self::Year => "Recent years are easy to guess.",
+        }
+    }
+}
+
+/// A pattern covering `chars[start..end]`.
+#[derive(Clone, Copy, Debug)]
+struct Match {
+    start: usize,
+    end: usize,
+    pattern: Pattern,
+    guesses: f64,
+}
+
+/// Estimates how hard `password` is to guess, treating `user_inputs`
+/// (username, email, name, …) as known to the attacker.
+pub fn estimate_strength(password: &str, user_inputs: &[&str]) -> PasswordStrength {
+    let all: Vec<char> = password.chars().collect();
+    let chars = &all[..all.len().min(MAX_ANALYSED)];
+    let rest = (all.len() - chars.len()) as f64;
+
+    let mut dictionary: Vec<(String, Pattern, f64)> = COMMON_PASSWORDS
+        .iter()
+        .enumerate()
+        .map(|(rank, w)| (w.to_string(), Pattern::Common, (rank + 1) as f64))
+        .collect();
+    for (rank, token) in identifier_tokens(user_inputs).into_iter().enumerate() {
+        dictionary.push((token, Pattern::Identifier, (rank + 1) as f64));
+    }
+
+    let mut matches = dictionary_matches(chars, &dictionary);
+    matches.extend(repeat_matches(chars));
+    matches.extend(sequence_matches(chars));
+    matches.extend(keyboard_matches(chars));
+    matches.extend(year_matches(chars));
+
+    // Cheapest segmentation: best[i] = (log10 guesses of chars[..i], match
+    // ending at i).
+    let n = chars.len();
+    let mut best: Vec<(f64, Option<Match>)> = vec![(f64::INFINITY, None); n + 1];
+    best[0].0 = 0.0;
+    for end in 1..=n {
+        let mut cost = best[end - 1].0 + BRUTEFORCE_CARDINALITY.log10();
+        let mut via = None;
+        for m in matches.iter().filter(|m| m.end == end) {
+            let c = best[m.start].0 + m.guesses.max(MIN_PATTERN_GUESSES).log10();
+            if c < cost {
+                cost = c;
+                via = Some(*m);
+            }
+        }
+        best[end] = (cost, via);
+    }
+
+    let mut used = Vec::new();
+    let mut i = n;
+    while i > 0 {
+        match best[i].1 {
+            Some(m) => {
+                used.push(m);
+                i = m.start;
+            }
+            None => i -= 1,
+        }
+    }
+
+    let guesses_log10 = best[n].0 + rest * BRUTEFORCE_CARDINALITY.log10();
+    let score = SCORE_THRESHOLDS
+        .iter()
+        .filter(|t| guesses_log10 >= **t)
+        .count() as u8;
+    let (warning, suggestions) = feedback(score, &used);
+    PasswordStrength {
+        score,
+        guesses_log10: (guesses_log10 * 100.0).round() / 100.0,
+        warning,
+        suggestions,
+    }
+}
+
+fn feedback(score: u8, used: &[Match]) -> (Option<String>, Vec<String>) {
+    if score >= 3 {
+        return (None, Vec::new());
+    }
+    let warning = used
+        .iter()
+        .max_by_key(|m| m.end - m.start)
+        .map(|m| m.pattern.warning().to_string());
+    let mut suggestions = vec!["Add another word or two. Uncommon words are better.".to_string()];
+    if used.iter().any(|m| m.pattern == Pattern::Identifier) {
+        suggestions.push("Avoid your name, username or email address.".into());
+    }
+    if used.iter().any(|m| {
+        matches!(
+            m.pattern,
+            Pattern::Repeat | Pattern::Sequence | Pattern::Keyboard
+        )
+    }) {
+        suggestions.push("Avoid repeated characters, sequences and keyboard patterns.".into());
+    }
+    (warning, suggestions)
+}
+
+/// Lowercase alphanumeric tokens (3+ characters) of the identifiers, whole
+/// identifiers first.
+pub(crate) fn identifier_tokens(user_inputs: &[&str]) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut push = |t: String| {
+        if t.chars().count() >= 3 && !tokens.contains(&t) {
+            tokens.push(t);
+        }
+    };
+    for input in user_inputs {
+        push(input.trim().to_lowercase());
+    }
+    for input in user_inputs {
+        for part in input.split(|c: char| !c.is_alphanumeric()) {
+            push(part.to_lowercase());
+        }
+    }
+    tokens
+}
+
+/// Lowercases and undoes common l33t substitutions.
+fn normalize(c: char) -> char {
+    match c {
+        '4' | '@' => 'a',
+        '3' => 'e',
+        '1' | '!' => 'i',
+        '0' => 'o',
+        '5' | '$' => 's',
+        '7' => 't',
+        c => c.to_ascii_lowercase(),
+    }
+}
+
+fn dictionary_matches(chars: &[char], dictionary: &[(String, Pattern, f64)]) -> Vec<Match> {
+    let mut out = Vec::new();
+    for start in 0..chars.len() {
+        for end in start + 3..=chars.len() {
+            let window = &chars[start..end];
+            let lower: String = window.iter().map(|c| c.to_ascii_lowercase()).collect();
+            let leet: String = window.iter().map(|c| normalize(*c)).collect();
+            let reversed: String = lower.chars().rev().collect();
+            for (word, pattern, rank) in dictionary {
+                // Reversed and l33t variants double the guesses.
+                let factor = if *word == lower {
+                    1.0
+                } else if *word == reversed || *word == leet {
+                    2.0
+                } else {
+                    continue;
+                };
+                out.push(Match {
+                    start,
+                    end,
+                    pattern: *pattern,
+                    guesses: rank * factor * case_variations(window),
+                });
+            }
+        }
+    }
+    out
+}
+
+/// Guess multiplier for capitalization: none, first letter only, or mixed.
+fn case_variations(window: &[char]) -> f64 {
+    let upper = window.iter().filter(|c| c.is_uppercase()).count();
+    if upper == 0 {
+        1.0
+    } else if (upper == 1 && window[0].is_uppercase()) || upper == window.len() {
+        2.0
+    } else {
+        window.len() as f64 * 2.0
+    }
+}
+
+fn repeat_matches(chars: &[char]) -> Vec<Match> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = start + 1;
+        while end < chars.len() && chars[end] == chars[start] {
+            end += 1;
+        }
+        if end - start >= 3 {
+            out.push(Match {
+                start,
+                end,
+                pattern: Pattern::Repeat,
+                guesses: BRUTEFORCE_CARDINALITY * (end - start) as f64,
+            });
+        }
+        start = end;
+    }
+    out
+}
+
+fn sequence_matches(chars: &[char]) -> Vec<Match> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    while start + 2 < chars.len() {
+        let delta = chars[start + 1] as i32 - chars[start] as i32;
+        let mut end = start + 1;
+        if delta.abs() == 1 {
+            while end < chars.len() && chars[end] as i32 - chars[end - 1] as i32 == delta {
+                end += 1;
+            }
+        }
+        if end - start >= 3 {
+            let first = chars[start];
+            let base = if matches!(first, 'a' | 'A' | 'z' | 'Z' | '0' | '1' | '9') {
+                4.0
+            } else if first.is_ascii_digit() {
+                10.0
+            } else {
+                26.0
+            };
+            out.push(Match {
+                start,
+                end,
+                pattern: Pattern::Sequence,
+                guesses: base * (end - start) as f64 * if delta < 0 { 2.0 } else { 1.0 },
+            });
+            start = end - 1;
+        } else {
+            start += 1;
+        }
+    }
+    out
+}
+
+fn keyboard_matches(chars: &[char]) -> Vec<Match> {
+    let lower: Vec<char> = chars.iter().map(|c| c.to_ascii_lowercase()).collect();
+    let mut out = Vec::new();
+    for row in KEYBOARD_ROWS {
+        let row: Vec<char> = row.chars().collect();
+        for start in 0..lower.len() {
+            let Some(pos) = row.iter().position(|c| *c == lower[start]) else {
+                continue;
+            };
+            let mut len = 1;
+            while start + len < lower.len()
+                && pos + len < row.len()
+                && lower[start + len] == row[pos + len]
+            {
+                len += 1;
+            }
+            if len >= 4 {
+                out.push(Match {
+                    start,
+                    end: start + len,
+                    pattern: Pattern::Keyboard,
+                    guesses: 40.0 * len as f64,
+                });
+            }
+        }
+    }
+    out
+}
+
+fn year_matches(chars: &[char]) -> Vec<Match> {
+    let mut out = Vec::new();
+    for start in 0..chars.len().saturating_sub(3) {
+        let window: String = chars[start..start + 4].iter().collect();
+        let Ok(year) = window.parse::<u32>() else {
+            continue;
+        };
+        if (1900..=2099).contains(&year) {
+            out.push(Match {
+                start,
+                end: start + 4,
+                pattern: Pattern::Year,
+                guesses: 200.0,
+            });
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn common_and_patterned_passwords_score_low() {
+        for pw in [
+            "password",
+            "P@ssw0rd",
+            "drowssap",
+            "aaaaaaaaaaaa",
+            "abcdefgh",
+            "qwertyuiop",
+            "123456789",
+        ] {
+            let s = estimate_strength(pw, &[]);
+            assert!(s.score <= 1, "{pw}: {s:?}");
+            assert!(s.warning.is_some(), "{pw}");
+            assert!(!s.suggestions.is_empty());
+        }
+        assert_eq!(
+            estimate_strength("password", &[]).warning.as_deref(),
+            Some(Pattern::Common.warning())
+        );
+    }
+
+    #[test]
+    fn identifiers_weaken_a_password() {
+        let alone = estimate_strength("kzbalicia2019", &[]);
+        let known = estimate_strength("kzbalicia2019", &["Alicia.Kzb@example.com", "kzb"]);
+        assert!(known.guesses_log10 < alone.guesses_log10);
+        assert!(known
+            .suggestions
+            .iter()
+            .any(|s| s.contains("email address")));
+    }
+
+    #[test]
+    fn long_random_passphrases_score_high() {
+        for pw in [
+            "correcthorsebatterystaple",
+            "vT9#qL2!mZr8",
+            "plum-orbit-lantern-97",
+        ] {
+            let s = estimate_strength(pw, &["alice"]);
+            assert_eq!(s.score, 4, "{pw}: {s:?}");
+            assert_eq!(s.warning, None);
+        }
+    }
+}
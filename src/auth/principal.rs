@@ -1,3 +1,7 @@
+use serde_json::Value;
+
+use crate::auth::jwt::Claims;
+
 /// An authenticated principal extracted from an authentication mechanism
 /// (e.g. JWT).
 ///
@@ -8,11 +12,12 @@
 /// as:
 ///
 /// - user / member / admin
-/// - roles or permissions
 /// - domain status or profile information
 ///
-/// Instead, it only carries the **JWT subject**, leaving all interpretation
-/// and authorization decisions to the application layer.
+/// Instead, it carries the **JWT subject** plus the roles and scopes the
+/// token asserts (see [`from_claims`](Self::from_claims)), leaving their
+/// meaning to the application layer. Common checks on them are provided by
+/// [`authorize`](crate::auth::authorize).
 ///
 /// # Design Intent
 ///
@@ -47,6 +52,17 @@ pub struct CurrentUser {
     /// The application decides whether it represents a user ID, member ID,
     /// admin ID, or something else.
     pub subject: String,
+
+    /// Roles asserted by the token (`roles` claim).
+    pub roles: Vec<String>,
+
+    /// OAuth-style scopes asserted by the token (`scope` / `scopes` claim).
+    pub scopes: Vec<String>,
+
+    /// The JWT `jti` claim, if any; lets the application revoke the token
+    /// the request was authenticated with (see
+    /// [`revocation`](crate::auth::revocation)).
+    pub token_id: Option<String>,
 }
 
 impl CurrentUser {
@@ -66,8 +82,88 @@ impl CurrentUser {
     pub fn new(subject: impl Into<String>) -> Self {
         Self {
             subject: subject.into(),
+            roles: Vec::new(),
+            scopes: Vec::new(),
+            token_id: None,
         }
     }
+
+    /// Builds a principal from verified JWT claims.
+    ///
+    /// Roles come from a `roles` claim (array of strings, or one string).
+    /// Scopes come from a space-delimited `scope` claim (RFC 8693) or a
+    /// `scopes` array. Malformed claims are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wzs_web::auth::jwt::{decode_jwt, JwtBuilder};
+    /// use wzs_web::auth::CurrentUser;
+    ///
+    /// let token = JwtBuilder::new("7")
+    ///     .claim("roles", ["admin"])
+    ///     .claim("scope", "orders:read orders:write")
+    ///     .sign("secret")
+    ///     .unwrap();
+    /// let user = CurrentUser::from_claims(&decode_jwt(&token, "secret").unwrap());
+    /// assert!(user.has_role("admin"));
+    /// assert!(user.has_scope("orders:write"));
+    /// ```
+    pub fn from_claims(claims: &Claims) -> Self {
+        let strings = |name: &str| match claims.extra.get(name) {
+            Some(Value::String(s)) => vec![s.clone()],
+            Some(Value::Array(items)) => items
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let scopes = match claims.extra.get("scope") {
+            Some(Value::String(s)) => s.split_whitespace().map(str::to_string).collect(),
+            _ => strings("scopes"),
+        };
+        Self {
+            subject: claims.sub.clone(),
+            roles: strings("roles"),
+            scopes,
+            token_id: claims.jti.clone(),
+        }
+    }
+
+    /// Sets the roles.
+    pub fn with_roles<I, R>(mut self, roles: I) -> Self
+    where
+        I: IntoIterator<Item = R>,
+        R: Into<String>,
+    {
+        self.roles = roles.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the scopes.
+    pub fn with_scopes<I, R>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = R>,
+        R: Into<String>,
+    {
+        self.scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Whether the principal has `role`.
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    /// Whether the principal has at least one of `roles`.
+    pub fn has_any_role(&self, roles: &[&str]) -> bool {
+        roles.iter().any(|r| self.has_role(r))
+    }
+
+    /// Whether the principal was granted `scope`.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
 }
 
 #[cfg(test)]
@@ -104,4 +200,32 @@ mod tests {
         // The library must not make assumptions about the subject format
         assert_eq!(user.subject, "member:999");
     }
+
+    #[test]
+    fn reads_roles_and_scopes_from_claims() {
+        let claims: Claims = serde_json::from_value(serde_json::json!({
+            "sub": "1",
+            "exp": 0,
+            "roles": "admin",
+            "scopes": ["a", "b", 3],
+        }))
+        .unwrap();
+        let user = CurrentUser::from_claims(&claims);
+        assert_eq!(user.roles, ["admin"]);
+        assert_eq!(user.scopes, ["a", "b"]);
+        assert!(user.has_any_role(&["editor", "admin"]));
+        assert!(!user.has_scope("c"));
+
+        let plain = CurrentUser::from_claims(&Claims {
+            sub: "2".into(),
+            exp: 0,
+            iss: None,
+            aud: None,
+            iat: None,
+            nbf: None,
+            jti: None,
+            extra: Default::default(),
+        });
+        assert_eq!(plain, CurrentUser::new("2"));
+    }
 }
@@ -0,0 +1,380 @@
+//! # Refresh tokens
+//!
+//! Short-lived access tokens ([`jwt`](crate::auth::jwt)) paired with
+//! long-lived, single-use refresh tokens.
+//!
+//! ## Design principles
+//! - Refresh tokens are JWTs signed with a key derived from the access
+//!   secret, so one can never be used as an access token
+//! - Every refresh token has an ID (`jti`) and belongs to a *family*
+//!   started at sign-in; rotation keeps the family
+//! - Each refresh token is single use: [`rotate_refresh_token`] consumes it
+//!   through the [`RefreshRevocation`] hook
+//! - Presenting an already used token revokes the whole family (the token
+//!   was probably stolen), as does [`revoke_refresh_token`] on logout
+//!
+//! ## Provided items
+//! - [`TokenLifetimes`] — access / refresh lifetimes
+//! - [`create_token_pair`] / [`rotate_refresh_token`] / [`revoke_refresh_token`]
+//! - [`RefreshRevocation`] / [`MemoryRefreshRevocation`] — revocation hook
+//!   and in-process implementation (applications store IDs in their DB)
+//!
+//! ## Example
+//! ```
+//! use wzs_web::auth::jwt::decode_jwt;
+//! use wzs_web::auth::refresh::{
+//!     create_token_pair, rotate_refresh_token, MemoryRefreshRevocation, TokenLifetimes,
+//! };
+//!
+//! let secret = "test-secret";
+//! let lifetimes = TokenLifetimes::default();
+//! let revocation = MemoryRefreshRevocation::default();
+//!
+//! let pair = create_token_pair(42, secret, &lifetimes, &revocation).unwrap();
+//! assert_eq!(decode_jwt(&pair.access_token, secret).unwrap().sub, "42");
+//!
+//! let next = rotate_refresh_token(&pair.refresh_token, secret, &lifetimes, &revocation).unwrap();
+//! assert_ne!(next.refresh_token, pair.refresh_token);
+//!
+//! // The first refresh token was consumed; replaying it fails.
+//! assert!(rotate_refresh_token(&pair.refresh_token, secret, &lifetimes, &revocation).is_err());
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::auth::jwt::create_jwt_with_ttl;
+
+/// `typ` claim of refresh tokens.
+const REFRESH_TYPE: &str = "refresh";
+
+/// Lifetimes of the two tokens of a [`TokenPair`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenLifetimes {
+    pub access: Duration,
+    pub refresh: Duration,
+}
+
+impl Default for TokenLifetimes {
+    /// 15-minute access tokens, 30-day refresh tokens.
+    fn default() -> Self {
+        Self {
+            access: Duration::minutes(15),
+            refresh: Duration::days(30),
+        }
+    }
+}
+
+impl TokenLifetimes {
+    /// Creates lifetimes from explicit durations.
+    pub fn new(access: Duration, refresh: Duration) -> Self {
+        Self { access, refresh }
+    }
+}
+
+/// Access and refresh token issued together.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Expiry of the access token (UNIX seconds).
+    pub access_expires_at: i64,
+    /// Expiry of the refresh token (UNIX seconds).
+    pub refresh_expires_at: i64,
+}
+
+/// Claims of a refresh token.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RefreshClaims {
+    /// Subject — typically the user ID
+    pub sub: String,
+    /// Expiration timestamp (UTC, seconds since UNIX epoch)
+    pub exp: usize,
+    /// Token ID
+    pub jti: String,
+    /// Family ID, shared by all tokens rotated from one sign-in
+    pub fam: String,
+    /// Always `"refresh"`
+    pub typ: String,
+}
+
+/// An issued refresh token, as handed to [`RefreshRevocation::issued`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefreshTokenRecord {
+    pub jti: String,
+    pub family: String,
+    pub sub: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Revocation hook: tracks refresh token IDs.
+///
+/// Applications typically persist the records in a table keyed by `jti`
+/// with a `family` index.
+pub trait RefreshRevocation: Send + Sync {
+    /// Records a newly issued refresh token.
+    fn issued(&self, record: &RefreshTokenRecord) -> Result<()>;
+
+    /// Marks `jti` as used; returns `false` if it was unknown, already used
+    /// or revoked. Must be atomic so a token cannot be rotated twice.
+    fn consume(&self, jti: &str) -> Result<bool>;
+
+    /// Revokes every token of `family` (logout, reuse detection).
+    fn revoke_family(&self, family: &str) -> Result<()>;
+}
+
+/// In-process [`RefreshRevocation`].
+#[derive(Default)]
+pub struct MemoryRefreshRevocation {
+    inner: Mutex<MemoryState>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    /// Unused tokens: `jti` → family.
+    active: HashMap<String, String>,
+    revoked_families: HashSet<String>,
+}
+
+impl RefreshRevocation for MemoryRefreshRevocation {
+    fn issued(&self, record: &RefreshTokenRecord) -> Result<()> {
+        self.inner
+            .lock()
+            .unwrap()
+            .active
+            .insert(record.jti.clone(), record.family.clone());
+        Ok(())
+    }
+
+    fn consume(&self, jti: &str) -> Result<bool> {
+        let mut state = self.inner.lock().unwrap();
+        Ok(match state.active.remove(jti) {
+            Some(family) => !state.revoked_families.contains(&family),
+            None => false,
+        })
+    }
+
+    fn revoke_family(&self, family: &str) -> Result<()> {
+        let mut state = self.inner.lock().unwrap();
+        state.active.retain(|_, f| f != family);
+        state.revoked_families.insert(family.to_string());
+        Ok(())
+    }
+}
+
+/// Issues an access token and a refresh token starting a new family
+/// (sign-in).
+pub fn create_token_pair(
+    id: u64,
+    secret: &str,
+    lifetimes: &TokenLifetimes,
+    revocation: &dyn RefreshRevocation,
+) -> Result<TokenPair> {
+    issue_pair(
+        &id.to_string(),
+        &Uuid::new_v4().to_string(),
+        secret,
+        lifetimes,
+        revocation,
+    )
+}
+
+/// Exchanges a refresh token for a new pair in the same family.
+///
+/// # Errors
+/// - the token is malformed, expired or not a refresh token
+/// - the token was already used or revoked; the family is then revoked
+pub fn rotate_refresh_token(
+    refresh_token: &str,
+    secret: &str,
+    lifetimes: &TokenLifetimes,
+    revocation: &dyn RefreshRevocation,
+) -> Result<TokenPair> {
+    let claims = decode_refresh_token(refresh_token, secret)?;
+    if !revocation.consume(&claims.jti)? {
+        warn!(
+            sub = claims.sub,
+            family = claims.fam,
+            "refresh token reused or revoked; revoking family"
+        );
+        revocation.revoke_family(&claims.fam)?;
+        bail!("refresh token is no longer valid");
+    }
+    issue_pair(&claims.sub, &claims.fam, secret, lifetimes, revocation)
+}
+
+/// Revokes the family of `refresh_token` (logout).
+///
+/// Expired tokens are rejected like any other invalid token.
+pub fn revoke_refresh_token(
+    refresh_token: &str,
+    secret: &str,
+    revocation: &dyn RefreshRevocation,
+) -> Result<()> {
+    let claims = decode_refresh_token(refresh_token, secret)?;
+    revocation.revoke_family(&claims.fam)
+}
+
+/// Validates and decodes a refresh token (signature, expiry and `typ`).
+pub fn decode_refresh_token(token: &str, secret: &str) -> Result<RefreshClaims> {
+    let claims = decode::<RefreshClaims>(
+        token,
+        &DecodingKey::from_secret(&refresh_key(secret)),
+        &Validation::default(),
+    )?
+    .claims;
+    if claims.typ != REFRESH_TYPE {
+        bail!("not a refresh token");
+    }
+    Ok(claims)
+}
+
+fn issue_pair(
+    sub: &str,
+    family: &str,
+    secret: &str,
+    lifetimes: &TokenLifetimes,
+    revocation: &dyn RefreshRevocation,
+) -> Result<TokenPair> {
+    let now = Utc::now();
+    let expiry = |ttl: Duration| {
+        now.checked_add_signed(ttl)
+            .ok_or_else(|| anyhow!("invalid token lifetime"))
+    };
+    let access_expires_at = expiry(lifetimes.access)?;
+    let refresh_expires_at = expiry(lifetimes.refresh)?;
+
+    let record = RefreshTokenRecord {
+        jti: Uuid::new_v4().to_string(),
+        family: family.to_string(),
+        sub: sub.to_string(),
+        expires_at: refresh_expires_at,
+    };
+    let claims = RefreshClaims {
+        sub: record.sub.clone(),
+        exp: refresh_expires_at.timestamp() as usize,
+        jti: record.jti.clone(),
+        fam: record.family.clone(),
+        typ: REFRESH_TYPE.into(),
+    };
+    let refresh_token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(&refresh_key(secret)),
+    )?;
+    revocation.issued(&record)?;
+
+    Ok(TokenPair {
+        access_token: create_jwt_with_ttl(sub, secret, lifetimes.access)?,
+        refresh_token,
+        access_expires_at: access_expires_at.timestamp(),
+        refresh_expires_at: refresh_expires_at.timestamp(),
+    })
+}
+
+/// Signing key for refresh tokens, distinct from the access token key.
+fn refresh_key(secret: &str) -> Vec<u8> {
+    format!("{secret}\0{REFRESH_TYPE}").into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::jwt::decode_jwt;
+
+    const SECRET: &str = "unit-test-secret";
+
+    fn pair(revocation: &MemoryRefreshRevocation) -> TokenPair {
+        create_token_pair(7, SECRET, &TokenLifetimes::default(), revocation).unwrap()
+    }
+
+    #[test]
+    fn pair_uses_configured_lifetimes_and_separate_keys() {
+        let revocation = MemoryRefreshRevocation::default();
+        let lifetimes = TokenLifetimes::new(Duration::minutes(5), Duration::days(1));
+        let pair = create_token_pair(7, SECRET, &lifetimes, &revocation).unwrap();
+
+        let now = Utc::now().timestamp();
+        assert!((pair.access_expires_at - now - 300).abs() <= 2);
+        assert!((pair.refresh_expires_at - now - 86_400).abs() <= 2);
+
+        assert_eq!(decode_jwt(&pair.access_token, SECRET).unwrap().sub, "7");
+        assert!(decode_jwt(&pair.refresh_token, SECRET).is_err());
+        assert!(decode_refresh_token(&pair.access_token, SECRET).is_err());
+        assert_eq!(
+            decode_refresh_token(&pair.refresh_token, SECRET)
+                .unwrap()
+                .sub,
+            "7"
+        );
+    }
+
+    #[test]
+    fn rotation_keeps_the_family_and_consumes_the_old_token() {
+        let revocation = MemoryRefreshRevocation::default();
+        let lifetimes = TokenLifetimes::default();
+        let first = pair(&revocation);
+        let second =
+            rotate_refresh_token(&first.refresh_token, SECRET, &lifetimes, &revocation).unwrap();
+
+        let (a, b) = (
+            decode_refresh_token(&first.refresh_token, SECRET).unwrap(),
+            decode_refresh_token(&second.refresh_token, SECRET).unwrap(),
+        );
+        assert_eq!(a.fam, b.fam);
+        assert_ne!(a.jti, b.jti);
+        assert_eq!(decode_jwt(&second.access_token, SECRET).unwrap().sub, "7");
+    }
+
+    #[test]
+    fn reuse_revokes_the_whole_family() {
+        let revocation = MemoryRefreshRevocation::default();
+        let lifetimes = TokenLifetimes::default();
+        let first = pair(&revocation);
+        let second =
+            rotate_refresh_token(&first.refresh_token, SECRET, &lifetimes, &revocation).unwrap();
+
+        assert!(
+            rotate_refresh_token(&first.refresh_token, SECRET, &lifetimes, &revocation).is_err()
+        );
+        assert!(
+            rotate_refresh_token(&second.refresh_token, SECRET, &lifetimes, &revocation).is_err(),
+            "legitimate holder must sign in again after reuse"
+        );
+    }
+
+    #[test]
+    fn logout_revokes_only_that_family() {
+        let revocation = MemoryRefreshRevocation::default();
+        let lifetimes = TokenLifetimes::default();
+        let laptop = pair(&revocation);
+        let phone = pair(&revocation);
+
+        revoke_refresh_token(&laptop.refresh_token, SECRET, &revocation).unwrap();
+        assert!(
+            rotate_refresh_token(&laptop.refresh_token, SECRET, &lifetimes, &revocation).is_err()
+        );
+        assert!(
+            rotate_refresh_token(&phone.refresh_token, SECRET, &lifetimes, &revocation).is_ok()
+        );
+    }
+
+    #[test]
+    fn wrong_secret_and_garbage_are_rejected() {
+        let revocation = MemoryRefreshRevocation::default();
+        let pair = pair(&revocation);
+        let lifetimes = TokenLifetimes::default();
+        assert!(
+            rotate_refresh_token(&pair.refresh_token, "other", &lifetimes, &revocation).is_err()
+        );
+        assert!(rotate_refresh_token("garbage", SECRET, &lifetimes, &revocation).is_err());
+    }
+}
@@ -0,0 +1,434 @@
+//! # Access token revocation
+//!
+//! JWTs stay valid until they expire. To make logout and forced
+//! invalidation effective, the `jti` of a revoked token is recorded in a
+//! [`TokenRevocationStore`] until the token's own expiry, and guards reject
+//! tokens whose `jti` is listed.
+//!
+//! ## Design principles
+//! - Entries only live as long as the token could, so the store stays small
+//!   ([`TokenRevocationStore::purge_expired`])
+//! - Tokens without a `jti` cannot be revoked and are accepted
+//!   ([`JwtBuilder`](crate::auth::jwt::JwtBuilder) always sets one)
+//! - Store failures reject the token (fail closed)
+//!
+//! ## Provided items
+//! - [`TokenRevocationStore`] — storage port
+//! - [`MemoryTokenRevocationStore`] / [`DbTokenRevocationStore`] — in-process
+//!   and `revoked_tokens` table implementations
+//! - [`CachedTokenRevocationStore`] — in-memory TTL cache in front of another
+//!   store, so guards do not query the database on every request
+//! - [`revoke_claims`] / [`is_revoked`] / [`decode_jwt_checked`]
+//!
+//! ## Example
+//! ```
+//! use wzs_web::auth::jwt::{create_jwt, decode_jwt};
+//! use wzs_web::auth::revocation::{decode_jwt_checked, revoke_claims, MemoryTokenRevocationStore};
+//!
+//! let secret = "test-secret";
+//! let store = MemoryTokenRevocationStore::default();
+//! let token = create_jwt(42, secret).unwrap();
+//! assert!(decode_jwt_checked(&token, secret, &store).is_ok());
+//!
+//! // Logout
+//! revoke_claims(&decode_jwt(&token, secret).unwrap(), &store).unwrap();
+//! assert!(decode_jwt_checked(&token, secret, &store).is_err());
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use tracing::warn;
+
+use crate::auth::jwt::{decode_jwt, Claims};
+use crate::auth::CurrentUser;
+use crate::db::port::{Db, Param};
+use crate::db::repository::ident;
+
+/// Storage port for revoked token IDs.
+///
+/// Implementations are blocking; call them on the blocking thread pool from
+/// async code.
+pub trait TokenRevocationStore: Send + Sync {
+    /// Records `jti` as revoked until `expires_at` (the token's `exp`).
+    fn revoke(&self, jti: &str, expires_at: DateTime<Utc>) -> Result<()>;
+
+    /// Whether `jti` has been revoked.
+    fn is_revoked(&self, jti: &str) -> Result<bool>;
+
+    /// Deletes entries of tokens that have expired anyway, returning how many
+    /// were removed.
+    fn purge_expired(&self) -> Result<u64>;
+}
+
+/// In-process [`TokenRevocationStore`] (single instance / tests).
+///
+/// Entries are dropped once the revoked token has expired.
+#[derive(Default)]
+pub struct MemoryTokenRevocationStore {
+    revoked: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl TokenRevocationStore for MemoryTokenRevocationStore {
+    fn revoke(&self, jti: &str, expires_at: DateTime<Utc>) -> Result<()> {
+        self.revoked
+            .lock()
+            .unwrap()
+            .insert(jti.to_string(), expires_at);
+        Ok(())
+    }
+
+    fn is_revoked(&self, jti: &str) -> Result<bool> {
+        let revoked = self.revoked.lock().unwrap();
+        Ok(revoked.get(jti).is_some_and(|exp| *exp > Utc::now()))
+    }
+
+    fn purge_expired(&self) -> Result<u64> {
+        let now = Utc::now();
+        let mut revoked = self.revoked.lock().unwrap();
+        let before = revoked.len();
+        revoked.retain(|_, exp| *exp > now);
+        Ok((before - revoked.len()) as u64)
+    }
+}
+
+/// [`TokenRevocationStore`] over a MySQL table through the [`Db`] port.
+///
+/// ```sql
+/// CREATE TABLE revoked_tokens (
+///   jti        VARCHAR(64) NOT NULL PRIMARY KEY,
+///   expires_at DATETIME    NOT NULL,
+///   INDEX (expires_at)
+/// );
+/// ```
+pub struct DbTokenRevocationStore {
+    db: Arc<dyn Db>,
+    table: String,
+}
+
+impl DbTokenRevocationStore {
+    /// Default table name.
+    pub const DEFAULT_TABLE: &'static str = "revoked_tokens";
+
+    /// Creates a store over the `revoked_tokens` table.
+    pub fn new(db: Arc<dyn Db>) -> Self {
+        Self {
+            db,
+            table: Self::DEFAULT_TABLE.into(),
+        }
+    }
+
+    /// Creates a store over a custom table.
+    ///
+    /// # Errors
+    /// Returns an error if `table` is not a plain SQL identifier.
+    pub fn with_table(db: Arc<dyn Db>, table: impl Into<String>) -> Result<Self> {
+        let table = table.into();
+        ident(&table)?;
+        Ok(Self { db, table })
+    }
+}
+
+impl TokenRevocationStore for DbTokenRevocationStore {
+    fn revoke(&self, jti: &str, expires_at: DateTime<Utc>) -> Result<()> {
+        let sql = format!(
+            "INSERT INTO {} (jti, expires_at) VALUES (?, ?) \
+             ON DUPLICATE KEY UPDATE expires_at = VALUES(expires_at)",
+            self.table
+        );
+        self.db.exec(
+            &sql,
+            &[Param::Str(jti), Param::DateTime(expires_at.naive_utc())],
+        )?;
+        Ok(())
+    }
+
+    fn is_revoked(&self, jti: &str) -> Result<bool> {
+        let sql = format!(
+            "SELECT 1 AS revoked FROM {} WHERE jti = ? AND expires_at > UTC_TIMESTAMP()",
+            self.table
+        );
+        Ok(self.db.fetch_one(&sql, &[Param::Str(jti)])?.is_some())
+    }
+
+    fn purge_expired(&self) -> Result<u64> {
+        let sql = format!(
+            "DELETE FROM {} WHERE expires_at <= UTC_TIMESTAMP()",
+            self.table
+        );
+        self.db.exec(&sql, &[])
+    }
+}
+
+/// Caches lookups of another [`TokenRevocationStore`] in memory.
+///
+/// Revoked IDs are cached until the token expires; unrevoked IDs for `ttl`.
+/// Revocations made through this instance take effect immediately, those
+/// made by other instances within `ttl`.
+pub struct CachedTokenRevocationStore<S> {
+    inner: S,
+    ttl: Duration,
+    /// `jti` → (revoked, cached until).
+    cache: Mutex<HashMap<String, (bool, Instant)>>,
+}
+
+impl<S: TokenRevocationStore> CachedTokenRevocationStore<S> {
+    /// Wraps `inner`, remembering negative lookups for `ttl`.
+    pub fn new(inner: S, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The wrapped store.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn remember(&self, jti: &str, revoked: bool, until: Instant) {
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(jti.to_string(), (revoked, until));
+    }
+}
+
+impl<S: TokenRevocationStore> TokenRevocationStore for CachedTokenRevocationStore<S> {
+    fn revoke(&self, jti: &str, expires_at: DateTime<Utc>) -> Result<()> {
+        self.inner.revoke(jti, expires_at)?;
+        let left = (expires_at - Utc::now()).to_std().unwrap_or_default();
+        self.remember(jti, true, Instant::now() + left);
+        Ok(())
+    }
+
+    fn is_revoked(&self, jti: &str) -> Result<bool> {
+        let now = Instant::now();
+        let cached = self.cache.lock().unwrap().get(jti).copied();
+        if let Some((revoked, _)) = cached.filter(|(_, until)| *until > now) {
+            return Ok(revoked);
+        }
+        let revoked = self.inner.is_revoked(jti)?;
+        // A revoked token's expiry is not known here; one `ttl` is enough
+        // since the inner store keeps answering `true` until then.
+        self.remember(jti, revoked, now + self.ttl);
+        Ok(revoked)
+    }
+
+    fn purge_expired(&self) -> Result<u64> {
+        let now = Instant::now();
+        self.cache
+            .lock()
+            .unwrap()
+            .retain(|_, (_, until)| *until > now);
+        self.inner.purge_expired()
+    }
+}
+
+/// Revokes the token described by `claims` until its expiry.
+///
+/// # Errors
+/// Returns an error if the token has no `jti` or the store fails.
+pub fn revoke_claims(claims: &Claims, store: &dyn TokenRevocationStore) -> Result<()> {
+    let Some(jti) = &claims.jti else {
+        bail!("token has no `jti` claim");
+    };
+    let expires_at = DateTime::from_timestamp(claims.exp as i64, 0).unwrap_or_else(Utc::now);
+    store.revoke(jti, expires_at)
+}
+
+/// Whether the token `user` was authenticated with has been revoked.
+///
+/// Principals without a token ID are never revoked; store failures count as
+/// revoked.
+pub fn is_revoked(user: &CurrentUser, store: &dyn TokenRevocationStore) -> bool {
+    let Some(jti) = &user.token_id else {
+        return false;
+    };
+    store.is_revoked(jti).unwrap_or_else(|e| {
+        warn!(error = %e, "token revocation lookup failed; rejecting token");
+        true
+    })
+}
+
+/// `user`, or `None` if `store` holds its token as revoked.
+///
+/// The store is blocking (it may query the database), so the lookup runs on
+/// the blocking thread pool. Without a store or a principal the user is
+/// returned as is.
+pub async fn unless_revoked(
+    user: Option<CurrentUser>,
+    store: Option<Arc<dyn TokenRevocationStore>>,
+) -> Option<CurrentUser> {
+    match (user, store) {
+        (Some(user), Some(store)) if user.token_id.is_some() => {
+            tokio::task::spawn_blocking(move || {
+                (!is_revoked(&user, store.as_ref())).then_some(user)
+            })
+            .await
+            .unwrap_or(None)
+        }
+        (user, _) => user,
+    }
+}
+
+/// [`decode_jwt`], also rejecting revoked tokens.
+///
+/// # Errors
+/// Same as [`decode_jwt`], plus a revoked `jti` or a store failure.
+pub fn decode_jwt_checked(
+    token: &str,
+    secret: &str,
+    store: &dyn TokenRevocationStore,
+) -> Result<Claims> {
+    let claims = decode_jwt(token, secret)?;
+    let revoked = match &claims.jti {
+        Some(jti) => store.is_revoked(jti)?,
+        None => false,
+    };
+    if revoked {
+        bail!("token has been revoked");
+    }
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use chrono::Duration as ChronoDuration;
+
+    use crate::auth::jwt::{create_jwt, JwtBuilder};
+    use crate::db::port::Row;
+
+    const SECRET: &str = "unit-test-secret";
+
+    #[test]
+    fn revoked_tokens_are_rejected_until_they_expire() {
+        let store = MemoryTokenRevocationStore::default();
+        let token = create_jwt(1, SECRET).unwrap();
+        let other = create_jwt(1, SECRET).unwrap();
+        revoke_claims(&decode_jwt(&token, SECRET).unwrap(), &store).unwrap();
+
+        assert!(decode_jwt_checked(&token, SECRET, &store).is_err());
+        assert!(decode_jwt_checked(&other, SECRET, &store).is_ok());
+
+        store
+            .revoke("old", Utc::now() - ChronoDuration::seconds(1))
+            .unwrap();
+        assert!(!store.is_revoked("old").unwrap());
+        assert_eq!(store.purge_expired().unwrap(), 1);
+        assert!(store
+            .is_revoked(&decode_jwt(&token, SECRET).unwrap().jti.unwrap())
+            .unwrap());
+    }
+
+    #[test]
+    fn principals_carry_the_token_id() {
+        let store = MemoryTokenRevocationStore::default();
+        let token = JwtBuilder::new("1").jwt_id("t-1").sign(SECRET).unwrap();
+        let user = CurrentUser::from_claims(&decode_jwt(&token, SECRET).unwrap());
+        assert!(!is_revoked(&user, &store));
+
+        store
+            .revoke("t-1", Utc::now() + ChronoDuration::minutes(1))
+            .unwrap();
+        assert!(is_revoked(&user, &store));
+        assert!(!is_revoked(&CurrentUser::new("1"), &store));
+    }
+
+    #[derive(Default)]
+    struct CountingStore {
+        inner: MemoryTokenRevocationStore,
+        lookups: AtomicUsize,
+    }
+
+    impl TokenRevocationStore for CountingStore {
+        fn revoke(&self, jti: &str, expires_at: DateTime<Utc>) -> Result<()> {
+            self.inner.revoke(jti, expires_at)
+        }
+
+        fn is_revoked(&self, jti: &str) -> Result<bool> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            self.inner.is_revoked(jti)
+        }
+
+        fn purge_expired(&self) -> Result<u64> {
+            self.inner.purge_expired()
+        }
+    }
+
+    #[test]
+    fn cache_answers_repeated_lookups_and_sees_local_revocations() {
+        let store =
+            CachedTokenRevocationStore::new(CountingStore::default(), Duration::from_secs(60));
+        assert!(!store.is_revoked("a").unwrap());
+        assert!(!store.is_revoked("a").unwrap());
+        assert_eq!(store.inner().lookups.load(Ordering::SeqCst), 1);
+
+        store
+            .revoke("a", Utc::now() + ChronoDuration::minutes(5))
+            .unwrap();
+        assert!(store.is_revoked("a").unwrap());
+        assert_eq!(store.inner().lookups.load(Ordering::SeqCst), 1);
+
+        let uncached = CachedTokenRevocationStore::new(CountingStore::default(), Duration::ZERO);
+        uncached
+            .inner()
+            .revoke("b", Utc::now() + ChronoDuration::minutes(5))
+            .unwrap();
+        assert!(uncached.is_revoked("b").unwrap());
+        assert!(uncached.is_revoked("b").unwrap());
+        assert_eq!(uncached.inner().lookups.load(Ordering::SeqCst), 2);
+    }
+
+    #[derive(Default)]
+    struct RecordingDb {
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl Db for RecordingDb {
+        fn fetch_one(&self, sql: &str, _: &[Param]) -> Result<Option<Row>> {
+            self.calls.lock().unwrap().push(sql.to_string());
+            Ok(Some(Row::default()))
+        }
+
+        fn fetch_all(&self, _: &str, _: &[Param]) -> Result<Vec<Row>> {
+            Ok(Vec::new())
+        }
+
+        fn exec(&self, sql: &str, _: &[Param]) -> Result<u64> {
+            self.calls.lock().unwrap().push(sql.to_string());
+            Ok(1)
+        }
+
+        fn exec_returning_last_insert_id(&self, _: &str, _: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn db_store_issues_expected_sql() {
+        let db = Arc::new(RecordingDb::default());
+        let store = DbTokenRevocationStore::new(db.clone());
+        store.revoke("a", Utc::now()).unwrap();
+        assert!(store.is_revoked("a").unwrap());
+        store.purge_expired().unwrap();
+
+        let calls = db.calls.lock().unwrap();
+        assert!(calls[0].starts_with("INSERT INTO revoked_tokens (jti, expires_at)"));
+        assert!(
+            calls[1].contains("FROM revoked_tokens WHERE jti = ? AND expires_at > UTC_TIMESTAMP()")
+        );
+        assert_eq!(
+            calls[2],
+            "DELETE FROM revoked_tokens WHERE expires_at <= UTC_TIMESTAMP()"
+        );
+        assert!(DbTokenRevocationStore::with_table(db.clone(), "bad name").is_err());
+    }
+}
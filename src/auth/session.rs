@@ -0,0 +1,762 @@
+//! # Server-side sessions
+//!
+//! Session data lives in a [`SessionStore`]; the browser only holds a signed
+//! session id cookie (`<id>.<hmac>`), so nothing readable or forgeable is
+//! kept client-side — an alternative to JWT cookies for classic web apps.
+//!
+//! ## Flow
+//! 1. [`session_layer`] (installed with `from_fn_with_state`) verifies the
+//!    cookie and loads the session before the handler runs
+//! 2. handlers read it with the [`Session`] extractor, or change it through
+//!    [`WritableSession`]
+//! 3. after the handler, changed sessions are saved with a fresh expiry and
+//!    the cookie is (re)sent; destroyed sessions are deleted and the cookie
+//!    cleared
+//!
+//! Call [`WritableSession::regenerate`] after sign-in to prevent session
+//! fixation.
+//!
+//! ## Provided types
+//! - [`SessionStore`] — storage port
+//! - [`MemorySessionStore`] / [`DbSessionStore`] — in-process and `sessions`
+//!   table implementations
+//! - [`SessionConfig`] / [`SessionManager`] — cookie settings and the state
+//!   passed to [`session_layer`]
+//!
+//! ## Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use axum::{middleware::from_fn_with_state, routing::{get, post}, Router};
+//! use wzs_web::auth::session::{
+//!     session_layer, MemorySessionStore, Session, SessionConfig, SessionManager, WritableSession,
+//! };
+//! use wzs_web::config::csrf::derive_secret_from_string;
+//!
+//! async fn login(mut session: WritableSession) -> &'static str {
+//!     session.regenerate();
+//!     session.insert("user_id", 42).unwrap();
+//!     "ok"
+//! }
+//!
+//! async fn me(session: Session) -> String {
+//!     match session.get::<u64>("user_id") {
+//!         Some(id) => format!("user {id}"),
+//!         None => "anonymous".into(),
+//!     }
+//! }
+//!
+//! let sessions = SessionManager::new(
+//!     Arc::new(MemorySessionStore::default()),
+//!     SessionConfig::new(derive_secret_from_string("session-secret")).secure(true),
+//! );
+//! let app: Router = Router::new()
+//!     .route("/login", post(login))
+//!     .route("/me", get(me))
+//!     .layer(from_fn_with_state(sessions, session_layer));
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::{header::SET_COOKIE, request::Parts, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use sha2::Sha256;
+
+use crate::db::port::{Db, Param};
+use crate::db::repository::ident;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Default session cookie name.
+pub const SESSION_COOKIE_NAME: &str = "sid";
+
+/// A stored session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionRecord {
+    pub data: Map<String, Value>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Storage port for session data.
+///
+/// Implementations are blocking; [`session_layer`] calls them on the
+/// blocking thread pool.
+pub trait SessionStore: Send + Sync {
+    /// Loads the session `id`, or `None` if it does not exist or expired.
+    fn load(&self, id: &str) -> Result<Option<SessionRecord>>;
+
+    /// Creates or replaces the session `id`.
+    fn save(&self, id: &str, record: &SessionRecord) -> Result<()>;
+
+    /// Deletes the session `id` (no error if it does not exist).
+    fn delete(&self, id: &str) -> Result<()>;
+
+    /// Deletes expired sessions, returning how many were removed.
+    fn purge_expired(&self) -> Result<u64>;
+}
+
+/// In-process [`SessionStore`] (single instance / tests).
+#[derive(Default)]
+pub struct MemorySessionStore {
+    sessions: Mutex<HashMap<String, SessionRecord>>,
+}
+
+impl SessionStore for MemorySessionStore {
+    fn load(&self, id: &str) -> Result<Option<SessionRecord>> {
+        let sessions = self.sessions.lock().unwrap();
+        Ok(sessions
+            .get(id)
+            .filter(|r| r.expires_at > Utc::now())
+            .cloned())
+    }
+
+    fn save(&self, id: &str, record: &SessionRecord) -> Result<()> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), record.clone());
+        Ok(())
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        self.sessions.lock().unwrap().remove(id);
+        Ok(())
+    }
+
+    fn purge_expired(&self) -> Result<u64> {
+        let now = Utc::now();
+        let mut sessions = self.sessions.lock().unwrap();
+        let before = sessions.len();
+        sessions.retain(|_, r| r.expires_at > now);
+        Ok((before - sessions.len()) as u64)
+    }
+}
+
+/// [`SessionStore`] over a MySQL table through the [`Db`] port.
+///
+/// ```sql
+/// CREATE TABLE sessions (
+///   id         VARCHAR(64) NOT NULL PRIMARY KEY,
+///   data       JSON        NOT NULL,
+///   expires_at DATETIME    NOT NULL,
+///   INDEX (expires_at)
+/// );
+/// ```
+pub struct DbSessionStore {
+    db: Arc<dyn Db>,
+    table: String,
+}
+
+impl DbSessionStore {
+    /// Default table name.
+    pub const DEFAULT_TABLE: &'static str = "sessions";
+
+    /// Creates a store over the `sessions` table.
+    pub fn new(db: Arc<dyn Db>) -> Self {
+        Self {
+            db,
+            table: Self::DEFAULT_TABLE.into(),
+        }
+    }
+
+    /// Creates a store over a custom table.
+    ///
+    /// # Errors
+    /// Returns an error if `table` is not a plain SQL identifier.
+    pub fn with_table(db: Arc<dyn Db>, table: impl Into<String>) -> Result<Self> {
+        let table = table.into();
+        ident(&table)?;
+        Ok(Self { db, table })
+    }
+}
+
+impl SessionStore for DbSessionStore {
+    fn load(&self, id: &str) -> Result<Option<SessionRecord>> {
+        let sql = format!(
+            "SELECT data, expires_at FROM {} WHERE id = ? AND expires_at > UTC_TIMESTAMP()",
+            self.table
+        );
+        let Some(row) = self.db.fetch_one(&sql, &[Param::Str(id)])? else {
+            return Ok(None);
+        };
+        let data = match row.get_json("data")? {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+        Ok(Some(SessionRecord {
+            data,
+            expires_at: row.get_datetime("expires_at")?.and_utc(),
+        }))
+    }
+
+    fn save(&self, id: &str, record: &SessionRecord) -> Result<()> {
+        let sql = format!(
+            "INSERT INTO {} (id, data, expires_at) VALUES (?, ?, ?) \
+             ON DUPLICATE KEY UPDATE data = VALUES(data), expires_at = VALUES(expires_at)",
+            self.table
+        );
+        let data = Value::Object(record.data.clone());
+        let expires: NaiveDateTime = record.expires_at.naive_utc();
+        self.db.exec(
+            &sql,
+            &[Param::Str(id), Param::Json(&data), Param::DateTime(expires)],
+        )?;
+        Ok(())
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        let sql = format!("DELETE FROM {} WHERE id = ?", self.table);
+        self.db.exec(&sql, &[Param::Str(id)])?;
+        Ok(())
+    }
+
+    fn purge_expired(&self) -> Result<u64> {
+        let sql = format!(
+            "DELETE FROM {} WHERE expires_at <= UTC_TIMESTAMP()",
+            self.table
+        );
+        self.db.exec(&sql, &[])
+    }
+}
+
+/// Session cookie settings.
+#[derive(Clone, Debug)]
+pub struct SessionConfig {
+    secret: [u8; 32],
+    cookie_name: String,
+    ttl: Duration,
+    secure: bool,
+    same_site: SameSite,
+}
+
+impl SessionConfig {
+    /// Cookie `sid`, 14-day idle lifetime, `SameSite=Lax`, not `Secure`.
+    pub fn new(secret: [u8; 32]) -> Self {
+        Self {
+            secret,
+            cookie_name: SESSION_COOKIE_NAME.into(),
+            ttl: Duration::days(14),
+            secure: false,
+            same_site: SameSite::Lax,
+        }
+    }
+
+    /// Sets the cookie name.
+    pub fn cookie_name(mut self, name: impl Into<String>) -> Self {
+        self.cookie_name = name.into();
+        self
+    }
+
+    /// Sets the idle lifetime (extended on every change).
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets the `Secure` cookie flag.
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Sets the `SameSite` cookie attribute.
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = same_site;
+        self
+    }
+
+    fn sign(&self, id: &str) -> String {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC key");
+        mac.update(id.as_bytes());
+        format!(
+            "{id}.{}",
+            URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+        )
+    }
+
+    /// Returns the session id of a cookie value with a valid signature.
+    fn verify<'a>(&self, value: &'a str) -> Option<&'a str> {
+        let (id, sig) = value.rsplit_once('.')?;
+        let sig = URL_SAFE_NO_PAD.decode(sig).ok()?;
+        let mut mac = HmacSha256::new_from_slice(&self.secret).ok()?;
+        mac.update(id.as_bytes());
+        mac.verify_slice(&sig).ok()?;
+        Some(id)
+    }
+
+    fn cookie(&self, value: String, max_age: i64) -> String {
+        let cookie = Cookie::build((self.cookie_name.clone(), value))
+            .path("/")
+            .http_only(true)
+            .secure(self.secure)
+            .same_site(self.same_site);
+        // `Max-Age` is appended by hand: setting it through the builder needs
+        // the `time` crate, which is not a direct dependency.
+        format!("{}; Max-Age={max_age}", cookie.build())
+    }
+}
+
+/// Store and cookie settings, the state of [`session_layer`].
+#[derive(Clone)]
+pub struct SessionManager {
+    store: Arc<dyn SessionStore>,
+    cfg: Arc<SessionConfig>,
+}
+
+impl SessionManager {
+    /// Creates a manager.
+    pub fn new(store: Arc<dyn SessionStore>, cfg: SessionConfig) -> Self {
+        Self {
+            store,
+            cfg: Arc::new(cfg),
+        }
+    }
+
+    /// Returns the store (e.g. to schedule [`SessionStore::purge_expired`]).
+    pub fn store(&self) -> &Arc<dyn SessionStore> {
+        &self.store
+    }
+}
+
+#[derive(Debug, Default)]
+struct SessionState {
+    id: Option<String>,
+    data: Map<String, Value>,
+    changed: bool,
+    regenerate: bool,
+    destroyed: bool,
+}
+
+type SharedState = Arc<Mutex<SessionState>>;
+
+/// Loads the session before the handler and persists changes after it.
+///
+/// A missing, tampered or expired cookie starts an empty session; nothing
+/// is stored or sent until the session is written to. Store errors are
+/// answered with `500`.
+pub async fn session_layer(
+    State(sessions): State<SessionManager>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let jar = CookieJar::from_headers(req.headers());
+    let id = jar
+        .get(&sessions.cfg.cookie_name)
+        .and_then(|c| sessions.cfg.verify(c.value()))
+        .map(str::to_string);
+
+    let mut state = SessionState::default();
+    if let Some(id) = id {
+        let store = sessions.store.clone();
+        let lookup = id.clone();
+        if let Some(record) = blocking(move || store.load(&lookup)).await? {
+            state.id = Some(id);
+            state.data = record.data;
+        }
+    }
+    let shared: SharedState = Arc::new(Mutex::new(state));
+    req.extensions_mut().insert(shared.clone());
+
+    let mut res = next.run(req).await;
+
+    let state = std::mem::take(&mut *shared.lock().unwrap());
+    let store = sessions.store.clone();
+    let cfg = sessions.cfg.clone();
+    let cookie = if state.destroyed {
+        if let Some(id) = state.id {
+            blocking(move || store.delete(&id)).await?;
+            Some(cfg.cookie(String::new(), 0))
+        } else {
+            None
+        }
+    } else if state.changed || state.regenerate {
+        let (id, previous) = match state.id {
+            Some(id) if !state.regenerate => (id, None),
+            previous => (new_session_id(), previous),
+        };
+        let record = SessionRecord {
+            data: state.data,
+            expires_at: Utc::now() + cfg.ttl,
+        };
+        let saved = id.clone();
+        blocking(move || {
+            if let Some(previous) = previous {
+                store.delete(&previous)?;
+            }
+            store.save(&saved, &record)
+        })
+        .await?;
+        Some(cfg.cookie(cfg.sign(&id), cfg.ttl.num_seconds()))
+    } else {
+        None
+    };
+
+    if let Some(cookie) = cookie {
+        let value =
+            HeaderValue::from_str(&cookie).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        res.headers_mut().append(SET_COOKIE, value);
+    }
+    Ok(res)
+}
+
+async fn blocking<T, F>(f: F) -> Result<T, StatusCode>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(Ok(v)) => Ok(v),
+        Ok(Err(e)) => {
+            tracing::error!(error = %e, "session store failed");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "session task failed");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+fn new_session_id() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn shared_state(parts: &Parts) -> Result<SharedState, (StatusCode, &'static str)> {
+    parts.extensions.get::<SharedState>().cloned().ok_or((
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "session_layer is not installed",
+    ))
+}
+
+/// Read-only snapshot of the current session.
+///
+/// Rejects with `500` when [`session_layer`] is not installed.
+#[derive(Clone, Debug, Default)]
+pub struct Session {
+    data: Map<String, Value>,
+    exists: bool,
+}
+
+impl Session {
+    /// Deserializes `key`; `None` if absent or of another type.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.data
+            .get(key)
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+    }
+
+    /// Whether a stored session was found for the request.
+    pub fn exists(&self) -> bool {
+        self.exists
+    }
+
+    /// All values.
+    pub fn data(&self) -> &Map<String, Value> {
+        &self.data
+    }
+}
+
+impl<S> FromRequestParts<S> for Session
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let shared = shared_state(parts)?;
+        let state = shared.lock().unwrap();
+        Ok(Self {
+            data: state.data.clone(),
+            exists: state.id.is_some(),
+        })
+    }
+}
+
+/// Mutable handle on the current session; changes are saved after the
+/// handler returns.
+///
+/// Rejects with `500` when [`session_layer`] is not installed.
+pub struct WritableSession {
+    state: SharedState,
+}
+
+impl WritableSession {
+    /// Deserializes `key`; `None` if absent or of another type.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let state = self.state.lock().unwrap();
+        state
+            .data
+            .get(key)
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+    }
+
+    /// Stores `value` under `key`.
+    ///
+    /// # Errors
+    /// Returns an error if `value` cannot be serialized to JSON.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Serialize) -> Result<()> {
+        let value = serde_json::to_value(value)?;
+        let mut state = self.state.lock().unwrap();
+        state.data.insert(key.into(), value);
+        state.changed = true;
+        Ok(())
+    }
+
+    /// Removes `key`, returning its previous value.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let mut state = self.state.lock().unwrap();
+        let old = state.data.remove(key);
+        state.changed |= old.is_some();
+        old
+    }
+
+    /// Removes all values (the session itself is kept).
+    pub fn clear(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.data.clear();
+        state.changed = true;
+    }
+
+    /// Issues a new session id, keeping the data (call after sign-in).
+    pub fn regenerate(&mut self) {
+        self.state.lock().unwrap().regenerate = true;
+    }
+
+    /// Deletes the session and clears the cookie (sign-out).
+    pub fn destroy(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.data.clear();
+        state.destroyed = true;
+    }
+}
+
+impl<S> FromRequestParts<S> for WritableSession
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self {
+            state: shared_state(parts)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::header::COOKIE,
+        middleware::from_fn_with_state,
+        routing::{get, post},
+        Router,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use crate::config::csrf::derive_secret_from_string;
+    use crate::db::port::Row;
+
+    fn app(store: Arc<MemorySessionStore>) -> Router {
+        async fn login(mut s: WritableSession) -> &'static str {
+            s.regenerate();
+            s.insert("user_id", 7).unwrap();
+            "ok"
+        }
+        async fn me(s: Session) -> String {
+            s.get::<u64>("user_id")
+                .map_or("anonymous".into(), |id| id.to_string())
+        }
+        async fn logout(mut s: WritableSession) {
+            s.destroy();
+        }
+        let sessions = SessionManager::new(
+            store,
+            SessionConfig::new(derive_secret_from_string("test")).secure(true),
+        );
+        Router::new()
+            .route("/login", post(login))
+            .route("/me", get(me))
+            .route("/logout", post(logout))
+            .layer(from_fn_with_state(sessions, session_layer))
+    }
+
+    async fn call(
+        app: &Router,
+        req: axum::http::request::Builder,
+        cookie: Option<&str>,
+    ) -> (Option<String>, String) {
+        let req = match cookie {
+            Some(c) => req.header(COOKIE, c),
+            None => req,
+        };
+        let res = app
+            .clone()
+            .oneshot(req.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let set_cookie = res
+            .headers()
+            .get(SET_COOKIE)
+            .map(|v| v.to_str().unwrap().to_string());
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        (set_cookie, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn pair(set_cookie: &str) -> &str {
+        set_cookie.split(';').next().unwrap()
+    }
+
+    #[tokio::test]
+    async fn login_read_and_logout() {
+        let store = Arc::new(MemorySessionStore::default());
+        let app = app(store.clone());
+
+        let (none, body) = call(&app, axum::http::Request::get("/me"), None).await;
+        assert_eq!((none, body.as_str()), (None, "anonymous"));
+
+        let (set, _) = call(&app, axum::http::Request::post("/login"), None).await;
+        let set = set.expect("session cookie");
+        assert!(set.starts_with("sid="));
+        assert!(set.contains("HttpOnly") && set.contains("Secure"));
+        assert!(set.contains("Max-Age=1209600"));
+        let cookie = pair(&set).to_string();
+
+        let (_, body) = call(&app, axum::http::Request::get("/me"), Some(&cookie)).await;
+        assert_eq!(body, "7");
+
+        let (cleared, _) = call(&app, axum::http::Request::post("/logout"), Some(&cookie)).await;
+        assert!(cleared.unwrap().contains("Max-Age=0"));
+        assert!(store.sessions.lock().unwrap().is_empty());
+        let (_, body) = call(&app, axum::http::Request::get("/me"), Some(&cookie)).await;
+        assert_eq!(body, "anonymous");
+    }
+
+    #[tokio::test]
+    async fn regenerate_replaces_the_session_id() {
+        let store = Arc::new(MemorySessionStore::default());
+        let app = app(store.clone());
+        let (first, _) = call(&app, axum::http::Request::post("/login"), None).await;
+        let first = pair(&first.unwrap()).to_string();
+        let (second, _) = call(&app, axum::http::Request::post("/login"), Some(&first)).await;
+        let second = pair(&second.unwrap()).to_string();
+
+        assert_ne!(first, second);
+        assert_eq!(store.sessions.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn tampered_cookies_start_an_empty_session() {
+        let store = Arc::new(MemorySessionStore::default());
+        let app = app(store.clone());
+        let (set, _) = call(&app, axum::http::Request::post("/login"), None).await;
+        let cookie = pair(&set.unwrap()).to_string();
+        let (id, _) = cookie.trim_start_matches("sid=").rsplit_once('.').unwrap();
+
+        let forged = format!("sid={id}.AAAA");
+        let (_, body) = call(&app, axum::http::Request::get("/me"), Some(&forged)).await;
+        assert_eq!(body, "anonymous");
+    }
+
+    #[tokio::test]
+    async fn extractors_require_the_layer() {
+        let app = Router::new().route("/", get(|_: Session| async {}));
+        let res = app
+            .oneshot(axum::http::Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn memory_store_expires_and_purges() {
+        let store = MemorySessionStore::default();
+        let expired = SessionRecord {
+            data: Map::new(),
+            expires_at: Utc::now() - Duration::seconds(1),
+        };
+        store.save("old", &expired).unwrap();
+        assert!(store.load("old").unwrap().is_none());
+        assert_eq!(store.purge_expired().unwrap(), 1);
+    }
+
+    #[derive(Default)]
+    struct RecordingDb {
+        calls: Mutex<Vec<(String, usize)>>,
+        row: Option<Row>,
+    }
+
+    impl Db for RecordingDb {
+        fn fetch_one(&self, sql: &str, params: &[Param]) -> Result<Option<Row>> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push((sql.to_string(), params.len()));
+            Ok(self.row.clone())
+        }
+
+        fn fetch_all(&self, _sql: &str, _params: &[Param]) -> Result<Vec<Row>> {
+            Ok(vec![])
+        }
+
+        fn exec(&self, sql: &str, params: &[Param]) -> Result<u64> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push((sql.to_string(), params.len()));
+            Ok(1)
+        }
+
+        fn exec_returning_last_insert_id(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn db_store_maps_rows_and_statements() {
+        let mut row = Row::default();
+        row.insert(
+            "data",
+            crate::db::port::Value::Str(r#"{"user_id":3}"#.into()),
+        );
+        let expires = Utc::now().naive_utc();
+        row.insert("expires_at", crate::db::port::Value::DateTime(expires));
+        let db = Arc::new(RecordingDb {
+            row: Some(row),
+            ..Default::default()
+        });
+        let store = DbSessionStore::with_table(db.clone(), "web_sessions").unwrap();
+
+        let record = store.load("abc").unwrap().unwrap();
+        assert_eq!(record.data["user_id"], 3);
+        assert_eq!(record.expires_at.naive_utc(), expires);
+        store.save("abc", &record).unwrap();
+        store.delete("abc").unwrap();
+
+        let calls = db.calls.lock().unwrap();
+        assert!(calls[0]
+            .0
+            .starts_with("SELECT data, expires_at FROM web_sessions"));
+        assert!(calls[1].0.contains("ON DUPLICATE KEY UPDATE"));
+        assert_eq!(calls[1].1, 3);
+        assert_eq!(calls[2].0, "DELETE FROM web_sessions WHERE id = ?");
+        assert!(DbSessionStore::with_table(db.clone(), "x; DROP").is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! # Attempt throttling
+//!
+//! Limits how many failed attempts a key (e.g. `"otp:sign_in:user@example.com"`)
+//! may make within a time window, to slow down guessing of codes and passwords.
+//!
+//! ## Provided types
+//! - [`Throttle`] — port consulted by verifiers such as [`auth::otp`](crate::auth::otp)
+//! - [`MemoryThrottle`] — fixed-window, in-process implementation
+//! - [`lockout`] — sign-in lockout with exponential backoff, persisted in a
+//!   pluggable store and usable as an Axum layer
+//!
+//! ## Example
+//! ```
+//! use std::time::Duration;
+//! use wzs_web::auth::throttle::{MemoryThrottle, Throttle};
+//!
+//! let throttle = MemoryThrottle::new(3, Duration::from_secs(900));
+//! for _ in 0..3 {
+//!     throttle.record_failure("login:alice");
+//! }
+//! assert!(throttle.is_blocked("login:alice"));
+//!
+//! throttle.reset("login:alice");
+//! assert_eq!(throttle.remaining("login:alice"), 3);
+//! ```
+
+pub mod lockout;
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Tracks failed attempts per key.
+pub trait Throttle: Send + Sync {
+    /// Returns how many failed attempts `key` may still make in the current window.
+    fn remaining(&self, key: &str) -> u32;
+
+    /// Records one failed attempt for `key`.
+    fn record_failure(&self, key: &str);
+
+    /// Clears the attempts for `key` (e.g. after a success).
+    fn reset(&self, key: &str);
+
+    /// Returns whether `key` has no attempts left.
+    fn is_blocked(&self, key: &str) -> bool {
+        self.remaining(key) == 0
+    }
+
+    /// Counts one attempt for `key` up front if any are left; returns
+    /// `false` (recording nothing) once blocked. Call [`reset`](Self::reset)
+    /// when the attempt succeeds.
+    ///
+    /// The default checks and records in two steps; implementations shared
+    /// by concurrent requests should override it to do both atomically, so
+    /// parallel guesses cannot all pass the check.
+    fn try_acquire(&self, key: &str) -> bool {
+        if self.is_blocked(key) {
+            return false;
+        }
+        self.record_failure(key);
+        true
+    }
+}
+
+/// In-process [`Throttle`] with a fixed window starting at the first failure.
+///
+/// State is per process; use a shared implementation when running several
+/// instances behind a load balancer.
+pub struct MemoryThrottle {
+    max_attempts: u32,
+    window: Duration,
+    state: Mutex<HashMap<String, (u32, Instant)>>,
+}
+
+impl MemoryThrottle {
+    /// Allows `max_attempts` failures per key within `window`.
+    pub fn new(max_attempts: u32, window: Duration) -> Self {
+        Self {
+            max_attempts,
+            window,
+            state: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn failures(&self, state: &mut HashMap<String, (u32, Instant)>, key: &str) -> u32 {
+        match state.get(key) {
+            Some((_, started)) if started.elapsed() >= self.window => {
+                state.remove(key);
+                0
+            }
+            Some((n, _)) => *n,
+            None => 0,
+        }
+    }
+}
+
+impl Throttle for MemoryThrottle {
+    fn remaining(&self, key: &str) -> u32 {
+        let mut state = self.state.lock().unwrap();
+        self.max_attempts
+            .saturating_sub(self.failures(&mut state, key))
+    }
+
+    fn record_failure(&self, key: &str) {
+        let mut state = self.state.lock().unwrap();
+        if self.failures(&mut state, key) == 0 {
+            state.insert(key.to_string(), (1, Instant::now()));
+        } else if let Some((n, _)) = state.get_mut(key) {
+            *n = n.saturating_add(1);
+        }
+    }
+
+    fn reset(&self, key: &str) {
+        self.state.lock().unwrap().remove(key);
+    }
+
+    fn try_acquire(&self, key: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match self.failures(&mut state, key) {
+            n if n >= self.max_attempts => false,
+            0 => {
+                state.insert(key.to_string(), (1, Instant::now()));
+                true
+            }
+            _ => {
+                if let Some((n, _)) = state.get_mut(key) {
+                    *n += 1;
+                }
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_after_max_failures_per_key() {
+        let t = MemoryThrottle::new(2, Duration::from_secs(60));
+        t.record_failure("a");
+        assert_eq!(t.remaining("a"), 1);
+        t.record_failure("a");
+        assert!(t.is_blocked("a"));
+        assert_eq!(t.remaining("b"), 2);
+    }
+
+    #[test]
+    fn try_acquire_reserves_attempts_atomically() {
+        let t = std::sync::Arc::new(MemoryThrottle::new(3, Duration::from_secs(60)));
+        let granted: usize = (0..8)
+            .map(|_| {
+                let t = t.clone();
+                std::thread::spawn(move || t.try_acquire("a"))
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|h| h.join().unwrap() as usize)
+            .sum();
+        assert_eq!(granted, 3);
+        assert!(t.is_blocked("a"));
+
+        t.reset("a");
+        assert!(t.try_acquire("a"));
+        assert_eq!(t.remaining("a"), 2);
+    }
+
+    #[test]
+    fn window_expiry_and_reset_clear_failures() {
+        let t = MemoryThrottle::new(1, Duration::ZERO);
+        t.record_failure("a");
+        assert!(!t.is_blocked("a"));
+
+        let t = MemoryThrottle::new(1, Duration::from_secs(60));
+        t.record_failure("a");
+        t.reset("a");
+        assert!(!t.is_blocked("a"));
+    }
+}
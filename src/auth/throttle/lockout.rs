@@ -0,0 +1,523 @@
+//! # Login lockout
+//!
+//! Tracks consecutive failed authentication attempts per key (client IP,
+//! username, …) and locks the key out for an exponentially growing period
+//! once a threshold is reached.
+//!
+//! With the default [`LockoutPolicy`] the first 5 failures are free; the
+//! 5th locks for 30 seconds, the 6th for 1 minute, the 7th for 2 minutes,
+//! and so on up to 1 hour. A key's failures are forgotten 24 hours after the
+//! last one, or on success.
+//!
+//! ## Provided items
+//! - [`LockoutStore`] — storage port, with [`MemoryLockoutStore`] and
+//!   [`DbLockoutStore`] (`auth_lockouts` table) implementations
+//! - [`Lockout`] — plain functions ([`check`](Lockout::check),
+//!   [`record_failure`](Lockout::record_failure),
+//!   [`record_success`](Lockout::record_success)) for handlers that know the
+//!   username
+//! - [`lockout_layer`] — middleware keyed by client IP: locked clients get
+//!   `429` with `Retry-After`; `401` responses count as failures, `2xx`
+//!   responses clear them
+//!
+//! ## Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use axum::{middleware::from_fn_with_state, routing::post, Router};
+//! use wzs_web::auth::throttle::lockout::{
+//!     lockout_layer, user_key, Lockout, LockoutPolicy, MemoryLockoutStore,
+//! };
+//!
+//! let lockout = Lockout::new(Arc::new(MemoryLockoutStore::default()), LockoutPolicy::default());
+//!
+//! // In the login handler, per username:
+//! # fn login(lockout: &Lockout) -> anyhow::Result<()> {
+//! let key = user_key("Alice@example.com");
+//! if let Some(wait) = lockout.check(&key, chrono::Utc::now())? {
+//!     anyhow::bail!("locked for {}s", wait.num_seconds());
+//! }
+//! # Ok(())
+//! # }
+//!
+//! // Per client IP, for every route behind the layer:
+//! let app: Router = Router::new()
+//!     .route("/login", post(|| async { "ok" }))
+//!     .layer(from_fn_with_state(lockout, lockout_layer));
+//! ```
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use axum::{
+    extract::{Request, State},
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Duration, Utc};
+
+use crate::db::port::{Db, Param};
+use crate::db::repository::ident;
+use crate::error::app::AppError;
+use crate::web::client_ip::ClientIp;
+use crate::web::problem::Problem;
+
+/// When and for how long keys are locked out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LockoutPolicy {
+    /// Failures that trigger the first lockout.
+    pub max_failures: u32,
+    /// Length of the first lockout; doubled for every further failure.
+    pub base_lockout: Duration,
+    /// Upper bound of a lockout.
+    pub max_lockout: Duration,
+    /// Failures older than this are forgotten.
+    pub reset_after: Duration,
+}
+
+impl Default for LockoutPolicy {
+    fn default() -> Self {
+        Self {
+            max_failures: 5,
+            base_lockout: Duration::seconds(30),
+            max_lockout: Duration::hours(1),
+            reset_after: Duration::hours(24),
+        }
+    }
+}
+
+impl LockoutPolicy {
+    /// Lockout after the `failures`-th consecutive failure, if any.
+    pub fn lockout_for(&self, failures: u32) -> Option<Duration> {
+        let excess = failures.checked_sub(self.max_failures.max(1))?;
+        let factor = 1i32.checked_shl(excess.min(30)).unwrap_or(i32::MAX);
+        let lockout = self
+            .base_lockout
+            .checked_mul(factor)
+            .unwrap_or(self.max_lockout);
+        Some(lockout.min(self.max_lockout))
+    }
+}
+
+/// Failure record of one key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LockoutState {
+    pub failures: u32,
+    pub last_failure: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+/// Storage port for [`LockoutState`]s.
+///
+/// Implementations are blocking; [`lockout_layer`] calls them on the
+/// blocking thread pool.
+pub trait LockoutStore: Send + Sync {
+    /// Loads the state of `key`.
+    fn load(&self, key: &str) -> Result<Option<LockoutState>>;
+
+    /// Creates or replaces the state of `key`.
+    fn save(&self, key: &str, state: &LockoutState) -> Result<()>;
+
+    /// Forgets `key` (no error if it is unknown).
+    fn clear(&self, key: &str) -> Result<()>;
+}
+
+/// In-process [`LockoutStore`] (single instance / tests).
+#[derive(Default)]
+pub struct MemoryLockoutStore {
+    states: Mutex<HashMap<String, LockoutState>>,
+}
+
+impl LockoutStore for MemoryLockoutStore {
+    fn load(&self, key: &str) -> Result<Option<LockoutState>> {
+        Ok(self.states.lock().unwrap().get(key).cloned())
+    }
+
+    fn save(&self, key: &str, state: &LockoutState) -> Result<()> {
+        self.states
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), state.clone());
+        Ok(())
+    }
+
+    fn clear(&self, key: &str) -> Result<()> {
+        self.states.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+/// [`LockoutStore`] over a MySQL table through the [`Db`] port.
+///
+/// ```sql
+/// CREATE TABLE auth_lockouts (
+///   lockout_key  VARCHAR(255) NOT NULL PRIMARY KEY,
+///   failures     INT UNSIGNED NOT NULL,
+///   last_failure DATETIME     NOT NULL,
+///   locked_until DATETIME     NULL
+/// );
+/// ```
+pub struct DbLockoutStore {
+    db: Arc<dyn Db>,
+    table: String,
+}
+
+impl DbLockoutStore {
+    /// Default table name.
+    pub const DEFAULT_TABLE: &'static str = "auth_lockouts";
+
+    /// Creates a store over the `auth_lockouts` table.
+    pub fn new(db: Arc<dyn Db>) -> Self {
+        Self {
+            db,
+            table: Self::DEFAULT_TABLE.into(),
+        }
+    }
+
+    /// Creates a store over a custom table.
+    ///
+    /// # Errors
+    /// Returns an error if `table` is not a plain SQL identifier.
+    pub fn with_table(db: Arc<dyn Db>, table: impl Into<String>) -> Result<Self> {
+        let table = table.into();
+        ident(&table)?;
+        Ok(Self { db, table })
+    }
+
+    /// Deletes states whose last failure is older than `before`, returning
+    /// how many were removed.
+    pub fn purge_before(&self, before: DateTime<Utc>) -> Result<u64> {
+        let sql = format!(
+            "DELETE FROM {} WHERE last_failure < ? \
+             AND (locked_until IS NULL OR locked_until < ?)",
+            self.table
+        );
+        let before = before.naive_utc();
+        self.db
+            .exec(&sql, &[Param::DateTime(before), Param::DateTime(before)])
+    }
+}
+
+impl LockoutStore for DbLockoutStore {
+    fn load(&self, key: &str) -> Result<Option<LockoutState>> {
+        let sql = format!(
+            "SELECT failures, last_failure, locked_until FROM {} WHERE lockout_key = ?",
+            self.table
+        );
+        let Some(row) = self.db.fetch_one(&sql, &[Param::Str(key)])? else {
+            return Ok(None);
+        };
+        Ok(Some(LockoutState {
+            failures: u32::try_from(row.get_u64("failures")?).unwrap_or(u32::MAX),
+            last_failure: row.get_datetime("last_failure")?.and_utc(),
+            locked_until: row.get_datetime_opt("locked_until")?.map(|t| t.and_utc()),
+        }))
+    }
+
+    fn save(&self, key: &str, state: &LockoutState) -> Result<()> {
+        let sql = format!(
+            "INSERT INTO {} (lockout_key, failures, last_failure, locked_until) \
+             VALUES (?, ?, ?, ?) \
+             ON DUPLICATE KEY UPDATE failures = VALUES(failures), \
+             last_failure = VALUES(last_failure), locked_until = VALUES(locked_until)",
+            self.table
+        );
+        let locked_until = match state.locked_until {
+            Some(t) => Param::DateTime(t.naive_utc()),
+            None => Param::Null,
+        };
+        self.db.exec(
+            &sql,
+            &[
+                Param::Str(key),
+                Param::U64(state.failures.into()),
+                Param::DateTime(state.last_failure.naive_utc()),
+                locked_until,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn clear(&self, key: &str) -> Result<()> {
+        let sql = format!("DELETE FROM {} WHERE lockout_key = ?", self.table);
+        self.db.exec(&sql, &[Param::Str(key)])?;
+        Ok(())
+    }
+}
+
+/// Lockout key for a client address.
+pub fn ip_key(ip: IpAddr) -> String {
+    format!("ip:{ip}")
+}
+
+/// Lockout key for a username (trimmed, case-insensitive).
+pub fn user_key(username: &str) -> String {
+    format!("user:{}", username.trim().to_lowercase())
+}
+
+/// A [`LockoutPolicy`] applied over a [`LockoutStore`]; also the state of
+/// [`lockout_layer`].
+///
+/// Cloning is cheap.
+#[derive(Clone)]
+pub struct Lockout {
+    store: Arc<dyn LockoutStore>,
+    policy: LockoutPolicy,
+}
+
+impl Lockout {
+    pub fn new(store: Arc<dyn LockoutStore>, policy: LockoutPolicy) -> Self {
+        Self { store, policy }
+    }
+
+    pub fn policy(&self) -> &LockoutPolicy {
+        &self.policy
+    }
+
+    /// Time left on `key`'s lockout at `now`, or `None` if it may try.
+    pub fn check(&self, key: &str, now: DateTime<Utc>) -> Result<Option<Duration>> {
+        Ok(self
+            .store
+            .load(key)?
+            .and_then(|s| s.locked_until)
+            .filter(|until| *until > now)
+            .map(|until| until - now))
+    }
+
+    /// Records a failure of `key` at `now`, returning the lockout it
+    /// triggered, if any.
+    pub fn record_failure(&self, key: &str, now: DateTime<Utc>) -> Result<Option<Duration>> {
+        let failures = match self.store.load(key)? {
+            Some(s) if now - s.last_failure < self.policy.reset_after => {
+                s.failures.saturating_add(1)
+            }
+            _ => 1,
+        };
+        let lockout = self.policy.lockout_for(failures);
+        if lockout.is_some() {
+            tracing::warn!(key, failures, "authentication locked out");
+        }
+        self.store.save(
+            key,
+            &LockoutState {
+                failures,
+                last_failure: now,
+                locked_until: lockout.map(|d| now + d),
+            },
+        )?;
+        Ok(lockout)
+    }
+
+    /// Clears `key`'s failures after a successful authentication.
+    pub fn record_success(&self, key: &str) -> Result<()> {
+        self.store.clear(key)
+    }
+}
+
+/// Middleware locking out client IPs after repeated `401` responses.
+///
+/// The client address comes from the [`ClientIp`] extension, else
+/// `ConnectInfo<SocketAddr>`; requests without either pass unchecked.
+/// Mount with `from_fn_with_state(lockout, lockout_layer)` on the sign-in
+/// routes.
+pub async fn lockout_layer(State(lockout): State<Lockout>, req: Request, next: Next) -> Response {
+    let Some(key) = ClientIp::of(req.extensions()).map(ip_key) else {
+        return next.run(req).await;
+    };
+
+    let (l, k) = (lockout.clone(), key.clone());
+    match blocking(move || l.check(&k, Utc::now())).await {
+        Ok(Some(wait)) => return locked(wait),
+        Ok(None) => {}
+        Err(e) => return e.into_response(),
+    }
+
+    let res = next.run(req).await;
+    let status = res.status();
+    if status == StatusCode::UNAUTHORIZED {
+        let _ = blocking(move || lockout.record_failure(&key, Utc::now())).await;
+    } else if status.is_success() {
+        let _ = blocking(move || lockout.record_success(&key)).await;
+    }
+    res
+}
+
+fn locked(wait: Duration) -> Response {
+    let secs = ((wait.num_milliseconds() + 999) / 1000).max(1);
+    let problem = Problem::new(StatusCode::TOO_MANY_REQUESTS).detail("too many failed attempts");
+    let mut res = problem.into_response();
+    res.headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(secs as u64));
+    res
+}
+
+async fn blocking<T, F>(f: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(Ok(v)) => Ok(v),
+        Ok(Err(e)) => Err(AppError::Internal(e.context("lockout store failed"))),
+        Err(e) => Err(AppError::Internal(anyhow!("lockout task failed: {e}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware::from_fn_with_state, routing::post, Extension, Router};
+    use tower::ServiceExt;
+
+    use crate::db::port::{Row, Value};
+
+    fn lockout() -> Lockout {
+        Lockout::new(
+            Arc::new(MemoryLockoutStore::default()),
+            LockoutPolicy {
+                max_failures: 3,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn lockout_doubles_up_to_the_cap() {
+        let p = LockoutPolicy::default();
+        assert_eq!(p.lockout_for(4), None);
+        assert_eq!(p.lockout_for(5), Some(Duration::seconds(30)));
+        assert_eq!(p.lockout_for(6), Some(Duration::seconds(60)));
+        assert_eq!(p.lockout_for(8), Some(Duration::seconds(240)));
+        assert_eq!(p.lockout_for(20), Some(Duration::hours(1)));
+        assert_eq!(p.lockout_for(u32::MAX), Some(Duration::hours(1)));
+    }
+
+    #[test]
+    fn failures_lock_the_key_until_success_or_reset() {
+        let l = lockout();
+        let now = Utc::now();
+        let key = user_key(" Alice ");
+        assert_eq!(key, "user:alice");
+
+        assert_eq!(l.record_failure(&key, now).unwrap(), None);
+        assert_eq!(l.record_failure(&key, now).unwrap(), None);
+        assert_eq!(
+            l.record_failure(&key, now).unwrap(),
+            Some(Duration::seconds(30))
+        );
+        assert_eq!(l.check(&key, now).unwrap(), Some(Duration::seconds(30)));
+        assert_eq!(l.check(&key, now + Duration::seconds(31)).unwrap(), None);
+        assert_eq!(
+            l.record_failure(&key, now + Duration::seconds(31)).unwrap(),
+            Some(Duration::seconds(60))
+        );
+        assert_eq!(l.check("user:bob", now).unwrap(), None);
+
+        let later = now + Duration::days(2);
+        assert_eq!(l.record_failure(&key, later).unwrap(), None, "forgotten");
+
+        l.record_success(&key).unwrap();
+        assert_eq!(l.check(&key, later).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn layer_counts_401s_per_ip_and_answers_429() {
+        let l = lockout();
+        let app = Router::new()
+            .route("/login", post(|| async { StatusCode::UNAUTHORIZED }))
+            .layer(from_fn_with_state(l.clone(), lockout_layer))
+            .layer(Extension(ClientIp(IpAddr::from([192, 0, 2, 1]))));
+        let call = || {
+            let app = app.clone();
+            async move {
+                app.oneshot(
+                    axum::http::Request::post("/login")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        for _ in 0..3 {
+            assert_eq!(call().await.status(), StatusCode::UNAUTHORIZED);
+        }
+        let res = call().await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers()[RETRY_AFTER], "30");
+        assert!(l
+            .check(&ip_key(IpAddr::from([192, 0, 2, 2])), Utc::now())
+            .unwrap()
+            .is_none());
+    }
+
+    #[derive(Default)]
+    struct RecordingDb {
+        calls: Mutex<Vec<(String, usize)>>,
+        row: Option<Row>,
+    }
+
+    impl Db for RecordingDb {
+        fn fetch_one(&self, sql: &str, params: &[Param]) -> Result<Option<Row>> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push((sql.to_string(), params.len()));
+            Ok(self.row.clone())
+        }
+
+        fn fetch_all(&self, _sql: &str, _params: &[Param]) -> Result<Vec<Row>> {
+            Ok(vec![])
+        }
+
+        fn exec(&self, sql: &str, params: &[Param]) -> Result<u64> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push((sql.to_string(), params.len()));
+            Ok(1)
+        }
+
+        fn exec_returning_last_insert_id(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn db_store_maps_rows_and_statements() {
+        let last = Utc::now().naive_utc();
+        let mut row = Row::default();
+        row.insert("failures", Value::U64(4));
+        row.insert("last_failure", Value::DateTime(last));
+        row.insert("locked_until", Value::Null);
+        let db = Arc::new(RecordingDb {
+            row: Some(row),
+            ..Default::default()
+        });
+        let store = DbLockoutStore::new(db.clone());
+
+        let state = store.load("ip:1.2.3.4").unwrap().unwrap();
+        assert_eq!(state.failures, 4);
+        assert_eq!(state.last_failure.naive_utc(), last);
+        assert_eq!(state.locked_until, None);
+        store.save("ip:1.2.3.4", &state).unwrap();
+        store.clear("ip:1.2.3.4").unwrap();
+        store.purge_before(Utc::now()).unwrap();
+
+        let calls = db.calls.lock().unwrap();
+        assert!(calls[0]
+            .0
+            .starts_with("SELECT failures, last_failure, locked_until FROM auth_lockouts"));
+        assert!(calls[1].0.contains("ON DUPLICATE KEY UPDATE"));
+        assert_eq!(calls[1].1, 4);
+        assert_eq!(
+            calls[2].0,
+            "DELETE FROM auth_lockouts WHERE lockout_key = ?"
+        );
+        assert_eq!(calls[3].1, 2);
+        assert!(DbLockoutStore::with_table(db.clone(), "x; DROP").is_err());
+    }
+}
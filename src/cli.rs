@@ -0,0 +1,561 @@
+//! # Ops CLI
+//!
+//! Subcommands shared by applications built on this crate, so every app's
+//! `main.rs` can expose the same operations CLI. Enabled with the `cli`
+//! feature.
+//!
+//! | Command                               | Action                                      |
+//! |---------------------------------------|---------------------------------------------|
+//! | `migrate up\|down\|status [--dir D]`  | Run [`Migrator`] over `DATABASE_URL`        |
+//! | `admin-token <user-id>`               | Print a JWT signed with `JWT_SECRET`        |
+//! | `test-email <address>`                | Send a test message via the SMTP config     |
+//! | `check-config`                        | Report missing or weak configuration        |
+//! | `check-dns [domain]`                  | Check MX/SPF/DKIM/DMARC for the sender domain |
+//! | `rotate-secret csrf\|jwt`             | Print a freshly generated secret            |
+//! | `storage-migrate <dir> [--move] [--concurrency N] [--rewrite FROM=TO]` | Copy uploads from `UPLOAD_ROOT` with [`StorageMigration`] |
+//!
+//! The migrations directory defaults to `MIGRATIONS_DIR`, then `./migrations`.
+//! `rotate-secret` only prints the new value as an env line; deploying it
+//! (and accepting that existing tokens/sessions become invalid) is up to the
+//! operator.
+//!
+//! `storage-migrate` copies the image and file directories of `UPLOAD_ROOT`
+//! into the local directory `<dir>` (e.g. a shared mount), verifying each
+//! copy, and prints the changed keys as `old -> new` lines for updating
+//! stored references. Other targets (object stores) need an application
+//! binary that builds its own [`StorageMigration`].
+//!
+//! # Example
+//! ```rust,no_run
+//! // src/bin/ops.rs
+//! #[tokio::main]
+//! async fn main() -> std::process::ExitCode {
+//!     match wzs_web::cli::run(std::env::args().skip(1)).await {
+//!         Ok(()) => std::process::ExitCode::SUCCESS,
+//!         Err(e) => {
+//!             eprintln!("error: {e:#}");
+//!             std::process::ExitCode::FAILURE
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use lettre::message::Mailbox;
+
+use crate::auth::jwt::create_jwt;
+use crate::config::app::AppConfig;
+use crate::config::csrf::random_secret;
+use crate::config::db::create_pool;
+use crate::db::migrate::Migrator;
+use crate::db::mysql_adapter::MySqlDb;
+use crate::db::port::Db;
+use crate::notification::dns::{sender_domain, DnsPreflight, UdpResolver};
+use crate::notification::email::{Email, EmailBody};
+use crate::notification::email_sender::EmailSender;
+use crate::web::upload::local_storage::LocalFileStorage;
+use crate::web::upload::migrate::{MigrationMode, StorageMigration};
+
+/// Usage text printed by `help` and on parse errors.
+pub const USAGE: &str = "\
+Usage: <app> <command> [args]
+
+Commands:
+  migrate up|down|status [--dir <path>]  Apply, roll back or list migrations
+  admin-token <user-id>                  Print a JWT signed with JWT_SECRET
+  test-email <address>                   Send a test email via SMTP_* settings
+  check-config                           Report missing or weak configuration
+  check-dns [domain]                     Check MX/SPF/DKIM/DMARC (default: SMTP_FROM_EMAIL domain)
+  rotate-secret csrf|jwt                 Print a newly generated secret
+  storage-migrate <dir> [--move] [--concurrency <n>] [--rewrite <from>=<to>]
+                                         Copy uploads from UPLOAD_ROOT to <dir>
+  help                                   Show this message
+";
+
+/// Minimum recommended length for `JWT_SECRET`.
+const MIN_SECRET_LEN: usize = 32;
+
+/// Direction for the `migrate` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrateAction {
+    Up,
+    Down,
+    Status,
+}
+
+/// Secret rotated by `rotate-secret`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretKind {
+    Csrf,
+    Jwt,
+}
+
+impl SecretKind {
+    /// Returns the environment variable holding the secret.
+    pub const fn env_var(&self) -> &'static str {
+        match self {
+            Self::Csrf => "CSRF_SECRET",
+            Self::Jwt => "JWT_SECRET",
+        }
+    }
+}
+
+/// A parsed subcommand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Migrate { action: MigrateAction, dir: PathBuf },
+    AdminToken { user_id: u64 },
+    TestEmail { to: String },
+    CheckConfig,
+    CheckDns { domain: Option<String> },
+    RotateSecret { kind: SecretKind },
+    StorageMigrate {
+        to: PathBuf,
+        mode: MigrationMode,
+        concurrency: usize,
+        rewrites: Vec<(String, String)>,
+    },
+    Help,
+}
+
+impl Command {
+    /// Parses arguments (without the program name).
+    ///
+    /// # Errors
+    /// Returns an error for unknown commands or missing/invalid arguments.
+    pub fn parse<I, S>(args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut args = args.into_iter().map(Into::into);
+        let cmd = args.next().unwrap_or_else(|| "help".into());
+        let mut next = |what: &str| args.next().ok_or_else(|| anyhow!("{cmd}: missing {what}"));
+
+        let parsed = match cmd.as_str() {
+            "migrate" => {
+                let action = match next("action")?.as_str() {
+                    "up" => MigrateAction::Up,
+                    "down" => MigrateAction::Down,
+                    "status" => MigrateAction::Status,
+                    other => bail!("migrate: unknown action {other}"),
+                };
+                let dir = match args.next().as_deref() {
+                    Some("--dir") => PathBuf::from(
+                        args.next()
+                            .ok_or_else(|| anyhow!("migrate: --dir needs a path"))?,
+                    ),
+                    Some(other) => bail!("migrate: unexpected argument {other}"),
+                    None => std::env::var("MIGRATIONS_DIR")
+                        .unwrap_or_else(|_| "migrations".into())
+                        .into(),
+                };
+                Self::Migrate { action, dir }
+            }
+            "admin-token" => Self::AdminToken {
+                user_id: next("user id")?
+                    .parse()
+                    .context("admin-token: user id must be a number")?,
+            },
+            "test-email" => Self::TestEmail {
+                to: next("address")?,
+            },
+            "check-config" => Self::CheckConfig,
+            "check-dns" => Self::CheckDns {
+                domain: args.next(),
+            },
+            "rotate-secret" => Self::RotateSecret {
+                kind: match next("secret kind")?.as_str() {
+                    "csrf" => SecretKind::Csrf,
+                    "jwt" => SecretKind::Jwt,
+                    other => bail!("rotate-secret: unknown secret {other}"),
+                },
+            },
+            "storage-migrate" => {
+                let to = PathBuf::from(next("target directory")?);
+                let (mut mode, mut concurrency, mut rewrites) = (MigrationMode::Copy, 4, vec![]);
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--move" => mode = MigrationMode::Move,
+                        "--concurrency" => {
+                            concurrency =
+                                args.next().and_then(|n| n.parse().ok()).ok_or_else(|| {
+                                    anyhow!("storage-migrate: --concurrency needs a number")
+                                })?
+                        }
+                        "--rewrite" => {
+                            let rule = args.next().unwrap_or_default();
+                            let (from, to) = rule.split_once('=').ok_or_else(|| {
+                                anyhow!("storage-migrate: --rewrite needs <from>=<to>")
+                            })?;
+                            rewrites.push((from.to_string(), to.to_string()));
+                        }
+                        other => bail!("storage-migrate: unexpected argument {other}"),
+                    }
+                }
+                Self::StorageMigrate {
+                    to,
+                    mode,
+                    concurrency,
+                    rewrites,
+                }
+            }
+            "help" | "--help" | "-h" => Self::Help,
+            other => bail!("unknown command: {other}\n\n{USAGE}"),
+        };
+        Ok(parsed)
+    }
+}
+
+/// Parses `args`, loads [`AppConfig::from_env`] and runs the command,
+/// writing output to stdout.
+///
+/// # Errors
+/// Returns parse errors and any error from the command.
+pub async fn run<I, S>(args: I) -> Result<()>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let cmd = Command::parse(args)?;
+    let cfg = AppConfig::from_env();
+    execute(&cmd, &cfg, &mut std::io::stdout()).await
+}
+
+/// Runs `cmd` against `cfg`, writing human-readable output to `out`.
+///
+/// # Errors
+/// Returns an error if the command fails; `check-config` fails when it
+/// finds problems.
+pub async fn execute(cmd: &Command, cfg: &AppConfig, out: &mut dyn Write) -> Result<()> {
+    match cmd {
+        Command::Migrate { action, dir } => {
+            let db: Arc<dyn Db> = Arc::new(MySqlDb::new(create_pool(&cfg.db)?));
+            let dir = dir.clone();
+            let action = *action;
+            let lines = tokio::task::spawn_blocking(move || migrate(db, &dir, action)).await??;
+            for line in lines {
+                writeln!(out, "{line}")?;
+            }
+        }
+        Command::AdminToken { user_id } => {
+            writeln!(out, "{}", admin_token(cfg, *user_id)?)?;
+        }
+        Command::TestEmail { to } => {
+            let mail = cfg
+                .mail
+                .as_ref()
+                .ok_or_else(|| anyhow!("mail is not configured (SMTP_HOST unset or invalid)"))?;
+            let sender = mail.build_sender()?;
+            send_test_email(&sender, to).await?;
+            writeln!(out, "test email sent to {to}")?;
+        }
+        Command::CheckConfig => {
+            let problems = check_config(cfg);
+            if problems.is_empty() {
+                writeln!(out, "config ok")?;
+            } else {
+                for p in &problems {
+                    writeln!(out, "- {p}")?;
+                }
+                bail!("{} configuration problem(s)", problems.len());
+            }
+        }
+        Command::CheckDns { domain } => {
+            let domain = match (domain, &cfg.mail) {
+                (Some(d), _) => d.clone(),
+                (None, Some(mail)) => sender_domain(&mail.from_email)
+                    .ok_or_else(|| anyhow!("SMTP_FROM_EMAIL has no domain"))?
+                    .to_string(),
+                (None, None) => bail!("check-dns: pass a domain or configure SMTP_*"),
+            };
+            let resolver = Arc::new(UdpResolver::from_system()?);
+            let preflight = match &cfg.mail {
+                Some(mail) => DnsPreflight::for_mail(resolver, mail),
+                None => DnsPreflight::new(resolver),
+            };
+            let checks = preflight.check_and_log(&domain).await;
+            for c in &checks {
+                writeln!(out, "- {c}")?;
+            }
+            let failed = checks.iter().filter(|c| !c.is_ok()).count();
+            if failed > 0 {
+                bail!("{failed} DNS problem(s) for {domain}");
+            }
+        }
+        Command::RotateSecret { kind } => {
+            writeln!(out, "{}={}", kind.env_var(), generate_secret())?;
+        }
+        Command::StorageMigrate {
+            to,
+            mode,
+            concurrency,
+            rewrites,
+        } => {
+            let mut migration = StorageMigration::new(
+                Arc::new(LocalFileStorage::new(&cfg.upload.root)),
+                Arc::new(LocalFileStorage::new(to)),
+            )
+            .prefixes([&cfg.upload.image_dir, &cfg.upload.file_dir])
+            .mode(*mode)
+            .concurrency(*concurrency);
+            for (from, to) in rewrites {
+                migration = migration.rewrite_prefix(from, to);
+            }
+            let report = migration.run().await?;
+            for (old, new) in report.rewrite_map() {
+                writeln!(out, "{old} -> {new}")?;
+            }
+            writeln!(out, "{}", report.summary())?;
+            if !report.failed.is_empty() {
+                bail!("{} object(s) failed to migrate", report.failed.len());
+            }
+        }
+        Command::Help => write!(out, "{USAGE}")?,
+    }
+    Ok(())
+}
+
+/// Runs `action` over the migrations in `dir`, returning report lines.
+///
+/// # Errors
+/// Returns an error if the migrations cannot be loaded or applied.
+pub fn migrate(
+    db: Arc<dyn Db>,
+    dir: &std::path::Path,
+    action: MigrateAction,
+) -> Result<Vec<String>> {
+    let migrator = Migrator::from_dir(db, dir)?;
+    let lines = match action {
+        MigrateAction::Up => {
+            let applied = migrator.up()?;
+            if applied.is_empty() {
+                vec!["nothing to apply".into()]
+            } else {
+                applied.iter().map(|v| format!("applied {v}")).collect()
+            }
+        }
+        MigrateAction::Down => match migrator.down()? {
+            Some(v) => vec![format!("rolled back {v}")],
+            None => vec!["nothing to roll back".into()],
+        },
+        MigrateAction::Status => migrator
+            .status()?
+            .iter()
+            .map(|s| match s.applied_at {
+                Some(at) => format!("{} {} applied {at}", s.version, s.name),
+                None => format!("{} {} pending", s.version, s.name),
+            })
+            .collect(),
+    };
+    Ok(lines)
+}
+
+/// Creates a JWT for `user_id` signed with the configured secret.
+///
+/// # Errors
+/// Returns an error if `JWT_SECRET` is empty or signing fails.
+pub fn admin_token(cfg: &AppConfig, user_id: u64) -> Result<String> {
+    if cfg.jwt_secret.is_empty() {
+        bail!("JWT_SECRET is not set");
+    }
+    create_jwt(user_id, &cfg.jwt_secret)
+}
+
+/// Sends a short plain-text message to `to`.
+///
+/// # Errors
+/// Returns an error if `to` is not a valid address or delivery fails.
+pub async fn send_test_email(sender: &dyn EmailSender, to: &str) -> Result<()> {
+    let to: Mailbox = to
+        .parse()
+        .with_context(|| format!("invalid address: {to}"))?;
+    sender
+        .send(Email {
+            subject: "Test email".into(),
+            body: EmailBody::Text("This is a test email sent from the ops CLI.\n".into()),
+            to: vec![to],
+            cc: vec![],
+            bcc: vec![],
+            headers: vec![],
+        })
+        .await
+}
+
+/// Returns a description of each missing or weak setting.
+pub fn check_config(cfg: &AppConfig) -> Vec<String> {
+    let mut problems = Vec::new();
+    if !cfg.db.is_valid() {
+        problems.push("DATABASE_URL is not set".to_string());
+    }
+    if let Err(e) = cfg.db.validate() {
+        problems.push(e.to_string());
+    }
+    if cfg.jwt_secret.is_empty() {
+        problems.push("JWT_SECRET is not set".to_string());
+    } else if cfg.jwt_secret.len() < MIN_SECRET_LEN {
+        problems.push(format!(
+            "JWT_SECRET is shorter than {MIN_SECRET_LEN} characters"
+        ));
+    }
+    if !cfg.is_csrf_enabled() {
+        problems.push("CSRF_SECRET is not set; CSRF protection is disabled".to_string());
+    }
+    if cfg.mail.is_none() {
+        problems.push("mail is not configured (SMTP_* missing or invalid)".to_string());
+    }
+    if cfg.cross_site.enabled && cfg.cross_site.origins.is_empty() {
+        problems.push("CROSS_SITE_ENABLED is set but CROSS_SITE_ORIGINS is empty".to_string());
+    }
+    problems
+}
+
+/// Generates a random URL-safe secret (256 bits).
+pub fn generate_secret() -> String {
+    URL_SAFE_NO_PAD.encode(random_secret())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+
+    use super::*;
+
+    #[test]
+    fn parses_subcommands() {
+        assert_eq!(
+            Command::parse(["migrate", "status", "--dir", "db/migrations"]).unwrap(),
+            Command::Migrate {
+                action: MigrateAction::Status,
+                dir: "db/migrations".into()
+            }
+        );
+        assert_eq!(
+            Command::parse(["admin-token", "7"]).unwrap(),
+            Command::AdminToken { user_id: 7 }
+        );
+        assert_eq!(
+            Command::parse(["rotate-secret", "jwt"]).unwrap(),
+            Command::RotateSecret {
+                kind: SecretKind::Jwt
+            }
+        );
+        assert_eq!(
+            Command::parse(["check-dns", "example.com"]).unwrap(),
+            Command::CheckDns {
+                domain: Some("example.com".into())
+            }
+        );
+        assert_eq!(
+            Command::parse(["check-dns"]).unwrap(),
+            Command::CheckDns { domain: None }
+        );
+        assert_eq!(
+            Command::parse([
+                "storage-migrate",
+                "/mnt/uploads",
+                "--move",
+                "--rewrite",
+                "images/=media/"
+            ])
+            .unwrap(),
+            Command::StorageMigrate {
+                to: "/mnt/uploads".into(),
+                mode: MigrationMode::Move,
+                concurrency: 4,
+                rewrites: vec![("images/".into(), "media/".into())],
+            }
+        );
+        assert_eq!(Command::parse(Vec::<String>::new()).unwrap(), Command::Help);
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(Command::parse(["migrate", "sideways"]).is_err());
+        assert!(Command::parse(["admin-token", "alice"]).is_err());
+        assert!(Command::parse(["test-email"]).is_err());
+        assert!(Command::parse(["deploy"]).is_err());
+        assert!(Command::parse(["storage-migrate", "/mnt", "--rewrite", "x"]).is_err());
+    }
+
+    fn config() -> AppConfig {
+        temp_env::with_vars(
+            [
+                ("APP_ENV", Some("production")),
+                ("DATABASE_URL", None::<&str>),
+                ("JWT_SECRET", Some("short")),
+                ("CSRF_SECRET", None),
+                ("SMTP_HOST", None),
+            ],
+            AppConfig::from_env,
+        )
+    }
+
+    #[tokio::test]
+    async fn check_config_reports_problems() {
+        let cfg = config();
+        let problems = check_config(&cfg);
+        assert_eq!(problems.len(), 4);
+        assert!(problems[1].contains("shorter than 32"));
+
+        let mut out = Vec::new();
+        assert!(execute(&Command::CheckConfig, &cfg, &mut out)
+            .await
+            .is_err());
+        assert!(String::from_utf8(out).unwrap().contains("DATABASE_URL"));
+    }
+
+    #[tokio::test]
+    async fn rotate_secret_prints_env_line() {
+        let mut out = Vec::new();
+        let cmd = Command::RotateSecret {
+            kind: SecretKind::Csrf,
+        };
+        execute(&cmd, &config(), &mut out).await.unwrap();
+
+        let line = String::from_utf8(out).unwrap();
+        let value = line.trim().strip_prefix("CSRF_SECRET=").unwrap();
+        assert_eq!(value.len(), 43);
+        assert_ne!(value, generate_secret());
+    }
+
+    #[test]
+    fn admin_token_requires_secret() {
+        let mut cfg = config();
+        assert!(!admin_token(&cfg, 1).unwrap().is_empty());
+        cfg.jwt_secret.clear();
+        assert!(admin_token(&cfg, 1).is_err());
+    }
+
+    #[derive(Default)]
+    struct RecordingSender {
+        sent: Mutex<Vec<Email>>,
+    }
+
+    #[async_trait]
+    impl EmailSender for RecordingSender {
+        async fn send(&self, email: Email) -> Result<()> {
+            self.sent.lock().unwrap().push(email);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn send_test_email_validates_address() {
+        let sender = RecordingSender::default();
+        send_test_email(&sender, "ops@example.com").await.unwrap();
+        assert!(send_test_email(&sender, "not an address").await.is_err());
+        assert_eq!(
+            sender.sent.lock().unwrap()[0].to[0].email.to_string(),
+            "ops@example.com"
+        );
+    }
+}
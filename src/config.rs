@@ -1,8 +1,12 @@
 pub mod app;
+pub mod cross_site;
+pub mod crypto;
 pub mod csrf;
 pub mod db;
 pub mod env;
+pub mod headers;
 pub mod image;
+pub mod locale;
 pub mod mail;
 pub mod upload;
 pub mod web;
@@ -24,9 +24,12 @@
 //! | `GRAPHIQL` | Enable GraphiQL IDE (development only) | `false` |
 //! | `CORS_ORIGINS` | Allowed origins for CORS | `""` |
 //! | `CORS_CREDENTIALS` | Allow credentials in CORS requests | `false` |
+//! | `CROSS_SITE_ENABLED` | `SameSite=None` cookies for cross-site embedding (see [`CrossSiteConfig`]) | `false` |
+//! | `CROSS_SITE_ORIGINS` | Partner origins allowed to embed the app | `""` |
 //! | `UPLOAD_ROOT` | Root directory for uploads | `"./var/uploads"` |
 //! | `UPLOAD_IMAGE_DIR` | Subdirectory for image uploads | `"images"` |
 //! | `UPLOAD_FILE_DIR` | Subdirectory for other file uploads | `"files"` |
+//! | `UPLOAD_KEY_SCHEME` | Storage key naming (`dated`, `content-hash`, `tenant`, `slug`) | `"dated"` |
 //! | `IMAGE_MAX_WIDTH` | Max allowed image width (px) | `1280` |
 //! | `IMAGE_MAX_HEIGHT` | Max allowed image height (px) | `1280` |
 //! | `SMTP_HOST` | SMTP server hostname | *none* |
@@ -36,6 +39,8 @@
 //! | `SMTP_FROM_EMAIL` | Sender email address | *none* |
 //! | `SMTP_FROM_NAME` | Sender display name | `"Notifier"` |
 //! | `NOTIFY_TO_EMAIL` | Notification recipients (comma-separated) | empty |
+//! | `APP_DEFAULT_LOCALE` | Locale used when negotiation finds no match | `"en"` |
+//! | `APP_LOCALES` | Supported locales (comma-separated) | empty (any) |
 //!
 //! # Example
 //! ```rust,no_run
@@ -51,10 +56,12 @@ use std::env;
 use std::path::PathBuf;
 
 use crate::config::{
+    cross_site::CrossSiteConfig,
     csrf::CsrfConfig,
     db::DbConfig,
     env::*,
     image::ImageConfig,
+    locale::LocaleConfig,
     mail::MailConfig,
     upload::UploadConfig,
     web::{CorsConfig, HttpConfig},
@@ -78,12 +85,16 @@ pub struct AppConfig {
     pub csrf: CsrfConfig,
     /// Cross-Origin Resource Sharing configuration.
     pub cors: CorsConfig,
+    /// Cross-site embedding mode, already applied to `csrf` and `cors`.
+    pub cross_site: CrossSiteConfig,
     /// Image dimension constraints.
     pub image: ImageConfig,
     /// File and image upload directory configuration.
     pub upload: UploadConfig,
     /// Optional mail (SMTP) configuration.
     pub mail: Option<MailConfig>,
+    /// Default and supported locales.
+    pub locale: LocaleConfig,
     /// Whether the GraphiQL IDE is enabled (typically only in development).
     pub enable_graphiql: bool,
     /// JWT signing secret.
@@ -155,6 +166,10 @@ impl AppConfig {
             .unwrap_or_else(|_| "./var/uploads".into());
         let image_dir = env::var("UPLOAD_IMAGE_DIR").unwrap_or_else(|_| "images".into());
         let file_dir = env::var("UPLOAD_FILE_DIR").unwrap_or_else(|_| "files".into());
+        let key_scheme = env::var("UPLOAD_KEY_SCHEME")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default();
 
         // --- Mail configuration (optional) ---
         //
@@ -173,17 +188,21 @@ impl AppConfig {
         let jwt_secret = env::var("JWT_SECRET").unwrap_or_else(|_| "".to_string());
         let html_path = env::var("HTML_PATH").unwrap_or_else(|_| "".to_string());
 
+        // Cross-site embedding adjusts the CSRF and CORS settings
+        let cross_site = CrossSiteConfig::from_env();
+
         AppConfig {
             db: DbConfig::from_env(),
             http: HttpConfig {
                 max_body_bytes: http_max_body_bytes,
             },
-            csrf: CsrfConfig::from_env(),
-            cors: CorsConfig {
+            csrf: cross_site.apply_csrf(CsrfConfig::from_env()),
+            cors: cross_site.apply_cors(CorsConfig {
                 enabled: cors_enabled,
                 env: cors_env,
                 credentials: cors_credentials,
-            },
+            }),
+            cross_site,
             image: ImageConfig {
                 max_width: max_w,
                 max_height: max_h,
@@ -192,8 +211,10 @@ impl AppConfig {
                 root: upload_root,
                 image_dir,
                 file_dir,
+                key_scheme,
             },
             mail,
+            locale: LocaleConfig::from_env(),
             enable_graphiql,
             jwt_secret,
             html_path,
@@ -211,6 +232,7 @@ impl AppConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::upload::KeyScheme;
     use temp_env;
 
     #[test]
@@ -292,6 +314,7 @@ mod tests {
             ("UPLOAD_ROOT", None),
             ("UPLOAD_IMAGE_DIR", None),
             ("UPLOAD_FILE_DIR", None),
+            ("UPLOAD_KEY_SCHEME", None),
         ];
 
         temp_env::with_vars(vars, || {
@@ -305,6 +328,7 @@ mod tests {
             assert_eq!(cfg.upload.root, PathBuf::from("./var/uploads"));
             assert_eq!(cfg.upload.image_dir, "images");
             assert_eq!(cfg.upload.file_dir, "files");
+            assert_eq!(cfg.upload.key_scheme, KeyScheme::Dated);
 
             assert!(!cfg.cors.enabled);
             assert_eq!(cfg.cors.env, "");
@@ -322,6 +346,7 @@ mod tests {
             ("UPLOAD_ROOT", Some("/data/uploads")),
             ("UPLOAD_IMAGE_DIR", Some("pics")),
             ("UPLOAD_FILE_DIR", Some("docs")),
+            ("UPLOAD_KEY_SCHEME", Some("content-hash")),
             ("CORS_ENABLED", Some("true")),
             (
                 "CORS_ORIGINS",
@@ -342,6 +367,7 @@ mod tests {
             assert_eq!(cfg.upload.root, PathBuf::from("/data/uploads"));
             assert_eq!(cfg.upload.image_dir, "pics");
             assert_eq!(cfg.upload.file_dir, "docs");
+            assert_eq!(cfg.upload.key_scheme, KeyScheme::ContentHash);
 
             assert!(cfg.cors.enabled);
             assert_eq!(cfg.cors.env, "https://a.example.com,https://b.example.com");
@@ -539,4 +565,27 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn cross_site_mode_adjusts_csrf_and_cors() {
+        temp_env::with_vars(
+            vec![
+                ("CROSS_SITE_ENABLED", Some("true")),
+                ("CROSS_SITE_ORIGINS", Some("https://portal.example")),
+                ("CORS_ORIGINS", Some("https://app.example")),
+                ("CSRF_COOKIE_SECURE", Some("false")),
+            ],
+            || {
+                let cfg = AppConfig::from_env();
+                assert!(cfg.cross_site.enabled);
+                assert!(cfg.csrf.cookie_secure);
+                assert_eq!(
+                    cfg.csrf.allowed_origins,
+                    Some(vec!["https://portal.example".to_string()])
+                );
+                assert!(cfg.cors.enabled && cfg.cors.credentials);
+                assert_eq!(cfg.cors.env, "https://app.example,https://portal.example");
+            },
+        );
+    }
 }
@@ -0,0 +1,245 @@
+//! # Cross-Site Embedding Configuration
+//!
+//! Partner portals that embed the SPA in an `<iframe>` (or call the API with
+//! `credentials: "include"`) make every request cross-site, so `SameSite=Lax`
+//! cookies are never sent. [`CrossSiteConfig`] switches the cookie-related
+//! settings to `SameSite=None; Secure` in one place and compensates for the
+//! lost `SameSite` protection by enforcing the CSRF `Origin` check.
+//!
+//! | Variable | Default | Meaning |
+//! |----------|---------|---------|
+//! | `CROSS_SITE_ENABLED` | `false` | Enables cross-site mode |
+//! | `CROSS_SITE_ORIGINS` | *(empty)* | Comma-separated partner origins |
+//!
+//! When disabled, every `apply_*` method returns its input unchanged.
+//!
+//! # Example
+//! ```rust
+//! use axum_extra::extract::cookie::SameSite;
+//! use wzs_web::config::cross_site::CrossSiteConfig;
+//! use wzs_web::config::csrf::CsrfConfig;
+//!
+//! let cross_site = CrossSiteConfig::from_env_with(|k| match k {
+//!     "CROSS_SITE_ENABLED" => Some("true".into()),
+//!     "CROSS_SITE_ORIGINS" => Some("https://portal.partner.example".into()),
+//!     _ => None,
+//! });
+//!
+//! let csrf = cross_site.apply_csrf(CsrfConfig::from_env_with(|_| None));
+//! assert_eq!(csrf.cookie_same_site, SameSite::None);
+//! assert!(csrf.cookie_secure);
+//! assert_eq!(
+//!     csrf.allowed_origins,
+//!     Some(vec!["https://portal.partner.example".to_string()])
+//! );
+//! ```
+
+use std::env;
+
+use axum_extra::extract::cookie::SameSite;
+
+use crate::auth::session::SessionConfig;
+use crate::config::csrf::{parse_origins, CsrfConfig};
+use crate::config::env::read_flag_from;
+use crate::config::web::CorsConfig;
+use crate::web::secure_cookie::SecureCookies;
+
+/// Cross-site embedding mode and the partner origins it admits.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CrossSiteConfig {
+    /// Whether cookies are issued with `SameSite=None; Secure`.
+    pub enabled: bool,
+    /// Normalized partner origins (`scheme://host[:port]`, lowercase).
+    pub origins: Vec<String>,
+}
+
+impl CrossSiteConfig {
+    /// Loads configuration from `CROSS_SITE_ENABLED` and `CROSS_SITE_ORIGINS`.
+    pub fn from_env() -> Self {
+        Self::from_env_with(|k| env::var(k).ok())
+    }
+
+    /// Loads configuration using a custom key provider (for testing/mocking).
+    pub fn from_env_with<F>(get: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        Self {
+            enabled: read_flag_from(&get, "CROSS_SITE_ENABLED", false),
+            origins: parse_origins(&get("CROSS_SITE_ORIGINS").unwrap_or_default()),
+        }
+    }
+
+    /// Enabled configuration admitting `origins`.
+    pub fn embedded<I, S>(origins: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let list = origins
+            .into_iter()
+            .map(|o| o.as_ref().to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        Self {
+            enabled: true,
+            origins: parse_origins(&list),
+        }
+    }
+
+    /// `SameSite` attribute for cookies: `None` when enabled, else `Lax`.
+    pub fn same_site(&self) -> SameSite {
+        if self.enabled {
+            SameSite::None
+        } else {
+            SameSite::Lax
+        }
+    }
+
+    /// Issues the CSRF cookie as `SameSite=None; Secure` and turns on the
+    /// `Origin` check with the partner origins added.
+    pub fn apply_csrf(&self, mut cfg: CsrfConfig) -> CsrfConfig {
+        if !self.enabled {
+            return cfg;
+        }
+        cfg.cookie_secure = true;
+        cfg.cookie_same_site = SameSite::None;
+        let mut allowed = cfg.allowed_origins.take().unwrap_or_default();
+        for origin in &self.origins {
+            if !allowed.contains(origin) {
+                allowed.push(origin.clone());
+            }
+        }
+        cfg.allowed_origins = Some(allowed);
+        cfg
+    }
+
+    /// Enables CORS with credentials for the partner origins.
+    ///
+    /// A wildcard `env` is replaced by the partner list, since credentialed
+    /// requests must not be answered for arbitrary origins.
+    pub fn apply_cors(&self, mut cfg: CorsConfig) -> CorsConfig {
+        if !self.enabled {
+            return cfg;
+        }
+        let mut origins = if cfg.env.trim() == "*" {
+            Vec::new()
+        } else {
+            parse_origins(&cfg.env)
+        };
+        for origin in &self.origins {
+            if !origins.contains(origin) {
+                origins.push(origin.clone());
+            }
+        }
+        cfg.enabled = true;
+        cfg.credentials = true;
+        cfg.env = origins.join(",");
+        cfg
+    }
+
+    /// Issues the session cookie as `SameSite=None; Secure`.
+    pub fn apply_session(&self, cfg: SessionConfig) -> SessionConfig {
+        if !self.enabled {
+            return cfg;
+        }
+        cfg.secure(true).same_site(SameSite::None)
+    }
+
+    /// Issues signed/encrypted cookies as `SameSite=None` (they are always
+    /// `Secure`).
+    pub fn apply_cookies(&self, cookies: SecureCookies) -> SecureCookies {
+        if !self.enabled {
+            return cookies;
+        }
+        cookies.same_site(SameSite::None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled() -> CrossSiteConfig {
+        CrossSiteConfig::from_env_with(|k| match k {
+            "CROSS_SITE_ENABLED" => Some("1".into()),
+            "CROSS_SITE_ORIGINS" => Some(" https://Portal.example/ , https://b.example".into()),
+            _ => None,
+        })
+    }
+
+    fn cors(env: &str) -> CorsConfig {
+        CorsConfig {
+            enabled: false,
+            env: env.into(),
+            credentials: false,
+        }
+    }
+
+    #[test]
+    fn from_env_defaults_to_disabled() {
+        let cfg = CrossSiteConfig::from_env_with(|_| None);
+        assert_eq!(cfg, CrossSiteConfig::default());
+        assert_eq!(cfg.same_site(), SameSite::Lax);
+    }
+
+    #[test]
+    fn from_env_normalizes_origins() {
+        let cfg = enabled();
+        assert!(cfg.enabled);
+        assert_eq!(cfg.origins, ["https://portal.example", "https://b.example"]);
+        assert_eq!(
+            cfg,
+            CrossSiteConfig::embedded(["https://portal.example/", "https://b.example"])
+        );
+        assert_eq!(cfg.same_site(), SameSite::None);
+    }
+
+    #[test]
+    fn disabled_config_changes_nothing() {
+        let cfg = CrossSiteConfig::default();
+        let csrf = CsrfConfig::from_env_with(|_| None);
+        assert_eq!(cfg.apply_csrf(csrf.clone()), csrf);
+        assert_eq!(cfg.apply_cors(cors("*")), cors("*"));
+    }
+
+    #[test]
+    fn apply_csrf_enforces_origin_check() {
+        let mut csrf =
+            CsrfConfig::from_env_with(|k| (k == "CSRF_COOKIE_SECURE").then(|| "false".into()));
+        csrf.allowed_origins = Some(vec!["https://b.example".into()]);
+
+        let csrf = enabled().apply_csrf(csrf);
+        assert!(csrf.cookie_secure);
+        assert_eq!(csrf.cookie_same_site, SameSite::None);
+        assert_eq!(
+            csrf.allowed_origins,
+            Some(vec![
+                "https://b.example".to_string(),
+                "https://portal.example".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn apply_cors_merges_origins_and_requires_credentials() {
+        let out = enabled().apply_cors(cors("https://app.example"));
+        assert!(out.enabled);
+        assert!(out.credentials);
+        assert_eq!(
+            out.env,
+            "https://app.example,https://portal.example,https://b.example"
+        );
+
+        let out = enabled().apply_cors(cors("*"));
+        assert_eq!(out.env, "https://portal.example,https://b.example");
+    }
+
+    #[test]
+    fn apply_cookies_uses_same_site_none() {
+        let cookies = enabled().apply_cookies(SecureCookies::new("k1", [1u8; 32]));
+        let cookie = cookies.signed_cookie("prefs", "x");
+        assert_eq!(cookie.same_site(), Some(SameSite::None));
+        assert_eq!(cookie.secure(), Some(true));
+    }
+}
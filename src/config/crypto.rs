@@ -0,0 +1,161 @@
+//! # Field Encryption Configuration
+//!
+//! Provides the key ring used by [`db::crypto`](crate::db::crypto) to encrypt
+//! individual database columns.
+//!
+//! The configuration reads from environment variables:
+//! - `DB_ENCRYPTION_KEYS` — comma-separated `key_id:secret` pairs. A secret that
+//!   decodes as base64 to exactly 32 bytes is used as-is; any other string is
+//!   hashed with SHA-256 (see [`derive_secret_from_string`]).
+//! - `DB_ENCRYPTION_ACTIVE_KEY` — key id used for new encryptions
+//!   (default: the first listed key)
+//!
+//! Old keys stay in the list after rotation so existing values can still be
+//! decrypted.
+//!
+//! # Examples
+//! ```rust
+//! use wzs_web::config::crypto::CryptoConfig;
+//!
+//! let cfg = CryptoConfig::from_env_with(|k| match k {
+//!     "DB_ENCRYPTION_KEYS" => Some("k2:new-secret,k1:old-secret".into()),
+//!     _ => None,
+//! });
+//! assert_eq!(cfg.active_key_id.as_deref(), Some("k2"));
+//! assert_eq!(cfg.keys.len(), 2);
+//! ```
+
+use std::env as std_env;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+use crate::config::csrf::derive_secret_from_string;
+
+/// Key ring for column encryption.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CryptoConfig {
+    /// `(key_id, 32-byte key)` pairs.
+    pub keys: Vec<(String, [u8; 32])>,
+    /// Key id used to encrypt new values.
+    pub active_key_id: Option<String>,
+}
+
+impl std::fmt::Debug for CryptoConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CryptoConfig")
+            .field(
+                "key_ids",
+                &self.keys.iter().map(|(id, _)| id).collect::<Vec<_>>(),
+            )
+            .field("active_key_id", &self.active_key_id)
+            .finish()
+    }
+}
+
+impl CryptoConfig {
+    /// Loads configuration from environment variables.
+    ///
+    /// # Environment variables
+    /// - `DB_ENCRYPTION_KEYS`
+    /// - `DB_ENCRYPTION_ACTIVE_KEY`
+    pub fn from_env() -> Self {
+        Self::from_env_with(|k| std_env::var(k).ok())
+    }
+
+    /// Loads configuration using a custom key provider (for testing/mocking).
+    pub fn from_env_with<F>(get: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let keys: Vec<(String, [u8; 32])> = get("DB_ENCRYPTION_KEYS")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|pair| {
+                let (id, secret) = pair.trim().split_once(':')?;
+                let (id, secret) = (id.trim(), secret.trim());
+                if id.is_empty() || secret.is_empty() {
+                    return None;
+                }
+                Some((id.to_string(), parse_key(secret)))
+            })
+            .collect();
+
+        let active_key_id = get("DB_ENCRYPTION_ACTIVE_KEY")
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .or_else(|| keys.first().map(|(id, _)| id.clone()));
+
+        Self {
+            keys,
+            active_key_id,
+        }
+    }
+
+    /// Returns `true` if at least one key is configured.
+    pub fn is_valid(&self) -> bool {
+        !self.keys.is_empty()
+    }
+}
+
+/// Uses a base64-encoded 32-byte key as-is, otherwise derives one.
+fn parse_key(secret: &str) -> [u8; 32] {
+    match STANDARD.decode(secret) {
+        Ok(bytes) if bytes.len() == 32 => {
+            let mut key = [0u8; 32];
+            key.copy_from_slice(&bytes);
+            key
+        }
+        _ => derive_secret_from_string(secret),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_env_yields_no_keys() {
+        let cfg = CryptoConfig::from_env_with(|_| None);
+        assert!(!cfg.is_valid());
+        assert_eq!(cfg.active_key_id, None);
+    }
+
+    #[test]
+    fn parses_pairs_and_defaults_active_to_first() {
+        let cfg = CryptoConfig::from_env_with(|k| match k {
+            "DB_ENCRYPTION_KEYS" => Some(" k2 : two , bad, :x, k1:one ".into()),
+            _ => None,
+        });
+
+        let ids: Vec<&str> = cfg.keys.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["k2", "k1"]);
+        assert_eq!(cfg.keys[0].1, derive_secret_from_string("two"));
+        assert_eq!(cfg.active_key_id.as_deref(), Some("k2"));
+    }
+
+    #[test]
+    fn explicit_active_key_and_base64_keys() {
+        let raw = [7u8; 32];
+        let b64 = STANDARD.encode(raw);
+        let cfg = CryptoConfig::from_env_with(|k| match k {
+            "DB_ENCRYPTION_KEYS" => Some(format!("a:{b64},b:text")),
+            "DB_ENCRYPTION_ACTIVE_KEY" => Some("b".into()),
+            _ => None,
+        });
+
+        assert_eq!(cfg.keys[0].1, raw);
+        assert_eq!(cfg.active_key_id.as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn debug_does_not_print_key_material() {
+        let cfg = CryptoConfig::from_env_with(|k| match k {
+            "DB_ENCRYPTION_KEYS" => Some("k1:super-secret".into()),
+            _ => None,
+        });
+        let dbg = format!("{cfg:?}");
+        assert!(dbg.contains("k1"));
+        assert!(!dbg.contains("super-secret"));
+        assert!(!dbg.contains(&format!("{:?}", cfg.keys[0].1)));
+    }
+}
@@ -7,6 +7,15 @@
 //! - `CSRF_SECRET` — base string used to derive a 32-byte secret (if missing, random key is generated)
 //! - `CSRF_COOKIE_SECURE` — enables `Secure` cookie flag (default: `true`)
 //! - `CSRF_COOKIE_HTTPONLY` — enables `HttpOnly` cookie flag (default: `true`)
+//! - `CSRF_ROTATE_ON_STATE_CHANGE` — rotate the token after successful
+//!   state-changing requests (default: `false`)
+//! - `CSRF_ROTATE_INTERVAL_SECS` — rotate tokens older than this (default: never)
+//! - `CSRF_TOKEN_TTL` — maximum token age in seconds; older tokens are
+//!   rejected (default: tokens never expire)
+//! - `CSRF_ACCEPT_LEGACY_TOKENS` — keep accepting untimed tokens while a TTL
+//!   is set, for rolling out the TTL (default: `false`)
+//! - `CSRF_CHECK_ORIGIN` — also require the `Origin` (or `Referer`) header
+//!   to match the request host or one of `CORS_ORIGINS` (default: `false`)
 //!
 //! # Examples
 //! ```rust
@@ -18,10 +27,14 @@
 //! ```
 
 use std::env as std_env;
+use std::time::Duration;
 
+use axum_extra::extract::cookie::SameSite;
 use rand::RngCore;
 use sha2::{Digest, Sha256};
 
+use crate::config::web::CorsConfig;
+
 /// Configuration for CSRF protection.
 ///
 /// Controls secret key generation and cookie security flags.
@@ -39,6 +52,38 @@ pub struct CsrfConfig {
     pub secret: [u8; 32],
     pub cookie_secure: bool,
     pub cookie_http_only: bool,
+    pub rotation: CsrfRotation,
+    /// Maximum age of a token; `None` accepts tokens of any age.
+    ///
+    /// Only timed (`v2`) tokens carry an issue time, so untimed (`v1`) ones
+    /// are rejected while this is set, unless
+    /// [`accept_legacy_tokens`](Self::accept_legacy_tokens); see
+    /// [`verify_token`](crate::web::csrf::verify_token).
+    pub token_ttl: Option<Duration>,
+    /// Origins accepted by the `Origin` / `Referer` check (besides the
+    /// request's own host); `None` disables the check.
+    ///
+    /// See [`origin_allowed`](crate::web::csrf::origin_allowed).
+    pub allowed_origins: Option<Vec<String>>,
+    /// `SameSite` attribute of the CSRF cookie (default `Lax`).
+    pub cookie_same_site: SameSite,
+    /// Accept untimed (`v1`) tokens while [`token_ttl`](Self::token_ttl) is
+    /// set — a grace switch for enabling the TTL without failing forms
+    /// rendered before it. [`csrf_handler`](crate::web::csrf::csrf_handler)
+    /// still replaces them with timed tokens.
+    pub accept_legacy_tokens: bool,
+}
+
+/// When CSRF tokens are replaced (see [`csrf_handler`](crate::web::csrf::csrf_handler)
+/// and [`rotate_csrf_token`](crate::web::csrf::rotate_csrf_token)).
+///
+/// The default never rotates: a valid token is reused for the cookie's lifetime.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CsrfRotation {
+    /// Issue a new token after each successful state-changing request.
+    pub on_state_change: bool,
+    /// Issue a new token once the current one is this old.
+    pub interval: Option<Duration>,
 }
 
 impl CsrfConfig {
@@ -48,6 +93,11 @@ impl CsrfConfig {
     /// - `CSRF_SECRET`
     /// - `CSRF_COOKIE_SECURE`
     /// - `CSRF_COOKIE_HTTPONLY`
+    /// - `CSRF_ROTATE_ON_STATE_CHANGE`
+    /// - `CSRF_ROTATE_INTERVAL_SECS`
+    /// - `CSRF_TOKEN_TTL`
+    /// - `CSRF_ACCEPT_LEGACY_TOKENS`
+    /// - `CSRF_CHECK_ORIGIN` (with `CORS_ORIGINS`)
     pub fn from_env() -> Self {
         Self::from_env_with(|k| std_env::var(k).ok())
     }
@@ -70,14 +120,46 @@ impl CsrfConfig {
             .as_deref()
             .map(is_truthy)
             .unwrap_or(true);
+        let rotation = CsrfRotation {
+            on_state_change: get("CSRF_ROTATE_ON_STATE_CHANGE")
+                .as_deref()
+                .is_some_and(is_truthy),
+            interval: get("CSRF_ROTATE_INTERVAL_SECS")
+                .and_then(|s| s.trim().parse::<u64>().ok())
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs),
+        };
+        let token_ttl = get("CSRF_TOKEN_TTL")
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .filter(|&secs| secs > 0)
+            .map(Duration::from_secs);
+        let accept_legacy_tokens = get("CSRF_ACCEPT_LEGACY_TOKENS")
+            .as_deref()
+            .is_some_and(is_truthy);
+        let allowed_origins = get("CSRF_CHECK_ORIGIN")
+            .as_deref()
+            .is_some_and(is_truthy)
+            .then(|| parse_origins(&get("CORS_ORIGINS").unwrap_or_default()));
 
         Self {
             secret,
             cookie_secure,
             cookie_http_only,
+            rotation,
+            token_ttl,
+            allowed_origins,
+            cookie_same_site: SameSite::Lax,
+            accept_legacy_tokens,
         }
     }
 
+    /// Enables the `Origin` / `Referer` check, accepting the origins allowed
+    /// by `cors` (comma-separated [`CorsConfig::env`]).
+    pub fn check_origin(mut self, cors: &CorsConfig) -> Self {
+        self.allowed_origins = Some(parse_origins(&cors.env));
+        self
+    }
+
     /// Returns `true` if CSRF protection should be active.
     ///
     /// By default, CSRF is considered **enabled** if `CSRF_SECRET`
@@ -88,6 +170,14 @@ impl CsrfConfig {
     }
 }
 
+/// Splits a comma-separated origin list, normalized for comparison.
+pub(crate) fn parse_origins(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|s| s.trim().trim_end_matches('/').to_ascii_lowercase())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
 /// Returns `true` if a string represents a truthy value.
 ///
 /// Accepts (case-insensitive): `"1"`, `"true"`, `"yes"`, `"on"`.
@@ -146,6 +236,68 @@ mod tests {
 
         assert!(!cfg.cookie_secure);
         assert!(!cfg.cookie_http_only);
+        assert_eq!(cfg.rotation, CsrfRotation::default());
+    }
+
+    #[test]
+    fn from_env_with_reads_rotation_policy() {
+        let cfg = CsrfConfig::from_env_with(|k| match k {
+            "CSRF_ROTATE_ON_STATE_CHANGE" => Some("yes".into()),
+            "CSRF_ROTATE_INTERVAL_SECS" => Some("3600".into()),
+            _ => None,
+        });
+        assert!(cfg.rotation.on_state_change);
+        assert_eq!(cfg.rotation.interval, Some(Duration::from_secs(3600)));
+
+        let cfg =
+            CsrfConfig::from_env_with(|k| (k == "CSRF_ROTATE_INTERVAL_SECS").then(|| "0".into()));
+        assert_eq!(cfg.rotation.interval, None);
+    }
+
+    #[test]
+    fn from_env_with_reads_token_ttl() {
+        assert_eq!(CsrfConfig::from_env_with(|_| None).token_ttl, None);
+        let cfg = CsrfConfig::from_env_with(|k| (k == "CSRF_TOKEN_TTL").then(|| "7200".into()));
+        assert_eq!(cfg.token_ttl, Some(Duration::from_secs(7200)));
+        let cfg = CsrfConfig::from_env_with(|k| (k == "CSRF_TOKEN_TTL").then(|| "soon".into()));
+        assert_eq!(cfg.token_ttl, None);
+    }
+
+    #[test]
+    fn from_env_with_reads_legacy_token_switch() {
+        assert!(!CsrfConfig::from_env_with(|_| None).accept_legacy_tokens);
+        let cfg =
+            CsrfConfig::from_env_with(|k| (k == "CSRF_ACCEPT_LEGACY_TOKENS").then(|| "1".into()));
+        assert!(cfg.accept_legacy_tokens);
+    }
+
+    #[test]
+    fn from_env_with_reads_origin_check() {
+        assert_eq!(CsrfConfig::from_env_with(|_| None).allowed_origins, None);
+
+        let cfg = CsrfConfig::from_env_with(|k| match k {
+            "CSRF_CHECK_ORIGIN" => Some("true".into()),
+            "CORS_ORIGINS" => Some("https://App.example.com/, http://localhost:5173".into()),
+            _ => None,
+        });
+        assert_eq!(
+            cfg.allowed_origins,
+            Some(vec![
+                "https://app.example.com".to_string(),
+                "http://localhost:5173".to_string()
+            ])
+        );
+
+        let cors = CorsConfig {
+            enabled: true,
+            env: "https://a.example.com".into(),
+            credentials: true,
+        };
+        let cfg = CsrfConfig::from_env_with(|_| None).check_origin(&cors);
+        assert_eq!(
+            cfg.allowed_origins,
+            Some(vec!["https://a.example.com".to_string()])
+        );
     }
 
     #[test]
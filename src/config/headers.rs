@@ -0,0 +1,111 @@
+//! # Response Header Policy Configuration
+//!
+//! Settings for the default [`HeaderRules`](crate::web::headers::HeaderRules)
+//! built by [`HeaderRules::from_config`](crate::web::headers::HeaderRules::from_config).
+//!
+//! | Variable | Default | Meaning |
+//! |----------|---------|---------|
+//! | `HEADERS_STATIC_PREFIX` | `/static` | Paths served with a public cache policy |
+//! | `HEADERS_STATIC_MAX_AGE` | `3600` | `max-age` (seconds) for static paths |
+//! | `HEADERS_API_PREFIX` | `/api` | Paths answered with `Cache-Control: no-store` |
+//! | `HEADERS_EMBED_PREFIX` | `/embed` | Paths that may be framed |
+//! | `HEADERS_FRAME_ANCESTORS` | *(empty)* | Comma-separated origins allowed to frame embed paths |
+//!
+//! An empty prefix disables its rule. Without frame ancestors, embed paths
+//! may only be framed by the same origin (`'self'`).
+//!
+//! # Example
+//! ```rust
+//! use wzs_web::config::headers::HeaderPolicyConfig;
+//!
+//! let cfg = HeaderPolicyConfig::from_env_with(|k| match k {
+//!     "HEADERS_STATIC_MAX_AGE" => Some("86400".into()),
+//!     "HEADERS_FRAME_ANCESTORS" => Some("https://portal.example".into()),
+//!     _ => None,
+//! });
+//!
+//! assert_eq!(cfg.static_prefix, "/static");
+//! assert_eq!(cfg.static_max_age, 86400);
+//! assert_eq!(cfg.frame_ancestors, ["https://portal.example"]);
+//! ```
+
+use std::env;
+
+use crate::config::csrf::parse_origins;
+
+/// Prefixes and values of the default header rules.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeaderPolicyConfig {
+    pub static_prefix: String,
+    pub static_max_age: u64,
+    pub api_prefix: String,
+    pub embed_prefix: String,
+    /// Normalized origins (`scheme://host[:port]`, lowercase).
+    pub frame_ancestors: Vec<String>,
+}
+
+impl Default for HeaderPolicyConfig {
+    fn default() -> Self {
+        Self {
+            static_prefix: "/static".into(),
+            static_max_age: 3600,
+            api_prefix: "/api".into(),
+            embed_prefix: "/embed".into(),
+            frame_ancestors: Vec::new(),
+        }
+    }
+}
+
+impl HeaderPolicyConfig {
+    /// Loads configuration from the `HEADERS_*` variables.
+    pub fn from_env() -> Self {
+        Self::from_env_with(|k| env::var(k).ok())
+    }
+
+    /// Loads configuration using a custom key provider (for testing/mocking).
+    pub fn from_env_with<F>(get: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let d = Self::default();
+        let prefix = |key: &str, default: String| match get(key) {
+            Some(v) => v.trim().to_string(),
+            None => default,
+        };
+        Self {
+            static_prefix: prefix("HEADERS_STATIC_PREFIX", d.static_prefix),
+            static_max_age: get("HEADERS_STATIC_MAX_AGE")
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(d.static_max_age),
+            api_prefix: prefix("HEADERS_API_PREFIX", d.api_prefix),
+            embed_prefix: prefix("HEADERS_EMBED_PREFIX", d.embed_prefix),
+            frame_ancestors: parse_origins(&get("HEADERS_FRAME_ANCESTORS").unwrap_or_default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_and_overrides() {
+        assert_eq!(
+            HeaderPolicyConfig::from_env_with(|_| None),
+            HeaderPolicyConfig::default()
+        );
+
+        let cfg = HeaderPolicyConfig::from_env_with(|k| match k {
+            "HEADERS_API_PREFIX" => Some("".into()),
+            "HEADERS_STATIC_MAX_AGE" => Some("soon".into()),
+            "HEADERS_FRAME_ANCESTORS" => Some("https://A.example/, https://b.example".into()),
+            _ => None,
+        });
+        assert_eq!(cfg.api_prefix, "");
+        assert_eq!(cfg.static_max_age, 3600);
+        assert_eq!(
+            cfg.frame_ancestors,
+            ["https://a.example", "https://b.example"]
+        );
+    }
+}
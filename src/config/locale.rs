@@ -0,0 +1,88 @@
+//! # Locale Configuration
+//!
+//! Application default locale and the locales it has translations for.
+//! Used by the [`Locale`](crate::web::locale::Locale) extractor to settle
+//! `Accept-Language` negotiation.
+//!
+//! | Variable | Default | Meaning |
+//! |----------|---------|---------|
+//! | `APP_DEFAULT_LOCALE` | `en` | Locale used when nothing requested is supported |
+//! | `APP_LOCALES` | *(empty)* | Comma-separated supported locales; empty accepts any |
+//!
+//! # Example
+//! ```rust
+//! use wzs_web::config::locale::LocaleConfig;
+//!
+//! let cfg = LocaleConfig::from_env_with(|k| match k {
+//!     "APP_DEFAULT_LOCALE" => Some("ja".into()),
+//!     "APP_LOCALES" => Some("ja, en".into()),
+//!     _ => None,
+//! });
+//! assert_eq!(cfg.default, "ja");
+//! assert_eq!(cfg.supported, ["ja", "en"]);
+//! ```
+
+use std::env;
+
+/// Default and supported locales (BCP 47 tags).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocaleConfig {
+    /// Locale used when negotiation finds no match.
+    pub default: String,
+    /// Locales the application can render; empty means any requested tag
+    /// is accepted.
+    pub supported: Vec<String>,
+}
+
+impl Default for LocaleConfig {
+    fn default() -> Self {
+        Self {
+            default: "en".into(),
+            supported: Vec::new(),
+        }
+    }
+}
+
+impl LocaleConfig {
+    /// Builds a [`LocaleConfig`] from environment variables.
+    pub fn from_env() -> Self {
+        Self::from_env_with(|k| env::var(k).ok())
+    }
+
+    /// Same as [`from_env`](Self::from_env) with a custom provider.
+    pub fn from_env_with<F>(get: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let default = get("APP_DEFAULT_LOCALE")
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| Self::default().default);
+        let supported = get("APP_LOCALES")
+            .map(|s| {
+                s.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { default, supported }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_to_english_and_any_locale() {
+        let cfg = LocaleConfig::from_env_with(|_| None);
+        assert_eq!(cfg, LocaleConfig::default());
+        assert_eq!(cfg.default, "en");
+        assert!(cfg.supported.is_empty());
+
+        let cfg = LocaleConfig::from_env_with(|k| (k == "APP_DEFAULT_LOCALE").then(|| " ".into()));
+        assert_eq!(cfg.default, "en");
+    }
+}
@@ -1,6 +1,11 @@
 use std::env;
+use std::time::Duration;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use lettre::message::Mailbox;
+
+use crate::config::env::read_flag_from;
+use crate::notification::smtp::smtp_email_sender::SmtpEmailSender;
 
 /// Configuration struct for sending emails.
 ///
@@ -16,6 +21,8 @@ use anyhow::{Context, Result};
 /// ## Optional
 /// - `SMTP_FROM_NAME` (default: `"Notifier"`)
 /// - `NOTIFY_TO_EMAIL`
+/// - `DKIM_SELECTORS` — comma-separated DKIM selectors checked by
+///   [`DnsPreflight`](crate::notification::dns::DnsPreflight)
 ///
 /// ### `NOTIFY_TO_EMAIL` format
 ///
@@ -30,6 +37,15 @@ use anyhow::{Context, Result};
 ///   ```
 ///
 /// Whitespace around addresses is trimmed, and empty entries are ignored.
+/// Entries may carry a display name (`Ops <ops@example.com>`).
+///
+/// ## Validation
+///
+/// [`from_env`](Self::from_env) also checks that the sender and every
+/// notification address parse as a [`Mailbox`], reporting all invalid
+/// addresses in one error. [`mailboxes`](Self::mailboxes) returns the parsed
+/// values and [`build_sender`](Self::build_sender) turns the config into an
+/// [`SmtpEmailSender`].
 #[derive(Clone, Debug)]
 pub struct MailConfig {
     /// SMTP server host name or IP address
@@ -54,6 +70,9 @@ pub struct MailConfig {
     ///
     /// When empty, no explicit notification recipient is configured.
     pub notify_to: Vec<String>,
+
+    /// DKIM selectors published for the sender domain (0 or more)
+    pub dkim_selectors: Vec<String>,
 }
 
 impl MailConfig {
@@ -62,6 +81,8 @@ impl MailConfig {
     /// # Errors
     /// - When a required environment variable is missing
     /// - When `SMTP_PORT` cannot be parsed as a number
+    /// - When `SMTP_FROM_EMAIL` / `SMTP_FROM_NAME` or any `NOTIFY_TO_EMAIL`
+    ///   entry is not a valid mailbox
     pub fn from_env() -> Result<Self> {
         let host = env::var("SMTP_HOST").context("SMTP_HOST not set")?;
         let port: u16 = env::var("SMTP_PORT")
@@ -77,10 +98,15 @@ impl MailConfig {
 
         let notify_to = env::var("NOTIFY_TO_EMAIL")
             .ok()
-            .map(parse_notify_to)
+            .map(parse_list)
+            .unwrap_or_default();
+
+        let dkim_selectors = env::var("DKIM_SELECTORS")
+            .ok()
+            .map(parse_list)
             .unwrap_or_default();
 
-        Ok(Self {
+        let config = Self {
             host,
             port,
             username,
@@ -88,16 +114,164 @@ impl MailConfig {
             from_email,
             from_name,
             notify_to,
-        })
+            dkim_selectors,
+        };
+        config.mailboxes()?;
+        Ok(config)
     }
+
+    /// Parses the sender and notification recipients.
+    ///
+    /// # Errors
+    /// One error listing every invalid address.
+    pub fn mailboxes(&self) -> Result<MailAddresses> {
+        let mut problems = Vec::new();
+
+        let from = match self.from_email.trim().parse() {
+            Ok(email) => Some(Mailbox::new(Some(self.from_name.clone()), email)),
+            Err(e) => {
+                problems.push(format!("SMTP_FROM_EMAIL {:?}: {e}", self.from_email));
+                None
+            }
+        };
+
+        let mut notify_to = Vec::with_capacity(self.notify_to.len());
+        for addr in &self.notify_to {
+            match addr.parse::<Mailbox>() {
+                Ok(mailbox) => notify_to.push(mailbox),
+                Err(e) => problems.push(format!("NOTIFY_TO_EMAIL {addr:?}: {e}")),
+            }
+        }
+
+        match from {
+            Some(from) if problems.is_empty() => Ok(MailAddresses { from, notify_to }),
+            _ => bail!("invalid mail address(es): {}", problems.join("; ")),
+        }
+    }
+
+    /// Builds an [`SmtpEmailSender`] sending from the configured sender,
+    /// with the notification recipients as its default recipients.
+    ///
+    /// Default timeouts apply; chain
+    /// [`resilience`](SmtpEmailSender::resilience) to change them.
+    pub fn build_sender(&self) -> Result<SmtpEmailSender> {
+        let addresses = self.mailboxes()?;
+        SmtpEmailSender::new(
+            &self.host,
+            self.port,
+            &self.username,
+            &self.password,
+            addresses.from.email.as_ref(),
+            &self.from_name,
+            addresses.notify_to,
+        )
+    }
+}
+
+/// Validated addresses from a [`MailConfig`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MailAddresses {
+    /// Sender (`SMTP_FROM_NAME <SMTP_FROM_EMAIL>`).
+    pub from: Mailbox,
+    /// Notification recipients (`NOTIFY_TO_EMAIL`).
+    pub notify_to: Vec<Mailbox>,
 }
 
-/// Parse NOTIFY_TO_EMAIL value into a list of email strings.
+/// Timeouts and circuit breaker settings for
+/// [`SmtpEmailSender`](crate::notification::smtp::smtp_email_sender::SmtpEmailSender).
+///
+/// Reads from environment variables:
+/// - `SMTP_CONNECT_TIMEOUT_MS` — connect / per-command timeout (default `10000`)
+/// - `SMTP_SEND_TIMEOUT_MS` — upper bound for one whole send (default `30000`)
+/// - `SMTP_BREAKER_THRESHOLD` — consecutive failures that open the breaker
+///   (default `5`, `0` disables it)
+/// - `SMTP_BREAKER_COOLDOWN_MS` — time the breaker stays open before a
+///   half-open probe is let through (default `30000`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SmtpResilienceConfig {
+    pub connect_timeout: Duration,
+    pub send_timeout: Duration,
+    pub breaker_threshold: u32,
+    pub breaker_cooldown: Duration,
+}
+
+impl Default for SmtpResilienceConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            send_timeout: Duration::from_secs(30),
+            breaker_threshold: 5,
+            breaker_cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+impl SmtpResilienceConfig {
+    /// Builds a [`SmtpResilienceConfig`] from environment variables.
+    pub fn from_env() -> Self {
+        Self::from_env_with(|k| env::var(k).ok())
+    }
+
+    /// Same as [`from_env`](Self::from_env) with a custom provider.
+    ///
+    /// Unparsable values fall back to the defaults.
+    pub fn from_env_with<F>(get: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let d = Self::default();
+        let num = |k: &str| get(k).and_then(|s| s.trim().parse::<u64>().ok());
+        let ms = |k: &str, default: Duration| num(k).map_or(default, Duration::from_millis);
+        Self {
+            connect_timeout: ms("SMTP_CONNECT_TIMEOUT_MS", d.connect_timeout),
+            send_timeout: ms("SMTP_SEND_TIMEOUT_MS", d.send_timeout),
+            breaker_threshold: num("SMTP_BREAKER_THRESHOLD")
+                .map_or(d.breaker_threshold, |n| n.min(u32::MAX as u64) as u32),
+            breaker_cooldown: ms("SMTP_BREAKER_COOLDOWN_MS", d.breaker_cooldown),
+        }
+    }
+}
+
+/// Privacy switches for email open / click tracking
+/// (see [`EmailTracker`](crate::notification::tracking::EmailTracker)).
+///
+/// Everything is off by default. Reads from environment variables:
+/// - `EMAIL_TRACK_OPENS` — embed a tracking pixel and record opens
+/// - `EMAIL_TRACK_CLICKS` — route links through the click redirect and record clicks
+/// - `EMAIL_TRACK_RECIPIENTS` — include the recipient address in tracking
+///   tokens and events; when off, only per-message counts are possible
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EmailTrackingConfig {
+    pub opens: bool,
+    pub clicks: bool,
+    pub identify_recipients: bool,
+}
+
+impl EmailTrackingConfig {
+    /// Builds an [`EmailTrackingConfig`] from environment variables.
+    pub fn from_env() -> Self {
+        Self::from_env_with(|k| env::var(k).ok())
+    }
+
+    /// Same as [`from_env`](Self::from_env) with a custom provider.
+    pub fn from_env_with<F>(get: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        Self {
+            opens: read_flag_from(&get, "EMAIL_TRACK_OPENS", false),
+            clicks: read_flag_from(&get, "EMAIL_TRACK_CLICKS", false),
+            identify_recipients: read_flag_from(&get, "EMAIL_TRACK_RECIPIENTS", false),
+        }
+    }
+}
+
+/// Parse a comma-separated value (`NOTIFY_TO_EMAIL`, `DKIM_SELECTORS`) into a list.
 ///
 /// - Splits by comma
 /// - Trims whitespace
 /// - Filters out empty entries
-fn parse_notify_to(value: String) -> Vec<String> {
+fn parse_list(value: String) -> Vec<String> {
     value
         .split(',')
         .map(|s| s.trim())
@@ -246,4 +420,105 @@ mod tests {
             },
         );
     }
+
+    fn config(from_email: &str, notify_to: &[&str]) -> MailConfig {
+        MailConfig {
+            host: "smtp.example.com".into(),
+            port: 587,
+            username: "user".into(),
+            password: "pass".into(),
+            from_email: from_email.into(),
+            from_name: "Notifier".into(),
+            notify_to: notify_to.iter().map(|s| s.to_string()).collect(),
+            dkim_selectors: vec![],
+        }
+    }
+
+    #[test]
+    fn mailboxes_parses_sender_and_recipients() {
+        let addrs = config(
+            "noreply@example.com",
+            &["a@example.com", "Ops <ops@example.com>"],
+        )
+        .mailboxes()
+        .unwrap();
+        assert_eq!(addrs.from.to_string(), "Notifier <noreply@example.com>");
+        assert_eq!(addrs.notify_to.len(), 2);
+        assert_eq!(addrs.notify_to[1].name.as_deref(), Some("Ops"));
+    }
+
+    #[test]
+    fn mailboxes_reports_every_invalid_address() {
+        let err = config("not-an-email", &["ok@example.com", "bad", "worse@"])
+            .mailboxes()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("SMTP_FROM_EMAIL \"not-an-email\""), "{err}");
+        assert!(err.contains("NOTIFY_TO_EMAIL \"bad\""), "{err}");
+        assert!(err.contains("NOTIFY_TO_EMAIL \"worse@\""), "{err}");
+        assert!(!err.contains("ok@example.com"), "{err}");
+    }
+
+    #[test]
+    fn test_from_env_rejects_invalid_notify_to() {
+        temp_env::with_vars(
+            vec![
+                ("SMTP_HOST", Some("smtp.example.com")),
+                ("SMTP_PORT", Some("587")),
+                ("SMTP_USERNAME", Some("user")),
+                ("SMTP_PASSWORD", Some("pass")),
+                ("SMTP_FROM_EMAIL", Some("noreply@example.com")),
+                ("NOTIFY_TO_EMAIL", Some("notify@example.com,oops")),
+            ],
+            || {
+                let msg = MailConfig::from_env().unwrap_err().to_string();
+                assert!(msg.contains("NOTIFY_TO_EMAIL \"oops\""), "{msg}");
+            },
+        );
+    }
+
+    #[tokio::test]
+    async fn build_sender_uses_validated_addresses() {
+        assert!(config("noreply@example.com", &["a@example.com"])
+            .build_sender()
+            .is_ok());
+        assert!(config("nope", &[]).build_sender().is_err());
+    }
+
+    #[test]
+    fn resilience_config_reads_env_with_defaults() {
+        assert_eq!(
+            SmtpResilienceConfig::from_env_with(|_| None),
+            SmtpResilienceConfig::default()
+        );
+
+        let cfg = SmtpResilienceConfig::from_env_with(|k| match k {
+            "SMTP_CONNECT_TIMEOUT_MS" => Some("2500".into()),
+            "SMTP_SEND_TIMEOUT_MS" => Some("oops".into()),
+            "SMTP_BREAKER_THRESHOLD" => Some("0".into()),
+            "SMTP_BREAKER_COOLDOWN_MS" => Some(" 1000 ".into()),
+            _ => None,
+        });
+        assert_eq!(cfg.connect_timeout, Duration::from_millis(2500));
+        assert_eq!(cfg.send_timeout, Duration::from_secs(30));
+        assert_eq!(cfg.breaker_threshold, 0);
+        assert_eq!(cfg.breaker_cooldown, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn tracking_config_is_off_unless_enabled() {
+        assert_eq!(
+            EmailTrackingConfig::from_env_with(|_| None),
+            EmailTrackingConfig::default()
+        );
+
+        let cfg = EmailTrackingConfig::from_env_with(|k| match k {
+            "EMAIL_TRACK_CLICKS" => Some("true".into()),
+            "EMAIL_TRACK_RECIPIENTS" => Some("no".into()),
+            _ => None,
+        });
+        assert!(!cfg.opens);
+        assert!(cfg.clicks);
+        assert!(!cfg.identify_recipients);
+    }
 }
@@ -2,29 +2,84 @@
 //!
 //! Provides configuration parameters for file and image uploads.
 //!
-//! Defines the root upload directory and separate subdirectories for
-//! images and general files.
+//! Defines the root upload directory, separate subdirectories for
+//! images and general files, and the [`KeyScheme`] naming stored files.
 //!
 //! Typically used by file storage or upload service layers
 //! (e.g. local filesystem or S3-compatible adapters).
 //!
 //! # Example
 //! ```rust
-//! use wzs_web::config::upload::UploadConfig;
+//! use wzs_web::config::upload::{KeyScheme, UploadConfig};
 //! use std::path::PathBuf;
 //!
 //! let cfg = UploadConfig {
 //!     root: PathBuf::from("/var/www/uploads"),
 //!     image_dir: "images".into(),
 //!     file_dir: "files".into(),
+//!     key_scheme: "content-hash".parse().unwrap(),
 //! };
 //!
 //! assert_eq!(cfg.root, PathBuf::from("/var/www/uploads"));
 //! assert_eq!(cfg.image_dir, "images");
 //! assert_eq!(cfg.file_dir, "files");
+//! assert_eq!(cfg.key_scheme, KeyScheme::ContentHash);
 //! ```
 
+use std::fmt;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+
+/// How storage keys for uploads are named
+/// (see [`keys`](crate::web::upload::keys) for the implementations).
+///
+/// String forms (`UPLOAD_KEY_SCHEME`): `dated`, `content-hash`, `tenant`, `slug`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyScheme {
+    /// `dir/YYYYMM/<uuid>.<ext>` (files keep their sanitized name).
+    #[default]
+    Dated,
+    /// `dir/<ab>/<sha256>.<ext>`: identical content gets the same key.
+    ContentHash,
+    /// `<tenant>/dir/YYYYMM/...`: [`Dated`](Self::Dated) under the tenant id.
+    TenantPrefixed,
+    /// `dir/YYYYMM/<name-slug>-<random>.<ext>`: readable URLs.
+    NameSlug,
+}
+
+impl KeyScheme {
+    /// Returns the string form.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Dated => "dated",
+            Self::ContentHash => "content-hash",
+            Self::TenantPrefixed => "tenant",
+            Self::NameSlug => "slug",
+        }
+    }
+}
+
+impl fmt::Display for KeyScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for KeyScheme {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "dated" => Ok(Self::Dated),
+            "content-hash" | "hash" => Ok(Self::ContentHash),
+            "tenant" | "tenant-prefixed" => Ok(Self::TenantPrefixed),
+            "slug" | "name-slug" => Ok(Self::NameSlug),
+            _ => bail!("unsupported upload key scheme: {s}"),
+        }
+    }
+}
 
 /// Configuration for upload directories.
 ///
@@ -37,6 +92,8 @@ pub struct UploadConfig {
     pub image_dir: String,
     /// Subdirectory for non-processed file uploads.
     pub file_dir: String,
+    /// Naming scheme for storage keys.
+    pub key_scheme: KeyScheme,
 }
 
 impl UploadConfig {
@@ -50,9 +107,16 @@ impl UploadConfig {
             root: root.into(),
             image_dir: image_dir.into(),
             file_dir: file_dir.into(),
+            key_scheme: KeyScheme::default(),
         }
     }
 
+    /// Sets the naming scheme for storage keys.
+    pub fn with_key_scheme(mut self, scheme: KeyScheme) -> Self {
+        self.key_scheme = scheme;
+        self
+    }
+
     /// Returns the upload root directory.
     pub fn root(&self) -> &Path {
         &self.root
@@ -75,6 +139,7 @@ impl Default for UploadConfig {
             root: PathBuf::from("./uploads"),
             image_dir: "images".into(),
             file_dir: "files".into(),
+            key_scheme: KeyScheme::default(),
         }
     }
 }
@@ -90,6 +155,7 @@ mod tests {
             root: PathBuf::from("/tmp/uploads"),
             image_dir: "imgs".into(),
             file_dir: "docs".into(),
+            key_scheme: KeyScheme::Dated,
         };
 
         assert_eq!(cfg.root, PathBuf::from("/tmp/uploads"));
@@ -130,6 +196,7 @@ mod tests {
             root: PathBuf::from("./var/uploads"),
             image_dir: "images".into(),
             file_dir: "files".into(),
+            key_scheme: KeyScheme::Dated,
         };
 
         let clone = cfg.clone();
@@ -147,19 +214,43 @@ mod tests {
             root: PathBuf::from("/data"),
             image_dir: "img".into(),
             file_dir: "f".into(),
+            key_scheme: KeyScheme::Dated,
         };
         let cfg2 = UploadConfig {
             root: PathBuf::from("/data"),
             image_dir: "img".into(),
             file_dir: "f".into(),
+            key_scheme: KeyScheme::Dated,
         };
         let cfg3 = UploadConfig {
             root: PathBuf::from("/data2"),
             image_dir: "imagez".into(),
             file_dir: "filez".into(),
+            key_scheme: KeyScheme::Dated,
         };
 
         assert_eq!(cfg1, cfg2);
         assert_ne!(cfg1, cfg3);
     }
+
+    #[test]
+    fn key_scheme_parses_and_round_trips() {
+        for scheme in [
+            KeyScheme::Dated,
+            KeyScheme::ContentHash,
+            KeyScheme::TenantPrefixed,
+            KeyScheme::NameSlug,
+        ] {
+            assert_eq!(scheme.to_string().parse::<KeyScheme>().unwrap(), scheme);
+        }
+        assert_eq!(
+            " Hash ".parse::<KeyScheme>().unwrap(),
+            KeyScheme::ContentHash
+        );
+        assert!("random".parse::<KeyScheme>().is_err());
+
+        let cfg = UploadConfig::new("/u", "i", "f").with_key_scheme(KeyScheme::NameSlug);
+        assert_eq!(cfg.key_scheme, KeyScheme::NameSlug);
+        assert_eq!(UploadConfig::default().key_scheme, KeyScheme::Dated);
+    }
 }
@@ -1,3 +1,19 @@
+pub mod async_adapter;
+pub mod async_port;
+pub mod blob;
+pub mod breaker;
 pub mod connection;
+pub mod context;
+pub mod crypto;
+pub mod decimal;
+pub mod drain;
+pub mod explain;
+pub mod migrate;
 pub mod mysql_adapter;
+pub mod observer;
 pub mod port;
+pub mod query;
+pub mod repository;
+pub mod router;
+pub mod seed;
+pub mod sql_log;
@@ -0,0 +1,173 @@
+//! # Blocking-Pool Adapter for [`AsyncDb`]
+//!
+//! [`SpawnBlockingDb`] implements [`AsyncDb`] over any synchronous
+//! [`Db`] by moving each statement to [`tokio::task::spawn_blocking`], so
+//! handlers never block a Tokio worker thread on a MySQL round-trip.
+//!
+//! Parameters are copied into owned [`Value`]s for the hop to the blocking
+//! pool.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use wzs_web::config::db::DbConfig;
+//! use wzs_web::db::{
+//!     async_adapter::SpawnBlockingDb, async_port::AsyncDb, connection::get_pool,
+//!     mysql_adapter::MySqlDb,
+//! };
+//!
+//! let db: Arc<dyn AsyncDb> = Arc::new(SpawnBlockingDb::new(Arc::new(MySqlDb::new(
+//!     get_pool(&DbConfig::from_env()),
+//! ))));
+//! ```
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+
+use crate::db::async_port::AsyncDb;
+use crate::db::port::{Db, Param, Row, Value};
+
+/// [`AsyncDb`] over a synchronous [`Db`], using Tokio's blocking pool.
+#[derive(Clone)]
+pub struct SpawnBlockingDb {
+    inner: Arc<dyn Db>,
+}
+
+impl SpawnBlockingDb {
+    /// Wraps a synchronous database.
+    pub fn new(inner: Arc<dyn Db>) -> Self {
+        Self { inner }
+    }
+
+    /// Returns the wrapped synchronous database.
+    pub fn inner(&self) -> &Arc<dyn Db> {
+        &self.inner
+    }
+
+    async fn run<T, F>(&self, sql: &str, params: &[Param<'_>], f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn Db, &str, &[Param]) -> Result<T> + Send + 'static,
+    {
+        let inner = self.inner.clone();
+        let sql = sql.to_string();
+        let owned: Vec<Value> = params.iter().map(Param::to_value).collect();
+
+        tokio::task::spawn_blocking(move || {
+            let params: Vec<Param> = owned.iter().map(Value::as_param).collect();
+            f(inner.as_ref(), &sql, &params)
+        })
+        .await
+        .context("database task panicked or was cancelled")?
+    }
+}
+
+#[async_trait]
+impl AsyncDb for SpawnBlockingDb {
+    async fn fetch_one(&self, sql: &str, params: &[Param<'_>]) -> Result<Option<Row>> {
+        self.run(sql, params, |db, sql, ps| db.fetch_one(sql, ps))
+            .await
+    }
+
+    async fn fetch_all(&self, sql: &str, params: &[Param<'_>]) -> Result<Vec<Row>> {
+        self.run(sql, params, |db, sql, ps| db.fetch_all(sql, ps))
+            .await
+    }
+
+    async fn exec(&self, sql: &str, params: &[Param<'_>]) -> Result<u64> {
+        self.run(sql, params, |db, sql, ps| db.exec(sql, ps)).await
+    }
+
+    async fn exec_returning_last_insert_id(&self, sql: &str, params: &[Param<'_>]) -> Result<u64> {
+        self.run(sql, params, |db, sql, ps| {
+            db.exec_returning_last_insert_id(sql, ps)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::thread::{self, ThreadId};
+
+    /// Records statements, parameters and the executing thread.
+    #[derive(Default)]
+    struct RecordingDb {
+        calls: Mutex<Vec<(String, String, ThreadId)>>,
+    }
+
+    impl RecordingDb {
+        fn record(&self, sql: &str, params: &[Param]) {
+            self.calls.lock().unwrap().push((
+                sql.to_string(),
+                format!("{params:?}"),
+                thread::current().id(),
+            ));
+        }
+    }
+
+    impl Db for RecordingDb {
+        fn fetch_one(&self, sql: &str, params: &[Param]) -> Result<Option<Row>> {
+            self.record(sql, params);
+            let mut r = Row::default();
+            r.insert("name", Value::Str("alice".into()));
+            Ok(Some(r))
+        }
+
+        fn fetch_all(&self, sql: &str, params: &[Param]) -> Result<Vec<Row>> {
+            self.record(sql, params);
+            Ok(vec![Row::default(), Row::default()])
+        }
+
+        fn exec(&self, sql: &str, params: &[Param]) -> Result<u64> {
+            self.record(sql, params);
+            anyhow::bail!("boom")
+        }
+
+        fn exec_returning_last_insert_id(&self, sql: &str, params: &[Param]) -> Result<u64> {
+            self.record(sql, params);
+            Ok(42)
+        }
+    }
+
+    #[tokio::test]
+    async fn delegates_on_blocking_pool_with_same_params() {
+        let sync = Arc::new(RecordingDb::default());
+        let db: Arc<dyn AsyncDb> = Arc::new(SpawnBlockingDb::new(sync.clone()));
+        let name = String::from("alice");
+
+        let row = db
+            .fetch_one(
+                "SELECT name FROM users WHERE name = ?",
+                &[Param::Str(&name)],
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(row.get_string("name").unwrap(), "alice");
+        assert_eq!(db.fetch_all("SELECT 1", &[]).await.unwrap().len(), 2);
+        assert_eq!(
+            db.exec_returning_last_insert_id("INSERT INTO t VALUES (?)", &[Param::U64(7)])
+                .await
+                .unwrap(),
+            42
+        );
+
+        let calls = sync.calls.lock().unwrap();
+        assert_eq!(calls[0].0, "SELECT name FROM users WHERE name = ?");
+        assert_eq!(calls[0].1, r#"[Str("alice")]"#);
+        assert_eq!(calls[2].1, "[U64(7)]");
+        assert!(calls.iter().all(|c| c.2 != thread::current().id()));
+    }
+
+    #[tokio::test]
+    async fn propagates_errors() {
+        let db = SpawnBlockingDb::new(Arc::new(RecordingDb::default()));
+        let err = db.exec("DELETE FROM t", &[]).await.unwrap_err();
+        assert_eq!(err.to_string(), "boom");
+    }
+}
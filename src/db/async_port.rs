@@ -0,0 +1,79 @@
+//! # Database Port (Asynchronous)
+//!
+//! [`AsyncDb`] mirrors the synchronous [`Db`](crate::db::port::Db) port for
+//! code running on Tokio worker threads (Axum handlers, GraphQL resolvers),
+//! reusing the same [`Param`] and [`Row`] types.
+//!
+//! [`SpawnBlockingDb`](crate::db::async_adapter::SpawnBlockingDb) adapts any
+//! synchronous `Db` (such as [`MySqlDb`](crate::db::mysql_adapter::MySqlDb))
+//! by running statements on Tokio's blocking pool.
+//!
+//! # Native driver
+//! A `mysql_async`-backed adapter is not part of the crate yet: `mysql_async`
+//! is not among its dependencies, so `SpawnBlockingDb` over `MySqlDb` is
+//! the only implementation for now. It keeps worker threads free but still
+//! holds one blocking-pool thread per statement in flight. A native adapter
+//! needs nothing beyond this trait; repositories written against
+//! `Arc<dyn AsyncDb>` switch over by changing the constructor.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use wzs_web::db::async_port::AsyncDb;
+//! use wzs_web::db::port::{params, Param};
+//!
+//! async fn user_name(db: Arc<dyn AsyncDb>, id: u64) -> anyhow::Result<Option<String>> {
+//!     let row = db
+//!         .fetch_one("SELECT name FROM users WHERE id = ?", &params([Param::U64(id)]))
+//!         .await?;
+//!     row.map(|r| r.get_string("name")).transpose()
+//! }
+//! ```
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::db::port::{map_row, FromRow, Param, Row};
+
+/// Database abstraction (asynchronous).
+#[async_trait]
+pub trait AsyncDb: Send + Sync + 'static {
+    async fn fetch_one(&self, sql: &str, params: &[Param<'_>]) -> Result<Option<Row>>;
+
+    async fn fetch_all(&self, sql: &str, params: &[Param<'_>]) -> Result<Vec<Row>>;
+
+    /// Execute a write operation (`INSERT`, `UPDATE`, `DELETE`).
+    ///
+    /// Returns affected row count.
+    async fn exec(&self, sql: &str, params: &[Param<'_>]) -> Result<u64>;
+
+    /// Execute and return `LAST_INSERT_ID()` (for inserts).
+    async fn exec_returning_last_insert_id(&self, sql: &str, params: &[Param<'_>]) -> Result<u64>;
+}
+
+impl dyn AsyncDb {
+    /// [`fetch_one`](AsyncDb::fetch_one), hydrating the row as `T`.
+    pub async fn fetch_one_as<T: FromRow>(
+        &self,
+        sql: &str,
+        params: &[Param<'_>],
+    ) -> Result<Option<T>> {
+        self.fetch_one(sql, params)
+            .await?
+            .map(|row| map_row(&row))
+            .transpose()
+    }
+
+    /// [`fetch_all`](AsyncDb::fetch_all), hydrating each row as `T`.
+    pub async fn fetch_all_as<T: FromRow>(
+        &self,
+        sql: &str,
+        params: &[Param<'_>],
+    ) -> Result<Vec<T>> {
+        self.fetch_all(sql, params)
+            .await?
+            .iter()
+            .map(map_row)
+            .collect()
+    }
+}
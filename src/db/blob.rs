@@ -0,0 +1,391 @@
+//! # BLOB Streaming
+//!
+//! Reads and writes large `BLOB` columns in fixed-size chunks, so a
+//! multi-megabyte image never has to sit in memory as one `Vec<u8>`.
+//!
+//! - [`BlobColumn`] names the column (`table`, `column`, key column) and the
+//!   chunk size
+//! - [`BlobReader`] implements [`Read`] with one
+//!   `SELECT SUBSTRING(column, ?, ?)` per chunk
+//! - [`BlobWriter`] implements [`Write`], truncating the column first and
+//!   appending each chunk with `UPDATE ... SET column = CONCAT(column, ?)`
+//!
+//! Keys are bound as strings, which MySQL compares against integer key
+//! columns as well. Keep the chunk size below the server's
+//! `max_allowed_packet`.
+//!
+//! See [`blob_transfer`](crate::web::upload::blob_transfer) for copying
+//! between a column and a [`FileStorage`](crate::web::upload::storage::FileStorage).
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::io::{self, Read};
+//! use wzs_web::config::db::DbConfig;
+//! use wzs_web::db::{blob::BlobColumn, connection::get_pool, mysql_adapter::MySqlDb};
+//!
+//! # fn run() -> anyhow::Result<()> {
+//! let db = MySqlDb::new(get_pool(&DbConfig::from_env()));
+//! let images = BlobColumn::new("legacy_images", "data", "id")?;
+//!
+//! if images.len(&db, "42")?.is_some() {
+//!     let mut out = std::fs::File::create("/tmp/42.png")?;
+//!     io::copy(&mut images.reader(&db, "42"), &mut out)?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io::{self, Read, Write};
+
+use anyhow::{bail, Result};
+
+use crate::db::port::{Db, Param, Value};
+use crate::db::repository::ident;
+
+/// Default chunk size: 1 MiB.
+pub const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// A `BLOB` column addressed by a single-column key.
+#[derive(Clone, Debug)]
+pub struct BlobColumn {
+    table: String,
+    column: String,
+    key_column: String,
+    chunk_size: usize,
+}
+
+impl BlobColumn {
+    /// Creates a column handle with [`DEFAULT_CHUNK_SIZE`].
+    ///
+    /// # Errors
+    /// Returns an error if a name is not a plain SQL identifier.
+    pub fn new(
+        table: impl Into<String>,
+        column: impl Into<String>,
+        key_column: impl Into<String>,
+    ) -> Result<Self> {
+        let (table, column, key_column) = (table.into(), column.into(), key_column.into());
+        for name in [&table, &column, &key_column] {
+            ident(name)?;
+        }
+        Ok(Self {
+            table,
+            column,
+            key_column,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        })
+    }
+
+    /// Sets the number of bytes transferred per statement (at least 1).
+    pub fn chunk_size(mut self, bytes: usize) -> Self {
+        self.chunk_size = bytes.max(1);
+        self
+    }
+
+    /// The table name.
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// The `BLOB` column name.
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    /// The key column name.
+    pub fn key_column(&self) -> &str {
+        &self.key_column
+    }
+
+    /// Size of the value in bytes; `None` if the row is missing or the
+    /// value is `NULL`.
+    pub fn len(&self, db: &dyn Db, key: &str) -> Result<Option<u64>> {
+        let sql = format!(
+            "SELECT OCTET_LENGTH({}) AS len FROM {} WHERE {} = ?",
+            self.column, self.table, self.key_column
+        );
+        match db.fetch_one(&sql, &[Param::Str(key)])? {
+            Some(row) => match row.get("len") {
+                None | Some(Value::Null) => Ok(None),
+                Some(_) => Ok(Some(row.get_u64("len")?)),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Streams the value of the row `key`.
+    ///
+    /// A missing row surfaces as an [`io::ErrorKind::NotFound`] read error;
+    /// a `NULL` value reads as empty.
+    pub fn reader<'a>(&'a self, db: &'a dyn Db, key: &str) -> BlobReader<'a> {
+        BlobReader {
+            db,
+            column: self,
+            key: key.to_string(),
+            offset: 0,
+            buf: Vec::new(),
+            pos: 0,
+            done: false,
+        }
+    }
+
+    /// Empties the value of the row `key` and returns a writer appending to it.
+    ///
+    /// # Errors
+    /// Returns an error if the row does not exist.
+    pub fn writer<'a>(&'a self, db: &'a dyn Db, key: &str) -> Result<BlobWriter<'a>> {
+        let sql = format!(
+            "SELECT {key_col} FROM {} WHERE {key_col} = ?",
+            self.table,
+            key_col = self.key_column
+        );
+        if db.fetch_one(&sql, &[Param::Str(key)])?.is_none() {
+            bail!("no {} row with {} = {key}", self.table, self.key_column);
+        }
+        let sql = format!(
+            "UPDATE {} SET {} = '' WHERE {} = ?",
+            self.table, self.column, self.key_column
+        );
+        db.exec(&sql, &[Param::Str(key)])?;
+        Ok(BlobWriter {
+            db,
+            column: self,
+            key: key.to_string(),
+            buf: Vec::with_capacity(self.chunk_size),
+            written: 0,
+        })
+    }
+}
+
+/// [`Read`] over a `BLOB` value, one `SUBSTRING` query per chunk.
+pub struct BlobReader<'a> {
+    db: &'a dyn Db,
+    column: &'a BlobColumn,
+    key: String,
+    offset: u64,
+    buf: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl BlobReader<'_> {
+    fn fetch_chunk(&mut self) -> io::Result<()> {
+        let c = self.column;
+        let sql = format!(
+            "SELECT SUBSTRING({}, ?, ?) AS chunk FROM {} WHERE {} = ?",
+            c.column, c.table, c.key_column
+        );
+        let params = [
+            Param::U64(self.offset + 1),
+            Param::U64(c.chunk_size as u64),
+            Param::Str(&self.key),
+        ];
+        let Some(row) = self.db.fetch_one(&sql, &params).map_err(io::Error::other)? else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no {} row with {} = {}", c.table, c.key_column, self.key),
+            ));
+        };
+        self.buf = match row.get("chunk") {
+            Some(Value::Bin(b)) => b.clone(),
+            Some(Value::Str(s)) => s.clone().into_bytes(),
+            Some(Value::Null) | None => Vec::new(),
+            Some(other) => {
+                return Err(io::Error::other(format!(
+                    "column `{}` is not binary: {other:?}",
+                    c.column
+                )))
+            }
+        };
+        self.pos = 0;
+        self.offset += self.buf.len() as u64;
+        self.done = self.buf.len() < c.chunk_size;
+        Ok(())
+    }
+}
+
+impl Read for BlobReader<'_> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.buf.len() {
+            if self.done {
+                return Ok(0);
+            }
+            self.fetch_chunk()?;
+        }
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// [`Write`] appending to a `BLOB` value, one `CONCAT` update per chunk.
+///
+/// Call [`finish`](Self::finish) to send the last partial chunk; dropping
+/// the writer discards it.
+pub struct BlobWriter<'a> {
+    db: &'a dyn Db,
+    column: &'a BlobColumn,
+    key: String,
+    buf: Vec<u8>,
+    written: u64,
+}
+
+impl BlobWriter<'_> {
+    /// Sends any buffered bytes and returns the total written.
+    pub fn finish(mut self) -> Result<u64> {
+        self.send()?;
+        Ok(self.written)
+    }
+
+    fn send(&mut self) -> Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let c = self.column;
+        let sql = format!(
+            "UPDATE {} SET {col} = CONCAT({col}, ?) WHERE {} = ?",
+            c.table,
+            c.key_column,
+            col = c.column
+        );
+        self.db
+            .exec(&sql, &[Param::Bin(&self.buf), Param::Str(&self.key)])?;
+        self.written += self.buf.len() as u64;
+        self.buf.clear();
+        Ok(())
+    }
+}
+
+impl Write for BlobWriter<'_> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let n = data.len().min(self.column.chunk_size - self.buf.len());
+        self.buf.extend_from_slice(&data[..n]);
+        if self.buf.len() == self.column.chunk_size {
+            self.send().map_err(io::Error::other)?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send().map_err(io::Error::other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use crate::db::port::Row;
+
+    /// Holds one row (`id = "1"`) and interprets the statements issued here.
+    #[derive(Default)]
+    struct BlobDb {
+        data: Mutex<Option<Vec<u8>>>,
+        statements: Mutex<Vec<String>>,
+    }
+
+    impl Db for BlobDb {
+        fn fetch_one(&self, sql: &str, params: &[Param]) -> Result<Option<Row>> {
+            self.statements.lock().unwrap().push(sql.to_string());
+            let Some(Param::Str("1")) = params.last() else {
+                return Ok(None);
+            };
+            let data = self.data.lock().unwrap().clone();
+            let mut row = Row::default();
+            if sql.contains("OCTET_LENGTH") {
+                let len = data.map_or(Value::Null, |d| Value::I64(d.len() as i64));
+                row.insert("len", len);
+            } else if sql.contains("SUBSTRING") {
+                let (Param::U64(start), Param::U64(len)) = (&params[0], &params[1]) else {
+                    bail!("bad params");
+                };
+                let chunk = data.map_or(Value::Null, |d| {
+                    let start = (*start as usize - 1).min(d.len());
+                    let end = (start + *len as usize).min(d.len());
+                    Value::Bin(d[start..end].to_vec())
+                });
+                row.insert("chunk", chunk);
+            }
+            Ok(Some(row))
+        }
+
+        fn fetch_all(&self, _sql: &str, _params: &[Param]) -> Result<Vec<Row>> {
+            Ok(vec![])
+        }
+
+        fn exec(&self, sql: &str, params: &[Param]) -> Result<u64> {
+            self.statements.lock().unwrap().push(sql.to_string());
+            let mut data = self.data.lock().unwrap();
+            match params.first() {
+                Some(Param::Bin(b)) => data.get_or_insert_with(Vec::new).extend_from_slice(b),
+                _ => *data = Some(Vec::new()),
+            }
+            Ok(1)
+        }
+
+        fn exec_returning_last_insert_id(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    fn column() -> BlobColumn {
+        BlobColumn::new("legacy_images", "data", "id")
+            .unwrap()
+            .chunk_size(4)
+    }
+
+    #[test]
+    fn rejects_invalid_identifiers() {
+        assert!(BlobColumn::new("images; DROP", "data", "id").is_err());
+        assert!(BlobColumn::new("images", "", "id").is_err());
+    }
+
+    #[test]
+    fn reader_fetches_one_chunk_per_query() {
+        let db = BlobDb::default();
+        *db.data.lock().unwrap() = Some(b"0123456789".to_vec());
+        let col = column();
+
+        assert_eq!(col.len(&db, "1").unwrap(), Some(10));
+        assert_eq!(col.len(&db, "2").unwrap(), None);
+
+        let mut out = Vec::new();
+        col.reader(&db, "1").read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"0123456789");
+        let substrings = db
+            .statements
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| s.starts_with("SELECT SUBSTRING(data, ?, ?) AS chunk FROM legacy_images"))
+            .count();
+        assert_eq!(substrings, 3);
+
+        let err = col.reader(&db, "2").read_to_end(&mut out).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn writer_truncates_then_appends_chunks() {
+        let db = BlobDb::default();
+        *db.data.lock().unwrap() = Some(b"old".to_vec());
+        let col = column();
+
+        let mut w = col.writer(&db, "1").unwrap();
+        w.write_all(b"0123456789").unwrap();
+        assert_eq!(w.finish().unwrap(), 10);
+        assert_eq!(db.data.lock().unwrap().as_deref(), Some(&b"0123456789"[..]));
+        let concats = db
+            .statements
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| *s == "UPDATE legacy_images SET data = CONCAT(data, ?) WHERE id = ?")
+            .count();
+        assert_eq!(concats, 3);
+
+        assert!(col.writer(&db, "2").is_err());
+    }
+}
@@ -0,0 +1,232 @@
+//! # Outage Circuit Breaker
+//!
+//! [`BreakerDb`] wraps a [`Db`] with a [`CircuitBreaker`] so that, once
+//! MySQL is unreachable, statements fail immediately with [`DbUnavailable`]
+//! instead of each request waiting out a pool checkout timeout.
+//!
+//! Only connectivity failures count against the breaker (I/O errors,
+//! connect / checkout timeouts, and the server's connection limits — see
+//! [`is_outage`]). SQL errors mean the server answered, so they count as
+//! success. While the breaker is half-open a single statement probes the
+//! server; the others keep failing fast.
+//!
+//! Share the breaker with
+//! [`DegradedMode`](crate::web::middleware::degraded::DegradedMode) to serve
+//! stale reads and a uniform 503 for writes during the outage.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//! use wzs_web::config::db::DbConfig;
+//! use wzs_web::db::breaker::BreakerDb;
+//! use wzs_web::db::connection::get_pool;
+//! use wzs_web::db::mysql_adapter::MySqlDb;
+//! use wzs_web::db::port::Db;
+//! use wzs_web::notification::smtp::circuit_breaker::CircuitBreaker;
+//!
+//! let breaker = CircuitBreaker::new(5, Duration::from_secs(10));
+//! let mysql = Arc::new(MySqlDb::new(get_pool(&DbConfig::from_env())));
+//! let db: Arc<dyn Db> = Arc::new(BreakerDb::new(mysql, breaker.clone()));
+//! ```
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use thiserror::Error;
+
+use crate::db::context::DbContext;
+use crate::db::mysql_adapter::is_transient;
+use crate::db::port::{Db, Param, Row, RowStream};
+use crate::notification::smtp::circuit_breaker::CircuitBreaker;
+
+/// Returned while the breaker is open.
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+#[error("database unavailable (circuit breaker open)")]
+pub struct DbUnavailable;
+
+/// `true` if `err` means the database could not be reached.
+///
+/// Matches [`DbUnavailable`] and connectivity errors of the MySQL driver
+/// anywhere in the error chain.
+pub fn is_outage(err: &anyhow::Error) -> bool {
+    err.chain().any(|e| {
+        e.is::<DbUnavailable>() || e.downcast_ref::<mysql::Error>().is_some_and(is_transient)
+    })
+}
+
+/// [`Db`] failing fast while the database is unreachable.
+pub struct BreakerDb {
+    inner: Arc<dyn Db>,
+    breaker: CircuitBreaker,
+}
+
+impl BreakerDb {
+    /// Guards `inner` with `breaker`.
+    pub fn new(inner: Arc<dyn Db>, breaker: CircuitBreaker) -> Self {
+        Self { inner, breaker }
+    }
+
+    /// The breaker (shared with its clones).
+    pub fn breaker(&self) -> &CircuitBreaker {
+        &self.breaker
+    }
+
+    /// The wrapped database.
+    pub fn inner(&self) -> &Arc<dyn Db> {
+        &self.inner
+    }
+
+    fn call<'a, T>(&'a self, f: impl FnOnce(&'a dyn Db) -> Result<T>) -> Result<T> {
+        if !self.breaker.try_acquire() {
+            return Err(DbUnavailable.into());
+        }
+        let result = f(self.inner.as_ref());
+        match &result {
+            Err(e) if is_outage(e) => self.breaker.on_failure(),
+            _ => self.breaker.on_success(),
+        }
+        result
+    }
+}
+
+impl Db for BreakerDb {
+    fn fetch_one(&self, sql: &str, params: &[Param]) -> Result<Option<Row>> {
+        self.call(|db| db.fetch_one(sql, params))
+    }
+
+    fn fetch_all(&self, sql: &str, params: &[Param]) -> Result<Vec<Row>> {
+        self.call(|db| db.fetch_all(sql, params))
+    }
+
+    /// Guards opening the stream; errors while iterating are not counted.
+    fn fetch_stream(&self, sql: &str, params: &[Param]) -> Result<RowStream<'_>> {
+        self.call(|db| db.fetch_stream(sql, params))
+    }
+
+    fn exec(&self, sql: &str, params: &[Param]) -> Result<u64> {
+        self.call(|db| db.exec(sql, params))
+    }
+
+    fn exec_returning_last_insert_id(&self, sql: &str, params: &[Param]) -> Result<u64> {
+        self.call(|db| db.exec_returning_last_insert_id(sql, params))
+    }
+
+    fn ping(&self) -> Result<()> {
+        self.call(|db| db.ping())
+    }
+
+    fn fetch_one_with_ctx(
+        &self,
+        ctx: &DbContext,
+        sql: &str,
+        params: &[Param],
+    ) -> Result<Option<Row>> {
+        self.call(|db| db.fetch_one_with_ctx(ctx, sql, params))
+    }
+
+    fn fetch_all_with_ctx(&self, ctx: &DbContext, sql: &str, params: &[Param]) -> Result<Vec<Row>> {
+        self.call(|db| db.fetch_all_with_ctx(ctx, sql, params))
+    }
+
+    fn exec_with_ctx(&self, ctx: &DbContext, sql: &str, params: &[Param]) -> Result<u64> {
+        self.call(|db| db.exec_with_ctx(ctx, sql, params))
+    }
+
+    fn exec_returning_last_insert_id_with_ctx(
+        &self,
+        ctx: &DbContext,
+        sql: &str,
+        params: &[Param],
+    ) -> Result<u64> {
+        self.call(|db| db.exec_returning_last_insert_id_with_ctx(ctx, sql, params))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use anyhow::{bail, Context};
+
+    use crate::notification::smtp::circuit_breaker::BreakerState;
+
+    #[derive(Default)]
+    struct FlakyDb {
+        down: AtomicBool,
+        calls: AtomicUsize,
+    }
+
+    impl Db for FlakyDb {
+        fn fetch_one(&self, _sql: &str, _params: &[Param]) -> Result<Option<Row>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.down.load(Ordering::SeqCst) {
+                let io = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+                return Err(
+                    anyhow::Error::new(mysql::Error::IoError(io)).context("get_conn failed")
+                );
+            }
+            Ok(None)
+        }
+
+        fn fetch_all(&self, _sql: &str, _params: &[Param]) -> Result<Vec<Row>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            bail!("You have an error in your SQL syntax")
+        }
+
+        fn exec(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(1)
+        }
+
+        fn exec_returning_last_insert_id(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn opens_on_connectivity_errors_and_fails_fast() {
+        let inner = Arc::new(FlakyDb::default());
+        let db = BreakerDb::new(
+            inner.clone(),
+            CircuitBreaker::new(2, Duration::from_secs(60)),
+        );
+
+        // SQL errors mean the server answered.
+        for _ in 0..3 {
+            assert!(db.fetch_all("SELEKT", &[]).is_err());
+        }
+        assert_eq!(db.breaker().state(), BreakerState::Closed);
+
+        inner.down.store(true, Ordering::SeqCst);
+        for _ in 0..2 {
+            let err = db.fetch_one("SELECT 1", &[]).unwrap_err();
+            assert!(is_outage(&err));
+        }
+        assert_eq!(db.breaker().state(), BreakerState::Open);
+
+        let calls = inner.calls.load(Ordering::SeqCst);
+        let err = db.exec("UPDATE t SET a = 1", &[]).unwrap_err();
+        assert!(err.is::<DbUnavailable>());
+        assert!(is_outage(&err.context("saving")));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), calls, "not forwarded");
+    }
+
+    #[test]
+    fn successful_probe_closes_the_breaker() {
+        let inner = Arc::new(FlakyDb::default());
+        inner.down.store(true, Ordering::SeqCst);
+        let db = BreakerDb::new(inner.clone(), CircuitBreaker::new(1, Duration::ZERO));
+
+        assert!(db.fetch_one("SELECT 1", &[]).is_err());
+        inner.down.store(false, Ordering::SeqCst);
+        assert!(db.fetch_one("SELECT 1", &[]).is_ok());
+        assert_eq!(db.breaker().state(), BreakerState::Closed);
+
+        let plain = Err::<(), _>(anyhow::anyhow!("boom"))
+            .context("x")
+            .unwrap_err();
+        assert!(!is_outage(&plain));
+    }
+}
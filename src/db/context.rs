@@ -0,0 +1,165 @@
+//! # Per-Request Database Context
+//!
+//! [`DbContext`] carries request-scoped information down to the [`Db`](crate::db::port::Db)
+//! port through the `*_with_ctx` methods:
+//!
+//! - `request_id` — tagged onto every statement as an SQL comment, so slow-log
+//!   entries can be correlated with HTTP requests
+//! - `deadline` — statements are refused once it has passed; adapters may also
+//!   bound execution time (MySQL: `MAX_EXECUTION_TIME` hint on `SELECT`)
+//! - `read_only` — write methods are rejected
+//!
+//! # Example
+//! ```rust
+//! use std::time::Duration;
+//! use wzs_web::db::context::DbContext;
+//!
+//! let ctx = DbContext::new()
+//!     .request_id("req-123")
+//!     .timeout(Duration::from_secs(2))
+//!     .read_only();
+//!
+//! assert!(ctx.ensure_writable().is_err());
+//! assert_eq!(ctx.sql_comment().as_deref(), Some("/* request_id=req-123 */"));
+//! ```
+
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+
+/// Request-scoped options for database calls.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DbContext {
+    /// Correlation id added to statements as a comment.
+    pub request_id: Option<String>,
+    /// Point in time after which statements are refused.
+    pub deadline: Option<Instant>,
+    /// Rejects write statements when `true`.
+    pub read_only: bool,
+}
+
+impl DbContext {
+    /// Creates an empty context (no id, no deadline, writable).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the request id.
+    pub fn request_id(mut self, id: impl Into<String>) -> Self {
+        self.request_id = Some(id.into());
+        self
+    }
+
+    /// Sets an absolute deadline.
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Sets the deadline to now + `timeout`.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.deadline(Instant::now() + timeout)
+    }
+
+    /// Marks the context read-only.
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Time left before the deadline (`None` when no deadline is set).
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|d| d.saturating_duration_since(Instant::now()))
+    }
+
+    /// Fails if the deadline has passed.
+    pub fn ensure_not_expired(&self) -> Result<()> {
+        if self.remaining() == Some(Duration::ZERO) {
+            bail!("database deadline exceeded{}", self.id_suffix());
+        }
+        Ok(())
+    }
+
+    /// Fails if the context is read-only.
+    pub fn ensure_writable(&self) -> Result<()> {
+        if self.read_only {
+            bail!(
+                "write rejected on read-only database context{}",
+                self.id_suffix()
+            );
+        }
+        Ok(())
+    }
+
+    /// SQL comment carrying the request id, e.g. `/* request_id=abc */`.
+    ///
+    /// Characters that could terminate the comment are replaced.
+    pub fn sql_comment(&self) -> Option<String> {
+        let id = self.request_id.as_deref()?;
+        let safe: String = id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        Some(format!("/* request_id={safe} */"))
+    }
+
+    fn id_suffix(&self) -> String {
+        match &self.request_id {
+            Some(id) => format!(" (request_id={id})"),
+            None => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_context_allows_everything() {
+        let ctx = DbContext::new();
+        assert!(ctx.ensure_not_expired().is_ok());
+        assert!(ctx.ensure_writable().is_ok());
+        assert_eq!(ctx.remaining(), None);
+        assert_eq!(ctx.sql_comment(), None);
+    }
+
+    #[test]
+    fn expired_deadline_is_rejected() {
+        let ctx = DbContext::new()
+            .request_id("r1")
+            .deadline(Instant::now() - Duration::from_millis(1));
+        let err = ctx.ensure_not_expired().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("deadline exceeded (request_id=r1)"));
+    }
+
+    #[test]
+    fn future_deadline_reports_remaining_time() {
+        let ctx = DbContext::new().timeout(Duration::from_secs(60));
+        assert!(ctx.ensure_not_expired().is_ok());
+        assert!(ctx.remaining().unwrap() > Duration::from_secs(50));
+    }
+
+    #[test]
+    fn read_only_rejects_writes() {
+        assert!(DbContext::new().read_only().ensure_writable().is_err());
+    }
+
+    #[test]
+    fn sql_comment_cannot_break_out() {
+        let ctx = DbContext::new().request_id("abc */ DROP TABLE x; /*");
+        assert_eq!(
+            ctx.sql_comment().as_deref(),
+            Some("/* request_id=abc____DROP_TABLE_x____ */")
+        );
+    }
+}
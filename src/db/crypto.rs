@@ -0,0 +1,232 @@
+//! # Encrypted Columns
+//!
+//! Application-level field encryption for PII columns using AES-256-GCM.
+//!
+//! Stored values are text, so they fit `VARCHAR` / `TEXT` columns:
+//!
+//! ```text
+//! <key_id>:<base64(nonce || ciphertext || tag)>
+//! ```
+//!
+//! The key-id prefix selects the decryption key, so keys can be rotated by
+//! adding a new active key while keeping old ones for reading
+//! (see [`CryptoConfig`]).
+//!
+//! Encrypted values are bound with [`Param::Encrypted`] and read back with
+//! [`Row::get_decrypted`](crate::db::port::Row::get_decrypted).
+//!
+//! # Example
+//! ```rust
+//! use wzs_web::config::crypto::CryptoConfig;
+//! use wzs_web::db::crypto::FieldCipher;
+//!
+//! let cfg = CryptoConfig::from_env_with(|k| match k {
+//!     "DB_ENCRYPTION_KEYS" => Some("k1:secret".into()),
+//!     _ => None,
+//! });
+//! let cipher = FieldCipher::new(&cfg).unwrap();
+//!
+//! let stored = cipher.encrypt("alice@example.com").unwrap();
+//! assert!(stored.starts_with("k1:"));
+//! assert_eq!(cipher.decrypt(&stored).unwrap(), "alice@example.com");
+//! ```
+//!
+//! Note that encryption is randomized: the same plaintext yields different
+//! ciphertexts, so encrypted columns cannot be searched with `=`.
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine as _};
+use rand::RngCore;
+
+use crate::config::crypto::CryptoConfig;
+use crate::db::port::Param;
+
+const NONCE_LEN: usize = 12;
+
+/// Encrypts and decrypts column values with a rotating key ring.
+#[derive(Clone)]
+pub struct FieldCipher {
+    keys: Vec<(String, Aes256Gcm)>,
+    active: usize,
+}
+
+impl FieldCipher {
+    /// Builds a cipher from configuration.
+    ///
+    /// # Errors
+    /// Returns an error if no keys are configured, a key id contains `:`,
+    /// or the active key id is not in the key list.
+    pub fn new(cfg: &CryptoConfig) -> Result<Self> {
+        if cfg.keys.is_empty() {
+            bail!("DB_ENCRYPTION_KEYS is not set");
+        }
+
+        let mut keys = Vec::with_capacity(cfg.keys.len());
+        for (id, key) in &cfg.keys {
+            if id.contains(':') {
+                bail!("invalid encryption key id: {id}");
+            }
+            keys.push((id.clone(), Aes256Gcm::new(key.into())));
+        }
+
+        let active_id = cfg.active_key_id.as_deref().unwrap_or(keys[0].0.as_str());
+        let active = keys
+            .iter()
+            .position(|(id, _)| id == active_id)
+            .ok_or_else(|| anyhow!("active encryption key not found: {active_id}"))?;
+
+        Ok(Self { keys, active })
+    }
+
+    /// Id of the key used for new encryptions.
+    pub fn active_key_id(&self) -> &str {
+        &self.keys[self.active].0
+    }
+
+    /// Encrypts `plaintext` with the active key.
+    pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+        let (id, aead) = &self.keys[self.active];
+
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rng().fill_bytes(&mut nonce);
+
+        let ct = aead
+            .encrypt(&Nonce::from(nonce), plaintext.as_bytes())
+            .map_err(|_| anyhow!("encryption failed"))?;
+
+        let mut blob = Vec::with_capacity(NONCE_LEN + ct.len());
+        blob.extend_from_slice(&nonce);
+        blob.extend_from_slice(&ct);
+
+        Ok(format!("{id}:{}", STANDARD_NO_PAD.encode(blob)))
+    }
+
+    /// Decrypts a stored value produced by [`encrypt`](Self::encrypt).
+    ///
+    /// # Errors
+    /// Returns an error if the format is invalid, the key id is unknown, or
+    /// authentication fails (wrong key or tampered value).
+    pub fn decrypt(&self, stored: &str) -> Result<String> {
+        let (id, b64) = stored
+            .split_once(':')
+            .ok_or_else(|| anyhow!("encrypted value has no key id"))?;
+        let aead = self
+            .keys
+            .iter()
+            .find(|(k, _)| k == id)
+            .map(|(_, a)| a)
+            .ok_or_else(|| anyhow!("unknown encryption key id: {id}"))?;
+
+        let blob = STANDARD_NO_PAD
+            .decode(b64)
+            .context("encrypted value is not valid base64")?;
+        if blob.len() < NONCE_LEN {
+            bail!("encrypted value is too short");
+        }
+        let (nonce, ct) = blob.split_at(NONCE_LEN);
+        let nonce: [u8; NONCE_LEN] = nonce.try_into().expect("nonce length checked");
+
+        let pt = aead
+            .decrypt(&Nonce::from(nonce), ct)
+            .map_err(|_| anyhow!("decryption failed (key {id})"))?;
+        String::from_utf8(pt).context("decrypted value is not UTF-8")
+    }
+
+    /// Returns `true` if `stored` was encrypted with a non-active key and
+    /// should be re-encrypted.
+    pub fn needs_rotation(&self, stored: &str) -> bool {
+        stored
+            .split_once(':')
+            .is_none_or(|(id, _)| id != self.active_key_id())
+    }
+
+    /// Encrypts `plaintext` into a [`Param::Encrypted`].
+    pub fn param(&self, plaintext: &str) -> Result<Param<'static>> {
+        Ok(Param::Encrypted(self.encrypt(plaintext)?))
+    }
+
+    /// Encrypts an optional value; `None` becomes [`Param::Null`].
+    pub fn param_opt(&self, plaintext: Option<&str>) -> Result<Param<'static>> {
+        match plaintext {
+            Some(s) => self.param(s),
+            None => Ok(Param::Null),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg(keys: &str, active: Option<&str>) -> CryptoConfig {
+        let keys = keys.to_string();
+        let active = active.map(str::to_string);
+        CryptoConfig::from_env_with(move |k| match k {
+            "DB_ENCRYPTION_KEYS" => Some(keys.clone()),
+            "DB_ENCRYPTION_ACTIVE_KEY" => active.clone(),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn roundtrip_and_randomized_ciphertext() {
+        let c = FieldCipher::new(&cfg("k1:s1", None)).unwrap();
+        let a = c.encrypt("secret").unwrap();
+        let b = c.encrypt("secret").unwrap();
+
+        assert_ne!(a, b);
+        assert_eq!(c.decrypt(&a).unwrap(), "secret");
+        assert_eq!(c.decrypt(&b).unwrap(), "secret");
+    }
+
+    #[test]
+    fn rotated_keys_still_decrypt_old_values() {
+        let old = FieldCipher::new(&cfg("k1:s1", None)).unwrap();
+        let stored = old.encrypt("pii").unwrap();
+
+        let rotated = FieldCipher::new(&cfg("k1:s1,k2:s2", Some("k2"))).unwrap();
+        assert_eq!(rotated.active_key_id(), "k2");
+        assert_eq!(rotated.decrypt(&stored).unwrap(), "pii");
+        assert!(rotated.needs_rotation(&stored));
+        assert!(!rotated.needs_rotation(&rotated.encrypt("pii").unwrap()));
+    }
+
+    #[test]
+    fn tampered_or_foreign_values_are_rejected() {
+        let c = FieldCipher::new(&cfg("k1:s1", None)).unwrap();
+        let stored = c.encrypt("pii").unwrap();
+
+        let other = FieldCipher::new(&cfg("k1:other", None)).unwrap();
+        assert!(other.decrypt(&stored).is_err());
+
+        let mut tampered = stored.clone();
+        let last = tampered.pop().unwrap();
+        tampered.push(if last == 'A' { 'B' } else { 'A' });
+        assert!(c.decrypt(&tampered).is_err());
+
+        assert!(c.decrypt("no-key-id").is_err());
+        assert!(c.decrypt("k9:AAAA").is_err());
+        assert!(c.decrypt("k1:AAAA").is_err());
+    }
+
+    #[test]
+    fn new_validates_configuration() {
+        assert!(FieldCipher::new(&cfg("", None)).is_err());
+        assert!(FieldCipher::new(&cfg("k1:s1", Some("k2"))).is_err());
+    }
+
+    #[test]
+    fn param_helpers_produce_encrypted_or_null() {
+        let c = FieldCipher::new(&cfg("k1:s1", None)).unwrap();
+
+        match c.param("x").unwrap() {
+            Param::Encrypted(s) => assert_eq!(c.decrypt(&s).unwrap(), "x"),
+            other => panic!("expected Encrypted, got {other:?}"),
+        }
+        assert!(matches!(c.param_opt(None).unwrap(), Param::Null));
+    }
+}
@@ -0,0 +1,197 @@
+//! # Decimal
+//!
+//! [`Decimal`] is an exact decimal number for `DECIMAL` / `NUMERIC` columns
+//! ([`Value::Decimal`](crate::db::port::Value::Decimal),
+//! [`Param::Decimal`](crate::db::port::Param::Decimal)).
+//!
+//! It keeps the textual digits as returned by the server, so values never
+//! pass through floating point and any MySQL precision (up to 65 digits) is
+//! preserved. Equality is numeric: `1.50 == 1.5`, while
+//! [`Display`](std::fmt::Display) keeps the original scale. Arithmetic is
+//! out of scope; convert with [`to_f64`](Decimal::to_f64) or parse the
+//! string into a dedicated decimal type at the edge.
+//!
+//! Serialized with serde as a JSON string to avoid precision loss.
+//!
+//! # Example
+//! ```rust
+//! use wzs_web::db::decimal::Decimal;
+//!
+//! let price: Decimal = "0012.3400".parse()?;
+//! assert_eq!(price.to_string(), "12.3400");
+//! assert_eq!(price.scale(), 4);
+//! assert_eq!(price, "12.34".parse()?);
+//! assert!("1e5".parse::<Decimal>().is_err());
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use anyhow::{bail, Error};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Exact decimal number in canonical text form (`-?\d+(\.\d+)?`).
+#[derive(Clone, Debug)]
+pub struct Decimal {
+    text: String,
+}
+
+impl Decimal {
+    /// Returns the canonical text (no leading zeros, original scale).
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Number of digits after the decimal point.
+    pub fn scale(&self) -> u32 {
+        self.text
+            .split_once('.')
+            .map_or(0, |(_, frac)| frac.len() as u32)
+    }
+
+    /// Returns `true` if the value is below zero.
+    pub fn is_negative(&self) -> bool {
+        self.text.starts_with('-')
+    }
+
+    /// Nearest `f64` (may lose precision).
+    pub fn to_f64(&self) -> f64 {
+        self.text.parse().unwrap_or(f64::NAN)
+    }
+
+    /// Sign, integer digits and fractional digits without trailing zeros.
+    fn parts(&self) -> (bool, &str, &str) {
+        let (neg, digits) = match self.text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, self.text.as_str()),
+        };
+        let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+        (neg, int, frac.trim_end_matches('0'))
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (neg, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (int, frac) = match digits.split_once('.') {
+            Some((int, frac)) => (int, Some(frac)),
+            None => (digits, None),
+        };
+        let all_digits = |p: &str| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit());
+        if !all_digits(int) || frac.is_some_and(|f| !all_digits(f)) {
+            bail!("invalid decimal: {s:?}");
+        }
+
+        let int = int.trim_start_matches('0');
+        let int = if int.is_empty() { "0" } else { int };
+        let is_zero = int == "0" && frac.is_none_or(|f| f.bytes().all(|b| b == b'0'));
+
+        let mut text = String::with_capacity(s.len());
+        if neg && !is_zero {
+            text.push('-');
+        }
+        text.push_str(int);
+        if let Some(frac) = frac {
+            text.push('.');
+            text.push_str(frac);
+        }
+        Ok(Self { text })
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.parts() == other.parts()
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.parts().hash(state);
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(v: i64) -> Self {
+        Self {
+            text: v.to_string(),
+        }
+    }
+}
+
+impl From<u64> for Decimal {
+    fn from(v: u64) -> Self {
+        Self {
+            text: v.to_string(),
+        }
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.text)
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_canonicalizes() {
+        let d: Decimal = "+007.250".parse().unwrap();
+        assert_eq!(d.as_str(), "7.250");
+        assert_eq!(d.scale(), 3);
+        assert_eq!("-0.00".parse::<Decimal>().unwrap().as_str(), "0.00");
+        assert_eq!(".5".parse::<Decimal>().ok(), None);
+        assert!("1.".parse::<Decimal>().is_err());
+        assert!("abc".parse::<Decimal>().is_err());
+    }
+
+    #[test]
+    fn equality_ignores_trailing_zeros_but_not_sign() {
+        let a: Decimal = "1.50".parse().unwrap();
+        assert_eq!(a, "1.5".parse().unwrap());
+        assert_ne!(a, "-1.5".parse().unwrap());
+        assert_eq!(Decimal::from(3i64), "3.000".parse().unwrap());
+    }
+
+    #[test]
+    fn keeps_precision_beyond_f64() {
+        let text = "12345678901234567890123456789.123456789";
+        let d: Decimal = text.parse().unwrap();
+        assert_eq!(d.to_string(), text);
+        assert!((d.to_f64() - 1.2345678901234568e28).abs() < 1e13);
+    }
+
+    #[test]
+    fn serializes_as_json_string() {
+        let d: Decimal = "19.99".parse().unwrap();
+        assert_eq!(serde_json::to_string(&d).unwrap(), "\"19.99\"");
+        let back: Decimal = serde_json::from_str("\"19.990\"").unwrap();
+        assert_eq!(back, d);
+    }
+}
@@ -0,0 +1,220 @@
+//! # Connection Pool Drain
+//!
+//! [`PoolDrain`] tracks connections checked out of a pool so that shutdown can
+//! wait for in-flight queries to finish while refusing new checkouts.
+//!
+//! There is one tracker per pool ([`PoolDrain::for_pool`]):
+//! [`MySqlDb`](crate::db::mysql_adapter::MySqlDb) enters it around every
+//! query, so every adapter built over the same `Arc<Pool>` is drained
+//! together. [`Server::drain_on_shutdown`](crate::web::server::Server::drain_on_shutdown)
+//! drains it once the HTTP server has stopped accepting requests.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use axum::Router;
+//! use wzs_web::config::db::DbConfig;
+//! use wzs_web::db::{connection::get_pool, mysql_adapter::MySqlDb};
+//! use wzs_web::web::server::Server;
+//!
+//! # async fn run(app: Router) -> anyhow::Result<()> {
+//! let db = MySqlDb::new(get_pool(&DbConfig::from_env()));
+//!
+//! // Let in-flight queries finish, then refuse everything else.
+//! Server::new(app)
+//!     .drain_on_shutdown(db.drain_handle(), Duration::from_secs(10))
+//!     .serve("0.0.0.0:8080")
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::any::Any;
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Mutex, Weak,
+};
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use tokio::sync::Notify;
+
+#[derive(Default)]
+struct Inner {
+    draining: AtomicBool,
+    active: AtomicUsize,
+    idle: Notify,
+}
+
+/// Shared checkout counter with a drain switch.
+///
+/// Cloning is cheap; all clones observe the same state.
+#[derive(Clone, Default)]
+pub struct PoolDrain {
+    inner: Arc<Inner>,
+}
+
+/// Trackers of live pools, matched by allocation.
+type Registry = Vec<(Weak<dyn Any + Send + Sync>, PoolDrain)>;
+
+static POOLS: Mutex<Registry> = Mutex::new(Vec::new());
+
+/// Marks one checked-out connection; released on drop.
+pub struct DrainGuard {
+    inner: Arc<Inner>,
+}
+
+impl PoolDrain {
+    /// Creates a new, non-draining tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The tracker of `pool`, shared by every caller passing the same
+    /// `Arc` (or a clone of it) while the pool is alive.
+    pub fn for_pool<T: Send + Sync + 'static>(pool: &Arc<T>) -> Self {
+        let weak: Weak<dyn Any + Send + Sync> = Arc::<T>::downgrade(pool);
+        let mut pools = POOLS.lock().unwrap();
+        pools.retain(|(p, _)| p.strong_count() > 0);
+        if let Some((_, drain)) = pools.iter().find(|(p, _)| p.ptr_eq(&weak)) {
+            return drain.clone();
+        }
+        let drain = Self::new();
+        pools.push((weak, drain.clone()));
+        drain
+    }
+
+    /// Registers a checkout.
+    ///
+    /// # Errors
+    /// Returns an error once [`drain`](Self::drain) has been called.
+    pub fn enter(&self) -> Result<DrainGuard> {
+        self.inner.active.fetch_add(1, Ordering::SeqCst);
+        if self.inner.draining.load(Ordering::SeqCst) {
+            release(&self.inner);
+            bail!("database pool is draining; new checkouts are refused");
+        }
+        Ok(DrainGuard {
+            inner: self.inner.clone(),
+        })
+    }
+
+    /// Number of connections currently checked out.
+    pub fn active(&self) -> usize {
+        self.inner.active.load(Ordering::SeqCst)
+    }
+
+    /// Returns `true` once draining has started.
+    pub fn is_draining(&self) -> bool {
+        self.inner.draining.load(Ordering::SeqCst)
+    }
+
+    /// Refuses new checkouts and waits until every checked-out connection has
+    /// been returned.
+    ///
+    /// # Errors
+    /// Returns an error if connections are still checked out after `timeout`.
+    pub async fn drain(&self, timeout: Duration) -> Result<()> {
+        self.inner.draining.store(true, Ordering::SeqCst);
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            // Created before the check so a release in between is not missed.
+            let idle = self.inner.idle.notified();
+            if self.active() == 0 {
+                return Ok(());
+            }
+            if tokio::time::timeout_at(deadline, idle).await.is_err() {
+                bail!(
+                    "database pool drain timed out with {} connection(s) still checked out",
+                    self.active()
+                );
+            }
+        }
+    }
+}
+
+impl Drop for DrainGuard {
+    fn drop(&mut self) {
+        release(&self.inner);
+    }
+}
+
+fn release(inner: &Inner) {
+    if inner.active.fetch_sub(1, Ordering::SeqCst) == 1 {
+        inner.idle.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guards_track_active_checkouts() {
+        let drain = PoolDrain::new();
+        let a = drain.enter().unwrap();
+        let b = drain.enter().unwrap();
+        assert_eq!(drain.active(), 2);
+
+        drop(a);
+        drop(b);
+        assert_eq!(drain.active(), 0);
+    }
+
+    #[tokio::test]
+    async fn one_tracker_per_pool() {
+        let pool = Arc::new(String::from("pool"));
+        let other = Arc::new(String::from("pool"));
+        let a = PoolDrain::for_pool(&pool);
+        let b = PoolDrain::for_pool(&pool.clone());
+
+        let _guard = a.enter().unwrap();
+        assert_eq!(b.active(), 1);
+        assert_eq!(PoolDrain::for_pool(&other).active(), 0);
+
+        drop(_guard);
+        b.drain(Duration::from_millis(10)).await.unwrap();
+        assert!(a.enter().is_err());
+        assert!(PoolDrain::for_pool(&other).enter().is_ok());
+
+        drop(pool);
+        let fresh = Arc::new(String::from("pool"));
+        assert!(!PoolDrain::for_pool(&fresh).is_draining());
+    }
+
+    #[tokio::test]
+    async fn drain_refuses_new_checkouts() {
+        let drain = PoolDrain::new();
+        drain.drain(Duration::from_millis(10)).await.unwrap();
+
+        assert!(drain.is_draining());
+        let err = drain.enter().err().unwrap();
+        assert!(err.to_string().contains("draining"));
+        assert_eq!(drain.active(), 0);
+    }
+
+    #[tokio::test]
+    async fn drain_waits_for_in_flight_checkouts() {
+        let drain = PoolDrain::new();
+        let guard = drain.enter().unwrap();
+
+        let releaser = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            drop(guard);
+        });
+
+        drain.drain(Duration::from_secs(5)).await.unwrap();
+        assert_eq!(drain.active(), 0);
+        releaser.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn drain_times_out_when_checkouts_are_held() {
+        let drain = PoolDrain::new();
+        let _guard = drain.enter().unwrap();
+
+        let err = drain.drain(Duration::from_millis(20)).await.unwrap_err();
+        assert!(err.to_string().contains("1 connection(s)"));
+    }
+}
@@ -0,0 +1,392 @@
+//! # Slow-Query EXPLAIN Wrapper
+//!
+//! [`ExplainDb`] wraps any [`Db`] and times every statement. When a statement
+//! exceeds [`SlowQueryConfig::threshold`], it runs `EXPLAIN` for it and reports
+//! the plan, flagging full table scans and filesorts.
+//!
+//! Enable with `SQL_EXPLAIN=1` (or implicitly via `SQL_DEBUG`); see
+//! [`SlowQueryConfig`].
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use wzs_web::config::db::{DbConfig, SlowQueryConfig};
+//! use wzs_web::db::{connection::get_pool, explain::ExplainDb, mysql_adapter::MySqlDb, port::Db};
+//!
+//! let base: Arc<dyn Db> = Arc::new(MySqlDb::new(get_pool(&DbConfig::from_env())));
+//! // Returns `base` unchanged unless slow-query analysis is enabled.
+//! let db: Arc<dyn Db> = ExplainDb::wrap(base, SlowQueryConfig::from_env());
+//! ```
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use tracing::warn;
+
+use crate::config::db::SlowQueryConfig;
+use crate::db::context::DbContext;
+use crate::db::port::{Db, Param, Row, RowStream, Value};
+
+/// Columns of MySQL's tabular `EXPLAIN` output, in display order.
+const PLAN_COLUMNS: [&str; 10] = [
+    "id",
+    "select_type",
+    "table",
+    "type",
+    "possible_keys",
+    "key",
+    "key_len",
+    "rows",
+    "filtered",
+    "Extra",
+];
+
+/// A statement that exceeded the latency threshold.
+#[derive(Clone, Debug)]
+pub struct SlowQuery {
+    /// The SQL as issued (without context annotations).
+    pub sql: String,
+    /// Wall-clock execution time.
+    pub elapsed: Duration,
+    /// Formatted plan (`None` if not explainable or `EXPLAIN` failed).
+    pub plan: Option<String>,
+    /// `true` if any plan row has access type `ALL`.
+    pub full_scan: bool,
+    /// `true` if any plan row reports `Using filesort`.
+    pub filesort: bool,
+}
+
+type Reporter = Arc<dyn Fn(&SlowQuery) + Send + Sync>;
+
+/// [`Db`] decorator that explains slow statements.
+pub struct ExplainDb {
+    inner: Arc<dyn Db>,
+    threshold: Duration,
+    reporter: Reporter,
+}
+
+impl ExplainDb {
+    /// Wraps `inner`, reporting statements slower than `threshold` via `tracing`.
+    pub fn new(inner: Arc<dyn Db>, threshold: Duration) -> Self {
+        Self {
+            inner,
+            threshold,
+            reporter: Arc::new(log_slow_query),
+        }
+    }
+
+    /// Wraps `inner` only when `cfg.enabled`; otherwise returns it as is.
+    pub fn wrap(inner: Arc<dyn Db>, cfg: SlowQueryConfig) -> Arc<dyn Db> {
+        if cfg.enabled {
+            Arc::new(Self::new(inner, cfg.threshold))
+        } else {
+            inner
+        }
+    }
+
+    /// Replaces the default `tracing` reporter.
+    pub fn on_slow_query<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&SlowQuery) + Send + Sync + 'static,
+    {
+        self.reporter = Arc::new(f);
+        self
+    }
+
+    fn timed<T>(&self, sql: &str, params: &[Param], run: impl FnOnce() -> Result<T>) -> Result<T> {
+        let started = Instant::now();
+        let out = run();
+        let elapsed = started.elapsed();
+        if elapsed >= self.threshold {
+            self.report(sql, params, elapsed);
+        }
+        out
+    }
+
+    fn report(&self, sql: &str, params: &[Param], elapsed: Duration) {
+        let mut slow = SlowQuery {
+            sql: sql.to_string(),
+            elapsed,
+            plan: None,
+            full_scan: false,
+            filesort: false,
+        };
+
+        if is_explainable(sql) {
+            match self.inner.fetch_all(&format!("EXPLAIN {sql}"), params) {
+                Ok(rows) => {
+                    slow.full_scan = rows.iter().any(|r| cell(r, "type") == "ALL");
+                    slow.filesort = rows
+                        .iter()
+                        .any(|r| cell(r, "Extra").contains("Using filesort"));
+                    slow.plan = Some(format_plan(&rows));
+                }
+                Err(e) => warn!("EXPLAIN failed for slow query: {e:#}"),
+            }
+        }
+
+        (self.reporter)(&slow);
+    }
+}
+
+impl Db for ExplainDb {
+    fn fetch_one(&self, sql: &str, params: &[Param]) -> Result<Option<Row>> {
+        self.timed(sql, params, || self.inner.fetch_one(sql, params))
+    }
+
+    fn fetch_all(&self, sql: &str, params: &[Param]) -> Result<Vec<Row>> {
+        self.timed(sql, params, || self.inner.fetch_all(sql, params))
+    }
+
+    fn fetch_stream(&self, sql: &str, params: &[Param]) -> Result<RowStream<'_>> {
+        // Timing covers statement start only; rows are pulled by the caller.
+        self.timed(sql, params, || self.inner.fetch_stream(sql, params))
+    }
+
+    fn exec(&self, sql: &str, params: &[Param]) -> Result<u64> {
+        self.timed(sql, params, || self.inner.exec(sql, params))
+    }
+
+    fn exec_returning_last_insert_id(&self, sql: &str, params: &[Param]) -> Result<u64> {
+        self.timed(sql, params, || {
+            self.inner.exec_returning_last_insert_id(sql, params)
+        })
+    }
+
+    fn ping(&self) -> Result<()> {
+        self.inner.ping()
+    }
+
+    fn fetch_one_with_ctx(
+        &self,
+        ctx: &DbContext,
+        sql: &str,
+        params: &[Param],
+    ) -> Result<Option<Row>> {
+        self.timed(sql, params, || {
+            self.inner.fetch_one_with_ctx(ctx, sql, params)
+        })
+    }
+
+    fn fetch_all_with_ctx(&self, ctx: &DbContext, sql: &str, params: &[Param]) -> Result<Vec<Row>> {
+        self.timed(sql, params, || {
+            self.inner.fetch_all_with_ctx(ctx, sql, params)
+        })
+    }
+
+    fn exec_with_ctx(&self, ctx: &DbContext, sql: &str, params: &[Param]) -> Result<u64> {
+        self.timed(sql, params, || self.inner.exec_with_ctx(ctx, sql, params))
+    }
+
+    fn exec_returning_last_insert_id_with_ctx(
+        &self,
+        ctx: &DbContext,
+        sql: &str,
+        params: &[Param],
+    ) -> Result<u64> {
+        self.timed(sql, params, || {
+            self.inner
+                .exec_returning_last_insert_id_with_ctx(ctx, sql, params)
+        })
+    }
+}
+
+/// Default reporter: one `warn!` line per slow statement.
+fn log_slow_query(q: &SlowQuery) {
+    warn!(
+        elapsed_ms = q.elapsed.as_millis() as u64,
+        full_scan = q.full_scan,
+        filesort = q.filesort,
+        "slow query: {}\n{}",
+        q.sql,
+        q.plan.as_deref().unwrap_or("(no plan)")
+    );
+}
+
+/// `true` for statements MySQL can `EXPLAIN`.
+fn is_explainable(sql: &str) -> bool {
+    let mut s = sql.trim_start();
+    while let Some(rest) = s.strip_prefix("/*") {
+        match rest.find("*/") {
+            Some(end) => s = rest[end + 2..].trim_start(),
+            None => return false,
+        }
+    }
+    let head: String = s
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_ascii_uppercase();
+    matches!(
+        head.as_str(),
+        "SELECT" | "INSERT" | "UPDATE" | "DELETE" | "REPLACE" | "WITH"
+    )
+}
+
+fn cell(row: &Row, key: &str) -> String {
+    match row.get(key) {
+        Some(Value::I64(v)) => v.to_string(),
+        Some(Value::U64(v)) => v.to_string(),
+        Some(Value::F32(v)) => v.to_string(),
+        Some(Value::F64(v)) => v.to_string(),
+        Some(Value::Bool(v)) => v.to_string(),
+        Some(Value::Str(s)) => s.clone(),
+        Some(Value::DateTime(dt)) => dt.to_string(),
+        Some(Value::Bin(b)) => String::from_utf8_lossy(b).into_owned(),
+        Some(Value::Decimal(d)) => d.to_string(),
+        Some(Value::Json(v)) => v.to_string(),
+        Some(Value::Null) | None => "NULL".into(),
+    }
+}
+
+/// Renders plan rows as a `|`-separated table.
+fn format_plan(rows: &[Row]) -> String {
+    let mut out = PLAN_COLUMNS.join(" | ");
+    for row in rows {
+        out.push('\n');
+        let cells: Vec<String> = PLAN_COLUMNS.iter().map(|c| cell(row, c)).collect();
+        out.push_str(&cells.join(" | "));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Sleeps on queries containing `slow` and answers `EXPLAIN` with a full scan.
+    #[derive(Default)]
+    struct FakeDb {
+        seen: Mutex<Vec<String>>,
+    }
+
+    impl FakeDb {
+        fn run(&self, sql: &str) {
+            self.seen.lock().unwrap().push(sql.to_string());
+            if sql.contains("slow") && !sql.starts_with("EXPLAIN") {
+                std::thread::sleep(Duration::from_millis(15));
+            }
+        }
+    }
+
+    impl Db for FakeDb {
+        fn fetch_one(&self, sql: &str, _params: &[Param]) -> Result<Option<Row>> {
+            self.run(sql);
+            Ok(None)
+        }
+
+        fn fetch_all(&self, sql: &str, _params: &[Param]) -> Result<Vec<Row>> {
+            self.run(sql);
+            if !sql.starts_with("EXPLAIN") {
+                return Ok(vec![]);
+            }
+            let mut r = Row::default();
+            r.insert("id", Value::U64(1));
+            r.insert("select_type", Value::Str("SIMPLE".into()));
+            r.insert("table", Value::Str("users".into()));
+            r.insert("type", Value::Str("ALL".into()));
+            r.insert("key", Value::Null);
+            r.insert("rows", Value::U64(1000));
+            r.insert("Extra", Value::Str("Using where; Using filesort".into()));
+            Ok(vec![r])
+        }
+
+        fn exec(&self, sql: &str, _params: &[Param]) -> Result<u64> {
+            self.run(sql);
+            Ok(1)
+        }
+
+        fn exec_returning_last_insert_id(&self, sql: &str, _params: &[Param]) -> Result<u64> {
+            self.run(sql);
+            Ok(1)
+        }
+    }
+
+    fn wrapped() -> (ExplainDb, Arc<FakeDb>, Arc<Mutex<Vec<SlowQuery>>>) {
+        let fake = Arc::new(FakeDb::default());
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let db = ExplainDb::new(fake.clone(), Duration::from_millis(10))
+            .on_slow_query(move |q| sink.lock().unwrap().push(q.clone()));
+        (db, fake, reports)
+    }
+
+    #[test]
+    fn slow_select_is_explained() {
+        let (db, fake, reports) = wrapped();
+
+        db.fetch_all("SELECT * FROM users WHERE note = 'slow'", &[])
+            .unwrap();
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        let q = &reports[0];
+        assert!(q.elapsed >= Duration::from_millis(10));
+        assert!(q.full_scan);
+        assert!(q.filesort);
+        let plan = q.plan.as_deref().unwrap();
+        assert!(plan.starts_with("id | select_type | table | type"));
+        assert!(plan.contains("1 | SIMPLE | users | ALL | NULL | NULL | NULL | 1000"));
+
+        assert_eq!(
+            fake.seen.lock().unwrap().last().unwrap(),
+            "EXPLAIN SELECT * FROM users WHERE note = 'slow'"
+        );
+    }
+
+    #[test]
+    fn fast_queries_are_not_reported() {
+        let (db, fake, reports) = wrapped();
+
+        db.fetch_one("SELECT 1", &[]).unwrap();
+        db.exec("UPDATE t SET a = 1", &[]).unwrap();
+
+        assert!(reports.lock().unwrap().is_empty());
+        assert_eq!(fake.seen.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn non_explainable_statements_report_without_plan() {
+        let (db, fake, reports) = wrapped();
+
+        db.exec("CREATE TABLE slow (id INT)", &[]).unwrap();
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].plan.is_none());
+        assert_eq!(fake.seen.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn ctx_variants_are_timed_and_delegated() {
+        let (db, _, reports) = wrapped();
+        let ctx = DbContext::new().read_only();
+
+        db.fetch_one_with_ctx(&ctx, "SELECT 'slow'", &[]).unwrap();
+        assert!(db.exec_with_ctx(&ctx, "DELETE FROM t", &[]).is_err());
+
+        assert_eq!(reports.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn explainable_detection_skips_leading_comments() {
+        assert!(is_explainable("/* rid=abc */ SELECT 1"));
+        assert!(is_explainable("  update t set a = 1"));
+        assert!(is_explainable("WITH x AS (SELECT 1) SELECT * FROM x"));
+        assert!(!is_explainable("SHOW TABLES"));
+        assert!(!is_explainable("/* unterminated SELECT 1"));
+    }
+
+    #[test]
+    fn wrap_is_noop_when_disabled() {
+        let fake: Arc<dyn Db> = Arc::new(FakeDb::default());
+        let cfg = SlowQueryConfig {
+            enabled: false,
+            threshold: Duration::ZERO,
+        };
+        let db = ExplainDb::wrap(fake.clone(), cfg);
+        assert!(Arc::ptr_eq(&db, &fake));
+    }
+}
@@ -0,0 +1,584 @@
+//! # Migrations
+//!
+//! [`Migrator`] applies versioned SQL migrations over the
+//! [`Db`](crate::db::port::Db) port and records them in a
+//! `schema_migrations` table.
+//!
+//! Migration files are named `<version>_<name>.up.sql` with an optional
+//! `<version>_<name>.down.sql`; a plain `<version>_<name>.sql` is treated as
+//! an `up` script. Versions are unsigned integers (e.g. `20260301120000`)
+//! and are applied in ascending order. A file may hold several statements
+//! separated by `;`.
+//!
+//! Migrations are loaded from a directory at runtime
+//! ([`Migrator::from_dir`]) or compiled into the binary with `include_str!`
+//! ([`Migrator::from_files`]).
+//!
+//! MySQL commits DDL implicitly, so a migration that fails half-way is not
+//! rolled back; it is left unrecorded and the error names the statement.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use wzs_web::config::db::DbConfig;
+//! use wzs_web::db::{connection::get_pool, migrate::Migrator, mysql_adapter::MySqlDb};
+//!
+//! # fn run() -> anyhow::Result<()> {
+//! let db = Arc::new(MySqlDb::new(get_pool(&DbConfig::from_env())));
+//!
+//! let migrator = Migrator::from_files(
+//!     db,
+//!     [
+//!         ("20260301000000_users.up.sql", "CREATE TABLE users (id BIGINT PRIMARY KEY)"),
+//!         ("20260301000000_users.down.sql", "DROP TABLE users"),
+//!     ],
+//! )?;
+//!
+//! for version in migrator.up()? {
+//!     println!("applied {version}");
+//! }
+//! for s in migrator.status()? {
+//!     println!("{} {} {:?}", s.version, s.name, s.applied_at);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{NaiveDateTime, Utc};
+use tracing::info;
+
+use crate::db::port::{Db, Param};
+use crate::db::repository::ident;
+
+/// One versioned migration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    pub version: u64,
+    pub name: String,
+    pub up: String,
+    /// Rollback script; [`Migrator::down`] refuses migrations without one.
+    pub down: Option<String>,
+}
+
+/// State of one migration as reported by [`Migrator::status`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: u64,
+    pub name: String,
+    /// When it was applied; `None` if pending.
+    pub applied_at: Option<NaiveDateTime>,
+}
+
+impl MigrationStatus {
+    /// Returns whether the migration has been applied.
+    pub fn is_applied(&self) -> bool {
+        self.applied_at.is_some()
+    }
+}
+
+/// Applies and rolls back [`Migration`]s, tracking them in a table.
+#[derive(Clone)]
+pub struct Migrator {
+    db: Arc<dyn Db>,
+    table: String,
+    migrations: Vec<Migration>,
+}
+
+impl Migrator {
+    /// Default tracking table name.
+    pub const DEFAULT_TABLE: &'static str = "schema_migrations";
+
+    /// Creates a migrator over already-built migrations.
+    ///
+    /// # Errors
+    /// Returns an error if two migrations share a version.
+    pub fn new(db: Arc<dyn Db>, mut migrations: Vec<Migration>) -> Result<Self> {
+        migrations.sort_by_key(|m| m.version);
+        if let Some(w) = migrations.windows(2).find(|w| w[0].version == w[1].version) {
+            bail!("duplicate migration version: {}", w[0].version);
+        }
+        Ok(Self {
+            db,
+            table: Self::DEFAULT_TABLE.into(),
+            migrations,
+        })
+    }
+
+    /// Creates a migrator from `(file name, contents)` pairs, e.g. embedded
+    /// with `include_str!`. Names not ending in `.sql` are ignored.
+    ///
+    /// # Errors
+    /// Returns an error for malformed names, duplicate scripts, or a
+    /// `.down.sql` without its `.up.sql`.
+    pub fn from_files<N, C>(
+        db: Arc<dyn Db>,
+        files: impl IntoIterator<Item = (N, C)>,
+    ) -> Result<Self>
+    where
+        N: AsRef<str>,
+        C: Into<String>,
+    {
+        let mut ups: BTreeMap<u64, (String, String)> = BTreeMap::new();
+        let mut downs: HashMap<u64, (String, String)> = HashMap::new();
+
+        for (file, contents) in files {
+            let file = file.as_ref();
+            let Some((version, name, dir)) = parse_file_name(file)? else {
+                continue;
+            };
+            let target = match dir {
+                Direction::Up => ups.insert(version, (name, contents.into())),
+                Direction::Down => downs.insert(version, (name, contents.into())),
+            };
+            if target.is_some() {
+                bail!("duplicate {} script for migration {version}", dir.as_str());
+            }
+        }
+
+        let mut migrations = Vec::with_capacity(ups.len());
+        for (version, (name, up)) in ups {
+            let down = downs.remove(&version).map(|(_, sql)| sql);
+            migrations.push(Migration {
+                version,
+                name,
+                up,
+                down,
+            });
+        }
+        if let Some(version) = downs.keys().min() {
+            bail!("down script without up script for migration {version}");
+        }
+        Self::new(db, migrations)
+    }
+
+    /// Creates a migrator from the `.sql` files in `dir` (not recursive).
+    ///
+    /// # Errors
+    /// Returns an error if the directory cannot be read, or as
+    /// [`from_files`](Self::from_files).
+    pub fn from_dir(db: Arc<dyn Db>, dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut files = Vec::new();
+        for entry in fs::read_dir(dir).with_context(|| format!("read_dir {:?}", dir))? {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+            let Some(file) = path.file_name().and_then(|f| f.to_str()) else {
+                continue;
+            };
+            let sql = fs::read_to_string(&path).with_context(|| format!("read {:?}", path))?;
+            files.push((file.to_string(), sql));
+        }
+        Self::from_files(db, files)
+    }
+
+    /// Uses a custom tracking table.
+    ///
+    /// # Errors
+    /// Returns an error if `table` is not a plain SQL identifier.
+    pub fn with_table(mut self, table: impl Into<String>) -> Result<Self> {
+        let table = table.into();
+        ident(&table)?;
+        self.table = table;
+        Ok(self)
+    }
+
+    /// Returns the known migrations in version order.
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Applies every pending migration in version order, returning the
+    /// versions applied.
+    ///
+    /// # Errors
+    /// Stops at the first failing statement; earlier migrations stay applied.
+    pub fn up(&self) -> Result<Vec<u64>> {
+        let applied = self.applied()?;
+        let mut done = Vec::new();
+        for m in self
+            .migrations
+            .iter()
+            .filter(|m| !applied.contains_key(&m.version))
+        {
+            self.run_script(m, &m.up, "up")?;
+            self.db.exec(
+                &format!(
+                    "INSERT INTO {} (version, name, applied_at) VALUES (?, ?, ?)",
+                    self.table
+                ),
+                &[
+                    Param::U64(m.version),
+                    Param::Str(&m.name),
+                    Param::DateTime(Utc::now().naive_utc()),
+                ],
+            )?;
+            info!("migration {} ({}) applied", m.version, m.name);
+            done.push(m.version);
+        }
+        Ok(done)
+    }
+
+    /// Rolls back the most recently applied migration, returning its version
+    /// (`None` when nothing is applied).
+    ///
+    /// # Errors
+    /// Returns an error if the migration is unknown locally or has no down script.
+    pub fn down(&self) -> Result<Option<u64>> {
+        let Some(&version) = self.applied()?.keys().next_back() else {
+            return Ok(None);
+        };
+        let m = self
+            .migrations
+            .iter()
+            .find(|m| m.version == version)
+            .ok_or_else(|| anyhow!("applied migration {version} not found locally"))?;
+        let down = m
+            .down
+            .as_deref()
+            .ok_or_else(|| anyhow!("migration {version} ({}) has no down script", m.name))?;
+
+        self.run_script(m, down, "down")?;
+        self.db.exec(
+            &format!("DELETE FROM {} WHERE version = ?", self.table),
+            &[Param::U64(version)],
+        )?;
+        info!("migration {} ({}) rolled back", m.version, m.name);
+        Ok(Some(version))
+    }
+
+    /// Reports every known migration with its applied time.
+    ///
+    /// # Errors
+    /// Returns an error if the tracking table cannot be read.
+    pub fn status(&self) -> Result<Vec<MigrationStatus>> {
+        let applied = self.applied()?;
+        Ok(self
+            .migrations
+            .iter()
+            .map(|m| MigrationStatus {
+                version: m.version,
+                name: m.name.clone(),
+                applied_at: applied.get(&m.version).copied(),
+            })
+            .collect())
+    }
+
+    /// Creates the tracking table if needed and returns applied versions.
+    fn applied(&self) -> Result<BTreeMap<u64, NaiveDateTime>> {
+        self.db.exec(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} (\
+                 version BIGINT UNSIGNED NOT NULL PRIMARY KEY, \
+                 name VARCHAR(255) NOT NULL, \
+                 applied_at DATETIME NOT NULL)",
+                self.table
+            ),
+            &[],
+        )?;
+        self.db
+            .fetch_all(
+                &format!("SELECT version, applied_at FROM {}", self.table),
+                &[],
+            )?
+            .iter()
+            .map(|r| Ok((r.get_u64("version")?, r.get_datetime("applied_at")?)))
+            .collect()
+    }
+
+    fn run_script(&self, m: &Migration, sql: &str, dir: &str) -> Result<()> {
+        for (i, stmt) in split_statements(sql).iter().enumerate() {
+            self.db.exec(stmt, &[]).with_context(|| {
+                format!(
+                    "migration {} ({}) {dir}: statement {} failed",
+                    m.version,
+                    m.name,
+                    i + 1
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Up,
+    Down,
+}
+
+impl Direction {
+    const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Up => "up",
+            Self::Down => "down",
+        }
+    }
+}
+
+/// Parses `<version>_<name>[.up|.down].sql`; `Ok(None)` for non-SQL files.
+fn parse_file_name(file: &str) -> Result<Option<(u64, String, Direction)>> {
+    let Some(stem) = file.strip_suffix(".sql") else {
+        return Ok(None);
+    };
+    let (stem, dir) = if let Some(s) = stem.strip_suffix(".down") {
+        (s, Direction::Down)
+    } else {
+        (stem.strip_suffix(".up").unwrap_or(stem), Direction::Up)
+    };
+    let (version, name) = stem
+        .split_once('_')
+        .ok_or_else(|| anyhow!("migration file must be <version>_<name>.sql: {file}"))?;
+    let version = version
+        .parse()
+        .with_context(|| format!("invalid migration version in {file}"))?;
+    if name.is_empty() {
+        bail!("migration file has an empty name: {file}");
+    }
+    Ok(Some((version, name.to_string(), dir)))
+}
+
+/// Splits a script on `;`, ignoring semicolons inside quotes and comments.
+/// Comment-only and empty statements are dropped.
+pub(crate) fn split_statements(sql: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut cur = String::new();
+    let mut has_code = false;
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                has_code = true;
+                cur.push(c);
+                while let Some(n) = chars.next() {
+                    cur.push(n);
+                    if n == '\\' && c != '`' {
+                        if let Some(esc) = chars.next() {
+                            cur.push(esc);
+                        }
+                    } else if n == c {
+                        break;
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => skip_line(&mut chars),
+            '#' => skip_line(&mut chars),
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for n in chars.by_ref() {
+                    if prev == '*' && n == '/' {
+                        break;
+                    }
+                    prev = n;
+                }
+                cur.push(' ');
+            }
+            ';' => {
+                if has_code {
+                    out.push(cur.trim().to_string());
+                }
+                cur.clear();
+                has_code = false;
+            }
+            _ => {
+                has_code |= !c.is_whitespace();
+                cur.push(c);
+            }
+        }
+    }
+    if has_code {
+        out.push(cur.trim().to_string());
+    }
+    out
+}
+
+fn skip_line(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    for n in chars.by_ref() {
+        if n == '\n' {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::db::port::{Row, Value};
+
+    /// Keeps the tracking table in memory and records other statements.
+    #[derive(Default)]
+    struct MemoryDb {
+        applied: Mutex<BTreeMap<u64, NaiveDateTime>>,
+        statements: Mutex<Vec<String>>,
+        fail_on: Option<&'static str>,
+    }
+
+    impl Db for MemoryDb {
+        fn fetch_one(&self, _sql: &str, _params: &[Param]) -> Result<Option<Row>> {
+            Ok(None)
+        }
+
+        fn fetch_all(&self, _sql: &str, _params: &[Param]) -> Result<Vec<Row>> {
+            Ok(self
+                .applied
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(v, at)| {
+                    let mut row = Row::default();
+                    row.insert("version", Value::U64(*v));
+                    row.insert("applied_at", Value::DateTime(*at));
+                    row
+                })
+                .collect())
+        }
+
+        fn exec(&self, sql: &str, params: &[Param]) -> Result<u64> {
+            let version = match params.first().map(Param::to_value) {
+                Some(Value::U64(v)) => Some(v),
+                _ => None,
+            };
+            if sql.starts_with("CREATE TABLE IF NOT EXISTS schema_migrations") {
+                return Ok(0);
+            }
+            if sql.starts_with("INSERT INTO schema_migrations") {
+                let at = Utc::now().naive_utc();
+                self.applied.lock().unwrap().insert(version.unwrap(), at);
+                return Ok(1);
+            }
+            if sql.starts_with("DELETE FROM schema_migrations") {
+                self.applied.lock().unwrap().remove(&version.unwrap());
+                return Ok(1);
+            }
+            if self.fail_on.is_some_and(|f| sql.contains(f)) {
+                bail!("syntax error");
+            }
+            self.statements.lock().unwrap().push(sql.to_string());
+            Ok(0)
+        }
+
+        fn exec_returning_last_insert_id(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    fn files() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("2_posts.up.sql", "CREATE TABLE posts (id INT);"),
+            ("2_posts.down.sql", "DROP TABLE posts;"),
+            (
+                "1_users.sql",
+                "CREATE TABLE users (id INT); CREATE INDEX ix ON users (id);",
+            ),
+            ("README.md", "ignored"),
+        ]
+    }
+
+    #[test]
+    fn up_applies_pending_in_order_and_is_idempotent() {
+        let db = Arc::new(MemoryDb::default());
+        let m = Migrator::from_files(db.clone(), files()).unwrap();
+
+        assert_eq!(m.up().unwrap(), vec![1, 2]);
+        assert_eq!(
+            *db.statements.lock().unwrap(),
+            vec![
+                "CREATE TABLE users (id INT)",
+                "CREATE INDEX ix ON users (id)",
+                "CREATE TABLE posts (id INT)",
+            ]
+        );
+        assert!(m.up().unwrap().is_empty());
+        assert!(m.status().unwrap().iter().all(MigrationStatus::is_applied));
+    }
+
+    #[test]
+    fn down_rolls_back_latest_and_requires_a_down_script() {
+        let db = Arc::new(MemoryDb::default());
+        let m = Migrator::from_files(db.clone(), files()).unwrap();
+        m.up().unwrap();
+
+        assert_eq!(m.down().unwrap(), Some(2));
+        assert_eq!(
+            db.statements.lock().unwrap().last().unwrap(),
+            "DROP TABLE posts"
+        );
+
+        let status = m.status().unwrap();
+        assert!(status[0].is_applied());
+        assert!(!status[1].is_applied());
+
+        let err = m.down().unwrap_err();
+        assert!(err.to_string().contains("no down script"));
+    }
+
+    #[test]
+    fn failed_migration_is_not_recorded() {
+        let db = Arc::new(MemoryDb {
+            fail_on: Some("posts"),
+            ..Default::default()
+        });
+        let m = Migrator::from_files(db.clone(), files()).unwrap();
+
+        let err = m.up().unwrap_err();
+        assert!(format!("{err:#}").contains("migration 2 (posts) up: statement 1 failed"));
+        assert_eq!(
+            db.applied
+                .lock()
+                .unwrap()
+                .keys()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn from_files_rejects_bad_names_and_orphans() {
+        let db: Arc<dyn Db> = Arc::new(MemoryDb::default());
+        assert!(Migrator::from_files(db.clone(), [("users.sql", "")]).is_err());
+        assert!(Migrator::from_files(db.clone(), [("x_users.sql", "")]).is_err());
+        assert!(Migrator::from_files(db.clone(), [("1_a.sql", ""), ("1_b.up.sql", "")]).is_err());
+        assert!(Migrator::from_files(db, [("1_a.down.sql", "")]).is_err());
+    }
+
+    #[test]
+    fn from_dir_reads_sql_files() {
+        let dir = std::env::temp_dir().join(format!("migrations-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("10_init.up.sql"), "SELECT 1").unwrap();
+        fs::write(dir.join("notes.txt"), "x").unwrap();
+
+        let m = Migrator::from_dir(Arc::new(MemoryDb::default()), &dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(m.migrations().len(), 1);
+        assert_eq!(m.migrations()[0].name, "init");
+    }
+
+    #[test]
+    fn split_statements_respects_quotes_and_comments() {
+        let sql = "-- header; comment\n\
+                   INSERT INTO t VALUES ('a;b', \"c\\\";d\");\n\
+                   /* block; */ UPDATE t SET x = 1; # trailing;\n\
+                   ;  ";
+        assert_eq!(
+            split_statements(sql),
+            vec![
+                "INSERT INTO t VALUES ('a;b', \"c\\\";d\")",
+                "UPDATE t SET x = 1",
+            ]
+        );
+    }
+}
@@ -6,9 +6,18 @@
 //!
 //! ## Responsibilities
 //! - Convert generic [`Param`] values into [`mysql::Value`]
-//! - Convert [`mysql::Row`] into a generic [`Row`]
-//! - Implement `fetch_one`, `fetch_all`, `exec`, and
+//! - Convert [`mysql::Row`] into a generic [`Row`]; binary columns (`BLOB`,
+//!   `VARBINARY`) become [`Value::Bin`], text columns [`Value::Str`]
+//! - Implement `fetch_one`, `fetch_all`, `fetch_stream`, `exec`, and
 //!   `exec_returning_last_insert_id` using `mysql::Pool`
+//! - Track checked-out connections so shutdown can [`drain`](MySqlDb::drain)
+//! - Retry transient checkout failures per [`DbRetryPolicy`], and answer
+//!   health checks with a protocol-level [`ping`](Db::ping)
+//! - Log each statement as a structured `sql` span ([`SqlSpan`]) with
+//!   parameter redaction per [`SqlLogConfig`], and report it to an optional
+//!   [`QueryObserver`]
+//! - Apply [`DbContext`]: request-id comments, `MAX_EXECUTION_TIME` hints on
+//!   `SELECT`, deadline and read-only checks
 //!
 //! ## Testing Policy
 //! - Unit tests focus only on pure conversion functions
@@ -26,25 +35,40 @@
 //! }
 //! ```
 
-use std::sync::{Arc, OnceLock};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use mysql::consts::ColumnType;
 use mysql::{prelude::*, Error as MyError, Params, Pool, Value as My};
 
-use crate::db::port::{Db, Param, Row as GRow, Value};
-
-static SQL_DEBUG: OnceLock<bool> = OnceLock::new();
-
-#[inline]
-fn sql_debug() -> bool {
-    *SQL_DEBUG.get_or_init(|| std::env::var_os("SQL_DEBUG").is_some())
-}
-
-macro_rules! dbglog {
-    ($($arg:tt)*) => {
-       if sql_debug() { eprintln!($($arg)*); }
-    }
+use crate::config::db::{DbRetryPolicy, SqlLogConfig};
+use crate::db::context::DbContext;
+use crate::db::drain::PoolDrain;
+use crate::db::observer::QueryObserver;
+use crate::db::port::{Db, Param, Row as GRow, RowStream, Value};
+use crate::db::sql_log::SqlSpan;
+
+/// Rows buffered between the streaming worker and the consumer.
+const STREAM_BUFFER: usize = 256;
+
+/// Collation id MySQL reports for binary strings (`BLOB`, `VARBINARY`, ...).
+const BINARY_CHARSET: u16 = 63;
+
+/// Column types whose binary-collation values are raw bytes rather than text.
+fn is_byte_column(t: ColumnType) -> bool {
+    matches!(
+        t,
+        ColumnType::MYSQL_TYPE_TINY_BLOB
+            | ColumnType::MYSQL_TYPE_BLOB
+            | ColumnType::MYSQL_TYPE_MEDIUM_BLOB
+            | ColumnType::MYSQL_TYPE_LONG_BLOB
+            | ColumnType::MYSQL_TYPE_STRING
+            | ColumnType::MYSQL_TYPE_VAR_STRING
+            | ColumnType::MYSQL_TYPE_VARCHAR
+    )
 }
 
 #[inline]
@@ -63,15 +87,46 @@ fn mysql_err_summary(e: &MyError) -> String {
     }
 }
 
-#[inline]
-fn log_who_where(conn: &mut mysql::PooledConn) {
-    if !sql_debug() {
-        return;
+/// Server error code, when the error came from the server.
+fn mysql_err_code(e: &MyError) -> Option<u16> {
+    match e {
+        MyError::MySqlError(me) => Some(me.code),
+        _ => None,
+    }
+}
+
+/// `true` for checkout errors worth retrying: network / timeout failures
+/// and server-side connection limits (`ER_CON_COUNT_ERROR`,
+/// `ER_TOO_MANY_USER_CONNECTIONS`).
+pub(crate) fn is_transient(e: &MyError) -> bool {
+    match e {
+        MyError::IoError(_) => true,
+        MyError::DriverError(de) => matches!(
+            de,
+            mysql::DriverError::ConnectTimeout
+                | mysql::DriverError::CouldNotConnect(_)
+                | mysql::DriverError::Timeout
+        ),
+        MyError::MySqlError(me) => matches!(me.code, 1040 | 1203),
+        _ => false,
     }
-    if let Ok(Some(row)) = conn.query_first::<(String, String, String, String), _>(
-        "SELECT CURRENT_USER(), USER(), DATABASE(), @@hostname",
-    ) {
-        eprintln!("who/where = {:?}", row);
+}
+
+/// Logs which account and schema the connection is using, to diagnose
+/// failures caused by connecting to the wrong database or user.
+fn log_who_where(conn: &mut mysql::PooledConn) {
+    if let Ok(Some((current_user, user, database, host))) = conn
+        .query_first::<(String, String, String, String), _>(
+            "SELECT CURRENT_USER(), USER(), DATABASE(), @@hostname",
+        )
+    {
+        tracing::warn!(
+            current_user,
+            user,
+            database,
+            host,
+            "sql connection diagnostics"
+        );
     }
 }
 
@@ -82,19 +137,108 @@ fn log_who_where(conn: &mut mysql::PooledConn) {
 #[derive(Clone)]
 pub struct MySqlDb {
     pool: Arc<Pool>,
+    drain: PoolDrain,
+    retry: DbRetryPolicy,
+    sql_log: SqlLogConfig,
+    observer: Option<Arc<dyn QueryObserver>>,
 }
 
 impl MySqlDb {
     /// Creates a new adapter instance using the provided connection pool.
+    ///
+    /// Connection checkout uses [`DbRetryPolicy::default`]; statement logging
+    /// uses [`SqlLogConfig::from_env`]. Adapters over the same pool share
+    /// its [`PoolDrain::for_pool`] tracker.
     pub fn new(pool: Arc<Pool>) -> Self {
-        Self { pool }
+        Self {
+            drain: PoolDrain::for_pool(&pool),
+            pool,
+            retry: DbRetryPolicy::default(),
+            sql_log: SqlLogConfig::from_env(),
+            observer: None,
+        }
+    }
+
+    /// Sets the retry policy for connection checkout.
+    pub fn retry(mut self, policy: DbRetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Sets how statements are logged.
+    pub fn sql_log(mut self, cfg: SqlLogConfig) -> Self {
+        self.sql_log = cfg;
+        self
+    }
+
+    /// Reports every statement's SQL, duration and outcome to `observer`
+    /// (e.g. [`QueryMetrics`](crate::db::observer::QueryMetrics)).
+    pub fn observer(mut self, observer: Arc<dyn QueryObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Returns the checkout tracker of this adapter's pool.
+    pub fn drain_handle(&self) -> PoolDrain {
+        self.drain.clone()
+    }
+
+    /// Refuses new queries on the pool and waits for in-flight ones to
+    /// finish.
+    ///
+    /// # Errors
+    /// Returns an error if queries are still running after `timeout`.
+    pub async fn drain(&self, timeout: Duration) -> Result<()> {
+        self.drain.drain(timeout).await
+    }
+
+    /// Checks out a pooled connection, retrying transient failures.
+    fn get_conn(&self) -> Result<mysql::PooledConn> {
+        let mut retry = 0;
+        loop {
+            match self.pool.get_conn() {
+                Ok(conn) => return Ok(conn),
+                Err(e) if retry < self.retry.retries && is_transient(&e) => {
+                    retry += 1;
+                    let wait = self.retry.backoff(retry);
+                    tracing::warn!(
+                        "get_conn failed ({}); retry {retry}/{} in {wait:?}",
+                        mysql_err_summary(&e),
+                        self.retry.retries
+                    );
+                    std::thread::sleep(wait);
+                }
+                Err(e) => return Err(anyhow::Error::new(e).context("get_conn failed")),
+            }
+        }
+    }
+
+    /// Opens the log span for one statement, attaching the observer.
+    fn span(&self, op: &'static str, sql: &str, params: &[Param]) -> SqlSpan {
+        SqlSpan::start(op, sql, params, &self.sql_log).observed_by(self.observer.clone(), sql)
+    }
+
+    /// Marks `span` failed, logs connection diagnostics if enabled, and wraps `e`.
+    fn statement_error(
+        &self,
+        span: &SqlSpan,
+        conn: &mut mysql::PooledConn,
+        e: MyError,
+        what: &'static str,
+    ) -> anyhow::Error {
+        span.fail(mysql_err_code(&e), &mysql_err_summary(&e));
+        if self.sql_log.diagnostics {
+            log_who_where(conn);
+        }
+        anyhow::Error::new(e).context(what)
     }
 
     /// Converts a single [`Param`] into a [`mysql::Value`].
     ///
     /// Mapping conventions:
     /// - `Bool(true)` → `Int(1)` / `Bool(false)` → `Int(0)`
-    /// - `Str` → `Bytes`
+    /// - `Str` / `Encrypted` → `Bytes`
+    /// - `Decimal` → `Bytes` (decimal text), `Json` → `Bytes` (serialized JSON)
     /// - `DateTime` → `Value::Date` (Y, M, D, H, M, S, μs)
     /// - `Null` → `NULL`
     #[inline]
@@ -120,6 +264,9 @@ impl MySqlDb {
                 )
             }
             Param::Bin(b) => My::Bytes(b.to_vec()),
+            Param::Encrypted(s) => My::Bytes(s.as_bytes().to_vec()),
+            Param::Decimal(d) => My::Bytes(d.as_str().as_bytes().to_vec()),
+            Param::Json(v) => My::Bytes(v.to_string().into_bytes()),
             Param::Null => My::NULL,
         }
     }
@@ -131,149 +278,287 @@ impl MySqlDb {
         Params::Positional(v)
     }
 
+    /// Rewrites `sql` for the given context.
+    ///
+    /// - prefixes the request-id comment (for slow-log correlation)
+    /// - adds `/*+ MAX_EXECUTION_TIME(ms) */` to `SELECT` statements when a
+    ///   deadline is set (MySQL only honors the hint on `SELECT`)
+    fn annotate_sql(ctx: &DbContext, sql: &str) -> String {
+        let trimmed = sql.trim_start();
+        let is_select = trimmed
+            .get(..6)
+            .is_some_and(|head| head.eq_ignore_ascii_case("select"));
+
+        let body = match ctx.remaining() {
+            Some(left) if is_select => {
+                let ms = left.as_millis().clamp(1, u32::MAX as u128);
+                format!("SELECT /*+ MAX_EXECUTION_TIME({ms}) */{}", &trimmed[6..])
+            }
+            _ => sql.to_string(),
+        };
+
+        match ctx.sql_comment() {
+            Some(comment) => format!("{comment} {body}"),
+            None => body,
+        }
+    }
+
     /// Converts a [`mysql::Row`] into a generic [`Row`].
     ///
-    /// Unsupported types (e.g., decimals, time) are temporarily stringified.
-    /// Extend [`Value`] as needed for stricter type support.
+    /// `DECIMAL` and `JSON` columns become [`Value::Decimal`] /
+    /// [`Value::Json`]; `TIME` is stringified.
     fn row_from_mysql(mut r: mysql::Row) -> GRow {
         // 列名を先にコピー（borrow 競合回避）
-        let names: Vec<String> = r
+        let columns: Vec<(String, ColumnType, bool)> = r
             .columns_ref()
             .iter()
-            .map(|c| c.name_str().to_string())
+            .map(|c| {
+                let binary = c.character_set() == BINARY_CHARSET && is_byte_column(c.column_type());
+                (c.name_str().to_string(), c.column_type(), binary)
+            })
             .collect();
 
         let mut out = GRow::default();
-        for (idx, name) in names.into_iter().enumerate() {
+        for (idx, (name, column_type, binary)) in columns.into_iter().enumerate() {
             let v = r
                 .take_opt::<My, _>(idx)
                 .unwrap_or(Ok(My::NULL))
                 .unwrap_or(My::NULL);
+            let value = match v {
+                // BLOB / BINARY / VARBINARY: keep the raw bytes.
+                My::Bytes(b) if binary => Value::Bin(b),
+                v => Self::value_from_mysql(v, column_type),
+            };
+            out.insert(name, value);
+        }
+        out
+    }
 
-            let vv = match v {
-                My::NULL => Value::Null,
-                My::Int(i) => Value::I64(i),
-                My::UInt(u) => Value::U64(u),
-
-                My::Float(f) => Value::F32(f),
-                My::Double(f) => Value::F64(f),
-
-                // BLOB/TEXT
-                My::Bytes(b) => match String::from_utf8(b) {
-                    Ok(s) => Value::Str(s),
-                    Err(e) => Value::Str(String::from_utf8_lossy(e.as_bytes()).into_owned()),
-                },
-
-                // DATE/DATETIME → NaiveDateTime
-                My::Date(y, m, d, hh, mm, ss, _micro) => {
-                    let date = NaiveDate::from_ymd_opt(y as i32, m as u32, d as u32)
-                        .unwrap_or_else(|| NaiveDate::from_ymd_opt(1970, 1, 1).unwrap());
-                    let time = NaiveTime::from_hms_opt(hh as u32, mm as u32, ss as u32)
-                        .unwrap_or_else(|| NaiveTime::from_hms_opt(0, 0, 0).unwrap());
-                    Value::DateTime(NaiveDateTime::new(date, time))
+    /// Converts one column value, using the column type to recognize
+    /// `DECIMAL` and `JSON` text.
+    fn value_from_mysql(v: My, column_type: ColumnType) -> Value {
+        match v {
+            My::NULL => Value::Null,
+            My::Int(i) => Value::I64(i),
+            My::UInt(u) => Value::U64(u),
+
+            My::Float(f) => Value::F32(f),
+            My::Double(f) => Value::F64(f),
+
+            // DECIMAL / JSON arrive as text; fall back to Str if unparsable.
+            My::Bytes(b)
+                if matches!(
+                    column_type,
+                    ColumnType::MYSQL_TYPE_DECIMAL | ColumnType::MYSQL_TYPE_NEWDECIMAL
+                ) =>
+            {
+                let text = String::from_utf8_lossy(&b);
+                match text.parse() {
+                    Ok(d) => Value::Decimal(d),
+                    Err(_) => Value::Str(text.into_owned()),
                 }
-
-                // TIME は（符号付き 日/時/分/秒.μ）→ String
-                My::Time(neg, days, hh, mm, ss, micro) => {
-                    // 例: "-001 12:34:56.789012"
-                    let sign = if neg { "-" } else { "" };
-                    let s = if micro > 0 {
-                        format!("{sign}{days:03} {hh:02}:{mm:02}:{ss:02}.{micro:06}")
-                    } else {
-                        format!("{sign}{days:03} {hh:02}:{mm:02}:{ss:02}")
-                    };
-                    Value::Str(s)
+            }
+            My::Bytes(b) if column_type == ColumnType::MYSQL_TYPE_JSON => {
+                match serde_json::from_slice(&b) {
+                    Ok(json) => Value::Json(json),
+                    Err(_) => Value::Str(String::from_utf8_lossy(&b).into_owned()),
                 }
-            };
+            }
+
+            // BLOB/TEXT
+            My::Bytes(b) => match String::from_utf8(b) {
+                Ok(s) => Value::Str(s),
+                Err(e) => Value::Str(String::from_utf8_lossy(e.as_bytes()).into_owned()),
+            },
+
+            // DATE/DATETIME → NaiveDateTime
+            My::Date(y, m, d, hh, mm, ss, _micro) => {
+                let date = NaiveDate::from_ymd_opt(y as i32, m as u32, d as u32)
+                    .unwrap_or_else(|| NaiveDate::from_ymd_opt(1970, 1, 1).unwrap());
+                let time = NaiveTime::from_hms_opt(hh as u32, mm as u32, ss as u32)
+                    .unwrap_or_else(|| NaiveTime::from_hms_opt(0, 0, 0).unwrap());
+                Value::DateTime(NaiveDateTime::new(date, time))
+            }
 
-            out.insert(name, vv);
+            // TIME は（符号付き 日/時/分/秒.μ）→ String
+            My::Time(neg, days, hh, mm, ss, micro) => {
+                // 例: "-001 12:34:56.789012"
+                let sign = if neg { "-" } else { "" };
+                let s = if micro > 0 {
+                    format!("{sign}{days:03} {hh:02}:{mm:02}:{ss:02}.{micro:06}")
+                } else {
+                    format!("{sign}{days:03} {hh:02}:{mm:02}:{ss:02}")
+                };
+                Value::Str(s)
+            }
         }
-        out
     }
 }
 
 impl Db for MySqlDb {
     fn fetch_one(&self, sql: &str, params_in: &[Param]) -> Result<Option<GRow>> {
-        let params = Self::to_mysql_params(params_in);
-        let mut conn = self.pool.get_conn().context("get_conn failed")?;
+        self.fetch_one_with_ctx(&DbContext::default(), sql, params_in)
+    }
 
-        dbglog!("-- exec_first about to run\nSQL: {sql}");
-        for (i, p) in params_in.iter().enumerate() {
-            dbglog!("param[{i}] = {:?}", p);
-        }
+    fn fetch_one_with_ctx(
+        &self,
+        ctx: &DbContext,
+        sql: &str,
+        params_in: &[Param],
+    ) -> Result<Option<GRow>> {
+        ctx.ensure_not_expired()?;
+        let sql = &Self::annotate_sql(ctx, sql);
+        let params = Self::to_mysql_params(params_in);
+        let _checkout = self.drain.enter()?;
+        let mut conn = self.get_conn()?;
 
-        let res: std::result::Result<Option<mysql::Row>, MyError> = conn.exec_first(sql, params);
-        if let Err(ref e) = res {
-            eprintln!("exec_first failed: {}", mysql_err_summary(e));
-            dbglog!("exec_first failed (debug): {e:?}");
-            log_who_where(&mut conn);
-        }
-        let row_opt = res.context("exec_first failed")?;
-        dbglog!("fetch_one: row_present={}", row_opt.is_some());
+        let span = self.span("fetch_one", sql, params_in);
+        let row_opt = conn
+            .exec_first(sql, params)
+            .map_err(|e| self.statement_error(&span, &mut conn, e, "exec_first failed"))?;
+        span.rows(usize::from(row_opt.is_some()));
+        span.ok();
 
         Ok(row_opt.map(Self::row_from_mysql))
     }
 
     fn fetch_all(&self, sql: &str, params_in: &[Param]) -> Result<Vec<GRow>> {
+        self.fetch_all_with_ctx(&DbContext::default(), sql, params_in)
+    }
+
+    fn fetch_all_with_ctx(
+        &self,
+        ctx: &DbContext,
+        sql: &str,
+        params_in: &[Param],
+    ) -> Result<Vec<GRow>> {
+        ctx.ensure_not_expired()?;
+        let sql = &Self::annotate_sql(ctx, sql);
         let params = Self::to_mysql_params(params_in);
-        let mut conn = self.pool.get_conn().context("get_conn failed")?;
+        let _checkout = self.drain.enter()?;
+        let mut conn = self.get_conn()?;
 
-        dbglog!("-- exec(fetch_all) about to run\nSQL: {sql}");
-        for (i, p) in params_in.iter().enumerate() {
-            dbglog!("param[{i}] = {:?}", p);
-        }
+        let span = self.span("fetch_all", sql, params_in);
+        let rows: Vec<mysql::Row> = conn
+            .exec(sql, params)
+            .map_err(|e| self.statement_error(&span, &mut conn, e, "exec (fetch_all) failed"))?;
+        span.rows(rows.len());
+        span.ok();
+
+        Ok(rows.into_iter().map(Self::row_from_mysql).collect())
+    }
 
-        let res: std::result::Result<Vec<mysql::Row>, MyError> = conn.exec(sql, params);
-        if let Err(ref e) = res {
-            eprintln!("exec (fetch_all) failed: {}", mysql_err_summary(e));
-            dbglog!("exec (fetch_all) failed (debug): {e:?}");
-            log_who_where(&mut conn);
+    /// Streams rows with `exec_iter` on a worker thread that owns the pooled
+    /// connection; at most [`STREAM_BUFFER`] rows are buffered ahead of the
+    /// consumer. Dropping the iterator stops the worker early.
+    fn fetch_stream(&self, sql: &str, params_in: &[Param]) -> Result<RowStream<'_>> {
+        let sql = sql.to_string();
+        let params = Self::to_mysql_params(params_in);
+        let checkout = self.drain.enter()?;
+        let mut conn = self.get_conn()?;
+
+        let span = self.span("fetch_stream", &sql, params_in);
+
+        // `Ok(None)` acknowledges that the statement ran, so execution
+        // errors surface from this call rather than from the first `next()`.
+        let (tx, rx) = sync_channel::<Result<Option<GRow>>>(STREAM_BUFFER);
+        std::thread::Builder::new()
+            .name("mysql-fetch-stream".into())
+            .spawn(move || {
+                let _checkout = checkout;
+                let res = conn.exec_iter(sql.as_str(), params);
+                let mut result = match res {
+                    Ok(result) => result,
+                    Err(e) => {
+                        span.fail(mysql_err_code(&e), &mysql_err_summary(&e));
+                        let _ = tx.send(Err(
+                            anyhow::Error::new(e).context("exec_iter (fetch_stream) failed")
+                        ));
+                        return;
+                    }
+                };
+                if tx.send(Ok(None)).is_err() {
+                    return;
+                }
+                let mut n = 0usize;
+                for row in result.by_ref() {
+                    let item = row
+                        .map(|r| Some(Self::row_from_mysql(r)))
+                        .context("reading streamed row failed");
+                    if let Err(e) = &item {
+                        span.rows(n);
+                        span.fail(None, &format!("{e:#}"));
+                        let _ = tx.send(item);
+                        return;
+                    }
+                    if tx.send(item).is_err() {
+                        return;
+                    }
+                    n += 1;
+                }
+                span.rows(n);
+                span.ok();
+            })
+            .context("spawning fetch_stream worker failed")?;
+
+        match rx.recv() {
+            Ok(Ok(_)) => Ok(Box::new(StreamRows { rx })),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(anyhow::anyhow!("fetch_stream worker exited unexpectedly")),
         }
-        let rows = res.context("exec (fetch_all) failed")?;
-        dbglog!("fetch_all: rows={}", rows.len());
+    }
 
-        Ok(rows.into_iter().map(Self::row_from_mysql).collect())
+    /// Checks out a connection (with retries) and sends `COM_PING`.
+    fn ping(&self) -> Result<()> {
+        let _checkout = self.drain.enter()?;
+        let mut conn = self.get_conn()?;
+        conn.as_mut().ping().context("ping failed")
     }
 
     fn exec(&self, sql: &str, params_in: &[Param]) -> Result<u64> {
-        let params = Self::to_mysql_params(params_in);
-        let mut conn = self.pool.get_conn().context("get_conn failed")?;
+        self.exec_with_ctx(&DbContext::default(), sql, params_in)
+    }
 
-        dbglog!("-- exec_drop about to run\nSQL: {sql}");
-        for (i, p) in params_in.iter().enumerate() {
-            dbglog!("param[{i}] = {:?}", p);
-        }
+    fn exec_with_ctx(&self, ctx: &DbContext, sql: &str, params_in: &[Param]) -> Result<u64> {
+        ctx.ensure_writable()?;
+        ctx.ensure_not_expired()?;
+        let sql = &Self::annotate_sql(ctx, sql);
+        let params = Self::to_mysql_params(params_in);
+        let _checkout = self.drain.enter()?;
+        let mut conn = self.get_conn()?;
 
-        let res: std::result::Result<(), MyError> = conn.exec_drop(sql, params);
-        if let Err(ref e) = res {
-            eprintln!("exec_drop failed: {}", mysql_err_summary(e));
-            dbglog!("exec_drop failed (debug): {e:?}");
-            log_who_where(&mut conn);
-        }
-        res.context("exec_drop failed")?;
+        let span = self.span("exec", sql, params_in);
+        conn.exec_drop(sql, params)
+            .map_err(|e| self.statement_error(&span, &mut conn, e, "exec_drop failed"))?;
 
         let n = conn.affected_rows();
-        dbglog!("affected_rows = {n}");
+        span.affected(n);
+        span.ok();
         Ok(n)
     }
 
     fn exec_returning_last_insert_id(&self, sql: &str, params_in: &[Param]) -> Result<u64> {
+        self.exec_returning_last_insert_id_with_ctx(&DbContext::default(), sql, params_in)
+    }
+
+    fn exec_returning_last_insert_id_with_ctx(
+        &self,
+        ctx: &DbContext,
+        sql: &str,
+        params_in: &[Param],
+    ) -> Result<u64> {
+        ctx.ensure_writable()?;
+        ctx.ensure_not_expired()?;
+        let sql = &Self::annotate_sql(ctx, sql);
         let params = Self::to_mysql_params(params_in);
-        let mut conn = self.pool.get_conn().context("get_conn failed")?;
+        let _checkout = self.drain.enter()?;
+        let mut conn = self.get_conn()?;
 
-        dbglog!("-- exec_drop about to run");
-        dbglog!("SQL  : {sql}");
-        for (i, p) in params_in.iter().enumerate() {
-            dbglog!("param[{i}] = {:?}", p);
-        }
-
-        let res: std::result::Result<(), MyError> = conn.exec_drop(sql, params);
-        if let Err(ref e) = res {
-            eprintln!("exec_drop failed: {}", mysql_err_summary(e));
-            dbglog!("exec_drop failed (debug): {e:?}");
-            log_who_where(&mut conn);
-        }
-        res.context("exec_drop failed")?;
+        let span = self.span("exec_returning_last_insert_id", sql, params_in);
+        conn.exec_drop(sql, params)
+            .map_err(|e| self.statement_error(&span, &mut conn, e, "exec_drop failed"))?;
+        span.affected(conn.affected_rows());
+        span.ok();
 
         let id: Option<u64> = conn
             .query_first("SELECT LAST_INSERT_ID()")
@@ -283,9 +568,44 @@ impl Db for MySqlDb {
     }
 }
 
+/// Consumer side of [`MySqlDb::fetch_stream`].
+struct StreamRows {
+    rx: Receiver<Result<Option<GRow>>>,
+}
+
+impl Iterator for StreamRows {
+    type Item = Result<GRow>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rx.recv().ok()?.transpose()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn is_transient_distinguishes_network_from_server_errors() {
+        let io = MyError::IoError(std::io::Error::from(std::io::ErrorKind::ConnectionRefused));
+        assert!(is_transient(&io));
+        assert!(is_transient(&MyError::DriverError(
+            mysql::DriverError::ConnectTimeout
+        )));
+
+        let server = |code| {
+            MyError::MySqlError(mysql::MySqlError {
+                state: "HY000".into(),
+                message: "x".into(),
+                code,
+            })
+        };
+        assert!(is_transient(&server(1040)));
+        assert!(!is_transient(&server(1045)));
+        assert!(!is_transient(&MyError::DriverError(
+            mysql::DriverError::SetupError
+        )));
+    }
     use chrono::NaiveDate;
 
     /// Verifies primitive `Param` → `mysql::Value` conversions.
@@ -409,4 +729,69 @@ mod tests {
             other => panic!("expected Double(3.14159), got {other:?}"),
         }
     }
+
+    #[test]
+    fn decimal_and_json_params_are_sent_as_text() {
+        let d: crate::db::decimal::Decimal = "12.50".parse().unwrap();
+        let j = serde_json::json!({"a": [1, 2]});
+        assert_eq!(
+            MySqlDb::to_mysql_value(&Param::Decimal(&d)),
+            My::Bytes(b"12.50".to_vec())
+        );
+        assert_eq!(
+            MySqlDb::to_mysql_value(&Param::Json(&j)),
+            My::Bytes(br#"{"a":[1,2]}"#.to_vec())
+        );
+    }
+
+    #[test]
+    fn byte_columns_cover_blob_and_binary_strings() {
+        assert!(is_byte_column(ColumnType::MYSQL_TYPE_BLOB));
+        assert!(is_byte_column(ColumnType::MYSQL_TYPE_VAR_STRING));
+        assert!(!is_byte_column(ColumnType::MYSQL_TYPE_NEWDECIMAL));
+        assert!(!is_byte_column(ColumnType::MYSQL_TYPE_LONGLONG));
+    }
+
+    #[test]
+    fn value_from_mysql_types_decimal_and_json_columns() {
+        let v = MySqlDb::value_from_mysql(
+            My::Bytes(b"-0012.340".to_vec()),
+            ColumnType::MYSQL_TYPE_NEWDECIMAL,
+        );
+        assert!(matches!(v, Value::Decimal(d) if d.as_str() == "-12.340"));
+
+        let v = MySqlDb::value_from_mysql(
+            My::Bytes(br#"{"k":true}"#.to_vec()),
+            ColumnType::MYSQL_TYPE_JSON,
+        );
+        assert!(matches!(v, Value::Json(j) if j["k"] == true));
+
+        let v = MySqlDb::value_from_mysql(
+            My::Bytes(b"12.5".to_vec()),
+            ColumnType::MYSQL_TYPE_VAR_STRING,
+        );
+        assert!(matches!(v, Value::Str(s) if s == "12.5"));
+    }
+
+    /// `annotate_sql` leaves statements untouched for an empty context.
+    #[test]
+    fn annotate_sql_is_noop_without_context() {
+        let sql = "SELECT * FROM t WHERE id = ?";
+        assert_eq!(MySqlDb::annotate_sql(&DbContext::default(), sql), sql);
+    }
+
+    /// Request ids become a leading comment; deadlines hint only `SELECT`.
+    #[test]
+    fn annotate_sql_adds_comment_and_select_hint() {
+        let ctx = DbContext::new()
+            .request_id("req-1")
+            .timeout(std::time::Duration::from_secs(5));
+
+        let select = MySqlDb::annotate_sql(&ctx, "  select id FROM t");
+        assert!(select.starts_with("/* request_id=req-1 */ SELECT /*+ MAX_EXECUTION_TIME("));
+        assert!(select.ends_with(") */ id FROM t"));
+
+        let update = MySqlDb::annotate_sql(&ctx, "UPDATE t SET a = 1");
+        assert_eq!(update, "/* request_id=req-1 */ UPDATE t SET a = 1");
+    }
 }
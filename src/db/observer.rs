@@ -0,0 +1,176 @@
+//! # Query Observers
+//!
+//! [`QueryObserver`] is an optional hook that
+//! [`MySqlDb`](crate::db::mysql_adapter::MySqlDb) calls after every
+//! statement with the SQL, its duration and the [`QueryOutcome`].
+//!
+//! [`QueryMetrics`] is the built-in observer. It logs statements slower than
+//! a threshold and keeps counters rendered in the Prometheus text format:
+//!
+//! - `db_queries_total{outcome="ok"|"error"}`
+//! - `db_slow_queries_total`
+//! - `db_query_duration_seconds_sum` / `_count`
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use axum::{routing::get, Router};
+//! use wzs_web::config::db::{DbConfig, SlowQueryConfig};
+//! use wzs_web::db::{connection::get_pool, mysql_adapter::MySqlDb, observer::QueryMetrics};
+//!
+//! // Threshold from `SQL_SLOW_MS` (default 200 ms).
+//! let metrics = QueryMetrics::new(SlowQueryConfig::from_env().threshold);
+//! let db = MySqlDb::new(get_pool(&DbConfig::from_env())).observer(Arc::new(metrics.clone()));
+//!
+//! let app: Router = Router::new()
+//!     .route("/metrics/db", get(move || async move { metrics.render() }));
+//! ```
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tracing::warn;
+
+/// How a statement finished.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueryOutcome {
+    Ok,
+    /// Failed; `code` is the server error code when one was reported.
+    Error {
+        code: Option<u16>,
+    },
+}
+
+impl QueryOutcome {
+    /// Returns `true` for [`QueryOutcome::Ok`].
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Self::Ok)
+    }
+}
+
+/// Receives one call per executed statement.
+///
+/// Called on the thread that ran the statement; implementations should be
+/// cheap and must not block.
+pub trait QueryObserver: Send + Sync {
+    fn on_query(&self, sql: &str, elapsed: Duration, outcome: QueryOutcome);
+}
+
+#[derive(Default)]
+struct Inner {
+    ok: AtomicU64,
+    error: AtomicU64,
+    slow: AtomicU64,
+    duration_micros: AtomicU64,
+}
+
+/// Built-in [`QueryObserver`]: slow-query log plus counters.
+///
+/// Cloning is cheap; all clones share the same counters.
+#[derive(Clone)]
+pub struct QueryMetrics {
+    inner: Arc<Inner>,
+    slow_threshold: Duration,
+}
+
+impl QueryMetrics {
+    /// Creates zeroed counters; statements taking at least `slow_threshold`
+    /// are logged and counted as slow.
+    pub fn new(slow_threshold: Duration) -> Self {
+        Self {
+            inner: Arc::default(),
+            slow_threshold,
+        }
+    }
+
+    /// Total number of observed statements.
+    pub fn total(&self) -> u64 {
+        self.inner.ok.load(Ordering::Relaxed) + self.inner.error.load(Ordering::Relaxed)
+    }
+
+    /// Number of statements at or above the slow threshold.
+    pub fn slow(&self) -> u64 {
+        self.inner.slow.load(Ordering::Relaxed)
+    }
+
+    /// Renders the counters in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# TYPE db_queries_total counter\n");
+        for (outcome, counter) in [("ok", &self.inner.ok), ("error", &self.inner.error)] {
+            let _ = writeln!(
+                out,
+                "db_queries_total{{outcome=\"{outcome}\"}} {}",
+                counter.load(Ordering::Relaxed)
+            );
+        }
+        out.push_str("# TYPE db_slow_queries_total counter\n");
+        let _ = writeln!(out, "db_slow_queries_total {}", self.slow());
+
+        let secs = self.inner.duration_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        out.push_str("# TYPE db_query_duration_seconds summary\n");
+        let _ = writeln!(out, "db_query_duration_seconds_sum {secs}");
+        let _ = writeln!(out, "db_query_duration_seconds_count {}", self.total());
+        out
+    }
+}
+
+impl QueryObserver for QueryMetrics {
+    fn on_query(&self, sql: &str, elapsed: Duration, outcome: QueryOutcome) {
+        let counter = match outcome {
+            QueryOutcome::Ok => &self.inner.ok,
+            QueryOutcome::Error { .. } => &self.inner.error,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        self.inner
+            .duration_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+
+        if elapsed >= self.slow_threshold {
+            self.inner.slow.fetch_add(1, Ordering::Relaxed);
+            warn!(
+                elapsed_ms = elapsed.as_millis() as u64,
+                ok = outcome.is_ok(),
+                sql,
+                "slow query"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_outcomes_and_slow_queries() {
+        let metrics = QueryMetrics::new(Duration::from_millis(100));
+        metrics.on_query("SELECT 1", Duration::from_millis(5), QueryOutcome::Ok);
+        metrics.on_query("SELECT 2", Duration::from_millis(150), QueryOutcome::Ok);
+        metrics.on_query(
+            "UPDATE t",
+            Duration::from_millis(100),
+            QueryOutcome::Error { code: Some(1213) },
+        );
+
+        assert_eq!(metrics.total(), 3);
+        assert_eq!(metrics.slow(), 2);
+
+        let text = metrics.render();
+        assert!(text.contains("db_queries_total{outcome=\"ok\"} 2\n"));
+        assert!(text.contains("db_queries_total{outcome=\"error\"} 1\n"));
+        assert!(text.contains("db_slow_queries_total 2\n"));
+        assert!(text.contains("db_query_duration_seconds_sum 0.255\n"));
+        assert!(text.contains("db_query_duration_seconds_count 3\n"));
+    }
+
+    #[test]
+    fn clones_share_counters() {
+        let a = QueryMetrics::new(Duration::from_secs(1));
+        let b = a.clone();
+        b.on_query("SELECT 1", Duration::ZERO, QueryOutcome::Ok);
+        assert_eq!(a.total(), 1);
+    }
+}
@@ -4,8 +4,13 @@
 //! used by adapters such as the MySQL implementation.
 //!
 //! - [`Param`]: Represents SQL parameters.
-//! - [`Value`] / [`Row`]: Generic owned data representations.
+//! - [`Value`] / [`Row`]: Generic owned data representations; rows can be
+//!   introspected ([`Row::columns`], [`Row::iter`]) and serialized with
+//!   [`Row::to_json`].
 //! - [`Db`]: Defines minimal operations (`fetch_one`, `fetch_all`, `exec`, etc.).
+//!   Each has a `*_with_ctx` variant taking a [`DbContext`].
+//! - [`FromRow`] / [`FromColumn`]: Row → struct mapping, used by
+//!   `fetch_one_as` / `fetch_all_as` and [`impl_from_row!`](crate::impl_from_row).
 //!
 //! # Example
 //! ```rust,ignore
@@ -17,15 +22,22 @@
 //! ```
 use std::collections::HashMap;
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
 use chrono::NaiveDateTime;
 use uuid::Uuid;
 
+use crate::db::context::DbContext;
+use crate::db::crypto::FieldCipher;
+use crate::db::decimal::Decimal;
+
 /// SQL parameter types passed to a query.
 ///
 /// - `Str(&str)` holds a borrowed string reference.
 /// - `Null` represents an SQL NULL.
 /// - `DateTime` uses [`NaiveDateTime`] (no time zone).
+/// - `Encrypted` holds an already-encrypted value (see [`FieldCipher`]).
+/// - `Decimal` / `Json` bind `DECIMAL` and `JSON` columns.
 #[derive(Debug)]
 pub enum Param<'a> {
     I64(i64),
@@ -36,6 +48,9 @@ pub enum Param<'a> {
     Str(&'a str),
     DateTime(NaiveDateTime),
     Bin(&'a [u8]), // BINARY/VARBINARY 用
+    Encrypted(String),
+    Decimal(&'a Decimal),
+    Json(&'a serde_json::Value),
     Null,
 }
 
@@ -50,13 +65,19 @@ pub enum Value {
     Str(String),
     DateTime(NaiveDateTime),
     Bin(Vec<u8>), // 所有データとして保持（ライフタイム不要）
+    Decimal(Decimal),
+    Json(serde_json::Value),
     Null,
 }
 
 /// Represents a single database row (column name → value map).
+///
+/// Columns keep the order in which they were inserted (the adapters insert
+/// them in result-set order); see [`columns`](Row::columns) / [`iter`](Row::iter).
 #[derive(Debug, Clone, Default)]
 pub struct Row {
     cols: HashMap<String, Value>,
+    order: Vec<String>,
 }
 
 // ------------------------------
@@ -75,6 +96,18 @@ impl<'a> From<u64> for Param<'a> {
     }
 }
 
+impl<'a> From<&'a i64> for Param<'a> {
+    fn from(x: &'a i64) -> Self {
+        Param::I64(*x)
+    }
+}
+
+impl<'a> From<&'a u64> for Param<'a> {
+    fn from(x: &'a u64) -> Self {
+        Param::U64(*x)
+    }
+}
+
 impl<'a> From<f32> for Param<'a> {
     fn from(x: f32) -> Self {
         Param::F32(x)
@@ -99,6 +132,12 @@ impl<'a> From<&'a str> for Param<'a> {
     }
 }
 
+impl<'a> From<&'a String> for Param<'a> {
+    fn from(s: &'a String) -> Self {
+        Param::Str(s)
+    }
+}
+
 impl<'a> From<Option<&'a str>> for Param<'a> {
     fn from(x: Option<&'a str>) -> Self {
         match x {
@@ -123,12 +162,65 @@ impl<'a> From<Option<&'a [u8]>> for Param<'a> {
     }
 }
 
+impl<'a> From<&'a Decimal> for Param<'a> {
+    fn from(d: &'a Decimal) -> Self {
+        Param::Decimal(d)
+    }
+}
+
+impl<'a> From<&'a serde_json::Value> for Param<'a> {
+    fn from(v: &'a serde_json::Value) -> Self {
+        Param::Json(v)
+    }
+}
+
 impl<'a> From<&'a Uuid> for Param<'a> {
     fn from(u: &'a Uuid) -> Self {
         Param::Bin(u.as_bytes())
     }
 }
 
+impl Param<'_> {
+    /// Converts to an owned [`Value`] (e.g. to move parameters to another thread).
+    ///
+    /// `Encrypted` becomes `Str`; both are sent as text.
+    pub fn to_value(&self) -> Value {
+        match self {
+            Param::I64(v) => Value::I64(*v),
+            Param::U64(v) => Value::U64(*v),
+            Param::F32(v) => Value::F32(*v),
+            Param::F64(v) => Value::F64(*v),
+            Param::Bool(v) => Value::Bool(*v),
+            Param::Str(s) => Value::Str((*s).to_string()),
+            Param::DateTime(dt) => Value::DateTime(*dt),
+            Param::Bin(b) => Value::Bin(b.to_vec()),
+            Param::Encrypted(s) => Value::Str(s.clone()),
+            Param::Decimal(d) => Value::Decimal((*d).clone()),
+            Param::Json(v) => Value::Json((*v).clone()),
+            Param::Null => Value::Null,
+        }
+    }
+}
+
+impl Value {
+    /// Borrows this value as a [`Param`].
+    pub fn as_param(&self) -> Param<'_> {
+        match self {
+            Value::I64(v) => Param::I64(*v),
+            Value::U64(v) => Param::U64(*v),
+            Value::F32(v) => Param::F32(*v),
+            Value::F64(v) => Param::F64(*v),
+            Value::Bool(v) => Param::Bool(*v),
+            Value::Str(s) => Param::Str(s),
+            Value::DateTime(dt) => Param::DateTime(*dt),
+            Value::Bin(b) => Param::Bin(b),
+            Value::Decimal(d) => Param::Decimal(d),
+            Value::Json(v) => Param::Json(v),
+            Value::Null => Param::Null,
+        }
+    }
+}
+
 // ------------------------------------
 // params! macro
 // ------------------------------------
@@ -164,8 +256,18 @@ macro_rules! params {
 
 impl Row {
     /// Inserts a new column (used internally by DB adapters).
+    ///
+    /// Re-inserting an existing column replaces its value in place.
     pub fn insert(&mut self, key: impl Into<String>, val: Value) {
-        self.cols.insert(key.into(), val);
+        let key = key.into();
+        if self.cols.insert(key.clone(), val).is_none() {
+            self.order.push(key);
+        }
+    }
+
+    /// Returns the raw [`Value`] of a column, if present.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.cols.get(key)
     }
 
     /// Returns a `u64` (accepts non-negative `i64`).
@@ -257,6 +359,42 @@ impl Row {
         }
     }
 
+    /// Decrypts a column written with [`Param::Encrypted`].
+    pub fn get_decrypted(&self, key: &str, cipher: &FieldCipher) -> Result<String> {
+        cipher.decrypt(&self.get_string(key)?)
+    }
+
+    /// Decrypts an optional encrypted column (`NULL` → `None`).
+    pub fn get_decrypted_opt(&self, key: &str, cipher: &FieldCipher) -> Result<Option<String>> {
+        self.get_string_opt(key)?
+            .map(|s| cipher.decrypt(&s))
+            .transpose()
+    }
+
+    /// Returns a [`Decimal`] (accepts integers and numeric strings).
+    pub fn get_decimal(&self, key: &str) -> Result<Decimal> {
+        match self.cols.get(key) {
+            Some(Value::Decimal(d)) => Ok(d.clone()),
+            Some(Value::I64(v)) => Ok(Decimal::from(*v)),
+            Some(Value::U64(v)) => Ok(Decimal::from(*v)),
+            Some(Value::Str(s)) => s
+                .parse()
+                .with_context(|| format!("column `{key}` is not Decimal")),
+            _ => bail!("column `{key}` is not Decimal"),
+        }
+    }
+
+    /// Returns a JSON document (accepts JSON text stored as a string).
+    pub fn get_json(&self, key: &str) -> Result<serde_json::Value> {
+        match self.cols.get(key) {
+            Some(Value::Json(v)) => Ok(v.clone()),
+            Some(Value::Str(s)) => {
+                serde_json::from_str(s).with_context(|| format!("column `{key}` is not Json"))
+            }
+            _ => bail!("column `{key}` is not Json"),
+        }
+    }
+
     /// Returns an optional [`NaiveDateTime`] (`NULL` → `None`).
     pub fn get_datetime_opt(&self, key: &str) -> Result<Option<NaiveDateTime>> {
         match self.cols.get(key) {
@@ -268,19 +406,348 @@ impl Row {
     }
 }
 
+// ------------------------------
+// Coercing Row accessors
+// ------------------------------
+
+/// Lenient accessors for columns whose [`Value`] kind depends on the
+/// protocol: the text protocol returns numbers as strings, while prepared
+/// statements return typed values of varying width.
+///
+/// Conversions are exact: a value that does not fit the target type, has a
+/// fractional part, or does not parse is an error, as is `NULL`.
+///
+/// # Example
+/// ```rust
+/// use wzs_web::db::port::{Row, Value};
+///
+/// let mut row = Row::default();
+/// row.insert("count", Value::Str("42".into()));
+/// row.insert("total", Value::U64(7));
+///
+/// assert_eq!(row.get_i64_coerced("count")?, 42);
+/// assert_eq!(row.get_string_coerced("total")?, "7");
+/// assert!(row.get_i64("count").is_err());
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+impl Row {
+    /// Returns an `i64`, converting other integer widths, whole floats,
+    /// whole decimals, booleans and numeric strings.
+    pub fn get_i64_coerced(&self, key: &str) -> Result<i64> {
+        self.coerce(key, "I64", |v| match v {
+            Value::U64(n) => i64::try_from(*n).ok(),
+            Value::Decimal(d) => whole_decimal(d)?.parse().ok(),
+            Value::Str(s) => s.trim().parse().ok(),
+            other => signed_int(other),
+        })
+    }
+
+    /// Returns a `u64`; same conversions as
+    /// [`get_i64_coerced`](Self::get_i64_coerced), rejecting negatives.
+    pub fn get_u64_coerced(&self, key: &str) -> Result<u64> {
+        self.coerce(key, "U64", |v| match v {
+            Value::U64(n) => Some(*n),
+            Value::Str(s) => s.trim().parse().ok(),
+            Value::Decimal(d) => whole_decimal(d)?.parse().ok(),
+            other => u64::try_from(signed_int(other)?).ok(),
+        })
+    }
+
+    /// Returns an `f64`, converting integers, decimals and numeric strings.
+    ///
+    /// Large integers and decimals may lose precision.
+    pub fn get_f64_coerced(&self, key: &str) -> Result<f64> {
+        self.coerce(key, "F64", |v| match v {
+            Value::F64(f) => Some(*f),
+            Value::F32(f) => Some(f64::from(*f)),
+            Value::I64(n) => Some(*n as f64),
+            Value::U64(n) => Some(*n as f64),
+            Value::Decimal(d) => Some(d.to_f64()),
+            Value::Str(s) => s.trim().parse().ok(),
+            _ => None,
+        })
+    }
+
+    /// Returns a `String`, formatting numbers, decimals, booleans (`"1"` /
+    /// `"0"`), datetimes, JSON and UTF-8 binary values.
+    pub fn get_string_coerced(&self, key: &str) -> Result<String> {
+        self.coerce(key, "String", |v| match v {
+            Value::Str(s) => Some(s.clone()),
+            Value::I64(n) => Some(n.to_string()),
+            Value::U64(n) => Some(n.to_string()),
+            Value::F32(f) => Some(f.to_string()),
+            Value::F64(f) => Some(f.to_string()),
+            Value::Bool(b) => Some(if *b { "1" } else { "0" }.to_string()),
+            Value::DateTime(dt) => Some(dt.format("%Y-%m-%d %H:%M:%S%.f").to_string()),
+            Value::Bin(b) => String::from_utf8(b.clone()).ok(),
+            Value::Decimal(d) => Some(d.to_string()),
+            Value::Json(j) => Some(j.to_string()),
+            Value::Null => None,
+        })
+    }
+
+    fn coerce<T>(
+        &self,
+        key: &str,
+        target: &str,
+        convert: impl FnOnce(&Value) -> Option<T>,
+    ) -> Result<T> {
+        let value = self
+            .cols
+            .get(key)
+            .ok_or_else(|| anyhow!("column `{key}` not found"))?;
+        convert(value).ok_or_else(|| {
+            anyhow!(
+                "column `{key}` cannot be coerced to {target} (found {})",
+                value.kind()
+            )
+        })
+    }
+}
+
+/// Integer value of `I64`, `Bool` and whole `F32` / `F64` values.
+fn signed_int(v: &Value) -> Option<i64> {
+    match v {
+        Value::I64(n) => Some(*n),
+        Value::Bool(b) => Some(i64::from(*b)),
+        Value::F32(f) => whole_f64(f64::from(*f)).and_then(i64_from_f64),
+        Value::F64(f) => whole_f64(*f).and_then(i64_from_f64),
+        _ => None,
+    }
+}
+
+/// Integer digits of a decimal whose fractional part is zero.
+fn whole_decimal(d: &Decimal) -> Option<&str> {
+    match d.as_str().split_once('.') {
+        Some((int, frac)) => frac.bytes().all(|b| b == b'0').then_some(int),
+        None => Some(d.as_str()),
+    }
+}
+
+fn whole_f64(f: f64) -> Option<f64> {
+    (f.is_finite() && f.fract() == 0.0).then_some(f)
+}
+
+fn i64_from_f64(f: f64) -> Option<i64> {
+    // `i64::MAX as f64` rounds up to 2^63, which is out of range.
+    (f >= i64::MIN as f64 && f < i64::MAX as f64).then_some(f as i64)
+}
+
+// ------------------------------
+// Row introspection
+// ------------------------------
+
+impl Row {
+    /// Column names in result-set order.
+    pub fn columns(&self) -> Vec<&str> {
+        self.order.iter().map(String::as_str).collect()
+    }
+
+    /// `(column, value)` pairs in result-set order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.order
+            .iter()
+            .filter_map(|k| self.cols.get(k).map(|v| (k.as_str(), v)))
+    }
+
+    /// Serializes the row as a JSON object (see [`Value::to_json`]).
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::Value::Object(
+            self.iter()
+                .map(|(k, v)| (k.to_string(), v.to_json()))
+                .collect(),
+        )
+    }
+}
+
+impl Value {
+    /// Converts the value to JSON without knowing the column type:
+    ///
+    /// | Value | JSON |
+    /// |-------|------|
+    /// | `I64`, `U64`, `F32`, `F64` | number (`null` for NaN / infinity) |
+    /// | `Bool` | boolean |
+    /// | `Str` | string |
+    /// | `DateTime` | string, `YYYY-MM-DD HH:MM:SS[.fff]` |
+    /// | `Bin` | standard base64 string |
+    /// | `Decimal` | string (keeps the exact digits) |
+    /// | `Json` | the value itself |
+    /// | `Null` | `null` |
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::Value as J;
+        match self {
+            Value::I64(n) => J::from(*n),
+            Value::U64(n) => J::from(*n),
+            Value::F32(f) => J::from(f64::from(*f)),
+            Value::F64(f) => J::from(*f),
+            Value::Bool(b) => J::Bool(*b),
+            Value::Str(s) => J::String(s.clone()),
+            Value::DateTime(dt) => J::String(dt.format("%Y-%m-%d %H:%M:%S%.f").to_string()),
+            Value::Bin(b) => J::String(STANDARD.encode(b)),
+            Value::Decimal(d) => J::String(d.to_string()),
+            Value::Json(j) => j.clone(),
+            Value::Null => J::Null,
+        }
+    }
+}
+
+// ------------------------------
+// Row → struct mapping
+// ------------------------------
+
+/// Types that can be read from a single column (see [`Row::col`]).
+///
+/// Implemented for the types covered by the `get_*` helpers and for
+/// `Option<T>` (`NULL` → `None`).
+pub trait FromColumn: Sized {
+    fn from_column(row: &Row, key: &str) -> Result<Self>;
+}
+
+macro_rules! from_column_via {
+    ($($ty:ty => $getter:ident),* $(,)?) => {
+        $(impl FromColumn for $ty {
+            fn from_column(row: &Row, key: &str) -> Result<Self> {
+                row.$getter(key)
+            }
+        })*
+    };
+}
+
+from_column_via! {
+    u64 => get_u64,
+    i64 => get_i64,
+    f32 => get_f32,
+    f64 => get_f64,
+    bool => get_bool,
+    String => get_string,
+    NaiveDateTime => get_datetime,
+    Vec<u8> => get_bin,
+    Uuid => get_uuid,
+    Decimal => get_decimal,
+    serde_json::Value => get_json,
+}
+
+impl<T: FromColumn> FromColumn for Option<T> {
+    fn from_column(row: &Row, key: &str) -> Result<Self> {
+        match row.cols.get(key) {
+            Some(Value::Null) => Ok(None),
+            _ => T::from_column(row, key).map(Some),
+        }
+    }
+}
+
+/// Types that can be hydrated from a [`Row`].
+///
+/// Implement by hand with [`Row::col`], or use [`impl_from_row!`](crate::impl_from_row).
+pub trait FromRow: Sized {
+    fn from_row(row: &Row) -> Result<Self>;
+}
+
+impl FromRow for Row {
+    fn from_row(row: &Row) -> Result<Self> {
+        Ok(row.clone())
+    }
+}
+
+/// Implements [`FromRow`] for a struct whose fields map to columns.
+///
+/// Each field is read with [`Row::col`]; use `field = "column"` when the
+/// names differ.
+///
+/// # Example
+/// ```rust
+/// use wzs_web::db::port::{FromRow, Row, Value};
+/// use wzs_web::impl_from_row;
+///
+/// struct User {
+///     id: u64,
+///     name: String,
+///     email: Option<String>,
+/// }
+///
+/// impl_from_row!(User { id = "user_id", name, email });
+///
+/// let mut row = Row::default();
+/// row.insert("user_id", Value::U64(7));
+/// row.insert("name", Value::Str("Alice".into()));
+/// row.insert("email", Value::Null);
+///
+/// let user = User::from_row(&row).unwrap();
+/// assert_eq!((user.id, user.name.as_str(), user.email), (7, "Alice", None));
+/// ```
+#[macro_export]
+macro_rules! impl_from_row {
+    ($ty:ident { $($field:ident $(= $col:literal)?),* $(,)? }) => {
+        impl $crate::db::port::FromRow for $ty {
+            fn from_row(row: &$crate::db::port::Row) -> $crate::anyhow::Result<Self> {
+                Ok(Self {
+                    $($field: row.col($crate::impl_from_row!(@col $field $(, $col)?))?,)*
+                })
+            }
+        }
+    };
+    (@col $field:ident) => { stringify!($field) };
+    (@col $field:ident, $col:literal) => { $col };
+}
+
+impl Row {
+    /// Reads a column as any [`FromColumn`] type.
+    ///
+    /// Errors name the column and, on a type mismatch, the stored kind; a
+    /// missing column lists the available ones.
+    pub fn col<T: FromColumn>(&self, key: &str) -> Result<T> {
+        let Some(value) = self.cols.get(key) else {
+            let mut names: Vec<&str> = self.cols.keys().map(String::as_str).collect();
+            names.sort_unstable();
+            bail!("column `{key}` not found (available: {})", names.join(", "));
+        };
+        T::from_column(self, key).map_err(|e| anyhow!("{e} (found {})", value.kind()))
+    }
+}
+
+impl Value {
+    fn kind(&self) -> &'static str {
+        match self {
+            Value::I64(_) => "I64",
+            Value::U64(_) => "U64",
+            Value::F32(_) => "F32",
+            Value::F64(_) => "F64",
+            Value::Bool(_) => "Bool",
+            Value::Str(_) => "Str",
+            Value::DateTime(_) => "DateTime",
+            Value::Bin(_) => "Bin",
+            Value::Decimal(_) => "Decimal",
+            Value::Json(_) => "Json",
+            Value::Null => "NULL",
+        }
+    }
+}
+
 /// Helper to build `Vec<Param>` without using the [`params!`] macro.
 pub fn params<'a>(xs: impl Into<Vec<Param<'a>>>) -> Vec<Param<'a>> {
     xs.into()
 }
 
+/// Lazily produced rows from [`Db::fetch_stream`].
+pub type RowStream<'a> = Box<dyn Iterator<Item = Result<Row>> + Send + 'a>;
+
 /// Database abstraction (synchronous).
 ///
-/// For async support, define an equivalent trait with `async_trait`.
+/// For async code, see [`AsyncDb`](crate::db::async_port::AsyncDb).
 pub trait Db: Send + Sync + 'static {
     fn fetch_one(&self, sql: &str, params: &[Param]) -> Result<Option<Row>>;
 
     fn fetch_all(&self, sql: &str, params: &[Param]) -> Result<Vec<Row>>;
 
+    /// Like [`fetch_all`](Db::fetch_all), but yields rows one at a time so
+    /// large result sets (exports) are never held in memory at once.
+    ///
+    /// The default materializes via `fetch_all`; adapters backed by a
+    /// streaming driver API should override it.
+    fn fetch_stream(&self, sql: &str, params: &[Param]) -> Result<RowStream<'_>> {
+        Ok(Box::new(self.fetch_all(sql, params)?.into_iter().map(Ok)))
+    }
+
     /// Execute a write operation (`INSERT`, `UPDATE`, `DELETE`).
     ///
     /// Returns affected row count.
@@ -288,12 +755,79 @@ pub trait Db: Send + Sync + 'static {
 
     /// Execute and return `LAST_INSERT_ID()` (for inserts).
     fn exec_returning_last_insert_id(&self, sql: &str, params: &[Param]) -> Result<u64>;
+
+    /// Verifies connectivity (for health checks).
+    ///
+    /// The default runs `SELECT 1`; adapters may use a cheaper protocol ping.
+    fn ping(&self) -> Result<()> {
+        self.fetch_one("SELECT 1", &[]).map(|_| ())
+    }
+
+    /// [`fetch_one`](Db::fetch_one) under a [`DbContext`].
+    ///
+    /// The default checks the deadline and delegates; adapters may override
+    /// to tag statements and bound execution time.
+    fn fetch_one_with_ctx(
+        &self,
+        ctx: &DbContext,
+        sql: &str,
+        params: &[Param],
+    ) -> Result<Option<Row>> {
+        ctx.ensure_not_expired()?;
+        self.fetch_one(sql, params)
+    }
+
+    /// [`fetch_all`](Db::fetch_all) under a [`DbContext`].
+    fn fetch_all_with_ctx(&self, ctx: &DbContext, sql: &str, params: &[Param]) -> Result<Vec<Row>> {
+        ctx.ensure_not_expired()?;
+        self.fetch_all(sql, params)
+    }
+
+    /// [`exec`](Db::exec) under a [`DbContext`]. Rejected when read-only.
+    fn exec_with_ctx(&self, ctx: &DbContext, sql: &str, params: &[Param]) -> Result<u64> {
+        ctx.ensure_writable()?;
+        ctx.ensure_not_expired()?;
+        self.exec(sql, params)
+    }
+
+    /// [`exec_returning_last_insert_id`](Db::exec_returning_last_insert_id)
+    /// under a [`DbContext`]. Rejected when read-only.
+    fn exec_returning_last_insert_id_with_ctx(
+        &self,
+        ctx: &DbContext,
+        sql: &str,
+        params: &[Param],
+    ) -> Result<u64> {
+        ctx.ensure_writable()?;
+        ctx.ensure_not_expired()?;
+        self.exec_returning_last_insert_id(sql, params)
+    }
+}
+
+impl dyn Db {
+    /// [`fetch_one`](Db::fetch_one), hydrating the row as `T`.
+    pub fn fetch_one_as<T: FromRow>(&self, sql: &str, params: &[Param]) -> Result<Option<T>> {
+        self.fetch_one(sql, params)?
+            .map(|row| map_row(&row))
+            .transpose()
+    }
+
+    /// [`fetch_all`](Db::fetch_all), hydrating each row as `T`.
+    pub fn fetch_all_as<T: FromRow>(&self, sql: &str, params: &[Param]) -> Result<Vec<T>> {
+        self.fetch_all(sql, params)?.iter().map(map_row).collect()
+    }
+}
+
+/// [`FromRow::from_row`] with the target type in the error.
+pub(crate) fn map_row<T: FromRow>(row: &Row) -> Result<T> {
+    T::from_row(row).with_context(|| format!("mapping row to {}", std::any::type_name::<T>()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use chrono::NaiveDate;
+    use std::sync::Arc;
 
     #[test]
     fn params_macro_and_from_impls_work() {
@@ -307,6 +841,92 @@ mod tests {
         assert!(matches!(v[4], Param::Null));
     }
 
+    #[test]
+    fn columns_iter_and_to_json_follow_result_set_order() {
+        let mut row = Row::default();
+        row.insert("id", Value::U64(7));
+        row.insert("name", Value::Str("Alice".into()));
+        row.insert("price", Value::Decimal("12.50".parse().unwrap()));
+        row.insert("avatar", Value::Bin(vec![0xde, 0xad]));
+        row.insert(
+            "seen_at",
+            Value::DateTime(
+                NaiveDate::from_ymd_opt(2024, 5, 1)
+                    .unwrap()
+                    .and_hms_opt(9, 30, 0)
+                    .unwrap(),
+            ),
+        );
+        row.insert("ratio", Value::F64(f64::NAN));
+        row.insert("note", Value::Null);
+        row.insert("name", Value::Str("Bob".into()));
+
+        assert_eq!(
+            row.columns(),
+            ["id", "name", "price", "avatar", "seen_at", "ratio", "note"]
+        );
+        let pairs: Vec<(&str, String)> = row
+            .iter()
+            .take(2)
+            .map(|(k, v)| (k, format!("{v:?}")))
+            .collect();
+        assert_eq!(
+            pairs,
+            [("id", "U64(7)".into()), ("name", "Str(\"Bob\")".into())]
+        );
+
+        assert_eq!(
+            row.to_json(),
+            serde_json::json!({
+                "id": 7,
+                "name": "Bob",
+                "price": "12.50",
+                "avatar": "3q0=",
+                "seen_at": "2024-05-01 09:30:00",
+                "ratio": null,
+                "note": null,
+            })
+        );
+    }
+
+    #[test]
+    fn coerced_getters_convert_between_protocol_representations() {
+        let mut r = Row::default();
+        r.insert("s", Value::Str(" 42 ".into()));
+        r.insert("u", Value::U64(7));
+        r.insert("neg", Value::I64(-3));
+        r.insert("f", Value::F64(5.0));
+        r.insert("frac", Value::F64(5.5));
+        r.insert("dec", Value::Decimal("12.00".parse().unwrap()));
+        r.insert("big", Value::U64(u64::MAX));
+        r.insert("b", Value::Bool(true));
+        r.insert("bin", Value::Bin(b"abc".to_vec()));
+        r.insert("null", Value::Null);
+
+        assert_eq!(r.get_i64_coerced("s").unwrap(), 42);
+        assert_eq!(r.get_i64_coerced("u").unwrap(), 7);
+        assert_eq!(r.get_i64_coerced("f").unwrap(), 5);
+        assert_eq!(r.get_i64_coerced("dec").unwrap(), 12);
+        assert_eq!(r.get_u64_coerced("big").unwrap(), u64::MAX);
+        assert_eq!(r.get_u64_coerced("b").unwrap(), 1);
+        assert_eq!(r.get_f64_coerced("s").unwrap(), 42.0);
+        assert_eq!(r.get_f64_coerced("dec").unwrap(), 12.0);
+        assert_eq!(r.get_string_coerced("neg").unwrap(), "-3");
+        assert_eq!(r.get_string_coerced("dec").unwrap(), "12.00");
+        assert_eq!(r.get_string_coerced("bin").unwrap(), "abc");
+
+        assert!(r.get_i64_coerced("frac").is_err());
+        assert!(r.get_i64_coerced("big").is_err());
+        assert!(r.get_u64_coerced("neg").is_err());
+        assert!(r.get_string_coerced("null").is_err());
+        let err = r.get_i64_coerced("bin").unwrap_err().to_string();
+        assert!(
+            err.contains("cannot be coerced to I64 (found Bin)"),
+            "{err}"
+        );
+        assert!(r.get_i64_coerced("missing").is_err());
+    }
+
     #[test]
     fn row_getters_happy_paths() {
         let mut r = Row::default();
@@ -402,4 +1022,231 @@ mod tests {
         let e2 = r.get_f64("not_f64").unwrap_err().to_string();
         assert!(e2.contains("is not F64"));
     }
+
+    #[test]
+    fn params_round_trip_through_owned_values() {
+        let dt = NaiveDate::from_ymd_opt(2024, 1, 2)
+            .unwrap()
+            .and_hms_opt(3, 4, 5)
+            .unwrap();
+        let bin = [1u8, 2, 3];
+        let ps = [
+            Param::I64(-1),
+            Param::Str("a"),
+            Param::Bin(&bin),
+            Param::DateTime(dt),
+            Param::Encrypted("k1:xyz".into()),
+            Param::Null,
+        ];
+
+        let owned: Vec<Value> = ps.iter().map(Param::to_value).collect();
+        let back: Vec<Param> = owned.iter().map(Value::as_param).collect();
+
+        assert!(matches!(back[0], Param::I64(-1)));
+        assert!(matches!(back[1], Param::Str("a")));
+        assert!(matches!(back[2], Param::Bin(b) if b == [1, 2, 3]));
+        assert!(matches!(back[3], Param::DateTime(d) if d == dt));
+        assert!(matches!(back[4], Param::Str("k1:xyz")));
+        assert!(matches!(back[5], Param::Null));
+    }
+
+    #[test]
+    fn row_get_decimal_and_json() {
+        let price: Decimal = "19.90".parse().unwrap();
+        let mut r = Row::default();
+        r.insert("price", Value::Decimal(price.clone()));
+        r.insert("qty", Value::U64(3));
+        r.insert("legacy", Value::Str("0.5".into()));
+        r.insert("meta", Value::Json(serde_json::json!({"tags": ["a"]})));
+        r.insert("meta_text", Value::Str(r#"{"n":1}"#.into()));
+        r.insert("name", Value::Str("x".into()));
+        r.insert("gone", Value::Null);
+
+        assert_eq!(r.get_decimal("price").unwrap(), price);
+        assert_eq!(r.get_decimal("qty").unwrap().as_str(), "3");
+        assert_eq!(r.get_decimal("legacy").unwrap().as_str(), "0.5");
+        assert!(r.get_decimal("name").is_err());
+        assert_eq!(r.get_json("meta").unwrap()["tags"][0], "a");
+        assert_eq!(r.get_json("meta_text").unwrap()["n"], 1);
+        assert!(r.get_json("name").is_err());
+        assert_eq!(r.col::<Option<Decimal>>("gone").unwrap(), None);
+
+        let back = Value::Decimal(price.clone());
+        assert!(matches!(back.as_param(), Param::Decimal(d) if *d == price));
+    }
+
+    /// Minimal `Db` that counts delegated calls.
+    #[derive(Default)]
+    struct CountingDb {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl Db for CountingDb {
+        fn fetch_one(&self, _sql: &str, _params: &[Param]) -> Result<Option<Row>> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(None)
+        }
+
+        fn fetch_all(&self, _sql: &str, _params: &[Param]) -> Result<Vec<Row>> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(vec![])
+        }
+
+        fn exec(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(1)
+        }
+
+        fn exec_returning_last_insert_id(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn with_ctx_defaults_reject_writes_on_read_only_context() {
+        let db = CountingDb::default();
+        let ctx = DbContext::new().read_only();
+
+        assert!(db.fetch_one_with_ctx(&ctx, "SELECT 1", &[]).is_ok());
+        assert!(db.fetch_all_with_ctx(&ctx, "SELECT 1", &[]).is_ok());
+        assert!(db.exec_with_ctx(&ctx, "DELETE FROM t", &[]).is_err());
+        assert!(db
+            .exec_returning_last_insert_id_with_ctx(&ctx, "INSERT INTO t VALUES ()", &[])
+            .is_err());
+        assert_eq!(db.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn with_ctx_defaults_reject_expired_deadline() {
+        let db = CountingDb::default();
+        let ctx = DbContext::new()
+            .deadline(std::time::Instant::now() - std::time::Duration::from_millis(1));
+
+        assert!(db.fetch_one_with_ctx(&ctx, "SELECT 1", &[]).is_err());
+        assert!(db.exec_with_ctx(&ctx, "UPDATE t SET a = 1", &[]).is_err());
+        assert_eq!(db.calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn row_get_decrypted_reads_encrypted_columns() {
+        let cfg = crate::config::crypto::CryptoConfig::from_env_with(|k| match k {
+            "DB_ENCRYPTION_KEYS" => Some("k1:test".into()),
+            _ => None,
+        });
+        let cipher = FieldCipher::new(&cfg).unwrap();
+
+        let mut r = Row::default();
+        r.insert(
+            "email",
+            Value::Str(cipher.encrypt("a@example.com").unwrap()),
+        );
+        r.insert("phone", Value::Null);
+
+        assert_eq!(r.get_decrypted("email", &cipher).unwrap(), "a@example.com");
+        assert_eq!(r.get_decrypted_opt("phone", &cipher).unwrap(), None);
+        assert_eq!(
+            r.get_decrypted_opt("email", &cipher).unwrap().as_deref(),
+            Some("a@example.com")
+        );
+    }
+
+    #[derive(Debug)]
+    struct Member {
+        id: u64,
+        name: String,
+        nickname: Option<String>,
+        active: bool,
+    }
+
+    crate::impl_from_row!(Member { id = "member_id", name, nickname, active });
+
+    fn member_row() -> Row {
+        let mut r = Row::default();
+        r.insert("member_id", Value::I64(3));
+        r.insert("name", Value::Str("Alice".into()));
+        r.insert("nickname", Value::Null);
+        r.insert("active", Value::U64(1));
+        r
+    }
+
+    struct OneRowDb(Row);
+
+    impl Db for OneRowDb {
+        fn fetch_one(&self, _: &str, _: &[Param]) -> Result<Option<Row>> {
+            Ok(Some(self.0.clone()))
+        }
+
+        fn fetch_all(&self, _: &str, _: &[Param]) -> Result<Vec<Row>> {
+            Ok(vec![self.0.clone(), self.0.clone()])
+        }
+
+        fn exec(&self, _: &str, _: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+
+        fn exec_returning_last_insert_id(&self, _: &str, _: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn fetch_stream_default_yields_fetch_all_rows() {
+        let db = OneRowDb(member_row());
+        let rows: Vec<Row> = db
+            .fetch_stream("SELECT * FROM members", &[])
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].get_string("name").unwrap(), "Alice");
+    }
+
+    #[test]
+    fn col_reads_typed_and_optional_values() {
+        let r = member_row();
+        assert_eq!(r.col::<u64>("member_id").unwrap(), 3);
+        assert_eq!(r.col::<Option<String>>("nickname").unwrap(), None);
+        assert_eq!(
+            r.col::<Option<String>>("name").unwrap().as_deref(),
+            Some("Alice")
+        );
+        assert!(r.col::<bool>("active").unwrap());
+    }
+
+    #[test]
+    fn col_errors_name_column_and_found_kind() {
+        let r = member_row();
+
+        let err = r.col::<String>("member_id").unwrap_err().to_string();
+        assert_eq!(err, "column `member_id` is not String (found I64)");
+
+        let err = r.col::<u64>("email").unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "column `email` not found (available: active, member_id, name, nickname)"
+        );
+    }
+
+    #[test]
+    fn fetch_as_hydrates_structs_with_type_in_errors() {
+        let db: Arc<dyn Db> = Arc::new(OneRowDb(member_row()));
+
+        let m: Member = db.fetch_one_as("SELECT ...", &[]).unwrap().unwrap();
+        assert_eq!(
+            (m.id, m.name.as_str(), m.nickname, m.active),
+            (3, "Alice", None, true)
+        );
+        assert_eq!(
+            db.fetch_all_as::<Member>("SELECT ...", &[]).unwrap().len(),
+            2
+        );
+
+        let mut bad = member_row();
+        bad.insert("name", Value::I64(1));
+        let db: Arc<dyn Db> = Arc::new(OneRowDb(bad));
+        let err = db.fetch_one_as::<Member>("SELECT ...", &[]).unwrap_err();
+        assert!(format!("{err:#}").contains("mapping row to "));
+        assert!(format!("{err:#}").contains("Member: column `name` is not String (found I64)"));
+    }
 }
@@ -0,0 +1,431 @@
+//! # Query Builder
+//!
+//! [`QueryBuilder`] assembles a statement with a dynamic `WHERE` clause,
+//! whitelisted `ORDER BY` and `LIMIT` / `OFFSET`, producing a
+//! `(sql, Vec<Param>)` pair for the [`Db`](crate::db::port::Db) port.
+//!
+//! Injection safety:
+//! - values are always bound as `?` placeholders, never spliced into SQL
+//! - column names must be plain identifiers (`name` or `table.name`)
+//! - sort columns must appear in the caller's whitelist
+//!
+//! Invalid input does not panic; the first error is reported by
+//! [`build`](QueryBuilder::build).
+//!
+//! # Example
+//! ```rust
+//! use wzs_web::db::query::{QueryBuilder, SortDir};
+//!
+//! let status: Option<&str> = Some("active");
+//! let ids = [3u64, 5, 8];
+//!
+//! let (sql, params) = QueryBuilder::new("SELECT id, name FROM users")
+//!     .when(status.is_some(), |q| q.eq("status", status))
+//!     .in_list("id", ids)
+//!     .like("name", "%ali%")
+//!     .order_by("created_at", SortDir::Desc, &["name", "created_at"])
+//!     .limit(20)
+//!     .offset(40)
+//!     .build()?;
+//!
+//! assert_eq!(
+//!     sql,
+//!     "SELECT id, name FROM users WHERE status = ? AND id IN (?, ?, ?) AND name LIKE ? \
+//!      ORDER BY created_at DESC LIMIT ? OFFSET ?"
+//! );
+//! assert_eq!(params.len(), 7);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::db::port::Param;
+use crate::db::repository::ident;
+
+/// Sort direction for [`QueryBuilder::order_by`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SortDir {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortDir {
+    /// Returns the SQL keyword.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Asc => "ASC",
+            Self::Desc => "DESC",
+        }
+    }
+}
+
+impl fmt::Display for SortDir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SortDir {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "asc" => Ok(Self::Asc),
+            "desc" => Ok(Self::Desc),
+            _ => bail!("unsupported sort direction: {s}"),
+        }
+    }
+}
+
+/// Builds a parameterized statement from a trusted base query.
+#[derive(Debug)]
+pub struct QueryBuilder<'a> {
+    base: String,
+    conditions: Vec<String>,
+    params: Vec<Param<'a>>,
+    order: Vec<String>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+    error: Option<anyhow::Error>,
+}
+
+impl<'a> QueryBuilder<'a> {
+    /// Starts from `base`, e.g. `"SELECT id, name FROM users"`.
+    ///
+    /// `base` is used verbatim and must not contain user input.
+    pub fn new(base: impl Into<String>) -> Self {
+        Self {
+            base: base.into(),
+            conditions: Vec::new(),
+            params: Vec::new(),
+            order: Vec::new(),
+            limit: None,
+            offset: None,
+            error: None,
+        }
+    }
+
+    /// Adds `column = ?`.
+    pub fn eq(self, column: &str, value: impl Into<Param<'a>>) -> Self {
+        self.compare(column, "=", value.into())
+    }
+
+    /// Adds `column <> ?`.
+    pub fn ne(self, column: &str, value: impl Into<Param<'a>>) -> Self {
+        self.compare(column, "<>", value.into())
+    }
+
+    /// Adds `column < ?`.
+    pub fn lt(self, column: &str, value: impl Into<Param<'a>>) -> Self {
+        self.compare(column, "<", value.into())
+    }
+
+    /// Adds `column <= ?`.
+    pub fn le(self, column: &str, value: impl Into<Param<'a>>) -> Self {
+        self.compare(column, "<=", value.into())
+    }
+
+    /// Adds `column > ?`.
+    pub fn gt(self, column: &str, value: impl Into<Param<'a>>) -> Self {
+        self.compare(column, ">", value.into())
+    }
+
+    /// Adds `column >= ?`.
+    pub fn ge(self, column: &str, value: impl Into<Param<'a>>) -> Self {
+        self.compare(column, ">=", value.into())
+    }
+
+    /// Adds `column LIKE ?`. Wildcards in `pattern` are the caller's choice.
+    pub fn like(self, column: &str, pattern: impl Into<Param<'a>>) -> Self {
+        self.compare(column, "LIKE", pattern.into())
+    }
+
+    /// Adds `column IS NULL`.
+    pub fn is_null(self, column: &str) -> Self {
+        self.column_condition(column, |c| format!("{c} IS NULL"), Vec::new())
+    }
+
+    /// Adds `column IS NOT NULL`.
+    pub fn is_not_null(self, column: &str) -> Self {
+        self.column_condition(column, |c| format!("{c} IS NOT NULL"), Vec::new())
+    }
+
+    /// Adds `column IN (?, ...)`.
+    ///
+    /// An empty list matches nothing (`1 = 0`) rather than producing invalid SQL.
+    pub fn in_list<I, P>(self, column: &str, values: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<Param<'a>>,
+    {
+        let values: Vec<Param<'a>> = values.into_iter().map(Into::into).collect();
+        if values.is_empty() {
+            return self.column_condition(column, |_| "1 = 0".into(), values);
+        }
+        let marks = vec!["?"; values.len()].join(", ");
+        self.column_condition(column, |c| format!("{c} IN ({marks})"), values)
+    }
+
+    /// Adds a trusted condition with its own `?` placeholders,
+    /// e.g. `("created_at >= NOW() - INTERVAL ? DAY", params![7i64])`.
+    ///
+    /// The placeholder count must match `params`.
+    pub fn where_raw(mut self, fragment: &str, params: Vec<Param<'a>>) -> Self {
+        let marks = fragment.matches('?').count();
+        if marks != params.len() {
+            return self.fail(anyhow!(
+                "where_raw expects {marks} parameter(s), got {}: {fragment}",
+                params.len()
+            ));
+        }
+        self.conditions.push(format!("({fragment})"));
+        self.params.extend(params);
+        self
+    }
+
+    /// Applies `f` only when `cond` holds; for optional filters.
+    pub fn when(self, cond: bool, f: impl FnOnce(Self) -> Self) -> Self {
+        if cond {
+            f(self)
+        } else {
+            self
+        }
+    }
+
+    /// Adds an `ORDER BY` term; `column` must be listed in `allowed`.
+    ///
+    /// Typically `column` comes straight from a request parameter.
+    pub fn order_by(mut self, column: &str, dir: SortDir, allowed: &[&str]) -> Self {
+        if !allowed.contains(&column) {
+            return self.fail(anyhow!("sort column not allowed: {column}"));
+        }
+        if !is_column(column) {
+            return self.fail(anyhow!("invalid column name: {column}"));
+        }
+        self.order.push(format!("{column} {dir}"));
+        self
+    }
+
+    /// Sets `LIMIT ?`.
+    pub fn limit(mut self, n: u64) -> Self {
+        self.limit = Some(n);
+        self
+    }
+
+    /// Sets `OFFSET ?`. Requires [`limit`](Self::limit).
+    pub fn offset(mut self, n: u64) -> Self {
+        self.offset = Some(n);
+        self
+    }
+
+    /// Returns the statement and its parameters, in placeholder order.
+    ///
+    /// # Errors
+    /// Returns the first invalid column, sort column or `where_raw` mismatch,
+    /// or an error if `offset` is set without `limit`.
+    pub fn build(self) -> Result<(String, Vec<Param<'a>>)> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+
+        let mut sql = self.base.trim_end().to_string();
+        let mut params = self.params;
+
+        if !self.conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&self.conditions.join(" AND "));
+        }
+        if !self.order.is_empty() {
+            sql.push_str(" ORDER BY ");
+            sql.push_str(&self.order.join(", "));
+        }
+        match (self.limit, self.offset) {
+            (Some(limit), offset) => {
+                sql.push_str(" LIMIT ?");
+                params.push(Param::U64(limit));
+                if let Some(offset) = offset {
+                    sql.push_str(" OFFSET ?");
+                    params.push(Param::U64(offset));
+                }
+            }
+            (None, Some(_)) => bail!("offset requires limit"),
+            (None, None) => {}
+        }
+
+        Ok((sql, params))
+    }
+
+    fn compare(self, column: &str, op: &str, value: Param<'a>) -> Self {
+        self.column_condition(column, |c| format!("{c} {op} ?"), vec![value])
+    }
+
+    fn column_condition(
+        mut self,
+        column: &str,
+        render: impl FnOnce(&str) -> String,
+        params: Vec<Param<'a>>,
+    ) -> Self {
+        if !is_column(column) {
+            return self.fail(anyhow!("invalid column name: {column}"));
+        }
+        self.conditions.push(render(column));
+        self.params.extend(params);
+        self
+    }
+
+    /// Keeps the first error; later calls are still accepted but ignored by `build`.
+    fn fail(mut self, e: anyhow::Error) -> Self {
+        self.error.get_or_insert(e);
+        self
+    }
+}
+
+/// `name` or `table.name`, each part `[A-Za-z0-9_]+`.
+fn is_column(s: &str) -> bool {
+    let mut parts = 0;
+    for part in s.split('.') {
+        parts += 1;
+        if ident(part).is_err() {
+            return false;
+        }
+    }
+    parts <= 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::port::Value;
+
+    fn values(params: &[Param]) -> Vec<Value> {
+        params.iter().map(Param::to_value).collect()
+    }
+
+    #[test]
+    fn build_without_clauses_returns_base() {
+        let (sql, params) = QueryBuilder::new("SELECT * FROM t ").build().unwrap();
+        assert_eq!(sql, "SELECT * FROM t");
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn conditions_are_anded_and_params_follow_placeholder_order() {
+        let (sql, params) = QueryBuilder::new("SELECT * FROM users u")
+            .eq("u.status", "active")
+            .ge("u.age", 18i64)
+            .is_null("u.deleted_at")
+            .where_raw(
+                "u.score > ? OR u.vip = ?",
+                vec![Param::F64(1.5), Param::Bool(true)],
+            )
+            .limit(10)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM users u WHERE u.status = ? AND u.age >= ? AND u.deleted_at IS NULL \
+             AND (u.score > ? OR u.vip = ?) LIMIT ?"
+        );
+        let v = values(&params);
+        assert!(matches!(&v[0], Value::Str(s) if s == "active"));
+        assert!(matches!(v[1], Value::I64(18)));
+        assert!(matches!(v[2], Value::F64(x) if x == 1.5));
+        assert!(matches!(v[3], Value::Bool(true)));
+        assert!(matches!(v[4], Value::U64(10)));
+    }
+
+    #[test]
+    fn in_list_expands_placeholders_and_handles_empty_lists() {
+        let (sql, params) = QueryBuilder::new("SELECT * FROM t")
+            .in_list("id", [1u64, 2, 3])
+            .build()
+            .unwrap();
+        assert_eq!(sql, "SELECT * FROM t WHERE id IN (?, ?, ?)");
+        assert_eq!(params.len(), 3);
+
+        let (sql, params) = QueryBuilder::new("SELECT * FROM t")
+            .in_list("id", Vec::<u64>::new())
+            .build()
+            .unwrap();
+        assert_eq!(sql, "SELECT * FROM t WHERE 1 = 0");
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn when_applies_optional_filters() {
+        let name: Option<&str> = None;
+        let (sql, _) = QueryBuilder::new("SELECT * FROM t")
+            .when(name.is_some(), |q| q.eq("name", name))
+            .when(true, |q| q.is_not_null("email"))
+            .build()
+            .unwrap();
+        assert_eq!(sql, "SELECT * FROM t WHERE email IS NOT NULL");
+    }
+
+    #[test]
+    fn order_by_requires_whitelisted_columns() {
+        let allowed = ["name", "created_at"];
+        let (sql, _) = QueryBuilder::new("SELECT * FROM t")
+            .order_by("created_at", SortDir::Desc, &allowed)
+            .order_by("name", SortDir::Asc, &allowed)
+            .build()
+            .unwrap();
+        assert_eq!(sql, "SELECT * FROM t ORDER BY created_at DESC, name ASC");
+
+        let err = QueryBuilder::new("SELECT * FROM t")
+            .order_by("name; DROP TABLE t", SortDir::Asc, &allowed)
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("sort column not allowed"));
+    }
+
+    #[test]
+    fn invalid_columns_and_raw_mismatches_are_reported() {
+        for column in ["", "a b", "x = 1 OR 1", "a.b.c", "t.", "`t`"] {
+            let err = QueryBuilder::new("SELECT * FROM t")
+                .eq(column, 1i64)
+                .build()
+                .unwrap_err();
+            assert!(err.to_string().contains("invalid column name"), "{column}");
+        }
+
+        let err = QueryBuilder::new("SELECT * FROM t")
+            .where_raw("a = ? AND b = ?", vec![Param::I64(1)])
+            .eq("c", 2i64)
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("expects 2 parameter(s), got 1"));
+    }
+
+    #[test]
+    fn limit_and_offset_are_bound_parameters() {
+        let (sql, params) = QueryBuilder::new("SELECT * FROM t")
+            .limit(20)
+            .offset(40)
+            .build()
+            .unwrap();
+        assert_eq!(sql, "SELECT * FROM t LIMIT ? OFFSET ?");
+        assert!(matches!(
+            values(&params)[..],
+            [Value::U64(20), Value::U64(40)]
+        ));
+
+        assert!(QueryBuilder::new("SELECT * FROM t")
+            .offset(5)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn sort_dir_parses_case_insensitively() {
+        assert_eq!("DESC".parse::<SortDir>().unwrap(), SortDir::Desc);
+        assert_eq!("asc".parse::<SortDir>().unwrap(), SortDir::Asc);
+        assert!("up".parse::<SortDir>().is_err());
+        assert_eq!(SortDir::default().to_string(), "ASC");
+    }
+}
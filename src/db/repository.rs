@@ -0,0 +1,314 @@
+//! # Repository Base Trait
+//!
+//! [`Repository`] provides CRUD statements for a table from three pieces
+//! of information:
+//!
+//! - the table and primary key column ([`TABLE`](Repository::TABLE),
+//!   [`ID_COLUMN`](Repository::ID_COLUMN)),
+//! - how to read an entity ([`FromRow`], e.g. via
+//!   [`impl_from_row!`](crate::impl_from_row)),
+//! - how to write one ([`ToParams`]).
+//!
+//! | Method | Statement |
+//! |--------|-----------|
+//! | [`find_by_id`](Repository::find_by_id) | `SELECT * FROM t WHERE id = ?` |
+//! | [`list`](Repository::list) | `SELECT * FROM t ORDER BY id LIMIT ? OFFSET ?` |
+//! | [`insert`](Repository::insert) | `INSERT INTO t (cols…) VALUES (?…)` |
+//! | [`update`](Repository::update) | `UPDATE t SET col = ?… WHERE id = ?` |
+//! | [`delete`](Repository::delete) | `DELETE FROM t WHERE id = ?` |
+//!
+//! Every method has a default; override the ones that need custom SQL.
+//! Table and column names must be plain identifiers (ASCII alphanumerics
+//! and `_`) and are rejected otherwise.
+//!
+//! # Example
+//! ```rust
+//! use std::sync::Arc;
+//! use wzs_web::db::port::{Db, Param};
+//! use wzs_web::db::repository::{Repository, ToParams};
+//! use wzs_web::impl_from_row;
+//!
+//! struct User {
+//!     id: u64,
+//!     name: String,
+//! }
+//!
+//! impl_from_row!(User { id, name });
+//!
+//! impl ToParams for User {
+//!     fn to_params(&self) -> Vec<(&'static str, Param<'_>)> {
+//!         vec![("name", Param::from(self.name.as_str()))]
+//!     }
+//! }
+//!
+//! struct UserRepo {
+//!     db: Arc<dyn Db>,
+//! }
+//!
+//! impl Repository<User, u64> for UserRepo {
+//!     const TABLE: &'static str = "users";
+//!
+//!     fn db(&self) -> &dyn Db {
+//!         self.db.as_ref()
+//!     }
+//! }
+//!
+//! fn rename(repo: &UserRepo, id: u64) -> anyhow::Result<()> {
+//!     if let Some(mut user) = repo.find_by_id(&id)? {
+//!         user.name.push_str(" (renamed)");
+//!         repo.update(&user.id, &user)?;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use anyhow::{bail, Result};
+
+use crate::db::port::{Db, FromRow, Param};
+
+/// Column values written by [`Repository::insert`] and [`Repository::update`].
+///
+/// Usually omits the primary key when it is generated by the database.
+pub trait ToParams {
+    /// `(column, value)` pairs, in column order.
+    fn to_params(&self) -> Vec<(&'static str, Param<'_>)>;
+}
+
+/// CRUD operations on one table, generated from [`FromRow`] / [`ToParams`].
+///
+/// `Id` is the primary key type; any type whose reference converts into a
+/// [`Param`] works (`u64`, `i64`, `String`, `Uuid`, …).
+pub trait Repository<T, Id>
+where
+    T: FromRow + ToParams,
+    for<'a> &'a Id: Into<Param<'a>>,
+{
+    /// Table name.
+    const TABLE: &'static str;
+
+    /// Primary key column.
+    const ID_COLUMN: &'static str = "id";
+
+    /// Database the statements run against.
+    fn db(&self) -> &dyn Db;
+
+    /// Loads the row with primary key `id`.
+    fn find_by_id(&self, id: &Id) -> Result<Option<T>> {
+        let sql = format!(
+            "SELECT * FROM {} WHERE {} = ?",
+            ident(Self::TABLE)?,
+            ident(Self::ID_COLUMN)?
+        );
+        self.db().fetch_one_as(&sql, &[id.into()])
+    }
+
+    /// Loads one page of rows ordered by primary key.
+    fn list(&self, limit: u64, offset: u64) -> Result<Vec<T>> {
+        let sql = format!(
+            "SELECT * FROM {} ORDER BY {} LIMIT ? OFFSET ?",
+            ident(Self::TABLE)?,
+            ident(Self::ID_COLUMN)?
+        );
+        self.db()
+            .fetch_all_as(&sql, &[Param::U64(limit), Param::U64(offset)])
+    }
+
+    /// Inserts `entity` and returns the generated id (`0` if the table has
+    /// no `AUTO_INCREMENT` column).
+    fn insert(&self, entity: &T) -> Result<u64> {
+        let (columns, params) = split(entity.to_params())?;
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({placeholders})",
+            ident(Self::TABLE)?,
+            columns.join(", ")
+        );
+        self.db().exec_returning_last_insert_id(&sql, &params)
+    }
+
+    /// Overwrites the row with primary key `id`; returns the affected row count.
+    fn update(&self, id: &Id, entity: &T) -> Result<u64> {
+        let (columns, mut params) = split(entity.to_params())?;
+        let assignments: Vec<String> = columns.iter().map(|c| format!("{c} = ?")).collect();
+        let sql = format!(
+            "UPDATE {} SET {} WHERE {} = ?",
+            ident(Self::TABLE)?,
+            assignments.join(", "),
+            ident(Self::ID_COLUMN)?
+        );
+        params.push(id.into());
+        self.db().exec(&sql, &params)
+    }
+
+    /// Deletes the row with primary key `id`; returns the affected row count.
+    fn delete(&self, id: &Id) -> Result<u64> {
+        let sql = format!(
+            "DELETE FROM {} WHERE {} = ?",
+            ident(Self::TABLE)?,
+            ident(Self::ID_COLUMN)?
+        );
+        self.db().exec(&sql, &[id.into()])
+    }
+}
+
+/// `name` if it is a plain SQL identifier (`[A-Za-z0-9_]+`), safe to
+/// interpolate into a statement.
+pub(crate) fn ident(name: &str) -> Result<&str> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        bail!("invalid identifier `{name}`");
+    }
+    Ok(name)
+}
+
+fn split<'a>(pairs: Vec<(&'static str, Param<'a>)>) -> Result<(Vec<&'static str>, Vec<Param<'a>>)> {
+    if pairs.is_empty() {
+        bail!("ToParams returned no columns");
+    }
+    let mut columns = Vec::with_capacity(pairs.len());
+    let mut params = Vec::with_capacity(pairs.len());
+    for (column, param) in pairs {
+        columns.push(ident(column)?);
+        params.push(param);
+    }
+    Ok((columns, params))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use crate::db::port::{Row, Value};
+
+    #[derive(Debug, PartialEq)]
+    struct Post {
+        id: u64,
+        title: String,
+        views: i64,
+    }
+
+    crate::impl_from_row!(Post { id, title, views });
+
+    impl ToParams for Post {
+        fn to_params(&self) -> Vec<(&'static str, Param<'_>)> {
+            vec![
+                ("title", Param::from(self.title.as_str())),
+                ("views", Param::I64(self.views)),
+            ]
+        }
+    }
+
+    /// Records statements and their parameters; every fetch returns one post.
+    #[derive(Default)]
+    struct FakeDb {
+        log: Mutex<Vec<(String, Vec<String>)>>,
+    }
+
+    impl FakeDb {
+        fn record(&self, sql: &str, params: &[Param]) {
+            let params = params.iter().map(|p| format!("{p:?}")).collect();
+            self.log.lock().unwrap().push((sql.to_string(), params));
+        }
+    }
+
+    fn post_row() -> Row {
+        let mut row = Row::default();
+        row.insert("id", Value::U64(3));
+        row.insert("title", Value::Str("Hello".into()));
+        row.insert("views", Value::I64(10));
+        row
+    }
+
+    impl Db for FakeDb {
+        fn fetch_one(&self, sql: &str, params: &[Param]) -> Result<Option<Row>> {
+            self.record(sql, params);
+            Ok(Some(post_row()))
+        }
+
+        fn fetch_all(&self, sql: &str, params: &[Param]) -> Result<Vec<Row>> {
+            self.record(sql, params);
+            Ok(vec![post_row()])
+        }
+
+        fn exec(&self, sql: &str, params: &[Param]) -> Result<u64> {
+            self.record(sql, params);
+            Ok(1)
+        }
+
+        fn exec_returning_last_insert_id(&self, sql: &str, params: &[Param]) -> Result<u64> {
+            self.record(sql, params);
+            Ok(42)
+        }
+    }
+
+    struct PostRepo {
+        db: Arc<FakeDb>,
+    }
+
+    impl Repository<Post, u64> for PostRepo {
+        const TABLE: &'static str = "posts";
+        const ID_COLUMN: &'static str = "post_id";
+
+        fn db(&self) -> &dyn Db {
+            self.db.as_ref()
+        }
+    }
+
+    struct BadRepo {
+        db: Arc<FakeDb>,
+    }
+
+    impl Repository<Post, String> for BadRepo {
+        const TABLE: &'static str = "posts; DROP TABLE users";
+
+        fn db(&self) -> &dyn Db {
+            self.db.as_ref()
+        }
+    }
+
+    #[test]
+    fn default_methods_generate_crud_statements() {
+        let db = Arc::new(FakeDb::default());
+        let repo = PostRepo { db: db.clone() };
+        let post = Post {
+            id: 3,
+            title: "Hello".into(),
+            views: 10,
+        };
+
+        assert_eq!(repo.find_by_id(&3).unwrap(), Some(post));
+        assert_eq!(repo.list(20, 40).unwrap().len(), 1);
+        let new = Post {
+            id: 0,
+            title: "New".into(),
+            views: 0,
+        };
+        assert_eq!(repo.insert(&new).unwrap(), 42);
+        assert_eq!(repo.update(&3, &new).unwrap(), 1);
+        assert_eq!(repo.delete(&3).unwrap(), 1);
+
+        let log = db.log.lock().unwrap();
+        let sql: Vec<&str> = log.iter().map(|(s, _)| s.as_str()).collect();
+        assert_eq!(
+            sql,
+            [
+                "SELECT * FROM posts WHERE post_id = ?",
+                "SELECT * FROM posts ORDER BY post_id LIMIT ? OFFSET ?",
+                "INSERT INTO posts (title, views) VALUES (?, ?)",
+                "UPDATE posts SET title = ?, views = ? WHERE post_id = ?",
+                "DELETE FROM posts WHERE post_id = ?",
+            ]
+        );
+        assert_eq!(log[1].1, ["U64(20)", "U64(40)"]);
+        assert_eq!(log[3].1, ["Str(\"New\")", "I64(0)", "U64(3)"]);
+    }
+
+    #[test]
+    fn invalid_identifiers_are_rejected_before_querying() {
+        let db = Arc::new(FakeDb::default());
+        let repo = BadRepo { db: db.clone() };
+        assert!(repo.find_by_id(&"a".to_string()).is_err());
+        assert!(repo.delete(&"a".to_string()).is_err());
+        assert!(db.log.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+//! # Read/Write Routing
+//!
+//! [`DbRouter`] is a [`Db`] that sends writes to a primary and spreads reads
+//! over read replicas (round-robin):
+//!
+//! | Operation | Target |
+//! |-----------|--------|
+//! | `fetch_one`, `fetch_all`, `fetch_stream` (and `*_with_ctx`) | a replica |
+//! | `exec`, `exec_returning_last_insert_id` (and `*_with_ctx`) | the primary |
+//! | locking reads (`... FOR UPDATE`, `FOR SHARE`, `LOCK IN SHARE MODE`) | the primary |
+//!
+//! Without replicas every statement goes to the primary. Replicas lag
+//! behind the primary, so code that must read its own writes should query
+//! [`primary`](DbRouter::primary) directly.
+//!
+//! Configured from `DATABASE_URL` and `DATABASE_READ_URLS`
+//! ([`DbConfig`]).
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use wzs_web::config::db::DbConfig;
+//! use wzs_web::db::port::Db;
+//! use wzs_web::db::router::DbRouter;
+//!
+//! let db: Arc<dyn Db> = Arc::new(DbRouter::from_config(&DbConfig::from_env())?);
+//! let users = db.fetch_all("SELECT id FROM users", &[])?; // replica
+//! db.exec("UPDATE users SET active = 1", &[])?; // primary
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+
+use crate::config::db::{create_pool, create_read_pools, DbConfig};
+use crate::db::context::DbContext;
+use crate::db::mysql_adapter::MySqlDb;
+use crate::db::port::{Db, Param, Row, RowStream};
+
+/// [`Db`] routing reads to replicas and writes to the primary.
+pub struct DbRouter {
+    primary: Arc<dyn Db>,
+    replicas: Vec<Arc<dyn Db>>,
+    next: AtomicUsize,
+}
+
+impl DbRouter {
+    /// Routes between `primary` and `replicas`.
+    pub fn new(primary: Arc<dyn Db>, replicas: Vec<Arc<dyn Db>>) -> Self {
+        Self {
+            primary,
+            replicas,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Builds MySQL pools for `DATABASE_URL` and each `DATABASE_READ_URLS` entry.
+    ///
+    /// # Errors
+    /// Returns an error if `DATABASE_URL` is missing or any pool cannot be created.
+    pub fn from_config(cfg: &DbConfig) -> Result<Self> {
+        let primary: Arc<dyn Db> = Arc::new(MySqlDb::new(
+            create_pool(cfg).context("primary database pool")?,
+        ));
+        let replicas = create_read_pools(cfg)?
+            .into_iter()
+            .map(|pool| Arc::new(MySqlDb::new(pool)) as Arc<dyn Db>)
+            .collect();
+        Ok(Self::new(primary, replicas))
+    }
+
+    /// The primary (for writes and read-your-writes queries).
+    pub fn primary(&self) -> &Arc<dyn Db> {
+        &self.primary
+    }
+
+    /// Number of read replicas.
+    pub fn replica_count(&self) -> usize {
+        self.replicas.len()
+    }
+
+    /// Target for a read of `sql`: the next replica, or the primary for
+    /// locking reads and when there are no replicas.
+    fn reader(&self, sql: &str) -> &dyn Db {
+        if self.replicas.is_empty() || is_locking_read(sql) {
+            return self.primary.as_ref();
+        }
+        let i = self.next.fetch_add(1, Ordering::Relaxed) % self.replicas.len();
+        self.replicas[i].as_ref()
+    }
+}
+
+impl Db for DbRouter {
+    fn fetch_one(&self, sql: &str, params: &[Param]) -> Result<Option<Row>> {
+        self.reader(sql).fetch_one(sql, params)
+    }
+
+    fn fetch_all(&self, sql: &str, params: &[Param]) -> Result<Vec<Row>> {
+        self.reader(sql).fetch_all(sql, params)
+    }
+
+    fn fetch_stream(&self, sql: &str, params: &[Param]) -> Result<RowStream<'_>> {
+        self.reader(sql).fetch_stream(sql, params)
+    }
+
+    fn exec(&self, sql: &str, params: &[Param]) -> Result<u64> {
+        self.primary.exec(sql, params)
+    }
+
+    fn exec_returning_last_insert_id(&self, sql: &str, params: &[Param]) -> Result<u64> {
+        self.primary.exec_returning_last_insert_id(sql, params)
+    }
+
+    /// Pings the primary and every replica.
+    fn ping(&self) -> Result<()> {
+        self.primary.ping().context("primary")?;
+        for (i, replica) in self.replicas.iter().enumerate() {
+            replica
+                .ping()
+                .with_context(|| format!("read replica #{i}"))?;
+        }
+        Ok(())
+    }
+
+    fn fetch_one_with_ctx(
+        &self,
+        ctx: &DbContext,
+        sql: &str,
+        params: &[Param],
+    ) -> Result<Option<Row>> {
+        self.reader(sql).fetch_one_with_ctx(ctx, sql, params)
+    }
+
+    fn fetch_all_with_ctx(&self, ctx: &DbContext, sql: &str, params: &[Param]) -> Result<Vec<Row>> {
+        self.reader(sql).fetch_all_with_ctx(ctx, sql, params)
+    }
+
+    fn exec_with_ctx(&self, ctx: &DbContext, sql: &str, params: &[Param]) -> Result<u64> {
+        self.primary.exec_with_ctx(ctx, sql, params)
+    }
+
+    fn exec_returning_last_insert_id_with_ctx(
+        &self,
+        ctx: &DbContext,
+        sql: &str,
+        params: &[Param],
+    ) -> Result<u64> {
+        self.primary
+            .exec_returning_last_insert_id_with_ctx(ctx, sql, params)
+    }
+}
+
+/// `SELECT ... FOR UPDATE` / `FOR SHARE` / `LOCK IN SHARE MODE` must run on
+/// the primary to take the lock there.
+fn is_locking_read(sql: &str) -> bool {
+    let words: Vec<String> = sql
+        .split_whitespace()
+        .map(|w| w.trim_end_matches(';').to_ascii_uppercase())
+        .collect();
+    words
+        .windows(2)
+        .any(|w| w[0] == "FOR" && (w[1] == "UPDATE" || w[1] == "SHARE"))
+        || words
+            .windows(4)
+            .any(|w| w == ["LOCK", "IN", "SHARE", "MODE"])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records which instance ran each statement.
+    struct NamedDb {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl NamedDb {
+        fn arc(name: &'static str, log: &Arc<Mutex<Vec<String>>>) -> Arc<dyn Db> {
+            Arc::new(Self {
+                name,
+                log: log.clone(),
+            })
+        }
+
+        fn record(&self, op: &str) {
+            self.log.lock().unwrap().push(format!("{}:{op}", self.name));
+        }
+    }
+
+    impl Db for NamedDb {
+        fn fetch_one(&self, _: &str, _: &[Param]) -> Result<Option<Row>> {
+            self.record("fetch_one");
+            Ok(None)
+        }
+
+        fn fetch_all(&self, _: &str, _: &[Param]) -> Result<Vec<Row>> {
+            self.record("fetch_all");
+            Ok(vec![])
+        }
+
+        fn exec(&self, _: &str, _: &[Param]) -> Result<u64> {
+            self.record("exec");
+            Ok(1)
+        }
+
+        fn exec_returning_last_insert_id(&self, _: &str, _: &[Param]) -> Result<u64> {
+            self.record("insert");
+            Ok(1)
+        }
+    }
+
+    fn router(replicas: &[&'static str]) -> (DbRouter, Arc<Mutex<Vec<String>>>) {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let router = DbRouter::new(
+            NamedDb::arc("primary", &log),
+            replicas.iter().map(|n| NamedDb::arc(n, &log)).collect(),
+        );
+        (router, log)
+    }
+
+    #[test]
+    fn reads_round_robin_over_replicas_and_writes_hit_primary() {
+        let (db, log) = router(&["r1", "r2"]);
+        db.fetch_all("SELECT 1", &[]).unwrap();
+        db.fetch_one("SELECT 1", &[]).unwrap();
+        db.fetch_all("SELECT 1", &[]).unwrap();
+        db.exec("UPDATE t SET a = 1", &[]).unwrap();
+        db.exec_returning_last_insert_id("INSERT INTO t VALUES (1)", &[])
+            .unwrap();
+        db.exec_with_ctx(&DbContext::new(), "DELETE FROM t", &[])
+            .unwrap();
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "r1:fetch_all",
+                "r2:fetch_one",
+                "r1:fetch_all",
+                "primary:exec",
+                "primary:insert",
+                "primary:exec",
+            ]
+        );
+    }
+
+    #[test]
+    fn locking_reads_and_replica_less_setups_use_primary() {
+        let (db, log) = router(&["r1"]);
+        db.fetch_one("SELECT * FROM t WHERE id = ? FOR UPDATE", &[])
+            .unwrap();
+        db.fetch_all("select * from t lock in share mode;", &[])
+            .unwrap();
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["primary:fetch_one", "primary:fetch_all"]
+        );
+
+        let (db, log) = router(&[]);
+        db.fetch_all("SELECT 1", &[]).unwrap();
+        assert_eq!(*log.lock().unwrap(), vec!["primary:fetch_all"]);
+        assert!(!is_locking_read("SELECT 'for update' AS note"));
+    }
+}
@@ -0,0 +1,488 @@
+//! # Seed Data
+//!
+//! [`SeedSet`]s describe development and test data as ordered steps:
+//!
+//! - [`sql`](SeedSet::sql) — a script (several statements separated by `;`)
+//! - [`records`](SeedSet::records) — rows built in Rust, inserted through
+//!   [`ToParams`] with `INSERT IGNORE`
+//! - [`step`](SeedSet::step) — any closure over the [`Db`] port
+//!
+//! [`Seeder`] applies them idempotently: each applied step is recorded in a
+//! `seed_runs` table and skipped on later runs, so seeding can run on every
+//! start of a development server. [`Seeder::forget`] clears the record of a
+//! set so it is applied again (e.g. after truncating the tables).
+//!
+//! Seeding is gated on the profile (`APP_ENV`): it always refuses
+//! `production`, and a set limited with [`profiles`](SeedSet::profiles)
+//! only runs in those profiles. Test suites use [`Seeder::for_tests`]
+//! (profile `test`) to start from known data.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use wzs_web::config::db::DbConfig;
+//! use wzs_web::db::{connection::get_pool, mysql_adapter::MySqlDb, port::Param};
+//! use wzs_web::db::repository::ToParams;
+//! use wzs_web::db::seed::{SeedSet, Seeder};
+//!
+//! struct User {
+//!     id: u64,
+//!     email: &'static str,
+//! }
+//!
+//! impl ToParams for User {
+//!     fn to_params(&self) -> Vec<(&'static str, Param<'_>)> {
+//!         vec![("id", Param::U64(self.id)), ("email", Param::Str(self.email))]
+//!     }
+//! }
+//!
+//! # fn run() -> anyhow::Result<()> {
+//! let db = Arc::new(MySqlDb::new(get_pool(&DbConfig::from_env())));
+//!
+//! let demo = SeedSet::new("demo")
+//!     .sql("plans", "INSERT IGNORE INTO plans (id, name) VALUES (1, 'free'), (2, 'pro')")
+//!     .records("users", "users", vec![User { id: 1, email: "admin@example.com" }])
+//!     .step("settings", |db| {
+//!         db.exec("UPDATE settings SET value = 'Demo' WHERE name = 'site.title'", &[])?;
+//!         Ok(())
+//!     });
+//!
+//! let applied = Seeder::from_env(db).run(&demo)?;
+//! println!("applied {applied:?}");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use tracing::info;
+
+use crate::db::migrate::split_statements;
+use crate::db::port::{Db, Param};
+use crate::db::repository::{ident, ToParams};
+
+/// Profile that never runs seeds.
+pub const PRODUCTION_PROFILE: &str = "production";
+
+type StepFn = Arc<dyn Fn(&dyn Db) -> Result<()> + Send + Sync>;
+
+/// One named step of a [`SeedSet`].
+#[derive(Clone)]
+pub struct SeedStep {
+    pub name: String,
+    run: StepFn,
+}
+
+impl fmt::Debug for SeedStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SeedStep")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+/// An ordered, named group of seed steps.
+#[derive(Clone, Debug)]
+pub struct SeedSet {
+    pub name: String,
+    steps: Vec<SeedStep>,
+    profiles: Vec<String>,
+}
+
+impl SeedSet {
+    /// Empty set allowed in every non-production profile.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            steps: Vec::new(),
+            profiles: Vec::new(),
+        }
+    }
+
+    /// Limits the set to `profiles` (e.g. `["development"]`).
+    pub fn profiles<I, P>(mut self, profiles: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<String>,
+    {
+        self.profiles = profiles.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Adds a SQL script step. Statements should be idempotent on their own
+    /// (`INSERT IGNORE`, `ON DUPLICATE KEY UPDATE`) if the set may be
+    /// [forgotten](Seeder::forget) and re-applied.
+    pub fn sql(self, name: impl Into<String>, script: impl Into<String>) -> Self {
+        let name = name.into();
+        let statements = split_statements(&script.into());
+        let step = name.clone();
+        self.step(name, move |db| {
+            for (i, stmt) in statements.iter().enumerate() {
+                db.exec(stmt, &[])
+                    .with_context(|| format!("seed step {step}: statement {} failed", i + 1))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Adds a step inserting `rows` into `table` with `INSERT IGNORE`.
+    ///
+    /// Every row must produce the same columns. The step fails if `table`
+    /// is not a plain SQL identifier.
+    pub fn records<T>(self, name: impl Into<String>, table: &str, rows: Vec<T>) -> Self
+    where
+        T: ToParams + Send + Sync + 'static,
+    {
+        let table = table.to_string();
+        self.step(name, move |db| {
+            ident(&table)?;
+            for row in &rows {
+                let pairs = row.to_params();
+                if pairs.is_empty() {
+                    bail!("ToParams returned no columns");
+                }
+                let columns: Vec<&str> = pairs.iter().map(|(c, _)| *c).collect();
+                let sql = format!(
+                    "INSERT IGNORE INTO {table} ({}) VALUES ({})",
+                    columns.join(", "),
+                    vec!["?"; columns.len()].join(", ")
+                );
+                let params: Vec<Param<'_>> = pairs.into_iter().map(|(_, p)| p).collect();
+                db.exec(&sql, &params)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Adds a step running `f`.
+    pub fn step<F>(mut self, name: impl Into<String>, f: F) -> Self
+    where
+        F: Fn(&dyn Db) -> Result<()> + Send + Sync + 'static,
+    {
+        self.steps.push(SeedStep {
+            name: name.into(),
+            run: Arc::new(f),
+        });
+        self
+    }
+
+    /// The steps in order.
+    pub fn steps(&self) -> &[SeedStep] {
+        &self.steps
+    }
+
+    fn allows(&self, profile: &str) -> bool {
+        profile != PRODUCTION_PROFILE
+            && (self.profiles.is_empty() || self.profiles.iter().any(|p| p == profile))
+    }
+}
+
+/// Applies [`SeedSet`]s, tracking applied steps in a table.
+#[derive(Clone)]
+pub struct Seeder {
+    db: Arc<dyn Db>,
+    profile: String,
+    table: String,
+}
+
+impl Seeder {
+    /// Default tracking table name.
+    pub const DEFAULT_TABLE: &'static str = "seed_runs";
+
+    /// Creates a seeder for `profile` (e.g. `"development"`).
+    pub fn new(db: Arc<dyn Db>, profile: impl Into<String>) -> Self {
+        Self {
+            db,
+            profile: profile.into(),
+            table: Self::DEFAULT_TABLE.into(),
+        }
+    }
+
+    /// Creates a seeder for the `APP_ENV` profile (default `development`).
+    pub fn from_env(db: Arc<dyn Db>) -> Self {
+        let profile = std::env::var("APP_ENV").unwrap_or_else(|_| "development".into());
+        Self::new(db, profile)
+    }
+
+    /// Creates a seeder for the `test` profile.
+    pub fn for_tests(db: Arc<dyn Db>) -> Self {
+        Self::new(db, "test")
+    }
+
+    /// Uses a custom tracking table.
+    ///
+    /// # Errors
+    /// Returns an error if `table` is not a plain SQL identifier.
+    pub fn with_table(mut self, table: impl Into<String>) -> Result<Self> {
+        let table = table.into();
+        ident(&table)?;
+        self.table = table;
+        Ok(self)
+    }
+
+    /// The profile seeds are checked against.
+    pub fn profile(&self) -> &str {
+        &self.profile
+    }
+
+    /// Applies the steps of `set` not applied before, in order, returning
+    /// the names of the steps applied.
+    ///
+    /// # Errors
+    /// Refuses to run in `production` or in a profile the set is not
+    /// limited to. Stops at the first failing step; earlier steps stay
+    /// applied and recorded.
+    pub fn run(&self, set: &SeedSet) -> Result<Vec<String>> {
+        if !set.allows(&self.profile) {
+            bail!(
+                "seed set {} is not allowed in profile {}",
+                set.name,
+                self.profile
+            );
+        }
+        let applied = self.applied(&set.name)?;
+        let mut done = Vec::new();
+        for step in set.steps.iter().filter(|s| !applied.contains(&s.name)) {
+            (step.run)(self.db.as_ref())
+                .with_context(|| format!("seed {} / {} failed", set.name, step.name))?;
+            self.db.exec(
+                &format!(
+                    "INSERT INTO {} (seed_set, step, applied_at) VALUES (?, ?, ?)",
+                    self.table
+                ),
+                &[
+                    Param::Str(&set.name),
+                    Param::Str(&step.name),
+                    Param::DateTime(Utc::now().naive_utc()),
+                ],
+            )?;
+            info!("seed {} / {} applied", set.name, step.name);
+            done.push(step.name.clone());
+        }
+        Ok(done)
+    }
+
+    /// Applies several sets in order, returning the number of steps applied.
+    ///
+    /// # Errors
+    /// As [`run`](Self::run); stops at the first failing set.
+    pub fn run_all(&self, sets: &[SeedSet]) -> Result<usize> {
+        let mut count = 0;
+        for set in sets {
+            count += self.run(set)?.len();
+        }
+        Ok(count)
+    }
+
+    /// Forgets which steps of the set `name` were applied, so the next
+    /// [`run`](Self::run) applies them again. Data is not touched.
+    ///
+    /// # Errors
+    /// Refuses to run in `production`.
+    pub fn forget(&self, name: &str) -> Result<u64> {
+        if self.profile == PRODUCTION_PROFILE {
+            bail!("seeding is disabled in production");
+        }
+        self.ensure_table()?;
+        self.db.exec(
+            &format!("DELETE FROM {} WHERE seed_set = ?", self.table),
+            &[Param::Str(name)],
+        )
+    }
+
+    fn ensure_table(&self) -> Result<()> {
+        self.db.exec(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} (\
+                 seed_set VARCHAR(128) NOT NULL, \
+                 step VARCHAR(128) NOT NULL, \
+                 applied_at DATETIME NOT NULL, \
+                 PRIMARY KEY (seed_set, step))",
+                self.table
+            ),
+            &[],
+        )?;
+        Ok(())
+    }
+
+    /// Creates the tracking table if needed and returns applied step names.
+    fn applied(&self, set: &str) -> Result<Vec<String>> {
+        self.ensure_table()?;
+        self.db
+            .fetch_all(
+                &format!("SELECT step FROM {} WHERE seed_set = ?", self.table),
+                &[Param::Str(set)],
+            )?
+            .iter()
+            .map(|r| r.get_string("step"))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::db::port::{Row, Value};
+
+    /// Keeps the tracking table in memory and records other statements.
+    #[derive(Default)]
+    struct MemoryDb {
+        applied: Mutex<BTreeSet<(String, String)>>,
+        statements: Mutex<Vec<(String, usize)>>,
+    }
+
+    impl Db for MemoryDb {
+        fn fetch_one(&self, _sql: &str, _params: &[Param]) -> Result<Option<Row>> {
+            Ok(None)
+        }
+
+        fn fetch_all(&self, _sql: &str, params: &[Param]) -> Result<Vec<Row>> {
+            let set = match params[0].to_value() {
+                Value::Str(s) => s,
+                _ => unreachable!(),
+            };
+            Ok(self
+                .applied
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(s, _)| *s == set)
+                .map(|(_, step)| {
+                    let mut row = Row::default();
+                    row.insert("step", Value::Str(step.clone()));
+                    row
+                })
+                .collect())
+        }
+
+        fn exec(&self, sql: &str, params: &[Param]) -> Result<u64> {
+            let str_at = |i: usize| match params.get(i).map(Param::to_value) {
+                Some(Value::Str(s)) => s,
+                _ => String::new(),
+            };
+            if sql.starts_with("CREATE TABLE IF NOT EXISTS seed_runs") {
+                return Ok(0);
+            }
+            if sql.starts_with("INSERT INTO seed_runs") {
+                self.applied.lock().unwrap().insert((str_at(0), str_at(1)));
+                return Ok(1);
+            }
+            if sql.starts_with("DELETE FROM seed_runs") {
+                let set = str_at(0);
+                let mut applied = self.applied.lock().unwrap();
+                let before = applied.len();
+                applied.retain(|(s, _)| *s != set);
+                return Ok((before - applied.len()) as u64);
+            }
+            if sql.contains("fail") {
+                bail!("syntax error");
+            }
+            self.statements
+                .lock()
+                .unwrap()
+                .push((sql.to_string(), params.len()));
+            Ok(1)
+        }
+
+        fn exec_returning_last_insert_id(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    struct Plan(u64, &'static str);
+
+    impl ToParams for Plan {
+        fn to_params(&self) -> Vec<(&'static str, Param<'_>)> {
+            vec![("id", Param::U64(self.0)), ("name", Param::Str(self.1))]
+        }
+    }
+
+    fn demo() -> SeedSet {
+        SeedSet::new("demo")
+            .sql(
+                "schema",
+                "INSERT IGNORE INTO a VALUES (1); INSERT IGNORE INTO b VALUES (2);",
+            )
+            .records("plans", "plans", vec![Plan(1, "free"), Plan(2, "pro")])
+    }
+
+    #[test]
+    fn applies_steps_once_in_order() {
+        let db = Arc::new(MemoryDb::default());
+        let seeder = Seeder::for_tests(db.clone());
+
+        assert_eq!(seeder.run(&demo()).unwrap(), ["schema", "plans"]);
+        assert_eq!(
+            *db.statements.lock().unwrap(),
+            [
+                ("INSERT IGNORE INTO a VALUES (1)".to_string(), 0),
+                ("INSERT IGNORE INTO b VALUES (2)".to_string(), 0),
+                (
+                    "INSERT IGNORE INTO plans (id, name) VALUES (?, ?)".to_string(),
+                    2
+                ),
+                (
+                    "INSERT IGNORE INTO plans (id, name) VALUES (?, ?)".to_string(),
+                    2
+                ),
+            ]
+        );
+        assert!(seeder.run(&demo()).unwrap().is_empty());
+
+        assert_eq!(seeder.forget("demo").unwrap(), 2);
+        assert_eq!(seeder.run_all(&[demo()]).unwrap(), 2);
+    }
+
+    #[test]
+    fn refuses_production_and_other_profiles() {
+        let db = Arc::new(MemoryDb::default());
+        let err = Seeder::new(db.clone(), "production")
+            .run(&demo())
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("not allowed in profile production"));
+        assert!(Seeder::new(db.clone(), "production")
+            .forget("demo")
+            .is_err());
+
+        let dev_only = demo().profiles(["development"]);
+        assert!(Seeder::for_tests(db.clone()).run(&dev_only).is_err());
+        assert!(Seeder::new(db.clone(), "development")
+            .run(&dev_only)
+            .is_ok());
+        assert!(db.applied.lock().unwrap().len() == 2);
+    }
+
+    #[test]
+    fn failing_step_stops_and_is_retried() {
+        let db = Arc::new(MemoryDb::default());
+        let seeder = Seeder::for_tests(db.clone());
+        let set = SeedSet::new("s")
+            .step("ok", |db| db.exec("SELECT 1", &[]).map(|_| ()))
+            .sql("bad", "INSERT INTO fail VALUES (1)");
+
+        let err = seeder.run(&set).unwrap_err();
+        assert_eq!(err.to_string(), "seed s / bad failed");
+        assert_eq!(db.applied.lock().unwrap().len(), 1);
+
+        let fixed = SeedSet::new("s")
+            .step("ok", |_| unreachable!("already applied"))
+            .sql("bad", "INSERT INTO good VALUES (1)");
+        assert_eq!(seeder.run(&fixed).unwrap(), ["bad"]);
+    }
+
+    #[test]
+    fn validates_table_names() {
+        let db = Arc::new(MemoryDb::default());
+        assert!(Seeder::for_tests(db.clone()).with_table("x; DROP").is_err());
+        let bad = SeedSet::new("s").records("r", "bad name", vec![Plan(1, "x")]);
+        assert!(Seeder::for_tests(db).run(&bad).is_err());
+    }
+}
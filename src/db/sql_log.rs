@@ -0,0 +1,216 @@
+//! # SQL Statement Logging
+//!
+//! [`SqlSpan`] wraps one statement in a `sql` [`tracing`] span so adapters
+//! report the same structured fields:
+//!
+//! | Field          | Content                                            |
+//! |----------------|----------------------------------------------------|
+//! | `op`           | adapter operation (`fetch_one`, `exec`, ...)       |
+//! | `sql`          | statement text                                     |
+//! | `params`       | parameter count                                    |
+//! | `param_values` | per [`SqlParamLog`]: types, values, or absent      |
+//! | `elapsed_ms`   | wall time until completion                         |
+//! | `rows`         | rows returned (reads)                              |
+//! | `affected`     | affected rows (writes)                             |
+//! | `error_code`   | server error code, when the driver reports one     |
+//!
+//! A [`QueryObserver`] attached with [`observed_by`](SqlSpan::observed_by)
+//! is notified when the statement completes.
+//!
+//! Completion emits a `debug` event and failure a `warn` event inside the
+//! span, so the usual `RUST_LOG`-style filters apply (e.g.
+//! `wzs_web::db=debug`).
+//!
+//! # Example
+//! ```rust
+//! use wzs_web::config::db::SqlLogConfig;
+//! use wzs_web::db::port::Param;
+//! use wzs_web::db::sql_log::SqlSpan;
+//!
+//! let params = [Param::U64(7)];
+//! let span = SqlSpan::start("fetch_all", "SELECT * FROM t WHERE id = ?", &params, &SqlLogConfig::default());
+//! span.rows(1);
+//! span.ok();
+//! ```
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use tracing::field::Empty;
+use tracing::Span;
+
+use crate::config::db::{SqlLogConfig, SqlParamLog};
+use crate::db::observer::{QueryObserver, QueryOutcome};
+use crate::db::port::Param;
+
+/// Structured log span for one SQL statement.
+pub struct SqlSpan {
+    span: Span,
+    started: Instant,
+    observer: Option<(Arc<dyn QueryObserver>, String)>,
+}
+
+impl SqlSpan {
+    /// Opens the span, recording the statement and its parameters per `cfg`.
+    pub fn start(op: &'static str, sql: &str, params: &[Param], cfg: &SqlLogConfig) -> Self {
+        let span = tracing::debug_span!(
+            "sql",
+            op,
+            sql,
+            params = params.len(),
+            param_values = Empty,
+            elapsed_ms = Empty,
+            rows = Empty,
+            affected = Empty,
+            error_code = Empty,
+        );
+        let values = (!span.is_disabled())
+            .then(|| format_params(params, cfg.params))
+            .flatten();
+        if let Some(values) = values {
+            span.record("param_values", values.as_str());
+        }
+        Self {
+            span,
+            started: Instant::now(),
+            observer: None,
+        }
+    }
+
+    /// Notifies `observer` (if any) when the statement completes.
+    pub fn observed_by(mut self, observer: Option<Arc<dyn QueryObserver>>, sql: &str) -> Self {
+        self.observer = observer.map(|o| (o, sql.to_string()));
+        self
+    }
+
+    /// Records the number of rows returned.
+    pub fn rows(&self, n: usize) {
+        self.span.record("rows", n);
+    }
+
+    /// Records the number of affected rows.
+    pub fn affected(&self, n: u64) {
+        self.span.record("affected", n);
+    }
+
+    /// Marks the statement as successful.
+    pub fn ok(&self) {
+        self.record_elapsed();
+        self.notify(QueryOutcome::Ok);
+        tracing::debug!(parent: &self.span, "sql ok");
+    }
+
+    /// Marks the statement as failed with the driver's error `code` and summary.
+    pub fn fail(&self, code: Option<u16>, error: &str) {
+        self.record_elapsed();
+        if let Some(code) = code {
+            self.span.record("error_code", code);
+        }
+        self.notify(QueryOutcome::Error { code });
+        tracing::warn!(parent: &self.span, error, "sql failed");
+    }
+
+    fn notify(&self, outcome: QueryOutcome) {
+        if let Some((observer, sql)) = &self.observer {
+            observer.on_query(sql, self.started.elapsed(), outcome);
+        }
+    }
+
+    fn record_elapsed(&self) {
+        self.span
+            .record("elapsed_ms", self.started.elapsed().as_secs_f64() * 1000.0);
+    }
+}
+
+/// Renders parameters for the `param_values` field; `None` when hidden.
+fn format_params(params: &[Param], mode: SqlParamLog) -> Option<String> {
+    let items: Vec<String> = match mode {
+        SqlParamLog::Hidden => return None,
+        SqlParamLog::Types => params.iter().map(|p| param_type(p).to_string()).collect(),
+        SqlParamLog::Values => params
+            .iter()
+            .map(|p| match p {
+                Param::Encrypted(_) => "Encrypted(<redacted>)".to_string(),
+                other => format!("{other:?}"),
+            })
+            .collect(),
+    };
+    Some(format!("[{}]", items.join(", ")))
+}
+
+fn param_type(p: &Param) -> &'static str {
+    match p {
+        Param::I64(_) => "I64",
+        Param::U64(_) => "U64",
+        Param::F32(_) => "F32",
+        Param::F64(_) => "F64",
+        Param::Bool(_) => "Bool",
+        Param::Str(_) => "Str",
+        Param::DateTime(_) => "DateTime",
+        Param::Bin(_) => "Bin",
+        Param::Encrypted(_) => "Encrypted",
+        Param::Decimal(_) => "Decimal",
+        Param::Json(_) => "Json",
+        Param::Null => "NULL",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> Vec<Param<'static>> {
+        vec![
+            Param::U64(7),
+            Param::Str("alice@example.com"),
+            Param::Encrypted("ciphertext".into()),
+            Param::Null,
+        ]
+    }
+
+    #[test]
+    fn types_mode_never_includes_values() {
+        assert_eq!(
+            format_params(&params(), SqlParamLog::Types).as_deref(),
+            Some("[U64, Str, Encrypted, NULL]")
+        );
+        assert_eq!(format_params(&params(), SqlParamLog::Hidden), None);
+    }
+
+    #[test]
+    fn values_mode_masks_encrypted_params() {
+        let out = format_params(&params(), SqlParamLog::Values).unwrap();
+        assert_eq!(
+            out,
+            "[U64(7), Str(\"alice@example.com\"), Encrypted(<redacted>), Null]"
+        );
+    }
+
+    #[test]
+    fn span_lifecycle_without_subscriber_is_a_noop() {
+        let span = SqlSpan::start("exec", "DELETE FROM t", &[], &SqlLogConfig::default());
+        span.affected(3);
+        span.fail(Some(1213), "deadlock");
+    }
+
+    #[test]
+    fn observer_is_notified_with_outcome() {
+        use crate::db::observer::QueryMetrics;
+
+        let metrics = QueryMetrics::new(std::time::Duration::from_secs(60));
+        let observer: Arc<dyn QueryObserver> = Arc::new(metrics.clone());
+        let cfg = SqlLogConfig::default();
+
+        SqlSpan::start("exec", "DELETE FROM t", &[], &cfg)
+            .observed_by(Some(observer.clone()), "DELETE FROM t")
+            .fail(Some(1213), "deadlock");
+        SqlSpan::start("fetch_all", "SELECT 1", &[], &cfg)
+            .observed_by(Some(observer), "SELECT 1")
+            .ok();
+
+        assert_eq!(metrics.total(), 2);
+        assert!(metrics
+            .render()
+            .contains("db_queries_total{outcome=\"error\"} 1\n"));
+    }
+}
@@ -0,0 +1,195 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use thiserror::Error;
+
+use crate::error::entity::NotFoundError;
+use crate::web::problem::{FieldError, Problem};
+
+/// Application error returned by handlers.
+///
+/// Each variant maps to one status code and renders as a
+/// [`Problem`] (`application/problem+json`). `Internal` errors are logged
+/// and answered without detail.
+///
+/// # Example
+/// ```
+/// use axum::http::StatusCode;
+/// use axum::response::IntoResponse;
+/// use wzs_web::error::app::AppError;
+///
+/// fn load(id: u64) -> Result<String, AppError> {
+///     if id == 0 {
+///         return Err(AppError::bad_request("id must be positive"));
+///     }
+///     Err(anyhow::anyhow!("connection reset").into())
+/// }
+///
+/// assert_eq!(load(0).unwrap_err().status(), StatusCode::BAD_REQUEST);
+/// let res = load(1).unwrap_err().into_response();
+/// assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+/// ```
+#[derive(Debug, Error)]
+pub enum AppError {
+    /// `400`: the request is malformed.
+    #[error("{0}")]
+    BadRequest(String),
+    /// `401`: authentication is missing or invalid.
+    #[error("{0}")]
+    Unauthorized(String),
+    /// `403`: the caller may not do this.
+    #[error("{0}")]
+    Forbidden(String),
+    /// `404`: the target does not exist.
+    #[error("{0}")]
+    NotFound(String),
+    /// `409`: the request conflicts with the current state.
+    #[error("{0}")]
+    Conflict(String),
+    /// `422`: invalid input fields.
+    #[error("invalid fields")]
+    Validation(Vec<FieldError>),
+    /// A fully specified problem (other statuses, custom types).
+    #[error("{0}")]
+    Problem(Box<Problem>),
+    /// `500`: anything else.
+    #[error(transparent)]
+    Internal(anyhow::Error),
+}
+
+impl AppError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::BadRequest(message.into())
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::Unauthorized(message.into())
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::Forbidden(message.into())
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(message.into())
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::Conflict(message.into())
+    }
+
+    /// Response status.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Problem(p) => p.status_code(),
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    /// Keeps a wrapped `AppError`, `Problem` or [`NotFoundError`]; anything
+    /// else is `Internal`.
+    fn from(e: anyhow::Error) -> Self {
+        let e = match e.downcast::<AppError>() {
+            Ok(app) => return app,
+            Err(e) => e,
+        };
+        let e = match e.downcast::<Problem>() {
+            Ok(problem) => return Self::Problem(Box::new(problem)),
+            Err(e) => e,
+        };
+        match e.downcast_ref::<NotFoundError>() {
+            Some(not_found) => Self::NotFound(not_found.to_string()),
+            None => Self::Internal(e),
+        }
+    }
+}
+
+impl From<NotFoundError> for AppError {
+    fn from(e: NotFoundError) -> Self {
+        Self::NotFound(e.to_string())
+    }
+}
+
+impl From<Problem> for AppError {
+    fn from(p: Problem) -> Self {
+        Self::Problem(Box::new(p))
+    }
+}
+
+impl From<AppError> for Problem {
+    fn from(e: AppError) -> Self {
+        let status = e.status();
+        match e {
+            AppError::BadRequest(m)
+            | AppError::Unauthorized(m)
+            | AppError::Forbidden(m)
+            | AppError::NotFound(m)
+            | AppError::Conflict(m) => Problem::new(status).detail(m),
+            AppError::Validation(errors) => Problem::validation(errors),
+            AppError::Problem(p) => *p,
+            AppError::Internal(e) => {
+                tracing::error!(error = %format!("{e:#}"), "internal error");
+                Problem::internal()
+            }
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        Problem::from(self).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::anyhow;
+
+    #[test]
+    fn variants_map_to_problems() {
+        let cases = [
+            (AppError::bad_request("bad"), 400, Some("bad")),
+            (AppError::unauthorized("who?"), 401, Some("who?")),
+            (AppError::forbidden("no"), 403, Some("no")),
+            (AppError::not_found("gone"), 404, Some("gone")),
+            (AppError::conflict("taken"), 409, Some("taken")),
+            (AppError::Internal(anyhow!("secret dsn")), 500, None),
+        ];
+        for (err, status, detail) in cases {
+            let p = Problem::from(err);
+            assert_eq!((p.status, p.detail.as_deref()), (status, detail));
+        }
+
+        let p = Problem::from(AppError::Validation(vec![FieldError::new(
+            "email",
+            "is required",
+        )]));
+        assert_eq!((p.status, p.errors.len()), (422, 1));
+    }
+
+    #[test]
+    fn anyhow_keeps_known_errors() {
+        let e: AppError = anyhow::Error::new(AppError::conflict("taken")).into();
+        assert!(matches!(e, AppError::Conflict(_)));
+
+        let e: AppError = anyhow::Error::new(NotFoundError::new("User"))
+            .context("loading")
+            .into();
+        assert_eq!(e.status(), StatusCode::NOT_FOUND);
+
+        let e: AppError = anyhow::Error::new(Problem::new(StatusCode::GONE)).into();
+        assert_eq!(e.status(), StatusCode::GONE);
+
+        let e: AppError = anyhow!("boom").into();
+        assert_eq!(e.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,499 @@
+//! # A/B Experiments
+//!
+//! [`Experiments`] assigns visitors to weighted variants of named
+//! experiments:
+//!
+//! - the assignment is deterministic — a hash of the experiment name and the
+//!   subject (the [`CurrentUser`] request extension when present, else a
+//!   random visitor id) — so the same visitor always lands in the same
+//!   variant
+//! - assignments are kept in an HMAC-signed cookie, so changing the weights
+//!   later does not move visitors who already saw a variant
+//! - the first read of an experiment in a request emits an [`Exposure`] to
+//!   [`subscribe`](Experiments::subscribe)rs. The crate has no shared event
+//!   bus, so, as with [`settings`](crate::settings), events are broadcast per
+//!   process
+//!
+//! [`experiments_layer`] loads the cookie and inserts an [`Assignments`]
+//! handle into the request; handlers take it as an extractor and hand it to
+//! templates, and GraphQL resolvers get it with [`Assignments::of`].
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use axum::{middleware::from_fn_with_state, routing::get, Router};
+//! use wzs_web::config::csrf::derive_secret_from_string;
+//! use wzs_web::experiments::{experiments_layer, Assignments, Experiment, Experiments};
+//!
+//! async fn checkout(ab: Assignments) -> String {
+//!     match ab.variant("checkout_button").as_deref() {
+//!         Some("green") => "green button".into(),
+//!         _ => "blue button".into(),
+//!     }
+//! }
+//!
+//! let experiments = Arc::new(
+//!     Experiments::new(derive_secret_from_string("ab-secret")).experiment(
+//!         Experiment::new("checkout_button")
+//!             .variant("blue", 50)
+//!             .variant("green", 50),
+//!     ),
+//! );
+//!
+//! let mut exposures = experiments.subscribe();
+//! tokio::spawn(async move {
+//!     while let Ok(e) = exposures.recv().await {
+//!         tracing::info!(experiment = %e.experiment, variant = %e.variant, "exposure");
+//!     }
+//! });
+//!
+//! let app: Router = Router::new()
+//!     .route("/checkout", get(checkout))
+//!     .layer(from_fn_with_state(experiments, experiments_layer));
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use async_graphql::Context;
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::{header::SET_COOKIE, request::Parts, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::broadcast;
+
+use crate::auth::CurrentUser;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Default assignment cookie name.
+pub const EXPERIMENTS_COOKIE_NAME: &str = "ab";
+
+/// Exposure events kept for slow subscribers.
+const EXPOSURE_CAPACITY: usize = 256;
+
+/// A named experiment with weighted variants.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Experiment {
+    pub name: String,
+    pub variants: Vec<(String, u32)>,
+}
+
+impl Experiment {
+    /// Experiment without variants (add them with [`variant`](Self::variant)).
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            variants: Vec::new(),
+        }
+    }
+
+    /// Adds a variant with a relative `weight` (`0` disables it).
+    pub fn variant(mut self, name: impl Into<String>, weight: u32) -> Self {
+        self.variants.push((name.into(), weight));
+        self
+    }
+
+    /// Deterministic variant for `subject`; `None` when all weights are `0`.
+    pub fn assign(&self, subject: &str) -> Option<&str> {
+        let total: u64 = self.variants.iter().map(|(_, w)| u64::from(*w)).sum();
+        if total == 0 {
+            return None;
+        }
+        let digest = Sha256::digest(format!("{}:{subject}", self.name).as_bytes());
+        let mut bucket = u64::from_be_bytes(digest[..8].try_into().unwrap()) % total;
+        for (name, weight) in &self.variants {
+            let weight = u64::from(*weight);
+            if bucket < weight {
+                return Some(name);
+            }
+            bucket -= weight;
+        }
+        None
+    }
+
+    fn has_variant(&self, variant: &str) -> bool {
+        self.variants.iter().any(|(v, w)| v == variant && *w > 0)
+    }
+}
+
+/// A visitor seeing a variant, emitted once per request and experiment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Exposure {
+    pub experiment: String,
+    pub variant: String,
+    /// User subject or visitor id the assignment was made for.
+    pub subject: String,
+    pub at: DateTime<Utc>,
+}
+
+/// Experiment registry, cookie settings and exposure channel; the state of
+/// [`experiments_layer`].
+pub struct Experiments {
+    secret: [u8; 32],
+    cookie_name: String,
+    secure: bool,
+    experiments: BTreeMap<String, Experiment>,
+    exposures: broadcast::Sender<Exposure>,
+}
+
+impl fmt::Debug for Experiments {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Experiments")
+            .field("cookie_name", &self.cookie_name)
+            .field("experiments", &self.experiments.keys())
+            .finish()
+    }
+}
+
+impl Experiments {
+    /// Registry signing its cookie with `secret`.
+    pub fn new(secret: [u8; 32]) -> Self {
+        Self {
+            secret,
+            cookie_name: EXPERIMENTS_COOKIE_NAME.into(),
+            secure: false,
+            experiments: BTreeMap::new(),
+            exposures: broadcast::channel(EXPOSURE_CAPACITY).0,
+        }
+    }
+
+    /// Registers an experiment (replacing one with the same name).
+    pub fn experiment(mut self, experiment: Experiment) -> Self {
+        self.experiments.insert(experiment.name.clone(), experiment);
+        self
+    }
+
+    /// Sets the cookie name.
+    pub fn cookie_name(mut self, name: impl Into<String>) -> Self {
+        self.cookie_name = name.into();
+        self
+    }
+
+    /// Sets the `Secure` cookie flag.
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Receives [`Exposure`]s from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Exposure> {
+        self.exposures.subscribe()
+    }
+
+    fn mac(&self, payload: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC key");
+        mac.update(payload.as_bytes());
+        mac
+    }
+
+    fn encode(&self, state: &CookieState) -> String {
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(state).unwrap_or_default());
+        let sig = URL_SAFE_NO_PAD.encode(self.mac(&payload).finalize().into_bytes());
+        format!("{payload}.{sig}")
+    }
+
+    fn decode(&self, value: &str) -> Option<CookieState> {
+        let (payload, sig) = value.rsplit_once('.')?;
+        let sig = URL_SAFE_NO_PAD.decode(sig).ok()?;
+        self.mac(payload).verify_slice(&sig).ok()?;
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()
+    }
+}
+
+/// Cookie payload.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct CookieState {
+    /// Visitor id.
+    v: String,
+    /// Experiment → variant.
+    a: BTreeMap<String, String>,
+}
+
+struct AssignmentState {
+    cookie: CookieState,
+    subject: String,
+    exposed: BTreeSet<String>,
+    changed: bool,
+}
+
+/// Variants of the current visitor, inserted by [`experiments_layer`].
+///
+/// Reading an experiment assigns the visitor on first use. Rejects with
+/// `500` when the layer is not installed.
+#[derive(Clone)]
+pub struct Assignments {
+    registry: Arc<Experiments>,
+    state: Arc<Mutex<AssignmentState>>,
+}
+
+impl fmt::Debug for Assignments {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.lock().unwrap();
+        f.debug_struct("Assignments")
+            .field("subject", &state.subject)
+            .field("variants", &state.cookie.a)
+            .finish()
+    }
+}
+
+impl Assignments {
+    /// Variant of `experiment` (`None` for unknown experiments), recording
+    /// an [`Exposure`] on the first call in the request.
+    pub fn variant(&self, experiment: &str) -> Option<String> {
+        let exp = self.registry.experiments.get(experiment)?;
+        let mut state = self.state.lock().unwrap();
+        let kept = state
+            .cookie
+            .a
+            .get(experiment)
+            .filter(|v| exp.has_variant(v))
+            .cloned();
+        let variant = match kept {
+            Some(v) => v,
+            None => {
+                let v = exp.assign(&state.subject)?.to_string();
+                state.cookie.a.insert(experiment.to_string(), v.clone());
+                state.changed = true;
+                v
+            }
+        };
+        if state.exposed.insert(experiment.to_string()) {
+            // No subscribers is not an error.
+            let _ = self.registry.exposures.send(Exposure {
+                experiment: experiment.to_string(),
+                variant: variant.clone(),
+                subject: state.subject.clone(),
+                at: Utc::now(),
+            });
+        }
+        Some(variant)
+    }
+
+    /// Whether the visitor is in `variant` of `experiment` (for templates:
+    /// `{% if ab.in_variant("checkout_button", "green") %}`).
+    pub fn in_variant(&self, experiment: &str, variant: &str) -> bool {
+        self.variant(experiment).as_deref() == Some(variant)
+    }
+
+    /// Variants of every registered experiment, recording exposures (e.g.
+    /// to pass to a client-side app).
+    pub fn all(&self) -> BTreeMap<String, String> {
+        let names: Vec<String> = self.registry.experiments.keys().cloned().collect();
+        names
+            .into_iter()
+            .filter_map(|name| self.variant(&name).map(|v| (name, v)))
+            .collect()
+    }
+
+    /// Subject assignments are made for.
+    pub fn subject(&self) -> String {
+        self.state.lock().unwrap().subject.clone()
+    }
+
+    /// The handle injected into GraphQL requests, if any.
+    pub fn of<'a>(ctx: &'a Context<'_>) -> Option<&'a Assignments> {
+        ctx.data_opt::<Assignments>()
+    }
+}
+
+impl<S> FromRequestParts<S> for Assignments
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Assignments>().cloned().ok_or((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "experiments_layer is not installed",
+        ))
+    }
+}
+
+/// Loads assignments from the cookie and saves new ones after the handler.
+///
+/// Mount with `from_fn_with_state(Arc<Experiments>, experiments_layer)`,
+/// inside any layer inserting [`CurrentUser`]. A missing or tampered cookie
+/// starts a new visitor; the cookie is only sent once a variant has been
+/// assigned.
+pub async fn experiments_layer(
+    State(registry): State<Arc<Experiments>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let jar = CookieJar::from_headers(req.headers());
+    let loaded = jar
+        .get(&registry.cookie_name)
+        .and_then(|c| registry.decode(c.value()));
+    let changed = loaded.is_none();
+    let cookie = loaded.unwrap_or_else(|| CookieState {
+        v: uuid::Uuid::new_v4().simple().to_string(),
+        a: BTreeMap::new(),
+    });
+    let subject = req
+        .extensions()
+        .get::<CurrentUser>()
+        .map_or_else(|| cookie.v.clone(), |u| u.subject.clone());
+
+    let state = Arc::new(Mutex::new(AssignmentState {
+        cookie,
+        subject,
+        exposed: BTreeSet::new(),
+        changed,
+    }));
+    req.extensions_mut().insert(Assignments {
+        registry: registry.clone(),
+        state: state.clone(),
+    });
+
+    let mut res = next.run(req).await;
+
+    let state = state.lock().unwrap();
+    if state.changed && !state.cookie.a.is_empty() {
+        let cookie = Cookie::build((registry.cookie_name.clone(), registry.encode(&state.cookie)))
+            .path("/")
+            .http_only(true)
+            .secure(registry.secure)
+            .same_site(SameSite::Lax)
+            .build();
+        // One year; see `auth::session` for why `Max-Age` is appended by hand.
+        if let Ok(value) = HeaderValue::from_str(&format!("{cookie}; Max-Age=31536000")) {
+            res.headers_mut().append(SET_COOKIE, value);
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body, http::header::COOKIE, middleware::from_fn_with_state, routing::get, Router,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use crate::config::csrf::derive_secret_from_string;
+
+    fn registry() -> Arc<Experiments> {
+        Arc::new(
+            Experiments::new(derive_secret_from_string("test")).experiment(
+                Experiment::new("button")
+                    .variant("blue", 1)
+                    .variant("green", 1),
+            ),
+        )
+    }
+
+    fn app(registry: Arc<Experiments>) -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(|ab: Assignments| async move { ab.variant("button").unwrap_or_default() }),
+            )
+            .route("/none", get(|| async { "" }))
+            .layer(from_fn_with_state(registry, experiments_layer))
+    }
+
+    async fn call(app: &Router, uri: &str, cookie: Option<&str>) -> (Option<String>, String) {
+        let mut req = axum::http::Request::get(uri);
+        if let Some(c) = cookie {
+            req = req.header(COOKIE, c);
+        }
+        let res = app
+            .clone()
+            .oneshot(req.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let set = res
+            .headers()
+            .get(SET_COOKIE)
+            .map(|v| v.to_str().unwrap().split(';').next().unwrap().to_string());
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        (set, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn assignment_is_deterministic_and_weighted() {
+        let exp = Experiment::new("e").variant("a", 1).variant("b", 3);
+        assert_eq!(exp.assign("visitor-1"), exp.assign("visitor-1"));
+
+        let b = (0..2000)
+            .filter(|i| exp.assign(&i.to_string()) == Some("b"))
+            .count();
+        assert!((1300..1700).contains(&b), "b = {b}");
+
+        assert_eq!(Experiment::new("off").variant("a", 0).assign("x"), None);
+    }
+
+    #[tokio::test]
+    async fn cookie_keeps_the_variant_and_exposures_are_emitted() {
+        let registry = registry();
+        let mut exposures = registry.subscribe();
+        let app = app(registry.clone());
+
+        let (none, _) = call(&app, "/none", None).await;
+        assert_eq!(none, None);
+
+        let (set, variant) = call(&app, "/", None).await;
+        let cookie = set.expect("assignment cookie");
+        assert!(cookie.starts_with("ab="));
+        assert!(variant == "blue" || variant == "green");
+
+        let exposure = exposures.try_recv().unwrap();
+        assert_eq!(
+            (exposure.experiment.as_str(), exposure.variant.as_str()),
+            ("button", variant.as_str())
+        );
+
+        for _ in 0..5 {
+            let (set, again) = call(&app, "/", Some(&cookie)).await;
+            assert_eq!(again, variant);
+            assert_eq!(set, None);
+        }
+        assert_eq!(exposures.try_recv().unwrap().subject, exposure.subject);
+    }
+
+    #[tokio::test]
+    async fn tampered_cookies_start_a_new_visitor() {
+        let registry = registry();
+        let (set, _) = call(&app(registry.clone()), "/", None).await;
+        let cookie = set.unwrap();
+        let forged = format!("{}x", cookie);
+        let (set, _) = call(&app(registry), "/", Some(&forged)).await;
+        assert!(set.is_some_and(|c| c != cookie));
+    }
+
+    #[tokio::test]
+    async fn signed_in_users_are_assigned_by_subject() {
+        let registry = registry();
+        let app = app(registry.clone()).layer(axum::Extension(CurrentUser::new("user-9")));
+        let (_, variant) = call(&app, "/", None).await;
+        let expected = registry.experiments["button"].assign("user-9").unwrap();
+        assert_eq!(variant, expected);
+    }
+
+    #[tokio::test]
+    async fn unknown_experiments_are_none() {
+        let registry = registry();
+        let app = Router::new()
+            .route(
+                "/",
+                get(|ab: Assignments| async move { format!("{:?}", ab.variant("nope")) }),
+            )
+            .layer(from_fn_with_state(registry, experiments_layer));
+        let (set, body) = call(&app, "/", None).await;
+        assert_eq!((set, body.as_str()), (None, "None"));
+    }
+}
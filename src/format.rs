@@ -0,0 +1,11 @@
+//! # Formatting
+//!
+//! Locale-aware formatting for invoices and dashboards.
+//!
+//! - [`number`] — number, currency, percentage and abbreviation helpers
+//! - [`filters`] — Askama filters exposing the same helpers to templates
+//!
+//! Locales are passed explicitly as BCP 47 tags (e.g. `"ja-JP"`).
+
+pub mod filters;
+pub mod number;
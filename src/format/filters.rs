@@ -0,0 +1,160 @@
+//! Askama filters wrapping [`number`](crate::format::number).
+//!
+//! Askama resolves custom filters through a `filters` module in scope of the
+//! template struct, so import this module under that name:
+//!
+//! ```rust
+//! use askama::Template;
+//! use wzs_web::format::filters;
+//!
+//! #[derive(Template)]
+//! #[template(
+//!     source = "{{ total|currency(\"USD\", locale) }} / {{ rate|percent(1, locale) }} / {{ views|abbrev(locale) }}",
+//!     ext = "txt"
+//! )]
+//! struct Invoice<'a> {
+//!     total: f64,
+//!     rate: f64,
+//!     views: u64,
+//!     locale: &'a str,
+//! }
+//!
+//! let out = Invoice { total: 1234.5, rate: 0.075, views: 15_300, locale: "en-US" }
+//!     .render()
+//!     .unwrap();
+//! assert_eq!(out, "$1,234.50 / 7.5% / 15.3K");
+//! ```
+
+use crate::format::number;
+
+/// Numeric values accepted by the filters (by value or by reference).
+pub trait FilterNumber {
+    /// Converts to `f64` for formatting.
+    fn to_f64(&self) -> f64;
+}
+
+macro_rules! impl_filter_number {
+    ($($t:ty),*) => {
+        $(impl FilterNumber for $t {
+            fn to_f64(&self) -> f64 {
+                *self as f64
+            }
+        })*
+    };
+}
+
+impl_filter_number!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+impl<T: FilterNumber + ?Sized> FilterNumber for &T {
+    fn to_f64(&self) -> f64 {
+        (**self).to_f64()
+    }
+}
+
+/// Converts a decimals argument (literal or field) to `usize`.
+fn digits(d: impl FilterNumber) -> usize {
+    d.to_f64().max(0.0) as usize
+}
+
+/// `{{ value|number(decimals, locale) }}`
+pub fn number(
+    value: impl FilterNumber,
+    _: &dyn askama::Values,
+    decimals: impl FilterNumber,
+    locale: impl AsRef<str>,
+) -> askama::Result<String> {
+    Ok(number::format_number(
+        value.to_f64(),
+        digits(decimals),
+        locale.as_ref(),
+    ))
+}
+
+/// `{{ amount|currency("USD", locale) }}`
+pub fn currency(
+    value: impl FilterNumber,
+    _: &dyn askama::Values,
+    code: impl AsRef<str>,
+    locale: impl AsRef<str>,
+) -> askama::Result<String> {
+    Ok(number::format_currency(
+        value.to_f64(),
+        code.as_ref(),
+        locale.as_ref(),
+    ))
+}
+
+/// `{{ ratio|percent(decimals, locale) }}`
+pub fn percent(
+    value: impl FilterNumber,
+    _: &dyn askama::Values,
+    decimals: impl FilterNumber,
+    locale: impl AsRef<str>,
+) -> askama::Result<String> {
+    Ok(number::format_percent(
+        value.to_f64(),
+        digits(decimals),
+        locale.as_ref(),
+    ))
+}
+
+/// `{{ value|abbrev(locale) }}`
+pub fn abbrev(
+    value: impl FilterNumber,
+    _: &dyn askama::Values,
+    locale: impl AsRef<str>,
+) -> askama::Result<String> {
+    Ok(number::abbreviate(value.to_f64(), locale.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use askama::Template;
+
+    use crate::format::filters;
+
+    #[derive(Template)]
+    #[template(
+        source = "{{ n|number(2, locale) }}|{{ amount|currency(code, locale) }}|{{ ratio|percent(0, locale) }}|{{ big|abbrev(locale) }}",
+        ext = "html"
+    )]
+    struct Dashboard<'a> {
+        n: i64,
+        amount: f64,
+        code: String,
+        ratio: f32,
+        big: u64,
+        locale: &'a str,
+    }
+
+    #[test]
+    fn filters_render_with_field_and_literal_arguments() {
+        let t = Dashboard {
+            n: 1234567,
+            amount: 99.5,
+            code: "EUR".into(),
+            ratio: 0.25,
+            big: 350_000_000,
+            locale: "de-DE",
+        };
+
+        assert_eq!(
+            t.render().unwrap(),
+            "1.234.567,00|99,50\u{a0}€|25\u{a0}%|350M"
+        );
+    }
+
+    #[test]
+    fn filters_follow_japanese_conventions() {
+        let t = Dashboard {
+            n: 1000,
+            amount: 1500.0,
+            code: "JPY".into(),
+            ratio: 0.5,
+            big: 120_000,
+            locale: "ja-JP",
+        };
+
+        assert_eq!(t.render().unwrap(), "1,000.00|￥1,500|50%|12万");
+    }
+}
@@ -0,0 +1,271 @@
+//! Locale-aware number, currency and percentage formatting.
+//!
+//! Locales are identified by BCP 47 tags such as `"en-US"` or `"ja-JP"`.
+//! Only the language (and a few regions) matter for separators and currency
+//! placement; unknown tags fall back to `en-US` conventions.
+//!
+//! # Provided Functions
+//! - [`format_number`]: Grouped decimal number (`1,234.5` / `1.234,5`).
+//! - [`format_currency`]: Amount with symbol and the currency's minor digits.
+//! - [`format_percent`]: Ratio as a percentage (`0.125` → `12.5%`).
+//! - [`abbreviate`]: Compact large numbers (`1.2K`, `3.4M`, `1.2万`).
+//!
+//! ## Example
+//! ```
+//! use wzs_web::format::number::{abbreviate, format_currency, format_percent};
+//!
+//! assert_eq!(format_currency(1234.5, "USD", "en-US"), "$1,234.50");
+//! assert_eq!(format_currency(1234.5, "EUR", "de-DE"), "1.234,50\u{a0}€");
+//! assert_eq!(format_currency(1234.5, "JPY", "ja-JP"), "￥1,235");
+//! assert_eq!(format_percent(0.125, 1, "en-US"), "12.5%");
+//! assert_eq!(abbreviate(1_250_000.0, "en-US"), "1.3M");
+//! assert_eq!(abbreviate(12_000.0, "ja-JP"), "1.2万");
+//! ```
+
+/// Separator and placement conventions for one locale.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NumberLocale {
+    /// Decimal separator.
+    pub decimal: &'static str,
+    /// Thousands separator.
+    pub group: &'static str,
+    /// `true` when the currency symbol follows the amount (`1.234,50 €`).
+    pub currency_after: bool,
+    /// Text between the percentage and `%` (e.g. a non-breaking space).
+    pub percent_gap: &'static str,
+    /// `true` for East Asian 10⁴-based abbreviations (万 / 億 / 兆).
+    pub myriad: bool,
+}
+
+impl NumberLocale {
+    /// `en-US` conventions (also the fallback).
+    pub const EN: NumberLocale = NumberLocale {
+        decimal: ".",
+        group: ",",
+        currency_after: false,
+        percent_gap: "",
+        myriad: false,
+    };
+
+    /// Resolves a BCP 47 tag (case-insensitive, `-` or `_`).
+    pub fn for_tag(tag: &str) -> NumberLocale {
+        let tag = tag.trim().replace('_', "-").to_ascii_lowercase();
+        let lang = tag.split('-').next().unwrap_or_default();
+
+        match lang {
+            "ja" | "zh" => NumberLocale {
+                myriad: true,
+                ..Self::EN
+            },
+            "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" => NumberLocale {
+                decimal: ",",
+                group: ".",
+                currency_after: true,
+                percent_gap: "\u{a0}",
+                myriad: false,
+            },
+            "fr" => NumberLocale {
+                decimal: ",",
+                group: "\u{202f}",
+                currency_after: true,
+                percent_gap: "\u{202f}",
+                myriad: false,
+            },
+            "sv" | "nb" | "fi" | "pl" | "cs" | "ru" => NumberLocale {
+                decimal: ",",
+                group: "\u{a0}",
+                currency_after: true,
+                percent_gap: "\u{a0}",
+                myriad: false,
+            },
+            _ => Self::EN,
+        }
+    }
+}
+
+/// Returns `(symbol, minor digits)` for an ISO 4217 code.
+///
+/// Unknown codes use the code itself and two decimals.
+fn currency_info(code: &str, locale_tag: &str) -> (String, usize) {
+    let code = code.trim().to_ascii_uppercase();
+    let lang_ja = locale_tag.to_ascii_lowercase().starts_with("ja");
+
+    let (symbol, digits) = match code.as_str() {
+        "USD" => ("$", 2),
+        "EUR" => ("€", 2),
+        "GBP" => ("£", 2),
+        "JPY" if lang_ja => ("￥", 0),
+        "JPY" => ("¥", 0),
+        "KRW" => ("₩", 0),
+        "CNY" => ("CN¥", 2),
+        "AUD" => ("A$", 2),
+        "CAD" => ("CA$", 2),
+        "INR" => ("₹", 2),
+        "CHF" => ("CHF", 2),
+        _ => return (code, 2),
+    };
+    (symbol.to_string(), digits)
+}
+
+/// Formats `value` with `decimals` fraction digits and locale separators.
+///
+/// ```
+/// use wzs_web::format::number::format_number;
+/// assert_eq!(format_number(-1234567.891, 2, "en-US"), "-1,234,567.89");
+/// assert_eq!(format_number(1234567.891, 1, "de-DE"), "1.234.567,9");
+/// ```
+pub fn format_number(value: f64, decimals: usize, locale: &str) -> String {
+    format_with(value, decimals, &NumberLocale::for_tag(locale))
+}
+
+/// Formats a monetary amount using the currency's minor digits.
+pub fn format_currency(amount: f64, currency: &str, locale: &str) -> String {
+    let loc = NumberLocale::for_tag(locale);
+    let (symbol, digits) = currency_info(currency, locale);
+    let number = format_with(amount.abs(), digits, &loc);
+    let sign = if is_negative(amount, digits) { "-" } else { "" };
+
+    if loc.currency_after {
+        format!("{sign}{number}\u{a0}{symbol}")
+    } else {
+        format!("{sign}{symbol}{number}")
+    }
+}
+
+/// Formats a ratio (`0.125`) as a percentage (`12.5%`).
+pub fn format_percent(ratio: f64, decimals: usize, locale: &str) -> String {
+    let loc = NumberLocale::for_tag(locale);
+    format!(
+        "{}{}%",
+        format_with(ratio * 100.0, decimals, &loc),
+        loc.percent_gap
+    )
+}
+
+/// Abbreviates large numbers with one fraction digit (`1.2K`, `3.4M`).
+///
+/// Japanese and Chinese locales use 万 / 億 / 兆. Values below the first
+/// unit are formatted as whole numbers.
+pub fn abbreviate(value: f64, locale: &str) -> String {
+    let loc = NumberLocale::for_tag(locale);
+    let units: &[(f64, &str)] = if loc.myriad {
+        &[(1e12, "兆"), (1e8, "億"), (1e4, "万")]
+    } else {
+        &[(1e12, "T"), (1e9, "B"), (1e6, "M"), (1e3, "K")]
+    };
+
+    let abs = value.abs();
+    let Some(mut idx) = units.iter().position(|(scale, _)| abs >= *scale) else {
+        return format_with(value, 0, &loc);
+    };
+    // Promote 999.96K to 1M instead of printing 1000K.
+    if idx > 0 && (abs / units[idx].0 * 10.0).round() / 10.0 >= units[idx - 1].0 / units[idx].0 {
+        idx -= 1;
+    }
+
+    let (scale, suffix) = units[idx];
+    let n = format_with(abs / scale, 1, &loc);
+    let n = match n.strip_suffix(&format!("{}0", loc.decimal)) {
+        Some(whole) => whole.to_string(),
+        None => n,
+    };
+    let sign = if value < 0.0 { "-" } else { "" };
+    format!("{sign}{n}{suffix}")
+}
+
+/// Rounds half away from zero (`format!` alone rounds half to even).
+fn round_half_away(value: f64, decimals: usize) -> f64 {
+    let factor = 10f64.powi(decimals.min(15) as i32);
+    (value * factor).round() / factor
+}
+
+/// `true` if `value` is still negative after rounding to `decimals`.
+fn is_negative(value: f64, decimals: usize) -> bool {
+    value < 0.0
+        && format!("{:.*}", decimals, round_half_away(value.abs(), decimals))
+            .bytes()
+            .any(|b| b > b'0' && b <= b'9')
+}
+
+fn format_with(value: f64, decimals: usize, loc: &NumberLocale) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+
+    let raw = format!("{:.*}", decimals, round_half_away(value.abs(), decimals));
+    let (int_part, frac_part) = match raw.split_once('.') {
+        Some((i, f)) => (i, Some(f)),
+        None => (raw.as_str(), None),
+    };
+
+    let mut out = String::with_capacity(raw.len() + int_part.len() / 3 * loc.group.len() + 1);
+    if is_negative(value, decimals) {
+        out.push('-');
+    }
+    let len = int_part.len();
+    for (i, ch) in int_part.chars().enumerate() {
+        if i > 0 && (len - i) % 3 == 0 {
+            out.push_str(loc.group);
+        }
+        out.push(ch);
+    }
+    if let Some(f) = frac_part {
+        out.push_str(loc.decimal);
+        out.push_str(f);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_use_locale_separators() {
+        assert_eq!(format_number(0.0, 0, "en-US"), "0");
+        assert_eq!(format_number(999.0, 0, "en-US"), "999");
+        assert_eq!(format_number(1000.0, 0, "en-US"), "1,000");
+        assert_eq!(format_number(1234567.891, 2, "en_us"), "1,234,567.89");
+        assert_eq!(
+            format_number(1234567.891, 2, "fr-FR"),
+            "1\u{202f}234\u{202f}567,89"
+        );
+        assert_eq!(format_number(-0.001, 2, "en-US"), "0.00");
+    }
+
+    #[test]
+    fn unknown_locale_falls_back_to_english() {
+        assert_eq!(format_number(1234.5, 1, "xx"), "1,234.5");
+        assert_eq!(format_number(1234.5, 1, ""), "1,234.5");
+    }
+
+    #[test]
+    fn currency_placement_and_minor_digits() {
+        assert_eq!(format_currency(-1234.5, "usd", "en-US"), "-$1,234.50");
+        assert_eq!(format_currency(1234.5, "JPY", "en-US"), "¥1,235");
+        assert_eq!(
+            format_currency(1234.5, "EUR", "fr-FR"),
+            "1\u{202f}234,50\u{a0}€"
+        );
+        assert_eq!(format_currency(10.0, "XYZ", "en-US"), "XYZ10.00");
+    }
+
+    #[test]
+    fn percentages() {
+        assert_eq!(format_percent(0.5, 0, "en-US"), "50%");
+        assert_eq!(format_percent(0.1234, 1, "de-DE"), "12,3\u{a0}%");
+        assert_eq!(format_percent(-0.05, 0, "ja-JP"), "-5%");
+    }
+
+    #[test]
+    fn abbreviations() {
+        assert_eq!(abbreviate(999.0, "en-US"), "999");
+        assert_eq!(abbreviate(1000.0, "en-US"), "1K");
+        assert_eq!(abbreviate(1540.0, "en-US"), "1.5K");
+        assert_eq!(abbreviate(999_960.0, "en-US"), "1M");
+        assert_eq!(abbreviate(-2_500_000_000.0, "en-US"), "-2.5B");
+        assert_eq!(abbreviate(1234.0, "de-DE"), "1,2K");
+        assert_eq!(abbreviate(9999.0, "ja-JP"), "9,999");
+        assert_eq!(abbreviate(350_000_000.0, "ja-JP"), "3.5億");
+    }
+}
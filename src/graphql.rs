@@ -1,5 +1,8 @@
 pub mod config;
 pub mod context;
+pub mod fragments;
 pub mod graphiql;
 pub mod guard;
 pub mod handler;
+pub mod loader;
+pub mod operation_policy;
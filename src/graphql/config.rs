@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 /// Configuration for GraphQL authentication handling.
 ///
 /// This configuration is injected via `axum::Extension` and
@@ -8,14 +10,59 @@ pub struct GraphqlAuthConfig {
     ///
     /// Example: `"foo_token"`
     pub jwt_cookie_name: String,
+
+    /// Also accept `Authorization: Bearer <jwt>`, for clients without
+    /// cookies such as mobile apps (default: `false`). A valid bearer token
+    /// takes precedence over the cookie.
+    pub accept_bearer: bool,
+
+    /// Accept `Authorization: Bearer <jwt>` and skip CSRF validation for
+    /// requests it authenticates (default: `false`). Implies
+    /// [`accept_bearer`](Self::accept_bearer).
+    ///
+    /// Browsers never attach this header on their own, so such requests
+    /// cannot be forged cross-site. Requests without a valid bearer token
+    /// (including all cookie-authenticated ones) are still CSRF-checked.
+    pub bearer_csrf_exempt: bool,
+
+    /// Time budget of one request, exposed to resolvers as
+    /// [`RequestContext::deadline`](crate::graphql::context::RequestContext::deadline)
+    /// (default: none).
+    pub request_timeout: Option<Duration>,
 }
 
 impl GraphqlAuthConfig {
     pub fn new(jwt_cookie_name: impl Into<String>) -> Self {
         Self {
             jwt_cookie_name: jwt_cookie_name.into(),
+            accept_bearer: false,
+            bearer_csrf_exempt: false,
+            request_timeout: None,
         }
     }
+
+    /// Sets [`accept_bearer`](Self::accept_bearer).
+    pub fn accept_bearer(mut self, accept: bool) -> Self {
+        self.accept_bearer = accept;
+        self
+    }
+
+    /// Whether bearer tokens are read at all.
+    pub fn bearer_enabled(&self) -> bool {
+        self.accept_bearer || self.bearer_csrf_exempt
+    }
+
+    /// Sets [`bearer_csrf_exempt`](Self::bearer_csrf_exempt).
+    pub fn bearer_csrf_exempt(mut self, exempt: bool) -> Self {
+        self.bearer_csrf_exempt = exempt;
+        self
+    }
+
+    /// Sets [`request_timeout`](Self::request_timeout).
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -27,6 +74,17 @@ mod tests {
         let cfg = GraphqlAuthConfig::new("foo_token");
 
         assert_eq!(cfg.jwt_cookie_name, "foo_token");
+        assert!(!cfg.bearer_csrf_exempt);
+        assert!(!cfg.bearer_enabled());
+        assert!(cfg.clone().accept_bearer(true).bearer_enabled());
+        assert!(cfg.bearer_csrf_exempt(true).bearer_csrf_exempt);
+    }
+
+    #[test]
+    fn csrf_exemption_implies_bearer() {
+        let cfg = GraphqlAuthConfig::new("t").bearer_csrf_exempt(true);
+        assert!(!cfg.accept_bearer);
+        assert!(cfg.bearer_enabled());
     }
 
     #[test]
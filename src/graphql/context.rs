@@ -1,19 +1,151 @@
-use axum::http::HeaderMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use async_graphql::{Context, ErrorExtensions};
+use axum::http::{header::AUTHORIZATION, Extensions, HeaderMap};
 use axum_extra::extract::cookie::CookieJar;
 
 use crate::auth::jwt::decode_jwt;
+use crate::auth::revocation::{is_revoked, TokenRevocationStore};
 use crate::auth::CurrentUser;
+use crate::db::context::DbContext;
+use crate::web::locale::accept_language;
+use crate::web::middleware::request_id::RequestId;
+use crate::web::secure_cookie::SecureCookies;
+
+/// Tenant of the current request, inserted as a request extension by the
+/// application's tenant resolution (host name, path, header, …).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TenantId(pub String);
+
+pub use crate::web::client_ip::ClientIp;
 
-/// Extract an authenticated principal (`CurrentUser`) from a JWT stored in a cookie.
+/// Request-scoped values for resolvers, injected by
+/// [`graphql_post_handler`](crate::graphql::handler::graphql_post_handler)
+/// as a single `data()` value.
+///
+/// # Example
+/// ```rust
+/// use async_graphql::{Context, Object, Result};
+/// use wzs_web::graphql::context::RequestContext;
+///
+/// struct Query;
+///
+/// #[Object]
+/// impl Query {
+///     async fn greeting(&self, ctx: &Context<'_>) -> Result<String> {
+///         let rc = RequestContext::of(ctx);
+///         let user = rc.require_user()?;
+///         let hello = match rc.locale() {
+///             Some(l) if l.starts_with("ja") => "こんにちは",
+///             _ => "Hello",
+///         };
+///         Ok(format!("{hello}, {}", user.subject))
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RequestContext {
+    pub current_user: Option<CurrentUser>,
+    pub request_id: Option<String>,
+    /// Preferred language tag from `Accept-Language` (e.g. `"ja-JP"`).
+    pub locale: Option<String>,
+    pub tenant: Option<String>,
+    pub client_ip: Option<IpAddr>,
+    /// Point in time by which the request should be answered.
+    pub deadline: Option<Instant>,
+}
+
+impl RequestContext {
+    /// Builds a context from the request headers and extensions
+    /// ([`RequestId`], [`TenantId`], [`ClientIp`] / `ConnectInfo<SocketAddr>`).
+    pub fn from_request(
+        headers: &HeaderMap,
+        extensions: &Extensions,
+        current_user: Option<CurrentUser>,
+        timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            current_user,
+            request_id: extensions.get::<RequestId>().map(|id| id.0.clone()),
+            locale: accept_language(headers).into_iter().next(),
+            tenant: extensions.get::<TenantId>().map(|t| t.0.clone()),
+            client_ip: ClientIp::of(extensions),
+            deadline: timeout.map(|t| Instant::now() + t),
+        }
+    }
+
+    /// Returns the context injected for this request (an empty one when
+    /// the schema runs outside the handler, e.g. in tests).
+    pub fn of<'a>(ctx: &'a Context<'_>) -> &'a RequestContext {
+        static EMPTY: RequestContext = RequestContext {
+            current_user: None,
+            request_id: None,
+            locale: None,
+            tenant: None,
+            client_ip: None,
+            deadline: None,
+        };
+        ctx.data_opt::<RequestContext>().unwrap_or(&EMPTY)
+    }
+
+    /// The authenticated principal, if any.
+    pub fn user(&self) -> Option<&CurrentUser> {
+        self.current_user.as_ref()
+    }
+
+    /// The authenticated principal, or an `UNAUTHENTICATED` GraphQL error.
+    pub fn require_user(&self) -> async_graphql::Result<&CurrentUser> {
+        self.current_user.as_ref().ok_or_else(|| {
+            async_graphql::Error::new("authentication required")
+                .extend_with(|_, e| e.set("code", "UNAUTHENTICATED"))
+        })
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    pub fn locale(&self) -> Option<&str> {
+        self.locale.as_deref()
+    }
+
+    pub fn tenant(&self) -> Option<&str> {
+        self.tenant.as_deref()
+    }
+
+    pub fn client_ip(&self) -> Option<IpAddr> {
+        self.client_ip
+    }
+
+    /// Time left before the deadline (`None` when there is none).
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|d| d.saturating_duration_since(Instant::now()))
+    }
+
+    /// [`DbContext`] carrying the request id and deadline.
+    pub fn db_context(&self) -> DbContext {
+        DbContext {
+            request_id: self.request_id.clone(),
+            deadline: self.deadline,
+            read_only: false,
+        }
+    }
+}
+
+/// Extract an authenticated principal (`CurrentUser`) from a JWT in an
+/// `Authorization: Bearer` header or a cookie.
 ///
 /// # Overview
 ///
 /// This function is **application-agnostic** and performs only authentication:
 ///
-/// - Reads a JWT from a cookie
+/// - Reads a JWT from an `Authorization: Bearer` header, or else from a
+///   cookie
 /// - Verifies it using the provided secret
-/// - Extracts the `sub` (subject) claim
-/// - Wraps it in [`CurrentUser`]
+/// - Extracts the `sub` (subject), `roles` and `scope` claims
+/// - Wraps them in [`CurrentUser`] ([`CurrentUser::from_claims`])
 ///
 /// It does **not**:
 ///
@@ -26,8 +158,9 @@ use crate::auth::CurrentUser;
 /// - `jar`:
 ///   The cookie jar containing the JWT cookie.
 /// - `headers`:
-///   Currently unused, but kept for forward compatibility
-///   (e.g. Authorization header support).
+///   Request headers; a valid bearer token takes precedence over the
+///   cookie (see [`extract_bearer_user`]). Pass an empty map to use the
+///   cookie only.
 /// - `jwt_secret`:
 ///   The secret used to verify the JWT.
 ///   If `None`, authentication is disabled and this function always returns `None`.
@@ -59,7 +192,43 @@ use crate::auth::CurrentUser;
 /// ```
 pub fn extract_current_user(
     jar: &CookieJar,
-    _headers: &HeaderMap,
+    headers: &HeaderMap,
+    jwt_secret: Option<&str>,
+    cookie_name: &str,
+) -> Option<CurrentUser> {
+    extract_bearer_user(headers, jwt_secret)
+        .or_else(|| extract_cookie_user(jar, jwt_secret, cookie_name))
+}
+
+/// Same as [`extract_current_user`], but also rejects tokens revoked in
+/// `store` (see [`revocation`](crate::auth::revocation)).
+///
+/// A revoked bearer token falls back to the cookie, like an invalid one.
+/// `store` is called synchronously; use an in-memory or
+/// [cached](crate::auth::revocation::CachedTokenRevocationStore) store on
+/// async paths.
+pub fn extract_current_user_checked(
+    jar: &CookieJar,
+    headers: &HeaderMap,
+    jwt_secret: Option<&str>,
+    cookie_name: &str,
+    store: &dyn TokenRevocationStore,
+) -> Option<CurrentUser> {
+    extract_bearer_user(headers, jwt_secret)
+        .filter(|user| !is_revoked(user, store))
+        .or_else(|| {
+            extract_cookie_user(jar, jwt_secret, cookie_name)
+                .filter(|user| !is_revoked(user, store))
+        })
+}
+
+/// Extract a `CurrentUser` from the JWT cookie only.
+///
+/// The cookie holds a JSON payload `{"token": "<jwt>"}`. Returns `None` when
+/// `jwt_secret` is `None`, the cookie is missing or malformed, or the token
+/// fails verification.
+pub fn extract_cookie_user(
+    jar: &CookieJar,
     jwt_secret: Option<&str>,
     cookie_name: &str,
 ) -> Option<CurrentUser> {
@@ -69,12 +238,57 @@ pub fn extract_current_user(
         .and_then(|cookie| serde_json::from_str::<serde_json::Value>(cookie.value()).ok())
         .and_then(|value| value.get("token")?.as_str().map(String::from))
         .and_then(|token| decode_jwt(&token, secret).ok())
-        .map(|claims| CurrentUser::new(claims.sub))
+        .map(|claims| CurrentUser::from_claims(&claims))
+}
+
+/// Extract a `CurrentUser` from a JWT cookie written with
+/// [`SecureCookies::encrypted_cookie`].
+///
+/// The cookie value is the encrypted token itself, so neither the browser
+/// nor scripts can read it. Returns `None` when `jwt_secret` is `None`, the
+/// cookie is missing or cannot be decrypted, or the token fails verification.
+pub fn extract_encrypted_cookie_user(
+    jar: &CookieJar,
+    jwt_secret: Option<&str>,
+    cookie_name: &str,
+    cookies: &SecureCookies,
+) -> Option<CurrentUser> {
+    let secret = jwt_secret?;
+    let token = cookies.get_encrypted(jar, cookie_name)?;
+    decode_jwt(&token, secret)
+        .ok()
+        .map(|claims| CurrentUser::from_claims(&claims))
+}
+
+/// Extract a `CurrentUser` from an `Authorization: Bearer <jwt>` header.
+///
+/// Used by non-cookie clients (mobile apps, server-to-server calls).
+/// Returns `None` when `jwt_secret` is `None`, the header is missing or not
+/// a bearer credential, or the token fails verification.
+pub fn extract_bearer_user(headers: &HeaderMap, jwt_secret: Option<&str>) -> Option<CurrentUser> {
+    let secret = jwt_secret?;
+    let token = bearer_token(headers)?;
+    decode_jwt(token, secret)
+        .ok()
+        .map(|claims| CurrentUser::from_claims(&claims))
+}
+
+/// Returns the token of an `Authorization: Bearer <token>` header.
+///
+/// The scheme is matched case-insensitively.
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.trim().split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::net::SocketAddr;
+
+    use axum::extract::ConnectInfo;
     use axum::http::HeaderMap;
     use axum_extra::extract::cookie::{Cookie, CookieJar};
 
@@ -130,4 +344,124 @@ mod tests {
 
         assert_eq!(user.subject, "42");
     }
+
+    #[test]
+    fn encrypted_cookie_user_requires_a_sealed_token() {
+        let cookies = SecureCookies::new("k1", [3u8; 32]);
+        let token = create_jwt(42, JWT_SECRET).unwrap();
+        let jar = CookieJar::new().add(cookies.encrypted_cookie(COOKIE_NAME, &token).unwrap());
+
+        let user =
+            extract_encrypted_cookie_user(&jar, Some(JWT_SECRET), COOKIE_NAME, &cookies).unwrap();
+        assert_eq!(user.subject, "42");
+
+        let plain = CookieJar::new().add(Cookie::new(COOKIE_NAME, token));
+        assert!(
+            extract_encrypted_cookie_user(&plain, Some(JWT_SECRET), COOKIE_NAME, &cookies)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn bearer_header_takes_precedence_over_cookie() {
+        let jar = jar_with_token(&create_jwt(42, JWT_SECRET).unwrap());
+        let mut headers = headers();
+        let bearer = format!("Bearer {}", create_jwt(7, JWT_SECRET).unwrap());
+        headers.insert(AUTHORIZATION, bearer.parse().unwrap());
+
+        let user = extract_current_user(&jar, &headers, Some(JWT_SECRET), COOKIE_NAME).unwrap();
+        assert_eq!(user.subject, "7");
+
+        let cookieless =
+            extract_current_user(&CookieJar::new(), &headers, Some(JWT_SECRET), COOKIE_NAME);
+        assert_eq!(cookieless.unwrap().subject, "7");
+
+        headers.insert(AUTHORIZATION, "Bearer forged".parse().unwrap());
+        let user = extract_current_user(&jar, &headers, Some(JWT_SECRET), COOKIE_NAME).unwrap();
+        assert_eq!(user.subject, "42");
+        assert!(extract_cookie_user(&CookieJar::new(), Some(JWT_SECRET), COOKIE_NAME).is_none());
+    }
+
+    #[test]
+    fn checked_extraction_skips_revoked_tokens() {
+        use crate::auth::revocation::{revoke_claims, MemoryTokenRevocationStore};
+
+        let store = MemoryTokenRevocationStore::default();
+        let cookie_token = create_jwt(42, JWT_SECRET).unwrap();
+        let bearer_token = create_jwt(7, JWT_SECRET).unwrap();
+        let jar = jar_with_token(&cookie_token);
+        let mut headers = headers();
+        headers.insert(
+            AUTHORIZATION,
+            format!("Bearer {bearer_token}").parse().unwrap(),
+        );
+
+        let user =
+            extract_current_user_checked(&jar, &headers, Some(JWT_SECRET), COOKIE_NAME, &store);
+        assert_eq!(user.unwrap().subject, "7");
+
+        revoke_claims(&decode_jwt(&bearer_token, JWT_SECRET).unwrap(), &store).unwrap();
+        let user =
+            extract_current_user_checked(&jar, &headers, Some(JWT_SECRET), COOKIE_NAME, &store);
+        assert_eq!(user.unwrap().subject, "42");
+
+        revoke_claims(&decode_jwt(&cookie_token, JWT_SECRET).unwrap(), &store).unwrap();
+        assert!(extract_current_user_checked(
+            &jar,
+            &headers,
+            Some(JWT_SECRET),
+            COOKIE_NAME,
+            &store
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn request_context_collects_headers_and_extensions() {
+        let mut headers = headers();
+        headers.insert(
+            axum::http::header::ACCEPT_LANGUAGE,
+            "en;q=0.8, ja-JP, *;q=0.5, fr;q=0".parse().unwrap(),
+        );
+        let mut extensions = Extensions::new();
+        extensions.insert(RequestId("req-1".into()));
+        extensions.insert(TenantId("acme".into()));
+        extensions.insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4000))));
+
+        let rc = RequestContext::from_request(
+            &headers,
+            &extensions,
+            Some(CurrentUser::new("7")),
+            Some(Duration::from_secs(5)),
+        );
+        assert_eq!(rc.user().unwrap().subject, "7");
+        assert_eq!(rc.request_id(), Some("req-1"));
+        assert_eq!(rc.locale(), Some("ja-JP"));
+        assert_eq!(rc.tenant(), Some("acme"));
+        assert_eq!(rc.client_ip(), Some(IpAddr::from([10, 0, 0, 1])));
+        assert!(rc.remaining().unwrap() > Duration::from_secs(4));
+        assert_eq!(rc.db_context().request_id.as_deref(), Some("req-1"));
+
+        extensions.insert(ClientIp(IpAddr::from([203, 0, 113, 9])));
+        let rc = RequestContext::from_request(&HeaderMap::new(), &extensions, None, None);
+        assert_eq!(rc.client_ip(), Some(IpAddr::from([203, 0, 113, 9])));
+        assert_eq!(rc.locale(), None);
+        assert_eq!(rc.remaining(), None);
+        assert!(rc.require_user().is_err());
+    }
+
+    #[test]
+    fn bearer_header_authenticates_without_cookie() {
+        let token = create_jwt(7, JWT_SECRET).unwrap();
+        let mut headers = headers();
+        headers.insert(AUTHORIZATION, format!("bearer {token}").parse().unwrap());
+
+        let user = extract_bearer_user(&headers, Some(JWT_SECRET)).unwrap();
+        assert_eq!(user.subject, "7");
+        assert!(extract_bearer_user(&headers, Some("other-secret")).is_none());
+        assert!(extract_bearer_user(&headers, None).is_none());
+
+        headers.insert(AUTHORIZATION, "Basic dXNlcjpwYXNz".parse().unwrap());
+        assert!(bearer_token(&headers).is_none());
+    }
 }
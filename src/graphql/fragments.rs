@@ -0,0 +1,456 @@
+//! # Mergeable GraphQL Root Fragments
+//!
+//! Provides ready-made query and mutation root fragments for functionality
+//! already offered by this crate, so applications can merge them into their
+//! own schema instead of re-declaring the same resolvers.
+//!
+//! Each fragment is a plain `#[Object]` type with a `Default` implementation,
+//! which makes it usable inside `#[derive(MergedObject)]` roots.
+//!
+//! - [`HealthQuery`] — `health` liveness field
+//! - [`CurrentUserQuery`] — `currentUser` field backed by the injected
+//!   `Option<CurrentUser>` (see [`graphql_post_handler`](crate::graphql::handler::graphql_post_handler))
+//! - [`UploadMutation`] — `upload` mutation backed by an injected
+//!   `Arc<UploadService>`
+//!
+//! # Example
+//! ```rust,no_run
+//! use async_graphql::{EmptySubscription, MergedObject, Object, Schema};
+//! use wzs_web::graphql::fragments::{CurrentUserQuery, HealthQuery, UploadMutation};
+//!
+//! #[derive(Default)]
+//! struct AppQuery;
+//!
+//! #[Object]
+//! impl AppQuery {
+//!     async fn version(&self) -> &str {
+//!         "1.0.0"
+//!     }
+//! }
+//!
+//! #[derive(MergedObject, Default)]
+//! struct QueryRoot(AppQuery, HealthQuery, CurrentUserQuery);
+//!
+//! #[derive(MergedObject, Default)]
+//! struct MutationRoot(UploadMutation);
+//!
+//! let schema = Schema::build(
+//!     QueryRoot::default(),
+//!     MutationRoot::default(),
+//!     EmptySubscription,
+//! )
+//! .finish();
+//! ```
+
+use std::io::Read;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context as _};
+use async_graphql::{Context, InputObject, Object, Result, SimpleObject, Upload};
+
+use crate::auth::CurrentUser;
+use crate::graphql::context::RequestContext;
+use crate::web::upload::uploader::{UploadImageParamsInput, UploadService};
+
+/// Query fragment exposing a `health` field.
+///
+/// Always resolves to `"ok"` once the schema is able to execute queries.
+/// Readiness of downstream dependencies is intentionally out of scope.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HealthQuery;
+
+#[Object]
+impl HealthQuery {
+    /// Returns `"ok"` while the GraphQL endpoint is serving requests.
+    async fn health(&self) -> &str {
+        "ok"
+    }
+}
+
+/// GraphQL representation of the authenticated principal.
+///
+/// Exposes what the token asserts, mirroring [`CurrentUser`] itself.
+#[Object(name = "CurrentUser")]
+impl CurrentUser {
+    /// The JWT `sub` claim of the authenticated principal.
+    async fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    /// Roles asserted by the token.
+    async fn roles(&self) -> &[String] {
+        &self.roles
+    }
+
+    /// Scopes granted to the token.
+    async fn scopes(&self) -> &[String] {
+        &self.scopes
+    }
+}
+
+/// Query fragment exposing a `currentUser` field.
+///
+/// Resolves to `null` when the request is unauthenticated or when no
+/// `Option<CurrentUser>` has been injected into the request data.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CurrentUserQuery;
+
+#[Object]
+impl CurrentUserQuery {
+    /// Returns the authenticated principal, or `null` if unauthenticated.
+    async fn current_user(&self, ctx: &Context<'_>) -> Option<CurrentUser> {
+        ctx.data_opt::<Option<CurrentUser>>()
+            .and_then(|user| user.clone())
+    }
+}
+
+/// Image resize options accepted by the `upload` mutation.
+///
+/// The values are validated with the same rules as the multipart
+/// [`upload_handler`](crate::web::upload::upload_handler::upload_handler).
+#[derive(Clone, Debug, InputObject)]
+pub struct UploadImageInput {
+    /// Target maximum width.
+    pub max_width: u32,
+    /// Target maximum height.
+    pub max_height: u32,
+    /// Whether smaller images may be enlarged.
+    pub upscale: bool,
+    /// Resize strategy (`fit`, `contain` or `cover`).
+    pub resize_mode: String,
+    /// Background color (`#rrggbb` or `#rrggbbaa`).
+    pub background: String,
+    /// Focal point kept in frame by `cover` crops (`"x,y"` ratios).
+    pub focal_point: Option<String>,
+}
+
+impl From<UploadImageInput> for UploadImageParamsInput {
+    fn from(input: UploadImageInput) -> Self {
+        Self {
+            max_width: Some(input.max_width.to_string()),
+            max_height: Some(input.max_height.to_string()),
+            upscale: Some(input.upscale.to_string()),
+            resize_mode: Some(input.resize_mode),
+            background: Some(input.background),
+            focal_point: input.focal_point,
+        }
+    }
+}
+
+/// Result returned by the `upload` mutation.
+///
+/// Field names match the JSON response of the multipart upload handler.
+#[derive(Clone, Debug, SimpleObject)]
+pub struct UploadPayload {
+    /// Public path corresponding to the stored key.
+    pub path: String,
+    /// Original file name received from the client.
+    pub original_filename: String,
+    /// Final saved byte size.
+    pub bytes: u64,
+    /// Final content type returned by the upload service.
+    pub content_type: String,
+}
+
+/// Mutation fragment exposing an `upload` mutation.
+///
+/// Requires an `Arc<UploadService>` in the schema or request data.
+/// Authorization is left to the application (e.g. via a field guard on a
+/// wrapping fragment), matching the multipart upload handler.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UploadMutation;
+
+#[Object]
+impl UploadMutation {
+    /// Uploads a file, resizing it first when `image` options are given.
+    async fn upload(
+        &self,
+        ctx: &Context<'_>,
+        file: Upload,
+        image: Option<UploadImageInput>,
+    ) -> Result<UploadPayload> {
+        let service = ctx.data::<Arc<UploadService>>()?;
+
+        let params = image
+            .map(UploadImageParamsInput::from)
+            .unwrap_or_default()
+            .parse()
+            .map_err(|e| format!("invalid image params: {e}"))?;
+
+        let value = file.value(ctx)?;
+        let filename = value.filename.clone();
+        let content_type = value.content_type.clone().unwrap_or_default();
+
+        // Reading the spooled file and saving it are blocking IO, so both
+        // run on the blocking thread pool. Failures are logged and answered
+        // without detail, so storage errors never reach clients.
+        let service = Arc::clone(service);
+        let tenant = RequestContext::of(ctx).tenant.clone();
+        let name = filename.clone();
+        let saved = tokio::task::spawn_blocking(move || {
+            let mut bytes = Vec::new();
+            value
+                .into_read()
+                .read_to_end(&mut bytes)
+                .context("failed to read upload")?;
+            service.upload_for_tenant(tenant.as_deref(), &name, &content_type, &bytes, params)
+        })
+        .await
+        .map_err(|e| anyhow!("upload task failed: {e}"))
+        .and_then(|saved| saved)
+        .map_err(|e| {
+            tracing::error!(error = %format!("{e:#}"), "graphql upload failed");
+            "upload failed"
+        })?;
+
+        Ok(UploadPayload {
+            path: format!("/{}", saved.key),
+            original_filename: filename,
+            bytes: saved.bytes,
+            content_type: saved.content_type,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use anyhow::Result as AnyResult;
+    use async_graphql::{EmptySubscription, MergedObject, Request, Schema, UploadValue, Variables};
+
+    use crate::image::processor::{ImageProcessor, ResizeOpts};
+    use crate::web::upload::storage::FileStorage;
+
+    #[derive(Default)]
+    struct AppQuery;
+
+    #[Object]
+    impl AppQuery {
+        async fn app_name(&self) -> &str {
+            "test-app"
+        }
+    }
+
+    #[derive(MergedObject, Default)]
+    struct QueryRoot(AppQuery, HealthQuery, CurrentUserQuery);
+
+    #[derive(MergedObject, Default)]
+    struct MutationRoot(UploadMutation);
+
+    type TestSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+    #[derive(Default)]
+    struct MemoryStorage {
+        saved: Mutex<Vec<(String, Vec<u8>)>>,
+        fail: bool,
+    }
+
+    impl FileStorage for MemoryStorage {
+        fn save(&self, rel_path: &str, bytes: &[u8]) -> AnyResult<String> {
+            if self.fail {
+                anyhow::bail!("disk full at /var/uploads/{rel_path}");
+            }
+            self.saved
+                .lock()
+                .unwrap()
+                .push((rel_path.to_string(), bytes.to_vec()));
+            Ok(format!("/mem/{rel_path}"))
+        }
+    }
+
+    struct NoopImageProcessor;
+
+    impl ImageProcessor for NoopImageProcessor {
+        fn is_supported(&self, content_type: &str) -> bool {
+            content_type == "image/png"
+        }
+
+        fn resize_same_format(
+            &self,
+            img_bytes: &[u8],
+            _content_type: &str,
+            _opts: ResizeOpts,
+        ) -> AnyResult<Vec<u8>> {
+            Ok(img_bytes.to_vec())
+        }
+    }
+
+    fn schema_with(storage: Arc<MemoryStorage>) -> TestSchema {
+        let service = Arc::new(UploadService::new(storage, Arc::new(NoopImageProcessor)));
+        Schema::build(
+            QueryRoot::default(),
+            MutationRoot::default(),
+            EmptySubscription,
+        )
+        .data(service)
+        .finish()
+    }
+
+    fn upload_value(filename: &str, content_type: &str, bytes: &[u8]) -> UploadValue {
+        UploadValue {
+            filename: filename.into(),
+            content_type: Some(content_type.into()),
+            content: tempfile_with(bytes),
+        }
+    }
+
+    fn tempfile_with(bytes: &[u8]) -> std::fs::File {
+        let path = std::env::temp_dir().join(format!("fragments-test-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, bytes).unwrap();
+        let file = std::fs::File::open(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        file
+    }
+
+    #[tokio::test]
+    async fn merged_schema_resolves_app_and_health_fields() {
+        let schema = schema_with(Arc::new(MemoryStorage::default()));
+
+        let resp = schema.execute("{ appName health }").await;
+
+        assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+        let json = resp.data.into_json().unwrap();
+        assert_eq!(json["appName"], "test-app");
+        assert_eq!(json["health"], "ok");
+    }
+
+    #[tokio::test]
+    async fn current_user_is_null_without_authentication() {
+        let schema = schema_with(Arc::new(MemoryStorage::default()));
+
+        let req = Request::new("{ currentUser { subject } }").data(None::<CurrentUser>);
+        let resp = schema.execute(req).await;
+
+        assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+        assert!(resp.data.into_json().unwrap()["currentUser"].is_null());
+    }
+
+    #[tokio::test]
+    async fn current_user_is_null_when_not_injected() {
+        let schema = schema_with(Arc::new(MemoryStorage::default()));
+
+        let resp = schema.execute("{ currentUser { subject } }").await;
+
+        assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+        assert!(resp.data.into_json().unwrap()["currentUser"].is_null());
+    }
+
+    #[tokio::test]
+    async fn current_user_exposes_subject_when_authenticated() {
+        let schema = schema_with(Arc::new(MemoryStorage::default()));
+
+        let req = Request::new("{ currentUser { subject } }").data(Some(CurrentUser::new("42")));
+        let resp = schema.execute(req).await;
+
+        assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+        assert_eq!(
+            resp.data.into_json().unwrap()["currentUser"]["subject"],
+            "42"
+        );
+    }
+
+    #[tokio::test]
+    async fn upload_mutation_stores_regular_file() {
+        let storage = Arc::new(MemoryStorage::default());
+        let schema = schema_with(storage.clone());
+
+        let mut req = Request::new(
+            "mutation($file: Upload!) { upload(file: $file) { path originalFilename bytes contentType } }",
+        )
+        .variables(Variables::from_json(serde_json::json!({ "file": null })));
+        req.set_upload(
+            "variables.file",
+            upload_value("notes.txt", "text/plain", b"hello"),
+        );
+
+        let resp = schema.execute(req).await;
+
+        assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+        let json = resp.data.into_json().unwrap();
+        assert_eq!(json["upload"]["originalFilename"], "notes.txt");
+        assert_eq!(json["upload"]["bytes"], 5);
+        assert_eq!(json["upload"]["contentType"], "text/plain");
+        assert!(json["upload"]["path"]
+            .as_str()
+            .unwrap()
+            .ends_with("/notes.txt"));
+
+        let saved = storage.saved.lock().unwrap();
+        assert_eq!(saved.len(), 1);
+        assert!(saved[0].0.starts_with("files/"));
+        assert_eq!(saved[0].1, b"hello");
+    }
+
+    #[tokio::test]
+    async fn upload_mutation_hides_storage_errors() {
+        let storage = Arc::new(MemoryStorage {
+            fail: true,
+            ..Default::default()
+        });
+        let schema = schema_with(storage);
+
+        let mut req = Request::new("mutation($file: Upload!) { upload(file: $file) { path } }")
+            .variables(Variables::from_json(serde_json::json!({ "file": null })));
+        req.set_upload(
+            "variables.file",
+            upload_value("notes.txt", "text/plain", b"hello"),
+        );
+
+        let resp = schema.execute(req).await;
+
+        assert_eq!(resp.errors.len(), 1);
+        assert_eq!(resp.errors[0].message, "upload failed");
+    }
+
+    #[tokio::test]
+    async fn upload_mutation_rejects_invalid_image_params() {
+        let storage = Arc::new(MemoryStorage::default());
+        let schema = schema_with(storage.clone());
+
+        let mut req = Request::new(
+            r##"mutation($file: Upload!) {
+                upload(file: $file, image: {
+                    maxWidth: 10, maxHeight: 10, upscale: false,
+                    resizeMode: "stretch", background: "#ffffff"
+                }) { path }
+            }"##,
+        )
+        .variables(Variables::from_json(serde_json::json!({ "file": null })));
+        req.set_upload("variables.file", upload_value("a.png", "image/png", b"png"));
+
+        let resp = schema.execute(req).await;
+
+        assert_eq!(resp.errors.len(), 1);
+        assert!(resp.errors[0].message.contains("invalid image params"));
+        assert!(storage.saved.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn upload_mutation_saves_images_under_image_dir() {
+        let storage = Arc::new(MemoryStorage::default());
+        let schema = schema_with(storage.clone());
+
+        let mut req = Request::new(
+            r##"mutation($file: Upload!) {
+                upload(file: $file, image: {
+                    maxWidth: 10, maxHeight: 10, upscale: false,
+                    resizeMode: "cover", background: "#ffffff"
+                }) { path contentType }
+            }"##,
+        )
+        .variables(Variables::from_json(serde_json::json!({ "file": null })));
+        req.set_upload("variables.file", upload_value("a.png", "image/png", b"png"));
+
+        let resp = schema.execute(req).await;
+
+        assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+        let json = resp.data.into_json().unwrap();
+        assert_eq!(json["upload"]["contentType"], "image/png");
+        assert!(json["upload"]["path"]
+            .as_str()
+            .unwrap()
+            .starts_with("/images/"));
+    }
+}
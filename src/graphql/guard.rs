@@ -82,7 +82,7 @@ where
 mod tests {
     use super::*;
     use axum::http::HeaderMap;
-    use axum_extra::extract::cookie::{Cookie, CookieJar};
+    use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
 
     use crate::auth::jwt::create_jwt;
     use crate::config::csrf::CsrfConfig;
@@ -104,6 +104,11 @@ mod tests {
             secret: [0u8; 32],
             cookie_secure: false,
             cookie_http_only: true,
+            rotation: Default::default(),
+            token_ttl: None,
+            allowed_origins: None,
+            cookie_same_site: SameSite::Lax,
+            accept_legacy_tokens: false,
         }
     }
 
@@ -1,14 +1,22 @@
-use async_graphql::{ObjectType, Schema, SubscriptionType};
+use std::sync::Arc;
+
+use async_graphql::{ObjectType, Response, Schema, ServerError, SubscriptionType};
 use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
-use axum::http::HeaderMap;
+use axum::http::{Extensions, HeaderMap};
 use axum::Extension;
 use axum_extra::extract::cookie::CookieJar;
 
+use crate::audit::context::AuditContext;
+use crate::audit::event::AuditEvent;
+use crate::auth::revocation::{unless_revoked, TokenRevocationStore};
 use crate::auth::CurrentUser;
 use crate::config::csrf::CsrfConfig;
+use crate::experiments::Assignments;
 use crate::graphql::config::GraphqlAuthConfig;
-use crate::graphql::context::extract_current_user;
+use crate::graphql::context::{extract_bearer_user, extract_cookie_user, RequestContext};
 use crate::graphql::guard::validate_csrf_guard;
+use crate::graphql::loader::Loaders;
+use crate::graphql::operation_policy::OperationPolicy;
 
 /// GraphQL POST endpoint handler.
 ///
@@ -21,6 +29,7 @@ use crate::graphql::guard::validate_csrf_guard;
 /// that are common across applications:
 ///
 /// - CSRF validation
+/// - Operation allow/deny lists ([`OperationPolicy`], optional)
 /// - Authentication (JWT extraction)
 /// - Injecting authentication context
 ///
@@ -29,10 +38,20 @@ use crate::graphql::guard::validate_csrf_guard;
 ///
 /// # Responsibilities
 ///
-/// - Validate CSRF tokens when CSRF protection is enabled
-/// - Extract a JWT from cookies
-/// - Authenticate the request and build `CurrentUser`
-/// - Inject `Option<CurrentUser>` into the GraphQL context
+/// - Validate CSRF tokens when CSRF protection is enabled (skipped for
+///   bearer-authenticated requests when
+///   [`GraphqlAuthConfig::bearer_csrf_exempt`] is set), recording rejections
+///   through [`AuditContext`]
+/// - Reject operations blocked by an injected `OperationPolicy`
+/// - Extract a JWT from cookies (or an `Authorization: Bearer` header,
+///   when enabled)
+/// - Authenticate the request and build `CurrentUser`, ignoring tokens
+///   revoked in an injected `Arc<dyn TokenRevocationStore>` (optional)
+/// - Inject a [`RequestContext`] (user, request id, locale, tenant, client
+///   IP, deadline) into the GraphQL context; `Option<CurrentUser>` is
+///   injected as well for resolvers that read it directly
+/// - Build fresh [`RequestLoaders`](crate::graphql::loader::RequestLoaders) from a [`Loaders`] extension (optional),
+///   so data loader caches never outlive the request
 ///
 /// # Non-Responsibilities
 ///
@@ -62,8 +81,11 @@ pub async fn graphql_post_handler<Q, M, S>(
     Extension(csrf_cfg): Extension<CsrfConfig>,
     Extension(jwt_secret): Extension<Option<String>>,
     Extension(auth_cfg): Extension<GraphqlAuthConfig>,
-    jar: CookieJar,
+    policy: Option<Extension<OperationPolicy>>,
+    revocation: Option<Extension<Arc<dyn TokenRevocationStore>>>,
+    audit: AuditContext,
     headers: HeaderMap,
+    extensions: Extensions,
     req: GraphQLRequest,
 ) -> GraphQLResponse
 where
@@ -71,42 +93,92 @@ where
     M: ObjectType + Send + Sync + 'static,
     S: SubscriptionType + Send + Sync + 'static,
 {
+    let jar = CookieJar::from_headers(&headers);
+
+    // -----------------------------
+    // Bearer authentication (opt-in)
+    // -----------------------------
+    //
+    // With `accept_bearer`, a valid `Authorization: Bearer` token
+    // authenticates the request instead of the cookie (clients without
+    // cookies, e.g. mobile apps). Such a token cannot be attached by a
+    // cross-site form or script, so with `bearer_csrf_exempt` these
+    // requests also skip CSRF validation.
+    let bearer_user = auth_cfg
+        .bearer_enabled()
+        .then(|| extract_bearer_user(&headers, jwt_secret.as_deref()))
+        .flatten();
+
     // -----------------------------
     // CSRF validation
     // -----------------------------
     //
     // When CSRF protection is enabled, validate the request
-    // headers and cookies. On failure, return a GraphQL-
-    // compliant error response (HTTP 200 with `errors`).
-    if let Err(resp) = validate_csrf_guard(enable_csrf, &headers, &jar, &csrf_cfg) {
+    // headers and cookies. On failure, audit the rejection and return
+    // a GraphQL-compliant error response (HTTP 200 with `errors`).
+    let csrf_required = enable_csrf && !(auth_cfg.bearer_csrf_exempt && bearer_user.is_some());
+    if let Err(resp) = validate_csrf_guard(csrf_required, &headers, &jar, &csrf_cfg) {
+        audit.emit(AuditEvent::csrf_rejected(audit.path())).await;
         return resp.into();
     }
 
+    // -----------------------------
+    // Operation allow/deny lists
+    // -----------------------------
+    //
+    // Applied before authentication so blocked operations
+    // (e.g. introspection) never reach the schema.
+    let req = req.into_inner();
+    let rejected = policy
+        .as_ref()
+        .and_then(|Extension(p)| p.check(&req.query, req.operation_name.as_deref()).err());
+    if let Some(msg) = rejected {
+        return Response::from_errors(vec![ServerError::new(msg, None)]).into();
+    }
+
     // -----------------------------
     // Authentication (JWT → CurrentUser)
     // -----------------------------
     //
-    // Extract an authenticated principal from the JWT cookie.
+    // Without a bearer principal, extract one from the JWT cookie.
     // This step is intentionally application-agnostic: only the
-    // JWT subject is extracted and wrapped in `CurrentUser`.
-    let current_user: Option<CurrentUser> = extract_current_user(
-        &jar,
-        &headers,
-        jwt_secret.as_deref(),
-        &auth_cfg.jwt_cookie_name,
-    );
+    // JWT subject, roles and scopes are wrapped in `CurrentUser`.
+    let current_user: Option<CurrentUser> = bearer_user
+        .or_else(|| extract_cookie_user(&jar, jwt_secret.as_deref(), &auth_cfg.jwt_cookie_name));
+
+    // -----------------------------
+    // Revocation (opt-in)
+    // -----------------------------
+    //
+    // Tokens revoked on logout or forced invalidation are treated
+    // like invalid ones. The store is blocking (it may query the
+    // database), so it runs on the blocking thread pool.
+    let current_user = unless_revoked(current_user, revocation.map(|Extension(store)| store)).await;
 
     // -----------------------------
     // Execute GraphQL with injected context
     // -----------------------------
     //
-    // The authentication result is injected into the GraphQL
-    // execution context, allowing resolvers to decide how to
-    // handle authenticated vs unauthenticated requests.
-    schema
-        .execute(req.into_inner().data(current_user))
-        .await
-        .into()
+    // The authentication result and the other request-scoped values
+    // are injected as one `RequestContext`, allowing resolvers to
+    // decide how to handle authenticated vs unauthenticated requests.
+    // Experiment assignments (see `experiments_layer`) are passed along
+    // so resolvers can read them with `Assignments::of`, and data loaders
+    // are created per request so their caches start empty.
+    let request_ctx = RequestContext::from_request(
+        &headers,
+        &extensions,
+        current_user.clone(),
+        auth_cfg.request_timeout,
+    );
+    let mut req = req.data(request_ctx).data(current_user);
+    if let Some(assignments) = extensions.get::<Assignments>() {
+        req = req.data(assignments.clone());
+    }
+    if let Some(loaders) = extensions.get::<Loaders>() {
+        req = req.data(loaders.scope());
+    }
+    schema.execute(req).await.into()
 }
 
 #[tokio::test]
@@ -153,3 +225,353 @@ async fn graphql_handler_executes_query() {
 
     assert_eq!(response.status(), StatusCode::OK);
 }
+
+#[tokio::test]
+async fn graphql_handler_enforces_operation_policy() {
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::{routing::post, Extension, Router};
+    use tower::ServiceExt; // oneshot
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn dummy(&self) -> &str {
+            "ok"
+        }
+    }
+
+    let schema = Schema::build(Query, EmptyMutation, EmptySubscription).finish();
+
+    let app = Router::new()
+        .route(
+            "/graphql",
+            post(graphql_post_handler::<Query, EmptyMutation, EmptySubscription>),
+        )
+        .layer(Extension(schema))
+        .layer(Extension(false))
+        .layer(Extension(CsrfConfig::from_env_with(|_| None)))
+        .layer(Extension(None::<String>))
+        .layer(Extension(GraphqlAuthConfig::new("auth")))
+        .layer(Extension(
+            OperationPolicy::default().block_introspection(true),
+        ));
+
+    let run = |query: &'static str| {
+        let app = app.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/graphql")
+                        .header("content-type", "application/json")
+                        .body(Body::from(query))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            String::from_utf8(body.to_vec()).unwrap()
+        }
+    };
+
+    let blocked = run(r#"{"query":"{ __schema { queryType { name } } }"}"#).await;
+    assert!(blocked.contains("introspection is disabled"));
+
+    let allowed = run(r#"{"query":"{ dummy }"}"#).await;
+    assert!(allowed.contains(r#""dummy":"ok""#));
+}
+
+#[tokio::test]
+async fn graphql_handler_exempts_bearer_requests_from_csrf() {
+    use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema};
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::{routing::post, Extension, Router};
+    use axum_extra::extract::cookie::Cookie;
+    use tower::ServiceExt; // oneshot
+
+    use crate::auth::jwt::create_jwt;
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn me(&self, ctx: &Context<'_>) -> Option<String> {
+            ctx.data_unchecked::<Option<CurrentUser>>()
+                .as_ref()
+                .map(|u| u.subject.clone())
+        }
+
+        async fn ctx_user(&self, ctx: &Context<'_>) -> Option<String> {
+            RequestContext::of(ctx).user().map(|u| u.subject.clone())
+        }
+    }
+
+    let secret = "bearer-test-secret";
+    let schema = Schema::build(Query, EmptyMutation, EmptySubscription).finish();
+    let app = |exempt: bool| {
+        Router::new()
+            .route(
+                "/graphql",
+                post(graphql_post_handler::<Query, EmptyMutation, EmptySubscription>),
+            )
+            .layer(Extension(schema.clone()))
+            .layer(Extension(true)) // CSRF enabled
+            .layer(Extension(CsrfConfig::from_env_with(|_| None)))
+            .layer(Extension(Some(secret.to_string())))
+            .layer(Extension(
+                GraphqlAuthConfig::new("auth").bearer_csrf_exempt(exempt),
+            ))
+    };
+    let run = |app: Router, authorization: Option<String>, cookie: Option<String>| async move {
+        let mut req = Request::builder()
+            .method("POST")
+            .uri("/graphql")
+            .header("content-type", "application/json");
+        if let Some(value) = authorization {
+            req = req.header("authorization", value);
+        }
+        if let Some(value) = cookie {
+            req = req.header("cookie", value);
+        }
+        let response = app
+            .oneshot(
+                req.body(Body::from(r#"{"query":"{ me ctxUser }"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    };
+
+    let bearer = format!("Bearer {}", create_jwt(5, secret).unwrap());
+    let cookie = Cookie::new(
+        "auth",
+        format!(r#"{{"token":"{}"}}"#, create_jwt(9, secret).unwrap()),
+    )
+    .to_string();
+
+    let ok = run(app(true), Some(bearer.clone()), Some(cookie.clone())).await;
+    assert!(ok.contains(r#""me":"5""#), "{ok}");
+    assert!(ok.contains(r#""ctxUser":"5""#), "{ok}");
+
+    let denied = run(app(false), Some(bearer), None).await;
+    assert!(denied.to_lowercase().contains("csrf"), "{denied}");
+
+    let cookie_only = run(app(true), None, Some(cookie)).await;
+    assert!(cookie_only.to_lowercase().contains("csrf"), "{cookie_only}");
+
+    let forged = run(app(true), Some("Bearer not-a-jwt".into()), None).await;
+    assert!(forged.to_lowercase().contains("csrf"), "{forged}");
+}
+
+#[tokio::test]
+async fn graphql_handler_accepts_bearer_when_enabled() {
+    use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema};
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::{routing::post, Extension, Router};
+    use tower::ServiceExt; // oneshot
+
+    use crate::auth::jwt::create_jwt;
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn me(&self, ctx: &Context<'_>) -> Option<String> {
+            RequestContext::of(ctx).user().map(|u| u.subject.clone())
+        }
+    }
+
+    let secret = "bearer-test-secret";
+    let schema = Schema::build(Query, EmptyMutation, EmptySubscription).finish();
+    let run = |accept: bool| {
+        let app = Router::new()
+            .route(
+                "/graphql",
+                post(graphql_post_handler::<Query, EmptyMutation, EmptySubscription>),
+            )
+            .layer(Extension(schema.clone()))
+            .layer(Extension(false)) // CSRF disabled
+            .layer(Extension(CsrfConfig::from_env_with(|_| None)))
+            .layer(Extension(Some(secret.to_string())))
+            .layer(Extension(
+                GraphqlAuthConfig::new("auth").accept_bearer(accept),
+            ));
+        async move {
+            let req = Request::builder()
+                .method("POST")
+                .uri("/graphql")
+                .header("content-type", "application/json")
+                .header(
+                    "authorization",
+                    format!("Bearer {}", create_jwt(3, secret).unwrap()),
+                )
+                .body(Body::from(r#"{"query":"{ me }"}"#))
+                .unwrap();
+            let response = app.oneshot(req).await.unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            String::from_utf8(body.to_vec()).unwrap()
+        }
+    };
+
+    let accepted = run(true).await;
+    assert!(accepted.contains(r#""me":"3""#), "{accepted}");
+
+    let ignored = run(false).await;
+    assert!(ignored.contains(r#""me":null"#), "{ignored}");
+}
+
+#[tokio::test]
+async fn graphql_handler_ignores_revoked_tokens() {
+    use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema};
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::{routing::post, Extension, Router};
+    use axum_extra::extract::cookie::Cookie;
+    use tower::ServiceExt; // oneshot
+
+    use crate::auth::jwt::{create_jwt, decode_jwt};
+    use crate::auth::revocation::{revoke_claims, MemoryTokenRevocationStore};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn me(&self, ctx: &Context<'_>) -> Option<String> {
+            RequestContext::of(ctx).user().map(|u| u.subject.clone())
+        }
+    }
+
+    let secret = "revocation-test-secret";
+    let schema = Schema::build(Query, EmptyMutation, EmptySubscription).finish();
+    let store = Arc::new(MemoryTokenRevocationStore::default());
+    let app = Router::new()
+        .route(
+            "/graphql",
+            post(graphql_post_handler::<Query, EmptyMutation, EmptySubscription>),
+        )
+        .layer(Extension(schema))
+        .layer(Extension(false)) // CSRF disabled
+        .layer(Extension(CsrfConfig::from_env_with(|_| None)))
+        .layer(Extension(Some(secret.to_string())))
+        .layer(Extension(GraphqlAuthConfig::new("auth")))
+        .layer(Extension(store.clone() as Arc<dyn TokenRevocationStore>));
+
+    let token = create_jwt(4, secret).unwrap();
+    let run = || {
+        let req = Request::builder()
+            .method("POST")
+            .uri("/graphql")
+            .header("content-type", "application/json")
+            .header(
+                "cookie",
+                Cookie::new("auth", format!(r#"{{"token":"{token}"}}"#)).to_string(),
+            )
+            .body(Body::from(r#"{"query":"{ me }"}"#))
+            .unwrap();
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(req).await.unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            String::from_utf8(body.to_vec()).unwrap()
+        }
+    };
+
+    let before = run().await;
+    assert!(before.contains(r#""me":"4""#), "{before}");
+
+    revoke_claims(&decode_jwt(&token, secret).unwrap(), store.as_ref()).unwrap();
+    let after = run().await;
+    assert!(after.contains(r#""me":null"#), "{after}");
+}
+
+#[tokio::test]
+async fn graphql_handler_audits_csrf_rejections() {
+    use std::sync::Mutex;
+
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::{routing::post, Extension, Router};
+    use tower::ServiceExt; // oneshot
+
+    use crate::audit::store::{AuditQuery, AuditStore};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn dummy(&self) -> &str {
+            "ok"
+        }
+    }
+
+    #[derive(Default)]
+    struct Events(Mutex<Vec<AuditEvent>>);
+
+    impl AuditStore for Events {
+        fn record(&self, event: &AuditEvent) -> anyhow::Result<()> {
+            self.0.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+
+        fn query(&self, _query: &AuditQuery) -> anyhow::Result<Vec<AuditEvent>> {
+            Ok(vec![])
+        }
+    }
+
+    let events = Arc::new(Events::default());
+    let schema = Schema::build(Query, EmptyMutation, EmptySubscription).finish();
+    let app = Router::new()
+        .route(
+            "/graphql",
+            post(graphql_post_handler::<Query, EmptyMutation, EmptySubscription>),
+        )
+        .layer(Extension(schema))
+        .layer(Extension(true)) // CSRF enabled
+        .layer(Extension(CsrfConfig::from_env_with(|_| None)))
+        .layer(Extension(None::<String>))
+        .layer(Extension(GraphqlAuthConfig::new("auth")))
+        .layer(Extension(events.clone() as Arc<dyn AuditStore>));
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/graphql")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"query":"{ dummy }"}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert!(String::from_utf8_lossy(&body)
+        .to_lowercase()
+        .contains("csrf"));
+
+    let recorded = events.0.lock().unwrap();
+    assert_eq!(recorded.len(), 1);
+    assert_eq!(
+        recorded[0].action,
+        crate::audit::event::actions::CSRF_REJECTED
+    );
+    assert_eq!(recorded[0].detail.as_deref(), Some("/graphql"));
+}
@@ -0,0 +1,271 @@
+//! Request-scoped [`DataLoader`]s.
+//!
+//! A `DataLoader` shared by the whole schema (`Schema::build(..).data(loader)`)
+//! keeps its cache across requests and users: values loaded for one request
+//! are served to the next, and a mutation never sees its own writes. Instead,
+//! register loader *factories* once in [`Loaders`] and add it as a router
+//! extension; [`graphql_post_handler`](crate::graphql::handler::graphql_post_handler)
+//! builds a fresh set of loaders for every request and injects it as
+//! [`RequestLoaders`].
+//!
+//! Within a request, mutations keep the cache honest with
+//! [`RequestLoaders::prime`] (store the value just written) and
+//! [`RequestLoaders::clear`] (drop a value so it is reloaded).
+//!
+//! # Example
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use async_graphql::dataloader::Loader;
+//! use async_graphql::{Context, Object, Result};
+//! use wzs_web::graphql::loader::{Loaders, RequestLoaders};
+//!
+//! struct NameLoader;
+//!
+//! impl Loader<u64> for NameLoader {
+//!     type Value = String;
+//!     type Error = String;
+//!
+//!     async fn load(&self, keys: &[u64]) -> Result<HashMap<u64, String>, String> {
+//!         Ok(keys.iter().map(|k| (*k, format!("user-{k}"))).collect())
+//!     }
+//! }
+//!
+//! struct Mutation;
+//!
+//! #[Object]
+//! impl Mutation {
+//!     async fn rename(&self, ctx: &Context<'_>, id: u64, name: String) -> Result<String> {
+//!         // … persist the new name …
+//!         let loaders = RequestLoaders::of(ctx)?;
+//!         loaders.prime::<NameLoader, _>(id, name).await;
+//!         let name = loaders.loader::<NameLoader>()?.load_one(id).await?;
+//!         Ok(name.unwrap_or_default())
+//!     }
+//! }
+//!
+//! // Router: `.layer(Extension(loaders))`
+//! let loaders = Loaders::new().register(|| NameLoader);
+//! # let _ = loaders;
+//! ```
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use async_graphql::dataloader::{DataLoader, HashMapCache, Loader};
+use async_graphql::{Context, Error, Result};
+
+/// Caching `DataLoader` as created for each request.
+pub type RequestLoader<T> = DataLoader<T, HashMapCache>;
+
+type Factory = Arc<dyn Fn() -> Box<dyn Any + Send + Sync> + Send + Sync>;
+
+/// Loader factories, shared by all requests.
+#[derive(Clone, Default)]
+pub struct Loaders {
+    factories: HashMap<TypeId, Factory>,
+}
+
+impl Loaders {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a factory for loader `T`, replacing an earlier one.
+    ///
+    /// The factory runs once per request; it should be cheap (clone an
+    /// `Arc` to a repository, not open connections).
+    pub fn register<T, F>(mut self, factory: F) -> Self
+    where
+        T: Send + Sync + 'static,
+        F: Fn() -> T + Send + Sync + 'static,
+    {
+        let factory: Factory = Arc::new(move || {
+            let loader: RequestLoader<T> =
+                DataLoader::with_cache(factory(), tokio::spawn, HashMapCache::default());
+            Box::new(loader)
+        });
+        self.factories.insert(TypeId::of::<T>(), factory);
+        self
+    }
+
+    /// Builds the loaders of one request, each with an empty cache.
+    pub fn scope(&self) -> RequestLoaders {
+        RequestLoaders {
+            loaders: self
+                .factories
+                .iter()
+                .map(|(id, factory)| (*id, factory()))
+                .collect(),
+        }
+    }
+}
+
+/// The loaders of one request.
+pub struct RequestLoaders {
+    loaders: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl RequestLoaders {
+    /// Returns the loaders injected into the GraphQL context.
+    ///
+    /// # Errors
+    /// Fails when no [`Loaders`] extension was installed.
+    pub fn of<'a>(ctx: &'a Context<'_>) -> Result<&'a Self> {
+        ctx.data::<Self>()
+            .map_err(|_| Error::new("data loaders are not configured"))
+    }
+
+    /// Returns loader `T`.
+    ///
+    /// # Errors
+    /// Fails when `T` was not registered.
+    pub fn loader<T>(&self) -> Result<&RequestLoader<T>>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.loaders
+            .get(&TypeId::of::<T>())
+            .and_then(|l| l.downcast_ref())
+            .ok_or_else(|| {
+                Error::new(format!(
+                    "data loader {} is not registered",
+                    std::any::type_name::<T>()
+                ))
+            })
+    }
+
+    /// Stores `value` under `key`, so later loads in this request return it
+    /// without hitting the loader. No-op when `T` is not registered.
+    pub async fn prime<T, K>(&self, key: K, value: T::Value)
+    where
+        T: Loader<K>,
+        K: Send + Sync + Hash + Eq + Clone + 'static,
+    {
+        if let Ok(loader) = self.loader::<T>() {
+            loader.feed_one(key, value).await;
+        }
+    }
+
+    /// [`prime`](Self::prime) for several values.
+    pub async fn prime_many<T, K, I>(&self, values: I)
+    where
+        T: Loader<K>,
+        K: Send + Sync + Hash + Eq + Clone + 'static,
+        I: IntoIterator<Item = (K, T::Value)>,
+    {
+        if let Ok(loader) = self.loader::<T>() {
+            loader.feed_many(values).await;
+        }
+    }
+
+    /// Drops the cached value of `key`, so the next load fetches it again.
+    pub async fn clear<T, K>(&self, key: &K)
+    where
+        T: Loader<K>,
+        K: Send + Sync + Hash + Eq + Clone + 'static,
+    {
+        let Ok(loader) = self.loader::<T>() else {
+            return;
+        };
+        let mut cached = loader.get_cached_values::<K>();
+        if cached.remove(key).is_some() {
+            loader.clear::<K>();
+            loader.feed_many(cached).await;
+        }
+    }
+
+    /// Drops every cached `K` value of loader `T`.
+    pub fn clear_all<T, K>(&self)
+    where
+        T: Loader<K>,
+        K: Send + Sync + Hash + Eq + Clone + 'static,
+    {
+        if let Ok(loader) = self.loader::<T>() {
+            loader.clear::<K>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Counting {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Loader<u32> for Counting {
+        type Value = String;
+        type Error = String;
+
+        async fn load(&self, keys: &[u32]) -> Result<HashMap<u32, String>, String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(keys.iter().map(|k| (*k, format!("v{k}"))).collect())
+        }
+    }
+
+    fn loaders(counting: &Counting) -> Loaders {
+        let counting = counting.clone();
+        Loaders::new().register(move || counting.clone())
+    }
+
+    #[tokio::test]
+    async fn each_scope_has_its_own_cache() {
+        let counting = Counting::default();
+        let loaders = loaders(&counting);
+
+        let first = loaders.scope();
+        let loader = first.loader::<Counting>().unwrap();
+        assert_eq!(loader.load_one(1).await.unwrap().as_deref(), Some("v1"));
+        assert_eq!(loader.load_one(1).await.unwrap().as_deref(), Some("v1"));
+        assert_eq!(counting.calls.load(Ordering::SeqCst), 1);
+
+        let second = loaders.scope();
+        second
+            .loader::<Counting>()
+            .unwrap()
+            .load_one(1)
+            .await
+            .unwrap();
+        assert_eq!(counting.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn prime_and_clear_update_the_cache() {
+        let counting = Counting::default();
+        let scope = loaders(&counting).scope();
+        let loader = scope.loader::<Counting>().unwrap();
+
+        scope.prime::<Counting, _>(1, "renamed".into()).await;
+        scope
+            .prime_many::<Counting, _, _>([(2, "two".into())])
+            .await;
+        assert_eq!(
+            loader.load_one(1).await.unwrap().as_deref(),
+            Some("renamed")
+        );
+        assert_eq!(counting.calls.load(Ordering::SeqCst), 0);
+
+        scope.clear::<Counting, _>(&1).await;
+        assert_eq!(loader.load_one(1).await.unwrap().as_deref(), Some("v1"));
+        assert_eq!(loader.load_one(2).await.unwrap().as_deref(), Some("two"));
+        assert_eq!(counting.calls.load(Ordering::SeqCst), 1);
+
+        scope.clear_all::<Counting, u32>();
+        loader.load_one(2).await.unwrap();
+        assert_eq!(counting.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn unregistered_loader_is_an_error() {
+        let scope = Loaders::new().scope();
+        assert!(scope.loader::<Counting>().is_err());
+    }
+}
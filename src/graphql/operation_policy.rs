@@ -0,0 +1,294 @@
+//! # GraphQL Operation Allow / Deny Lists
+//!
+//! [`OperationPolicy`] restricts which operations the public GraphQL endpoint
+//! accepts. It is enforced by
+//! [`graphql_post_handler`](crate::graphql::handler::graphql_post_handler)
+//! when injected as an `Extension`.
+//!
+//! Entries are matched against both the **operation name** and the
+//! **root field names** of the executed operation (fragments at the root are
+//! followed), so renaming an operation does not bypass a denylist entry.
+//!
+//! - **Allowlist**: when non-empty, only named operations listed here run.
+//! - **Denylist**: operations whose name or any root field is listed are rejected.
+//! - **Introspection**: `__schema` / `__type` root fields can be blocked.
+//!
+//! # Environment Variables
+//! | Key | Description |
+//! |-----|-------------|
+//! | `GRAPHQL_ALLOWED_OPERATIONS` | Comma-separated allowlist |
+//! | `GRAPHQL_DENIED_OPERATIONS` | Comma-separated denylist |
+//! | `GRAPHQL_BLOCK_INTROSPECTION` | Block `__schema` / `__type` (flag) |
+//!
+//! # Example
+//! ```rust
+//! use wzs_web::graphql::operation_policy::OperationPolicy;
+//!
+//! let policy = OperationPolicy::default()
+//!     .deny(["adminStats", "purgeCache"])
+//!     .block_introspection(true);
+//!
+//! assert!(policy.check("{ __schema { types { name } } }", None).is_err());
+//! assert!(policy.check("query Dashboard { adminStats }", None).is_err());
+//! assert!(policy.check("query Me { me { id } }", None).is_ok());
+//! ```
+
+use std::collections::{HashSet, VecDeque};
+use std::env as std_env;
+
+use async_graphql::parser::{
+    parse_query,
+    types::{ExecutableDocument, Selection, SelectionSet},
+};
+
+use crate::config::env::read_flag_from;
+
+/// Root fields used for schema introspection.
+const INTROSPECTION_FIELDS: [&str; 2] = ["__schema", "__type"];
+
+/// Config-driven operation filter.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OperationPolicy {
+    allowed: HashSet<String>,
+    denied: HashSet<String>,
+    block_introspection: bool,
+}
+
+impl OperationPolicy {
+    /// Loads the policy from environment variables.
+    pub fn from_env() -> Self {
+        Self::from_env_with(|k| std_env::var(k).ok())
+    }
+
+    /// Same as [`from_env`](Self::from_env) with a custom provider.
+    pub fn from_env_with<F>(get: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let list = |key: &str| -> Vec<String> {
+            get(key)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect()
+        };
+
+        Self::default()
+            .allow(list("GRAPHQL_ALLOWED_OPERATIONS"))
+            .deny(list("GRAPHQL_DENIED_OPERATIONS"))
+            .block_introspection(read_flag_from(&get, "GRAPHQL_BLOCK_INTROSPECTION", false))
+    }
+
+    /// Adds operation names to the allowlist.
+    pub fn allow<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Adds operation or root field names to the denylist.
+    pub fn deny<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.denied.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Enables or disables blocking of introspection root fields.
+    pub fn block_introspection(mut self, on: bool) -> Self {
+        self.block_introspection = on;
+        self
+    }
+
+    /// Returns `true` if the policy never rejects anything.
+    pub fn is_open(&self) -> bool {
+        self.allowed.is_empty() && self.denied.is_empty() && !self.block_introspection
+    }
+
+    /// Checks a request against the policy.
+    ///
+    /// Documents that fail to parse, or whose operation cannot be selected,
+    /// pass through so the schema reports the usual GraphQL error.
+    ///
+    /// # Errors
+    /// Returns a human-readable reason when the operation is rejected.
+    pub fn check(&self, query: &str, operation_name: Option<&str>) -> Result<(), String> {
+        if self.is_open() {
+            return Ok(());
+        }
+        let Ok(doc) = parse_query(query) else {
+            return Ok(());
+        };
+        let Some((name, selection_set)) = select_operation(&doc, operation_name) else {
+            return Ok(());
+        };
+
+        if !self.allowed.is_empty() && !name.is_some_and(|n| self.allowed.contains(n)) {
+            return Err(match name {
+                Some(n) => format!("operation `{n}` is not allowed"),
+                None => "anonymous operations are not allowed".into(),
+            });
+        }
+
+        if let Some(n) = name.filter(|n| self.denied.contains(*n)) {
+            return Err(format!("operation `{n}` is not allowed"));
+        }
+
+        for field in root_fields(&doc, selection_set) {
+            if self.block_introspection && INTROSPECTION_FIELDS.contains(&field) {
+                return Err("introspection is disabled".into());
+            }
+            if self.denied.contains(field) {
+                return Err(format!("field `{field}` is not allowed"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Picks the operation that would execute, mirroring GraphQL's rules.
+fn select_operation<'a>(
+    doc: &'a ExecutableDocument,
+    operation_name: Option<&str>,
+) -> Option<(Option<&'a str>, &'a SelectionSet)> {
+    let mut ops = doc.operations.iter();
+    match operation_name {
+        Some(wanted) => ops
+            .find(|(name, _)| name.is_some_and(|n| n.as_str() == wanted))
+            .map(|(name, op)| (name.map(|n| n.as_str()), &op.node.selection_set.node)),
+        None => {
+            let (name, op) = ops.next()?;
+            if ops.next().is_some() {
+                return None;
+            }
+            Some((name.map(|n| n.as_str()), &op.node.selection_set.node))
+        }
+    }
+}
+
+/// Collects root field names, following fragments (each fragment once).
+fn root_fields<'a>(doc: &'a ExecutableDocument, root: &'a SelectionSet) -> Vec<&'a str> {
+    let mut fields = Vec::new();
+    let mut seen_fragments = HashSet::new();
+    let mut queue = VecDeque::from([root]);
+
+    while let Some(set) = queue.pop_front() {
+        for item in &set.items {
+            match &item.node {
+                Selection::Field(f) => fields.push(f.node.name.node.as_str()),
+                Selection::InlineFragment(f) => queue.push_back(&f.node.selection_set.node),
+                Selection::FragmentSpread(s) => {
+                    let name = &s.node.fragment_name.node;
+                    if !seen_fragments.insert(name.as_str()) {
+                        continue;
+                    }
+                    if let Some(def) = doc.fragments.get(name) {
+                        queue.push_back(&def.node.selection_set.node);
+                    }
+                }
+            }
+        }
+    }
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_policy_accepts_everything() {
+        let p = OperationPolicy::default();
+        assert!(p.is_open());
+        assert!(p.check("{ __schema { types { name } } }", None).is_ok());
+    }
+
+    #[test]
+    fn introspection_is_blocked_including_through_fragments() {
+        let p = OperationPolicy::default().block_introspection(true);
+
+        assert!(p
+            .check("{ __schema { queryType { name } } }", None)
+            .is_err());
+        assert!(p
+            .check(r#"{ __type(name: "User") { name } }"#, None)
+            .is_err());
+        assert!(p
+            .check(
+                "query Q { ...F } fragment F on Query { __schema { types { name } } }",
+                None
+            )
+            .is_err());
+        assert!(p
+            .check("{ ... on Query { __schema { types { name } } } }", None)
+            .is_err());
+
+        assert!(p.check("{ __typename me { id } }", None).is_ok());
+    }
+
+    #[test]
+    fn denylist_matches_operation_names_and_root_fields() {
+        let p = OperationPolicy::default().deny(["adminStats", "Purge"]);
+
+        assert_eq!(
+            p.check("query Renamed { stats: adminStats }", None),
+            Err("field `adminStats` is not allowed".into())
+        );
+        assert_eq!(
+            p.check("mutation Purge { ok }", None),
+            Err("operation `Purge` is not allowed".into())
+        );
+        assert!(p.check("query Me { me { adminStats } }", None).is_ok());
+    }
+
+    #[test]
+    fn allowlist_requires_listed_named_operation() {
+        let p = OperationPolicy::default().allow(["Me", "Dashboard"]);
+
+        assert!(p.check("query Me { me { id } }", None).is_ok());
+        assert_eq!(
+            p.check("{ me { id } }", None),
+            Err("anonymous operations are not allowed".into())
+        );
+        assert!(p.check("query Other { me { id } }", None).is_err());
+
+        let doc = "query Me { me { id } } query Other { secret }";
+        assert!(p.check(doc, Some("Me")).is_ok());
+        assert!(p.check(doc, Some("Other")).is_err());
+    }
+
+    #[test]
+    fn unparsable_documents_pass_through_to_the_schema() {
+        let p = OperationPolicy::default().deny(["x"]);
+        assert!(p.check("{ unclosed", None).is_ok());
+        assert!(p.check("query A { x } query B { y }", None).is_ok());
+    }
+
+    #[test]
+    fn from_env_with_reads_lists_and_flag() {
+        let p = OperationPolicy::from_env_with(|k| match k {
+            "GRAPHQL_ALLOWED_OPERATIONS" => Some("Me, Dashboard ,".into()),
+            "GRAPHQL_DENIED_OPERATIONS" => Some("adminStats".into()),
+            "GRAPHQL_BLOCK_INTROSPECTION" => Some("true".into()),
+            _ => None,
+        });
+
+        assert_eq!(
+            p,
+            OperationPolicy::default()
+                .allow(["Me", "Dashboard"])
+                .deny(["adminStats"])
+                .block_introspection(true)
+        );
+        assert!(OperationPolicy::from_env_with(|_| None).is_open());
+    }
+}
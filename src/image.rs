@@ -1,2 +1,3 @@
+pub mod cache;
 pub mod image_rs_processor;
 pub mod processor;
@@ -0,0 +1,334 @@
+//! # Processed Image Cache
+//!
+//! [`CachedImageProcessor`] wraps any [`ImageProcessor`] and stores its
+//! output in a [`FileStorage`], keyed by the SHA-256 of the input bytes and
+//! the operation with all its options. Repeating a resize of the same
+//! content — from the on-the-fly [`media`](crate::web::upload::media)
+//! endpoint, re-uploads of the same file, or several keys holding identical
+//! images — returns the stored result instead of decoding and re-encoding.
+//!
+//! Cache entries live under `cache_prefix` (default `"cache/images"`) as
+//! `<prefix>/<aa>/<input sha256>/<operation hash>`. Storage errors are
+//! logged and the image is processed normally, so the cache can never make
+//! a request fail.
+//!
+//! Hits, misses and storage errors are counted; [`ImageCacheStats`] gives a
+//! snapshot with the hit rate and [`CachedImageProcessor::render_metrics`]
+//! renders them in the Prometheus text format.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use wzs_web::config::csrf::derive_secret_from_string;
+//! use wzs_web::image::cache::CachedImageProcessor;
+//! use wzs_web::image::image_rs_processor::ImageRsProcessor;
+//! use wzs_web::web::upload::local_storage::LocalFileStorage;
+//! use wzs_web::web::upload::media::{MediaConfig, MediaService};
+//!
+//! let storage = Arc::new(LocalFileStorage::new("./uploads"));
+//! let processor = Arc::new(CachedImageProcessor::new(
+//!     Arc::new(ImageRsProcessor::default()),
+//!     storage.clone(),
+//! ));
+//!
+//! let cfg = MediaConfig::new(derive_secret_from_string("media-secret"), vec![(400, 300)]);
+//! let media = MediaService::new(storage, processor.clone(), cfg);
+//!
+//! let stats = processor.stats();
+//! println!("hit rate {:.1}%", stats.hit_rate() * 100.0);
+//! ```
+
+use std::fmt::Write;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::image::processor::{ImageProcessor, ResizeOpts};
+use crate::web::upload::storage::FileStorage;
+
+#[derive(Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    errors: AtomicU64,
+}
+
+/// Point-in-time cache counters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ImageCacheStats {
+    /// Results served from the cache.
+    pub hits: u64,
+    /// Results computed by the wrapped processor.
+    pub misses: u64,
+    /// Cache reads or writes that failed.
+    pub errors: u64,
+}
+
+impl ImageCacheStats {
+    /// `hits / (hits + misses)`, or `0.0` before the first request.
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            total => self.hits as f64 / total as f64,
+        }
+    }
+}
+
+/// [`ImageProcessor`] decorator caching results by content hash.
+pub struct CachedImageProcessor {
+    inner: Arc<dyn ImageProcessor>,
+    storage: Arc<dyn FileStorage>,
+    cache_prefix: String,
+    counters: Arc<Counters>,
+}
+
+impl CachedImageProcessor {
+    /// Caches results of `inner` in `storage` under `"cache/images"`.
+    pub fn new(inner: Arc<dyn ImageProcessor>, storage: Arc<dyn FileStorage>) -> Self {
+        Self {
+            inner,
+            storage,
+            cache_prefix: "cache/images".into(),
+            counters: Arc::default(),
+        }
+    }
+
+    /// Sets the storage prefix for cache entries.
+    pub fn cache_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.cache_prefix = prefix.into();
+        self
+    }
+
+    /// Returns the current counters.
+    pub fn stats(&self) -> ImageCacheStats {
+        ImageCacheStats {
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            errors: self.counters.errors.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Renders the counters in the Prometheus text exposition format.
+    pub fn render_metrics(&self) -> String {
+        let stats = self.stats();
+        let mut out = String::new();
+        out.push_str("# TYPE image_cache_requests_total counter\n");
+        let _ = writeln!(
+            out,
+            "image_cache_requests_total{{result=\"hit\"}} {}",
+            stats.hits
+        );
+        let _ = writeln!(
+            out,
+            "image_cache_requests_total{{result=\"miss\"}} {}",
+            stats.misses
+        );
+        out.push_str("# TYPE image_cache_errors_total counter\n");
+        let _ = writeln!(out, "image_cache_errors_total {}", stats.errors);
+        out
+    }
+
+    /// Storage key of the result of `operation` on `input`.
+    pub fn cache_key(&self, input: &[u8], operation: &str) -> String {
+        let input = hex(&Sha256::digest(input));
+        let op = hex(&Sha256::digest(operation.as_bytes()));
+        format!(
+            "{}/{}/{input}/{}",
+            self.cache_prefix.trim_end_matches('/'),
+            &input[..2],
+            &op[..32]
+        )
+    }
+
+    fn load(&self, key: &str) -> Option<Vec<u8>> {
+        match self.storage.load(key) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.counters.errors.fetch_add(1, Ordering::Relaxed);
+                warn!("image cache read failed for {key}: {e:#}");
+                None
+            }
+        }
+    }
+}
+
+impl ImageProcessor for CachedImageProcessor {
+    fn is_supported(&self, content_type: &str) -> bool {
+        self.inner.is_supported(content_type)
+    }
+
+    fn resize_same_format(
+        &self,
+        img_bytes: &[u8],
+        content_type: &str,
+        opts: ResizeOpts,
+    ) -> Result<Vec<u8>> {
+        let key = self.cache_key(img_bytes, &resize_operation(content_type, &opts));
+        if let Some(bytes) = self.load(&key) {
+            self.counters.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(bytes);
+        }
+        self.counters.misses.fetch_add(1, Ordering::Relaxed);
+
+        let bytes = self
+            .inner
+            .resize_same_format(img_bytes, content_type, opts)?;
+        if let Err(e) = self.storage.save(&key, &bytes) {
+            self.counters.errors.fetch_add(1, Ordering::Relaxed);
+            warn!("image cache write failed for {key}: {e:#}");
+        }
+        Ok(bytes)
+    }
+}
+
+/// Canonical description of a resize, covering every option that changes
+/// the output.
+fn resize_operation(content_type: &str, opts: &ResizeOpts) -> String {
+    format!(
+        "resize_same_format\n{content_type}\n{}x{}\n{}\n{}\n{}\n{}",
+        opts.max_w,
+        opts.max_h,
+        opts.upscale,
+        opts.resize_mode,
+        opts.bg_color.to_hex_rgba(),
+        opts.focal
+    )
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut s, b| {
+        let _ = write!(s, "{b:02x}");
+        s
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use anyhow::bail;
+
+    use crate::image::processor::{BgColor, FocalPoint, ResizeMode};
+
+    #[derive(Default)]
+    struct CountingProcessor {
+        calls: Mutex<u32>,
+    }
+
+    impl ImageProcessor for CountingProcessor {
+        fn is_supported(&self, content_type: &str) -> bool {
+            content_type == "image/png"
+        }
+
+        fn resize_same_format(&self, img: &[u8], _ct: &str, opts: ResizeOpts) -> Result<Vec<u8>> {
+            *self.calls.lock().unwrap() += 1;
+            Ok(format!("{}:{}", img.len(), opts.max_w).into_bytes())
+        }
+    }
+
+    #[derive(Default)]
+    struct MemoryStorage {
+        files: Mutex<HashMap<String, Vec<u8>>>,
+        broken: bool,
+    }
+
+    impl FileStorage for MemoryStorage {
+        fn save(&self, rel_path: &str, bytes: &[u8]) -> Result<String> {
+            if self.broken {
+                bail!("disk full");
+            }
+            self.files
+                .lock()
+                .unwrap()
+                .insert(rel_path.to_string(), bytes.to_vec());
+            Ok(rel_path.to_string())
+        }
+
+        fn load(&self, rel_path: &str) -> Result<Option<Vec<u8>>> {
+            if self.broken {
+                bail!("io error");
+            }
+            Ok(self.files.lock().unwrap().get(rel_path).cloned())
+        }
+    }
+
+    fn opts(w: u32) -> ResizeOpts {
+        ResizeOpts::new(w, 100, false, ResizeMode::Cover, BgColor::white())
+    }
+
+    #[test]
+    fn repeated_resizes_are_served_from_the_cache() {
+        let inner = Arc::new(CountingProcessor::default());
+        let storage = Arc::new(MemoryStorage::default());
+        let cached = CachedImageProcessor::new(inner.clone(), storage.clone());
+
+        let first = cached
+            .resize_same_format(b"png", "image/png", opts(100))
+            .unwrap();
+        let second = cached
+            .resize_same_format(b"png", "image/png", opts(100))
+            .unwrap();
+        assert_eq!(first, second);
+        assert_eq!(*inner.calls.lock().unwrap(), 1);
+
+        // Other options or other content are new entries.
+        cached
+            .resize_same_format(b"png", "image/png", opts(200))
+            .unwrap();
+        let focal = opts(100).with_focal(FocalPoint::new(0.1, 0.1).unwrap());
+        cached
+            .resize_same_format(b"png", "image/png", focal)
+            .unwrap();
+        cached
+            .resize_same_format(b"other", "image/png", opts(100))
+            .unwrap();
+        assert_eq!(*inner.calls.lock().unwrap(), 4);
+        assert_eq!(storage.files.lock().unwrap().len(), 4);
+
+        let stats = cached.stats();
+        assert_eq!((stats.hits, stats.misses, stats.errors), (1, 4, 0));
+        assert!((stats.hit_rate() - 0.2).abs() < 1e-9);
+        assert!(cached.is_supported("image/png"));
+    }
+
+    #[test]
+    fn keys_are_sharded_by_content_hash() {
+        let cached = CachedImageProcessor::new(
+            Arc::new(CountingProcessor::default()),
+            Arc::new(MemoryStorage::default()),
+        )
+        .cache_prefix("variants/");
+        let key = cached.cache_key(b"abc", "op");
+        // sha256("abc")
+        assert!(key.starts_with(
+            "variants/ba/ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad/"
+        ));
+        assert_eq!(key.rsplit('/').next().unwrap().len(), 32);
+    }
+
+    #[test]
+    fn storage_failures_fall_back_to_processing() {
+        let inner = Arc::new(CountingProcessor::default());
+        let storage = Arc::new(MemoryStorage {
+            broken: true,
+            ..Default::default()
+        });
+        let cached = CachedImageProcessor::new(inner.clone(), storage);
+
+        assert!(cached
+            .resize_same_format(b"png", "image/png", opts(1))
+            .is_ok());
+        assert_eq!(cached.stats().errors, 2);
+        assert_eq!(cached.stats().hit_rate(), 0.0);
+        let text = cached.render_metrics();
+        assert!(text.contains("image_cache_requests_total{result=\"miss\"} 1"));
+        assert!(text.contains("image_cache_errors_total 2"));
+    }
+}
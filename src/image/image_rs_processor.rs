@@ -65,7 +65,7 @@ use image::{
     ColorType, DynamicImage, GenericImageView, ImageFormat, ImageReader, Rgba,
 };
 
-use super::processor::{BgColor, ImageProcessor, ResizeMode, ResizeOpts};
+use super::processor::{BgColor, FocalPoint, ImageProcessor, ResizeMode, ResizeOpts};
 
 /// Decode/input safety limits used to mitigate oversized images and
 /// decompression-bomb-style attacks.
@@ -298,7 +298,7 @@ fn process_image(img: DynamicImage, opts: ResizeOpts) -> DynamicImage {
             opts.upscale,
             bg_color_to_rgba(opts.bg_color),
         ),
-        ResizeMode::Cover => resize_cover(img, opts.max_w, opts.max_h, opts.upscale),
+        ResizeMode::Cover => resize_cover(img, opts.max_w, opts.max_h, opts.upscale, opts.focal),
     }
 }
 
@@ -340,8 +340,15 @@ fn resize_contain(
     canvas
 }
 
-/// Keeps aspect ratio, fills the full target box, and crops overflow from the center.
-fn resize_cover(img: DynamicImage, max_w: u32, max_h: u32, upscale: bool) -> DynamicImage {
+/// Keeps aspect ratio, fills the full target box, and crops overflow around
+/// `focal` (clamped so the crop stays inside the image).
+fn resize_cover(
+    img: DynamicImage,
+    max_w: u32,
+    max_h: u32,
+    upscale: bool,
+    focal: FocalPoint,
+) -> DynamicImage {
     let (w, h) = img.dimensions();
 
     if !upscale && w <= max_w && h <= max_h {
@@ -357,12 +364,19 @@ fn resize_cover(img: DynamicImage, max_w: u32, max_h: u32, upscale: bool) -> Dyn
 
     let resized = img.resize_exact(new_w, new_h, FilterType::Triangle);
 
-    let crop_x = (new_w.saturating_sub(max_w)) / 2;
-    let crop_y = (new_h.saturating_sub(max_h)) / 2;
+    let crop_x = crop_offset(new_w, max_w, focal.x());
+    let crop_y = crop_offset(new_h, max_h, focal.y());
 
     resized.crop_imm(crop_x, crop_y, max_w, max_h)
 }
 
+/// Offset of a `window`-long crop centered on `ratio` of `len`, kept in bounds.
+fn crop_offset(len: u32, window: u32, ratio: f32) -> u32 {
+    let max_offset = len.saturating_sub(window);
+    let centered = (len as f32 * ratio - window as f32 / 2.0).round();
+    (centered.max(0.0) as u32).min(max_offset)
+}
+
 fn maybe_normalize_orientation(
     img_bytes: &[u8],
     content_type: &str,
@@ -834,6 +848,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cover_crops_around_focal_point() {
+        let p = ImageRsProcessor::default();
+
+        let mut src_img = ImageBuffer::from_pixel(400, 200, Rgba([0, 0, 0, 255]));
+        for y in 0..200 {
+            for x in 0..100 {
+                src_img.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+            }
+        }
+        let src = encode_png(&src_img);
+
+        let opts = ResizeOpts::new(100, 100, false, ResizeMode::Cover, BgColor::transparent());
+        let centered = decode_rgba(&p.resize_same_format(&src, "image/png", opts).unwrap());
+        assert!(
+            centered.get_pixel(20, 50)[0] < 80,
+            "center crop drops the left edge"
+        );
+
+        let focal = FocalPoint::new(0.1, 0.5).unwrap();
+        let out = p
+            .resize_same_format(&src, "image/png", opts.with_focal(focal))
+            .expect("resize ok");
+        let decoded = decode_rgba(&out);
+        assert_eq!(decoded.dimensions(), (100, 100));
+
+        let px = decoded.get_pixel(20, 50);
+        assert!(
+            px[0] > 200 && px[1] < 80,
+            "expected focal crop to keep the red left edge, got {:?}",
+            px
+        );
+    }
+
+    #[test]
+    fn crop_offset_clamps_to_image_bounds() {
+        assert_eq!(crop_offset(200, 100, 0.5), 50);
+        assert_eq!(crop_offset(200, 100, 0.0), 0);
+        assert_eq!(crop_offset(200, 100, 1.0), 100);
+        assert_eq!(crop_offset(200, 100, 0.6), 70);
+        assert_eq!(crop_offset(100, 100, 0.9), 0);
+    }
+
     #[test]
     fn all_modes_return_original_when_small_and_upscale_is_false() {
         let src = encode_png(&make_pattern_rgba(100, 50));
@@ -5,6 +5,7 @@
 //! This module provides:
 //! - [`BgColor`] — background color used when padding an image in `contain` mode.
 //! - [`ResizeMode`] — resize strategy (`fit`, `contain`, `cover`).
+//! - [`FocalPoint`] — subject position kept in frame by `cover` crops.
 //! - [`ResizeOpts`] — configuration for resizing.
 //! - [`ImageProcessor`] — trait abstraction for concrete image processing backends.
 //!
@@ -13,7 +14,8 @@
 //! - [`ResizeMode::Fit`] preserves aspect ratio and fits the whole image inside the box.
 //! - [`ResizeMode::Contain`] preserves aspect ratio, fits inside the box, and pads the
 //!   remaining area with [`BgColor`] to produce an exact output box size.
-//! - [`ResizeMode::Cover`] preserves aspect ratio, fills the whole box, and crops overflow.
+//! - [`ResizeMode::Cover`] preserves aspect ratio, fills the whole box, and crops overflow
+//!   around [`ResizeOpts::focal`] (the center by default).
 //! - [`BgColor`] accepts `#rrggbb` and `#rrggbbaa` formats.
 //!
 //! # Example
@@ -203,11 +205,91 @@ impl FromStr for ResizeMode {
     }
 }
 
+/// Point of interest in an image as `x`/`y` ratios of its width and height.
+///
+/// `(0.0, 0.0)` is the top-left corner, `(1.0, 1.0)` the bottom-right.
+/// [`ResizeMode::Cover`] crops so this point stays as close to the center of
+/// the output as the image allows.
+///
+/// String form is `"x,y"` (e.g. `"0.5,0.3"`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FocalPoint {
+    x: f32,
+    y: f32,
+}
+
+impl FocalPoint {
+    /// The image center, used when no focal point is given.
+    pub const CENTER: Self = Self { x: 0.5, y: 0.5 };
+
+    /// Creates a focal point from `x`/`y` ratios.
+    ///
+    /// # Errors
+    /// Returns an error unless both ratios are within `0.0..=1.0`.
+    pub fn new(x: f32, y: f32) -> Result<Self> {
+        for (name, v) in [("x", x), ("y", y)] {
+            if !(0.0..=1.0).contains(&v) {
+                bail!("focal point {name} must be within 0..=1: {v}");
+            }
+        }
+        Ok(Self { x, y })
+    }
+
+    /// Horizontal ratio.
+    pub const fn x(&self) -> f32 {
+        self.x
+    }
+
+    /// Vertical ratio.
+    pub const fn y(&self) -> f32 {
+        self.y
+    }
+}
+
+impl Default for FocalPoint {
+    fn default() -> Self {
+        Self::CENTER
+    }
+}
+
+// Ratios are range-checked on construction, so NaN never appears.
+impl Eq for FocalPoint {}
+
+impl std::hash::Hash for FocalPoint {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.x.to_bits().hash(state);
+        self.y.to_bits().hash(state);
+    }
+}
+
+impl fmt::Display for FocalPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+impl FromStr for FocalPoint {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((x, y)) = s.split_once(',') else {
+            bail!("invalid focal point: {s} (expected x,y)");
+        };
+        let parse = |v: &str| {
+            v.trim()
+                .parse::<f32>()
+                .map_err(|_| anyhow::anyhow!("invalid focal point: {s}"))
+        };
+        Self::new(parse(x)?, parse(y)?)
+    }
+}
+
 /// Options for resizing an image.
 ///
 /// `max_w` and `max_h` define the target box.
 /// `upscale` controls whether images already smaller than the target box may be enlarged.
-/// `bg_color` is used only for [`ResizeMode::Contain`].
+/// `bg_color` is used only for [`ResizeMode::Contain`], `focal` only for
+/// [`ResizeMode::Cover`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ResizeOpts {
     /// Target width in pixels.
@@ -220,6 +302,8 @@ pub struct ResizeOpts {
     pub resize_mode: ResizeMode,
     /// Background color used for padding in contain mode.
     pub bg_color: BgColor,
+    /// Point kept in frame when cropping in cover mode.
+    pub focal: FocalPoint,
 }
 
 impl ResizeOpts {
@@ -237,8 +321,15 @@ impl ResizeOpts {
             upscale,
             resize_mode,
             bg_color,
+            focal: FocalPoint::CENTER,
         }
     }
+
+    /// Sets the focal point used by [`ResizeMode::Cover`].
+    pub const fn with_focal(mut self, focal: FocalPoint) -> Self {
+        self.focal = focal;
+        self
+    }
 }
 
 /// Trait defining common image processing behavior.
@@ -437,6 +528,30 @@ mod tests {
         assert_eq!(opts.bg_color, BgColor::new(255, 255, 255, 128));
     }
 
+    #[test]
+    fn resize_opts_default_focal_is_center() {
+        let opts = ResizeOpts::new(1, 2, false, ResizeMode::Cover, BgColor::white());
+        assert_eq!(opts.focal, FocalPoint::CENTER);
+
+        let focal = FocalPoint::new(0.2, 0.8).unwrap();
+        assert_eq!(opts.with_focal(focal).focal, focal);
+    }
+
+    #[test]
+    fn focal_point_parses_and_displays_ratios() {
+        let p = FocalPoint::from_str("0.25, 0.75").expect("parse focal");
+        assert_eq!((p.x(), p.y()), (0.25, 0.75));
+        assert_eq!(p.to_string(), "0.25,0.75");
+        assert_eq!(FocalPoint::default(), FocalPoint::CENTER);
+    }
+
+    #[test]
+    fn focal_point_rejects_out_of_range_and_malformed_values() {
+        for s in ["", "0.5", "1.5,0.5", "0.5,-0.1", "a,b", "NaN,0.5"] {
+            assert!(FocalPoint::from_str(s).is_err(), "{s} must be rejected");
+        }
+    }
+
     #[test]
     fn resize_opts_is_copy_clone_eq_hash() {
         assert_clone_copy_eq::<ResizeOpts>();
@@ -47,12 +47,23 @@ pub use uuid;
 // ===============================
 // Public modules
 // ===============================
+pub mod audit;
 pub mod auth;
+#[cfg(feature = "cli")]
+pub mod cli;
 pub mod config;
 pub mod db;
 pub mod error;
+pub mod experiments;
+pub mod format;
 pub mod graphql;
 pub mod image;
 pub mod notification;
+pub mod reports;
+pub mod settings;
+pub mod tasks;
+pub mod telemetry;
+pub mod tenant;
+pub mod testkit;
 pub mod time;
 pub mod web;
@@ -1,3 +1,9 @@
+pub mod dns;
 pub mod email;
 pub mod email_sender;
+pub mod notifier;
+pub mod send_log;
 pub mod smtp;
+pub mod template;
+pub mod tracking;
+pub mod unsubscribe;
@@ -0,0 +1,686 @@
+//! # Email DNS Preflight
+//!
+//! Checks the DNS records that decide whether mail from the configured
+//! sender domain is delivered or dropped as spam:
+//!
+//! | Check | Record | Warns when |
+//! |-------|--------|------------|
+//! | MX    | `MX <domain>` | none (bounces and replies cannot be delivered) |
+//! | SPF   | `TXT <domain>` starting with `v=spf1` | missing, duplicated, or `+all` |
+//! | DKIM  | `TXT <selector>._domainkey.<domain>` | no selector configured, or no public key |
+//! | DMARC | `TXT _dmarc.<domain>` starting with `v=DMARC1` | missing, or `p=none` |
+//!
+//! Every warning carries a remediation hint. [`DnsPreflight::check_and_log`]
+//! is meant to run once at startup; the ops CLI exposes the same checks as
+//! `check-dns`.
+//!
+//! Lookups go through a [`DnsResolver`]. [`UdpResolver`] is a minimal stub
+//! resolver (UDP only, no TCP fallback for truncated answers) that queries
+//! the first `nameserver` in `/etc/resolv.conf`.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use wzs_web::notification::dns::{DnsPreflight, UdpResolver};
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let preflight = DnsPreflight::new(Arc::new(UdpResolver::from_system()?))
+//!     .dkim_selectors(["mail"]);
+//! let checks = preflight.check_and_log("example.com").await;
+//! assert!(checks.iter().all(|c| c.is_ok()));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use rand::Rng;
+
+use crate::config::mail::MailConfig;
+
+/// DNS record type `MX`.
+const TYPE_MX: u16 = 15;
+/// DNS record type `TXT`.
+const TYPE_TXT: u16 = 16;
+
+/// Looks up the records needed by [`DnsPreflight`].
+///
+/// Names that do not exist resolve to an empty list, not an error.
+pub trait DnsResolver: Send + Sync {
+    /// Returns the TXT records of `name`, each with its strings joined.
+    fn txt(&self, name: &str) -> Result<Vec<String>>;
+
+    /// Returns the MX records of `name` as `(preference, exchange)`.
+    fn mx(&self, name: &str) -> Result<Vec<(u16, String)>>;
+}
+
+/// Stub resolver sending single UDP queries to one name server.
+#[derive(Debug, Clone)]
+pub struct UdpResolver {
+    server: SocketAddr,
+    timeout: Duration,
+}
+
+impl UdpResolver {
+    /// Queries `server` with a 5-second timeout.
+    pub fn new(server: SocketAddr) -> Self {
+        Self {
+            server,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Uses the first `nameserver` in `/etc/resolv.conf`.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or lists no name server.
+    pub fn from_system() -> Result<Self> {
+        let conf = std::fs::read_to_string("/etc/resolv.conf").context("/etc/resolv.conf")?;
+        let server =
+            parse_resolv_conf(&conf).ok_or_else(|| anyhow!("no nameserver in /etc/resolv.conf"))?;
+        Ok(Self::new(server))
+    }
+
+    /// Sets the per-query timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn query(&self, name: &str, qtype: u16) -> Result<Vec<Answer>> {
+        let bind = if self.server.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(bind)?;
+        socket.set_read_timeout(Some(self.timeout))?;
+        socket.connect(self.server)?;
+
+        let id: u16 = rand::rng().random();
+        socket.send(&encode_query(id, name, qtype)?)?;
+
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = socket
+                .recv(&mut buf)
+                .with_context(|| format!("DNS query for {name} to {}", self.server))?;
+            // Ignore stray datagrams from earlier queries.
+            if n >= 2 && u16::from_be_bytes([buf[0], buf[1]]) == id {
+                return parse_response(&buf[..n], qtype);
+            }
+        }
+    }
+}
+
+impl DnsResolver for UdpResolver {
+    fn txt(&self, name: &str) -> Result<Vec<String>> {
+        Ok(self
+            .query(name, TYPE_TXT)?
+            .into_iter()
+            .filter_map(|a| match a {
+                Answer::Txt(t) => Some(t),
+                Answer::Mx(..) => None,
+            })
+            .collect())
+    }
+
+    fn mx(&self, name: &str) -> Result<Vec<(u16, String)>> {
+        Ok(self
+            .query(name, TYPE_MX)?
+            .into_iter()
+            .filter_map(|a| match a {
+                Answer::Mx(pref, host) => Some((pref, host)),
+                Answer::Txt(_) => None,
+            })
+            .collect())
+    }
+}
+
+/// Record family covered by a [`DnsCheck`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DnsCheckKind {
+    Mx,
+    Spf,
+    Dkim,
+    Dmarc,
+}
+
+impl fmt::Display for DnsCheckKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Mx => "MX",
+            Self::Spf => "SPF",
+            Self::Dkim => "DKIM",
+            Self::Dmarc => "DMARC",
+        })
+    }
+}
+
+/// Outcome of one preflight check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsCheck {
+    pub kind: DnsCheckKind,
+    /// What was found (or not found).
+    pub message: String,
+    /// How to fix it; `None` when the check passed.
+    pub hint: Option<String>,
+}
+
+impl DnsCheck {
+    fn ok(kind: DnsCheckKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(kind: DnsCheckKind, message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    /// Whether the check passed.
+    pub fn is_ok(&self) -> bool {
+        self.hint.is_none()
+    }
+}
+
+impl fmt::Display for DnsCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.hint {
+            None => write!(f, "{}: ok — {}", self.kind, self.message),
+            Some(hint) => write!(f, "{}: {} (hint: {hint})", self.kind, self.message),
+        }
+    }
+}
+
+/// Runs the MX / SPF / DKIM / DMARC checks for a sender domain.
+#[derive(Clone)]
+pub struct DnsPreflight {
+    resolver: Arc<dyn DnsResolver>,
+    dkim_selectors: Vec<String>,
+}
+
+impl DnsPreflight {
+    /// Creates a preflight without DKIM selectors.
+    pub fn new(resolver: Arc<dyn DnsResolver>) -> Self {
+        Self {
+            resolver,
+            dkim_selectors: Vec::new(),
+        }
+    }
+
+    /// Creates a preflight using the selectors from `DKIM_SELECTORS`.
+    pub fn for_mail(resolver: Arc<dyn DnsResolver>, mail: &MailConfig) -> Self {
+        Self::new(resolver).dkim_selectors(mail.dkim_selectors.iter())
+    }
+
+    /// Sets the DKIM selectors to verify.
+    pub fn dkim_selectors<I, S>(mut self, selectors: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.dkim_selectors = selectors.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Runs all checks for `domain` (blocking).
+    pub fn check(&self, domain: &str) -> Vec<DnsCheck> {
+        let domain = domain.trim_end_matches('.');
+        let mut checks = vec![self.check_mx(domain), self.check_spf(domain)];
+        checks.extend(self.check_dkim(domain));
+        checks.push(self.check_dmarc(domain));
+        checks
+    }
+
+    /// Runs [`check`](Self::check) on a blocking thread and logs each
+    /// failed check as a warning.
+    pub async fn check_and_log(&self, domain: &str) -> Vec<DnsCheck> {
+        let this = self.clone();
+        let target = domain.to_string();
+        let checks = match tokio::task::spawn_blocking(move || this.check(&target)).await {
+            Ok(checks) => checks,
+            Err(e) => {
+                tracing::warn!(domain, error = %e, "email DNS preflight failed");
+                return Vec::new();
+            }
+        };
+        for c in checks.iter().filter(|c| !c.is_ok()) {
+            tracing::warn!(
+                domain,
+                check = %c.kind,
+                hint = c.hint.as_deref().unwrap_or_default(),
+                "{}",
+                c.message
+            );
+        }
+        checks
+    }
+
+    fn check_mx(&self, domain: &str) -> DnsCheck {
+        let kind = DnsCheckKind::Mx;
+        match self.resolver.mx(domain) {
+            Err(e) => lookup_failed(kind, domain, &e),
+            Ok(records) if records.is_empty() => DnsCheck::warn(
+                kind,
+                format!("no MX records for {domain}; bounces and replies cannot be delivered"),
+                format!("add an MX record for {domain} pointing at your mail provider"),
+            ),
+            Ok(mut records) => {
+                records.sort();
+                let hosts: Vec<_> = records.into_iter().map(|(_, h)| h).collect();
+                DnsCheck::ok(kind, hosts.join(", "))
+            }
+        }
+    }
+
+    fn check_spf(&self, domain: &str) -> DnsCheck {
+        let kind = DnsCheckKind::Spf;
+        let records = match self.resolver.txt(domain) {
+            Ok(txt) => txt
+                .into_iter()
+                .filter(|t| has_tag(t, "v=spf1"))
+                .collect::<Vec<_>>(),
+            Err(e) => return lookup_failed(kind, domain, &e),
+        };
+        match records.as_slice() {
+            [] => DnsCheck::warn(
+                kind,
+                format!("no SPF record for {domain}"),
+                format!("publish TXT \"v=spf1 include:<provider> ~all\" on {domain}"),
+            ),
+            [spf] if spf.split_whitespace().any(|t| t == "+all") => DnsCheck::warn(
+                kind,
+                format!("SPF record allows any sender: {spf}"),
+                "replace +all with ~all or -all",
+            ),
+            [spf] => DnsCheck::ok(kind, spf.clone()),
+            _ => DnsCheck::warn(
+                kind,
+                format!(
+                    "{} SPF records for {domain} (receivers treat this as an error)",
+                    records.len()
+                ),
+                "merge them into a single v=spf1 record",
+            ),
+        }
+    }
+
+    fn check_dkim(&self, domain: &str) -> Vec<DnsCheck> {
+        let kind = DnsCheckKind::Dkim;
+        if self.dkim_selectors.is_empty() {
+            return vec![DnsCheck::warn(
+                kind,
+                "no DKIM selector configured; signing cannot be verified",
+                "set DKIM_SELECTORS to the selector(s) your mail provider signs with",
+            )];
+        }
+        self.dkim_selectors
+            .iter()
+            .map(|selector| {
+                let name = format!("{selector}._domainkey.{domain}");
+                match self.resolver.txt(&name) {
+                    Err(e) => lookup_failed(kind, &name, &e),
+                    Ok(txt) if txt.iter().any(|t| has_public_key(t)) => {
+                        DnsCheck::ok(kind, format!("public key at {name}"))
+                    }
+                    Ok(txt) if txt.is_empty() => DnsCheck::warn(
+                        kind,
+                        format!("no DKIM record at {name}"),
+                        format!("publish the DKIM public key from your mail provider at {name}"),
+                    ),
+                    Ok(_) => DnsCheck::warn(
+                        kind,
+                        format!("DKIM record at {name} has no public key (p=)"),
+                        "re-publish the key; an empty p= means the key was revoked",
+                    ),
+                }
+            })
+            .collect()
+    }
+
+    fn check_dmarc(&self, domain: &str) -> DnsCheck {
+        let kind = DnsCheckKind::Dmarc;
+        let name = format!("_dmarc.{domain}");
+        let record = match self.resolver.txt(&name) {
+            Ok(txt) => txt.into_iter().find(|t| has_tag(t, "v=DMARC1")),
+            Err(e) => return lookup_failed(kind, &name, &e),
+        };
+        match record {
+            None => DnsCheck::warn(
+                kind,
+                format!("no DMARC record at {name}"),
+                format!("publish TXT \"v=DMARC1; p=quarantine; rua=mailto:<reports>\" at {name}"),
+            ),
+            Some(r) if tag_value(&r, "p").is_some_and(|p| p.eq_ignore_ascii_case("none")) => {
+                DnsCheck::warn(
+                    kind,
+                    format!("DMARC policy is monitoring only: {r}"),
+                    "move to p=quarantine or p=reject once reports look clean",
+                )
+            }
+            Some(r) => DnsCheck::ok(kind, r),
+        }
+    }
+}
+
+/// Returns the domain part of an address such as `noreply@example.com`.
+pub fn sender_domain(address: &str) -> Option<&str> {
+    let address = address.trim().trim_end_matches('>');
+    address
+        .rsplit_once('@')
+        .map(|(_, domain)| domain)
+        .filter(|d| !d.is_empty())
+}
+
+fn lookup_failed(kind: DnsCheckKind, name: &str, e: &anyhow::Error) -> DnsCheck {
+    DnsCheck::warn(
+        kind,
+        format!("lookup of {name} failed: {e:#}"),
+        "check network access to the name server and retry",
+    )
+}
+
+/// Whether `record` starts with the version tag `tag` (case-insensitive).
+fn has_tag(record: &str, tag: &str) -> bool {
+    let first = record.split([' ', ';']).next().unwrap_or_default();
+    first.eq_ignore_ascii_case(tag)
+}
+
+/// Value of `name=` in a `;`-separated tag list.
+fn tag_value<'a>(record: &'a str, name: &str) -> Option<&'a str> {
+    record.split(';').find_map(|part| {
+        let (k, v) = part.split_once('=')?;
+        k.trim().eq_ignore_ascii_case(name).then(|| v.trim())
+    })
+}
+
+fn has_public_key(record: &str) -> bool {
+    tag_value(record, "p").is_some_and(|p| !p.is_empty())
+}
+
+fn parse_resolv_conf(conf: &str) -> Option<SocketAddr> {
+    conf.lines().find_map(|line| {
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some("nameserver"), Some(addr)) => {
+                // Drop an IPv6 zone id (`fe80::1%eth0`).
+                let ip = addr.split('%').next()?.parse().ok()?;
+                Some(SocketAddr::new(ip, 53))
+            }
+            _ => None,
+        }
+    })
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Answer {
+    Txt(String),
+    Mx(u16, String),
+}
+
+fn encode_query(id: u16, name: &str, qtype: u16) -> Result<Vec<u8>> {
+    let mut msg = Vec::with_capacity(512);
+    msg.extend_from_slice(&id.to_be_bytes());
+    // Flags: standard query, recursion desired; one question.
+    msg.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            bail!("invalid DNS name: {name}");
+        }
+        msg.push(label.len() as u8);
+        msg.extend_from_slice(label.as_bytes());
+    }
+    msg.push(0);
+    msg.extend_from_slice(&qtype.to_be_bytes());
+    msg.extend_from_slice(&1u16.to_be_bytes()); // class IN
+    Ok(msg)
+}
+
+fn parse_response(msg: &[u8], qtype: u16) -> Result<Vec<Answer>> {
+    let u16_at = |i: usize| -> Result<u16> {
+        msg.get(i..i + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .ok_or_else(|| anyhow!("truncated DNS message"))
+    };
+    let flags = u16_at(2)?;
+    if flags & 0x0200 != 0 {
+        bail!("DNS answer truncated (TCP fallback is not supported)");
+    }
+    match flags & 0x000f {
+        0 => {}
+        3 => return Ok(Vec::new()), // NXDOMAIN
+        rcode => bail!("DNS server returned rcode {rcode}"),
+    }
+    let questions = u16_at(4)?;
+    let answers = u16_at(6)?;
+
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = read_name(msg, pos)?.1 + 4;
+    }
+
+    let mut out = Vec::new();
+    for _ in 0..answers {
+        pos = read_name(msg, pos)?.1;
+        let rtype = u16_at(pos)?;
+        let len = u16_at(pos + 8)? as usize;
+        let start = pos + 10;
+        let rdata = msg
+            .get(start..start + len)
+            .ok_or_else(|| anyhow!("truncated DNS record"))?;
+        pos = start + len;
+        if rtype != qtype {
+            continue; // e.g. CNAME records preceding the answer
+        }
+        match rtype {
+            TYPE_TXT => {
+                let mut text = Vec::new();
+                let mut i = 0;
+                while i < rdata.len() {
+                    let n = rdata[i] as usize;
+                    let chunk = rdata
+                        .get(i + 1..i + 1 + n)
+                        .ok_or_else(|| anyhow!("truncated TXT record"))?;
+                    text.extend_from_slice(chunk);
+                    i += 1 + n;
+                }
+                out.push(Answer::Txt(String::from_utf8_lossy(&text).into_owned()));
+            }
+            TYPE_MX => {
+                let pref = u16_at(start)?;
+                let (host, _) = read_name(msg, start + 2)?;
+                out.push(Answer::Mx(pref, host));
+            }
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+/// Reads a (possibly compressed) name at `pos`, returning it and the
+/// position just after it.
+fn read_name(msg: &[u8], mut pos: usize) -> Result<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    for _ in 0..128 {
+        let len = *msg.get(pos).ok_or_else(|| anyhow!("truncated DNS name"))? as usize;
+        match len {
+            0 => {
+                return Ok((labels.join("."), end.unwrap_or(pos + 1)));
+            }
+            l if l & 0xc0 == 0xc0 => {
+                let low = *msg
+                    .get(pos + 1)
+                    .ok_or_else(|| anyhow!("truncated DNS name"))?;
+                end.get_or_insert(pos + 2);
+                pos = ((l & 0x3f) << 8) | low as usize;
+            }
+            l => {
+                let label = msg
+                    .get(pos + 1..pos + 1 + l)
+                    .ok_or_else(|| anyhow!("truncated DNS name"))?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                pos += 1 + l;
+            }
+        }
+    }
+    bail!("DNS name compression loop")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct FakeResolver {
+        txt: HashMap<&'static str, Vec<&'static str>>,
+        mx: Vec<(u16, String)>,
+    }
+
+    impl DnsResolver for FakeResolver {
+        fn txt(&self, name: &str) -> Result<Vec<String>> {
+            if name == "broken.example.com" {
+                bail!("timed out");
+            }
+            Ok(self
+                .txt
+                .get(name)
+                .map(|v| v.iter().map(|s| s.to_string()).collect())
+                .unwrap_or_default())
+        }
+
+        fn mx(&self, _name: &str) -> Result<Vec<(u16, String)>> {
+            Ok(self.mx.clone())
+        }
+    }
+
+    fn preflight(resolver: FakeResolver) -> DnsPreflight {
+        DnsPreflight::new(Arc::new(resolver)).dkim_selectors(["s1"])
+    }
+
+    #[test]
+    fn passes_a_well_configured_domain() {
+        let resolver = FakeResolver {
+            txt: HashMap::from([
+                (
+                    "example.com",
+                    vec![
+                        "google-site-verification=x",
+                        "v=spf1 include:_spf.example.net ~all",
+                    ],
+                ),
+                (
+                    "s1._domainkey.example.com",
+                    vec!["v=DKIM1; k=rsa; p=MIGfMA0"],
+                ),
+                ("_dmarc.example.com", vec!["v=DMARC1; p=reject"]),
+            ]),
+            mx: vec![
+                (20, "mx2.example.net".into()),
+                (10, "mx1.example.net".into()),
+            ],
+        };
+        let checks = preflight(resolver).check("example.com.");
+        assert_eq!(checks.len(), 4);
+        assert!(checks.iter().all(DnsCheck::is_ok), "{checks:?}");
+        assert_eq!(checks[0].message, "mx1.example.net, mx2.example.net");
+    }
+
+    #[test]
+    fn warns_with_hints_for_missing_and_weak_records() {
+        let resolver = FakeResolver {
+            txt: HashMap::from([
+                ("example.com", vec!["v=spf1 +all"]),
+                ("s1._domainkey.example.com", vec!["v=DKIM1; p="]),
+                (
+                    "_dmarc.example.com",
+                    vec!["v=DMARC1; p=none; rua=mailto:d@example.com"],
+                ),
+            ]),
+            mx: vec![],
+        };
+        let checks = preflight(resolver).check("example.com");
+        assert!(checks.iter().all(|c| !c.is_ok()), "{checks:?}");
+        assert!(checks[0].message.contains("no MX"));
+        assert!(checks[1].to_string().contains("hint: replace +all"));
+        assert!(checks[2].message.contains("no public key"));
+        assert!(checks[3].message.contains("monitoring only"));
+
+        let resolver = FakeResolver {
+            txt: HashMap::from([("example.com", vec!["v=spf1 a ~all", "v=spf1 mx ~all"])]),
+            mx: vec![(10, "mx".into())],
+        };
+        let checks = DnsPreflight::new(Arc::new(resolver)).check("example.com");
+        assert!(checks[1].message.starts_with("2 SPF records"));
+        assert!(checks[2].message.contains("no DKIM selector"));
+        assert!(checks[3].message.contains("no DMARC record"));
+    }
+
+    #[test]
+    fn reports_lookup_failures() {
+        let checks = preflight(FakeResolver::default()).check("broken.example.com");
+        assert!(checks[1]
+            .message
+            .contains("lookup of broken.example.com failed"));
+    }
+
+    #[test]
+    fn extracts_sender_domain() {
+        assert_eq!(sender_domain("noreply@example.com"), Some("example.com"));
+        assert_eq!(sender_domain("<a@b.example.org>"), Some("b.example.org"));
+        assert_eq!(sender_domain("nobody"), None);
+    }
+
+    #[test]
+    fn encodes_queries_and_parses_compressed_answers() {
+        let query = encode_query(0xabcd, "example.com", TYPE_MX).unwrap();
+        assert_eq!(&query[..2], &[0xab, 0xcd]);
+        assert_eq!(&query[12..25], b"\x07example\x03com\x00");
+        assert!(encode_query(1, "a..b", TYPE_MX).is_err());
+
+        // Response: the question, then one MX and one TXT answer using
+        // pointers back to the question name (offset 12).
+        let mut msg = query.clone();
+        msg[2..8].copy_from_slice(&[0x81, 0x80, 0, 1, 0, 2]);
+        msg.extend_from_slice(&[0xc0, 12, 0, 15, 0, 1, 0, 0, 0, 60, 0, 7, 0, 10]);
+        msg.extend_from_slice(b"\x02mx\xc0\x0c");
+        msg.extend_from_slice(&[0xc0, 12, 0, 16, 0, 1, 0, 0, 0, 60, 0, 8]);
+        msg.extend_from_slice(b"\x03v=s\x03pf1");
+
+        assert_eq!(
+            parse_response(&msg, TYPE_MX).unwrap(),
+            vec![Answer::Mx(10, "mx.example.com".into())]
+        );
+        assert_eq!(
+            parse_response(&msg, TYPE_TXT).unwrap(),
+            vec![Answer::Txt("v=spf1".into())]
+        );
+
+        msg[3] = 0x83; // NXDOMAIN
+        assert!(parse_response(&msg, TYPE_MX).unwrap().is_empty());
+        msg[2] |= 0x02; // TC
+        assert!(parse_response(&msg, TYPE_MX).is_err());
+    }
+
+    #[test]
+    fn reads_first_nameserver() {
+        let conf = "# comment\nsearch lan\nnameserver fe80::1%eth0\nnameserver 1.1.1.1\n";
+        assert_eq!(
+            parse_resolv_conf(conf),
+            Some("[fe80::1]:53".parse().unwrap())
+        );
+        assert_eq!(parse_resolv_conf("search lan\n"), None);
+    }
+}
@@ -31,6 +31,59 @@ pub struct Email {
     ///
     /// Avoid logging this list in application logs.
     pub bcc: Vec<Mailbox>,
+
+    /// Additional message headers (e.g. `List-Unsubscribe`).
+    ///
+    /// Standard headers (`From`, `To`, `Subject`, ...) are managed by the
+    /// transport adapter; see [`EmailHeader::is_reserved`].
+    pub headers: Vec<EmailHeader>,
+}
+
+/// Headers set by the transport adapter from the [`Email`] fields, or by
+/// the transport itself (envelope, message id, date, MIME framing).
+pub const RESERVED_HEADERS: [&str; 12] = [
+    "From",
+    "Sender",
+    "To",
+    "Cc",
+    "Bcc",
+    "Subject",
+    "Return-Path",
+    "Message-ID",
+    "Date",
+    "MIME-Version",
+    "Content-Type",
+    "Content-Transfer-Encoding",
+];
+
+/// A custom header attached to an [`Email`].
+///
+/// Like the subject, values are sanitized by the transport adapter, which
+/// rejects [reserved](Self::is_reserved) names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailHeader {
+    /// Header name (e.g. `List-Unsubscribe`).
+    pub name: String,
+    /// Raw header value.
+    pub value: String,
+}
+
+impl EmailHeader {
+    /// Creates a new header.
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Whether the name is one of [`RESERVED_HEADERS`] (case-insensitive).
+    pub fn is_reserved(&self) -> bool {
+        let name = self.name.trim();
+        RESERVED_HEADERS
+            .iter()
+            .any(|r| r.eq_ignore_ascii_case(name))
+    }
 }
 
 /// The body representation of an email.
@@ -118,6 +171,7 @@ mod tests {
             to: vec![mb("to@example.com")],
             cc: vec![mb("cc@example.com")],
             bcc: vec![mb("bcc@example.com")],
+            headers: vec![],
         };
 
         // Clone
@@ -139,6 +193,7 @@ mod tests {
             to: vec![],
             cc: vec![],
             bcc: vec![],
+            headers: vec![],
         };
 
         match email.body {
@@ -166,6 +221,7 @@ mod tests {
             to: vec![mb("to@example.com")],
             cc: vec![],
             bcc: vec![],
+            headers: vec![],
         };
 
         match email.body {
@@ -190,6 +246,7 @@ mod tests {
             to: vec![mb("to@example.com")],
             cc: vec![],
             bcc: vec![],
+            headers: vec![],
         };
 
         match email.body {
@@ -221,6 +278,7 @@ mod tests {
             to: vec![mb("to@example.com")],
             cc: vec![mb("cc@example.com")],
             bcc: vec![],
+            headers: vec![],
         };
 
         match email.body {
@@ -249,10 +307,45 @@ mod tests {
             to: vec![],
             cc: vec![],
             bcc: vec![],
+            headers: vec![],
         };
 
         assert!(email.to.is_empty());
         assert!(email.cc.is_empty());
         assert!(email.bcc.is_empty());
     }
+
+    #[test]
+    fn custom_headers_are_carried_by_the_value_object() {
+        let email = Email {
+            subject: "S".into(),
+            body: EmailBody::Text("Body".into()),
+            to: vec![mb("to@example.com")],
+            cc: vec![],
+            bcc: vec![],
+            headers: vec![EmailHeader::new("X-Campaign", "spring")],
+        };
+
+        assert_eq!(email.headers.len(), 1);
+        assert_eq!(email.headers[0].name, "X-Campaign");
+        assert_eq!(email.headers[0].value, "spring");
+        assert!(!email.headers[0].is_reserved());
+        assert!(!EmailHeader::new("Reply-To", "x").is_reserved());
+        for name in RESERVED_HEADERS {
+            assert!(EmailHeader::new(name, "x").is_reserved(), "{name}");
+            let lower = format!(" {} ", name.to_ascii_lowercase());
+            assert!(EmailHeader::new(lower, "x").is_reserved(), "{name}");
+        }
+        for name in [
+            "Sender",
+            "Return-Path",
+            "Message-ID",
+            "Date",
+            "MIME-Version",
+            "Content-Transfer-Encoding",
+        ] {
+            assert!(RESERVED_HEADERS.contains(&name), "{name}");
+        }
+        assert!(!EmailHeader::new("X-Message-Id", "x").is_reserved());
+    }
 }
@@ -98,6 +98,7 @@ mod tests {
             to: vec![mb("to@example.com")],
             cc: vec![],
             bcc: vec![],
+            headers: vec![],
         };
 
         sender
@@ -121,6 +122,7 @@ mod tests {
             to: vec![mb("to@example.com")],
             cc: vec![],
             bcc: vec![],
+            headers: vec![],
         };
 
         // Clone the Arc to simulate multi-owner usage
@@ -144,6 +146,7 @@ mod tests {
             to: vec![],
             cc: vec![],
             bcc: vec![],
+            headers: vec![],
         };
 
         sender.send(email).await.expect("send should succeed");
@@ -0,0 +1,133 @@
+//! # Operator Notifications
+//!
+//! [`Notifier`] delivers short operational notices (quarantined uploads,
+//! failed jobs, …) to the people running the application. It is separate
+//! from [`EmailSender`] so subsystems do not need to know recipients or
+//! message formats.
+//!
+//! [`EmailNotifier`] sends each notice as a plain-text email to a fixed
+//! list of admin addresses.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use wzs_web::notification::email_sender::EmailSender;
+//! use wzs_web::notification::notifier::{EmailNotifier, Notifier};
+//!
+//! # async fn run(sender: Arc<dyn EmailSender>) -> anyhow::Result<()> {
+//! let notifier = EmailNotifier::new(sender, vec!["ops@example.com".parse()?]);
+//! notifier
+//!     .notify("Upload quarantined", "files/202603/a.pdf: Eicar-Test-Signature")
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+
+use super::email::{Email, EmailBody};
+use super::email_sender::EmailSender;
+
+/// Delivers operational notices to administrators.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Sends a notice with a one-line `subject` and a plain-text `message`.
+    async fn notify(&self, subject: &str, message: &str) -> Result<()>;
+}
+
+/// [`Notifier`] emailing every notice to a fixed list of admins.
+pub struct EmailNotifier {
+    sender: Arc<dyn EmailSender>,
+    recipients: Vec<Mailbox>,
+    subject_prefix: String,
+}
+
+impl EmailNotifier {
+    /// Sends notices to `recipients`, subjects prefixed with `[wzs-web]`.
+    pub fn new(sender: Arc<dyn EmailSender>, recipients: Vec<Mailbox>) -> Self {
+        Self {
+            sender,
+            recipients,
+            subject_prefix: "[wzs-web]".into(),
+        }
+    }
+
+    /// Sets the subject prefix (empty for none).
+    pub fn subject_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.subject_prefix = prefix.into();
+        self
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    async fn notify(&self, subject: &str, message: &str) -> Result<()> {
+        if self.recipients.is_empty() {
+            bail!("no notification recipients configured");
+        }
+        let subject = if self.subject_prefix.is_empty() {
+            subject.to_string()
+        } else {
+            format!("{} {subject}", self.subject_prefix)
+        };
+        self.sender
+            .send(Email {
+                subject,
+                body: EmailBody::Text(message.to_string()),
+                to: self.recipients.clone(),
+                cc: vec![],
+                bcc: vec![],
+                headers: vec![],
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingSender {
+        sent: Mutex<Vec<Email>>,
+    }
+
+    #[async_trait]
+    impl EmailSender for RecordingSender {
+        async fn send(&self, email: Email) -> Result<()> {
+            self.sent.lock().unwrap().push(email);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn email_notifier_sends_prefixed_text_mail_to_admins() {
+        let sender = Arc::new(RecordingSender::default());
+        let admins = vec![
+            "a@example.com".parse().unwrap(),
+            "b@example.com".parse().unwrap(),
+        ];
+        let notifier = EmailNotifier::new(sender.clone(), admins);
+        notifier
+            .notify("Disk full", "uploads at 95%")
+            .await
+            .unwrap();
+
+        let sent = sender.sent.lock().unwrap();
+        assert_eq!(sent[0].subject, "[wzs-web] Disk full");
+        assert_eq!(sent[0].to.len(), 2);
+        assert!(matches!(&sent[0].body, EmailBody::Text(t) if t == "uploads at 95%"));
+    }
+
+    #[tokio::test]
+    async fn email_notifier_without_recipients_fails() {
+        let notifier =
+            EmailNotifier::new(Arc::new(RecordingSender::default()), vec![]).subject_prefix("");
+        assert!(notifier.notify("x", "y").await.is_err());
+    }
+}
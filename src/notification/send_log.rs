@@ -0,0 +1,621 @@
+//! # Email Send Log
+//!
+//! Records every delivery attempt and skips suppressed recipients:
+//!
+//! - [`SendLog`] — port for writing and querying the log, with a
+//!   [`DbSendLog`] implementation over the [`Db`] port
+//! - [`AuditedEmailSender`] — [`EmailSender`] decorator that drops recipients
+//!   on the [`SuppressionList`] (hard bounces, and optionally a list's
+//!   unsubscribes), sends, and logs the outcome
+//! - [`record_hard_bounce`] — suppresses an address for every message
+//!
+//! Recipients are logged only as a SHA-256 hash of the sorted, normalized
+//! `To` / `Cc` / `Bcc` addresses ([`recipients_hash`]), so support staff can
+//! look up what was sent to an address without the log holding addresses.
+//!
+//! The template name is taken from the [`TEMPLATE_HEADER`] header (removed
+//! before delivery); each message is tagged with [`MESSAGE_ID_HEADER`] so
+//! bounces can be correlated with the log.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use wzs_web::config::db::DbConfig;
+//! use wzs_web::db::{connection::get_pool, mysql_adapter::MySqlDb};
+//! use wzs_web::notification::email_sender::EmailSender;
+//! use wzs_web::notification::send_log::{AuditedEmailSender, DbSendLog, SendLog};
+//! use wzs_web::notification::unsubscribe::DbSuppressionList;
+//!
+//! # fn run(smtp: Arc<dyn EmailSender>) -> anyhow::Result<()> {
+//! let db = Arc::new(MySqlDb::new(get_pool(&DbConfig::from_env())));
+//! let log = Arc::new(DbSendLog::new(db.clone()));
+//!
+//! let sender = AuditedEmailSender::new(smtp, log.clone())
+//!     .suppression(Arc::new(DbSuppressionList::new(db)), Some("newsletter"));
+//!
+//! for entry in log.by_recipient("user@example.com", 20)? {
+//!     println!("{} {} {:?}", entry.created_at, entry.status, entry.template);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use lettre::message::Mailbox;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::port::{Db, Param, Row};
+use crate::db::repository::ident;
+use crate::notification::email::{Email, EmailHeader};
+use crate::notification::email_sender::EmailSender;
+use crate::notification::unsubscribe::SuppressionList;
+
+/// Header naming the template an email was rendered from; stripped before delivery.
+pub const TEMPLATE_HEADER: &str = "X-Template";
+
+/// Header carrying the logged message id.
+pub const MESSAGE_ID_HEADER: &str = "X-Message-Id";
+
+/// Suppression list id for addresses that must never be mailed again.
+pub const HARD_BOUNCE_LIST: &str = "hard_bounce";
+
+/// Outcome of a delivery attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SendStatus {
+    /// Handed off to the transport.
+    Sent,
+    /// The transport returned an error.
+    Failed,
+    /// Not sent because every recipient is suppressed.
+    Suppressed,
+}
+
+impl SendStatus {
+    /// Returns the stored string form.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sent => "sent",
+            Self::Failed => "failed",
+            Self::Suppressed => "suppressed",
+        }
+    }
+}
+
+impl fmt::Display for SendStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SendStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "sent" => Ok(Self::Sent),
+            "failed" => Ok(Self::Failed),
+            "suppressed" => Ok(Self::Suppressed),
+            _ => bail!("unknown send status: {s}"),
+        }
+    }
+}
+
+/// One row of the send log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendLogEntry {
+    /// Id also sent as [`MESSAGE_ID_HEADER`].
+    pub message_id: String,
+    /// See [`recipients_hash`].
+    pub recipients_hash: String,
+    /// Template name from [`TEMPLATE_HEADER`], if set.
+    pub template: Option<String>,
+    pub status: SendStatus,
+    /// Transport error for [`SendStatus::Failed`].
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+/// Port for recording and querying delivery attempts.
+pub trait SendLog: Send + Sync {
+    /// Appends an entry.
+    fn record(&self, entry: &SendLogEntry) -> Result<()>;
+
+    /// Returns the entry for `message_id`, if any.
+    fn by_message_id(&self, message_id: &str) -> Result<Option<SendLogEntry>>;
+
+    /// Returns the newest entries for messages addressed only to `email`.
+    fn by_recipient(&self, email: &str, limit: u64) -> Result<Vec<SendLogEntry>>;
+}
+
+/// [`SendLog`] stored in a database table via the [`Db`] port.
+///
+/// Expected schema (MySQL):
+///
+/// ```sql
+/// CREATE TABLE email_send_log (
+///     id              BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+///     message_id      CHAR(36)     NOT NULL,
+///     recipients_hash CHAR(64)     NOT NULL,
+///     template        VARCHAR(128) NULL,
+///     status          VARCHAR(16)  NOT NULL,
+///     error           TEXT         NULL,
+///     created_at      DATETIME     NOT NULL,
+///     UNIQUE KEY uq_email_send_log_message (message_id),
+///     KEY ix_email_send_log_recipients (recipients_hash, created_at)
+/// );
+/// ```
+#[derive(Clone)]
+pub struct DbSendLog {
+    db: Arc<dyn Db>,
+    table: String,
+}
+
+impl DbSendLog {
+    /// Default table name.
+    pub const DEFAULT_TABLE: &'static str = "email_send_log";
+
+    /// Creates a send log backed by the default table.
+    pub fn new(db: Arc<dyn Db>) -> Self {
+        Self {
+            db,
+            table: Self::DEFAULT_TABLE.into(),
+        }
+    }
+
+    /// Creates a send log backed by a custom table.
+    ///
+    /// # Errors
+    /// Returns an error if `table` is not a plain SQL identifier.
+    pub fn with_table(db: Arc<dyn Db>, table: impl Into<String>) -> Result<Self> {
+        let table = table.into();
+        ident(&table)?;
+        Ok(Self { db, table })
+    }
+
+    fn columns() -> &'static str {
+        "message_id, recipients_hash, template, status, error, created_at"
+    }
+}
+
+impl SendLog for DbSendLog {
+    fn record(&self, entry: &SendLogEntry) -> Result<()> {
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES (?, ?, ?, ?, ?, ?)",
+            self.table,
+            Self::columns()
+        );
+        self.db.exec(
+            &sql,
+            &[
+                Param::Str(&entry.message_id),
+                Param::Str(&entry.recipients_hash),
+                Param::from(entry.template.as_deref()),
+                Param::Str(entry.status.as_str()),
+                Param::from(entry.error.as_deref()),
+                Param::DateTime(entry.created_at),
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn by_message_id(&self, message_id: &str) -> Result<Option<SendLogEntry>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE message_id = ? LIMIT 1",
+            Self::columns(),
+            self.table
+        );
+        self.db
+            .fetch_one(&sql, &[Param::Str(message_id)])?
+            .map(|r| entry_from_row(&r))
+            .transpose()
+    }
+
+    fn by_recipient(&self, email: &str, limit: u64) -> Result<Vec<SendLogEntry>> {
+        let hash = hash_addresses(&[email]);
+        let sql = format!(
+            "SELECT {} FROM {} WHERE recipients_hash = ? ORDER BY created_at DESC LIMIT ?",
+            Self::columns(),
+            self.table
+        );
+        self.db
+            .fetch_all(&sql, &[Param::Str(&hash), Param::U64(limit)])?
+            .iter()
+            .map(entry_from_row)
+            .collect()
+    }
+}
+
+fn entry_from_row(r: &Row) -> Result<SendLogEntry> {
+    Ok(SendLogEntry {
+        message_id: r.get_string("message_id")?,
+        recipients_hash: r.get_string("recipients_hash")?,
+        template: r.get_string_opt("template")?,
+        status: r.get_string("status")?.parse()?,
+        error: r.get_string_opt("error")?,
+        created_at: r.get_datetime("created_at")?,
+    })
+}
+
+/// Hex SHA-256 of the email's sorted, normalized recipients (`To`, `Cc`, `Bcc`).
+pub fn recipients_hash(email: &Email) -> String {
+    let addrs: Vec<String> = email
+        .to
+        .iter()
+        .chain(&email.cc)
+        .chain(&email.bcc)
+        .map(|mb| mb.email.to_string())
+        .collect();
+    let refs: Vec<&str> = addrs.iter().map(String::as_str).collect();
+    hash_addresses(&refs)
+}
+
+fn hash_addresses(addrs: &[&str]) -> String {
+    let mut normalized: Vec<String> = addrs
+        .iter()
+        .map(|a| a.trim().to_ascii_lowercase())
+        .collect();
+    normalized.sort();
+    normalized.dedup();
+
+    Sha256::digest(normalized.join("\n").as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Suppresses `email` for every message sent through an [`AuditedEmailSender`]
+/// configured with `list`.
+pub fn record_hard_bounce(list: &dyn SuppressionList, email: &str) -> Result<()> {
+    list.suppress(email, HARD_BOUNCE_LIST)
+}
+
+/// [`EmailSender`] decorator that enforces suppressions and logs every attempt.
+///
+/// Log write failures are reported with `warn!` and never fail the send.
+#[derive(Clone)]
+pub struct AuditedEmailSender {
+    inner: Arc<dyn EmailSender>,
+    log: Arc<dyn SendLog>,
+    suppression: Option<(Arc<dyn SuppressionList>, Option<String>)>,
+}
+
+impl AuditedEmailSender {
+    /// Wraps `inner`, recording attempts in `log`.
+    pub fn new(inner: Arc<dyn EmailSender>, log: Arc<dyn SendLog>) -> Self {
+        Self {
+            inner,
+            log,
+            suppression: None,
+        }
+    }
+
+    /// Drops recipients suppressed under [`HARD_BOUNCE_LIST`] and, when given,
+    /// under `list_id` (e.g. the newsletter's unsubscribes).
+    pub fn suppression(
+        mut self,
+        list: Arc<dyn SuppressionList>,
+        list_id: Option<impl Into<String>>,
+    ) -> Self {
+        self.suppression = Some((list, list_id.map(Into::into)));
+        self
+    }
+
+    fn is_suppressed(&self, mb: &Mailbox) -> Result<bool> {
+        let Some((list, list_id)) = &self.suppression else {
+            return Ok(false);
+        };
+        let addr = mb.email.to_string();
+        if list.is_suppressed(&addr, HARD_BOUNCE_LIST)? {
+            return Ok(true);
+        }
+        match list_id {
+            Some(id) => list.is_suppressed(&addr, id),
+            None => Ok(false),
+        }
+    }
+
+    fn retain_allowed(&self, mailboxes: &mut Vec<Mailbox>) -> Result<()> {
+        let mut kept = Vec::with_capacity(mailboxes.len());
+        for mb in mailboxes.drain(..) {
+            if !self.is_suppressed(&mb)? {
+                kept.push(mb);
+            }
+        }
+        *mailboxes = kept;
+        Ok(())
+    }
+
+    fn write(&self, entry: SendLogEntry) {
+        if let Err(e) = self.log.record(&entry) {
+            warn!(
+                "email send log write failed for {}: {e:#}",
+                entry.message_id
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl EmailSender for AuditedEmailSender {
+    async fn send(&self, mut email: Email) -> Result<()> {
+        let template = take_header(&mut email, TEMPLATE_HEADER);
+        let message_id = Uuid::now_v7().to_string();
+        let mut entry = SendLogEntry {
+            message_id: message_id.clone(),
+            recipients_hash: recipients_hash(&email),
+            template,
+            status: SendStatus::Sent,
+            error: None,
+            created_at: Utc::now().naive_utc(),
+        };
+
+        let had_to = !email.to.is_empty();
+        self.retain_allowed(&mut email.to)?;
+        self.retain_allowed(&mut email.cc)?;
+        self.retain_allowed(&mut email.bcc)?;
+
+        let nobody_left = email.to.is_empty() && email.cc.is_empty() && email.bcc.is_empty();
+        if (had_to && email.to.is_empty()) || nobody_left {
+            info!("all recipients suppressed; message {message_id} skipped");
+            entry.status = SendStatus::Suppressed;
+            self.write(entry);
+            return Ok(());
+        }
+
+        email
+            .headers
+            .push(EmailHeader::new(MESSAGE_ID_HEADER, message_id));
+        let result = self.inner.send(email).await;
+        if let Err(e) = &result {
+            entry.status = SendStatus::Failed;
+            entry.error = Some(format!("{e:#}"));
+        }
+        self.write(entry);
+        result
+    }
+}
+
+/// Removes every `name` header (case-insensitive), returning the last value.
+fn take_header(email: &mut Email, name: &str) -> Option<String> {
+    let mut value = None;
+    email.headers.retain(|h| {
+        if h.name.eq_ignore_ascii_case(name) {
+            value = Some(h.value.clone());
+            false
+        } else {
+            true
+        }
+    });
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    use crate::db::port::Value;
+    use crate::notification::email::EmailBody;
+
+    #[derive(Default)]
+    struct MemoryLog {
+        entries: Mutex<Vec<SendLogEntry>>,
+    }
+
+    impl SendLog for MemoryLog {
+        fn record(&self, entry: &SendLogEntry) -> Result<()> {
+            self.entries.lock().unwrap().push(entry.clone());
+            Ok(())
+        }
+
+        fn by_message_id(&self, message_id: &str) -> Result<Option<SendLogEntry>> {
+            Ok(self
+                .entries
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|e| e.message_id == message_id)
+                .cloned())
+        }
+
+        fn by_recipient(&self, email: &str, _limit: u64) -> Result<Vec<SendLogEntry>> {
+            let hash = hash_addresses(&[email]);
+            Ok(self
+                .entries
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|e| e.recipients_hash == hash)
+                .cloned()
+                .collect())
+        }
+    }
+
+    #[derive(Default)]
+    struct MemorySuppression {
+        entries: Mutex<HashSet<(String, String)>>,
+    }
+
+    impl SuppressionList for MemorySuppression {
+        fn is_suppressed(&self, email: &str, list_id: &str) -> Result<bool> {
+            Ok(self
+                .entries
+                .lock()
+                .unwrap()
+                .contains(&(email.to_ascii_lowercase(), list_id.to_string())))
+        }
+
+        fn suppress(&self, email: &str, list_id: &str) -> Result<()> {
+            self.entries
+                .lock()
+                .unwrap()
+                .insert((email.to_ascii_lowercase(), list_id.to_string()));
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingSender {
+        sent: Mutex<Vec<Email>>,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl EmailSender for RecordingSender {
+        async fn send(&self, email: Email) -> Result<()> {
+            if self.fail {
+                bail!("smtp 451");
+            }
+            self.sent.lock().unwrap().push(email);
+            Ok(())
+        }
+    }
+
+    fn email_to(to: &[&str]) -> Email {
+        Email {
+            subject: "Hi".into(),
+            body: EmailBody::Text("Body".into()),
+            to: to.iter().map(|a| a.parse().unwrap()).collect(),
+            cc: vec![],
+            bcc: vec![],
+            headers: vec![EmailHeader::new(TEMPLATE_HEADER, "welcome")],
+        }
+    }
+
+    #[tokio::test]
+    async fn logs_sent_messages_and_tags_them() {
+        let inner = Arc::new(RecordingSender::default());
+        let log = Arc::new(MemoryLog::default());
+        let sender = AuditedEmailSender::new(inner.clone(), log.clone());
+
+        sender.send(email_to(&["A@example.com"])).await.unwrap();
+
+        let entries = log.by_recipient("a@example.com", 10).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].status, SendStatus::Sent);
+        assert_eq!(entries[0].template.as_deref(), Some("welcome"));
+
+        let sent = inner.sent.lock().unwrap();
+        let headers: Vec<&str> = sent[0].headers.iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(headers, vec![MESSAGE_ID_HEADER]);
+        assert_eq!(sent[0].headers[0].value, entries[0].message_id);
+    }
+
+    #[tokio::test]
+    async fn logs_transport_failures_and_returns_the_error() {
+        let inner = Arc::new(RecordingSender {
+            fail: true,
+            ..Default::default()
+        });
+        let log = Arc::new(MemoryLog::default());
+        let sender = AuditedEmailSender::new(inner, log.clone());
+
+        assert!(sender.send(email_to(&["a@example.com"])).await.is_err());
+
+        let entries = log.entries.lock().unwrap();
+        assert_eq!(entries[0].status, SendStatus::Failed);
+        assert_eq!(entries[0].error.as_deref(), Some("smtp 451"));
+    }
+
+    #[tokio::test]
+    async fn skips_hard_bounced_and_unsubscribed_recipients() {
+        let inner = Arc::new(RecordingSender::default());
+        let log = Arc::new(MemoryLog::default());
+        let list = Arc::new(MemorySuppression::default());
+        record_hard_bounce(list.as_ref(), "bounced@example.com").unwrap();
+        list.suppress("gone@example.com", "newsletter").unwrap();
+
+        let sender = AuditedEmailSender::new(inner.clone(), log.clone())
+            .suppression(list, Some("newsletter"));
+
+        sender
+            .send(email_to(&["bounced@example.com", "ok@example.com"]))
+            .await
+            .unwrap();
+        sender.send(email_to(&["gone@example.com"])).await.unwrap();
+
+        let sent = inner.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to.len(), 1);
+        assert_eq!(sent[0].to[0].email.to_string(), "ok@example.com");
+
+        let suppressed = log.by_recipient("gone@example.com", 10).unwrap();
+        assert_eq!(suppressed[0].status, SendStatus::Suppressed);
+    }
+
+    #[test]
+    fn recipients_hash_ignores_order_and_case() {
+        let a = email_to(&["a@example.com", "B@example.com"]);
+        let b = email_to(&["b@example.com", "a@example.com"]);
+        assert_eq!(recipients_hash(&a), recipients_hash(&b));
+        assert_eq!(recipients_hash(&a).len(), 64);
+        assert_ne!(
+            recipients_hash(&a),
+            recipients_hash(&email_to(&["a@example.com"]))
+        );
+    }
+
+    struct RowDb {
+        rows: Vec<Row>,
+        execs: Mutex<Vec<String>>,
+    }
+
+    impl Db for RowDb {
+        fn fetch_one(&self, _: &str, _: &[Param]) -> Result<Option<Row>> {
+            Ok(self.rows.first().cloned())
+        }
+
+        fn fetch_all(&self, _: &str, _: &[Param]) -> Result<Vec<Row>> {
+            Ok(self.rows.clone())
+        }
+
+        fn exec(&self, sql: &str, _: &[Param]) -> Result<u64> {
+            self.execs.lock().unwrap().push(sql.to_string());
+            Ok(1)
+        }
+
+        fn exec_returning_last_insert_id(&self, _: &str, _: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn db_send_log_writes_and_reads_rows() {
+        let created_at = chrono::NaiveDate::from_ymd_opt(2026, 3, 1)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        let mut row = Row::default();
+        row.insert("message_id", Value::Str("m-1".into()));
+        row.insert("recipients_hash", Value::Str("h".into()));
+        row.insert("template", Value::Null);
+        row.insert("status", Value::Str("suppressed".into()));
+        row.insert("error", Value::Null);
+        row.insert("created_at", Value::DateTime(created_at));
+
+        let db = Arc::new(RowDb {
+            rows: vec![row],
+            execs: Mutex::new(vec![]),
+        });
+        let log = DbSendLog::with_table(db.clone(), "mail_log").unwrap();
+
+        let entry = log.by_message_id("m-1").unwrap().unwrap();
+        assert_eq!(entry.status, SendStatus::Suppressed);
+        assert_eq!(entry.template, None);
+        assert_eq!(entry.created_at, created_at);
+
+        log.record(&entry).unwrap();
+        assert!(db.execs.lock().unwrap()[0].starts_with("INSERT INTO mail_log (message_id"));
+
+        assert!(DbSendLog::with_table(db, "mail log").is_err());
+    }
+}
@@ -1 +1,2 @@
+pub mod circuit_breaker;
 pub mod smtp_email_sender;
@@ -0,0 +1,186 @@
+//! # Circuit Breaker
+//!
+//! [`CircuitBreaker`] stops calling a failing dependency (here: the SMTP
+//! relay) so callers fail fast instead of each waiting for a timeout.
+//!
+//! - **Closed** — calls pass; `threshold` consecutive failures open it.
+//! - **Open** — calls are rejected until `cooldown` has elapsed.
+//! - **Half-open** — one probe call is let through; success closes the
+//!   breaker, failure opens it for another `cooldown`. A probe that never
+//!   reports back (e.g. its future was dropped) frees the slot after
+//!   `cooldown`.
+//!
+//! A `threshold` of `0` disables the breaker.
+//!
+//! # Example
+//! ```rust
+//! use std::time::Duration;
+//! use wzs_web::notification::smtp::circuit_breaker::{BreakerState, CircuitBreaker};
+//!
+//! let breaker = CircuitBreaker::new(2, Duration::from_secs(30));
+//! for _ in 0..2 {
+//!     assert!(breaker.try_acquire());
+//!     breaker.on_failure();
+//! }
+//! assert_eq!(breaker.state(), BreakerState::Open);
+//! assert!(!breaker.try_acquire());
+//! ```
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+
+/// Observable state of a [`CircuitBreaker`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BreakerState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug)]
+enum Inner {
+    Closed { failures: u32 },
+    Open { since: Instant },
+    HalfOpen { probe_since: Instant },
+}
+
+/// Consecutive-failure circuit breaker.
+///
+/// Cloning is cheap; all clones share the same state.
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl CircuitBreaker {
+    /// Opens after `threshold` consecutive failures and stays open for `cooldown`.
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            inner: Arc::new(Mutex::new(Inner::Closed { failures: 0 })),
+        }
+    }
+
+    /// Current state (an expired open state reports as half-open).
+    pub fn state(&self) -> BreakerState {
+        match *self.inner.lock().unwrap() {
+            Inner::Closed { .. } => BreakerState::Closed,
+            Inner::Open { since } if since.elapsed() >= self.cooldown => BreakerState::HalfOpen,
+            Inner::Open { .. } => BreakerState::Open,
+            Inner::HalfOpen { .. } => BreakerState::HalfOpen,
+        }
+    }
+
+    /// Returns whether a call may proceed.
+    ///
+    /// Every admitted call must be followed by [`on_success`](Self::on_success)
+    /// or [`on_failure`](Self::on_failure).
+    pub fn try_acquire(&self) -> bool {
+        if self.threshold == 0 {
+            return true;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        match *inner {
+            Inner::Closed { .. } => true,
+            Inner::Open { since } | Inner::HalfOpen { probe_since: since }
+                if since.elapsed() >= self.cooldown =>
+            {
+                *inner = Inner::HalfOpen {
+                    probe_since: Instant::now(),
+                };
+                true
+            }
+            Inner::Open { .. } | Inner::HalfOpen { .. } => false,
+        }
+    }
+
+    /// Records a successful call; closes the breaker.
+    pub fn on_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if matches!(*inner, Inner::HalfOpen { .. }) {
+            info!("circuit breaker closed after successful probe");
+        }
+        *inner = Inner::Closed { failures: 0 };
+    }
+
+    /// Records a failed call; opens the breaker at the threshold or when a
+    /// half-open probe fails.
+    pub fn on_failure(&self) {
+        if self.threshold == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        let failures = match *inner {
+            Inner::Closed { failures } => failures.saturating_add(1),
+            Inner::HalfOpen { .. } => self.threshold,
+            Inner::Open { .. } => return,
+        };
+        if failures >= self.threshold {
+            warn!(
+                failures,
+                cooldown_ms = self.cooldown.as_millis() as u64,
+                "circuit breaker opened"
+            );
+            *inner = Inner::Open {
+                since: Instant::now(),
+            };
+        } else {
+            *inner = Inner::Closed { failures };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_after_consecutive_failures_only() {
+        let b = CircuitBreaker::new(3, Duration::from_secs(60));
+        b.on_failure();
+        b.on_failure();
+        b.on_success();
+        b.on_failure();
+        b.on_failure();
+        assert_eq!(b.state(), BreakerState::Closed);
+
+        b.on_failure();
+        assert_eq!(b.state(), BreakerState::Open);
+        assert!(!b.try_acquire());
+    }
+
+    #[test]
+    fn half_open_admits_a_single_probe() {
+        let b = CircuitBreaker::new(1, Duration::ZERO);
+        b.on_failure();
+        assert_eq!(b.state(), BreakerState::HalfOpen);
+
+        assert!(b.try_acquire());
+        b.on_success();
+        assert_eq!(b.state(), BreakerState::Closed);
+
+        let b = CircuitBreaker::new(1, Duration::from_millis(20));
+        b.on_failure();
+        assert!(!b.try_acquire());
+        std::thread::sleep(Duration::from_millis(25));
+        assert!(b.try_acquire());
+        assert!(!b.try_acquire(), "second caller must wait for the probe");
+        b.on_failure();
+        assert_eq!(b.state(), BreakerState::Open);
+    }
+
+    #[test]
+    fn zero_threshold_disables_the_breaker() {
+        let b = CircuitBreaker::new(0, Duration::from_secs(60));
+        for _ in 0..10 {
+            assert!(b.try_acquire());
+            b.on_failure();
+        }
+        assert_eq!(b.state(), BreakerState::Closed);
+    }
+}
@@ -1,13 +1,19 @@
-use anyhow::{Context, Result};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
+use lettre::message::header::{HeaderName, HeaderValue};
 use lettre::message::{Attachment as LettreAttachment, Mailbox, Message, MultiPart, SinglePart};
 use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::AsyncSmtpTransportBuilder;
 use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
-use tracing::info;
+use tracing::{info, warn};
 
+use crate::config::mail::SmtpResilienceConfig;
 use crate::notification::{
     email::{Email, EmailBody},
     email_sender::EmailSender,
+    smtp::circuit_breaker::{BreakerState, CircuitBreaker},
 };
 
 /// SMTP-based implementation of [`EmailSender`].
@@ -16,6 +22,10 @@ use crate::notification::{
 ///
 /// - Builds a MIME-compliant email message from [`Email`]
 /// - Sends the message via SMTP using STARTTLS
+/// - Bounds each send by a connect and an overall timeout, and fails fast
+///   through a [`CircuitBreaker`] while the relay keeps failing
+///   (see [`SmtpResilienceConfig`]; defaults apply unless
+///   [`resilience`](Self::resilience) is called)
 ///
 /// ## Assumptions
 ///
@@ -31,9 +41,12 @@ use crate::notification::{
 /// Those concerns belong to higher layers.
 #[derive(Clone, Debug)]
 pub struct SmtpEmailSender {
+    relay: AsyncSmtpTransportBuilder,
     mailer: AsyncSmtpTransport<Tokio1Executor>,
     from: Mailbox,
     default_to: Vec<Mailbox>,
+    send_timeout: Duration,
+    breaker: CircuitBreaker,
 }
 
 impl SmtpEmailSender {
@@ -68,21 +81,61 @@ impl SmtpEmailSender {
 
         let creds = Credentials::new(username.to_string(), password.to_string());
 
-        let mailer = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(smtp_host)
+        let relay = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(smtp_host)
             .with_context(|| format!("invalid relay host: {smtp_host}"))?
             .port(smtp_port)
-            .credentials(creds)
-            .build();
+            .credentials(creds);
 
         let from = Mailbox::new(Some(from_name.to_string()), from_email.parse()?);
 
+        let defaults = SmtpResilienceConfig::default();
         Ok(Self {
-            mailer,
+            mailer: relay
+                .clone()
+                .timeout(Some(defaults.connect_timeout))
+                .build(),
+            relay,
             from,
             default_to,
+            send_timeout: defaults.send_timeout,
+            breaker: CircuitBreaker::new(defaults.breaker_threshold, defaults.breaker_cooldown),
         })
     }
 
+    /// Applies timeouts and circuit breaker settings.
+    ///
+    /// Replaces the breaker, so failure history is reset. Clones made
+    /// afterwards share the new breaker.
+    pub fn resilience(mut self, cfg: &SmtpResilienceConfig) -> Self {
+        self.mailer = self
+            .relay
+            .clone()
+            .timeout(Some(cfg.connect_timeout))
+            .build();
+        self.send_timeout = cfg.send_timeout;
+        self.breaker = CircuitBreaker::new(cfg.breaker_threshold, cfg.breaker_cooldown);
+        self
+    }
+
+    /// Current state of the circuit breaker (e.g. for health checks).
+    pub fn breaker_state(&self) -> BreakerState {
+        self.breaker.state()
+    }
+
+    /// Connects to the relay and checks that it answers, bounded by the send
+    /// timeout (e.g. for readiness probes). Does not touch the breaker.
+    pub async fn test_connection(&self) -> Result<()> {
+        match tokio::time::timeout(self.send_timeout, self.mailer.test_connection()).await {
+            Ok(Ok(true)) => Ok(()),
+            Ok(Ok(false)) => Err(anyhow!("SMTP relay did not answer")),
+            Ok(Err(e)) => Err(e).context("SMTP connection failed"),
+            Err(_) => Err(anyhow!(
+                "SMTP connection timed out after {} ms",
+                self.send_timeout.as_millis()
+            )),
+        }
+    }
+
     /// Builds a `lettre::Message` from an [`Email`].
     ///
     /// This method contains all MIME construction logic and is kept
@@ -113,6 +166,19 @@ impl SmtpEmailSender {
             builder = builder.bcc(bcc);
         }
 
+        // Custom headers (sanitized the same way as the subject); the
+        // reserved ones would override the fields set above
+        for header in email.headers {
+            if header.is_reserved() {
+                anyhow::bail!("reserved header: {}", header.name);
+            }
+            let name = HeaderName::new_from_ascii(header.name.clone())
+                .map_err(|_| anyhow::anyhow!("invalid header name: {}", header.name))?;
+            let mut value = header.value;
+            value.retain(|c| c != '\r' && c != '\n');
+            builder = builder.raw_header(HeaderValue::new(name, value));
+        }
+
         let message = match email.body {
             EmailBody::Text(text) => builder.singlepart(SinglePart::plain(text))?,
 
@@ -158,11 +224,26 @@ impl SmtpEmailSender {
 impl EmailSender for SmtpEmailSender {
     async fn send(&self, email: Email) -> Result<()> {
         let message = self.build_message(email)?;
-        self.mailer
-            .send(message)
-            .await
-            .context("SMTP send failed")?;
-        Ok(())
+        if !self.breaker.try_acquire() {
+            return Err(anyhow!("SMTP circuit breaker open; send rejected"));
+        }
+
+        let result = match tokio::time::timeout(self.send_timeout, self.mailer.send(message)).await
+        {
+            Ok(sent) => sent.map(|_| ()).context("SMTP send failed"),
+            Err(_) => Err(anyhow!(
+                "SMTP send timed out after {} ms",
+                self.send_timeout.as_millis()
+            )),
+        };
+        match &result {
+            Ok(()) => self.breaker.on_success(),
+            Err(e) => {
+                warn!("SMTP send failed: {e:#}");
+                self.breaker.on_failure();
+            }
+        }
+        result
     }
 }
 
@@ -171,6 +252,8 @@ mod tests {
     use super::*;
     use lettre::message::header::ContentType;
 
+    use crate::notification::email::EmailHeader;
+
     fn mb(addr: &str) -> Mailbox {
         addr.parse::<Mailbox>().expect("valid mailbox")
     }
@@ -198,6 +281,7 @@ mod tests {
             to: vec![],
             cc: vec![],
             bcc: vec![],
+            headers: vec![],
         };
 
         let msg = sender.build_message(email).expect("message build");
@@ -219,6 +303,7 @@ mod tests {
             to: vec![mb("to@example.com")],
             cc: vec![],
             bcc: vec![],
+            headers: vec![],
         };
 
         let msg = sender.build_message(email).expect("message build");
@@ -242,6 +327,7 @@ mod tests {
             to: vec![mb("to@example.com")],
             cc: vec![],
             bcc: vec![],
+            headers: vec![],
         };
 
         let msg = sender.build_message(email).unwrap();
@@ -272,6 +358,7 @@ mod tests {
             to: vec![mb("to@example.com")],
             cc: vec![],
             bcc: vec![],
+            headers: vec![],
         };
 
         let msg = sender.build_message(email).unwrap();
@@ -282,4 +369,113 @@ mod tests {
         assert!(raw.contains("file.txt"));
         assert!(raw.contains("hello"));
     }
+
+    #[test]
+    fn builds_message_with_sanitized_custom_headers() {
+        let sender = test_sender();
+
+        let email = Email {
+            subject: "Headers".into(),
+            body: EmailBody::Text("Body".into()),
+            to: vec![mb("to@example.com")],
+            cc: vec![],
+            bcc: vec![],
+            headers: vec![EmailHeader::new(
+                "List-Unsubscribe",
+                "<https://example.com/u>\r\nBcc: evil@example.com",
+            )],
+        };
+
+        let msg = sender.build_message(email).unwrap();
+        let formatted = msg.formatted();
+        let raw = String::from_utf8_lossy(&formatted);
+
+        assert!(raw.contains("List-Unsubscribe: <https://example.com/u>Bcc: evil@example.com"));
+        assert!(!raw.contains("\r\nBcc: evil@example.com"));
+    }
+
+    #[test]
+    fn rejects_invalid_custom_header_name() {
+        let sender = test_sender();
+
+        let email = Email {
+            subject: "Headers".into(),
+            body: EmailBody::Text("Body".into()),
+            to: vec![mb("to@example.com")],
+            cc: vec![],
+            bcc: vec![],
+            headers: vec![EmailHeader::new("Bad Header", "x")],
+        };
+
+        assert!(sender.build_message(email).is_err());
+    }
+
+    #[test]
+    fn rejects_reserved_custom_headers() {
+        let sender = test_sender();
+
+        for name in [
+            "From",
+            "bcc",
+            "Subject",
+            "content-type",
+            "Sender",
+            "return-path",
+            "Message-ID",
+            "Date",
+            "MIME-Version",
+            "Content-Transfer-Encoding",
+        ] {
+            let email = Email {
+                subject: "Headers".into(),
+                body: EmailBody::Text("Body".into()),
+                to: vec![mb("to@example.com")],
+                cc: vec![],
+                bcc: vec![],
+                headers: vec![EmailHeader::new(name, "evil@example.com")],
+            };
+
+            let err = sender.build_message(email).unwrap_err();
+            assert!(err.to_string().contains("reserved header"), "{name}: {err}");
+        }
+    }
+
+    #[tokio::test]
+    async fn hung_relay_times_out_and_opens_the_breaker() {
+        // Accepts TCP connections (via the backlog) but never sends a greeting.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let sender = SmtpEmailSender::new(
+            "127.0.0.1",
+            port,
+            "user",
+            "pass",
+            "from@example.com",
+            "Sender",
+            vec![mb("default@example.com")],
+        )
+        .unwrap()
+        .resilience(&SmtpResilienceConfig {
+            connect_timeout: Duration::from_secs(5),
+            send_timeout: Duration::from_millis(100),
+            breaker_threshold: 1,
+            breaker_cooldown: Duration::from_secs(60),
+        });
+        let email = || Email {
+            subject: "Test".into(),
+            body: EmailBody::Text("Body".into()),
+            to: vec![],
+            cc: vec![],
+            bcc: vec![],
+            headers: vec![],
+        };
+
+        let err = sender.send(email()).await.unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{err:#}");
+        assert_eq!(sender.breaker_state(), BreakerState::Open);
+
+        let err = sender.clone().send(email()).await.unwrap_err();
+        assert!(err.to_string().contains("circuit breaker open"), "{err:#}");
+    }
 }
@@ -0,0 +1,10 @@
+//! # Email Templates
+//!
+//! - [`registry`] — [`RenderedEmail`](registry::RenderedEmail) and the
+//!   [`EmailTemplateRegistry`](registry::EmailTemplateRegistry) of templates
+//!   paired with sample contexts
+//! - [`lint`] — renders every registered template, reports unreplaced
+//!   placeholders / missing text alternatives and writes HTML snapshots
+
+pub mod lint;
+pub mod registry;
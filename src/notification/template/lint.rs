@@ -0,0 +1,322 @@
+//! Lints every registered email template.
+//!
+//! Intended for a test in the application crate, so broken templates fail CI
+//! instead of reaching users:
+//!
+//! - render errors
+//! - empty subjects
+//! - unreplaced placeholders (`{{ ... }}`, `{% ... %}`, `{# ... #}`)
+//! - HTML bodies without a plain text alternative
+//!
+//! [`write_snapshots`] stores the rendered output for review in diffs.
+//!
+//! # Example
+//! ```rust
+//! use wzs_web::notification::template::lint::lint;
+//! use wzs_web::notification::template::registry::{EmailTemplateRegistry, RenderedEmail};
+//!
+//! let mut registry = EmailTemplateRegistry::new();
+//! registry.register("reset", || {
+//!     Ok(RenderedEmail::new("Reset your password")
+//!         .text("Open https://example.com/reset/abc")
+//!         .html("<a href=\"https://example.com/reset/{{ token }}\">Reset</a>"))
+//! });
+//!
+//! let report = lint(&registry);
+//! assert_eq!(report.issues.len(), 1);
+//! assert!(report.issues[0].to_string().contains("{{ token }}"));
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::notification::template::registry::{EmailTemplateRegistry, RenderedEmail};
+
+/// Delimiters that should never survive rendering.
+const PLACEHOLDER_DELIMITERS: [(&str, &str); 3] = [("{{", "}}"), ("{%", "%}"), ("{#", "#}")];
+
+/// Kind of problem found in a template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintKind {
+    /// The template failed to render.
+    RenderFailed(String),
+    /// The subject is empty.
+    EmptySubject,
+    /// Neither a text nor an HTML body was produced.
+    EmptyBody,
+    /// An HTML body without a non-empty text alternative.
+    MissingTextAlternative,
+    /// A placeholder left in the named part (`subject`, `text`, `html`).
+    UnreplacedPlaceholder { part: &'static str, found: String },
+}
+
+/// A problem found in one template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintIssue {
+    pub template: String,
+    pub kind: LintKind,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let t = &self.template;
+        match &self.kind {
+            LintKind::RenderFailed(e) => write!(f, "{t}: render failed: {e}"),
+            LintKind::EmptySubject => write!(f, "{t}: empty subject"),
+            LintKind::EmptyBody => write!(f, "{t}: empty body"),
+            LintKind::MissingTextAlternative => {
+                write!(f, "{t}: HTML body has no text alternative")
+            }
+            LintKind::UnreplacedPlaceholder { part, found } => {
+                write!(f, "{t}: unreplaced placeholder in {part}: {found}")
+            }
+        }
+    }
+}
+
+/// Result of linting a registry.
+#[derive(Debug, Default)]
+pub struct LintReport {
+    /// Successfully rendered templates, in registration order.
+    pub rendered: Vec<(String, RenderedEmail)>,
+    /// All problems found.
+    pub issues: Vec<LintIssue>,
+}
+
+impl LintReport {
+    /// `true` if no issues were found.
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Panics with every issue listed; for use in tests.
+    pub fn assert_ok(&self) {
+        if !self.is_ok() {
+            let lines: Vec<String> = self.issues.iter().map(|i| format!("  - {i}")).collect();
+            panic!("email template lint failed:\n{}", lines.join("\n"));
+        }
+    }
+}
+
+/// Renders every template in `registry` and checks the output.
+pub fn lint(registry: &EmailTemplateRegistry) -> LintReport {
+    let mut report = LintReport::default();
+
+    for (name, result) in registry.render_all() {
+        let email = match result {
+            Ok(email) => email,
+            Err(e) => {
+                report.issues.push(LintIssue {
+                    template: name.to_string(),
+                    kind: LintKind::RenderFailed(format!("{e:#}")),
+                });
+                continue;
+            }
+        };
+
+        let issue = |kind| LintIssue {
+            template: name.to_string(),
+            kind,
+        };
+
+        if email.subject.trim().is_empty() {
+            report.issues.push(issue(LintKind::EmptySubject));
+        }
+
+        let has_text = email.text.as_deref().is_some_and(|t| !t.trim().is_empty());
+        match (&email.html, has_text) {
+            (None, false) => report.issues.push(issue(LintKind::EmptyBody)),
+            (Some(_), false) => report.issues.push(issue(LintKind::MissingTextAlternative)),
+            _ => {}
+        }
+
+        let parts = [
+            ("subject", Some(email.subject.as_str())),
+            ("text", email.text.as_deref()),
+            ("html", email.html.as_deref()),
+        ];
+        for (part, content) in parts {
+            if let Some(found) = content.and_then(find_placeholder) {
+                report
+                    .issues
+                    .push(issue(LintKind::UnreplacedPlaceholder { part, found }));
+            }
+        }
+
+        report.rendered.push((name.to_string(), email));
+    }
+
+    report
+}
+
+/// Writes `<name>.html` (and `<name>.txt` when present) for each rendered
+/// template into `dir`, returning the written paths.
+///
+/// Text-only templates get an HTML snapshot wrapping the text in `<pre>`.
+pub fn write_snapshots(report: &LintReport, dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir).with_context(|| format!("create_dir_all {:?}", dir))?;
+
+    let mut written = Vec::new();
+    for (name, email) in &report.rendered {
+        let stem = snapshot_stem(name);
+
+        let html = match (&email.html, &email.text) {
+            (Some(html), _) => html.clone(),
+            (None, text) => format!(
+                "<pre>{}</pre>",
+                html_escape(text.as_deref().unwrap_or_default())
+            ),
+        };
+        let page = format!(
+            "<!-- subject: {} -->\n{html}\n",
+            html_escape(&email.subject)
+        );
+        let path = dir.join(format!("{stem}.html"));
+        fs::write(&path, page).with_context(|| format!("write {:?}", &path))?;
+        written.push(path);
+
+        if let Some(text) = &email.text {
+            let path = dir.join(format!("{stem}.txt"));
+            fs::write(&path, text).with_context(|| format!("write {:?}", &path))?;
+            written.push(path);
+        }
+    }
+
+    Ok(written)
+}
+
+/// Returns the first placeholder-looking fragment (truncated) if any.
+fn find_placeholder(s: &str) -> Option<String> {
+    PLACEHOLDER_DELIMITERS.iter().find_map(|(open, close)| {
+        let start = s.find(open)?;
+        let end = s[start..]
+            .find(close)
+            .map(|i| start + i + close.len())
+            .unwrap_or(s.len());
+        Some(s[start..end].chars().take(60).collect())
+    })
+}
+
+/// Maps a template name to a safe file stem.
+fn snapshot_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn registry() -> EmailTemplateRegistry {
+        let mut r = EmailTemplateRegistry::new();
+        r.register("welcome", || {
+            Ok(RenderedEmail::new("Welcome")
+                .text("Hi Alice")
+                .html("<p>Hi Alice</p>"))
+        })
+        .register("html_only", || {
+            Ok(RenderedEmail::new("Hello {{ name }}").html("<p>{% if x %}</p>"))
+        })
+        .register("broken", || Err(anyhow!("missing field")))
+        .register("blank", || Ok(RenderedEmail::new(" ")));
+        r
+    }
+
+    #[test]
+    fn clean_templates_pass() {
+        let mut r = EmailTemplateRegistry::new();
+        r.register("ok", || Ok(RenderedEmail::new("S").text("t")));
+        let report = lint(&r);
+        assert!(report.is_ok());
+        report.assert_ok();
+        assert_eq!(report.rendered.len(), 1);
+    }
+
+    #[test]
+    fn reports_each_problem_kind() {
+        let report = lint(&registry());
+        let kinds: Vec<(&str, &LintKind)> = report
+            .issues
+            .iter()
+            .map(|i| (i.template.as_str(), &i.kind))
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                ("html_only", &LintKind::MissingTextAlternative),
+                (
+                    "html_only",
+                    &LintKind::UnreplacedPlaceholder {
+                        part: "subject",
+                        found: "{{ name }}".into()
+                    }
+                ),
+                (
+                    "html_only",
+                    &LintKind::UnreplacedPlaceholder {
+                        part: "html",
+                        found: "{% if x %}".into()
+                    }
+                ),
+                ("broken", &LintKind::RenderFailed("missing field".into())),
+                ("blank", &LintKind::EmptySubject),
+                ("blank", &LintKind::EmptyBody),
+            ]
+        );
+        assert_eq!(report.rendered.len(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "broken: render failed")]
+    fn assert_ok_panics_with_issue_list() {
+        lint(&registry()).assert_ok();
+    }
+
+    #[test]
+    fn writes_html_and_text_snapshots() -> Result<()> {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("email-snapshots-{stamp}"));
+
+        let mut r = EmailTemplateRegistry::new();
+        r.register("welcome/en", || {
+            Ok(RenderedEmail::new("Hi & bye")
+                .text("a < b")
+                .html("<p>x</p>"))
+        })
+        .register("plain", || Ok(RenderedEmail::new("P").text("1 < 2")));
+
+        let written = write_snapshots(&lint(&r), &dir)?;
+        assert_eq!(written.len(), 4);
+
+        let html = fs::read_to_string(dir.join("welcome_en.html"))?;
+        assert_eq!(html, "<!-- subject: Hi &amp; bye -->\n<p>x</p>\n");
+        assert_eq!(fs::read_to_string(dir.join("welcome_en.txt"))?, "a < b");
+        assert!(fs::read_to_string(dir.join("plain.html"))?.contains("<pre>1 &lt; 2</pre>"));
+
+        let _ = fs::remove_dir_all(&dir);
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+//! Registry of email templates with sample contexts.
+//!
+//! Applications render templates (Askama or otherwise) into a
+//! [`RenderedEmail`], and register each one with a representative sample so
+//! [`lint`](crate::notification::template::lint) can exercise all of them.
+//!
+//! # Example
+//! ```rust
+//! use askama::Template;
+//! use wzs_web::notification::template::registry::{EmailTemplateRegistry, RenderedEmail};
+//!
+//! #[derive(Template)]
+//! #[template(source = "<p>Welcome, {{ name }}!</p>", ext = "html")]
+//! struct WelcomeHtml<'a> { name: &'a str }
+//!
+//! #[derive(Template)]
+//! #[template(source = "Welcome, {{ name }}!", ext = "txt")]
+//! struct WelcomeText<'a> { name: &'a str }
+//!
+//! fn welcome(name: &str) -> anyhow::Result<RenderedEmail> {
+//!     Ok(RenderedEmail::new(format!("Welcome, {name}"))
+//!         .text(WelcomeText { name }.render()?)
+//!         .html(WelcomeHtml { name }.render()?))
+//! }
+//!
+//! let mut registry = EmailTemplateRegistry::new();
+//! registry.register("welcome", || welcome("Alice"));
+//!
+//! let email = registry.render("welcome").unwrap();
+//! assert_eq!(email.text.as_deref(), Some("Welcome, Alice!"));
+//! ```
+
+use anyhow::{anyhow, Result};
+
+use crate::notification::email::EmailBody;
+
+/// Output of rendering an email template.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderedEmail {
+    /// Subject line.
+    pub subject: String,
+    /// Plain text alternative.
+    pub text: Option<String>,
+    /// HTML body.
+    pub html: Option<String>,
+}
+
+impl RenderedEmail {
+    /// Creates a rendered email with only a subject.
+    pub fn new(subject: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the plain text body.
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    /// Sets the HTML body.
+    pub fn html(mut self, html: impl Into<String>) -> Self {
+        self.html = Some(html.into());
+        self
+    }
+
+    /// Converts into an [`EmailBody`].
+    ///
+    /// HTML without a text part yields an empty text alternative.
+    pub fn into_body(self) -> EmailBody {
+        match (self.text, self.html) {
+            (text, Some(html)) => EmailBody::TextAndHtml {
+                text: text.unwrap_or_default(),
+                html,
+            },
+            (text, None) => EmailBody::Text(text.unwrap_or_default()),
+        }
+    }
+}
+
+type Renderer = Box<dyn Fn() -> Result<RenderedEmail> + Send + Sync>;
+
+/// Named templates, each bound to a sample context.
+#[derive(Default)]
+pub struct EmailTemplateRegistry {
+    entries: Vec<(String, Renderer)>,
+}
+
+impl EmailTemplateRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a template; `render` should use a representative sample.
+    ///
+    /// Registering an existing name replaces it.
+    pub fn register<F>(&mut self, name: impl Into<String>, render: F) -> &mut Self
+    where
+        F: Fn() -> Result<RenderedEmail> + Send + Sync + 'static,
+    {
+        let name = name.into();
+        self.entries.retain(|(n, _)| *n != name);
+        self.entries.push((name, Box::new(render)));
+        self
+    }
+
+    /// Registered names in registration order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(n, _)| n.as_str())
+    }
+
+    /// Renders one template with its sample context.
+    pub fn render(&self, name: &str) -> Result<RenderedEmail> {
+        let (_, render) = self
+            .entries
+            .iter()
+            .find(|(n, _)| n == name)
+            .ok_or_else(|| anyhow!("unknown email template: {name}"))?;
+        render()
+    }
+
+    /// Renders every template, in registration order.
+    pub fn render_all(&self) -> Vec<(&str, Result<RenderedEmail>)> {
+        self.entries
+            .iter()
+            .map(|(n, render)| (n.as_str(), render()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_replaces_and_renders_in_order() {
+        let mut r = EmailTemplateRegistry::new();
+        r.register("a", || Ok(RenderedEmail::new("A1")))
+            .register("b", || Ok(RenderedEmail::new("B")))
+            .register("a", || Ok(RenderedEmail::new("A2")));
+
+        assert_eq!(r.names().collect::<Vec<_>>(), vec!["b", "a"]);
+        assert_eq!(r.render("a").unwrap().subject, "A2");
+        assert!(r.render("missing").is_err());
+        assert_eq!(r.render_all().len(), 2);
+    }
+
+    #[test]
+    fn into_body_picks_variant() {
+        let both = RenderedEmail::new("s").text("t").html("<p>h</p>");
+        assert!(matches!(
+            both.into_body(),
+            EmailBody::TextAndHtml { text, html } if text == "t" && html == "<p>h</p>"
+        ));
+
+        let text = RenderedEmail::new("s").text("only");
+        assert!(matches!(text.into_body(), EmailBody::Text(t) if t == "only"));
+    }
+}
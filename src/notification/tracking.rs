@@ -0,0 +1,478 @@
+//! # Email Open / Click Tracking
+//!
+//! Optional measurement for transactional email:
+//!
+//! - [`EmailTracker::apply`] — rewrites the `http(s)` links of a
+//!   [`RenderedEmail`]'s HTML part to a signed click redirect and appends a
+//!   1×1 tracking pixel
+//! - [`open_handler`] — serves the pixel and records an `email.open` event
+//! - [`click_handler`] — records an `email.click` event and redirects to
+//!   the original link
+//!
+//! Events go to the `Arc<dyn AuditStore>` extension, with the message id as
+//! the entity (`entity_type = "email"`) and, for clicks, the target URL as
+//! the detail.
+//!
+//! Tracking is privacy-gated by [`EmailTrackingConfig`]: opens and clicks
+//! are enabled separately, and the recipient address is only embedded in
+//! links (and recorded as the event actor) with `identify_recipients`.
+//! With everything off, [`apply`](EmailTracker::apply) leaves emails
+//! untouched. Links already delivered keep redirecting after clicks are
+//! turned off; they are just no longer recorded.
+//!
+//! Opens are a lower bound at best and an overcount at worst: clients block
+//! remote images, and privacy proxies fetch every image on delivery.
+//!
+//! Tokens follow the shape of unsubscribe tokens (`v1.<payload_b64>.<mac_b64>`,
+//! HMAC-SHA256), so only links this tracker generated redirect anywhere.
+//!
+//! # Example
+//! ```rust
+//! use wzs_web::config::csrf::derive_secret_from_string;
+//! use wzs_web::config::mail::EmailTrackingConfig;
+//! use wzs_web::notification::template::registry::RenderedEmail;
+//! use wzs_web::notification::tracking::EmailTracker;
+//!
+//! let tracker = EmailTracker::new(
+//!     derive_secret_from_string("tracking-secret"),
+//!     "https://example.com/t",
+//!     EmailTrackingConfig { opens: true, clicks: true, identify_recipients: false },
+//! );
+//!
+//! let email = RenderedEmail::new("Your order shipped")
+//!     .html(r#"<p><a href="https://example.com/orders/42">Track it</a></p>"#);
+//! let tracked = tracker.apply(email, "order-42-shipped", "user@example.com");
+//!
+//! let html = tracked.html.unwrap();
+//! assert!(html.contains(r#"href="https://example.com/t/click?t=v1."#));
+//! assert!(html.contains(r#"src="https://example.com/t/open?t=v1."#));
+//! ```
+
+use std::sync::Arc;
+
+use axum::{
+    extract::Query,
+    http::header,
+    response::{IntoResponse, Redirect, Response},
+    Extension,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+
+use crate::audit::context::AuditContext;
+use crate::audit::event::{actions, AuditEvent};
+use crate::audit::store::AuditStore;
+use crate::config::mail::EmailTrackingConfig;
+use crate::error::app::AppError;
+use crate::notification::template::registry::RenderedEmail;
+
+/// Transparent 1×1 GIF served by [`open_handler`].
+pub const PIXEL_GIF: &[u8] = &[
+    0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0xff, 0x21, 0xf9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3b,
+];
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Kind of tracked interaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrackingKind {
+    Open,
+    Click,
+}
+
+impl TrackingKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Click => "click",
+        }
+    }
+}
+
+/// A verified tracking token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrackedLink {
+    pub kind: TrackingKind,
+    /// Application-chosen id of the sent message (or campaign).
+    pub message_id: String,
+    /// Recipient address, when recipients are identified.
+    pub recipient: Option<String>,
+    /// Original link target (clicks only).
+    pub target: Option<String>,
+}
+
+/// Generates tracking pixels and click links and verifies their tokens.
+#[derive(Clone)]
+pub struct EmailTracker {
+    secret: [u8; 32],
+    base_url: String,
+    cfg: EmailTrackingConfig,
+}
+
+impl EmailTracker {
+    /// Creates a tracker.
+    ///
+    /// - `secret`: 32-byte HMAC key; rotating it breaks delivered links
+    /// - `base_url`: absolute URL under which `/open` ([`open_handler`]) and
+    ///   `/click` ([`click_handler`]) are mounted
+    pub fn new(secret: [u8; 32], base_url: impl Into<String>, cfg: EmailTrackingConfig) -> Self {
+        Self {
+            secret,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            cfg,
+        }
+    }
+
+    /// The privacy switches in effect.
+    pub fn config(&self) -> &EmailTrackingConfig {
+        &self.cfg
+    }
+
+    /// Tracking pixel `<img>` tag, or `None` when open tracking is off.
+    pub fn open_pixel(&self, message_id: &str, recipient: &str) -> Option<String> {
+        if !self.cfg.opens {
+            return None;
+        }
+        let token = self.token(TrackingKind::Open, message_id, recipient, "");
+        Some(format!(
+            r#"<img src="{}/open?t={token}" width="1" height="1" alt="" style="display:none">"#,
+            self.base_url
+        ))
+    }
+
+    /// Click redirect URL for `target`; `target` itself when click tracking
+    /// is off.
+    pub fn click_url(&self, message_id: &str, recipient: &str, target: &str) -> String {
+        if !self.cfg.clicks {
+            return target.to_string();
+        }
+        let token = self.token(TrackingKind::Click, message_id, recipient, target);
+        format!("{}/click?t={token}", self.base_url)
+    }
+
+    /// Adds tracking to the HTML part of `email`.
+    ///
+    /// `http(s)` links in `href="…"` attributes become click links and the
+    /// pixel goes before `</body>` (or at the end). The text part is left
+    /// alone so plain-text readers see the real URLs.
+    pub fn apply(
+        &self,
+        mut email: RenderedEmail,
+        message_id: &str,
+        recipient: &str,
+    ) -> RenderedEmail {
+        let Some(html) = email.html.take() else {
+            return email;
+        };
+        let mut html = if self.cfg.clicks {
+            self.rewrite_links(&html, message_id, recipient)
+        } else {
+            html
+        };
+        if let Some(pixel) = self.open_pixel(message_id, recipient) {
+            match html.rfind("</body>") {
+                Some(at) => html.insert_str(at, &pixel),
+                None => html.push_str(&pixel),
+            }
+        }
+        email.html = Some(html);
+        email
+    }
+
+    /// Verifies a token and returns what it tracks.
+    ///
+    /// Returns `None` if the token is malformed or the signature does not match.
+    pub fn verify(&self, token: &str) -> Option<TrackedLink> {
+        let mut parts = token.split('.');
+        let (Some("v1"), Some(payload_b64), Some(mac_b64), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return None;
+        };
+        let payload = URL_SAFE_NO_PAD.decode(payload_b64).ok()?;
+        let mac = URL_SAFE_NO_PAD.decode(mac_b64).ok()?;
+        if self.mac(&payload).as_slice().ct_eq(&mac).unwrap_u8() != 1 {
+            return None;
+        }
+
+        let payload = String::from_utf8(payload).ok()?;
+        let mut fields = payload.splitn(4, '\n');
+        let kind = match fields.next()? {
+            "open" => TrackingKind::Open,
+            "click" => TrackingKind::Click,
+            _ => return None,
+        };
+        let message_id = fields.next()?.to_string();
+        let non_empty = |s: &str| (!s.is_empty()).then(|| s.to_string());
+        let recipient = non_empty(fields.next()?);
+        let target = non_empty(fields.next()?);
+        Some(TrackedLink {
+            kind,
+            message_id,
+            recipient,
+            target,
+        })
+    }
+
+    fn token(&self, kind: TrackingKind, message_id: &str, recipient: &str, target: &str) -> String {
+        let recipient = if self.cfg.identify_recipients {
+            recipient.trim().to_ascii_lowercase()
+        } else {
+            String::new()
+        };
+        let payload = format!("{}\n{message_id}\n{recipient}\n{target}", kind.as_str());
+        format!(
+            "v1.{}.{}",
+            URL_SAFE_NO_PAD.encode(payload.as_bytes()),
+            URL_SAFE_NO_PAD.encode(self.mac(payload.as_bytes()))
+        )
+    }
+
+    fn rewrite_links(&self, html: &str, message_id: &str, recipient: &str) -> String {
+        let mut out = String::with_capacity(html.len());
+        let mut rest = html;
+        while let Some(at) = rest.find("href=") {
+            let (before, after) = rest.split_at(at + "href=".len());
+            out.push_str(before);
+            rest = after;
+
+            let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+                continue;
+            };
+            let Some(len) = rest[1..].find(quote) else {
+                continue;
+            };
+            let raw = &rest[1..1 + len];
+            let target = raw.replace("&amp;", "&");
+            let lower = target.to_ascii_lowercase();
+            let url = if lower.starts_with("http://") || lower.starts_with("https://") {
+                self.click_url(message_id, recipient, &target)
+            } else {
+                raw.to_string()
+            };
+            out.push(quote);
+            out.push_str(&url);
+            out.push(quote);
+            rest = &rest[len + 2..];
+        }
+        out.push_str(rest);
+        out
+    }
+
+    fn mac(&self, payload: &[u8]) -> Vec<u8> {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC key");
+        mac.update(payload);
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+/// Query string accepted by the tracking handlers.
+#[derive(Debug, Deserialize)]
+pub struct TrackingQuery {
+    /// Signed token produced by [`EmailTracker`].
+    pub t: String,
+}
+
+/// Serves the tracking pixel and records an `email.open` event.
+///
+/// Always answers with the pixel (never cached), so broken or forged
+/// tokens render like valid ones; they are just not recorded.
+///
+/// # Required Extensions
+/// - `EmailTracker`
+/// - `Arc<dyn AuditStore>` (optional; without it nothing is recorded)
+pub async fn open_handler(
+    Extension(tracker): Extension<EmailTracker>,
+    store: Option<Extension<Arc<dyn AuditStore>>>,
+    Query(query): Query<TrackingQuery>,
+) -> Response {
+    let link = tracker
+        .verify(&query.t)
+        .filter(|l| l.kind == TrackingKind::Open && tracker.cfg.opens);
+    if let Some(link) = link {
+        record(store, actions::EMAIL_OPEN, link).await;
+    }
+    (
+        [
+            (header::CONTENT_TYPE, "image/gif"),
+            (header::CACHE_CONTROL, "no-store, max-age=0"),
+        ],
+        PIXEL_GIF,
+    )
+        .into_response()
+}
+
+/// Records an `email.click` event and redirects to the original link.
+///
+/// # Required Extensions
+/// - `EmailTracker`
+/// - `Arc<dyn AuditStore>` (optional; without it nothing is recorded)
+///
+/// # Returns
+/// - `303 SEE OTHER` to the link target
+/// - `400 BAD REQUEST` when the token is invalid (never an open redirect),
+///   as an [`AppError`] problem document
+pub async fn click_handler(
+    Extension(tracker): Extension<EmailTracker>,
+    store: Option<Extension<Arc<dyn AuditStore>>>,
+    Query(query): Query<TrackingQuery>,
+) -> Result<Redirect, AppError> {
+    let (link, target) = tracker
+        .verify(&query.t)
+        .filter(|l| l.kind == TrackingKind::Click)
+        .and_then(|l| l.target.clone().map(|target| (l, target)))
+        .ok_or_else(|| AppError::bad_request("invalid tracking link"))?;
+    if tracker.cfg.clicks {
+        record(store, actions::EMAIL_CLICK, link).await;
+    }
+    Ok(Redirect::to(&target))
+}
+
+async fn record(store: Option<Extension<Arc<dyn AuditStore>>>, action: &str, link: TrackedLink) {
+    let mut event = AuditEvent::new(action).entity("email", link.message_id);
+    if let Some(target) = link.target {
+        event = event.detail(target);
+    }
+    AuditContext::new(store.map(|Extension(s)| s), link.recipient)
+        .emit(event)
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    use crate::audit::store::AuditQuery;
+    use crate::config::csrf::derive_secret_from_string;
+
+    #[derive(Default)]
+    struct Events(Mutex<Vec<AuditEvent>>);
+
+    impl AuditStore for Events {
+        fn record(&self, event: &AuditEvent) -> anyhow::Result<()> {
+            self.0.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+
+        fn query(&self, _query: &AuditQuery) -> anyhow::Result<Vec<AuditEvent>> {
+            Ok(vec![])
+        }
+    }
+
+    fn tracker(cfg: EmailTrackingConfig) -> EmailTracker {
+        EmailTracker::new(derive_secret_from_string("track"), "https://x.test/t/", cfg)
+    }
+
+    const ALL: EmailTrackingConfig = EmailTrackingConfig {
+        opens: true,
+        clicks: true,
+        identify_recipients: true,
+    };
+
+    fn app(tracker: EmailTracker, events: Arc<Events>) -> Router {
+        let store: Arc<dyn AuditStore> = events;
+        Router::new()
+            .route("/t/open", get(open_handler))
+            .route("/t/click", get(click_handler))
+            .layer(Extension(tracker))
+            .layer(Extension(store))
+    }
+
+    fn path(url: &str) -> &str {
+        url.strip_prefix("https://x.test").unwrap()
+    }
+
+    #[test]
+    fn apply_rewrites_http_links_and_adds_pixel() {
+        let email = RenderedEmail::new("Hi")
+            .text("https://a.test")
+            .html(concat!(
+                r#"<body><a href="https://a.test/?x=1&amp;y=2">A</a>"#,
+                r#"<a href='mailto:help@a.test'>Mail</a><a href="/rel">R</a></body>"#,
+            ));
+        let t = tracker(ALL);
+        let out = t.apply(email.clone(), "m1", "User@A.test");
+
+        assert_eq!(out.text.as_deref(), Some("https://a.test"));
+        let html = out.html.unwrap();
+        assert!(html.contains("href='mailto:help@a.test'"));
+        assert!(html.contains(r#"href="/rel""#));
+        assert!(html.ends_with(r#"style="display:none"></body>"#));
+
+        let click = html.split("click?t=").nth(1).unwrap();
+        let link = t.verify(click.split('"').next().unwrap()).unwrap();
+        assert_eq!(link.kind, TrackingKind::Click);
+        assert_eq!(link.message_id, "m1");
+        assert_eq!(link.recipient.as_deref(), Some("user@a.test"));
+        assert_eq!(link.target.as_deref(), Some("https://a.test/?x=1&y=2"));
+
+        let off = tracker(EmailTrackingConfig::default()).apply(email.clone(), "m1", "u@a.test");
+        assert_eq!(off, email);
+    }
+
+    #[test]
+    fn tokens_omit_recipient_unless_identified_and_reject_tampering() {
+        let t = tracker(EmailTrackingConfig {
+            identify_recipients: false,
+            ..ALL
+        });
+        let url = t.click_url("m1", "u@a.test", "https://a.test");
+        let token = url.split("t=").nth(1).unwrap();
+        assert_eq!(t.verify(token).unwrap().recipient, None);
+
+        let mut forged = token.to_string();
+        forged.replace_range(4..6, "AA");
+        assert_eq!(t.verify(&forged), None);
+        assert_eq!(tracker(ALL).verify("v1.a.b.c"), None);
+    }
+
+    #[tokio::test]
+    async fn handlers_record_events_and_redirect() {
+        let events = Arc::new(Events::default());
+        let t = tracker(ALL);
+        let app = app(t.clone(), events.clone());
+        let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+
+        let pixel = t.open_pixel("m1", "u@a.test").unwrap();
+        let src = pixel.split('"').nth(1).unwrap();
+        let res = app.clone().oneshot(get(path(src))).await.unwrap();
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "image/gif");
+
+        let click = t.click_url("m1", "u@a.test", "https://a.test/x");
+        let res = app.clone().oneshot(get(path(&click))).await.unwrap();
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_eq!(res.headers()[header::LOCATION], "https://a.test/x");
+
+        // An open token is not a redirect, and forged pixels are not recorded.
+        let open_as_click = src.replace("/open?", "/click?");
+        let res = app
+            .clone()
+            .oneshot(get(path(&open_as_click)))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let res = app.oneshot(get("/t/open?t=v1.x.y")).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let events = events.0.lock().unwrap();
+        let recorded: Vec<_> = events.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(recorded, [actions::EMAIL_OPEN, actions::EMAIL_CLICK]);
+        assert_eq!(events[1].actor.as_deref(), Some("u@a.test"));
+        assert_eq!(events[1].entity_id.as_deref(), Some("m1"));
+        assert_eq!(events[1].detail.as_deref(), Some("https://a.test/x"));
+    }
+}
@@ -0,0 +1,737 @@
+//! # Email Unsubscribe Links
+//!
+//! Provides the pieces required for compliant bulk mail:
+//!
+//! - [`UnsubscribeLinks`] — generates and verifies HMAC-signed unsubscribe URLs
+//!   and the matching `List-Unsubscribe` / `List-Unsubscribe-Post` headers
+//!   (one-click unsubscribe, RFC 8058)
+//! - [`SuppressionList`] — port for recording and checking opt-outs, with a
+//!   [`DbSuppressionList`] implementation over the [`Db`] port
+//! - [`unsubscribe_handler`] — Axum handler for the one-click `POST`
+//! - [`SuppressingEmailSender`] — [`EmailSender`] decorator that drops
+//!   opted-out recipients before delivery
+//!
+//! Tokens follow the same shape as CSRF tokens:
+//!
+//! ```text
+//! v1.<payload_b64>.<mac_b64>
+//! ```
+//!
+//! where the payload is `<list_id>\n<email>` and the MAC is HMAC-SHA256.
+//!
+//! # Example
+//! ```rust
+//! use wzs_web::config::csrf::derive_secret_from_string;
+//! use wzs_web::notification::unsubscribe::UnsubscribeLinks;
+//!
+//! let links = UnsubscribeLinks::new(
+//!     derive_secret_from_string("unsubscribe-secret"),
+//!     "https://example.com/unsubscribe",
+//! );
+//!
+//! let url = links.url("user@example.com", "newsletter");
+//! let token = url.split("token=").nth(1).unwrap();
+//!
+//! let unsub = links.verify(token).unwrap();
+//! assert_eq!(unsub.email, "user@example.com");
+//! assert_eq!(unsub.list_id, "newsletter");
+//! ```
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use axum::{extract::Query, Extension};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use lettre::message::Mailbox;
+use serde::Deserialize;
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use tracing::info;
+
+use crate::db::port::{Db, Param};
+use crate::db::repository::ident;
+use crate::error::app::AppError;
+use crate::notification::email::{Email, EmailHeader};
+use crate::notification::email_sender::EmailSender;
+
+/// Header carrying the unsubscribe URL.
+pub const LIST_UNSUBSCRIBE_HEADER: &str = "List-Unsubscribe";
+
+/// Header announcing one-click unsubscribe support (RFC 8058).
+pub const LIST_UNSUBSCRIBE_POST_HEADER: &str = "List-Unsubscribe-Post";
+
+/// Required value of the `List-Unsubscribe-Post` header (RFC 8058).
+pub const LIST_UNSUBSCRIBE_POST_VALUE: &str = "List-Unsubscribe=One-Click";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// A verified unsubscribe request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Unsubscription {
+    /// Normalized (lowercase) recipient address.
+    pub email: String,
+    /// Mailing list identifier (e.g. `"newsletter"`).
+    pub list_id: String,
+}
+
+/// Generates and verifies signed unsubscribe URLs.
+///
+/// The secret should be stable across deployments; rotating it invalidates
+/// every unsubscribe link already delivered.
+#[derive(Clone)]
+pub struct UnsubscribeLinks {
+    secret: [u8; 32],
+    base_url: String,
+}
+
+impl UnsubscribeLinks {
+    /// Creates a new link generator.
+    ///
+    /// - `secret`: 32-byte HMAC key (see [`derive_secret_from_string`](crate::config::csrf::derive_secret_from_string))
+    /// - `base_url`: absolute URL where [`unsubscribe_handler`] is mounted
+    pub fn new(secret: [u8; 32], base_url: impl Into<String>) -> Self {
+        Self {
+            secret,
+            base_url: base_url.into(),
+        }
+    }
+
+    /// Returns a signed token for the given recipient and list.
+    pub fn token(&self, email: &str, list_id: &str) -> String {
+        let payload = format!("{}\n{}", list_id, normalize_email(email));
+        let tag = self.mac(payload.as_bytes());
+
+        format!(
+            "v1.{}.{}",
+            URL_SAFE_NO_PAD.encode(payload.as_bytes()),
+            URL_SAFE_NO_PAD.encode(tag)
+        )
+    }
+
+    /// Returns the absolute unsubscribe URL for the given recipient and list.
+    pub fn url(&self, email: &str, list_id: &str) -> String {
+        let sep = if self.base_url.contains('?') {
+            '&'
+        } else {
+            '?'
+        };
+        format!(
+            "{}{}token={}",
+            self.base_url,
+            sep,
+            self.token(email, list_id)
+        )
+    }
+
+    /// Verifies a token and returns the unsubscribe request it encodes.
+    ///
+    /// Returns `None` if the token is malformed or the signature does not match.
+    pub fn verify(&self, token: &str) -> Option<Unsubscription> {
+        let mut parts = token.split('.');
+        let (Some(v), Some(payload_b64), Some(mac_b64)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return None;
+        };
+        if parts.next().is_some() || v != "v1" {
+            return None;
+        }
+
+        let payload = URL_SAFE_NO_PAD.decode(payload_b64).ok()?;
+        let mac = URL_SAFE_NO_PAD.decode(mac_b64).ok()?;
+
+        let expected = self.mac(&payload);
+        if expected.as_slice().ct_eq(&mac).unwrap_u8() != 1 {
+            return None;
+        }
+
+        let payload = String::from_utf8(payload).ok()?;
+        let (list_id, email) = payload.split_once('\n')?;
+
+        Some(Unsubscription {
+            email: email.to_string(),
+            list_id: list_id.to_string(),
+        })
+    }
+
+    /// Returns the `List-Unsubscribe` and `List-Unsubscribe-Post` headers
+    /// for the given recipient and list.
+    pub fn headers(&self, email: &str, list_id: &str) -> Vec<EmailHeader> {
+        vec![
+            EmailHeader::new(
+                LIST_UNSUBSCRIBE_HEADER,
+                format!("<{}>", self.url(email, list_id)),
+            ),
+            EmailHeader::new(LIST_UNSUBSCRIBE_POST_HEADER, LIST_UNSUBSCRIBE_POST_VALUE),
+        ]
+    }
+
+    /// Attaches unsubscribe headers to an email addressed to a single recipient.
+    ///
+    /// Bulk mail should be sent one recipient per message so each message
+    /// carries its own link. Returns `false` (and leaves the email untouched)
+    /// when the email does not have exactly one `To` recipient.
+    pub fn apply(&self, email: &mut Email, list_id: &str) -> bool {
+        let [recipient] = email.to.as_slice() else {
+            return false;
+        };
+        let address = recipient.email.to_string();
+        email.headers.extend(self.headers(&address, list_id));
+        true
+    }
+
+    fn mac(&self, payload: &[u8]) -> Vec<u8> {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC key");
+        mac.update(payload);
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+/// Port for recording and checking email opt-outs.
+pub trait SuppressionList: Send + Sync {
+    /// Returns `true` if the address has opted out of the given list.
+    fn is_suppressed(&self, email: &str, list_id: &str) -> Result<bool>;
+
+    /// Records an opt-out. Recording the same opt-out twice is not an error.
+    fn suppress(&self, email: &str, list_id: &str) -> Result<()>;
+}
+
+/// [`SuppressionList`] stored in a database table via the [`Db`] port.
+///
+/// Expected schema (MySQL):
+///
+/// ```sql
+/// CREATE TABLE email_unsubscribes (
+///     email      VARCHAR(255) NOT NULL,
+///     list_id    VARCHAR(64)  NOT NULL,
+///     created_at DATETIME     NOT NULL,
+///     PRIMARY KEY (email, list_id)
+/// );
+/// ```
+#[derive(Clone)]
+pub struct DbSuppressionList {
+    db: Arc<dyn Db>,
+    table: String,
+}
+
+impl DbSuppressionList {
+    /// Default table name.
+    pub const DEFAULT_TABLE: &'static str = "email_unsubscribes";
+
+    /// Creates a suppression list backed by the default table.
+    pub fn new(db: Arc<dyn Db>) -> Self {
+        Self {
+            db,
+            table: Self::DEFAULT_TABLE.into(),
+        }
+    }
+
+    /// Creates a suppression list backed by a custom table.
+    ///
+    /// # Errors
+    /// Returns an error if `table` is not a plain SQL identifier.
+    pub fn with_table(db: Arc<dyn Db>, table: impl Into<String>) -> Result<Self> {
+        let table = table.into();
+        ident(&table)?;
+        Ok(Self { db, table })
+    }
+}
+
+impl SuppressionList for DbSuppressionList {
+    fn is_suppressed(&self, email: &str, list_id: &str) -> Result<bool> {
+        let email = normalize_email(email);
+        let sql = format!(
+            "SELECT 1 AS found FROM {} WHERE email = ? AND list_id = ? LIMIT 1",
+            self.table
+        );
+        let row = self
+            .db
+            .fetch_one(&sql, &[Param::Str(&email), Param::Str(list_id)])?;
+        Ok(row.is_some())
+    }
+
+    fn suppress(&self, email: &str, list_id: &str) -> Result<()> {
+        let email = normalize_email(email);
+        let sql = format!(
+            "INSERT IGNORE INTO {} (email, list_id, created_at) VALUES (?, ?, ?)",
+            self.table
+        );
+        self.db.exec(
+            &sql,
+            &[
+                Param::Str(&email),
+                Param::Str(list_id),
+                Param::DateTime(Utc::now().naive_utc()),
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+/// Query string accepted by [`unsubscribe_handler`].
+#[derive(Debug, Deserialize)]
+pub struct UnsubscribeQuery {
+    /// Signed token produced by [`UnsubscribeLinks`].
+    pub token: String,
+}
+
+/// One-click unsubscribe endpoint (RFC 8058).
+///
+/// Mount this handler for `POST` at the `base_url` given to
+/// [`UnsubscribeLinks`]. Mail clients send the one-click request as a form
+/// post with `List-Unsubscribe=One-Click`; the token is taken from the URL.
+///
+/// `GET` should **not** be routed here, since link scanners prefetch URLs
+/// found in emails. Serve a confirmation page with a form instead.
+///
+/// # Required Extensions
+/// - `UnsubscribeLinks`
+/// - `Arc<dyn SuppressionList>`
+///
+/// # Returns
+/// - `200 OK` when the opt-out was recorded
+/// - `400 BAD REQUEST` when the token is invalid
+/// - `500 INTERNAL SERVER ERROR` when recording fails
+///
+/// Errors are [`AppError`] problem documents (`application/problem+json`).
+pub async fn unsubscribe_handler(
+    Extension(links): Extension<UnsubscribeLinks>,
+    Extension(list): Extension<Arc<dyn SuppressionList>>,
+    Query(query): Query<UnsubscribeQuery>,
+) -> Result<&'static str, AppError> {
+    let unsub = links
+        .verify(&query.token)
+        .ok_or_else(|| AppError::bad_request("invalid unsubscribe token"))?;
+
+    // The suppression list may query the database, so it runs on the
+    // blocking thread pool.
+    let list_id = tokio::task::spawn_blocking(move || {
+        list.suppress(&unsub.email, &unsub.list_id)
+            .map(|()| unsub.list_id)
+    })
+    .await
+    .map_err(|e| AppError::Internal(anyhow!("unsubscribe task failed: {e}")))?
+    .map_err(|e| AppError::Internal(e.context("recording unsubscribe failed")))?;
+    info!("unsubscribed from list {list_id}");
+    Ok("unsubscribed")
+}
+
+/// [`EmailSender`] decorator that removes opted-out recipients.
+///
+/// Every `To`, `Cc` and `Bcc` recipient is checked against the
+/// [`SuppressionList`] for the configured list. If all `To` recipients are
+/// suppressed, the message is skipped entirely (rather than letting the
+/// transport fall back to its default recipients).
+#[derive(Clone)]
+pub struct SuppressingEmailSender {
+    inner: Arc<dyn EmailSender>,
+    list: Arc<dyn SuppressionList>,
+    list_id: String,
+}
+
+impl SuppressingEmailSender {
+    /// Wraps `inner`, filtering recipients opted out of `list_id`.
+    pub fn new(
+        inner: Arc<dyn EmailSender>,
+        list: Arc<dyn SuppressionList>,
+        list_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            inner,
+            list,
+            list_id: list_id.into(),
+        }
+    }
+}
+
+/// `mailboxes` without those opted out of `list_id`.
+fn retain_allowed(
+    list: &dyn SuppressionList,
+    list_id: &str,
+    mailboxes: Vec<Mailbox>,
+) -> Result<Vec<Mailbox>> {
+    let mut kept = Vec::with_capacity(mailboxes.len());
+    for mb in mailboxes {
+        if !list.is_suppressed(mb.email.as_ref(), list_id)? {
+            kept.push(mb);
+        }
+    }
+    Ok(kept)
+}
+
+#[async_trait]
+impl EmailSender for SuppressingEmailSender {
+    async fn send(&self, mut email: Email) -> Result<()> {
+        let had_to = !email.to.is_empty();
+
+        // The suppression list may query the database, so the checks run on
+        // the blocking thread pool.
+        let (list, list_id) = (self.list.clone(), self.list_id.clone());
+        let recipients = [
+            std::mem::take(&mut email.to),
+            std::mem::take(&mut email.cc),
+            std::mem::take(&mut email.bcc),
+        ];
+        let [to, cc, bcc] = tokio::task::spawn_blocking(move || {
+            recipients.map(|mailboxes| retain_allowed(list.as_ref(), &list_id, mailboxes))
+        })
+        .await
+        .context("suppression check task failed")?;
+        (email.to, email.cc, email.bcc) = (to?, cc?, bcc?);
+
+        let nobody_left = email.to.is_empty() && email.cc.is_empty() && email.bcc.is_empty();
+        if (had_to && email.to.is_empty()) || nobody_left {
+            info!("all recipients suppressed for list {}", self.list_id);
+            return Ok(());
+        }
+
+        self.inner.send(email).await
+    }
+}
+
+/// Normalizes an address for signing and storage.
+fn normalize_email(email: &str) -> String {
+    email.trim().to_ascii_lowercase()
+}
+
+/// Returns `true` if `s` is a plain SQL identifier (`[A-Za-z0-9_]+`).
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    use axum::{
+        body::Body,
+        http::{header::CONTENT_TYPE, Request, StatusCode},
+        routing::post,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    use crate::config::csrf::derive_secret_from_string;
+    use crate::db::port::Row;
+    use crate::notification::email::EmailBody;
+
+    fn links() -> UnsubscribeLinks {
+        UnsubscribeLinks::new(
+            derive_secret_from_string("unsubscribe-test"),
+            "https://example.com/unsubscribe",
+        )
+    }
+
+    fn mb(addr: &str) -> Mailbox {
+        addr.parse::<Mailbox>().expect("valid mailbox")
+    }
+
+    fn email_to(to: &[&str], cc: &[&str]) -> Email {
+        Email {
+            subject: "News".into(),
+            body: EmailBody::Text("Body".into()),
+            to: to.iter().map(|a| mb(a)).collect(),
+            cc: cc.iter().map(|a| mb(a)).collect(),
+            bcc: vec![],
+            headers: vec![],
+        }
+    }
+
+    #[derive(Default)]
+    struct MemorySuppressionList {
+        entries: Mutex<HashSet<(String, String)>>,
+        fail: bool,
+    }
+
+    impl MemorySuppressionList {
+        fn with(entries: &[(&str, &str)]) -> Self {
+            Self {
+                entries: Mutex::new(
+                    entries
+                        .iter()
+                        .map(|(e, l)| (e.to_string(), l.to_string()))
+                        .collect(),
+                ),
+                fail: false,
+            }
+        }
+    }
+
+    impl SuppressionList for MemorySuppressionList {
+        fn is_suppressed(&self, email: &str, list_id: &str) -> Result<bool> {
+            Ok(self
+                .entries
+                .lock()
+                .unwrap()
+                .contains(&(normalize_email(email), list_id.to_string())))
+        }
+
+        fn suppress(&self, email: &str, list_id: &str) -> Result<()> {
+            if self.fail {
+                anyhow::bail!("db down");
+            }
+            self.entries
+                .lock()
+                .unwrap()
+                .insert((normalize_email(email), list_id.to_string()));
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingSender {
+        sent: Mutex<Vec<Email>>,
+    }
+
+    #[async_trait]
+    impl EmailSender for RecordingSender {
+        async fn send(&self, email: Email) -> Result<()> {
+            self.sent.lock().unwrap().push(email);
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingDb {
+        calls: Mutex<Vec<(String, usize)>>,
+        row: Option<Row>,
+    }
+
+    impl Db for RecordingDb {
+        fn fetch_one(&self, sql: &str, params: &[Param]) -> Result<Option<Row>> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push((sql.to_string(), params.len()));
+            Ok(self.row.clone())
+        }
+
+        fn fetch_all(&self, _sql: &str, _params: &[Param]) -> Result<Vec<Row>> {
+            Ok(vec![])
+        }
+
+        fn exec(&self, sql: &str, params: &[Param]) -> Result<u64> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push((sql.to_string(), params.len()));
+            Ok(1)
+        }
+
+        fn exec_returning_last_insert_id(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn token_roundtrip_normalizes_email() {
+        let links = links();
+        let token = links.token(" User@Example.COM ", "newsletter");
+
+        let unsub = links.verify(&token).expect("valid token");
+        assert_eq!(unsub.email, "user@example.com");
+        assert_eq!(unsub.list_id, "newsletter");
+    }
+
+    #[test]
+    fn verify_rejects_tampered_or_foreign_tokens() {
+        let links = links();
+        let token = links.token("user@example.com", "newsletter");
+
+        let mut parts: Vec<&str> = token.split('.').collect();
+        let forged_payload = URL_SAFE_NO_PAD.encode("newsletter\nother@example.com");
+        parts[1] = &forged_payload;
+        assert!(links.verify(&parts.join(".")).is_none());
+
+        let other = UnsubscribeLinks::new(derive_secret_from_string("other"), "https://x");
+        assert!(other.verify(&token).is_none());
+
+        assert!(links.verify("v1.only-two").is_none());
+        assert!(links.verify(&token.replacen("v1", "v2", 1)).is_none());
+    }
+
+    #[test]
+    fn url_appends_token_query_parameter() {
+        let links = links();
+        let url = links.url("user@example.com", "news");
+        assert!(url.starts_with("https://example.com/unsubscribe?token=v1."));
+
+        let with_query = UnsubscribeLinks::new([1u8; 32], "https://example.com/u?site=a");
+        assert!(with_query
+            .url("user@example.com", "news")
+            .starts_with("https://example.com/u?site=a&token=v1."));
+    }
+
+    #[test]
+    fn headers_follow_rfc_8058() {
+        let headers = links().headers("user@example.com", "news");
+
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers[0].name, "List-Unsubscribe");
+        assert!(headers[0]
+            .value
+            .starts_with("<https://example.com/unsubscribe?token="));
+        assert!(headers[0].value.ends_with('>'));
+        assert_eq!(headers[1].name, "List-Unsubscribe-Post");
+        assert_eq!(headers[1].value, "List-Unsubscribe=One-Click");
+    }
+
+    #[test]
+    fn apply_only_adds_headers_for_single_recipient() {
+        let links = links();
+
+        let mut single = email_to(&["a@example.com"], &[]);
+        assert!(links.apply(&mut single, "news"));
+        assert_eq!(single.headers.len(), 2);
+
+        let mut many = email_to(&["a@example.com", "b@example.com"], &[]);
+        assert!(!links.apply(&mut many, "news"));
+        assert!(many.headers.is_empty());
+    }
+
+    #[test]
+    fn db_suppression_list_queries_and_inserts() {
+        let db = Arc::new(RecordingDb::default());
+        let list = DbSuppressionList::new(db.clone());
+
+        assert!(!list.is_suppressed("A@example.com", "news").unwrap());
+        list.suppress("A@example.com", "news").unwrap();
+
+        let calls = db.calls.lock().unwrap();
+        assert!(calls[0]
+            .0
+            .starts_with("SELECT 1 AS found FROM email_unsubscribes"));
+        assert_eq!(calls[0].1, 2);
+        assert!(calls[1]
+            .0
+            .starts_with("INSERT IGNORE INTO email_unsubscribes"));
+        assert_eq!(calls[1].1, 3);
+    }
+
+    #[test]
+    fn db_suppression_list_reports_found_rows() {
+        let db = Arc::new(RecordingDb {
+            row: Some(Row::default()),
+            ..Default::default()
+        });
+        let list = DbSuppressionList::new(db);
+
+        assert!(list.is_suppressed("a@example.com", "news").unwrap());
+    }
+
+    #[test]
+    fn db_suppression_list_rejects_unsafe_table_names() {
+        let db: Arc<dyn Db> = Arc::new(RecordingDb::default());
+        assert!(DbSuppressionList::with_table(db.clone(), "unsubs; DROP TABLE x").is_err());
+        assert!(DbSuppressionList::with_table(db, "mail_optouts").is_ok());
+    }
+
+    fn app(list: Arc<dyn SuppressionList>) -> Router {
+        Router::new()
+            .route("/unsubscribe", post(unsubscribe_handler))
+            .layer(Extension(links()))
+            .layer(Extension(list))
+    }
+
+    fn one_click_request(uri: &str) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(Body::from(LIST_UNSUBSCRIBE_POST_VALUE))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn handler_records_opt_out_for_valid_token() {
+        let list = Arc::new(MemorySuppressionList::default());
+        let token = links().token("user@example.com", "news");
+
+        let resp = app(list.clone())
+            .oneshot(one_click_request(&format!("/unsubscribe?token={token}")))
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(list.is_suppressed("user@example.com", "news").unwrap());
+    }
+
+    #[tokio::test]
+    async fn handler_rejects_invalid_token() {
+        let list = Arc::new(MemorySuppressionList::default());
+
+        let resp = app(list.clone())
+            .oneshot(one_click_request("/unsubscribe?token=v1.bad.token"))
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(resp.headers()[CONTENT_TYPE], "application/problem+json");
+        assert!(list.entries.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn handler_returns_500_when_recording_fails() {
+        let list = Arc::new(MemorySuppressionList {
+            fail: true,
+            ..Default::default()
+        });
+        let token = links().token("user@example.com", "news");
+
+        let resp = app(list)
+            .oneshot(one_click_request(&format!("/unsubscribe?token={token}")))
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn suppressing_sender_filters_opted_out_recipients() {
+        let inner = Arc::new(RecordingSender::default());
+        let list = Arc::new(MemorySuppressionList::with(&[("b@example.com", "news")]));
+        let sender = SuppressingEmailSender::new(inner.clone(), list, "news");
+
+        sender
+            .send(email_to(
+                &["a@example.com", "B@example.com"],
+                &["b@example.com"],
+            ))
+            .await
+            .unwrap();
+
+        let sent = inner.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, vec![mb("a@example.com")]);
+        assert!(sent[0].cc.is_empty());
+    }
+
+    #[tokio::test]
+    async fn suppressing_sender_skips_when_all_to_recipients_opted_out() {
+        let inner = Arc::new(RecordingSender::default());
+        let list = Arc::new(MemorySuppressionList::with(&[("a@example.com", "news")]));
+        let sender = SuppressingEmailSender::new(inner.clone(), list, "news");
+
+        sender
+            .send(email_to(&["a@example.com"], &["c@example.com"]))
+            .await
+            .unwrap();
+
+        assert!(inner.sent.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn suppressing_sender_is_scoped_to_its_list() {
+        let inner = Arc::new(RecordingSender::default());
+        let list = Arc::new(MemorySuppressionList::with(&[("a@example.com", "promo")]));
+        let sender = SuppressingEmailSender::new(inner.clone(), list, "news");
+
+        sender
+            .send(email_to(&["a@example.com"], &[]))
+            .await
+            .unwrap();
+
+        assert_eq!(inner.sent.lock().unwrap().len(), 1);
+    }
+}
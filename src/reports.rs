@@ -0,0 +1,36 @@
+//! # Scheduled Reports
+//!
+//! Declare a report — a query, a [`ReportSchedule`](schedule::ReportSchedule)
+//! and recipients — in config or in a `scheduled_reports` table, and
+//! [`ReportRunner`](runner::ReportRunner) exports it on schedule and emails
+//! the file:
+//!
+//! - [`definition`] — report declarations and their sources
+//! - [`schedule`] — `daily 07:30`-style schedules in a configurable time zone
+//! - [`export`] — attachment formats; CSV is built in, XLSX and others plug
+//!   in through [`ReportFormat`](export::ReportFormat)
+//! - [`runner`] — paging, rendering, sending and failure alerts through a
+//!   [`Notifier`](crate::notification::notifier::Notifier)
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//! use wzs_web::db::port::Db;
+//! use wzs_web::notification::email_sender::EmailSender;
+//! use wzs_web::notification::notifier::Notifier;
+//! use wzs_web::reports::definition::DbReportSource;
+//! use wzs_web::reports::runner::ReportRunner;
+//!
+//! # async fn run(db: Arc<dyn Db>, mailer: Arc<dyn EmailSender>, ops: Arc<dyn Notifier>) {
+//! let runner = ReportRunner::new(db.clone(), mailer, Arc::new(DbReportSource::new(db)))
+//!     .timezone(chrono_tz::Asia::Tokyo)
+//!     .notify(ops);
+//! Arc::new(runner).spawn(Duration::from_secs(60));
+//! # }
+//! ```
+
+pub mod definition;
+pub mod export;
+pub mod runner;
+pub mod schedule;
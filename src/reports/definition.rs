@@ -0,0 +1,234 @@
+//! Report declarations and where they come from.
+//!
+//! A [`ReportDefinition`] is a query, a [`ReportSchedule`], recipients and a
+//! format name. A [`ReportSource`] lists the current definitions; the runner
+//! asks it on every tick, so edits take effect without a restart:
+//!
+//! - `Vec<ReportDefinition>` — declared in code or deserialized from config
+//! - [`DbReportSource`] — rows of a `scheduled_reports` table
+//!
+//! Report queries are trusted SQL written by operators: they run as-is
+//! (wrapped for paging), so never build them from user input.
+//!
+//! Expected schema for [`DbReportSource`] (MySQL):
+//!
+//! ```sql
+//! CREATE TABLE scheduled_reports (
+//!     name       VARCHAR(128) NOT NULL PRIMARY KEY,
+//!     query_sql  TEXT         NOT NULL,
+//!     schedule   VARCHAR(64)  NOT NULL,  -- e.g. 'weekly mon 07:30'
+//!     recipients TEXT         NOT NULL,  -- comma-separated addresses
+//!     format     VARCHAR(16)  NOT NULL DEFAULT 'csv',
+//!     enabled    BOOLEAN      NOT NULL DEFAULT TRUE
+//! );
+//! ```
+//!
+//! # Example
+//! ```rust
+//! use wzs_web::reports::definition::ReportDefinition;
+//!
+//! let from_config: Vec<ReportDefinition> = serde_json::from_str(r#"[{
+//!     "name": "weekly-signups",
+//!     "query": "SELECT id, email, created_at FROM users WHERE created_at > NOW() - INTERVAL 7 DAY",
+//!     "schedule": "weekly mon 07:30",
+//!     "recipients": ["sales@example.com"]
+//! }]"#).unwrap();
+//! assert_eq!(from_config[0].format, "csv");
+//! ```
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::db::port::Db;
+use crate::db::repository::ident;
+use crate::reports::schedule::ReportSchedule;
+
+/// One scheduled report.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportDefinition {
+    /// Unique name; also the attachment file stem.
+    pub name: String,
+    /// `SELECT` statement producing the report rows.
+    pub query: String,
+    /// When the report runs.
+    pub schedule: ReportSchedule,
+    /// Email addresses receiving the report.
+    pub recipients: Vec<String>,
+    /// Registered format name (default `csv`).
+    #[serde(default = "default_format")]
+    pub format: String,
+}
+
+fn default_format() -> String {
+    "csv".into()
+}
+
+impl ReportDefinition {
+    /// Creates a CSV report without recipients.
+    pub fn new(
+        name: impl Into<String>,
+        query: impl Into<String>,
+        schedule: ReportSchedule,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            query: query.into(),
+            schedule,
+            recipients: Vec::new(),
+            format: default_format(),
+        }
+    }
+
+    /// Adds a recipient address.
+    pub fn recipient(mut self, address: impl Into<String>) -> Self {
+        self.recipients.push(address.into());
+        self
+    }
+
+    /// Sets the format name.
+    pub fn format(mut self, format: impl Into<String>) -> Self {
+        self.format = format.into();
+        self
+    }
+}
+
+/// Lists the report definitions to schedule.
+pub trait ReportSource: Send + Sync {
+    /// Current definitions.
+    fn reports(&self) -> Result<Vec<ReportDefinition>>;
+}
+
+impl ReportSource for Vec<ReportDefinition> {
+    fn reports(&self) -> Result<Vec<ReportDefinition>> {
+        Ok(self.clone())
+    }
+}
+
+/// [`ReportSource`] reading enabled rows of a `scheduled_reports` table.
+#[derive(Clone)]
+pub struct DbReportSource {
+    db: Arc<dyn Db>,
+    table: String,
+}
+
+impl DbReportSource {
+    /// Default table name.
+    pub const DEFAULT_TABLE: &'static str = "scheduled_reports";
+
+    /// Creates a source reading [`DEFAULT_TABLE`](Self::DEFAULT_TABLE).
+    pub fn new(db: Arc<dyn Db>) -> Self {
+        Self {
+            db,
+            table: Self::DEFAULT_TABLE.into(),
+        }
+    }
+
+    /// Creates a source reading a custom table.
+    ///
+    /// # Errors
+    /// Returns an error if `table` is not a plain SQL identifier.
+    pub fn with_table(db: Arc<dyn Db>, table: impl Into<String>) -> Result<Self> {
+        let table = table.into();
+        ident(&table)?;
+        Ok(Self { db, table })
+    }
+}
+
+impl ReportSource for DbReportSource {
+    fn reports(&self) -> Result<Vec<ReportDefinition>> {
+        let sql = format!(
+            "SELECT name, query_sql, schedule, recipients, format FROM {} \
+             WHERE enabled = TRUE ORDER BY name",
+            self.table
+        );
+        self.db
+            .fetch_all(&sql, &[])?
+            .iter()
+            .map(|row| {
+                let name = row.get_string("name")?;
+                let schedule = row
+                    .get_string("schedule")?
+                    .parse()
+                    .with_context(|| format!("report {name}"))?;
+                Ok(ReportDefinition {
+                    query: row.get_string("query_sql")?,
+                    schedule,
+                    recipients: row
+                        .get_string("recipients")?
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                    format: row.get_string("format")?,
+                    name,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::port::{Param, Row, Value};
+
+    struct ReportsDb(Vec<Row>);
+
+    impl Db for ReportsDb {
+        fn fetch_one(&self, _sql: &str, _params: &[Param]) -> Result<Option<Row>> {
+            Ok(None)
+        }
+
+        fn fetch_all(&self, sql: &str, _params: &[Param]) -> Result<Vec<Row>> {
+            assert!(sql.starts_with(
+                "SELECT name, query_sql, schedule, recipients, format FROM reports_v2"
+            ));
+            Ok(self.0.clone())
+        }
+
+        fn exec(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+
+        fn exec_returning_last_insert_id(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    fn row(schedule: &str) -> Row {
+        let mut row = Row::default();
+        row.insert("name", Value::Str("sales".into()));
+        row.insert("query_sql", Value::Str("SELECT 1".into()));
+        row.insert("schedule", Value::Str(schedule.into()));
+        row.insert(
+            "recipients",
+            Value::Str(" a@example.com, ,b@example.com".into()),
+        );
+        row.insert("format", Value::Str("csv".into()));
+        row
+    }
+
+    #[test]
+    fn db_source_maps_rows() {
+        let db = Arc::new(ReportsDb(vec![row("daily 07:00")]));
+        let source = DbReportSource::with_table(db, "reports_v2").unwrap();
+        let reports = source.reports().unwrap();
+        assert_eq!(
+            reports,
+            vec![
+                ReportDefinition::new("sales", "SELECT 1", "daily 07:00".parse().unwrap())
+                    .recipient("a@example.com")
+                    .recipient("b@example.com")
+            ]
+        );
+
+        let bad =
+            DbReportSource::with_table(Arc::new(ReportsDb(vec![row("hourly")])), "reports_v2")
+                .unwrap();
+        assert!(format!("{:#}", bad.reports().unwrap_err()).contains("report sales"));
+        assert!(DbReportSource::with_table(Arc::new(ReportsDb(vec![])), "x;y").is_err());
+    }
+}
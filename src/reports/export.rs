@@ -0,0 +1,177 @@
+//! File formats for report attachments.
+//!
+//! A [`ReportFormat`] creates a [`ReportWriter`] that receives the header
+//! and then the rows page by page, and finally yields the file bytes. The
+//! crate ships [`CsvFormat`]; other formats (e.g. XLSX through a spreadsheet
+//! crate) are added by implementing the two traits and registering them
+//! with [`ReportRunner::format`](crate::reports::runner::ReportRunner::format).
+//!
+//! Cells are rendered from [`Value::to_json`](crate::db::port::Value::to_json):
+//! strings as-is, `NULL` as an empty cell, anything else in its JSON form.
+//!
+//! # Example
+//! ```rust
+//! use wzs_web::db::port::{Row, Value};
+//! use wzs_web::reports::export::{CsvFormat, ReportFormat};
+//!
+//! let mut row = Row::default();
+//! row.insert("name", Value::Str("Smith, Jane".into()));
+//! row.insert("total", Value::I64(42));
+//!
+//! let mut w = CsvFormat::new().writer();
+//! w.header(&["name", "total"]).unwrap();
+//! w.row(&row).unwrap();
+//! let csv = String::from_utf8(w.finish().unwrap()).unwrap();
+//! assert_eq!(csv, "name,total\r\n\"Smith, Jane\",42\r\n");
+//! ```
+
+use std::borrow::Cow;
+
+use anyhow::Result;
+
+use crate::db::port::Row;
+
+/// A report file format.
+pub trait ReportFormat: Send + Sync {
+    /// File name extension without the dot (e.g. `"csv"`).
+    fn extension(&self) -> &str;
+
+    /// MIME type of the produced file.
+    fn content_type(&self) -> &str;
+
+    /// Starts a new file.
+    fn writer(&self) -> Box<dyn ReportWriter>;
+}
+
+/// Accumulates one report file.
+pub trait ReportWriter: Send {
+    /// Writes the column header; called once, before any row.
+    fn header(&mut self, columns: &[&str]) -> Result<()>;
+
+    /// Writes one row, in header column order.
+    fn row(&mut self, row: &Row) -> Result<()>;
+
+    /// Returns the finished file.
+    fn finish(self: Box<Self>) -> Result<Vec<u8>>;
+}
+
+/// RFC 4180 CSV with `\r\n` line endings.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CsvFormat {
+    bom: bool,
+}
+
+impl CsvFormat {
+    /// Plain UTF-8 CSV.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prefixes the file with a UTF-8 byte order mark, so Excel detects the
+    /// encoding of non-ASCII text.
+    pub fn excel_bom(mut self) -> Self {
+        self.bom = true;
+        self
+    }
+}
+
+impl ReportFormat for CsvFormat {
+    fn extension(&self) -> &str {
+        "csv"
+    }
+
+    fn content_type(&self) -> &str {
+        "text/csv; charset=utf-8"
+    }
+
+    fn writer(&self) -> Box<dyn ReportWriter> {
+        let out = if self.bom {
+            "\u{feff}".as_bytes().to_vec()
+        } else {
+            Vec::new()
+        };
+        Box::new(CsvWriter {
+            out,
+            columns: Vec::new(),
+        })
+    }
+}
+
+struct CsvWriter {
+    out: Vec<u8>,
+    columns: Vec<String>,
+}
+
+impl CsvWriter {
+    fn line<'a>(&mut self, cells: impl Iterator<Item = Cow<'a, str>>) {
+        let line: Vec<Cow<'a, str>> = cells.map(|c| csv_field(c)).collect();
+        self.out.extend_from_slice(line.join(",").as_bytes());
+        self.out.extend_from_slice(b"\r\n");
+    }
+}
+
+impl ReportWriter for CsvWriter {
+    fn header(&mut self, columns: &[&str]) -> Result<()> {
+        self.columns = columns.iter().map(|c| c.to_string()).collect();
+        self.line(columns.iter().map(|c| Cow::Borrowed(*c)));
+        Ok(())
+    }
+
+    fn row(&mut self, row: &Row) -> Result<()> {
+        let cells: Vec<Cow<'static, str>> = self
+            .columns
+            .iter()
+            .map(|c| Cow::Owned(cell(row, c)))
+            .collect();
+        self.line(cells.into_iter());
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<Vec<u8>> {
+        Ok(self.out)
+    }
+}
+
+/// Text of one cell.
+fn cell(row: &Row, column: &str) -> String {
+    match row.get(column).map(|v| v.to_json()) {
+        None | Some(serde_json::Value::Null) => String::new(),
+        Some(serde_json::Value::String(s)) => s,
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Quotes `field` when it contains a separator, quote or line break.
+fn csv_field(field: Cow<'_, str>) -> Cow<'_, str> {
+    if field.contains([',', '"', '\r', '\n']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        field
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::port::Value;
+
+    #[test]
+    fn csv_quotes_and_renders_values() {
+        let mut row = Row::default();
+        row.insert("a", Value::Str("say \"hi\"\nthere".into()));
+        row.insert("b", Value::Null);
+        row.insert("c", Value::Bool(true));
+        row.insert("d", Value::F64(1.5));
+
+        let mut w = CsvFormat::new().excel_bom().writer();
+        w.header(&["a", "b", "c", "d", "missing"]).unwrap();
+        w.row(&row).unwrap();
+        let out = w.finish().unwrap();
+
+        assert!(out.starts_with("\u{feff}".as_bytes()));
+        assert_eq!(
+            std::str::from_utf8(&out[3..]).unwrap(),
+            "a,b,c,d,missing\r\n\"say \"\"hi\"\"\nthere\",,true,1.5,\r\n"
+        );
+    }
+}
@@ -0,0 +1,644 @@
+//! Runs scheduled reports and emails the results.
+//!
+//! [`ReportRunner::tick`] asks the [`ReportSource`] for the current
+//! definitions and runs every report whose next run time has passed;
+//! [`spawn`](ReportRunner::spawn) calls it periodically. A report run:
+//!
+//! 1. pages through the query (`SELECT * FROM (<query>) LIMIT ? OFFSET ?`),
+//!    up to [`max_rows`](ReportRunner::max_rows)
+//! 2. writes the rows with the definition's [`ReportFormat`]
+//! 3. renders the email (a default summary, or the
+//!    [`template`](ReportRunner::template)) and sends it with the file
+//!    attached
+//!
+//! Failures are logged and sent to the [`Notifier`], if one is set. Each
+//! report run is observed as job `report:<name>` and each scheduler tick as
+//! `report_scheduler` (see [`observe_job`]); with
+//! [`metrics`](ReportRunner::metrics) the next run times are exported too.
+//!
+//! Next run times live in memory: a report first runs at its next scheduled
+//! time after the runner starts, and runs missed while the process was down
+//! are not caught up. Run a single runner per deployment.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use lettre::message::{header::ContentType, Mailbox};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::db::context::DbContext;
+use crate::db::port::{Db, Param};
+use crate::notification::email::{Attachment, Email, EmailBody};
+use crate::notification::email_sender::EmailSender;
+use crate::notification::notifier::Notifier;
+use crate::notification::template::registry::RenderedEmail;
+use crate::reports::definition::{ReportDefinition, ReportSource};
+use crate::reports::export::{CsvFormat, ReportFormat};
+use crate::reports::schedule::ReportSchedule;
+use crate::telemetry::jobs::{observe_job, JobMetrics};
+
+/// Default rows fetched per query.
+pub const DEFAULT_PAGE_SIZE: u64 = 1_000;
+
+/// Default cap on rows per report.
+pub const DEFAULT_MAX_ROWS: u64 = 100_000;
+
+/// The attachment produced by a report run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReportFile {
+    /// `<name>-<YYYYMMDD>.<extension>`.
+    pub filename: String,
+    /// MIME type from the [`ReportFormat`].
+    pub content_type: String,
+    /// File contents.
+    pub bytes: Vec<u8>,
+}
+
+/// Result of one report run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReportRun {
+    /// Report name.
+    pub name: String,
+    /// Rows written to the file.
+    pub rows: u64,
+    /// Whether rows beyond [`max_rows`](ReportRunner::max_rows) were left out.
+    pub truncated: bool,
+    /// When the query ran.
+    pub generated_at: DateTime<Utc>,
+    /// The exported file.
+    pub file: ReportFile,
+}
+
+type Template = Arc<dyn Fn(&ReportDefinition, &ReportRun) -> Result<RenderedEmail> + Send + Sync>;
+
+/// Runs report definitions on schedule.
+pub struct ReportRunner {
+    db: Arc<dyn Db>,
+    sender: Arc<dyn EmailSender>,
+    source: Arc<dyn ReportSource>,
+    formats: HashMap<String, Arc<dyn ReportFormat>>,
+    notifier: Option<Arc<dyn Notifier>>,
+    template: Option<Template>,
+    timezone: Tz,
+    page_size: u64,
+    max_rows: u64,
+    next_runs: Mutex<HashMap<String, (ReportSchedule, DateTime<Utc>)>>,
+    metrics: Option<JobMetrics>,
+}
+
+impl ReportRunner {
+    /// Creates a runner with the `csv` format, UTC schedules and default
+    /// paging limits.
+    pub fn new(
+        db: Arc<dyn Db>,
+        sender: Arc<dyn EmailSender>,
+        source: Arc<dyn ReportSource>,
+    ) -> Self {
+        let mut formats: HashMap<String, Arc<dyn ReportFormat>> = HashMap::new();
+        formats.insert("csv".into(), Arc::new(CsvFormat::new()));
+        Self {
+            db,
+            sender,
+            source,
+            formats,
+            notifier: None,
+            template: None,
+            timezone: Tz::UTC,
+            page_size: DEFAULT_PAGE_SIZE,
+            max_rows: DEFAULT_MAX_ROWS,
+            next_runs: Mutex::new(HashMap::new()),
+            metrics: None,
+        }
+    }
+
+    /// Registers (or replaces) a format under `name`.
+    pub fn format(mut self, name: impl Into<String>, format: Arc<dyn ReportFormat>) -> Self {
+        self.formats.insert(name.into(), format);
+        self
+    }
+
+    /// Sends failure alerts through `notifier`.
+    pub fn notify(mut self, notifier: Arc<dyn Notifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Renders report emails with `template` instead of the default summary.
+    pub fn template<F>(mut self, template: F) -> Self
+    where
+        F: Fn(&ReportDefinition, &ReportRun) -> Result<RenderedEmail> + Send + Sync + 'static,
+    {
+        self.template = Some(Arc::new(template));
+        self
+    }
+
+    /// Records report runs, scheduler ticks and next run times in `metrics`.
+    pub fn metrics(mut self, metrics: JobMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Time zone for schedule wall-clock times and file dates.
+    pub fn timezone(mut self, tz: Tz) -> Self {
+        self.timezone = tz;
+        self
+    }
+
+    /// Rows fetched per query (at least 1).
+    pub fn page_size(mut self, rows: u64) -> Self {
+        self.page_size = rows.max(1);
+        self
+    }
+
+    /// Maximum rows per report; further rows are left out.
+    pub fn max_rows(mut self, rows: u64) -> Self {
+        self.max_rows = rows;
+        self
+    }
+
+    /// Runs the reports due at `now` and schedules their next runs.
+    ///
+    /// Newly seen reports (and reports whose schedule changed) are only
+    /// scheduled, not run. Returns the outcome of each report that ran.
+    pub async fn tick(&self, now: DateTime<Utc>) -> Result<Vec<(String, Result<ReportRun>)>> {
+        let source = self.source.clone();
+        let reports = match blocking(move || source.reports()).await {
+            Ok(reports) => reports,
+            Err(e) => {
+                self.alert("Scheduled reports could not be loaded", &format!("{e:#}"))
+                    .await;
+                return Err(e);
+            }
+        };
+
+        let due: Vec<ReportDefinition> = {
+            let mut next_runs = self.next_runs.lock().unwrap();
+            next_runs.retain(|name, _| {
+                let keep = reports.iter().any(|r| &r.name == name);
+                if let (false, Some(metrics)) = (keep, &self.metrics) {
+                    metrics.forget(&report_job(name));
+                }
+                keep
+            });
+            let due = reports
+                .into_iter()
+                .filter(|r| {
+                    let next = r.schedule.next_after(now, self.timezone);
+                    match next_runs.get_mut(&r.name) {
+                        Some((schedule, at)) if *schedule == r.schedule => {
+                            if now < *at {
+                                return false;
+                            }
+                            *at = next;
+                            true
+                        }
+                        _ => {
+                            next_runs.insert(r.name.clone(), (r.schedule.clone(), next));
+                            false
+                        }
+                    }
+                })
+                .collect();
+            if let Some(metrics) = &self.metrics {
+                for (name, (_, at)) in next_runs.iter() {
+                    metrics.set_next_run(&report_job(name), *at);
+                }
+            }
+            due
+        };
+
+        let mut outcomes = Vec::with_capacity(due.len());
+        for report in due {
+            let outcome = self.run(&report).await;
+            outcomes.push((report.name, outcome));
+        }
+        Ok(outcomes)
+    }
+
+    /// Runs `report` now: exports it and emails the file.
+    ///
+    /// Failures are reported to the notifier before being returned.
+    pub async fn run(&self, report: &ReportDefinition) -> Result<ReportRun> {
+        let job = report_job(&report.name);
+        match observe_job(self.metrics.as_ref(), &job, self.try_run(report)).await {
+            Ok(run) => {
+                info!(
+                    report = %run.name,
+                    rows = run.rows,
+                    truncated = run.truncated,
+                    "scheduled report sent"
+                );
+                Ok(run)
+            }
+            Err(e) => {
+                self.alert(&format!("Report {} failed", report.name), &format!("{e:#}"))
+                    .await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Exports `report` at `now` without sending it (blocking).
+    pub fn export(&self, report: &ReportDefinition, now: DateTime<Utc>) -> Result<ReportRun> {
+        let format = self
+            .formats
+            .get(&report.format)
+            .ok_or_else(|| anyhow!("unknown report format: {}", report.format))?;
+        export(
+            self.db.as_ref(),
+            format.as_ref(),
+            report,
+            ExportLimits {
+                page_size: self.page_size,
+                max_rows: self.max_rows,
+            },
+            now,
+            self.timezone,
+        )
+    }
+
+    /// Spawns a task calling [`tick`](Self::tick) every `poll`.
+    ///
+    /// The poll interval bounds how late a report may run; one minute suits
+    /// minute-precision schedules.
+    pub fn spawn(self: Arc<Self>, poll: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(poll);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let tick = self.tick(Utc::now());
+                let _ = observe_job(self.metrics.as_ref(), "report_scheduler", tick).await;
+            }
+        })
+    }
+
+    async fn try_run(&self, report: &ReportDefinition) -> Result<ReportRun> {
+        let format = self
+            .formats
+            .get(&report.format)
+            .cloned()
+            .ok_or_else(|| anyhow!("unknown report format: {}", report.format))?;
+        let (db, def, tz) = (self.db.clone(), report.clone(), self.timezone);
+        let limits = ExportLimits {
+            page_size: self.page_size,
+            max_rows: self.max_rows,
+        };
+        let run =
+            blocking(move || export(db.as_ref(), format.as_ref(), &def, limits, Utc::now(), tz))
+                .await?;
+
+        let email = self.email(report, &run)?;
+        self.sender
+            .send(email)
+            .await
+            .with_context(|| format!("send report {}", report.name))?;
+        Ok(run)
+    }
+
+    fn email(&self, report: &ReportDefinition, run: &ReportRun) -> Result<Email> {
+        let to = report
+            .recipients
+            .iter()
+            .map(|r| {
+                r.parse::<Mailbox>()
+                    .with_context(|| format!("invalid report recipient: {r}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if to.is_empty() {
+            bail!("report {} has no recipients", report.name);
+        }
+
+        let rendered = match &self.template {
+            Some(template) => template(report, run)?,
+            None => self.default_email(run),
+        };
+        let attachment = Attachment {
+            filename: run.file.filename.clone(),
+            content_type: ContentType::parse(&run.file.content_type)
+                .map_err(|e| anyhow!("invalid report content type: {e}"))?,
+            bytes: run.file.bytes.clone(),
+        };
+        let body = match (rendered.text, rendered.html) {
+            (text, Some(html)) => EmailBody::TextAndHtmlWithAttachments {
+                text: text.unwrap_or_default(),
+                html,
+                attachments: vec![attachment],
+            },
+            (text, None) => EmailBody::TextWithAttachments {
+                text: text.unwrap_or_default(),
+                attachments: vec![attachment],
+            },
+        };
+
+        Ok(Email {
+            subject: rendered.subject,
+            body,
+            to,
+            cc: vec![],
+            bcc: vec![],
+            headers: vec![],
+        })
+    }
+
+    fn default_email(&self, run: &ReportRun) -> RenderedEmail {
+        let at = run.generated_at.with_timezone(&self.timezone);
+        let mut text = format!(
+            "Report \"{}\" generated at {} with {} row(s).\n\nThe data is attached as {}.\n",
+            run.name,
+            at.format("%Y-%m-%d %H:%M %Z"),
+            run.rows,
+            run.file.filename
+        );
+        if run.truncated {
+            text.push_str(&format!(
+                "\nOnly the first {} rows are included.\n",
+                run.rows
+            ));
+        }
+        RenderedEmail::new(format!("Report: {} ({})", run.name, at.format("%Y-%m-%d"))).text(text)
+    }
+
+    async fn alert(&self, subject: &str, message: &str) {
+        let Some(notifier) = &self.notifier else {
+            return;
+        };
+        if let Err(e) = notifier.notify(subject, message).await {
+            warn!("report failure alert could not be sent: {e:#}");
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ExportLimits {
+    page_size: u64,
+    max_rows: u64,
+}
+
+fn export(
+    db: &dyn Db,
+    format: &dyn ReportFormat,
+    report: &ReportDefinition,
+    limits: ExportLimits,
+    now: DateTime<Utc>,
+    tz: Tz,
+) -> Result<ReportRun> {
+    let query = report.query.trim().trim_end_matches(';');
+    let sql = format!("SELECT * FROM ({query}) AS report_rows LIMIT ? OFFSET ?");
+    let ctx = DbContext::new().read_only();
+
+    let mut writer = format.writer();
+    let mut rows = 0u64;
+    let mut truncated = false;
+    loop {
+        // One row past the cap tells whether anything was left out.
+        let limit = limits.page_size.min(limits.max_rows - rows + 1);
+        let page = db
+            .fetch_all_with_ctx(&ctx, &sql, &[Param::U64(limit), Param::U64(rows)])
+            .with_context(|| format!("query report {}", report.name))?;
+        if rows == 0 && !page.is_empty() {
+            writer.header(&page[0].columns())?;
+        }
+        let fetched = page.len() as u64;
+        for row in page.iter().take((limits.max_rows - rows) as usize) {
+            writer.row(row)?;
+        }
+        if rows + fetched > limits.max_rows {
+            rows = limits.max_rows;
+            truncated = true;
+            break;
+        }
+        rows += fetched;
+        if fetched < limit || rows == limits.max_rows {
+            break;
+        }
+    }
+
+    Ok(ReportRun {
+        name: report.name.clone(),
+        rows,
+        truncated,
+        generated_at: now,
+        file: ReportFile {
+            filename: format!(
+                "{}-{}.{}",
+                report.name,
+                now.with_timezone(&tz).format("%Y%m%d"),
+                format.extension()
+            ),
+            content_type: format.content_type().to_string(),
+            bytes: writer.finish()?,
+        },
+    })
+}
+
+async fn blocking<T, F>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| anyhow!("blocking task failed: {e}"))?
+}
+
+/// Job name of report `name` in [`JobMetrics`].
+fn report_job(name: &str) -> String {
+    format!("report:{name}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+
+    use crate::db::port::{Row, Value};
+
+    /// `n` rows `{id}` for any paged report query.
+    struct NumbersDb {
+        n: u64,
+        queries: Mutex<Vec<(u64, u64)>>,
+    }
+
+    impl Db for NumbersDb {
+        fn fetch_one(&self, _sql: &str, _params: &[Param]) -> Result<Option<Row>> {
+            Ok(None)
+        }
+
+        fn fetch_all(&self, sql: &str, params: &[Param]) -> Result<Vec<Row>> {
+            if sql.contains("broken") {
+                bail!("syntax error");
+            }
+            let (Param::U64(limit), Param::U64(offset)) = (&params[0], &params[1]) else {
+                bail!("bad params");
+            };
+            self.queries.lock().unwrap().push((*limit, *offset));
+            Ok((*offset..(*offset + *limit).min(self.n))
+                .map(|i| {
+                    let mut row = Row::default();
+                    row.insert("id", Value::U64(i + 1));
+                    row
+                })
+                .collect())
+        }
+
+        fn exec(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+
+        fn exec_returning_last_insert_id(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    #[derive(Default)]
+    struct Outbox(Mutex<Vec<Email>>);
+
+    #[async_trait]
+    impl EmailSender for Outbox {
+        async fn send(&self, email: Email) -> Result<()> {
+            self.0.lock().unwrap().push(email);
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct Alerts(Mutex<Vec<String>>);
+
+    #[async_trait]
+    impl Notifier for Alerts {
+        async fn notify(&self, subject: &str, _message: &str) -> Result<()> {
+            self.0.lock().unwrap().push(subject.to_string());
+            Ok(())
+        }
+    }
+
+    fn report(query: &str) -> ReportDefinition {
+        ReportDefinition::new("numbers", query, "daily 07:00".parse().unwrap())
+            .recipient("ops@example.com")
+    }
+
+    fn runner(n: u64, reports: Vec<ReportDefinition>) -> (ReportRunner, Arc<Outbox>, Arc<Alerts>) {
+        let outbox = Arc::new(Outbox::default());
+        let alerts = Arc::new(Alerts::default());
+        let runner = ReportRunner::new(
+            Arc::new(NumbersDb {
+                n,
+                queries: Mutex::new(vec![]),
+            }),
+            outbox.clone(),
+            Arc::new(reports),
+        )
+        .notify(alerts.clone())
+        .page_size(2);
+        (runner, outbox, alerts)
+    }
+
+    #[test]
+    fn export_pages_through_the_query_up_to_max_rows() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 2, 7, 0, 0).unwrap();
+        let (runner, _, _) = runner(5, vec![]);
+
+        let run = runner.export(&report("SELECT id FROM t;"), now).unwrap();
+        assert_eq!((run.rows, run.truncated), (5, false));
+        assert_eq!(run.file.filename, "numbers-20260302.csv");
+        assert_eq!(
+            String::from_utf8(run.file.bytes).unwrap(),
+            "id\r\n1\r\n2\r\n3\r\n4\r\n5\r\n"
+        );
+
+        let run = runner
+            .max_rows(3)
+            .export(&report("SELECT id FROM t"), now)
+            .unwrap();
+        assert_eq!((run.rows, run.truncated), (3, true));
+        assert_eq!(
+            String::from_utf8(run.file.bytes).unwrap(),
+            "id\r\n1\r\n2\r\n3\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn tick_runs_due_reports_and_emails_the_file() {
+        let (runner, outbox, alerts) = runner(1, vec![report("SELECT id FROM t")]);
+        let start = Utc.with_ymd_and_hms(2026, 3, 2, 6, 0, 0).unwrap();
+
+        assert!(
+            runner.tick(start).await.unwrap().is_empty(),
+            "only scheduled"
+        );
+        assert!(runner
+            .tick(start + chrono::Duration::minutes(59))
+            .await
+            .unwrap()
+            .is_empty());
+
+        let ran = runner
+            .tick(start + chrono::Duration::hours(1))
+            .await
+            .unwrap();
+        assert_eq!(ran.len(), 1);
+        assert_eq!(ran[0].1.as_ref().unwrap().rows, 1);
+        assert!(runner
+            .tick(start + chrono::Duration::hours(2))
+            .await
+            .unwrap()
+            .is_empty());
+
+        let sent = outbox.0.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].subject.starts_with("Report: numbers ("));
+        match &sent[0].body {
+            EmailBody::TextWithAttachments { text, attachments } => {
+                assert!(text.contains("1 row(s)"));
+                assert!(attachments[0].filename.ends_with(".csv"));
+                assert_eq!(attachments[0].bytes, b"id\r\n1\r\n");
+            }
+            other => panic!("unexpected body: {other:?}"),
+        }
+        assert!(alerts.0.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn failures_are_alerted() {
+        let (runner, outbox, alerts) = runner(1, vec![]);
+        let metrics = JobMetrics::new();
+        let runner = runner.metrics(metrics.clone());
+
+        assert!(runner.run(&report("SELECT broken")).await.is_err());
+        let mut unknown = report("SELECT id FROM t").format("xlsx");
+        assert!(runner.run(&unknown).await.is_err());
+        unknown.format = "csv".into();
+        unknown.recipients = vec!["not an address".into()];
+        assert!(runner.run(&unknown).await.is_err());
+
+        assert!(outbox.0.lock().unwrap().is_empty());
+        assert_eq!(alerts.0.lock().unwrap().len(), 3);
+        assert_eq!(alerts.0.lock().unwrap()[0], "Report numbers failed");
+        assert_eq!(metrics.stats("report:numbers").unwrap().failed, 3);
+    }
+
+    #[tokio::test]
+    async fn template_renders_the_email() {
+        let (runner, outbox, _) = runner(2, vec![]);
+        let runner = runner.template(|def, run| {
+            Ok(RenderedEmail::new(format!("{} ready", def.name))
+                .text(format!("{} rows", run.rows))
+                .html("<p>see attachment</p>"))
+        });
+
+        runner.run(&report("SELECT id FROM t")).await.unwrap();
+        let sent = outbox.0.lock().unwrap();
+        assert_eq!(sent[0].subject, "numbers ready");
+        assert!(matches!(
+            &sent[0].body,
+            EmailBody::TextAndHtmlWithAttachments { text, .. } if text == "2 rows"
+        ));
+    }
+}
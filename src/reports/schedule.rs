@@ -0,0 +1,252 @@
+//! When a report runs.
+//!
+//! Schedules are written as short strings, so they fit a config file or a
+//! `VARCHAR` column:
+//!
+//! | Schedule | Runs |
+//! |----------|------|
+//! | `every 15m` | every 15 minutes (`s`, `m`, `h`, `d` units) |
+//! | `daily 07:30` | every day at 07:30 |
+//! | `weekly mon 07:30` | every Monday at 07:30 |
+//! | `monthly 1 07:30` | on the 1st of every month at 07:30 (days 1–28) |
+//!
+//! Wall-clock times are interpreted in the runner's time zone; a time that
+//! does not exist on a DST transition day is skipped for that day.
+//!
+//! # Example
+//! ```rust
+//! use chrono::{TimeZone, Utc};
+//! use chrono_tz::Asia::Tokyo;
+//! use wzs_web::reports::schedule::ReportSchedule;
+//!
+//! let schedule: ReportSchedule = "daily 09:00".parse().unwrap();
+//! let after = Utc.with_ymd_and_hms(2026, 3, 1, 0, 30, 0).unwrap(); // 09:30 JST
+//! let next = schedule.next_after(after, Tokyo);
+//! assert_eq!(next, Utc.with_ymd_and_hms(2026, 3, 2, 0, 0, 0).unwrap());
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Error, Result};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A report schedule; see the [module docs](self) for the string form.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReportSchedule {
+    /// A fixed interval.
+    Every(Duration),
+    /// Every day at a wall-clock time.
+    Daily(NaiveTime),
+    /// One weekday at a wall-clock time.
+    Weekly(Weekday, NaiveTime),
+    /// One day of the month (1–28) at a wall-clock time.
+    Monthly(u32, NaiveTime),
+}
+
+impl ReportSchedule {
+    /// The first run strictly after `after`, with wall-clock times in `tz`.
+    pub fn next_after(&self, after: DateTime<Utc>, tz: Tz) -> DateTime<Utc> {
+        let (time, matches): (NaiveTime, Box<dyn Fn(NaiveDate) -> bool>) = match self {
+            Self::Every(every) => {
+                let every = chrono::Duration::from_std(*every).unwrap_or(chrono::Duration::MAX);
+                return after
+                    .checked_add_signed(every)
+                    .unwrap_or(DateTime::<Utc>::MAX_UTC);
+            }
+            Self::Daily(t) => (*t, Box::new(|_| true)),
+            Self::Weekly(day, t) => {
+                let day = *day;
+                (*t, Box::new(move |d: NaiveDate| d.weekday() == day))
+            }
+            Self::Monthly(dom, t) => {
+                let dom = *dom;
+                (*t, Box::new(move |d: NaiveDate| d.day() == dom))
+            }
+        };
+
+        let mut date = after.with_timezone(&tz).date_naive();
+        // Monthly schedules match at least once every 31 days; the bound
+        // only guards against a time that never exists.
+        for _ in 0..400 {
+            if matches(date) {
+                let local = tz.from_local_datetime(&date.and_time(time)).earliest();
+                if let Some(at) = local
+                    .map(|l| l.with_timezone(&Utc))
+                    .filter(|at| *at > after)
+                {
+                    return at;
+                }
+            }
+            date = date.succ_opt().unwrap_or(date);
+        }
+        DateTime::<Utc>::MAX_UTC
+    }
+}
+
+impl FromStr for ReportSchedule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        match parts.as_slice() {
+            ["every", every] => parse_interval(every).map(Self::Every),
+            ["daily", at] => Ok(Self::Daily(parse_time(at)?)),
+            ["weekly", day, at] => {
+                let day: Weekday = day.parse().map_err(|_| anyhow!("invalid weekday: {day}"))?;
+                Ok(Self::Weekly(day, parse_time(at)?))
+            }
+            ["monthly", dom, at] => match dom.parse::<u32>() {
+                Ok(dom @ 1..=28) => Ok(Self::Monthly(dom, parse_time(at)?)),
+                _ => bail!("invalid day of month (1-28): {dom}"),
+            },
+            _ => bail!("invalid report schedule: {s:?}"),
+        }
+    }
+}
+
+impl fmt::Display for ReportSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Every(d) => {
+                let secs = d.as_secs();
+                match secs {
+                    s if s % 86_400 == 0 && s > 0 => write!(f, "every {}d", s / 86_400),
+                    s if s % 3600 == 0 && s > 0 => write!(f, "every {}h", s / 3600),
+                    s if s % 60 == 0 && s > 0 => write!(f, "every {}m", s / 60),
+                    s => write!(f, "every {s}s"),
+                }
+            }
+            Self::Daily(t) => write!(f, "daily {}", t.format("%H:%M")),
+            Self::Weekly(day, t) => write!(
+                f,
+                "weekly {} {}",
+                day.to_string().to_lowercase(),
+                t.format("%H:%M")
+            ),
+            Self::Monthly(dom, t) => write!(f, "monthly {dom} {}", t.format("%H:%M")),
+        }
+    }
+}
+
+impl Serialize for ReportSchedule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ReportSchedule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+fn parse_interval(s: &str) -> Result<Duration> {
+    let unit_at = s
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| anyhow!("interval needs a unit (s, m, h, d): {s}"))?;
+    let (n, unit) = s.split_at(unit_at);
+    let n: u64 = n.parse().map_err(|_| anyhow!("invalid interval: {s}"))?;
+    let secs = match unit {
+        "s" => n,
+        "m" => n * 60,
+        "h" => n * 3600,
+        "d" => n * 86_400,
+        _ => bail!("invalid interval unit: {unit}"),
+    };
+    if secs == 0 {
+        bail!("interval must be positive: {s}");
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+fn parse_time(s: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(s, "%H:%M").map_err(|_| anyhow!("invalid time (HH:MM): {s}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::{America::New_York, Asia::Tokyo};
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_and_displays_every_form() {
+        for s in [
+            "every 15m",
+            "every 2h",
+            "every 1d",
+            "every 90s",
+            "daily 07:30",
+            "weekly mon 07:30",
+            "monthly 28 23:05",
+        ] {
+            assert_eq!(s.parse::<ReportSchedule>().unwrap().to_string(), s);
+        }
+        for bad in [
+            "every 15",
+            "every 0m",
+            "hourly",
+            "daily 7",
+            "weekly xyz 07:00",
+            "monthly 31 07:00",
+        ] {
+            assert!(bad.parse::<ReportSchedule>().is_err(), "{bad}");
+        }
+
+        let json: ReportSchedule = serde_json::from_str(r#""weekly fri 18:00""#).unwrap();
+        assert_eq!(
+            json,
+            ReportSchedule::Weekly(Weekday::Fri, NaiveTime::from_hms_opt(18, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn next_after_uses_the_time_zone() {
+        let daily: ReportSchedule = "daily 09:00".parse().unwrap();
+        // 08:00 JST -> today 09:00 JST
+        assert_eq!(
+            daily.next_after(utc(2026, 3, 1, 23, 0), Tokyo),
+            utc(2026, 3, 2, 0, 0)
+        );
+        // exactly at the run time -> next day
+        assert_eq!(
+            daily.next_after(utc(2026, 3, 2, 0, 0), Tokyo),
+            utc(2026, 3, 3, 0, 0)
+        );
+
+        let weekly: ReportSchedule = "weekly mon 09:00".parse().unwrap();
+        // 2026-03-04 is a Wednesday
+        assert_eq!(
+            weekly.next_after(utc(2026, 3, 4, 0, 0), Tokyo),
+            utc(2026, 3, 9, 0, 0)
+        );
+
+        let monthly: ReportSchedule = "monthly 1 09:00".parse().unwrap();
+        assert_eq!(
+            monthly.next_after(utc(2026, 3, 4, 0, 0), Tokyo),
+            utc(2026, 4, 1, 0, 0)
+        );
+
+        let every: ReportSchedule = "every 15m".parse().unwrap();
+        assert_eq!(
+            every.next_after(utc(2026, 3, 4, 0, 0), Tokyo),
+            utc(2026, 3, 4, 0, 15)
+        );
+    }
+
+    #[test]
+    fn skips_wall_clock_times_missing_on_dst_days() {
+        // 2026-03-08 02:30 does not exist in New York.
+        let daily: ReportSchedule = "daily 02:30".parse().unwrap();
+        let next = daily.next_after(utc(2026, 3, 8, 5, 0), New_York);
+        assert_eq!(next, utc(2026, 3, 9, 6, 30));
+    }
+}
@@ -0,0 +1,367 @@
+//! # Application Settings
+//!
+//! [`SettingsStore`] keeps admin-editable options (site title, feature
+//! toggles, limits) in a key/value table accessed through the
+//! [`Db`](crate::db::port::Db) port, so changing them needs no redeploy.
+//!
+//! - Values are JSON documents read and written with serde
+//!   ([`get`](SettingsStore::get) / [`set`](SettingsStore::set)). A stored
+//!   value that is not valid JSON is read as a plain string, so `My Site`
+//!   typed into the table by hand works for a `String` setting.
+//! - The whole table is cached for a TTL (default 30 seconds);
+//!   [`set`](SettingsStore::set) and [`delete`](SettingsStore::delete)
+//!   refresh the cache immediately.
+//! - Changes made through the store are broadcast as [`SettingChange`]s to
+//!   [`subscribe`](SettingsStore::subscribe)rs. The crate has no shared event
+//!   bus, so notifications are per process; other instances see a change
+//!   once their cache expires.
+//!
+//! Expected schema (MySQL):
+//!
+//! ```sql
+//! CREATE TABLE app_settings (
+//!     name       VARCHAR(128) NOT NULL PRIMARY KEY,
+//!     value      TEXT         NOT NULL,
+//!     updated_at DATETIME     NOT NULL
+//! );
+//! ```
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use wzs_web::db::port::Db;
+//! use wzs_web::settings::SettingsStore;
+//!
+//! async fn run(db: Arc<dyn Db>) -> anyhow::Result<()> {
+//!     let settings = Arc::new(SettingsStore::new(db));
+//!
+//!     let mut changes = settings.subscribe();
+//!     tokio::spawn(async move {
+//!         while let Ok(change) = changes.recv().await {
+//!             tracing::info!(name = %change.name, "setting changed");
+//!         }
+//!     });
+//!
+//!     settings.set("site.title", &"Example Shop")?;
+//!     let title: String = settings.get_or("site.title", "Untitled".into())?;
+//!     let signups_open = settings.get_or("signups.open", true)?;
+//!     println!("{title} {signups_open}");
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::broadcast;
+
+use crate::db::port::{Db, Param};
+use crate::db::repository::ident;
+
+/// Longest accepted setting name (matches the `VARCHAR(128)` column).
+pub const MAX_NAME_LEN: usize = 128;
+
+/// A setting written or deleted through a [`SettingsStore`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SettingChange {
+    pub name: String,
+    /// New raw (JSON) value; `None` when the setting was deleted.
+    pub value: Option<String>,
+}
+
+type Snapshot = Arc<HashMap<String, String>>;
+
+/// Typed, cached access to the settings table.
+pub struct SettingsStore {
+    db: Arc<dyn Db>,
+    table: String,
+    ttl: Duration,
+    cache: Mutex<Option<(Instant, Snapshot)>>,
+    changes: broadcast::Sender<SettingChange>,
+}
+
+impl SettingsStore {
+    /// Default table name.
+    pub const DEFAULT_TABLE: &'static str = "app_settings";
+
+    /// Default cache lifetime.
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(30);
+
+    /// Creates a store backed by the default table.
+    pub fn new(db: Arc<dyn Db>) -> Self {
+        Self {
+            db,
+            table: Self::DEFAULT_TABLE.into(),
+            ttl: Self::DEFAULT_TTL,
+            cache: Mutex::new(None),
+            changes: broadcast::channel(64).0,
+        }
+    }
+
+    /// Creates a store backed by a custom table.
+    ///
+    /// # Errors
+    /// Returns an error if `table` is not a plain SQL identifier.
+    pub fn with_table(db: Arc<dyn Db>, table: impl Into<String>) -> Result<Self> {
+        let table = table.into();
+        ident(&table)?;
+        Ok(Self {
+            table,
+            ..Self::new(db)
+        })
+    }
+
+    /// Sets the cache lifetime.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Returns the setting `name` decoded as `T`, or `None` if unset.
+    ///
+    /// # Errors
+    /// Fails when the table cannot be read or the value does not decode as `T`.
+    pub fn get<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>> {
+        let snapshot = self.all()?;
+        let Some(raw) = snapshot.get(name) else {
+            return Ok(None);
+        };
+        decode(raw)
+            .map(Some)
+            .with_context(|| format!("setting `{name}` has an unexpected type"))
+    }
+
+    /// Returns the setting `name`, or `default` if unset.
+    pub fn get_or<T: DeserializeOwned>(&self, name: &str, default: T) -> Result<T> {
+        Ok(self.get(name)?.unwrap_or(default))
+    }
+
+    /// Stores `value` under `name` and notifies subscribers.
+    pub fn set<T: Serialize + ?Sized>(&self, name: &str, value: &T) -> Result<()> {
+        check_name(name)?;
+        let raw = serde_json::to_string(value)?;
+        let sql = format!(
+            "INSERT INTO {} (name, value, updated_at) VALUES (?, ?, ?) \
+             ON DUPLICATE KEY UPDATE value = VALUES(value), updated_at = VALUES(updated_at)",
+            self.table
+        );
+        self.db.exec(
+            &sql,
+            &[
+                Param::Str(name),
+                Param::Str(&raw),
+                Param::DateTime(Utc::now().naive_utc()),
+            ],
+        )?;
+        self.invalidate();
+        self.notify(name, Some(raw));
+        Ok(())
+    }
+
+    /// Removes `name`; returns `false` if it was not set.
+    pub fn delete(&self, name: &str) -> Result<bool> {
+        let sql = format!("DELETE FROM {} WHERE name = ?", self.table);
+        let removed = self.db.exec(&sql, &[Param::Str(name)])? > 0;
+        self.invalidate();
+        if removed {
+            self.notify(name, None);
+        }
+        Ok(removed)
+    }
+
+    /// Returns every setting as raw JSON text, from cache when fresh.
+    pub fn all(&self) -> Result<Snapshot> {
+        let cached = self
+            .cache
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|(at, _)| at.elapsed() < self.ttl)
+            .map(|(_, snapshot)| snapshot.clone());
+        if let Some(snapshot) = cached {
+            return Ok(snapshot);
+        }
+
+        let snapshot = Arc::new(self.load()?);
+        *self.cache.lock().unwrap() = Some((Instant::now(), snapshot.clone()));
+        Ok(snapshot)
+    }
+
+    /// Drops the cached snapshot so the next read hits the database.
+    pub fn invalidate(&self) {
+        *self.cache.lock().unwrap() = None;
+    }
+
+    /// Receives changes made through this store.
+    ///
+    /// A receiver that falls more than 64 changes behind gets
+    /// [`RecvError::Lagged`](broadcast::error::RecvError::Lagged) and should
+    /// re-read the settings it cares about.
+    pub fn subscribe(&self) -> broadcast::Receiver<SettingChange> {
+        self.changes.subscribe()
+    }
+
+    fn notify(&self, name: &str, value: Option<String>) {
+        // No subscribers is not an error.
+        let _ = self.changes.send(SettingChange {
+            name: name.to_string(),
+            value,
+        });
+    }
+
+    fn load(&self) -> Result<HashMap<String, String>> {
+        let sql = format!("SELECT name, value FROM {}", self.table);
+        self.db
+            .fetch_all(&sql, &[])?
+            .iter()
+            .map(|row| Ok((row.get_string("name")?, row.get_string("value")?)))
+            .collect()
+    }
+}
+
+/// Decodes JSON, falling back to the raw text as a JSON string.
+fn decode<T: DeserializeOwned>(raw: &str) -> Result<T> {
+    match serde_json::from_str(raw) {
+        Ok(v) => Ok(v),
+        Err(e) => {
+            serde_json::from_value(serde_json::Value::String(raw.to_string())).map_err(|_| e.into())
+        }
+    }
+}
+
+fn check_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+    if !valid {
+        bail!("invalid setting name: {name:?}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::port::{Row, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// In-memory `app_settings` table that counts `SELECT`s.
+    #[derive(Default)]
+    struct SettingsDb {
+        rows: Mutex<HashMap<String, String>>,
+        selects: AtomicUsize,
+    }
+
+    impl Db for SettingsDb {
+        fn fetch_one(&self, _: &str, _: &[Param]) -> Result<Option<Row>> {
+            unreachable!()
+        }
+
+        fn fetch_all(&self, sql: &str, _: &[Param]) -> Result<Vec<Row>> {
+            assert_eq!(sql, "SELECT name, value FROM app_settings");
+            self.selects.fetch_add(1, Ordering::SeqCst);
+            Ok(self
+                .rows
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(name, value)| {
+                    let mut r = Row::default();
+                    r.insert("name", Value::Str(name.clone()));
+                    r.insert("value", Value::Str(value.clone()));
+                    r
+                })
+                .collect())
+        }
+
+        fn exec(&self, sql: &str, params: &[Param]) -> Result<u64> {
+            let mut rows = self.rows.lock().unwrap();
+            match params {
+                [Param::Str(name), Param::Str(value), Param::DateTime(_)] => {
+                    assert!(sql.starts_with("INSERT INTO app_settings"));
+                    rows.insert(name.to_string(), value.to_string());
+                    Ok(1)
+                }
+                [Param::Str(name)] => {
+                    assert!(sql.starts_with("DELETE FROM app_settings"));
+                    Ok(rows.remove(*name).map_or(0, |_| 1))
+                }
+                _ => panic!("unexpected statement: {sql}"),
+            }
+        }
+
+        fn exec_returning_last_insert_id(&self, _: &str, _: &[Param]) -> Result<u64> {
+            unreachable!()
+        }
+    }
+
+    fn store() -> (SettingsStore, Arc<SettingsDb>) {
+        let db = Arc::new(SettingsDb::default());
+        db.rows
+            .lock()
+            .unwrap()
+            .insert("site.title".into(), "Hand Typed".into());
+        (SettingsStore::new(db.clone()), db)
+    }
+
+    #[test]
+    fn typed_round_trip_and_plain_text_fallback() {
+        let (settings, _) = store();
+        assert_eq!(
+            settings.get::<String>("site.title").unwrap().as_deref(),
+            Some("Hand Typed")
+        );
+
+        settings.set("signups.open", &false).unwrap();
+        settings.set("limits", &vec![1u32, 2, 3]).unwrap();
+        assert_eq!(settings.get::<bool>("signups.open").unwrap(), Some(false));
+        assert_eq!(
+            settings.get_or::<Vec<u32>>("limits", vec![]).unwrap(),
+            vec![1u32, 2, 3]
+        );
+        assert_eq!(settings.get_or("missing", 7u32).unwrap(), 7);
+        assert!(settings.get::<u32>("site.title").is_err());
+        assert!(settings.set("bad name", &1).is_err());
+    }
+
+    #[test]
+    fn reads_are_cached_until_a_write() {
+        let (settings, db) = store();
+        settings.get::<String>("site.title").unwrap();
+        settings.get::<String>("site.title").unwrap();
+        assert_eq!(db.selects.load(Ordering::SeqCst), 1);
+
+        settings.set("site.title", "New").unwrap();
+        assert_eq!(
+            settings.get::<String>("site.title").unwrap().as_deref(),
+            Some("New")
+        );
+        assert_eq!(db.selects.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn writes_and_deletes_are_broadcast() {
+        let (settings, _) = store();
+        let mut rx = settings.subscribe();
+
+        settings.set("theme", "dark").unwrap();
+        assert!(settings.delete("theme").unwrap());
+        assert!(!settings.delete("theme").unwrap());
+
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            SettingChange {
+                name: "theme".into(),
+                value: Some("\"dark\"".into())
+            }
+        );
+        assert_eq!(rx.try_recv().unwrap().value, None);
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,493 @@
+//! # Long-running Tasks
+//!
+//! [`TaskRegistry`] runs tracked background work (imports, exports, bulk
+//! email) started from a request, so the handler can answer immediately
+//! with a task id and the client follows progress:
+//!
+//! | Route | Response |
+//! |-------|----------|
+//! | `GET /tasks/{id}` | current [`TaskStatus`] as JSON (`404` once unknown or expired) |
+//! | `GET /tasks/{id}/events` | Server-Sent Events: a `status` event per change, ending after the final state |
+//!
+//! Tasks report progress through their [`TaskContext`] and finish with a
+//! JSON result or an error. The crate has no job queue yet, so tasks run
+//! in-process on the tokio runtime: [`max_concurrent`](TaskRegistry::max_concurrent)
+//! bounds how many run at once (the rest wait as `queued`), and state is
+//! lost on restart. Finished tasks are kept for
+//! [`retention`](TaskRegistry::retention) (default one hour).
+//!
+//! Each task runs as job `task:<kind>` through
+//! [`observe_job`](crate::telemetry::jobs::observe_job), so it gets a span,
+//! a success/failure event and, with [`metrics`](TaskRegistry::metrics),
+//! Prometheus series.
+//!
+//! Task ids are random UUIDs; anyone holding one can read the task, so
+//! results should not carry more than the starting user may see.
+//!
+//! # Example
+//! ```rust,no_run
+//! use axum::{extract::State, response::Response, routing::post, Router};
+//! use serde_json::json;
+//! use wzs_web::tasks::{accepted, tasks_router, TaskRegistry};
+//!
+//! async fn start_export(State(tasks): State<TaskRegistry>) -> Response {
+//!     let id = tasks.spawn("export", |ctx| async move {
+//!         for page in 1..=10 {
+//!             // ... export one page ...
+//!             ctx.progress(page, 10);
+//!         }
+//!         Ok(json!({ "url": "/downloads/export.csv" }))
+//!     });
+//!     accepted(&id)
+//! }
+//!
+//! let tasks = TaskRegistry::new().max_concurrent(4);
+//! let app: Router = Router::new()
+//!     .route("/exports", post(start_export))
+//!     .with_state(tasks.clone())
+//!     .merge(tasks_router(tasks));
+//! ```
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use axum::{
+    extract::{Path, State},
+    http::{header::LOCATION, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::get,
+    Json, Router,
+};
+use chrono::Utc;
+use futures_util::{stream, Stream};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::{watch, Semaphore};
+
+use crate::telemetry::jobs::{observe_job, JobMetrics};
+use crate::web::problem::Problem;
+
+/// Default time finished tasks stay readable.
+pub const DEFAULT_RETENTION: Duration = Duration::from_secs(3600);
+
+/// Lifecycle of a task.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    /// Waiting for a [`max_concurrent`](TaskRegistry::max_concurrent) slot.
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl TaskState {
+    /// Whether the task has finished.
+    pub fn is_final(self) -> bool {
+        matches!(self, Self::Succeeded | Self::Failed)
+    }
+}
+
+/// Snapshot of a task, as served by `GET /tasks/{id}`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TaskStatus {
+    pub id: String,
+    /// Name given to [`TaskRegistry::spawn`] (e.g. `"export"`).
+    pub kind: String,
+    pub state: TaskState,
+    /// Units of work done.
+    pub done: u64,
+    /// Total units of work, when known.
+    pub total: Option<u64>,
+    /// Latest progress message.
+    pub message: Option<String>,
+    /// Value returned by a succeeded task.
+    pub result: Option<Value>,
+    /// Error of a failed task.
+    pub error: Option<String>,
+    /// Unix timestamp (seconds).
+    pub created_at: i64,
+    /// Unix timestamp (seconds).
+    pub finished_at: Option<i64>,
+}
+
+/// Progress reporter handed to a running task.
+#[derive(Clone)]
+pub struct TaskContext {
+    tx: Arc<watch::Sender<TaskStatus>>,
+}
+
+impl TaskContext {
+    /// The task id.
+    pub fn id(&self) -> String {
+        self.tx.borrow().id.clone()
+    }
+
+    /// Reports `done` of `total` units.
+    pub fn progress(&self, done: u64, total: u64) {
+        self.tx.send_modify(|s| {
+            s.done = done;
+            s.total = Some(total);
+        });
+    }
+
+    /// Sets the progress message.
+    pub fn message(&self, message: impl Into<String>) {
+        let message = message.into();
+        self.tx.send_modify(|s| s.message = Some(message));
+    }
+}
+
+struct Entry {
+    tx: Arc<watch::Sender<TaskStatus>>,
+    finished: Option<Instant>,
+}
+
+/// Tracks and runs tasks; cheap to clone.
+#[derive(Clone)]
+pub struct TaskRegistry {
+    tasks: Arc<Mutex<HashMap<String, Entry>>>,
+    slots: Option<Arc<Semaphore>>,
+    retention: Duration,
+    metrics: Option<JobMetrics>,
+}
+
+impl Default for TaskRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TaskRegistry {
+    /// Registry without a concurrency limit.
+    pub fn new() -> Self {
+        Self {
+            tasks: Arc::new(Mutex::new(HashMap::new())),
+            slots: None,
+            retention: DEFAULT_RETENTION,
+            metrics: None,
+        }
+    }
+
+    /// Runs at most `n` tasks at once.
+    pub fn max_concurrent(mut self, n: usize) -> Self {
+        self.slots = Some(Arc::new(Semaphore::new(n.max(1))));
+        self
+    }
+
+    /// Sets how long finished tasks stay readable.
+    pub fn retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Records task runs in `metrics` (job `task:<kind>`).
+    pub fn metrics(mut self, metrics: JobMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Starts `task` in the background and returns its id.
+    ///
+    /// Must be called within a tokio runtime.
+    pub fn spawn<F, Fut>(&self, kind: &str, task: F) -> String
+    where
+        F: FnOnce(TaskContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Value>> + Send + 'static,
+    {
+        self.prune();
+        let id = uuid::Uuid::new_v4().to_string();
+        let (tx, _) = watch::channel(TaskStatus {
+            id: id.clone(),
+            kind: kind.to_string(),
+            state: TaskState::Queued,
+            done: 0,
+            total: None,
+            message: None,
+            result: None,
+            error: None,
+            created_at: Utc::now().timestamp(),
+            finished_at: None,
+        });
+        let tx = Arc::new(tx);
+        self.tasks.lock().unwrap().insert(
+            id.clone(),
+            Entry {
+                tx: tx.clone(),
+                finished: None,
+            },
+        );
+
+        let registry = self.clone();
+        let task_id = id.clone();
+        let job = format!("task:{kind}");
+        tokio::spawn(async move {
+            let _permit = match &registry.slots {
+                Some(slots) => slots.clone().acquire_owned().await.ok(),
+                None => None,
+            };
+            tx.send_modify(|s| s.state = TaskState::Running);
+            // A panicking task is reported as failed.
+            let running = tokio::spawn(task(TaskContext { tx: tx.clone() }));
+            let outcome = observe_job(registry.metrics.as_ref(), &job, async {
+                running
+                    .await
+                    .unwrap_or_else(|e| Err(anyhow!("task aborted: {e}")))
+            })
+            .await;
+            tx.send_modify(|s| {
+                match outcome {
+                    Ok(result) => {
+                        s.state = TaskState::Succeeded;
+                        s.result = Some(result);
+                    }
+                    Err(e) => {
+                        s.state = TaskState::Failed;
+                        s.error = Some(e.to_string());
+                    }
+                }
+                s.finished_at = Some(Utc::now().timestamp());
+            });
+            if let Some(entry) = registry.tasks.lock().unwrap().get_mut(&task_id) {
+                entry.finished = Some(Instant::now());
+            }
+        });
+        id
+    }
+
+    /// Current status of task `id`.
+    pub fn status(&self, id: &str) -> Option<TaskStatus> {
+        self.watch(id).map(|rx| rx.borrow().clone())
+    }
+
+    /// Receiver notified on every change of task `id`.
+    pub fn watch(&self, id: &str) -> Option<watch::Receiver<TaskStatus>> {
+        self.prune();
+        self.tasks
+            .lock()
+            .unwrap()
+            .get(id)
+            .map(|entry| entry.tx.subscribe())
+    }
+
+    /// Drops finished tasks older than the retention period.
+    fn prune(&self) {
+        let retention = self.retention;
+        self.tasks
+            .lock()
+            .unwrap()
+            .retain(|_, e| e.finished.is_none_or(|at| at.elapsed() < retention));
+    }
+}
+
+/// `202 Accepted` answer for a started task: `Location: /tasks/{id}` and
+/// `{"id": ...}`.
+pub fn accepted(id: &str) -> Response {
+    (
+        StatusCode::ACCEPTED,
+        [(LOCATION, format!("/tasks/{id}"))],
+        Json(serde_json::json!({ "id": id })),
+    )
+        .into_response()
+}
+
+/// Router serving `GET /tasks/{id}` and `GET /tasks/{id}/events`.
+pub fn tasks_router<S>(registry: TaskRegistry) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/tasks/{id}", get(task_status_handler))
+        .route("/tasks/{id}/events", get(task_events_handler))
+        .with_state(registry)
+}
+
+/// `GET /tasks/{id}`: the task's [`TaskStatus`].
+pub async fn task_status_handler(
+    State(registry): State<TaskRegistry>,
+    Path(id): Path<String>,
+) -> Response {
+    match registry.status(&id) {
+        Some(status) => Json(status).into_response(),
+        None => unknown_task(),
+    }
+}
+
+/// `GET /tasks/{id}/events`: the task's [`TaskStatus`] as `status` events
+/// until it finishes.
+pub async fn task_events_handler(
+    State(registry): State<TaskRegistry>,
+    Path(id): Path<String>,
+) -> Response {
+    match registry.watch(&id) {
+        Some(rx) => Sse::new(status_events(rx))
+            .keep_alive(KeepAlive::default())
+            .into_response(),
+        None => unknown_task(),
+    }
+}
+
+fn unknown_task() -> Response {
+    Problem::not_found("unknown task").into_response()
+}
+
+/// Current status, then one event per change, ending after a final state.
+fn status_events(
+    rx: watch::Receiver<TaskStatus>,
+) -> impl Stream<Item = Result<Event, Infallible>> + Send + 'static {
+    stream::unfold((rx, true, false), |(mut rx, first, ended)| async move {
+        if ended || (!first && rx.changed().await.is_err()) {
+            return None;
+        }
+        let status = rx.borrow_and_update().clone();
+        let event = Event::default()
+            .event("status")
+            .json_data(&status)
+            .unwrap_or_else(|_| Event::default().event("status"));
+        Some((Ok(event), (rx, false, status.state.is_final())))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use axum::body::Body;
+    use http_body_util::BodyExt;
+    use serde_json::json;
+    use tokio::sync::oneshot;
+    use tower::ServiceExt;
+
+    async fn finished(registry: &TaskRegistry, id: &str) -> TaskStatus {
+        let mut rx = registry.watch(id).unwrap();
+        rx.wait_for(|s| s.state.is_final()).await.unwrap().clone()
+    }
+
+    async fn get_body(app: Router, uri: &str) -> (StatusCode, String) {
+        let res = app
+            .oneshot(axum::http::Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = res.status();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn reports_progress_and_result() {
+        let registry = TaskRegistry::new();
+        let (go, wait) = oneshot::channel::<()>();
+        let id = registry.spawn("import", |ctx| async move {
+            ctx.message("reading");
+            ctx.progress(1, 2);
+            wait.await.ok();
+            ctx.progress(2, 2);
+            Ok(json!({ "rows": 2 }))
+        });
+
+        let mut rx = registry.watch(&id).unwrap();
+        let running = rx.wait_for(|s| s.done == 1).await.unwrap().clone();
+        assert_eq!(running.state, TaskState::Running);
+        assert_eq!(
+            (running.total, running.message.as_deref()),
+            (Some(2), Some("reading"))
+        );
+
+        go.send(()).unwrap();
+        let done = finished(&registry, &id).await;
+        assert_eq!(done.state, TaskState::Succeeded);
+        assert_eq!(done.result, Some(json!({ "rows": 2 })));
+        assert!(done.finished_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn errors_and_panics_fail_the_task() {
+        let metrics = JobMetrics::new();
+        let registry = TaskRegistry::new().metrics(metrics.clone());
+        let id = registry.spawn("bad", |_| async { Err(anyhow!("disk full")) });
+        assert_eq!(
+            finished(&registry, &id).await.error.as_deref(),
+            Some("disk full")
+        );
+
+        let id = registry.spawn("panics", |_| async { panic!("boom") });
+        let status = finished(&registry, &id).await;
+        assert_eq!(status.state, TaskState::Failed);
+        assert!(status.error.unwrap().starts_with("task aborted"));
+        assert_eq!(metrics.stats("task:bad").unwrap().failed, 1);
+        assert_eq!(metrics.stats("task:panics").unwrap().failed, 1);
+    }
+
+    #[tokio::test]
+    async fn max_concurrent_queues_the_rest() {
+        let registry = TaskRegistry::new().max_concurrent(1);
+        let (go, wait) = oneshot::channel::<()>();
+        let first = registry.spawn("a", |_| async move {
+            wait.await.ok();
+            Ok(Value::Null)
+        });
+        let second = registry.spawn("b", |_| async { Ok(Value::Null) });
+
+        let mut rx = registry.watch(&first).unwrap();
+        rx.wait_for(|s| s.state == TaskState::Running)
+            .await
+            .unwrap();
+        tokio::task::yield_now().await;
+        assert_eq!(registry.status(&second).unwrap().state, TaskState::Queued);
+
+        go.send(()).unwrap();
+        assert_eq!(
+            finished(&registry, &second).await.state,
+            TaskState::Succeeded
+        );
+    }
+
+    #[tokio::test]
+    async fn finished_tasks_expire() {
+        let registry = TaskRegistry::new().retention(Duration::ZERO);
+        let id = registry.spawn("quick", |_| async { Ok(Value::Null) });
+        finished(&registry, &id).await;
+        tokio::task::yield_now().await;
+        assert!(registry.status(&id).is_none());
+    }
+
+    #[tokio::test]
+    async fn routes_serve_status_and_events() {
+        let registry = TaskRegistry::new();
+        let id = registry.spawn("export", |ctx| async move {
+            ctx.progress(1, 1);
+            Ok(json!("done"))
+        });
+        finished(&registry, &id).await;
+        let app: Router = tasks_router(registry);
+
+        let (status, body) = get_body(app.clone(), &format!("/tasks/{id}")).await;
+        assert_eq!(status, StatusCode::OK);
+        let json: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["state"], "succeeded");
+        assert_eq!(json["result"], "done");
+
+        let (status, body) = get_body(app.clone(), &format!("/tasks/{id}/events")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.starts_with("event: status\ndata: {"));
+        assert!(body.contains(r#""state":"succeeded""#));
+
+        let (status, body) = get_body(app, "/tasks/nope").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body.contains("unknown task"));
+    }
+
+    #[test]
+    fn accepted_points_at_the_status_route() {
+        let res = accepted("abc");
+        assert_eq!(res.status(), StatusCode::ACCEPTED);
+        assert_eq!(res.headers()[LOCATION], "/tasks/abc");
+    }
+}
@@ -0,0 +1,287 @@
+//! # Background Job Telemetry
+//!
+//! Background work — [`TaskRegistry`](crate::tasks::TaskRegistry) tasks,
+//! the periodic [`UploadGc`](crate::web::upload::gc::UploadGc) and
+//! [`IntegrityAudit`](crate::web::upload::integrity::IntegrityAudit) runs,
+//! scheduled [`ReportRunner`](crate::reports::runner::ReportRunner)
+//! reports — fails silently unless someone reads the logs. [`observe_job`]
+//! wraps one run:
+//!
+//! - in a `job` span (field `job`), so every event of the run carries the
+//!   job name
+//! - with a `"job succeeded"` (`INFO`) or `"job failed"` (`WARN`, field
+//!   `error`) event carrying `duration_ms`
+//! - recorded in [`JobMetrics`], when one is configured
+//!
+//! [`JobMetrics`] renders per-job series in the Prometheus text format,
+//! next to the HTTP ones when attached with
+//! [`HttpMetrics::jobs`](crate::web::middleware::metrics::HttpMetrics::jobs):
+//!
+//! - `job_runs_total{job,outcome="success"|"failure"}`
+//! - `job_run_duration_seconds` histogram
+//! - `job_last_run_timestamp_seconds`, `job_last_success_timestamp_seconds`
+//! - `job_next_run_timestamp_seconds` (scheduled jobs)
+//!
+//! Alert on `time() - job_last_success_timestamp_seconds` to catch jobs
+//! that keep failing or stopped running.
+//!
+//! # Example
+//! ```rust
+//! use wzs_web::telemetry::jobs::{observe_job, JobMetrics};
+//!
+//! # async fn run() {
+//! let metrics = JobMetrics::new();
+//! let out: anyhow::Result<u32> = observe_job(Some(&metrics), "nightly_sync", async {
+//!     // ... work ...
+//!     Ok(42)
+//! })
+//! .await;
+//!
+//! assert_eq!(out.unwrap(), 42);
+//! assert_eq!(metrics.stats("nightly_sync").unwrap().succeeded, 1);
+//! assert!(metrics.render().contains("job_runs_total{job=\"nightly_sync\",outcome=\"success\"} 1"));
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use tracing::Instrument;
+
+/// Upper bounds (seconds) of the duration histogram buckets.
+pub const DURATION_BUCKETS: [f64; 9] = [0.1, 0.5, 1.0, 5.0, 15.0, 60.0, 300.0, 900.0, 3600.0];
+
+/// Reads one timestamp gauge from [`JobStats`].
+type Timestamp = fn(&JobStats) -> Option<DateTime<Utc>>;
+
+/// Recorded state of one job.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JobStats {
+    /// Successful runs.
+    pub succeeded: u64,
+    /// Failed runs.
+    pub failed: u64,
+    /// Cumulative counts per [`DURATION_BUCKETS`] bound.
+    pub buckets: [u64; DURATION_BUCKETS.len()],
+    /// Sum of run durations.
+    pub duration_sum: Duration,
+    /// End of the latest run.
+    pub last_run: Option<DateTime<Utc>>,
+    /// End of the latest successful run.
+    pub last_success: Option<DateTime<Utc>>,
+    /// Next scheduled run, for jobs with a schedule.
+    pub next_run: Option<DateTime<Utc>>,
+}
+
+/// Shared per-job counters.
+///
+/// Cloning is cheap; all clones share the same counters.
+#[derive(Clone, Debug, Default)]
+pub struct JobMetrics {
+    jobs: Arc<Mutex<BTreeMap<String, JobStats>>>,
+}
+
+impl JobMetrics {
+    /// Creates empty metrics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records one finished run of `job`.
+    pub fn record(&self, job: &str, success: bool, elapsed: Duration) {
+        let now = Utc::now();
+        let mut jobs = self.jobs.lock().unwrap();
+        let stats = jobs.entry(job.to_string()).or_default();
+        if success {
+            stats.succeeded += 1;
+            stats.last_success = Some(now);
+        } else {
+            stats.failed += 1;
+        }
+        let secs = elapsed.as_secs_f64();
+        for (count, bound) in stats.buckets.iter_mut().zip(DURATION_BUCKETS) {
+            if secs <= bound {
+                *count += 1;
+            }
+        }
+        stats.duration_sum += elapsed;
+        stats.last_run = Some(now);
+    }
+
+    /// Records when `job` is scheduled to run next.
+    pub fn set_next_run(&self, job: &str, at: DateTime<Utc>) {
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.entry(job.to_string()).or_default().next_run = Some(at);
+    }
+
+    /// Stops reporting `job` (e.g. a removed schedule).
+    pub fn forget(&self, job: &str) {
+        self.jobs.lock().unwrap().remove(job);
+    }
+
+    /// Recorded state of `job`.
+    pub fn stats(&self, job: &str) -> Option<JobStats> {
+        self.jobs.lock().unwrap().get(job).cloned()
+    }
+
+    /// Renders the counters in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let jobs = self.jobs.lock().unwrap();
+        let mut out = String::new();
+        if jobs.is_empty() {
+            return out;
+        }
+
+        out.push_str("# TYPE job_runs_total counter\n");
+        for (job, s) in jobs.iter() {
+            let job = escape(job);
+            let _ = writeln!(
+                out,
+                "job_runs_total{{job=\"{job}\",outcome=\"success\"}} {}",
+                s.succeeded
+            );
+            let _ = writeln!(
+                out,
+                "job_runs_total{{job=\"{job}\",outcome=\"failure\"}} {}",
+                s.failed
+            );
+        }
+
+        out.push_str("# TYPE job_run_duration_seconds histogram\n");
+        for (job, s) in jobs.iter() {
+            let job = escape(job);
+            for (bound, count) in DURATION_BUCKETS.iter().zip(s.buckets) {
+                let _ = writeln!(
+                    out,
+                    "job_run_duration_seconds_bucket{{job=\"{job}\",le=\"{bound}\"}} {count}"
+                );
+            }
+            let runs = s.succeeded + s.failed;
+            let _ = writeln!(
+                out,
+                "job_run_duration_seconds_bucket{{job=\"{job}\",le=\"+Inf\"}} {runs}"
+            );
+            let _ = writeln!(
+                out,
+                "job_run_duration_seconds_sum{{job=\"{job}\"}} {}",
+                s.duration_sum.as_secs_f64()
+            );
+            let _ = writeln!(
+                out,
+                "job_run_duration_seconds_count{{job=\"{job}\"}} {runs}"
+            );
+        }
+
+        let gauges: [(&str, Timestamp); 3] = [
+            ("job_last_run_timestamp_seconds", |s| s.last_run),
+            ("job_last_success_timestamp_seconds", |s| s.last_success),
+            ("job_next_run_timestamp_seconds", |s| s.next_run),
+        ];
+        for (name, get) in gauges {
+            let _ = writeln!(out, "# TYPE {name} gauge");
+            for (job, s) in jobs.iter() {
+                if let Some(at) = get(s) {
+                    let secs = at.timestamp_millis() as f64 / 1000.0;
+                    let _ = writeln!(out, "{name}{{job=\"{}\"}} {secs}", escape(job));
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Runs `fut` as one run of `job`: inside a `job` span, followed by a
+/// success or failure event, and recorded in `metrics` if given.
+pub async fn observe_job<T, E, Fut>(
+    metrics: Option<&JobMetrics>,
+    job: &str,
+    fut: Fut,
+) -> Result<T, E>
+where
+    E: Display,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let span = tracing::info_span!("job", job = %job);
+    let started = Instant::now();
+    let out = fut.instrument(span.clone()).await;
+    let elapsed = started.elapsed();
+    let duration_ms = elapsed.as_millis() as u64;
+
+    let _entered = span.enter();
+    match &out {
+        Ok(_) => tracing::info!(job, duration_ms, "job succeeded"),
+        Err(e) => tracing::warn!(job, duration_ms, error = %format!("{e:#}"), "job failed"),
+    }
+    if let Some(metrics) = metrics {
+        metrics.record(job, out.is_ok(), elapsed);
+    }
+    out
+}
+
+/// Escapes a Prometheus label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::anyhow;
+
+    #[tokio::test]
+    async fn observe_records_outcomes() {
+        let metrics = JobMetrics::new();
+        let ok: anyhow::Result<()> = observe_job(Some(&metrics), "sync", async { Ok(()) }).await;
+        assert!(ok.is_ok());
+        let err: anyhow::Result<()> =
+            observe_job(Some(&metrics), "sync", async { Err(anyhow!("boom")) }).await;
+        assert_eq!(err.unwrap_err().to_string(), "boom");
+
+        let stats = metrics.stats("sync").unwrap();
+        assert_eq!((stats.succeeded, stats.failed), (1, 1));
+        assert_eq!(stats.buckets[0], 2);
+        assert!(stats.last_success.is_some());
+        assert!(stats.last_run >= stats.last_success);
+
+        let unrecorded: anyhow::Result<()> = observe_job(None, "other", async { Ok(()) }).await;
+        assert!(unrecorded.is_ok());
+        assert!(metrics.stats("other").is_none());
+    }
+
+    #[test]
+    fn renders_prometheus_series() {
+        let metrics = JobMetrics::new();
+        assert_eq!(metrics.render(), "");
+
+        metrics.record("report:\"daily\"", true, Duration::from_secs(2));
+        metrics.set_next_run(
+            "report:\"daily\"",
+            DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+        );
+        let text = metrics.render();
+        let job = r#"job="report:\"daily\"""#;
+        for line in [
+            format!("job_runs_total{{{job},outcome=\"success\"}} 1\n"),
+            format!("job_runs_total{{{job},outcome=\"failure\"}} 0\n"),
+            format!("job_run_duration_seconds_bucket{{{job},le=\"1\"}} 0\n"),
+            format!("job_run_duration_seconds_bucket{{{job},le=\"5\"}} 1\n"),
+            format!("job_run_duration_seconds_bucket{{{job},le=\"+Inf\"}} 1\n"),
+            format!("job_run_duration_seconds_sum{{{job}}} 2\n"),
+            format!("job_next_run_timestamp_seconds{{{job}}} 1700000000\n"),
+        ] {
+            assert!(text.contains(&line), "{line} in {text}");
+        }
+        assert!(text.contains("job_last_success_timestamp_seconds{"));
+
+        metrics.forget("report:\"daily\"");
+        assert_eq!(metrics.render(), "");
+    }
+}
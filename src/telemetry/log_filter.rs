@@ -0,0 +1,508 @@
+//! # Runtime Log Filter
+//!
+//! [`LogFilter`] decides which `tracing` events and spans are recorded from
+//! `RUST_LOG`-style directives (`info,wzs_web::db=debug`) that can be
+//! changed while the process runs. Overrides may expire, so production
+//! debugging ("`wzs_web::db` at debug for 10 minutes") needs no redeploy.
+//!
+//! The crate does not install a subscriber; plug the filter into the
+//! application's own, e.g. with `tracing-subscriber`:
+//!
+//! ```text
+//! let filter = LogFilter::from_env()?;
+//! let f = filter.clone();
+//! tracing_subscriber::registry()
+//!     .with(fmt::layer().with_filter(filter_fn(move |meta| f.enabled(meta))))
+//!     .init();
+//! ```
+//!
+//! [`log_filter_router`] exposes the filter to operators; it requires a
+//! [`Require`] so it is never mounted unprotected:
+//!
+//! | Route | Effect |
+//! |-------|--------|
+//! | `GET /admin/log-filter` | current [`LogFilterStatus`] |
+//! | `PUT /admin/log-filter` | adds overrides: `{"directives": "wzs_web::db=debug", "ttl_secs": 600}` |
+//! | `DELETE /admin/log-filter` | drops all overrides, back to the base directives |
+//!
+//! A directive is `level` (default for all targets) or `target=level`; the
+//! longest target that is a module-path prefix of the event's target wins,
+//! and overrides win over base directives for the same target.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use axum::Router;
+//! use wzs_web::auth::authorize::Require;
+//! use wzs_web::telemetry::log_filter::{log_filter_router, LogFilter};
+//!
+//! let filter = LogFilter::new("info").unwrap();
+//! filter
+//!     .set("wzs_web::db=debug", Some(Duration::from_secs(600)))
+//!     .unwrap();
+//!
+//! // Mounted inside the layer that authenticates the request.
+//! let app: Router = Router::new().merge(log_filter_router(filter, Require::role("admin")));
+//! ```
+
+use std::cmp::Reverse;
+use std::env;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use axum::{
+    extract::State,
+    middleware::from_fn_with_state,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use tracing::level_filters::LevelFilter;
+use tracing::{info, Metadata};
+
+use crate::auth::authorize::{authorize, Require};
+use crate::web::problem::Problem;
+
+/// One `target=level` (or bare `level`) directive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Directive {
+    /// Module-path prefix; `None` matches every target.
+    pub target: Option<String>,
+    /// Most verbose level recorded for the target.
+    pub level: LevelFilter,
+}
+
+impl Directive {
+    fn matches(&self, target: &str) -> bool {
+        match &self.target {
+            None => true,
+            Some(prefix) => match target.strip_prefix(prefix.as_str()) {
+                Some(rest) => rest.is_empty() || rest.starts_with("::"),
+                None => false,
+            },
+        }
+    }
+
+    fn specificity(&self) -> usize {
+        self.target.as_ref().map_or(0, |t| t.len() + 1)
+    }
+}
+
+impl std::fmt::Display for Directive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.target {
+            Some(target) => write!(f, "{target}={}", self.level),
+            None => write!(f, "{}", self.level),
+        }
+    }
+}
+
+/// Parses a comma-separated directive list (`info,wzs_web::db=debug`).
+///
+/// # Errors
+/// When a level is unknown or a target is empty.
+pub fn parse_directives(spec: &str) -> Result<Vec<Directive>> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|d| {
+            let (target, level) = match d.split_once('=') {
+                Some((t, l)) if !t.trim().is_empty() => (Some(t.trim().to_string()), l),
+                Some(_) => return Err(anyhow!("empty target in directive {d:?}")),
+                None => (None, d),
+            };
+            let level = level
+                .trim()
+                .parse::<LevelFilter>()
+                .with_context(|| format!("invalid level in directive {d:?}"))?;
+            Ok(Directive { target, level })
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+struct Override {
+    directive: Directive,
+    expires_at: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+struct Directives {
+    base: Vec<Directive>,
+    overrides: Vec<Override>,
+}
+
+impl Directives {
+    /// Most specific matching directive; overrides win ties.
+    fn level_for(&self, target: &str, now: Option<Instant>) -> LevelFilter {
+        let overrides = self
+            .overrides
+            .iter()
+            .filter(|o| match (o.expires_at, now) {
+                (Some(at), Some(now)) => at > now,
+                _ => true,
+            })
+            .map(|o| (&o.directive, 1));
+        let base = self.base.iter().map(|d| (d, 0));
+        overrides
+            .chain(base)
+            .filter(|(d, _)| d.matches(target))
+            .max_by_key(|(d, prio)| (d.specificity(), *prio))
+            .map_or(LevelFilter::OFF, |(d, _)| d.level)
+    }
+
+    fn prune(&mut self, now: Instant) -> bool {
+        let before = self.overrides.len();
+        self.overrides
+            .retain(|o| o.expires_at.is_none_or(|at| at > now));
+        self.overrides.len() != before
+    }
+}
+
+/// Runtime-adjustable `tracing` filter; clones share state.
+#[derive(Clone, Debug)]
+pub struct LogFilter {
+    state: Arc<RwLock<Directives>>,
+}
+
+impl LogFilter {
+    /// Creates a filter from base directives.
+    ///
+    /// # Errors
+    /// When `spec` does not parse (see [`parse_directives`]).
+    pub fn new(spec: &str) -> Result<Self> {
+        Ok(Self {
+            state: Arc::new(RwLock::new(Directives {
+                base: parse_directives(spec)?,
+                overrides: Vec::new(),
+            })),
+        })
+    }
+
+    /// Creates a filter from `RUST_LOG` (default `"info"`).
+    pub fn from_env() -> Result<Self> {
+        Self::new(&env::var("RUST_LOG").unwrap_or_else(|_| "info".into()))
+    }
+
+    /// Whether an event or span with `meta` is recorded.
+    pub fn enabled(&self, meta: &Metadata<'_>) -> bool {
+        *meta.level() <= self.level_for(meta.target())
+    }
+
+    /// Effective level for `target`.
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        let state = self.state.read().unwrap();
+        let now = state
+            .overrides
+            .iter()
+            .any(|o| o.expires_at.is_some())
+            .then(Instant::now);
+        state.level_for(target, now)
+    }
+
+    /// Most verbose level any directive enables (a subscriber's max level
+    /// hint).
+    pub fn max_level(&self) -> LevelFilter {
+        let state = self.state.read().unwrap();
+        state
+            .base
+            .iter()
+            .chain(state.overrides.iter().map(|o| &o.directive))
+            .map(|d| d.level)
+            .max()
+            .unwrap_or(LevelFilter::OFF)
+    }
+
+    /// Adds override directives, replacing earlier overrides for the same
+    /// targets. With a `ttl` they lapse after that long.
+    ///
+    /// # Errors
+    /// When `spec` does not parse or `ttl` is too large to represent;
+    /// nothing is changed then.
+    pub fn set(&self, spec: &str, ttl: Option<Duration>) -> Result<()> {
+        let directives = parse_directives(spec)?;
+        let expires_at = match ttl {
+            Some(ttl) => Some(
+                Instant::now()
+                    .checked_add(ttl)
+                    .ok_or_else(|| anyhow!("ttl of {}s is too large", ttl.as_secs()))?,
+            ),
+            None => None,
+        };
+        {
+            let mut state = self.state.write().unwrap();
+            for directive in directives {
+                state
+                    .overrides
+                    .retain(|o| o.directive.target != directive.target);
+                state.overrides.push(Override {
+                    directive,
+                    expires_at,
+                });
+            }
+        }
+        info!(
+            directives = spec,
+            ttl_secs = ttl.map(|t| t.as_secs()),
+            "log filter override set"
+        );
+        tracing::callsite::rebuild_interest_cache();
+        Ok(())
+    }
+
+    /// Drops all overrides.
+    pub fn reset(&self) {
+        self.state.write().unwrap().overrides.clear();
+        info!("log filter overrides cleared");
+        tracing::callsite::rebuild_interest_cache();
+    }
+
+    /// Current base directives and live overrides.
+    pub fn status(&self) -> LogFilterStatus {
+        let now = Instant::now();
+        let mut state = self.state.write().unwrap();
+        if state.prune(now) {
+            tracing::callsite::rebuild_interest_cache();
+        }
+        let mut overrides: Vec<_> = state
+            .overrides
+            .iter()
+            .map(|o| OverrideStatus {
+                directive: o.directive.to_string(),
+                expires_in_secs: o
+                    .expires_at
+                    .map(|at| at.saturating_duration_since(now).as_secs()),
+            })
+            .collect();
+        overrides.sort_by_key(|o| Reverse(o.expires_in_secs));
+        LogFilterStatus {
+            base: join(&state.base),
+            overrides,
+        }
+    }
+}
+
+fn join(directives: &[Directive]) -> String {
+    directives
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Snapshot returned by [`LogFilter::status`] and `GET /admin/log-filter`.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct LogFilterStatus {
+    /// Base directives (`RUST_LOG`).
+    pub base: String,
+    /// Live overrides.
+    pub overrides: Vec<OverrideStatus>,
+}
+
+/// One override in a [`LogFilterStatus`].
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct OverrideStatus {
+    pub directive: String,
+    /// Seconds until it lapses; `None` lasts until reset.
+    pub expires_in_secs: Option<u64>,
+}
+
+/// Body of `PUT /admin/log-filter`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SetLogFilter {
+    pub directives: String,
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+}
+
+/// Router serving `GET`, `PUT` and `DELETE /admin/log-filter`, guarded by
+/// `require` (see [`authorize`]).
+pub fn log_filter_router<S>(filter: LogFilter, require: Require) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route(
+            "/admin/log-filter",
+            get(get_log_filter)
+                .put(put_log_filter)
+                .delete(delete_log_filter),
+        )
+        .layer(from_fn_with_state(require, authorize))
+        .with_state(filter)
+}
+
+async fn get_log_filter(State(filter): State<LogFilter>) -> Json<LogFilterStatus> {
+    Json(filter.status())
+}
+
+async fn put_log_filter(
+    State(filter): State<LogFilter>,
+    Json(body): Json<SetLogFilter>,
+) -> Response {
+    let ttl = body.ttl_secs.map(Duration::from_secs);
+    match filter.set(&body.directives, ttl) {
+        Ok(()) => Json(filter.status()).into_response(),
+        Err(e) => Problem::bad_request(format!("{e:#}")).into_response(),
+    }
+}
+
+async fn delete_log_filter(State(filter): State<LogFilter>) -> Json<LogFilterStatus> {
+    filter.reset();
+    Json(filter.status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use axum::{body::Body, http::Request, Extension};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+    use tracing::{span, subscriber::Interest, Event, Subscriber};
+
+    use crate::auth::CurrentUser;
+
+    #[test]
+    fn parses_directives() {
+        let ds = parse_directives(" info , wzs_web::db=DEBUG,,").unwrap();
+        assert_eq!(ds.len(), 2);
+        assert_eq!(ds[0].to_string(), "info");
+        assert_eq!(ds[1].to_string(), "wzs_web::db=debug");
+        assert!(parse_directives("wzs_web=loud").is_err());
+        assert!(parse_directives("=debug").is_err());
+    }
+
+    #[test]
+    fn most_specific_directive_wins() {
+        let f = LogFilter::new("warn,wzs_web=info,wzs_web::db=trace").unwrap();
+        assert_eq!(f.level_for("hyper::proto"), LevelFilter::WARN);
+        assert_eq!(f.level_for("wzs_web::web"), LevelFilter::INFO);
+        assert_eq!(f.level_for("wzs_web::db::query"), LevelFilter::TRACE);
+        // Prefixes only match on module boundaries.
+        assert_eq!(f.level_for("wzs_webx"), LevelFilter::WARN);
+        assert_eq!(LogFilter::new("").unwrap().level_for("x"), LevelFilter::OFF);
+        assert_eq!(f.max_level(), LevelFilter::TRACE);
+    }
+
+    #[test]
+    fn overrides_replace_and_expire() {
+        let f = LogFilter::new("info,wzs_web::db=warn").unwrap();
+        f.set("wzs_web::db=debug", None).unwrap();
+        f.set(
+            "wzs_web::db=trace,wzs_web::auth=debug",
+            Some(Duration::ZERO),
+        )
+        .unwrap();
+        // Both new overrides lapsed immediately; the replaced one is gone.
+        assert_eq!(f.level_for("wzs_web::db"), LevelFilter::WARN);
+        assert_eq!(f.level_for("wzs_web::auth"), LevelFilter::INFO);
+
+        f.set("wzs_web::db=debug", Some(Duration::from_secs(600)))
+            .unwrap();
+        assert_eq!(f.level_for("wzs_web::db"), LevelFilter::DEBUG);
+        let status = f.status();
+        assert_eq!(status.base, "info,wzs_web::db=warn");
+        assert_eq!(status.overrides.len(), 1);
+        assert_eq!(status.overrides[0].directive, "wzs_web::db=debug");
+        assert!(status.overrides[0].expires_in_secs.unwrap() > 590);
+
+        assert!(f.set("oops=???", None).is_err());
+        assert!(f
+            .set("wzs_web::db=trace", Some(Duration::from_secs(u64::MAX)))
+            .is_err());
+        assert_eq!(f.level_for("wzs_web::db"), LevelFilter::DEBUG);
+        f.reset();
+        assert_eq!(f.level_for("wzs_web::db"), LevelFilter::WARN);
+        assert!(f.status().overrides.is_empty());
+    }
+
+    struct Counting {
+        filter: LogFilter,
+        events: AtomicUsize,
+    }
+
+    impl Subscriber for Counting {
+        fn register_callsite(&self, _: &'static Metadata<'static>) -> Interest {
+            Interest::sometimes()
+        }
+        fn enabled(&self, meta: &Metadata<'_>) -> bool {
+            self.filter.enabled(meta)
+        }
+        fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+            span::Id::from_u64(1)
+        }
+        fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+        fn event(&self, _: &Event<'_>) {
+            self.events.fetch_add(1, Ordering::SeqCst);
+        }
+        fn enter(&self, _: &span::Id) {}
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    #[test]
+    fn filters_events_of_a_subscriber() {
+        let filter = LogFilter::new("warn").unwrap();
+        let subscriber = Arc::new(Counting {
+            filter: filter.clone(),
+            events: AtomicUsize::new(0),
+        });
+        tracing::subscriber::with_default(subscriber.clone(), || {
+            tracing::debug!(target: "wzs_web::db", "hidden");
+            filter.set("wzs_web::db=debug", None).unwrap();
+            tracing::debug!(target: "wzs_web::db", "shown");
+            tracing::debug!(target: "wzs_web::web", "hidden");
+        });
+        // `set` logs at info for its own target, which stays filtered.
+        assert_eq!(subscriber.events.load(Ordering::SeqCst), 1);
+    }
+
+    async fn call(app: Router, method: &str, body: &str, admin: bool) -> (StatusCode, String) {
+        let app = match admin {
+            true => app.layer(Extension(CurrentUser::new("1").with_roles(["admin"]))),
+            false => app,
+        };
+        let req = Request::builder()
+            .method(method)
+            .uri("/admin/log-filter")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        let status = res.status();
+        let bytes = res.into_body().collect().await.unwrap().to_bytes();
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn router_is_protected_and_updates_the_filter() {
+        let filter = LogFilter::new("info").unwrap();
+        let app = log_filter_router(filter.clone(), Require::role("admin"));
+
+        let (status, _) = call(app.clone(), "GET", "", false).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let body = r#"{"directives": "wzs_web::db=debug", "ttl_secs": 600}"#;
+        let (status, json) = call(app.clone(), "PUT", body, true).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(json.contains("wzs_web::db=debug"), "{json}");
+        assert_eq!(filter.level_for("wzs_web::db"), LevelFilter::DEBUG);
+
+        let (status, _) = call(app.clone(), "PUT", r#"{"directives": "x=loud"}"#, true).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let body = format!(r#"{{"directives": "x=trace", "ttl_secs": {}}}"#, u64::MAX);
+        let (status, json) = call(app.clone(), "PUT", &body, true).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(json.contains("too large"), "{json}");
+
+        let (status, json) = call(app, "DELETE", "", true).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(json.contains(r#""overrides":[]"#), "{json}");
+        assert_eq!(filter.level_for("wzs_web::db"), LevelFilter::INFO);
+    }
+}
@@ -0,0 +1,9 @@
+//! # Multi-Tenancy
+//!
+//! Support for serving several white-label tenants from one process.
+//!
+//! - [`config`] — per-tenant overrides of selected settings (CORS origins,
+//!   upload quota, email sender identity, feature flags) loaded from the
+//!   [`Db`](crate::db::port::Db) port and cached with a TTL
+
+pub mod config;
@@ -0,0 +1,400 @@
+//! # Per-Tenant Configuration Overrides
+//!
+//! [`TenantConfigProvider`] loads a [`TenantConfig`] per tenant from a
+//! key/value table and caches it for a TTL (default 60 seconds).
+//!
+//! Only the overridden settings are stored; everything else falls back to
+//! the process-wide [`AppConfig`](crate::config::app::AppConfig) values via
+//! the `*_for` helpers.
+//!
+//! Expected schema (MySQL):
+//!
+//! ```sql
+//! CREATE TABLE tenant_settings (
+//!     tenant_id VARCHAR(64)  NOT NULL,
+//!     name      VARCHAR(128) NOT NULL,
+//!     value     TEXT         NOT NULL,
+//!     PRIMARY KEY (tenant_id, name)
+//! );
+//! ```
+//!
+//! Recognized `name`s:
+//!
+//! | Name | Value |
+//! |------|-------|
+//! | `cors_origins` | Comma-separated origins |
+//! | `upload_quota_bytes` | Integer |
+//! | `mail_from_email` | Sender address |
+//! | `mail_from_name` | Sender display name |
+//! | `feature.<flag>` | `true`/`false` (`1`/`0`, `yes`/`no`, `on`/`off`) |
+//!
+//! Unknown names and unparsable values are ignored with a warning.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use wzs_web::config::app::AppConfig;
+//! use wzs_web::db::port::Db;
+//! use wzs_web::tenant::config::TenantConfigProvider;
+//!
+//! fn cors_for_tenant(db: Arc<dyn Db>, app: &AppConfig) -> anyhow::Result<()> {
+//!     let provider = TenantConfigProvider::new(db);
+//!     let tenant = provider.get("acme")?;
+//!
+//!     let cors = tenant.cors_for(&app.cors);
+//!     let beta = tenant.feature("beta_search", false);
+//!     println!("{} {beta}", cors.env);
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use crate::config::{mail::MailConfig, web::CorsConfig};
+use crate::db::port::{Db, Param};
+use crate::db::repository::ident;
+
+const FEATURE_PREFIX: &str = "feature.";
+
+/// Settings overridden for one tenant. `None` means "use the default".
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TenantConfig {
+    /// Allowed CORS origins.
+    pub cors_origins: Option<Vec<String>>,
+    /// Upload quota in bytes.
+    pub upload_quota_bytes: Option<u64>,
+    /// Sender address for outgoing email.
+    pub mail_from_email: Option<String>,
+    /// Sender display name for outgoing email.
+    pub mail_from_name: Option<String>,
+    /// Feature flags set for this tenant.
+    pub features: HashMap<String, bool>,
+}
+
+impl TenantConfig {
+    /// Returns `base` with the tenant's origins applied.
+    pub fn cors_for(&self, base: &CorsConfig) -> CorsConfig {
+        let mut cors = base.clone();
+        if let Some(origins) = &self.cors_origins {
+            cors.env = origins.join(",");
+        }
+        cors
+    }
+
+    /// Returns `base` with the tenant's sender identity applied.
+    pub fn mail_for(&self, base: &MailConfig) -> MailConfig {
+        let mut mail = base.clone();
+        if let Some(email) = &self.mail_from_email {
+            mail.from_email = email.clone();
+        }
+        if let Some(name) = &self.mail_from_name {
+            mail.from_name = name.clone();
+        }
+        mail
+    }
+
+    /// Returns the tenant's upload quota, or `default`.
+    pub fn upload_quota(&self, default: Option<u64>) -> Option<u64> {
+        self.upload_quota_bytes.or(default)
+    }
+
+    /// Returns the tenant's value for feature `name`, or `default`.
+    pub fn feature(&self, name: &str, default: bool) -> bool {
+        self.features.get(name).copied().unwrap_or(default)
+    }
+
+    fn apply(&mut self, tenant_id: &str, name: &str, value: &str) {
+        let value = value.trim();
+        match name {
+            "cors_origins" => {
+                self.cors_origins = Some(
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                )
+            }
+            "upload_quota_bytes" => match value.parse() {
+                Ok(n) => self.upload_quota_bytes = Some(n),
+                Err(_) => tracing::warn!(tenant_id, name, value, "invalid tenant setting"),
+            },
+            "mail_from_email" => self.mail_from_email = Some(value.to_string()),
+            "mail_from_name" => self.mail_from_name = Some(value.to_string()),
+            _ => {
+                let Some(flag) = name.strip_prefix(FEATURE_PREFIX) else {
+                    tracing::warn!(tenant_id, name, "unknown tenant setting");
+                    return;
+                };
+                match parse_bool(value) {
+                    Some(b) => {
+                        self.features.insert(flag.to_string(), b);
+                    }
+                    None => tracing::warn!(tenant_id, name, value, "invalid tenant setting"),
+                }
+            }
+        }
+    }
+}
+
+fn parse_bool(s: &str) -> Option<bool> {
+    match s.to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Loads and caches [`TenantConfig`]s from the database.
+pub struct TenantConfigProvider {
+    db: Arc<dyn Db>,
+    table: String,
+    ttl: Duration,
+    cache: Mutex<HashMap<String, (Instant, Arc<TenantConfig>)>>,
+}
+
+impl TenantConfigProvider {
+    /// Default table name.
+    pub const DEFAULT_TABLE: &'static str = "tenant_settings";
+
+    /// Default cache lifetime.
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(60);
+
+    /// Creates a provider backed by the default table.
+    pub fn new(db: Arc<dyn Db>) -> Self {
+        Self {
+            db,
+            table: Self::DEFAULT_TABLE.into(),
+            ttl: Self::DEFAULT_TTL,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Creates a provider backed by a custom table.
+    ///
+    /// # Errors
+    /// Returns an error if `table` is not a plain SQL identifier.
+    pub fn with_table(db: Arc<dyn Db>, table: impl Into<String>) -> Result<Self> {
+        let table = table.into();
+        ident(&table)?;
+        Ok(Self {
+            table,
+            ..Self::new(db)
+        })
+    }
+
+    /// Sets the cache lifetime.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Returns the overrides for `tenant_id`, from cache when fresh.
+    ///
+    /// A tenant without rows yields an empty [`TenantConfig`].
+    pub fn get(&self, tenant_id: &str) -> Result<Arc<TenantConfig>> {
+        let cached = self
+            .cache
+            .lock()
+            .unwrap()
+            .get(tenant_id)
+            .filter(|(at, _)| at.elapsed() < self.ttl)
+            .map(|(_, cfg)| cfg.clone());
+        if let Some(cfg) = cached {
+            return Ok(cfg);
+        }
+
+        let cfg = Arc::new(self.load(tenant_id)?);
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(tenant_id.to_string(), (Instant::now(), cfg.clone()));
+        Ok(cfg)
+    }
+
+    /// Drops the cached entry for `tenant_id`.
+    pub fn invalidate(&self, tenant_id: &str) {
+        self.cache.lock().unwrap().remove(tenant_id);
+    }
+
+    /// Drops every cached entry.
+    pub fn invalidate_all(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    fn load(&self, tenant_id: &str) -> Result<TenantConfig> {
+        let sql = format!("SELECT name, value FROM {} WHERE tenant_id = ?", self.table);
+        let mut cfg = TenantConfig::default();
+        for row in self.db.fetch_all(&sql, &[Param::Str(tenant_id)])? {
+            cfg.apply(
+                tenant_id,
+                &row.get_string("name")?,
+                &row.get_string("value")?,
+            );
+        }
+        Ok(cfg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::port::{Row, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Serves fixed settings and counts queries.
+    struct SettingsDb {
+        rows: Vec<(&'static str, &'static str, &'static str)>,
+        queries: AtomicUsize,
+    }
+
+    impl SettingsDb {
+        fn new(rows: Vec<(&'static str, &'static str, &'static str)>) -> Arc<Self> {
+            Arc::new(Self {
+                rows,
+                queries: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    impl Db for SettingsDb {
+        fn fetch_one(&self, _: &str, _: &[Param]) -> Result<Option<Row>> {
+            unreachable!()
+        }
+
+        fn fetch_all(&self, sql: &str, params: &[Param]) -> Result<Vec<Row>> {
+            self.queries.fetch_add(1, Ordering::SeqCst);
+            assert!(sql.contains("FROM tenant_settings WHERE tenant_id = ?"));
+            let Param::Str(tenant) = params[0] else {
+                panic!("unexpected param")
+            };
+            Ok(self
+                .rows
+                .iter()
+                .filter(|(t, _, _)| *t == tenant)
+                .map(|(_, name, value)| {
+                    let mut r = Row::default();
+                    r.insert("name", Value::Str((*name).into()));
+                    r.insert("value", Value::Str((*value).into()));
+                    r
+                })
+                .collect())
+        }
+
+        fn exec(&self, _: &str, _: &[Param]) -> Result<u64> {
+            unreachable!()
+        }
+
+        fn exec_returning_last_insert_id(&self, _: &str, _: &[Param]) -> Result<u64> {
+            unreachable!()
+        }
+    }
+
+    fn rows() -> Vec<(&'static str, &'static str, &'static str)> {
+        vec![
+            (
+                "acme",
+                "cors_origins",
+                "https://acme.example, https://app.acme.example",
+            ),
+            ("acme", "upload_quota_bytes", "1048576"),
+            ("acme", "mail_from_email", "noreply@acme.example"),
+            ("acme", "mail_from_name", "Acme"),
+            ("acme", "feature.beta", "on"),
+            ("acme", "feature.legacy", "0"),
+            ("acme", "feature.broken", "maybe"),
+            ("acme", "unknown", "x"),
+            ("other", "upload_quota_bytes", "5"),
+        ]
+    }
+
+    fn base_mail() -> MailConfig {
+        MailConfig {
+            host: "smtp.example.com".into(),
+            port: 587,
+            username: "u".into(),
+            password: "p".into(),
+            from_email: "noreply@example.com".into(),
+            from_name: "Notifier".into(),
+            notify_to: vec![],
+            dkim_selectors: vec![],
+        }
+    }
+
+    #[test]
+    fn parses_overrides_and_applies_them_to_defaults() {
+        let provider = TenantConfigProvider::new(SettingsDb::new(rows()));
+        let cfg = provider.get("acme").unwrap();
+
+        assert_eq!(
+            cfg.cors_origins.as_deref(),
+            Some(
+                &[
+                    "https://acme.example".to_string(),
+                    "https://app.acme.example".into()
+                ][..]
+            )
+        );
+        assert_eq!(cfg.upload_quota(Some(10)), Some(1_048_576));
+        assert!(cfg.feature("beta", false));
+        assert!(!cfg.feature("legacy", true));
+        assert!(cfg.feature("broken", true));
+
+        let cors = cfg.cors_for(&CorsConfig {
+            enabled: true,
+            env: "https://default.example".into(),
+            credentials: true,
+        });
+        assert_eq!(cors.env, "https://acme.example,https://app.acme.example");
+        assert!(cors.credentials);
+
+        let mail = cfg.mail_for(&base_mail());
+        assert_eq!(mail.from_email, "noreply@acme.example");
+        assert_eq!(mail.from_name, "Acme");
+        assert_eq!(mail.host, "smtp.example.com");
+    }
+
+    #[test]
+    fn tenant_without_rows_uses_defaults() {
+        let provider = TenantConfigProvider::new(SettingsDb::new(rows()));
+        let cfg = provider.get("nobody").unwrap();
+
+        assert_eq!(*cfg, TenantConfig::default());
+        assert_eq!(cfg.upload_quota(Some(10)), Some(10));
+        let mail = cfg.mail_for(&base_mail());
+        assert_eq!(mail.from_email, "noreply@example.com");
+        assert_eq!(mail.from_name, "Notifier");
+    }
+
+    #[test]
+    fn caches_until_ttl_or_invalidation() {
+        let db = SettingsDb::new(rows());
+        let provider = TenantConfigProvider::new(db.clone());
+
+        provider.get("acme").unwrap();
+        provider.get("acme").unwrap();
+        provider.get("other").unwrap();
+        assert_eq!(db.queries.load(Ordering::SeqCst), 2);
+
+        provider.invalidate("acme");
+        provider.get("acme").unwrap();
+        assert_eq!(db.queries.load(Ordering::SeqCst), 3);
+
+        let provider = TenantConfigProvider::new(db.clone()).ttl(Duration::ZERO);
+        provider.get("acme").unwrap();
+        provider.get("acme").unwrap();
+        assert_eq!(db.queries.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn rejects_invalid_table_name() {
+        let db = SettingsDb::new(vec![]);
+        assert!(TenantConfigProvider::with_table(db.clone(), "t; DROP").is_err());
+        assert!(TenantConfigProvider::with_table(db, "tenant_overrides").is_ok());
+    }
+}
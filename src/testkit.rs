@@ -0,0 +1,8 @@
+//! # Test Kit
+//!
+//! Helpers for application test suites built on this crate.
+//!
+//! - [`replay`] — serve or verify request fixtures recorded by
+//!   [`record_fixtures`](crate::web::middleware::fixtures::record_fixtures)
+
+pub mod replay;
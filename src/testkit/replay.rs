@@ -0,0 +1,360 @@
+//! Replaying recorded [`Fixture`]s.
+//!
+//! A [`FixtureSet`] loads the files written by
+//! [`record_fixtures`](crate::web::middleware::fixtures::record_fixtures)
+//! and uses them two ways:
+//!
+//! - [`router`](FixtureSet::router) — a mock backend answering each
+//!   recorded request with its recorded response, for frontend tests
+//! - [`verify`](FixtureSet::verify) — a contract test sending each recorded
+//!   request to the real router and reporting where the response drifted
+//!
+//! Redacted values are wildcards: a `[REDACTED]` header is not sent, and a
+//! `[REDACTED]` JSON field in the recorded response matches any value.
+//! Requests that need credentials get them from
+//! [`verify_with`](FixtureSet::verify_with).
+//!
+//! # Example
+//! ```rust,no_run
+//! use axum::{routing::get, Router};
+//! use wzs_web::testkit::replay::FixtureSet;
+//!
+//! # async fn contract_test() -> anyhow::Result<()> {
+//! let app: Router = Router::new().route("/api/me", get(|| async { "me" }));
+//! let fixtures = FixtureSet::load_dir("tests/fixtures/api")?;
+//!
+//! let mismatches = fixtures.verify(app).await;
+//! assert!(mismatches.is_empty(), "{mismatches:#?}");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+    Router,
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use crate::web::middleware::fixtures::{Fixture, FixtureBody, REDACTED};
+
+/// Recorded fixtures, in file name order.
+#[derive(Clone, Debug, Default)]
+pub struct FixtureSet {
+    fixtures: Vec<(String, Fixture)>,
+}
+
+/// Differences between a fixture and the live response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Fixture name (file name when loaded from disk).
+    pub fixture: String,
+    /// One line per difference.
+    pub differences: Vec<String>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.fixture, self.differences.join("; "))
+    }
+}
+
+impl FixtureSet {
+    /// Wraps fixtures built in code; names come from [`Fixture::file_name`].
+    pub fn new(fixtures: Vec<Fixture>) -> Self {
+        Self {
+            fixtures: fixtures.into_iter().map(|f| (f.file_name(), f)).collect(),
+        }
+    }
+
+    /// Loads every `*.json` file in `dir`.
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir).with_context(|| format!("read {}", dir.display()))? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let fixtures = paths
+            .iter()
+            .map(|path| {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                Ok((name.into_owned(), Fixture::load(path)?))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { fixtures })
+    }
+
+    /// Number of fixtures.
+    pub fn len(&self) -> usize {
+        self.fixtures.len()
+    }
+
+    /// `true` if there are no fixtures.
+    pub fn is_empty(&self) -> bool {
+        self.fixtures.is_empty()
+    }
+
+    /// The fixtures with their names.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Fixture)> {
+        self.fixtures.iter().map(|(name, f)| (name.as_str(), f))
+    }
+
+    /// A router answering recorded requests with their recorded responses.
+    ///
+    /// Requests match on method, path and query, falling back to method and
+    /// path; anything else gets `404`. Redacted response headers are left out.
+    pub fn router(&self) -> Router {
+        let fixtures: Arc<Vec<Fixture>> =
+            Arc::new(self.fixtures.iter().map(|(_, f)| f.clone()).collect());
+        Router::new().fallback(move |req: Request| {
+            let fixtures = fixtures.clone();
+            async move {
+                let (method, path) = (req.method().as_str(), req.uri().path());
+                let query = req.uri().query();
+                let same = |f: &&Fixture| f.request.method == method && f.request.path == path;
+                let found = fixtures
+                    .iter()
+                    .filter(same)
+                    .find(|f| f.request.query.as_deref() == query)
+                    .or_else(|| fixtures.iter().find(same));
+                match found {
+                    Some(f) => recorded_response(f),
+                    None => StatusCode::NOT_FOUND.into_response(),
+                }
+            }
+        })
+    }
+
+    /// Sends every recorded request to `app` and returns the fixtures whose
+    /// responses differ.
+    pub async fn verify(&self, app: Router) -> Vec<Mismatch> {
+        self.verify_with(app, |_| {}).await
+    }
+
+    /// Like [`verify`](Self::verify), letting `prepare` adjust each request
+    /// first (e.g. add the session cookie that was redacted).
+    pub async fn verify_with<F>(&self, app: Router, prepare: F) -> Vec<Mismatch>
+    where
+        F: Fn(&mut Request),
+    {
+        let mut mismatches = Vec::new();
+        for (name, fixture) in &self.fixtures {
+            let mut req = to_request(fixture);
+            prepare(&mut req);
+            let differences = match app.clone().oneshot(req).await {
+                Ok(res) => compare(fixture, res).await,
+                Err(e) => vec![format!("request failed: {e}")],
+            };
+            if !differences.is_empty() {
+                mismatches.push(Mismatch {
+                    fixture: name.clone(),
+                    differences,
+                });
+            }
+        }
+        mismatches
+    }
+}
+
+/// Rebuilds the recorded request; redacted headers are left out.
+pub fn to_request(fixture: &Fixture) -> Request {
+    let req = &fixture.request;
+    let uri = match &req.query {
+        Some(q) => format!("{}?{q}", req.path),
+        None => req.path.clone(),
+    };
+    let mut out = Request::new(Body::from(req.body.to_bytes()));
+    *out.method_mut() = Method::from_bytes(req.method.as_bytes()).unwrap_or(Method::GET);
+    *out.uri_mut() = uri.parse().unwrap_or_default();
+    copy_headers(&req.headers, out.headers_mut());
+    out
+}
+
+fn recorded_response(fixture: &Fixture) -> Response {
+    let res = &fixture.response;
+    let status = StatusCode::from_u16(res.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let mut out = (status, res.body.to_bytes()).into_response();
+    copy_headers(&res.headers, out.headers_mut());
+    out
+}
+
+fn copy_headers(from: &BTreeMap<String, String>, to: &mut axum::http::HeaderMap) {
+    for (name, value) in from {
+        if value == REDACTED || name == header::CONTENT_LENGTH.as_str() {
+            continue;
+        }
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            to.insert(name, value);
+        }
+    }
+}
+
+async fn compare(fixture: &Fixture, res: Response) -> Vec<String> {
+    let expected = &fixture.response;
+    let mut differences = Vec::new();
+    if res.status().as_u16() != expected.status {
+        differences.push(format!(
+            "status: expected {}, got {}",
+            expected.status,
+            res.status().as_u16()
+        ));
+    }
+    let content_type = header::CONTENT_TYPE.as_str();
+    if let Some(want) = expected.headers.get(content_type) {
+        let got = res
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        if got != want {
+            differences.push(format!("content-type: expected {want}, got {got}"));
+        }
+    }
+
+    let bytes = match axum::body::to_bytes(res.into_body(), usize::MAX).await {
+        Ok(b) => b,
+        Err(e) => {
+            differences.push(format!("body: {e}"));
+            return differences;
+        }
+    };
+    match &expected.body {
+        FixtureBody::Json(want) => match serde_json::from_slice::<Value>(&bytes) {
+            Ok(got) => compare_json("$", want, &got, &mut differences),
+            Err(_) => differences.push("body: expected JSON".into()),
+        },
+        other if other.to_bytes() != bytes => differences.push("body differs".into()),
+        _ => {}
+    }
+    differences
+}
+
+fn compare_json(at: &str, want: &Value, got: &Value, differences: &mut Vec<String>) {
+    match (want, got) {
+        (Value::String(s), _) if s == REDACTED => {}
+        (Value::Object(w), Value::Object(g)) => {
+            for (key, wv) in w {
+                match g.get(key) {
+                    Some(gv) => compare_json(&format!("{at}.{key}"), wv, gv, differences),
+                    None => differences.push(format!("{at}.{key}: missing")),
+                }
+            }
+            for key in g.keys().filter(|k| !w.contains_key(*k)) {
+                differences.push(format!("{at}.{key}: unexpected"));
+            }
+        }
+        (Value::Array(w), Value::Array(g)) if w.len() == g.len() => {
+            for (i, (wv, gv)) in w.iter().zip(g).enumerate() {
+                compare_json(&format!("{at}[{i}]"), wv, gv, differences);
+            }
+        }
+        (w, g) if w != g => differences.push(format!("{at}: expected {w}, got {g}")),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{routing::get, Json};
+    use serde_json::json;
+
+    use crate::web::middleware::fixtures::{FixtureRequest, FixtureResponse};
+
+    fn fixture(query: Option<&str>, body: Value) -> Fixture {
+        Fixture {
+            request: FixtureRequest {
+                method: "GET".into(),
+                path: "/api/me".into(),
+                query: query.map(str::to_string),
+                headers: BTreeMap::from([("cookie".into(), REDACTED.into())]),
+                body: FixtureBody::Empty,
+            },
+            response: FixtureResponse {
+                status: 200,
+                headers: BTreeMap::from([("content-type".into(), "application/json".into())]),
+                body: FixtureBody::Json(body),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn mock_router_serves_recorded_responses() {
+        let set = FixtureSet::new(vec![
+            fixture(None, json!({ "name": "default" })),
+            fixture(Some("lang=ja"), json!({ "name": "ja" })),
+        ]);
+        let router = set.router();
+
+        let body = |res: Response| async move {
+            let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<Value>(&bytes).unwrap()["name"].clone()
+        };
+        let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+
+        let res = router
+            .clone()
+            .oneshot(get("/api/me?lang=ja"))
+            .await
+            .unwrap();
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(body(res).await, "ja");
+        let res = router
+            .clone()
+            .oneshot(get("/api/me?lang=fr"))
+            .await
+            .unwrap();
+        assert_eq!(body(res).await, "default");
+        let res = router.oneshot(get("/api/other")).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn verify_reports_drift_and_treats_redacted_as_wildcard() {
+        let app = Router::new().route(
+            "/api/me",
+            get(|| async { Json(json!({ "id": 1, "token": "abc", "role": "admin" })) }),
+        );
+
+        let matching = FixtureSet::new(vec![fixture(
+            None,
+            json!({ "id": 1, "token": REDACTED, "role": "admin" }),
+        )]);
+        assert!(matching.verify(app.clone()).await.is_empty());
+
+        let drifted = FixtureSet::new(vec![fixture(
+            None,
+            json!({ "id": 2, "token": REDACTED, "name": "x" }),
+        )]);
+        let mismatches = drifted.verify(app).await;
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(
+            mismatches[0].differences,
+            [
+                "$.id: expected 2, got 1",
+                "$.name: missing",
+                "$.role: unexpected"
+            ]
+        );
+    }
+}
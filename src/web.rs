@@ -1,6 +1,29 @@
+pub mod app;
+pub mod client_ip;
+pub mod contact;
 pub mod cors;
 pub mod csrf;
+pub mod etag;
 pub mod fallback;
+pub mod flash;
+pub mod forms;
+pub mod headers;
+pub mod health;
+pub mod limits;
+pub mod locale;
+pub mod logging;
+pub mod middleware;
+pub mod pages;
+pub mod pagination;
+pub mod policy;
+pub mod problem;
+pub mod rate_limit;
+pub mod respond;
+pub mod secure_cookie;
+pub mod server;
 pub mod spa;
 pub mod template;
 pub mod upload;
+pub mod validation;
+pub mod webhook_inbox;
+pub mod ws;
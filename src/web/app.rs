@@ -0,0 +1,606 @@
+//! # Application Router Builder
+//!
+//! [`RouterBuilder`] assembles the crate's web features into one Axum
+//! [`Router`] driven by [`AppConfig`]:
+//!
+//! | Feature | Default | Effect |
+//! |---------|---------|--------|
+//! | CORS | on | [`build_cors`] layer when `cfg.cors.enabled` |
+//! | CSRF | on | `GET /csrf`, and the `Extension<bool>` / `Extension<CsrfConfig>` read by handlers (enabled when `cfg.is_csrf_enabled()`) |
+//! | Body limit | on | [`build_body_limit`] of `cfg.http.max_body_bytes` (JSON `413`) |
+//! | Request ID | on | [`request_id`] middleware |
+//! | Access log | on | [`access_log`] middleware |
+//! | Client IP | off | [`resolve_client_ip`] behind [`TrustedProxies`] ([`RouterBuilder::trusted_proxies`]) |
+//! | Health | on | `GET /healthz` (liveness; checks the database with [`RouterBuilder::health_db`]) |
+//! | Probes | off | `GET /livez` and `GET /readyz` from [`health_router`] ([`RouterBuilder::health_checks`]) |
+//! | Metrics | off | [`track_metrics`] and `GET /metrics` ([`RouterBuilder::metrics`]) |
+//! | Micro-cache | off | [`micro_cache`] for GET and GraphQL queries ([`RouterBuilder::micro_cache`]) |
+//! | Uploads | off | `POST /upload` ([`RouterBuilder::uploads`]) |
+//! | GraphQL | off | `POST /graphql`, plus `GET /graphiql` when `cfg.enable_graphiql` |
+//! | SPA entry | off | [`spa_entry_handler`] as fallback, else [`not_found`] |
+//! | Error pages | built-in | `Extension<Arc<ErrorPages>>` read by [`not_found`] ([`RouterBuilder::error_pages`]) |
+//! | Locale | on | `Extension<LocaleConfig>` from `cfg.locale`, read by the [`Locale`](crate::web::locale::Locale) extractor |
+//!
+//! Application routes are added with [`RouterBuilder::route`] or
+//! [`RouterBuilder::merge`] and receive every layer.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+//! use axum::routing::get;
+//! use wzs_web::config::app::AppConfig;
+//! use wzs_web::graphql::config::GraphqlAuthConfig;
+//! use wzs_web::web::app::RouterBuilder;
+//!
+//! struct Query;
+//!
+//! #[Object]
+//! impl Query {
+//!     async fn ping(&self) -> &str {
+//!         "pong"
+//!     }
+//! }
+//!
+//! let cfg = AppConfig::from_env();
+//! let schema = Schema::new(Query, EmptyMutation, EmptySubscription);
+//! let html = Arc::new(std::fs::read_to_string(&cfg.html_path).unwrap_or_default());
+//!
+//! let app = RouterBuilder::new(cfg)
+//!     .graphql(schema, GraphqlAuthConfig::new("app_token"))
+//!     .spa(html)
+//!     .route("/api/hello", get(|| async { "hello" }))
+//!     .build();
+//! ```
+
+use std::sync::Arc;
+
+use async_graphql::{ObjectType, Schema, SubscriptionType};
+use axum::{
+    middleware::{from_fn, from_fn_with_state},
+    routing::{get, post, MethodRouter},
+    Extension, Router,
+};
+
+use crate::config::app::AppConfig;
+use crate::db::port::Db;
+use crate::graphql::config::GraphqlAuthConfig;
+use crate::graphql::graphiql::graphiql_handler;
+use crate::graphql::handler::graphql_post_handler;
+use crate::web::client_ip::{resolve_client_ip, TrustedProxies};
+use crate::web::cors::build_cors;
+use crate::web::csrf::csrf_handler;
+use crate::web::fallback::{not_found, ErrorPages};
+use crate::web::health::{db_health_handler, health_handler, health_router, HealthChecks};
+use crate::web::limits::build_body_limit;
+use crate::web::middleware::metrics::{track_metrics, HttpMetrics};
+use crate::web::middleware::micro_cache::{micro_cache, MicroCache};
+use crate::web::middleware::request_id::{access_log, request_id};
+use crate::web::spa::bootstrap::SpaBootstrap;
+use crate::web::spa::spa_entry_handler;
+use crate::web::upload::upload_handler::upload_handler;
+use crate::web::upload::uploader::UploadService;
+
+pub const CSRF_PATH: &str = "/csrf";
+pub const HEALTH_PATH: &str = "/healthz";
+pub const METRICS_PATH: &str = "/metrics";
+pub const UPLOAD_PATH: &str = "/upload";
+pub const GRAPHQL_PATH: &str = "/graphql";
+pub const GRAPHIQL_PATH: &str = "/graphiql";
+
+/// Builder for a production-shaped application [`Router`].
+pub struct RouterBuilder {
+    cfg: AppConfig,
+    router: Router,
+    cors: bool,
+    csrf: bool,
+    body_limit: bool,
+    request_id: bool,
+    access_log: bool,
+    trusted_proxies: Option<Arc<TrustedProxies>>,
+    health: bool,
+    health_db: Option<Arc<dyn Db>>,
+    health_checks: Option<HealthChecks>,
+    metrics: Option<HttpMetrics>,
+    micro_cache: Option<MicroCache>,
+    spa: Option<Arc<String>>,
+    bootstrap: Option<SpaBootstrap>,
+    error_pages: Arc<ErrorPages>,
+}
+
+impl RouterBuilder {
+    /// Creates a builder with the default feature set (see the module docs).
+    pub fn new(cfg: AppConfig) -> Self {
+        Self {
+            cfg,
+            router: Router::new(),
+            cors: true,
+            csrf: true,
+            body_limit: true,
+            request_id: true,
+            access_log: true,
+            trusted_proxies: None,
+            health: true,
+            health_db: None,
+            health_checks: None,
+            metrics: None,
+            micro_cache: None,
+            spa: None,
+            bootstrap: None,
+            error_pages: Arc::new(ErrorPages::new()),
+        }
+    }
+
+    /// Toggles the CORS layer (still requires `cfg.cors.enabled`).
+    pub fn cors(mut self, enabled: bool) -> Self {
+        self.cors = enabled;
+        self
+    }
+
+    /// Toggles CSRF validation and the `/csrf` route (still requires a
+    /// configured `CSRF_SECRET`).
+    pub fn csrf(mut self, enabled: bool) -> Self {
+        self.csrf = enabled;
+        self
+    }
+
+    /// Toggles the request body size limit.
+    pub fn body_limit(mut self, enabled: bool) -> Self {
+        self.body_limit = enabled;
+        self
+    }
+
+    /// Toggles the request ID middleware.
+    pub fn request_id(mut self, enabled: bool) -> Self {
+        self.request_id = enabled;
+        self
+    }
+
+    /// Toggles the access log middleware.
+    pub fn access_log(mut self, enabled: bool) -> Self {
+        self.access_log = enabled;
+        self
+    }
+
+    /// Resolves the client address behind `proxies` (see
+    /// [`client_ip`](crate::web::client_ip)) before the access log, rate
+    /// limiting and handlers read it.
+    pub fn trusted_proxies(mut self, proxies: TrustedProxies) -> Self {
+        self.trusted_proxies = Some(Arc::new(proxies));
+        self
+    }
+
+    /// Toggles the `/healthz` route.
+    pub fn health(mut self, enabled: bool) -> Self {
+        self.health = enabled;
+        self
+    }
+
+    /// Makes `/healthz` a readiness probe that pings `db` (see
+    /// [`db_health_handler`]).
+    pub fn health_db(mut self, db: Arc<dyn Db>) -> Self {
+        self.health_db = Some(db);
+        self
+    }
+
+    /// Serves `/livez` and `/readyz`, readiness running `checks`.
+    pub fn health_checks(mut self, checks: HealthChecks) -> Self {
+        self.health_checks = Some(checks);
+        self
+    }
+
+    /// Records request metrics into `metrics` and serves them on `/metrics`.
+    pub fn metrics(mut self, metrics: HttpMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Serves repeated reads from `cache` (see
+    /// [`micro_cache`](crate::web::middleware::micro_cache)).
+    pub fn micro_cache(mut self, cache: MicroCache) -> Self {
+        self.micro_cache = Some(cache.graphql_path(GRAPHQL_PATH));
+        self
+    }
+
+    /// Mounts the multipart upload endpoint on `/upload`.
+    pub fn uploads(mut self, service: Arc<UploadService>) -> Self {
+        self.router = self
+            .router
+            .route(UPLOAD_PATH, post(upload_handler).layer(Extension(service)));
+        self
+    }
+
+    /// Mounts the GraphQL endpoint on `/graphql` (and GraphiQL on
+    /// `/graphiql` when `cfg.enable_graphiql` is set).
+    ///
+    /// The JWT secret is taken from `cfg.jwt_secret`; an empty secret
+    /// disables authentication.
+    pub fn graphql<Q, M, S>(mut self, schema: Schema<Q, M, S>, auth: GraphqlAuthConfig) -> Self
+    where
+        Q: ObjectType + Send + Sync + 'static,
+        M: ObjectType + Send + Sync + 'static,
+        S: SubscriptionType + Send + Sync + 'static,
+    {
+        let jwt_secret = Some(self.cfg.jwt_secret.clone()).filter(|s| !s.is_empty());
+
+        let graphql = Router::new()
+            .route(GRAPHQL_PATH, post(graphql_post_handler::<Q, M, S>))
+            .layer(Extension(schema))
+            .layer(Extension(auth))
+            .layer(Extension(jwt_secret));
+        self.router = self.router.merge(graphql);
+        if self.cfg.enable_graphiql {
+            self.router = self
+                .router
+                .route(GRAPHIQL_PATH, get(|| graphiql_handler(GRAPHQL_PATH)));
+        }
+        self
+    }
+
+    /// Serves the SPA entry HTML for every unmatched path.
+    pub fn spa(mut self, template_html: Arc<String>) -> Self {
+        self.spa = Some(template_html);
+        self
+    }
+
+    /// Injects the `window.__APP__` bootstrap script into the SPA entry.
+    pub fn spa_bootstrap(mut self, bootstrap: SpaBootstrap) -> Self {
+        self.bootstrap = Some(bootstrap);
+        self
+    }
+
+    /// Sets the templates for HTML error pages (404 fallback and
+    /// [`error_response`](crate::web::fallback::error_response) callers).
+    pub fn error_pages(mut self, pages: ErrorPages) -> Self {
+        self.error_pages = Arc::new(pages);
+        self
+    }
+
+    /// Adds an application route.
+    pub fn route(mut self, path: &str, method_router: MethodRouter) -> Self {
+        self.router = self.router.route(path, method_router);
+        self
+    }
+
+    /// Merges an application router.
+    pub fn merge(mut self, router: Router) -> Self {
+        self.router = self.router.merge(router);
+        self
+    }
+
+    /// Builds the router, applying layers from innermost to outermost:
+    /// extensions, micro-cache, body limit, metrics, access log, request ID,
+    /// CORS.
+    pub fn build(self) -> Router {
+        let cfg = self.cfg;
+        let mut router = self.router;
+
+        if self.health {
+            router = match self.health_db {
+                Some(db) => router.merge(
+                    Router::new()
+                        .route(HEALTH_PATH, get(db_health_handler))
+                        .layer(Extension(db)),
+                ),
+                None => router.route(HEALTH_PATH, get(health_handler)),
+            };
+        }
+        if let Some(checks) = self.health_checks {
+            router = router.merge(health_router(checks));
+        }
+        if let Some(metrics) = self.metrics.clone() {
+            router = router.route(METRICS_PATH, get(move || async move { metrics.render() }));
+        }
+        if self.csrf {
+            router = router.route(CSRF_PATH, get(csrf_handler));
+        }
+
+        router = match self.spa {
+            Some(html) => router.fallback(spa_entry_handler).layer(Extension(html)),
+            None => router.fallback(not_found),
+        };
+        if let Some(bootstrap) = self.bootstrap {
+            router = router.layer(Extension(bootstrap));
+        }
+
+        let enable_csrf = self.csrf && cfg.is_csrf_enabled();
+        router = router
+            .layer(Extension(enable_csrf))
+            .layer(Extension(cfg.csrf.clone()))
+            .layer(Extension(cfg.locale.clone()))
+            .layer(Extension(self.error_pages));
+
+        if let Some(cache) = self.micro_cache {
+            router = router.layer(from_fn_with_state(cache, micro_cache));
+        }
+        if self.body_limit {
+            router = router.layer(build_body_limit(&cfg.http));
+        }
+        if let Some(metrics) = self.metrics {
+            router = router.layer(from_fn_with_state(metrics, track_metrics));
+        }
+        if self.access_log {
+            router = router.layer(from_fn(access_log));
+        }
+        if let Some(proxies) = self.trusted_proxies {
+            router = router.layer(from_fn_with_state(proxies, resolve_client_ip));
+        }
+        if self.request_id {
+            router = router.layer(from_fn(request_id));
+        }
+        if let Some(cors) = build_cors(&cfg.cors).filter(|_| self.cors) {
+            router = router.layer(cors);
+        }
+
+        router
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum_extra::extract::cookie::SameSite;
+
+    use async_graphql::{EmptyMutation, EmptySubscription, Object};
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        response::Response,
+    };
+    use std::path::PathBuf;
+    use tower::ServiceExt;
+
+    use crate::config::{
+        csrf::{derive_secret_from_string, CsrfConfig},
+        db::DbConfig,
+        image::ImageConfig,
+        locale::LocaleConfig,
+        upload::UploadConfig,
+        web::{CorsConfig, HttpConfig},
+    };
+
+    fn cfg() -> AppConfig {
+        AppConfig {
+            db: DbConfig {
+                url: None,
+                min_connections: None,
+                max_connections: None,
+                connect_timeout: None,
+                idle_ttl: None,
+                read_urls: vec![],
+            },
+            http: HttpConfig { max_body_bytes: 16 },
+            csrf: CsrfConfig {
+                secret: derive_secret_from_string("test"),
+                cookie_secure: false,
+                cookie_http_only: true,
+                rotation: Default::default(),
+                token_ttl: None,
+                allowed_origins: None,
+                cookie_same_site: SameSite::Lax,
+                accept_legacy_tokens: false,
+            },
+            cors: CorsConfig {
+                enabled: false,
+                env: String::new(),
+                credentials: false,
+            },
+            cross_site: Default::default(),
+            image: ImageConfig {
+                max_width: 100,
+                max_height: 100,
+            },
+            upload: UploadConfig {
+                root: PathBuf::from("./var/uploads"),
+                image_dir: "images".into(),
+                file_dir: "files".into(),
+                key_scheme: Default::default(),
+            },
+            mail: None,
+            locale: LocaleConfig::default(),
+            enable_graphiql: false,
+            jwt_secret: String::new(),
+            html_path: String::new(),
+        }
+    }
+
+    async fn send(app: &Router, req: Request<Body>) -> Response {
+        app.clone().oneshot(req).await.unwrap()
+    }
+
+    async fn body(res: Response) -> String {
+        let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    fn req(uri: &str) -> Request<Body> {
+        Request::get(uri).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn default_features_are_mounted() {
+        let app = RouterBuilder::new(cfg()).build();
+
+        let res = send(&app, req(HEALTH_PATH)).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers().contains_key("x-request-id"));
+
+        assert_eq!(send(&app, req(CSRF_PATH)).await.status(), StatusCode::OK);
+        assert_eq!(
+            send(&app, req("/nope")).await.status(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            send(&app, req(METRICS_PATH)).await.status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    struct DownDb;
+
+    impl Db for DownDb {
+        fn fetch_one(
+            &self,
+            _: &str,
+            _: &[crate::db::port::Param],
+        ) -> anyhow::Result<Option<crate::db::port::Row>> {
+            anyhow::bail!("down")
+        }
+
+        fn fetch_all(
+            &self,
+            _: &str,
+            _: &[crate::db::port::Param],
+        ) -> anyhow::Result<Vec<crate::db::port::Row>> {
+            anyhow::bail!("down")
+        }
+
+        fn exec(&self, _: &str, _: &[crate::db::port::Param]) -> anyhow::Result<u64> {
+            anyhow::bail!("down")
+        }
+
+        fn exec_returning_last_insert_id(
+            &self,
+            _: &str,
+            _: &[crate::db::port::Param],
+        ) -> anyhow::Result<u64> {
+            anyhow::bail!("down")
+        }
+    }
+
+    #[tokio::test]
+    async fn health_db_turns_healthz_into_readiness_probe() {
+        let app = RouterBuilder::new(cfg())
+            .health_db(Arc::new(DownDb))
+            .build();
+
+        let res = send(&app, req(HEALTH_PATH)).await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let problem: serde_json::Value = serde_json::from_str(&body(res).await).unwrap();
+        assert_eq!(problem["detail"], "database unavailable");
+    }
+
+    #[tokio::test]
+    async fn health_checks_mount_probes() {
+        use crate::web::health::{DbCheck, LIVEZ_PATH, READYZ_PATH};
+
+        let app = RouterBuilder::new(cfg())
+            .health_checks(HealthChecks::new().check(DbCheck::new(Arc::new(DownDb))))
+            .build();
+
+        assert_eq!(send(&app, req(LIVEZ_PATH)).await.status(), StatusCode::OK);
+        let res = send(&app, req(READYZ_PATH)).await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body(res).await.contains(r#""name":"db","status":"down""#));
+    }
+
+    #[tokio::test]
+    async fn toggles_disable_features() {
+        let app = RouterBuilder::new(cfg())
+            .health(false)
+            .csrf(false)
+            .request_id(false)
+            .build();
+
+        let res = send(&app, req(HEALTH_PATH)).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert!(!res.headers().contains_key("x-request-id"));
+        assert_eq!(
+            send(&app, req(CSRF_PATH)).await.status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn trusted_proxies_resolve_the_client_ip() {
+        use crate::web::client_ip::ClientIp;
+        use axum::extract::ConnectInfo;
+        use std::net::SocketAddr;
+
+        let app = RouterBuilder::new(cfg())
+            .trusted_proxies(TrustedProxies::private_networks())
+            .route("/ip", get(|ClientIp(ip): ClientIp| async move { ip.to_string() }))
+            .build();
+        let mut req = Request::get("/ip")
+            .header("x-forwarded-for", "203.0.113.9")
+            .body(Body::empty())
+            .unwrap();
+        req.extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4000))));
+        assert_eq!(body(send(&app, req).await).await, "203.0.113.9");
+    }
+
+    #[tokio::test]
+    async fn app_routes_get_body_limit_and_metrics() {
+        let metrics = HttpMetrics::new();
+        let app = RouterBuilder::new(cfg())
+            .metrics(metrics.clone())
+            .route("/echo", post(|b: String| async move { b }))
+            .build();
+
+        let big = Request::post("/echo")
+            .body(Body::from("x".repeat(17)))
+            .unwrap();
+        assert_eq!(
+            send(&app, big).await.status(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        let small = Request::post("/echo").body(Body::from("hi")).unwrap();
+        assert_eq!(body(send(&app, small).await).await, "hi");
+
+        let text = body(send(&app, req(METRICS_PATH)).await).await;
+        assert!(text.contains("http_requests_total{class=\"4xx\"} 1"));
+        assert_eq!(metrics.total(), 3);
+    }
+
+    #[tokio::test]
+    async fn spa_entry_is_the_fallback() {
+        let html = Arc::new("<head></head>{{ csrf_token }}".to_string());
+        let app = RouterBuilder::new(cfg())
+            .spa(html)
+            .spa_bootstrap(SpaBootstrap::new("/api"))
+            .build();
+
+        let res = send(&app, req("/members/42")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let text = body(res).await;
+        assert!(text.contains("window.__APP__"));
+        assert!(!text.contains("{{ csrf_token }}"));
+    }
+
+    #[tokio::test]
+    async fn graphql_is_mounted() {
+        struct Query;
+
+        #[Object]
+        impl Query {
+            async fn ping(&self) -> &str {
+                "pong"
+            }
+        }
+
+        let schema = Schema::new(Query, EmptyMutation, EmptySubscription);
+        let app = RouterBuilder::new(cfg())
+            .csrf(false)
+            .body_limit(false)
+            .graphql(schema, GraphqlAuthConfig::new("token"))
+            .build();
+
+        let res = send(
+            &app,
+            Request::post(GRAPHQL_PATH)
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"query":"{ ping }"}"#))
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(body(res).await, r#"{"data":{"ping":"pong"}}"#);
+        assert_eq!(
+            send(&app, req(GRAPHIQL_PATH)).await.status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+}
@@ -0,0 +1,493 @@
+//! # Client IP Behind Reverse Proxies
+//!
+//! The peer address of a connection is the nearest proxy's when the app
+//! runs behind a load balancer. [`resolve_client_ip`] recovers the real
+//! client from the proxy's forwarding header and stores it as a
+//! [`ClientIp`] extension, which the rate limiter, login lockout, request
+//! logs and GraphQL [`RequestContext`](crate::graphql::context::RequestContext)
+//! read. The [`ClientIp`] extractor gives handlers the same value.
+//!
+//! Only proxies listed in [`TrustedProxies`] are believed: for a request
+//! from an untrusted peer the peer address is the client. Otherwise the
+//! forwarding chain is walked from the right (the hop added by the nearest
+//! proxy), skipping trusted addresses; the first untrusted address is the
+//! client. An unparsable hop ends the walk at the last address seen.
+//!
+//! Exactly one [`ProxyHeader`] is read (default `X-Forwarded-For`): a proxy
+//! appending to one header passes the others through untouched, so reading
+//! them would let clients pick their own address.
+//!
+//! | Variable | Default | Meaning |
+//! |----------|---------|---------|
+//! | `TRUSTED_PROXIES` | *(empty)* | Comma-separated addresses / CIDR ranges (`10.0.0.0/8`, `::1`) |
+//! | `TRUSTED_PROXY_HEADER` | `x-forwarded-for` | `forwarded`, `x-forwarded-for` or `x-real-ip` |
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use axum::{middleware::from_fn_with_state, routing::get, Router};
+//! use wzs_web::web::client_ip::{resolve_client_ip, ClientIp, TrustedProxies};
+//!
+//! let proxies = TrustedProxies::from_env().expect("invalid TRUSTED_PROXIES");
+//!
+//! let app: Router = Router::new()
+//!     .route("/ip", get(|ClientIp(ip): ClientIp| async move { ip.to_string() }))
+//!     .layer(from_fn_with_state(Arc::new(proxies), resolve_client_ip));
+//! ```
+
+use std::env;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use axum::{
+    extract::{ConnectInfo, FromRequestParts, OptionalFromRequestParts, Request, State},
+    http::{header, request::Parts, Extensions, HeaderMap, HeaderName},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::error::app::AppError;
+
+/// Client address of the current request, inserted as a request extension
+/// by [`resolve_client_ip`]. Without it the peer address
+/// (`ConnectInfo<SocketAddr>`) is used.
+///
+/// As an extractor it rejects with `500` when neither is available (the
+/// server was not started with connect info); extract `Option<ClientIp>` to
+/// handle that case.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+impl ClientIp {
+    /// The [`ClientIp`] extension, else the peer address.
+    pub fn of(extensions: &Extensions) -> Option<IpAddr> {
+        extensions.get::<ClientIp>().map(|ip| ip.0).or_else(|| {
+            extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip())
+        })
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Self::of(&parts.extensions)
+            .map(Self)
+            .ok_or_else(|| AppError::Internal(anyhow!("client address unavailable")))
+    }
+}
+
+impl<S: Send + Sync> OptionalFromRequestParts<S> for ClientIp {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        Ok(Self::of(&parts.extensions).map(Self))
+    }
+}
+
+/// Forwarding header set by the trusted proxies.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProxyHeader {
+    /// RFC 7239 `Forwarded: for=…` elements.
+    Forwarded,
+    /// `X-Forwarded-For: client, proxy1, proxy2`.
+    #[default]
+    XForwardedFor,
+    /// `X-Real-IP: client` (a single address).
+    XRealIp,
+}
+
+impl ProxyHeader {
+    fn name(self) -> HeaderName {
+        match self {
+            Self::Forwarded => header::FORWARDED,
+            Self::XForwardedFor => HeaderName::from_static("x-forwarded-for"),
+            Self::XRealIp => HeaderName::from_static("x-real-ip"),
+        }
+    }
+}
+
+/// An address range in CIDR notation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Net {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Net {
+    fn parse(s: &str) -> Result<Self> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr
+            .parse()
+            .with_context(|| format!("invalid proxy address: {s}"))?;
+        let addr = addr.to_canonical();
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p
+                .parse()
+                .ok()
+                .filter(|p| *p <= max)
+                .with_context(|| format!("invalid prefix length: {s}"))?,
+            None => max,
+        };
+        Ok(Self { addr, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Proxies whose forwarding header is believed; see the [module docs](self).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrustedProxies {
+    nets: Vec<Net>,
+    header: ProxyHeader,
+}
+
+impl TrustedProxies {
+    /// Trusts no proxy: the peer address is always the client.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a comma-separated list of addresses and CIDR ranges.
+    ///
+    /// # Errors
+    /// Returns an error naming the first invalid entry.
+    pub fn parse(list: &str) -> Result<Self> {
+        let nets = list
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(Net::parse)
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            nets,
+            header: ProxyHeader::default(),
+        })
+    }
+
+    /// Loopback and private ranges (`127.0.0.0/8`, `10.0.0.0/8`,
+    /// `172.16.0.0/12`, `192.168.0.0/16`, `::1`, `fc00::/7`), for proxies on
+    /// the same host or private network.
+    pub fn private_networks() -> Self {
+        Self::parse("127.0.0.0/8, 10.0.0.0/8, 172.16.0.0/12, 192.168.0.0/16, ::1, fc00::/7")
+            .expect("valid built-in ranges")
+    }
+
+    /// Loads the list from `TRUSTED_PROXIES` and `TRUSTED_PROXY_HEADER`.
+    ///
+    /// # Errors
+    /// Returns an error for an invalid entry or header name.
+    pub fn from_env() -> Result<Self> {
+        Self::from_env_with(|k| env::var(k).ok())
+    }
+
+    /// Loads the list using a custom key provider (for testing/mocking).
+    ///
+    /// # Errors
+    /// Returns an error for an invalid entry or header name.
+    pub fn from_env_with<F>(get: F) -> Result<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let proxies = Self::parse(&get("TRUSTED_PROXIES").unwrap_or_default())?;
+        let header = match get("TRUSTED_PROXY_HEADER")
+            .map(|v| v.trim().to_ascii_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("x-forwarded-for") => ProxyHeader::XForwardedFor,
+            Some("forwarded") => ProxyHeader::Forwarded,
+            Some("x-real-ip") => ProxyHeader::XRealIp,
+            Some(other) => bail!("unsupported TRUSTED_PROXY_HEADER: {other}"),
+        };
+        Ok(proxies.header(header))
+    }
+
+    /// Also trusts `net` (an address or CIDR range).
+    ///
+    /// # Panics
+    /// Panics if `net` is invalid.
+    pub fn trust(mut self, net: &str) -> Self {
+        self.nets
+            .push(Net::parse(net).expect("invalid trusted proxy"));
+        self
+    }
+
+    /// Reads the client from `header`.
+    pub fn header(mut self, header: ProxyHeader) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Whether `ip` belongs to a trusted proxy.
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.nets.iter().any(|net| net.contains(ip))
+    }
+
+    /// The client address of a request from `peer` with `headers`.
+    pub fn resolve(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let mut client = peer.to_canonical();
+        if !self.is_trusted(client) {
+            return client;
+        }
+        for hop in self.chain(headers).into_iter().rev() {
+            let Some(ip) = hop else {
+                break;
+            };
+            client = ip.to_canonical();
+            if !self.is_trusted(client) {
+                break;
+            }
+        }
+        client
+    }
+
+    /// Forwarded-for hops, left (client side) to right; `None` for entries
+    /// that are not addresses (`unknown`, obfuscated identifiers).
+    fn chain(&self, headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+        let values = headers
+            .get_all(self.header.name())
+            .into_iter()
+            .filter_map(|v| v.to_str().ok());
+        match self.header {
+            ProxyHeader::Forwarded => values
+                .flat_map(|v| v.split(','))
+                .map(|element| {
+                    element
+                        .split(';')
+                        .filter_map(|pair| pair.split_once('='))
+                        .find(|(key, _)| key.trim().eq_ignore_ascii_case("for"))
+                        .and_then(|(_, value)| parse_node(value.trim().trim_matches('"')))
+                })
+                .collect(),
+            ProxyHeader::XForwardedFor => values
+                .flat_map(|v| v.split(','))
+                .map(|hop| parse_node(hop.trim()))
+                .collect(),
+            ProxyHeader::XRealIp => values.map(|v| parse_node(v.trim())).collect(),
+        }
+    }
+}
+
+/// Address of a forwarded node: `1.2.3.4`, `1.2.3.4:80`, `::1` or
+/// `[::1]:80`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    if let Ok(ip) = node.parse() {
+        return Some(ip);
+    }
+    if let Some(rest) = node.strip_prefix('[') {
+        return rest.split(']').next()?.parse().ok();
+    }
+    let (host, _port) = node.rsplit_once(':')?;
+    host.parse::<std::net::Ipv4Addr>().ok().map(IpAddr::V4)
+}
+
+/// Middleware inserting the resolved [`ClientIp`] extension.
+///
+/// Mount with `from_fn_with_state(Arc::new(proxies), resolve_client_ip)`
+/// outside the layers reading it. Requests without
+/// `ConnectInfo<SocketAddr>` pass unchanged.
+pub async fn resolve_client_ip(
+    State(proxies): State<Arc<TrustedProxies>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    if let Some(peer) = peer {
+        let ip = proxies.resolve(peer, req.headers());
+        req.extensions_mut().insert(ClientIp(ip));
+    }
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{
+        body::Body, http::StatusCode, middleware::from_fn_with_state, routing::get, Router,
+    };
+    use tower::ServiceExt;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn headers(name: &str, values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let name = HeaderName::from_bytes(name.as_bytes()).unwrap();
+        for v in values {
+            headers.append(name.clone(), v.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn parses_addresses_and_ranges() {
+        let proxies = TrustedProxies::parse(" 10.0.0.0/8, 192.0.2.7 ,2001:db8::/32,").unwrap();
+        assert!(proxies.is_trusted(ip("10.200.0.1")));
+        assert!(proxies.is_trusted(ip("::ffff:10.1.2.3")));
+        assert!(proxies.is_trusted(ip("192.0.2.7")));
+        assert!(!proxies.is_trusted(ip("192.0.2.8")));
+        assert!(proxies.is_trusted(ip("2001:db8:1::1")));
+        assert!(!proxies.is_trusted(ip("2001:db9::1")));
+        assert!(TrustedProxies::parse("0.0.0.0/0")
+            .unwrap()
+            .is_trusted(ip("203.0.113.9")));
+
+        for bad in ["10.0.0.0/33", "example.com", "::1/129", "10.0.0.0/x"] {
+            assert!(TrustedProxies::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn walks_the_chain_from_the_right() {
+        let proxies = TrustedProxies::private_networks();
+        let xff = |values: &[&str]| headers("x-forwarded-for", values);
+
+        let spoofed = xff(&["6.6.6.6, 203.0.113.9, 10.0.0.2"]);
+        assert_eq!(proxies.resolve(ip("10.0.0.1"), &spoofed), ip("203.0.113.9"));
+        let split = xff(&["6.6.6.6", "203.0.113.9", "10.0.0.2"]);
+        assert_eq!(proxies.resolve(ip("10.0.0.1"), &split), ip("203.0.113.9"));
+
+        // Untrusted peers speak for themselves.
+        assert_eq!(
+            proxies.resolve(ip("198.51.100.4"), &spoofed),
+            ip("198.51.100.4")
+        );
+        // All hops trusted: the leftmost one.
+        assert_eq!(
+            proxies.resolve(ip("10.0.0.1"), &xff(&["192.168.1.5, 10.0.0.2"])),
+            ip("192.168.1.5")
+        );
+        // Garbage ends the walk at the last address seen.
+        assert_eq!(
+            proxies.resolve(ip("10.0.0.1"), &xff(&["203.0.113.9, unknown, 10.0.0.2"])),
+            ip("10.0.0.2")
+        );
+        assert_eq!(
+            proxies.resolve(ip("10.0.0.1"), &HeaderMap::new()),
+            ip("10.0.0.1")
+        );
+    }
+
+    #[test]
+    fn reads_only_the_configured_header() {
+        let mut all = headers(
+            "forwarded",
+            &[r#"for=192.0.2.60;proto=https, for="[2001:db8:cafe::17]:4711""#],
+        );
+        all.insert("x-forwarded-for", "198.51.100.1".parse().unwrap());
+        all.insert("x-real-ip", "198.51.100.2:8080".parse().unwrap());
+        let peer = ip("127.0.0.1");
+        let proxies = TrustedProxies::parse("127.0.0.1").unwrap();
+
+        assert_eq!(proxies.resolve(peer, &all), ip("198.51.100.1"));
+        let forwarded = proxies.clone().header(ProxyHeader::Forwarded);
+        assert_eq!(forwarded.resolve(peer, &all), ip("2001:db8:cafe::17"));
+        let real_ip = proxies.header(ProxyHeader::XRealIp);
+        assert_eq!(real_ip.resolve(peer, &all), ip("198.51.100.2"));
+    }
+
+    #[test]
+    fn loads_from_env() {
+        let proxies = TrustedProxies::from_env_with(|k| match k {
+            "TRUSTED_PROXIES" => Some("10.0.0.0/8".into()),
+            "TRUSTED_PROXY_HEADER" => Some("Forwarded".into()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(
+            proxies,
+            TrustedProxies::new()
+                .trust("10.0.0.0/8")
+                .header(ProxyHeader::Forwarded)
+        );
+        assert_eq!(
+            TrustedProxies::from_env_with(|_| None).unwrap(),
+            TrustedProxies::new()
+        );
+        assert!(TrustedProxies::from_env_with(
+            |k| (k == "TRUSTED_PROXY_HEADER").then(|| "via".into())
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn middleware_and_extractor_expose_the_client() {
+        let app = Router::new()
+            .route(
+                "/ip",
+                get(|ClientIp(ip): ClientIp| async move { ip.to_string() }),
+            )
+            .route(
+                "/maybe",
+                get(|ip: Option<ClientIp>| async move { format!("{:?}", ip.map(|ip| ip.0)) }),
+            )
+            .layer(from_fn_with_state(
+                Arc::new(TrustedProxies::private_networks()),
+                resolve_client_ip,
+            ));
+
+        let send = |uri: &str, peer: Option<&str>| {
+            let mut req = Request::get(uri)
+                .header("x-forwarded-for", "203.0.113.9")
+                .body(Body::empty())
+                .unwrap();
+            if let Some(peer) = peer {
+                req.extensions_mut()
+                    .insert(ConnectInfo(SocketAddr::new(ip(peer), 4000)));
+            }
+            app.clone().oneshot(req)
+        };
+        let text = |res: Response| async move {
+            let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            String::from_utf8(bytes.to_vec()).unwrap()
+        };
+
+        assert_eq!(
+            text(send("/ip", Some("10.0.0.1")).await.unwrap()).await,
+            "203.0.113.9"
+        );
+        assert_eq!(
+            text(send("/ip", Some("198.51.100.4")).await.unwrap()).await,
+            "198.51.100.4"
+        );
+        assert_eq!(text(send("/maybe", None).await.unwrap()).await, "None");
+        assert_eq!(
+            send("/ip", None).await.unwrap().status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
@@ -0,0 +1,638 @@
+//! # Contact Form
+//!
+//! [`contact_handler`] is a complete `POST` endpoint for a site's contact
+//! form. A submission (URL-encoded or multipart-free form fields) passes,
+//! in order:
+//!
+//! 1. **CSRF** — the `csrf` cookie must match the `X-CSRF-Token` header or
+//!    the `_csrf` form field (plain HTML forms cannot set headers)
+//! 2. **rate limit** — at most `n` submissions per client IP and window,
+//!    through a [`Throttle`]
+//! 3. **spam checks** — the [`FormGuard`] honeypot and fill-time token, and
+//!    an optional [`Captcha`] verifier (hCaptcha, Turnstile, reCAPTCHA, … —
+//!    the crate has no HTTP client, so the application implements the call)
+//! 4. **validation** — `name`, `email` and `message` are required; `subject`
+//!    is optional; messages with too many links are rejected
+//!
+//! It then emails the submission to the notification recipients
+//! (`NOTIFY_TO_EMAIL`, see [`ContactForm::from_config`]) with `Reply-To`
+//! set to the visitor, and optionally sends the visitor an auto-reply.
+//! Other form fields are passed through in [`ContactSubmission::extra`].
+//!
+//! | Outcome | Response |
+//! |---------|----------|
+//! | sent | `200 {"ok":true}`, or `303` to [`redirect_to`](ContactForm::redirect_to) |
+//! | invalid fields | `422 {"ok":false,"errors":{"email":"..."}}` |
+//! | spam or captcha failure | `400` |
+//! | CSRF failure | `403` |
+//! | rate limited | `429` |
+//! | notification could not be sent | `502` |
+//!
+//! Both emails are rendered by replaceable functions returning a
+//! [`RenderedEmail`]; the defaults are plain text.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//! use axum::{routing::post, Extension, Router};
+//! use wzs_web::auth::throttle::MemoryThrottle;
+//! use wzs_web::config::csrf::{derive_secret_from_string, CsrfConfig};
+//! use wzs_web::config::mail::MailConfig;
+//! use wzs_web::notification::template::registry::RenderedEmail;
+//! use wzs_web::web::contact::{contact_handler, ContactForm};
+//! use wzs_web::web::forms::FormGuard;
+//!
+//! # fn build() -> anyhow::Result<Router> {
+//! let mail = MailConfig::from_env()?;
+//! let form = ContactForm::from_config(Arc::new(mail.build_sender()?), &mail)?
+//!     .form_guard(FormGuard::new(derive_secret_from_string("form-secret")))
+//!     .throttle(Arc::new(MemoryThrottle::new(5, Duration::from_secs(3600))))
+//!     .auto_reply(|s| {
+//!         Ok(RenderedEmail::new("Thanks for contacting us")
+//!             .text(format!("Hi {},\n\nWe will get back to you soon.\n", s.name)))
+//!     })
+//!     .redirect_to("/contact/thanks");
+//!
+//! Ok(Router::new()
+//!     .route("/contact", post(contact_handler))
+//!     .with_state(Arc::new(form))
+//!     .layer(Extension(CsrfConfig::from_env())))
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use axum::{
+    extract::{FromRequest, Request, State},
+    http::{HeaderValue, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    Form, Json,
+};
+use axum_extra::extract::cookie::CookieJar;
+use lettre::message::Mailbox;
+use serde::Serialize;
+use serde_json::json;
+use thiserror::Error;
+use tracing::warn;
+
+use crate::auth::throttle::Throttle;
+use crate::config::csrf::CsrfConfig;
+use crate::config::mail::MailConfig;
+use crate::notification::email::{Email, EmailHeader};
+use crate::notification::email_sender::EmailSender;
+use crate::notification::template::registry::RenderedEmail;
+use crate::web::client_ip::ClientIp;
+use crate::web::csrf::{validate_csrf, CSRF_HEADER_NAME};
+use crate::web::forms::{
+    count_links, is_plausible_email, is_within_length, FormGuard, FormRejection,
+};
+
+/// Form field accepted in place of the `X-CSRF-Token` header.
+pub const CSRF_FIELD: &str = "_csrf";
+
+/// Form id passed to [`FormGuard`] (override with [`ContactForm::form_id`]).
+pub const DEFAULT_FORM_ID: &str = "contact";
+
+const FIELDS: [&str; 4] = ["name", "email", "subject", "message"];
+
+/// Verifies a CAPTCHA response token with its provider.
+#[async_trait]
+pub trait Captcha: Send + Sync {
+    /// Returns whether `response` (the widget's form value) is valid.
+    async fn verify(&self, response: &str, client_ip: Option<IpAddr>) -> Result<bool>;
+}
+
+/// A validated submission.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ContactSubmission {
+    pub name: String,
+    pub email: String,
+    pub subject: Option<String>,
+    pub message: String,
+    /// Any other submitted fields (minus CSRF, guard and captcha fields).
+    pub extra: BTreeMap<String, String>,
+    pub client_ip: Option<IpAddr>,
+}
+
+/// Why a submission was not sent.
+#[derive(Debug, Error)]
+pub enum ContactError {
+    #[error("CSRF token missing or invalid")]
+    Csrf,
+    #[error("too many submissions")]
+    RateLimited,
+    #[error("submission rejected: {0}")]
+    Spam(FormRejection),
+    #[error("captcha verification failed")]
+    Captcha,
+    #[error("invalid fields")]
+    Invalid(BTreeMap<String, String>),
+    #[error("notification could not be sent")]
+    Send(#[source] anyhow::Error),
+}
+
+impl IntoResponse for ContactError {
+    fn into_response(self) -> Response {
+        let (status, errors) = match &self {
+            Self::Csrf => (StatusCode::FORBIDDEN, None),
+            Self::RateLimited => (StatusCode::TOO_MANY_REQUESTS, None),
+            Self::Spam(_) | Self::Captcha => (StatusCode::BAD_REQUEST, None),
+            Self::Invalid(errors) => (StatusCode::UNPROCESSABLE_ENTITY, Some(errors)),
+            Self::Send(_) => (StatusCode::BAD_GATEWAY, None),
+        };
+        let body = match errors {
+            Some(errors) => json!({ "ok": false, "errors": errors }),
+            None => json!({ "ok": false, "error": self.to_string() }),
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+type Renderer = Arc<dyn Fn(&ContactSubmission) -> Result<RenderedEmail> + Send + Sync>;
+
+/// Contact form settings.
+pub struct ContactForm {
+    sender: Arc<dyn EmailSender>,
+    notify_to: Vec<Mailbox>,
+    form_id: String,
+    guard: Option<FormGuard>,
+    captcha: Option<(Arc<dyn Captcha>, String)>,
+    throttle: Option<Arc<dyn Throttle>>,
+    notification: Renderer,
+    auto_reply: Option<Renderer>,
+    redirect_to: Option<String>,
+    max_field_chars: usize,
+    max_message_chars: usize,
+    max_links: usize,
+}
+
+impl ContactForm {
+    /// Sends submissions to `notify_to`.
+    ///
+    /// # Errors
+    /// Returns an error if `notify_to` is empty.
+    pub fn new(sender: Arc<dyn EmailSender>, notify_to: Vec<Mailbox>) -> Result<Self> {
+        if notify_to.is_empty() {
+            bail!("contact form needs at least one notification recipient");
+        }
+        Ok(Self {
+            sender,
+            notify_to,
+            form_id: DEFAULT_FORM_ID.into(),
+            guard: None,
+            captcha: None,
+            throttle: None,
+            notification: Arc::new(|s| Ok(default_notification(s))),
+            auto_reply: None,
+            redirect_to: None,
+            max_field_chars: 200,
+            max_message_chars: 5000,
+            max_links: 3,
+        })
+    }
+
+    /// Sends submissions to [`MailConfig::notify_to`] (`NOTIFY_TO_EMAIL`).
+    ///
+    /// # Errors
+    /// Returns an error if an address is invalid or none is configured.
+    pub fn from_config(sender: Arc<dyn EmailSender>, cfg: &MailConfig) -> Result<Self> {
+        Self::new(sender, cfg.mailboxes()?.notify_to)
+    }
+
+    /// Sets the form id the [`FormGuard`] tokens are bound to.
+    pub fn form_id(mut self, id: impl Into<String>) -> Self {
+        self.form_id = id.into();
+        self
+    }
+
+    /// Checks the honeypot and form token (see [`FormGuard::hidden_fields_html`]).
+    pub fn form_guard(mut self, guard: FormGuard) -> Self {
+        self.guard = Some(guard);
+        self
+    }
+
+    /// Verifies the CAPTCHA response posted in `field`
+    /// (e.g. `"cf-turnstile-response"`).
+    pub fn captcha(mut self, captcha: Arc<dyn Captcha>, field: impl Into<String>) -> Self {
+        self.captcha = Some((captcha, field.into()));
+        self
+    }
+
+    /// Limits submissions per client IP: every submission counts as one
+    /// attempt under the key `contact:<ip>`.
+    pub fn throttle(mut self, throttle: Arc<dyn Throttle>) -> Self {
+        self.throttle = Some(throttle);
+        self
+    }
+
+    /// Renders the notification email instead of the default summary.
+    pub fn notification<F>(mut self, render: F) -> Self
+    where
+        F: Fn(&ContactSubmission) -> Result<RenderedEmail> + Send + Sync + 'static,
+    {
+        self.notification = Arc::new(render);
+        self
+    }
+
+    /// Sends the visitor an auto-reply rendered by `render`.
+    ///
+    /// A failed auto-reply is logged; the submission still succeeds.
+    pub fn auto_reply<F>(mut self, render: F) -> Self
+    where
+        F: Fn(&ContactSubmission) -> Result<RenderedEmail> + Send + Sync + 'static,
+    {
+        self.auto_reply = Some(Arc::new(render));
+        self
+    }
+
+    /// Answers successful submissions with `303 See Other` to `url`
+    /// instead of JSON.
+    pub fn redirect_to(mut self, url: impl Into<String>) -> Self {
+        self.redirect_to = Some(url.into());
+        self
+    }
+
+    /// Sets the maximum length of `message` in characters (default 5000).
+    pub fn max_message_chars(mut self, n: usize) -> Self {
+        self.max_message_chars = n;
+        self
+    }
+
+    /// Sets how many links `message` may contain (default 3).
+    pub fn max_links(mut self, n: usize) -> Self {
+        self.max_links = n;
+        self
+    }
+
+    /// Runs the rate limit, spam checks and validation, then sends the
+    /// emails. CSRF is checked by [`contact_handler`].
+    pub async fn submit(
+        &self,
+        fields: &HashMap<String, String>,
+        client_ip: Option<IpAddr>,
+    ) -> Result<ContactSubmission, ContactError> {
+        let throttle_key = client_ip.map(|ip| format!("contact:{ip}"));
+        if let (Some(throttle), Some(key)) = (&self.throttle, &throttle_key) {
+            if throttle.is_blocked(key) {
+                return Err(ContactError::RateLimited);
+            }
+            throttle.record_failure(key);
+        }
+
+        if let Some(guard) = &self.guard {
+            guard
+                .validate(&self.form_id, fields)
+                .map_err(ContactError::Spam)?;
+        }
+        if let Some((captcha, field)) = &self.captcha {
+            let response = fields.get(field).map(String::as_str).unwrap_or_default();
+            let valid = !response.is_empty()
+                && captcha
+                    .verify(response, client_ip)
+                    .await
+                    .unwrap_or_else(|e| {
+                        warn!("captcha verification failed: {e:#}");
+                        false
+                    });
+            if !valid {
+                return Err(ContactError::Captcha);
+            }
+        }
+
+        let submission = self.validate(fields, client_ip)?;
+        self.send(&submission).await.map_err(ContactError::Send)?;
+        Ok(submission)
+    }
+
+    fn validate(
+        &self,
+        fields: &HashMap<String, String>,
+        client_ip: Option<IpAddr>,
+    ) -> Result<ContactSubmission, ContactError> {
+        let get = |name: &str| fields.get(name).map(|v| v.trim()).unwrap_or_default();
+        let mut errors = BTreeMap::new();
+        let mut check = |field: &str, problem: Option<String>| {
+            if let Some(problem) = problem {
+                errors.insert(field.to_string(), problem);
+            }
+        };
+
+        let (name, email, subject, message) =
+            (get("name"), get("email"), get("subject"), get("message"));
+        check("name", self.text_problem(name, self.max_field_chars, true));
+        check(
+            "email",
+            match email {
+                "" => Some("required".into()),
+                e if !is_plausible_email(e) || e.parse::<Mailbox>().is_err() => {
+                    Some("invalid email address".into())
+                }
+                _ => None,
+            },
+        );
+        check(
+            "subject",
+            self.text_problem(subject, self.max_field_chars, false),
+        );
+        check(
+            "message",
+            self.text_problem(message, self.max_message_chars, true)
+                .or_else(|| {
+                    (count_links(message) > self.max_links).then(|| "too many links".into())
+                }),
+        );
+        if !errors.is_empty() {
+            return Err(ContactError::Invalid(errors));
+        }
+
+        let reserved = |key: &str| {
+            FIELDS.contains(&key)
+                || key == CSRF_FIELD
+                || self.captcha.as_ref().is_some_and(|(_, f)| f == key)
+                || self
+                    .guard
+                    .as_ref()
+                    .is_some_and(|g| g.is_reserved_field(key))
+        };
+        Ok(ContactSubmission {
+            name: name.to_string(),
+            email: email.to_string(),
+            subject: (!subject.is_empty()).then(|| subject.to_string()),
+            message: message.to_string(),
+            extra: fields
+                .iter()
+                .filter(|(k, _)| !reserved(k))
+                .map(|(k, v)| (k.clone(), v.trim().to_string()))
+                .collect(),
+            client_ip,
+        })
+    }
+
+    fn text_problem(&self, value: &str, max_chars: usize, required: bool) -> Option<String> {
+        if value.is_empty() {
+            return required.then(|| "required".into());
+        }
+        (!is_within_length(value, max_chars)).then(|| format!("at most {max_chars} characters"))
+    }
+
+    async fn send(&self, submission: &ContactSubmission) -> Result<()> {
+        let visitor: Mailbox = submission.email.parse()?;
+        let notification = (self.notification)(submission)?;
+        self.sender
+            .send(Email {
+                subject: notification.subject.clone(),
+                body: notification.into_body(),
+                to: self.notify_to.clone(),
+                cc: vec![],
+                bcc: vec![],
+                headers: vec![EmailHeader::new("Reply-To", visitor.to_string())],
+            })
+            .await?;
+
+        if let Some(render) = &self.auto_reply {
+            let reply = render(submission).map(|reply| Email {
+                subject: reply.subject.clone(),
+                body: reply.into_body(),
+                to: vec![visitor],
+                cc: vec![],
+                bcc: vec![],
+                headers: vec![],
+            });
+            let sent = match reply {
+                Ok(email) => self.sender.send(email).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = sent {
+                warn!("contact auto-reply not sent: {e:#}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The default notification: every field as plain text.
+pub fn default_notification(s: &ContactSubmission) -> RenderedEmail {
+    let mut text = format!("Name: {}\nEmail: {}\n", s.name, s.email);
+    if let Some(subject) = &s.subject {
+        text.push_str(&format!("Subject: {subject}\n"));
+    }
+    for (key, value) in &s.extra {
+        text.push_str(&format!("{key}: {value}\n"));
+    }
+    if let Some(ip) = s.client_ip {
+        text.push_str(&format!("IP: {ip}\n"));
+    }
+    text.push_str(&format!("\n{}\n", s.message));
+
+    let topic = s.subject.as_deref().unwrap_or(&s.name);
+    RenderedEmail::new(format!("[Contact] {topic}")).text(text)
+}
+
+/// `POST` handler for a [`ContactForm`] (see the [module docs](self)).
+///
+/// # Required Extensions
+/// - `CsrfConfig`
+pub async fn contact_handler(State(form): State<Arc<ContactForm>>, req: Request) -> Response {
+    let client_ip = ClientIp::of(req.extensions());
+    let Some(csrf) = req.extensions().get::<CsrfConfig>().cloned() else {
+        warn!("contact form mounted without a CsrfConfig extension");
+        return ContactError::Csrf.into_response();
+    };
+    let mut headers = req.headers().clone();
+
+    let fields = match Form::<HashMap<String, String>>::from_request(req, &()).await {
+        Ok(Form(fields)) => fields,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    let field_token = fields
+        .get(CSRF_FIELD)
+        .filter(|_| !headers.contains_key(CSRF_HEADER_NAME))
+        .and_then(|v| HeaderValue::from_str(v).ok());
+    if let Some(value) = field_token {
+        headers.insert(CSRF_HEADER_NAME, value);
+    }
+    if !validate_csrf(&headers, &CookieJar::from_headers(&headers), &csrf) {
+        return ContactError::Csrf.into_response();
+    }
+
+    match form.submit(&fields, client_ip).await {
+        Ok(_) => match &form.redirect_to {
+            Some(url) => Redirect::to(url).into_response(),
+            None => Json(json!({ "ok": true })).into_response(),
+        },
+        Err(e) => {
+            if let ContactError::Send(cause) = &e {
+                warn!("contact notification failed: {cause:#}");
+            }
+            e.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum_extra::extract::cookie::SameSite;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use axum::{body::Body, http::header, routing::post, Extension, Router};
+    use tower::ServiceExt;
+
+    use crate::auth::throttle::MemoryThrottle;
+    use crate::config::csrf::derive_secret_from_string;
+    use crate::notification::email::EmailBody;
+    use crate::web::csrf::{generate_csrf_token, CSRF_COOKIE_NAME};
+
+    #[derive(Default)]
+    struct Outbox(Mutex<Vec<Email>>);
+
+    #[async_trait]
+    impl EmailSender for Outbox {
+        async fn send(&self, email: Email) -> Result<()> {
+            self.0.lock().unwrap().push(email);
+            Ok(())
+        }
+    }
+
+    struct FixedCaptcha;
+
+    #[async_trait]
+    impl Captcha for FixedCaptcha {
+        async fn verify(&self, response: &str, _client_ip: Option<IpAddr>) -> Result<bool> {
+            Ok(response == "human")
+        }
+    }
+
+    fn csrf_cfg() -> CsrfConfig {
+        CsrfConfig {
+            secret: derive_secret_from_string("contact-secret"),
+            cookie_secure: true,
+            cookie_http_only: true,
+            rotation: Default::default(),
+            token_ttl: None,
+            allowed_origins: None,
+            cookie_same_site: SameSite::Lax,
+            accept_legacy_tokens: false,
+        }
+    }
+
+    fn app(form: ContactForm) -> Router {
+        Router::new()
+            .route("/contact", post(contact_handler))
+            .with_state(Arc::new(form))
+            .layer(Extension(ClientIp("203.0.113.7".parse().unwrap())))
+            .layer(Extension(csrf_cfg()))
+    }
+
+    fn form(outbox: &Arc<Outbox>) -> ContactForm {
+        ContactForm::new(outbox.clone(), vec!["ops@example.com".parse().unwrap()]).unwrap()
+    }
+
+    async fn post_form(app: &Router, body: &str) -> (StatusCode, serde_json::Value) {
+        let token = generate_csrf_token(&csrf_cfg());
+        let body = format!("_csrf={token}&{body}");
+        let req = Request::post("/contact")
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .header(header::COOKIE, format!("{CSRF_COOKIE_NAME}={token}"))
+            .body(Body::from(body))
+            .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        let status = res.status();
+        let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or_default())
+    }
+
+    const VALID: &str = "name=Jane&email=jane%40example.com&message=Hello+there&company=ACME";
+
+    #[tokio::test]
+    async fn sends_notification_with_reply_to_and_auto_reply() {
+        let outbox = Arc::new(Outbox::default());
+        let app = app(form(&outbox)
+            .auto_reply(|s| Ok(RenderedEmail::new("Thanks").text(format!("Hi {}", s.name)))));
+
+        let (status, body) = post_form(&app, VALID).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "ok": true }));
+
+        let sent = outbox.0.lock().unwrap();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].subject, "[Contact] Jane");
+        assert_eq!(sent[0].to[0].email.to_string(), "ops@example.com");
+        assert_eq!(sent[0].headers[0].value, "jane@example.com");
+        match &sent[0].body {
+            EmailBody::Text(text) => {
+                assert!(text.contains("company: ACME"));
+                assert!(text.contains("IP: 203.0.113.7"));
+                assert!(!text.contains("_csrf"));
+            }
+            other => panic!("unexpected body: {other:?}"),
+        }
+        assert_eq!(sent[1].to[0].email.to_string(), "jane@example.com");
+        assert_eq!(sent[1].subject, "Thanks");
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_fields_and_missing_csrf() {
+        let outbox = Arc::new(Outbox::default());
+        let app = app(form(&outbox));
+
+        let links = "message=http%3A%2F%2Fa+http%3A%2F%2Fb+http%3A%2F%2Fc+http%3A%2F%2Fd";
+        let (status, body) = post_form(&app, &format!("name=&email=nope&{links}")).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            body["errors"],
+            json!({
+                "name": "required",
+                "email": "invalid email address",
+                "message": "too many links",
+            })
+        );
+
+        let req = Request::post("/contact")
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(VALID))
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert!(outbox.0.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn spam_checks_and_rate_limit() {
+        let outbox = Arc::new(Outbox::default());
+        let guard = FormGuard::new(derive_secret_from_string("form")).min_fill_time(Duration::ZERO);
+        let token = guard.issue("contact");
+        let app = app(form(&outbox)
+            .form_guard(guard)
+            .captcha(Arc::new(FixedCaptcha), "captcha")
+            .throttle(Arc::new(MemoryThrottle::new(3, Duration::from_secs(60)))));
+
+        let with = |extra: &str| format!("{VALID}&_form_token={token}&{extra}");
+        assert_eq!(
+            post_form(&app, &with("website=spam&captcha=human")).await.0,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            post_form(&app, &with("captcha=robot")).await.0,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            post_form(&app, &with("captcha=human")).await.0,
+            StatusCode::OK
+        );
+        assert_eq!(
+            post_form(&app, &with("captcha=human")).await.0,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(outbox.0.lock().unwrap().len(), 1);
+    }
+}
@@ -13,9 +13,51 @@
 //! - Encoded using Base64 (URL-safe, no padding)
 //! - Tokens are stored in both a cookie and an HTTP header for verification
 //!
+//! When interval rotation or a token TTL is configured, tokens also carry
+//! their issue time (Unix seconds, covered by the MAC):
+//!
+//! ```text
+//! v2.<issued_at>.<nonce_b64>.<mac_b64>
+//! ```
+//!
+//! # Expiry
+//! With [`CsrfConfig::token_ttl`] set, [`verify_token`] rejects `v2` tokens
+//! older than the TTL, and `v1` tokens altogether since their age is
+//! unknown — unless [`CsrfConfig::accept_legacy_tokens`]
+//! (`CSRF_ACCEPT_LEGACY_TOKENS`) grants them a grace period while the TTL
+//! is rolled out. [`csrf_handler`] replaces a rejected or `v1` token with a
+//! fresh `v2` one, so clients recover by fetching a new token.
+//!
 //! # Endpoints
 //! The included [`csrf_handler`] can be mounted at `/csrf` to issue or refresh CSRF tokens.
 //!
+//! # Enforcement
+//! Mount [`CsrfLayer`](crate::web::middleware::csrf::CsrfLayer) on the router
+//! to check [`validate_csrf`] on every unsafe request instead of calling it
+//! in each handler.
+//!
+//! # Origin check
+//! With [`CsrfConfig::allowed_origins`] set (`CSRF_CHECK_ORIGIN`),
+//! [`validate_csrf`] additionally requires the request's `Origin` header —
+//! or, when it is missing or `null`, the origin of its `Referer` — to be the
+//! request's own host or one of the configured origins (the `CORS_ORIGINS`
+//! list). This is defense in depth on top of the token pair, e.g. against a
+//! token leaked from a sibling subdomain. Requests carrying neither header
+//! are rejected.
+//!
+//! # Rotation
+//! By default a valid token is reused forever. [`CsrfRotation`] enables:
+//!
+//! - **interval rotation** — [`csrf_handler`] replaces tokens older than the
+//!   interval and tells the SPA when to come back (`refreshAfter`);
+//! - **rotation on state change** — the [`rotate_csrf_token`] middleware
+//!   replaces the token after each successful `POST` / `PUT` / `PATCH` /
+//!   `DELETE` that passed CSRF validation and returns the new one in the
+//!   `X-CSRF-Token` response header.
+//!
+//! In both cases the JSON / header carries the new token, so the SPA must
+//! use it for subsequent requests (`rotated: true` in the JSON).
+//!
 //! # Example
 //! ```rust,no_run
 //! use axum::{Router, routing::get};
@@ -32,11 +74,16 @@
 //! // 2. Validate against the cookie
 //! ```
 
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use axum::{
+    extract::Request,
     http::{
-        header::{CACHE_CONTROL, CONTENT_TYPE},
-        HeaderMap, StatusCode,
+        header::{CONTENT_TYPE, HOST, ORIGIN, REFERER},
+        HeaderMap, HeaderValue, StatusCode,
     },
+    middleware::Next,
+    response::{IntoResponse, Response},
     Extension, Json,
 };
 use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
@@ -46,7 +93,8 @@ use serde::Serialize;
 use sha2::Sha256;
 use subtle::ConstantTimeEq;
 
-use crate::config::csrf::CsrfConfig;
+use crate::config::csrf::{CsrfConfig, CsrfRotation};
+use crate::web::headers::HeaderSet;
 
 /// Cookie name used to store the CSRF token.
 pub const CSRF_COOKIE_NAME: &str = "csrf";
@@ -82,18 +130,83 @@ pub fn generate_csrf_token(cfg: &CsrfConfig) -> String {
     )
 }
 
-/// Verifies a CSRF token’s HMAC signature and format.
+/// Generates a CSRF token carrying its issue time (`v2`), used for
+/// interval rotation.
+///
+/// # Example
+/// ```rust
+/// use wzs_web::config::csrf::CsrfConfig;
+/// use wzs_web::web::csrf::{generate_timed_csrf_token, token_issued_at, verify_token};
+///
+/// let cfg = CsrfConfig::from_env();
+/// let token = generate_timed_csrf_token(&cfg, 1_700_000_000);
+/// assert!(verify_token(&cfg, &token));
+/// assert_eq!(token_issued_at(&token), Some(1_700_000_000));
+/// ```
+pub fn generate_timed_csrf_token(cfg: &CsrfConfig, issued_at: u64) -> String {
+    let nonce: [u8; 32] = rand::random();
+    let tag = timed_mac(cfg, issued_at, &nonce).expect("HMAC key");
+
+    format!(
+        "v2.{issued_at}.{}.{}",
+        URL_SAFE_NO_PAD.encode(nonce),
+        URL_SAFE_NO_PAD.encode(tag)
+    )
+}
+
+/// Generates a token in the format `cfg` needs: timed (`v2`) when interval
+/// rotation or a TTL is configured, `v1` otherwise.
+///
+/// Use this rather than [`generate_csrf_token`] when rendering tokens into
+/// pages, so they pass [`verify_token`] under a TTL.
+pub fn issue_csrf_token(cfg: &CsrfConfig) -> String {
+    new_token(cfg, unix_now())
+}
+
+/// Returns the issue time of a `v2` token (not verified; `None` for `v1`).
+pub fn token_issued_at(token: &str) -> Option<u64> {
+    let mut parts = token.split('.');
+    (parts.next() == Some("v2"))
+        .then(|| parts.next()?.parse().ok())
+        .flatten()
+}
+
+/// Verifies a CSRF token’s HMAC signature and format (`v1` or `v2`), and
+/// the age of `v2` tokens against [`CsrfConfig::token_ttl`] (`v1` tokens are
+/// rejected while a TTL is set, unless
+/// [`CsrfConfig::accept_legacy_tokens`]).
 ///
 /// Returns `true` if valid, `false` otherwise.
 pub fn verify_token(cfg: &CsrfConfig, token: &str) -> bool {
-    let mut parts = token.split('.');
-    let (Some(v), Some(nonce_b64), Some(mac_b64)) = (parts.next(), parts.next(), parts.next())
-    else {
-        return false;
+    verify_token_at(cfg, token, unix_now())
+}
+
+/// [`verify_token`] at Unix time `now`.
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+/// use wzs_web::config::csrf::CsrfConfig;
+/// use wzs_web::web::csrf::{generate_timed_csrf_token, verify_token_at};
+///
+/// let cfg = CsrfConfig {
+///     token_ttl: Some(Duration::from_secs(3600)),
+///     ..CsrfConfig::from_env()
+/// };
+/// let token = generate_timed_csrf_token(&cfg, 1_700_000_000);
+/// assert!(verify_token_at(&cfg, &token, 1_700_000_000 + 3599));
+/// assert!(!verify_token_at(&cfg, &token, 1_700_000_000 + 3600));
+/// ```
+pub fn verify_token_at(cfg: &CsrfConfig, token: &str, now: u64) -> bool {
+    let parts: Vec<&str> = token.split('.').collect();
+    let (issued_at, nonce_b64, mac_b64) = match parts.as_slice() {
+        ["v1", nonce, mac] => (None, *nonce, *mac),
+        ["v2", iat, nonce, mac] => match iat.parse::<u64>() {
+            Ok(iat) => (Some(iat), *nonce, *mac),
+            Err(_) => return false,
+        },
+        _ => return false,
     };
-    if parts.next().is_some() || v != "v1" {
-        return false;
-    }
 
     let Ok(nonce) = URL_SAFE_NO_PAD.decode(nonce_b64) else {
         return false;
@@ -106,18 +219,45 @@ pub fn verify_token(cfg: &CsrfConfig, token: &str) -> bool {
         return false;
     }
 
-    let Ok(mut h) = HmacSha256::new_from_slice(&cfg.secret) else {
+    let expected = match issued_at {
+        Some(iat) => timed_mac(cfg, iat, &nonce),
+        None => HmacSha256::new_from_slice(&cfg.secret).ok().map(|mut h| {
+            h.update(&nonce);
+            h.finalize().into_bytes().to_vec()
+        }),
+    };
+    let Some(expected) = expected else {
         return false;
     };
-    h.update(&nonce);
-    let expected = h.finalize().into_bytes();
 
-    (&expected[..]).ct_eq(&mac).unwrap_u8() == 1
+    if expected[..].ct_eq(&mac).unwrap_u8() != 1 {
+        return false;
+    }
+    match (issued_at, cfg.token_ttl) {
+        (Some(iat), Some(ttl)) => now < iat.saturating_add(ttl.as_secs()),
+        (None, Some(_)) => cfg.accept_legacy_tokens,
+        (_, None) => true,
+    }
+}
+
+/// MAC of a `v2` token: covers the version, issue time and nonce.
+fn timed_mac(cfg: &CsrfConfig, issued_at: u64, nonce: &[u8]) -> Option<Vec<u8>> {
+    let mut h = HmacSha256::new_from_slice(&cfg.secret).ok()?;
+    h.update(format!("v2.{issued_at}.").as_bytes());
+    h.update(nonce);
+    Some(h.finalize().into_bytes().to_vec())
 }
 
-/// Sets a signed CSRF cookie using configuration flags (`Secure`, `HttpOnly`).
+/// Sets a signed CSRF cookie using configuration flags (`Secure`, `HttpOnly`,
+/// `SameSite`).
 pub fn set_csrf_cookie(jar: CookieJar, cfg: &CsrfConfig, token: &str) -> CookieJar {
-    set_csrf_cookie_with_flags(jar, token, cfg.cookie_secure, cfg.cookie_http_only)
+    let cookie = Cookie::build((CSRF_COOKIE_NAME, token.to_string()))
+        .path("/")
+        .same_site(cfg.cookie_same_site)
+        .secure(cfg.cookie_secure)
+        .http_only(cfg.cookie_http_only)
+        .build();
+    jar.add(cookie)
 }
 
 /// Adds a CSRF cookie with explicit security flags.
@@ -138,7 +278,9 @@ pub fn set_csrf_cookie_with_flags(
 
 /// Validates a CSRF token pair (header + cookie).
 ///
-/// Returns `true` only if both are present, identical, and correctly signed.
+/// Returns `true` only if both are present, identical, and correctly signed,
+/// and — when [`CsrfConfig::allowed_origins`] is set — the request passes
+/// [`origin_allowed`].
 ///
 /// # Example
 /// ```rust,no_run
@@ -178,7 +320,65 @@ pub fn validate_csrf(headers: &HeaderMap, jar: &CookieJar, cfg: &CsrfConfig) ->
         return false;
     }
 
-    verify_token(cfg, &cookie_token)
+    verify_token(cfg, &cookie_token) && origin_allowed(headers, cfg)
+}
+
+/// Checks the `Origin` (falling back to `Referer`) header against the
+/// request's `Host` and [`CsrfConfig::allowed_origins`].
+///
+/// Always `true` when the check is disabled.
+///
+/// # Example
+/// ```rust
+/// use axum::http::{header, HeaderMap, HeaderValue};
+/// use wzs_web::config::csrf::CsrfConfig;
+/// use wzs_web::web::csrf::origin_allowed;
+///
+/// let cfg = CsrfConfig {
+///     allowed_origins: Some(vec!["https://app.example.com".into()]),
+///     ..CsrfConfig::from_env()
+/// };
+/// let mut headers = HeaderMap::new();
+/// headers.insert(header::HOST, HeaderValue::from_static("api.example.com"));
+///
+/// headers.insert(header::ORIGIN, HeaderValue::from_static("https://app.example.com"));
+/// assert!(origin_allowed(&headers, &cfg));
+///
+/// headers.insert(header::ORIGIN, HeaderValue::from_static("https://evil.example"));
+/// assert!(!origin_allowed(&headers, &cfg));
+/// ```
+pub fn origin_allowed(headers: &HeaderMap, cfg: &CsrfConfig) -> bool {
+    let Some(allowed) = &cfg.allowed_origins else {
+        return true;
+    };
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    let origin = header(ORIGIN)
+        .filter(|o| !o.is_empty() && *o != "null")
+        .map(|o| o.trim_end_matches('/').to_ascii_lowercase())
+        .or_else(|| header(REFERER).and_then(referer_origin));
+    origin.is_some_and(|origin| origin_matches(headers, &origin, allowed))
+}
+
+/// Whether `origin` (lowercased, no trailing slash) is the request's own
+/// host or one of `allowed`.
+pub(crate) fn origin_matches(headers: &HeaderMap, origin: &str, allowed: &[String]) -> bool {
+    let same_host = headers
+        .get(HOST)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|host| {
+            origin
+                .split_once("://")
+                .is_some_and(|(_, authority)| authority.eq_ignore_ascii_case(host))
+        });
+    same_host || allowed.iter().any(|a| a == origin)
+}
+
+/// `scheme://authority` of a `Referer` URL, lowercased.
+fn referer_origin(referer: &str) -> Option<String> {
+    let (scheme, rest) = referer.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    (!scheme.is_empty() && !authority.is_empty())
+        .then(|| format!("{scheme}://{authority}").to_ascii_lowercase())
 }
 
 /// JSON response schema returned by [`csrf_handler`].
@@ -186,13 +386,23 @@ pub fn validate_csrf(headers: &HeaderMap, jar: &CookieJar, cfg: &CsrfConfig) ->
 pub struct CsrfResponse {
     #[serde(rename = "csrfToken")]
     pub csrf_token: String,
+    /// `true` when the cookie held a different token before this call; the
+    /// SPA must replace any token it cached.
+    pub rotated: bool,
+    /// Unix time (seconds) after which the SPA should fetch a new token
+    /// (interval rotation or token TTL only).
+    #[serde(rename = "refreshAfter", skip_serializing_if = "Option::is_none")]
+    pub refresh_after: Option<u64>,
 }
 
 /// Axum handler that issues or refreshes a CSRF token.
 ///
-/// - If a valid cookie token exists, it is reused.
+/// - If a valid cookie token exists (and is not due for rotation under
+///   [`CsrfRotation::interval`], nor an untimed token accepted only through
+///   [`CsrfConfig::accept_legacy_tokens`]), it is reused.
 /// - Otherwise, a new token is generated and set in a `Set-Cookie` header.
-/// - The token is also returned as JSON for the frontend.
+/// - The token is also returned as JSON for the frontend, with the
+///   rotation hints of [`CsrfResponse`].
 ///
 /// # Example
 /// ```rust,no_run
@@ -209,31 +419,111 @@ pub async fn csrf_handler(
     Extension(cfg): Extension<CsrfConfig>,
     jar: CookieJar,
 ) -> (CookieJar, (StatusCode, HeaderMap, Json<CsrfResponse>)) {
-    let token = match jar
-        .get(CSRF_COOKIE_NAME)
-        .map(|c| c.value().to_string())
+    let now = unix_now();
+    let previous = jar.get(CSRF_COOKIE_NAME).map(|c| c.value().to_string());
+    let token = match previous
+        .clone()
         .filter(|t| verify_token(&cfg, t))
+        .filter(|t| !rotation_due(&cfg.rotation, t, now))
+        .filter(|t| cfg.token_ttl.is_none() || token_issued_at(t).is_some())
     {
         Some(t) => t,
-        None => generate_csrf_token(&cfg),
+        None => new_token(&cfg, now),
     };
 
     let jar = set_csrf_cookie(jar, &cfg, &token);
 
     let mut headers = HeaderMap::new();
-    headers.insert(
-        CACHE_CONTROL,
-        "no-store, no-cache, must-revalidate".parse().unwrap(),
-    );
-    headers.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    HeaderSet::no_store()
+        .set(CONTENT_TYPE, HeaderValue::from_static("application/json"))
+        .apply(&mut headers);
 
     let json = Json(CsrfResponse {
         csrf_token: token.clone(),
+        rotated: previous.is_some_and(|p| p != token),
+        refresh_after: refresh_after(&cfg, &token),
     });
 
     (jar, (StatusCode::OK, headers, json))
 }
 
+/// Middleware rotating the CSRF token after successful state changes
+/// (when [`CsrfRotation::on_state_change`] is set).
+///
+/// Applies to unsafe methods whose CSRF pair validates and whose response
+/// is `2xx`; mount it on the authenticated routes. The new token is set as
+/// the cookie and returned in the `X-CSRF-Token` response header.
+///
+/// # Required Extensions
+/// - `CsrfConfig`
+///
+/// # Example
+/// ```rust,no_run
+/// use axum::{middleware::from_fn, routing::post, Extension, Router};
+/// use wzs_web::config::csrf::CsrfConfig;
+/// use wzs_web::web::csrf::rotate_csrf_token;
+///
+/// let app: Router = Router::new()
+///     .route("/account/email", post(|| async { "ok" }))
+///     .layer(from_fn(rotate_csrf_token))
+///     .layer(Extension(CsrfConfig::from_env()));
+/// ```
+pub async fn rotate_csrf_token(
+    Extension(cfg): Extension<CsrfConfig>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if !cfg.rotation.on_state_change || req.method().is_safe() {
+        return next.run(req).await;
+    }
+    let valid = validate_csrf(req.headers(), &CookieJar::from_headers(req.headers()), &cfg);
+    let res = next.run(req).await;
+    if !valid || !res.status().is_success() {
+        return res;
+    }
+
+    let token = new_token(&cfg, unix_now());
+    let mut res = (set_csrf_cookie(CookieJar::new(), &cfg, &token), res).into_response();
+    if let Ok(value) = HeaderValue::from_str(&token) {
+        res.headers_mut().insert(CSRF_HEADER_NAME, value);
+    }
+    res
+}
+
+/// New token in the format the rotation policy and TTL need.
+fn new_token(cfg: &CsrfConfig, now: u64) -> String {
+    if cfg.rotation.interval.is_some() || cfg.token_ttl.is_some() {
+        generate_timed_csrf_token(cfg, now)
+    } else {
+        generate_csrf_token(cfg)
+    }
+}
+
+/// When the SPA should fetch a new token: at the rotation interval or the
+/// expiry, whichever comes first.
+fn refresh_after(cfg: &CsrfConfig, token: &str) -> Option<u64> {
+    let iat = token_issued_at(token)?;
+    [cfg.rotation.interval, cfg.token_ttl]
+        .into_iter()
+        .flatten()
+        .map(|d| iat + d.as_secs())
+        .min()
+}
+
+/// Whether `token` is older than the rotation interval (untimed tokens
+/// always are).
+fn rotation_due(rotation: &CsrfRotation, token: &str, now: u64) -> bool {
+    rotation.interval.is_some_and(|iv| {
+        token_issued_at(token).is_none_or(|iat| now >= iat.saturating_add(iv.as_secs()))
+    })
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -247,6 +537,11 @@ mod tests {
             secret: derive_secret_from_string("test-fixed-secret"),
             cookie_secure: true,
             cookie_http_only: true,
+            rotation: Default::default(),
+            token_ttl: None,
+            allowed_origins: None,
+            cookie_same_site: SameSite::Lax,
+            accept_legacy_tokens: false,
         }
     }
 
@@ -406,6 +701,49 @@ mod tests {
         assert!(!validate_csrf(&headers, &jar, &cfg));
     }
 
+    #[test]
+    fn validate_csrf_checks_origin_when_enabled() {
+        let cfg = CsrfConfig {
+            allowed_origins: Some(vec!["https://app.example.com".into()]),
+            ..test_cfg()
+        };
+        let token = generate_csrf_token(&cfg);
+        let jar = CookieJar::new().add(Cookie::new(CSRF_COOKIE_NAME, token.clone()));
+        let with = |pairs: &[(&'static str, &'static str)]| {
+            let mut headers = HeaderMap::new();
+            headers.insert(CSRF_HEADER_NAME, HeaderValue::from_str(&token).unwrap());
+            headers.insert(HOST, HeaderValue::from_static("api.example.com"));
+            for (name, value) in pairs {
+                headers.insert(*name, HeaderValue::from_static(value));
+            }
+            headers
+        };
+
+        for ok in [
+            with(&[("origin", "https://APP.example.com")]),
+            with(&[("origin", "https://api.example.com")]),
+            with(&[
+                ("origin", "null"),
+                ("referer", "https://app.example.com/a?b"),
+            ]),
+            with(&[("referer", "https://api.example.com/form")]),
+        ] {
+            assert!(validate_csrf(&ok, &jar, &cfg), "{ok:?}");
+        }
+        for bad in [
+            with(&[]),
+            with(&[("origin", "https://evil.example")]),
+            with(&[("origin", "https://app.example.com.evil.example")]),
+            with(&[("referer", "https://evil.example/https://app.example.com")]),
+        ] {
+            assert!(!validate_csrf(&bad, &jar, &cfg), "{bad:?}");
+        }
+        assert!(
+            validate_csrf(&with(&[]), &jar, &test_cfg()),
+            "check disabled"
+        );
+    }
+
     #[tokio::test]
     async fn csrf_handler_sets_cookie_and_returns_token() {
         let cfg = test_cfg();
@@ -475,4 +813,160 @@ mod tests {
         assert_eq!(cookie.value(), body.csrf_token);
         assert!(verify_token(&cfg, cookie.value()));
     }
+
+    #[test]
+    fn timed_tokens_cover_the_issue_time() {
+        let cfg = test_cfg();
+        let t = generate_timed_csrf_token(&cfg, 1_000);
+        assert!(verify_token(&cfg, &t));
+        assert_eq!(token_issued_at(&t), Some(1_000));
+        assert_eq!(token_issued_at(&generate_csrf_token(&cfg)), None);
+
+        let backdated = t.replacen("v2.1000.", "v2.999999.", 1);
+        assert!(!verify_token(&cfg, &backdated));
+        assert!(!verify_token(&cfg, &t.replacen("v2.", "v1.", 1)));
+    }
+
+    fn rotating_cfg(interval_secs: u64) -> CsrfConfig {
+        CsrfConfig {
+            rotation: CsrfRotation {
+                on_state_change: true,
+                interval: Some(std::time::Duration::from_secs(interval_secs)),
+            },
+            ..test_cfg()
+        }
+    }
+
+    #[tokio::test]
+    async fn csrf_handler_rotates_tokens_past_the_interval() {
+        let cfg = rotating_cfg(3600);
+        let now = unix_now();
+
+        let fresh = generate_timed_csrf_token(&cfg, now - 10);
+        let jar = CookieJar::new().add(Cookie::new(CSRF_COOKIE_NAME, fresh.clone()));
+        let (_, (_, _, body)) = csrf_handler(Extension(cfg.clone()), jar).await;
+        assert_eq!(body.csrf_token, fresh);
+        assert!(!body.rotated);
+        assert_eq!(body.refresh_after, Some(now - 10 + 3600));
+
+        for stale in [
+            generate_timed_csrf_token(&cfg, now - 3600),
+            generate_csrf_token(&cfg),
+        ] {
+            let jar = CookieJar::new().add(Cookie::new(CSRF_COOKIE_NAME, stale.clone()));
+            let (jar_after, (_, _, body)) = csrf_handler(Extension(cfg.clone()), jar).await;
+            assert_ne!(body.csrf_token, stale);
+            assert!(body.rotated);
+            assert_eq!(
+                jar_after.get(CSRF_COOKIE_NAME).unwrap().value(),
+                body.csrf_token
+            );
+            assert!(token_issued_at(&body.csrf_token).unwrap() >= now);
+        }
+
+        let (_, (_, _, Json(plain))) = csrf_handler(Extension(test_cfg()), CookieJar::new()).await;
+        let json = serde_json::to_value(&plain).unwrap();
+        assert_eq!(json["rotated"], false);
+        assert!(json.get("refreshAfter").is_none());
+    }
+
+    #[tokio::test]
+    async fn token_ttl_expires_timed_tokens_and_rejects_v1() {
+        let cfg = CsrfConfig {
+            token_ttl: Some(std::time::Duration::from_secs(600)),
+            ..test_cfg()
+        };
+        let now = unix_now();
+
+        let t = generate_timed_csrf_token(&cfg, 1_000);
+        assert!(verify_token_at(&cfg, &t, 1_599));
+        assert!(!verify_token_at(&cfg, &t, 1_600));
+        assert!(verify_token_at(&test_cfg(), &t, 1_600), "no TTL configured");
+
+        let legacy = generate_csrf_token(&cfg);
+        assert!(!verify_token(&cfg, &legacy), "v1 tokens have no age");
+        assert!(verify_token(&test_cfg(), &legacy));
+
+        let issued = issue_csrf_token(&cfg);
+        assert!(issued.starts_with("v2."));
+        assert!(verify_token(&cfg, &issued));
+        assert!(issue_csrf_token(&test_cfg()).starts_with("v1."));
+
+        for old in [legacy, generate_timed_csrf_token(&cfg, now - 600)] {
+            let jar = CookieJar::new().add(Cookie::new(CSRF_COOKIE_NAME, old.clone()));
+            let (_, (_, _, body)) = csrf_handler(Extension(cfg.clone()), jar).await;
+            assert!(body.rotated);
+            assert!(body.csrf_token.starts_with("v2."));
+            let iat = token_issued_at(&body.csrf_token).unwrap();
+            assert_eq!(body.refresh_after, Some(iat + 600));
+        }
+    }
+
+    #[tokio::test]
+    async fn legacy_grace_accepts_v1_tokens_and_reissues_them_timed() {
+        let cfg = CsrfConfig {
+            token_ttl: Some(std::time::Duration::from_secs(600)),
+            accept_legacy_tokens: true,
+            ..test_cfg()
+        };
+        let legacy = generate_csrf_token(&cfg);
+        assert!(verify_token(&cfg, &legacy));
+        let forged = format!("{}x", &legacy[..legacy.len() - 1]);
+        assert!(!verify_token(&cfg, &forged));
+        let expired = generate_timed_csrf_token(&cfg, 1_000);
+        assert!(
+            !verify_token_at(&cfg, &expired, 1_600),
+            "TTL still applies to v2"
+        );
+
+        let jar = CookieJar::new().add(Cookie::new(CSRF_COOKIE_NAME, legacy.clone()));
+        let (_, (_, _, body)) = csrf_handler(Extension(cfg.clone()), jar).await;
+        assert!(body.rotated);
+        assert!(body.csrf_token.starts_with("v2."));
+        assert!(verify_token(&cfg, &body.csrf_token));
+    }
+
+    #[tokio::test]
+    async fn middleware_rotates_after_successful_validated_state_changes() {
+        use axum::{body::Body, http::Request, middleware::from_fn, routing::post, Router};
+        use tower::ServiceExt;
+
+        let cfg = rotating_cfg(3600);
+        let app = Router::new()
+            .route("/ok", post(|| async { "ok" }).get(|| async { "ok" }))
+            .route("/fail", post(|| async { StatusCode::UNPROCESSABLE_ENTITY }))
+            .layer(from_fn(rotate_csrf_token))
+            .layer(Extension(cfg.clone()));
+
+        let token = generate_timed_csrf_token(&cfg, unix_now());
+        let send = |method: &str, uri: &str, with_token: bool| {
+            let mut req = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("cookie", format!("{CSRF_COOKIE_NAME}={token}"));
+            if with_token {
+                req = req.header(CSRF_HEADER_NAME, token.as_str());
+            }
+            app.clone().oneshot(req.body(Body::empty()).unwrap())
+        };
+
+        let res = send("POST", "/ok", true).await.unwrap();
+        let rotated = res.headers()[CSRF_HEADER_NAME]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_ne!(rotated, token);
+        assert!(verify_token(&cfg, &rotated));
+        let set_cookie = res.headers()["set-cookie"].to_str().unwrap();
+        assert!(set_cookie.starts_with(&format!("{CSRF_COOKIE_NAME}={rotated}")));
+
+        for res in [
+            send("GET", "/ok", true).await.unwrap(),
+            send("POST", "/ok", false).await.unwrap(),
+            send("POST", "/fail", true).await.unwrap(),
+        ] {
+            assert!(!res.headers().contains_key(CSRF_HEADER_NAME));
+            assert!(!res.headers().contains_key("set-cookie"));
+        }
+    }
 }
@@ -0,0 +1,427 @@
+//! # Entity Tags and Conditional GET
+//!
+//! [`ETag`] is an entity tag, either strong (derived from the exact bytes,
+//! [`ETag::for_body`]) or weak (derived from file metadata,
+//! [`ETag::for_file`]).
+//!
+//! [`ETagLayer`] is a tower layer answering conditional `GET` / `HEAD`
+//! requests:
+//!
+//! - a `200 OK` response without an `ETag` whose body is known to be at
+//!   most [`max_body`](ETagLayer::max_body) bytes (default 1 MiB) is
+//!   buffered and tagged with [`ETag::for_body`]; streamed bodies of unknown
+//!   length pass through untagged;
+//! - when the request's `If-None-Match` matches the response `ETag` (weak
+//!   comparison, `*` matches anything), the response is replaced by
+//!   `304 Not Modified` keeping the validators and caching headers but no
+//!   body or `Content-*` headers.
+//!
+//! Handlers that know a cheaper validator (a version column, a content
+//! hash, a file mtime) set `ETag` themselves; the layer then only compares.
+//! `HEAD` bodies are stripped by the router, so `HEAD` responses are only
+//! compared, never tagged. Other methods pass through.
+//!
+//! If a compression layer runs *outside* this one, enable
+//! [`weak`](ETagLayer::weak): a strong tag must not be shared between the
+//! compressed and uncompressed bytes.
+//!
+//! For stored uploads, see
+//! [`mtime_download_handler`](crate::web::upload::download::mtime_download_handler),
+//! which tags files by modification time without reading them.
+//!
+//! # Example
+//! ```rust
+//! use axum::{routing::get, Router};
+//! use wzs_web::web::etag::ETagLayer;
+//!
+//! let app: Router = Router::new()
+//!     .route("/api/settings", get(|| async { r#"{"theme":"dark"}"# }))
+//!     .layer(ETagLayer::new().max_body(256 * 1024));
+//! # let _ = app;
+//! ```
+
+use std::fmt;
+use std::task::{Context, Poll};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    body::{to_bytes, Body, HttpBody},
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures_util::future::BoxFuture;
+use tower::{Layer, Service};
+
+use crate::web::problem::Problem;
+use crate::web::upload::cache_policy::{body_etag, etag_matches};
+
+/// Default [`ETagLayer::max_body`]: 1 MiB.
+pub const DEFAULT_MAX_BODY: usize = 1024 * 1024;
+
+/// An entity tag.
+///
+/// Displays as the header value (`"…"` or `W/"…"`).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ETag {
+    opaque: String,
+    weak: bool,
+}
+
+impl ETag {
+    /// Strong tag with the (unquoted) `opaque` value.
+    pub fn strong(opaque: impl Into<String>) -> Self {
+        Self {
+            opaque: opaque.into(),
+            weak: false,
+        }
+    }
+
+    /// Weak tag with the (unquoted) `opaque` value.
+    pub fn weak(opaque: impl Into<String>) -> Self {
+        Self {
+            opaque: opaque.into(),
+            weak: true,
+        }
+    }
+
+    /// Strong tag from a SHA-256 of `body` (first 128 bits).
+    pub fn for_body(body: &[u8]) -> Self {
+        Self::strong(body_etag(body).trim_matches('"'))
+    }
+
+    /// Weak tag from a file's modification time and size.
+    ///
+    /// Weak because a rewrite within the same timestamp and size keeps the
+    /// tag.
+    pub fn for_file(modified: SystemTime, len: u64) -> Self {
+        let since = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+        Self::weak(format!(
+            "{:x}.{:x}-{len:x}",
+            since.as_secs(),
+            since.subsec_nanos()
+        ))
+    }
+
+    /// Parses a header value (`"abc"` or `W/"abc"`).
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (weak, quoted) = match value.strip_prefix("W/") {
+            Some(rest) => (true, rest),
+            None => (false, value),
+        };
+        let opaque = quoted.strip_prefix('"')?.strip_suffix('"')?;
+        if opaque.contains('"') {
+            return None;
+        }
+        Some(Self {
+            opaque: opaque.to_string(),
+            weak,
+        })
+    }
+
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    /// The unquoted tag value.
+    pub fn opaque(&self) -> &str {
+        &self.opaque
+    }
+
+    /// Returns whether the request's `If-None-Match` matches this tag.
+    pub fn matches(&self, headers: &HeaderMap) -> bool {
+        etag_matches(headers, &self.to_string())
+    }
+
+    /// The header value; `None` if the tag contains characters not allowed
+    /// in a header.
+    pub fn to_header_value(&self) -> Option<HeaderValue> {
+        HeaderValue::from_str(&self.to_string()).ok()
+    }
+}
+
+impl fmt::Display for ETag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.weak {
+            f.write_str("W/")?;
+        }
+        write!(f, "\"{}\"", self.opaque)
+    }
+}
+
+/// Tower layer tagging responses and answering `If-None-Match`; see the
+/// [module docs](self).
+#[derive(Clone, Copy, Debug)]
+pub struct ETagLayer {
+    weak: bool,
+    max_body: usize,
+}
+
+impl Default for ETagLayer {
+    fn default() -> Self {
+        Self {
+            weak: false,
+            max_body: DEFAULT_MAX_BODY,
+        }
+    }
+}
+
+impl ETagLayer {
+    /// Strong tags for bodies up to [`DEFAULT_MAX_BODY`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks computed tags weak.
+    pub fn weak(mut self, enabled: bool) -> Self {
+        self.weak = enabled;
+        self
+    }
+
+    /// Largest body (in bytes) buffered to compute a tag.
+    pub fn max_body(mut self, bytes: usize) -> Self {
+        self.max_body = bytes;
+        self
+    }
+
+    /// Buffers the body of `res` and sets its `ETag` if it is small enough.
+    async fn tag(self, res: Response) -> Response {
+        let fits = res
+            .body()
+            .size_hint()
+            .upper()
+            .is_some_and(|n| n <= self.max_body as u64);
+        if !fits {
+            return res;
+        }
+        let (mut parts, body) = res.into_parts();
+        let bytes = match to_bytes(body, self.max_body).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                tracing::warn!("failed to buffer response body for ETag: {e}");
+                return Problem::internal().into_response();
+            }
+        };
+        let mut etag = ETag::for_body(&bytes);
+        etag.weak = self.weak;
+        if let Some(value) = etag.to_header_value() {
+            parts.headers.insert(header::ETAG, value);
+        }
+        Response::from_parts(parts, Body::from(bytes))
+    }
+}
+
+impl<S> Layer<S> for ETagLayer {
+    type Service = ETagService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ETagService {
+            inner,
+            layer: *self,
+        }
+    }
+}
+
+/// Service produced by [`ETagLayer`].
+#[derive(Clone)]
+pub struct ETagService<S> {
+    inner: S,
+    layer: ETagLayer,
+}
+
+impl<S> Service<Request> for ETagService<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let method = req.method().clone();
+        if method != Method::GET && method != Method::HEAD {
+            return Box::pin(self.inner.call(req));
+        }
+
+        let mut conditions = HeaderMap::new();
+        for value in req.headers().get_all(header::IF_NONE_MATCH) {
+            conditions.append(header::IF_NONE_MATCH, value.clone());
+        }
+        let layer = self.layer;
+        let fut = self.inner.call(req);
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+            if res.status() != StatusCode::OK {
+                return Ok(res);
+            }
+            if method == Method::GET && !res.headers().contains_key(header::ETAG) {
+                res = layer.tag(res).await;
+            }
+            let matched = res
+                .headers()
+                .get(header::ETAG)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|etag| etag_matches(&conditions, etag));
+            Ok(if matched { not_modified(&res) } else { res })
+        })
+    }
+}
+
+/// `304 Not Modified` with the headers of `res`, minus the `Content-*`
+/// representation metadata (`Content-Location` is kept).
+fn not_modified(res: &Response) -> Response {
+    let mut out = StatusCode::NOT_MODIFIED.into_response();
+    for (name, value) in res.headers() {
+        let content = name.as_str().starts_with("content-") && name != header::CONTENT_LOCATION;
+        if !content {
+            out.headers_mut().append(name.clone(), value.clone());
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use axum::{routing::get, Router};
+    use futures_util::stream;
+    use tower::ServiceExt;
+
+    #[test]
+    fn tags_display_and_parse() {
+        let strong = ETag::for_body(b"hello");
+        assert!(!strong.is_weak());
+        assert_eq!(strong.to_string(), body_etag(b"hello"));
+        assert_eq!(ETag::parse(&strong.to_string()), Some(strong));
+
+        let modified = UNIX_EPOCH + Duration::new(0x65a1_0000, 5);
+        let weak = ETag::for_file(modified, 255);
+        assert_eq!(weak.to_string(), "W/\"65a10000.5-ff\"");
+        assert_eq!(ETag::parse(" W/\"65a10000.5-ff\" "), Some(weak));
+
+        for bad in ["abc", "\"abc", "W/abc", "\"a\"b\""] {
+            assert_eq!(ETag::parse(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn matches_if_none_match_weakly() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, "\"x\", W/\"abc\"".parse().unwrap());
+        assert!(ETag::strong("abc").matches(&headers));
+        assert!(ETag::weak("x").matches(&headers));
+        assert!(!ETag::strong("ab").matches(&headers));
+        assert!(!ETag::strong("abc").matches(&HeaderMap::new()));
+    }
+
+    fn app(layer: ETagLayer) -> Router {
+        Router::new()
+            .route(
+                "/doc",
+                get(|| async { ([(header::CACHE_CONTROL, "no-cache")], "document") })
+                    .post(|| async { "created" }),
+            )
+            .route(
+                "/versioned",
+                get(|| async { ([(header::ETAG, "\"v7\"")], "versioned") }),
+            )
+            .route(
+                "/stream",
+                get(|| async {
+                    let chunks = stream::iter([Ok::<_, std::io::Error>("chunk")]);
+                    Body::from_stream(chunks)
+                }),
+            )
+            .route(
+                "/missing",
+                get(|| async { (StatusCode::NOT_FOUND, "missing") }),
+            )
+            .layer(layer)
+    }
+
+    async fn send(app: &Router, method: Method, uri: &str, tag: Option<&str>) -> Response {
+        let mut req = Request::builder().method(method).uri(uri);
+        if let Some(tag) = tag {
+            req = req.header(header::IF_NONE_MATCH, tag);
+        }
+        app.clone()
+            .oneshot(req.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    async fn text(res: Response) -> String {
+        let bytes = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    #[tokio::test]
+    async fn tags_bodies_and_answers_revalidation_with_304() {
+        let app = app(ETagLayer::new());
+        let res = send(&app, Method::GET, "/doc", None).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let etag = res.headers()[header::ETAG].to_str().unwrap().to_string();
+        assert_eq!(etag, body_etag(b"document"));
+        assert_eq!(text(res).await, "document");
+
+        let res = send(&app, Method::GET, "/doc", Some(&etag)).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers()[header::ETAG], etag.as_str());
+        assert_eq!(res.headers()[header::CACHE_CONTROL], "no-cache");
+        assert!(!res.headers().contains_key(header::CONTENT_TYPE));
+        assert_eq!(text(res).await, "");
+
+        let res = send(&app, Method::GET, "/doc", Some("\"other\"")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = send(&app, Method::POST, "/doc", Some("*")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(header::ETAG));
+    }
+
+    #[tokio::test]
+    async fn existing_tags_are_compared_and_head_is_not_tagged() {
+        let app = app(ETagLayer::new());
+        let res = send(&app, Method::GET, "/versioned", Some("W/\"v7\"")).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        let res = send(&app, Method::HEAD, "/versioned", Some("\"v7\"")).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        let res = send(&app, Method::HEAD, "/doc", None).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(header::ETAG));
+    }
+
+    #[tokio::test]
+    async fn skips_errors_streams_and_large_bodies() {
+        let app = app(ETagLayer::new().max_body(4));
+        for uri in ["/doc", "/stream", "/missing"] {
+            let res = send(&app, Method::GET, uri, Some("*")).await;
+            assert_ne!(res.status(), StatusCode::NOT_MODIFIED, "{uri}");
+            assert!(!res.headers().contains_key(header::ETAG), "{uri}");
+        }
+        assert_eq!(
+            text(send(&app, Method::GET, "/stream", None).await).await,
+            "chunk"
+        );
+    }
+
+    #[tokio::test]
+    async fn weak_mode_marks_computed_tags() {
+        let app = app(ETagLayer::new().weak(true));
+        let res = send(&app, Method::GET, "/doc", None).await;
+        let etag = res.headers()[header::ETAG].to_str().unwrap().to_string();
+        assert_eq!(etag, format!("W/{}", body_etag(b"document")));
+        let res = send(&app, Method::GET, "/versioned", None).await;
+        assert_eq!(res.headers()[header::ETAG], "\"v7\"");
+    }
+}
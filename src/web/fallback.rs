@@ -1,4 +1,173 @@
-use axum::{http::StatusCode, response::IntoResponse};
+//! # Error Responses and Fallback
+//!
+//! Error responses negotiated on the request's `Accept` header:
+//!
+//! - clients preferring `text/html` (browsers) get an HTML error page in the
+//!   request's [`Locale`] — the crate's [built-in pages](crate::web::pages),
+//!   or an application Askama template registered with
+//!   [`ErrorPages::template`]
+//! - everyone else gets an `application/problem+json` [`Problem`]:
+//!
+//! ```json
+//! { "type": "about:blank", "title": "Not Found", "status": 404 }
+//! ```
+//!
+//! [`not_found`] is the router fallback built on this; other handlers call
+//! [`error_response`]. Templates are looked up from an
+//! `Extension<Arc<ErrorPages>>` (see
+//! [`RouterBuilder::error_pages`](crate::web::app::RouterBuilder::error_pages)).
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use askama::Template;
+//! use axum::{http::StatusCode, Extension, Router};
+//! use wzs_web::web::fallback::{not_found, ErrorPageContext, ErrorPages};
+//!
+//! #[derive(Template)]
+//! #[template(source = "<h1>{{ title }}</h1><a href=\"/\">Home</a>", ext = "html")]
+//! struct NotFound {
+//!     title: String,
+//! }
+//!
+//! impl From<ErrorPageContext> for NotFound {
+//!     fn from(ctx: ErrorPageContext) -> Self {
+//!         Self { title: ctx.title }
+//!     }
+//! }
+//!
+//! let pages = ErrorPages::new().template::<NotFound>(StatusCode::NOT_FOUND);
+//! let app: Router = Router::new()
+//!     .fallback(not_found)
+//!     .layer(Extension(Arc::new(pages)));
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use askama::Template;
+use axum::{
+    http::{header::ACCEPT, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension,
+};
+
+use crate::web::locale::Locale;
+use crate::web::pages::BuiltInPage;
+use crate::web::problem::Problem;
+use crate::web::template::render_template_with_status;
+
+/// Values available to error page templates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErrorPageContext {
+    pub status: u16,
+    /// Localized page title.
+    pub title: String,
+    /// Detail passed to [`error_response`], else the localized message.
+    pub message: String,
+    /// `<html lang>` value.
+    pub lang: String,
+    /// `<html dir>` value.
+    pub dir: &'static str,
+}
+
+type Renderer = Arc<dyn Fn(ErrorPageContext, StatusCode) -> Response + Send + Sync>;
+
+/// Application templates for HTML error pages, by status code.
+///
+/// Statuses without a template use the built-in pages:
+/// [`BuiltInPage::NotFound`] for `404`, [`BuiltInPage::Maintenance`] for
+/// `503` and [`BuiltInPage::Error`] otherwise.
+#[derive(Clone, Default)]
+pub struct ErrorPages {
+    templates: HashMap<u16, Renderer>,
+}
+
+impl fmt::Debug for ErrorPages {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut statuses: Vec<_> = self.templates.keys().collect();
+        statuses.sort();
+        f.debug_struct("ErrorPages")
+            .field("templates", &statuses)
+            .finish()
+    }
+}
+
+impl ErrorPages {
+    /// Built-in pages only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Renders `status` with the Askama template `T`.
+    pub fn template<T>(mut self, status: StatusCode) -> Self
+    where
+        T: Template + From<ErrorPageContext> + 'static,
+    {
+        self.templates.insert(
+            status.as_u16(),
+            Arc::new(|ctx, status| render_template_with_status(T::from(ctx), status)),
+        );
+        self
+    }
+
+    /// HTML page for `status`.
+    pub fn render(&self, locale: &Locale, status: StatusCode, message: Option<&str>) -> Response {
+        let page = match status {
+            StatusCode::NOT_FOUND => BuiltInPage::NotFound,
+            StatusCode::SERVICE_UNAVAILABLE => BuiltInPage::Maintenance,
+            _ => BuiltInPage::Error,
+        };
+        match self.templates.get(&status.as_u16()) {
+            Some(render) => {
+                let text = page.text(locale);
+                let ctx = ErrorPageContext {
+                    status: status.as_u16(),
+                    title: text.title.to_string(),
+                    message: message.unwrap_or(text.message).to_string(),
+                    lang: locale.lang().to_string(),
+                    dir: locale.dir(),
+                };
+                render(ctx, status)
+            }
+            None => page.render(locale, status),
+        }
+    }
+
+    /// HTML page or [`Problem`], depending on `headers`.
+    pub fn respond(
+        &self,
+        headers: &HeaderMap,
+        locale: &Locale,
+        status: StatusCode,
+        message: Option<&str>,
+    ) -> Response {
+        if prefers_html(headers) {
+            self.render(locale, status, message)
+        } else {
+            let problem = Problem::new(status);
+            match message {
+                Some(detail) => problem.detail(detail).into_response(),
+                None => problem.into_response(),
+            }
+        }
+    }
+}
+
+/// Negotiated error response using the installed [`ErrorPages`], if any.
+pub fn error_response(
+    pages: Option<&ErrorPages>,
+    headers: &HeaderMap,
+    locale: &Locale,
+    status: StatusCode,
+    message: Option<&str>,
+) -> Response {
+    match pages {
+        Some(pages) => pages.respond(headers, locale, status, message),
+        None => ErrorPages::new().respond(headers, locale, status, message),
+    }
+}
 
 /// Default 404 Not Found handler.
 ///
@@ -7,27 +176,160 @@ use axum::{http::StatusCode, response::IntoResponse};
 /// This handler is intended to be used as the final fallback
 /// in an Axum router.
 ///
-/// It returns a plain `404 Not Found` response without a body.
+/// It returns a localized HTML page to browsers and a [`Problem`] to API
+/// clients (see the [module docs](self)).
 ///
 /// # Design Notes
 ///
 /// - Application-agnostic
 /// - Suitable for APIs and SPAs
 /// - Can be replaced by application-specific handlers if needed
-pub async fn not_found() -> impl IntoResponse {
-    StatusCode::NOT_FOUND
+pub async fn not_found(
+    locale: Locale,
+    headers: HeaderMap,
+    pages: Option<Extension<Arc<ErrorPages>>>,
+) -> Response {
+    let pages = pages.as_ref().map(|Extension(p)| p.as_ref());
+    error_response(pages, &headers, &locale, StatusCode::NOT_FOUND, None)
+}
+
+/// Whether `text/html` is acceptable and weighted at least as high as
+/// `application/json`.
+fn prefers_html(headers: &HeaderMap) -> bool {
+    let Some(accept) = headers.get(ACCEPT).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let weight = |wanted: &[&str]| {
+        accept
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';').map(str::trim);
+                let media = parts.next()?;
+                let q = parts
+                    .find_map(|p| p.strip_prefix("q="))
+                    .map_or(1.0, |q| q.parse().unwrap_or(0.0));
+                wanted
+                    .iter()
+                    .any(|w| media.eq_ignore_ascii_case(w))
+                    .then_some(q)
+            })
+            .fold(0.0_f32, f32::max)
+    };
+    let html = weight(&["text/html", "application/xhtml+xml"]);
+    html > 0.0 && html >= weight(&["application/json"])
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use axum::http::StatusCode;
+    use axum::http::{header::CONTENT_TYPE, HeaderValue};
     use axum::response::IntoResponse;
+    use http_body_util::BodyExt;
+
+    fn accept(value: &str) -> HeaderMap {
+        let mut h = HeaderMap::new();
+        h.insert(ACCEPT, HeaderValue::from_str(value).unwrap());
+        h
+    }
+
+    async fn body(res: Response) -> String {
+        let bytes = res.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
 
     #[tokio::test]
     async fn returns_404_not_found() {
-        let response = not_found().await.into_response();
+        let response = not_found(Locale("en".into()), HeaderMap::new(), None)
+            .await
+            .into_response();
 
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
+
+    #[tokio::test]
+    async fn api_clients_get_a_problem() {
+        let res = not_found(Locale("en".into()), accept("application/json"), None).await;
+        assert_eq!(res.headers()[CONTENT_TYPE], "application/problem+json");
+        let json: serde_json::Value = serde_json::from_str(&body(res).await).unwrap();
+        assert_eq!(json["status"], 404);
+        assert_eq!(json["title"], "Not Found");
+        assert!(json.get("detail").is_none());
+
+        let res = error_response(
+            None,
+            &HeaderMap::new(),
+            &Locale("en".into()),
+            StatusCode::SERVICE_UNAVAILABLE,
+            Some("back at 10:00"),
+        );
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let json: serde_json::Value = serde_json::from_str(&body(res).await).unwrap();
+        assert_eq!(json["detail"], "back at 10:00");
+    }
+
+    #[tokio::test]
+    async fn browsers_get_the_built_in_page() {
+        let headers = accept("text/html,application/xhtml+xml,*/*;q=0.8");
+        let res = not_found(Locale("ja".into()), headers.clone(), None).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert!(body(res).await.contains("ページが見つかりません"));
+
+        let res = error_response(
+            None,
+            &headers,
+            &Locale("en".into()),
+            StatusCode::SERVICE_UNAVAILABLE,
+            None,
+        );
+        assert!(body(res).await.contains("Down for maintenance"));
+    }
+
+    #[derive(Template)]
+    #[template(
+        source = "<p lang=\"{{ lang }}\">{{ status }}: {{ message }}</p>",
+        ext = "html"
+    )]
+    struct Custom {
+        status: u16,
+        message: String,
+        lang: String,
+    }
+
+    impl From<ErrorPageContext> for Custom {
+        fn from(ctx: ErrorPageContext) -> Self {
+            Self {
+                status: ctx.status,
+                message: ctx.message,
+                lang: ctx.lang,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_templates_replace_built_in_pages() {
+        let pages =
+            Arc::new(ErrorPages::new().template::<Custom>(StatusCode::INTERNAL_SERVER_ERROR));
+        let html = accept("text/html");
+        let res = error_response(
+            Some(&pages),
+            &html,
+            &Locale("de".into()),
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Some("database offline"),
+        );
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body(res).await, r#"<p lang="de">500: database offline</p>"#);
+
+        let res = not_found(Locale("de".into()), html, Some(Extension(pages))).await;
+        assert!(body(res).await.contains("Seite nicht gefunden"));
+    }
+
+    #[test]
+    fn negotiates_on_accept_weights() {
+        assert!(prefers_html(&accept("text/html")));
+        assert!(!prefers_html(&accept("application/json, text/html;q=0.5")));
+        assert!(!prefers_html(&accept("*/*")));
+        assert!(!prefers_html(&HeaderMap::new()));
+        assert!(!prefers_html(&accept("text/html;q=0")));
+    }
 }
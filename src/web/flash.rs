@@ -0,0 +1,380 @@
+//! # Flash Messages
+//!
+//! One-time messages for POST-redirect-GET flows, carried in a signed
+//! `flash` cookie (see [`SecureCookies`]):
+//!
+//! - [`Flash`] — queues messages in a handler; as a response part it sets
+//!   the cookie (replacing any unread messages)
+//! - [`IncomingFlashes`] — reads the pending messages; as a response part it
+//!   clears the cookie
+//! - [`load_flashes`] — middleware exposing the pending messages to
+//!   [`TemplateResponder`](crate::web::template::context::TemplateResponder)
+//!   (`ctx.flashes`) and clearing the cookie once a page was shown
+//!
+//! All three read the [`SecureCookies`] from an `Extension<SecureCookies>`.
+//! The cookie is only signed, so messages must not contain secrets; keep
+//! them short, browsers cap cookies at about 4 KB.
+//!
+//! # Example
+//! ```rust,no_run
+//! use axum::{
+//!     middleware::from_fn,
+//!     response::{Html, Redirect},
+//!     routing::get,
+//!     Extension, Router,
+//! };
+//! use wzs_web::config::csrf::derive_secret_from_string;
+//! use wzs_web::web::flash::{load_flashes, Flash, IncomingFlashes};
+//! use wzs_web::web::secure_cookie::SecureCookies;
+//!
+//! async fn save(flash: Flash) -> (Flash, Redirect) {
+//!     (flash.success("Saved"), Redirect::to("/posts"))
+//! }
+//!
+//! async fn list(flashes: IncomingFlashes) -> (IncomingFlashes, Html<String>) {
+//!     let banners: String = flashes
+//!         .iter()
+//!         .map(|f| format!("<p class=\"{}\">{}</p>", f.level, f.message))
+//!         .collect();
+//!     (flashes, Html(banners))
+//! }
+//!
+//! let cookies = SecureCookies::new("k1", derive_secret_from_string("secret"));
+//! let app: Router = Router::new()
+//!     .route("/posts", get(list).post(save))
+//!     .layer(from_fn(load_flashes))
+//!     .layer(Extension(cookies));
+//! ```
+
+use std::convert::Infallible;
+
+use anyhow::anyhow;
+use axum::{
+    extract::{FromRequestParts, Request},
+    http::{header::SET_COOKIE, request::Parts, HeaderValue},
+    middleware::Next,
+    response::{IntoResponseParts, Response, ResponseParts},
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar};
+
+use crate::error::app::AppError;
+use crate::web::secure_cookie::SecureCookies;
+pub use crate::web::template::context::FlashMessage;
+
+/// Name of the flash cookie.
+pub const FLASH_COOKIE: &str = "flash";
+
+/// Messages to show on the next page; see the [module docs](self).
+#[derive(Clone)]
+pub struct Flash {
+    cookies: SecureCookies,
+    messages: Vec<FlashMessage>,
+}
+
+impl Flash {
+    /// Empty set of messages signed with `cookies`.
+    pub fn new(cookies: SecureCookies) -> Self {
+        Self {
+            cookies,
+            messages: Vec::new(),
+        }
+    }
+
+    /// Queues a message with a custom level.
+    pub fn push(mut self, level: impl Into<String>, message: impl Into<String>) -> Self {
+        self.messages.push(FlashMessage::new(level, message));
+        self
+    }
+
+    pub fn success(self, message: impl Into<String>) -> Self {
+        self.push("success", message)
+    }
+
+    pub fn info(self, message: impl Into<String>) -> Self {
+        self.push("info", message)
+    }
+
+    pub fn warning(self, message: impl Into<String>) -> Self {
+        self.push("warning", message)
+    }
+
+    pub fn error(self, message: impl Into<String>) -> Self {
+        self.push("error", message)
+    }
+
+    pub fn messages(&self) -> &[FlashMessage] {
+        &self.messages
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Flash {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        secure_cookies(parts).map(Self::new)
+    }
+}
+
+impl IntoResponseParts for Flash {
+    type Error = Infallible;
+
+    fn into_response_parts(self, res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        if self.messages.is_empty() {
+            return Ok(res);
+        }
+        let value = serde_json::to_string(&self.messages).unwrap_or_default();
+        let jar = CookieJar::new().add(self.cookies.signed_cookie(FLASH_COOKIE, &value));
+        jar.into_response_parts(res)
+    }
+}
+
+/// Messages pending for this request; see the [module docs](self).
+///
+/// A missing, forged or malformed cookie yields no messages.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IncomingFlashes {
+    messages: Vec<FlashMessage>,
+    /// Whether the request carried a flash cookie to clear.
+    present: bool,
+}
+
+impl IncomingFlashes {
+    /// Reads the flash cookie of `jar`.
+    pub fn from_jar(jar: &CookieJar, cookies: &SecureCookies) -> Self {
+        Self {
+            messages: read(jar, cookies),
+            present: jar.get(FLASH_COOKIE).is_some(),
+        }
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, FlashMessage> {
+        self.messages.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub fn into_messages(self) -> Vec<FlashMessage> {
+        self.messages
+    }
+}
+
+impl<'a> IntoIterator for &'a IncomingFlashes {
+    type Item = &'a FlashMessage;
+    type IntoIter = std::slice::Iter<'a, FlashMessage>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for IncomingFlashes {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let cookies = secure_cookies(parts)?;
+        Ok(Self::from_jar(
+            &CookieJar::from_headers(&parts.headers),
+            &cookies,
+        ))
+    }
+}
+
+impl IntoResponseParts for IncomingFlashes {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        if self.present {
+            res.headers_mut().append(SET_COOKIE, removal());
+        }
+        Ok(res)
+    }
+}
+
+/// Middleware inserting the pending messages as a `Vec<FlashMessage>`
+/// request extension.
+///
+/// The cookie is cleared on the response unless it is a redirect (the
+/// messages are kept for the page redirected to) or the handler set new
+/// messages. Mount it on page routes only, so asset and API requests don't
+/// consume the messages. Without an `Extension<SecureCookies>` it does
+/// nothing.
+pub async fn load_flashes(mut req: Request, next: Next) -> Response {
+    let Some(cookies) = req.extensions().get::<SecureCookies>() else {
+        return next.run(req).await;
+    };
+    let incoming = IncomingFlashes::from_jar(&CookieJar::from_headers(req.headers()), cookies);
+    let present = incoming.present;
+    if !incoming.is_empty() {
+        req.extensions_mut().insert(incoming.into_messages());
+    }
+
+    let mut res = next.run(req).await;
+    if present && !res.status().is_redirection() && !sets_flash(&res) {
+        res.headers_mut().append(SET_COOKIE, removal());
+    }
+    res
+}
+
+fn secure_cookies(parts: &Parts) -> Result<SecureCookies, AppError> {
+    parts
+        .extensions
+        .get::<SecureCookies>()
+        .cloned()
+        .ok_or_else(|| AppError::Internal(anyhow!("missing Extension<SecureCookies>")))
+}
+
+fn read(jar: &CookieJar, cookies: &SecureCookies) -> Vec<FlashMessage> {
+    cookies
+        .get_signed(jar, FLASH_COOKIE)
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+fn sets_flash(res: &Response) -> bool {
+    let prefix = format!("{FLASH_COOKIE}=");
+    res.headers()
+        .get_all(SET_COOKIE)
+        .iter()
+        .any(|v| v.as_bytes().starts_with(prefix.as_bytes()))
+}
+
+fn removal() -> HeaderValue {
+    let mut cookie = Cookie::build((FLASH_COOKIE, "")).path("/").build();
+    cookie.make_removal();
+    HeaderValue::from_str(&cookie.to_string()).expect("valid cookie header")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use askama::Template;
+    use axum::{
+        body::Body,
+        http::{header::COOKIE, StatusCode},
+        middleware::from_fn,
+        response::Redirect,
+        routing::get,
+        Extension, Router,
+    };
+    use tower::ServiceExt;
+
+    use crate::web::template::context::{TemplateContext, TemplateResponder};
+
+    #[derive(Template)]
+    #[template(
+        source = "{% for f in ctx.flashes %}{{ f.level }}:{{ f.message }};{% endfor %}",
+        ext = "html"
+    )]
+    struct Page<'a> {
+        ctx: &'a TemplateContext,
+    }
+
+    fn cookies() -> SecureCookies {
+        SecureCookies::new("k1", [9u8; 32])
+    }
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/posts",
+                get(|page: TemplateResponder| async move { page.render(Page { ctx: page.ctx() }) })
+                    .post(|flash: Flash| async move {
+                        (flash.success("Saved").error("<b>"), Redirect::to("/posts"))
+                    }),
+            )
+            .route(
+                "/raw",
+                get(|flashes: IncomingFlashes| async move {
+                    let text: Vec<_> = flashes.iter().map(|f| f.message.clone()).collect();
+                    (flashes, text.join(","))
+                }),
+            )
+            .route("/login", get(|| async { Redirect::to("/posts") }))
+            .layer(from_fn(load_flashes))
+            .layer(Extension(cookies()))
+    }
+
+    async fn send(method: &str, uri: &str, cookie: Option<&str>) -> (Response, String) {
+        let mut req = Request::builder().method(method).uri(uri);
+        if let Some(cookie) = cookie {
+            req = req.header(COOKIE, cookie);
+        }
+        let res = app()
+            .oneshot(req.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let (parts, body) = res.into_parts();
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        (
+            Response::from_parts(parts, Body::empty()),
+            String::from_utf8(bytes.to_vec()).unwrap(),
+        )
+    }
+
+    fn set_cookie(res: &Response) -> Option<String> {
+        let value = res.headers().get(SET_COOKIE)?.to_str().unwrap();
+        Some(value.split(';').next().unwrap().to_string())
+    }
+
+    #[tokio::test]
+    async fn post_redirect_get_shows_messages_once() {
+        let (res, _) = send("POST", "/posts", None).await;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        let cookie = set_cookie(&res).unwrap();
+        assert!(cookie.starts_with("flash="));
+
+        // A redirect keeps the messages for the next page.
+        let (res, _) = send("GET", "/login", Some(&cookie)).await;
+        assert!(set_cookie(&res).is_none());
+
+        let (res, body) = send("GET", "/posts", Some(&cookie)).await;
+        assert_eq!(body, "success:Saved;error:&#60;b&#62;;");
+        assert_eq!(set_cookie(&res).unwrap(), "flash=");
+
+        let (res, body) = send("GET", "/posts", None).await;
+        assert_eq!(body, "");
+        assert!(set_cookie(&res).is_none());
+    }
+
+    #[tokio::test]
+    async fn incoming_flashes_read_and_clear() {
+        let (res, _) = send("POST", "/posts", None).await;
+        let cookie = set_cookie(&res).unwrap();
+
+        let (res, body) = send("GET", "/raw", Some(&cookie)).await;
+        assert_eq!(body, "Saved,<b>");
+        let cleared: Vec<_> = res.headers().get_all(SET_COOKIE).iter().collect();
+        assert_eq!(cleared.len(), 1);
+        assert!(cleared[0].to_str().unwrap().starts_with("flash=;"));
+    }
+
+    #[tokio::test]
+    async fn forged_cookie_yields_no_messages() {
+        let forged = format!(
+            "flash={}",
+            SecureCookies::new("k1", [1u8; 32])
+                .sign(FLASH_COOKIE, r#"[{"level":"info","message":"x"}]"#)
+        );
+        let (res, body) = send("GET", "/raw", Some(&forged)).await;
+        assert_eq!(body, "");
+        assert_eq!(set_cookie(&res).unwrap(), "flash=");
+    }
+
+    #[tokio::test]
+    async fn extractors_require_secure_cookies() {
+        let app = Router::new().route("/", get(|_: Flash| async { "" }));
+        let res = app
+            .oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(Flash::new(cookies()).messages().is_empty());
+    }
+}
@@ -0,0 +1,394 @@
+//! # Anti-Automation Form Helpers
+//!
+//! Cheap spam protection for public forms (contact, sign-up) without a CAPTCHA:
+//!
+//! - **Honeypot field** — a visually hidden input that humans leave empty
+//!   but naive bots fill in.
+//! - **Form token** — an HMAC-signed timestamp bound to a form id. Submissions
+//!   faster than a minimum fill time, older than a maximum age, or for a
+//!   different form are rejected.
+//!
+//! Token format (URL-safe base64 without padding for the MAC):
+//!
+//! ```text
+//! v1.<issued_at_unix_secs>.<mac_b64>
+//! ```
+//!
+//! # Example
+//! ```rust
+//! use std::collections::HashMap;
+//! use std::time::Duration;
+//! use wzs_web::config::csrf::derive_secret_from_string;
+//! use wzs_web::web::forms::{FormGuard, FormRejection, FORM_TOKEN_FIELD};
+//!
+//! let guard = FormGuard::new(derive_secret_from_string("form-secret"))
+//!     .min_fill_time(Duration::from_secs(3));
+//!
+//! // When rendering the form:
+//! let token = guard.issue_at("contact", 1_000);
+//!
+//! // When handling the submission:
+//! let mut fields = HashMap::new();
+//! fields.insert(FORM_TOKEN_FIELD.to_string(), token);
+//! fields.insert("website".to_string(), String::new()); // honeypot left empty
+//!
+//! assert_eq!(guard.validate_at("contact", &fields, 1_001), Err(FormRejection::TooFast));
+//! assert_eq!(guard.validate_at("contact", &fields, 1_010), Ok(()));
+//! ```
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+
+/// Default name of the form token field.
+pub const FORM_TOKEN_FIELD: &str = "_form_token";
+
+/// Default name of the honeypot field.
+pub const HONEYPOT_FIELD: &str = "website";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Reason a submission was rejected.
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum FormRejection {
+    #[error("honeypot field was filled")]
+    HoneypotFilled,
+    #[error("form token is missing")]
+    MissingToken,
+    #[error("form token is invalid")]
+    InvalidToken,
+    #[error("form was submitted too quickly")]
+    TooFast,
+    #[error("form token has expired")]
+    Expired,
+}
+
+/// Issues and validates form tokens and honeypot fields.
+#[derive(Clone)]
+pub struct FormGuard {
+    secret: [u8; 32],
+    min_fill_time: Duration,
+    max_age: Duration,
+    honeypot_field: String,
+    token_field: String,
+}
+
+impl FormGuard {
+    /// Creates a guard with defaults: 3 s minimum fill time, 24 h maximum age,
+    /// [`HONEYPOT_FIELD`] and [`FORM_TOKEN_FIELD`].
+    pub fn new(secret: [u8; 32]) -> Self {
+        Self {
+            secret,
+            min_fill_time: Duration::from_secs(3),
+            max_age: Duration::from_secs(24 * 60 * 60),
+            honeypot_field: HONEYPOT_FIELD.into(),
+            token_field: FORM_TOKEN_FIELD.into(),
+        }
+    }
+
+    /// Sets the minimum time between rendering and submitting.
+    pub fn min_fill_time(mut self, d: Duration) -> Self {
+        self.min_fill_time = d;
+        self
+    }
+
+    /// Sets the maximum token age.
+    pub fn max_age(mut self, d: Duration) -> Self {
+        self.max_age = d;
+        self
+    }
+
+    /// Sets the honeypot field name.
+    pub fn honeypot_field(mut self, name: impl Into<String>) -> Self {
+        self.honeypot_field = name.into();
+        self
+    }
+
+    /// Sets the token field name.
+    pub fn token_field(mut self, name: impl Into<String>) -> Self {
+        self.token_field = name.into();
+        self
+    }
+
+    /// `true` if `name` is the honeypot or token field, which are not
+    /// part of the submitted data.
+    pub fn is_reserved_field(&self, name: &str) -> bool {
+        name == self.honeypot_field || name == self.token_field
+    }
+
+    /// Issues a token for `form_id` using the current time.
+    pub fn issue(&self, form_id: &str) -> String {
+        self.issue_at(form_id, unix_now())
+    }
+
+    /// Issues a token for `form_id` at `issued_at` (Unix seconds).
+    pub fn issue_at(&self, form_id: &str, issued_at: u64) -> String {
+        let mac = self.mac(form_id, issued_at);
+        format!("v1.{issued_at}.{}", URL_SAFE_NO_PAD.encode(mac))
+    }
+
+    /// Validates a submission using the current time.
+    pub fn validate(
+        &self,
+        form_id: &str,
+        fields: &HashMap<String, String>,
+    ) -> Result<(), FormRejection> {
+        self.validate_at(form_id, fields, unix_now())
+    }
+
+    /// Validates a submission at `now` (Unix seconds).
+    ///
+    /// Checks, in order: honeypot, token presence, signature, fill time, age.
+    pub fn validate_at(
+        &self,
+        form_id: &str,
+        fields: &HashMap<String, String>,
+        now: u64,
+    ) -> Result<(), FormRejection> {
+        if fields
+            .get(&self.honeypot_field)
+            .is_some_and(|v| !v.trim().is_empty())
+        {
+            return Err(FormRejection::HoneypotFilled);
+        }
+
+        let token = fields
+            .get(&self.token_field)
+            .filter(|t| !t.is_empty())
+            .ok_or(FormRejection::MissingToken)?;
+        let issued_at = self
+            .verify(form_id, token)
+            .ok_or(FormRejection::InvalidToken)?;
+
+        let elapsed = now.saturating_sub(issued_at);
+        if issued_at > now || elapsed < self.min_fill_time.as_secs() {
+            return Err(FormRejection::TooFast);
+        }
+        if elapsed > self.max_age.as_secs() {
+            return Err(FormRejection::Expired);
+        }
+        Ok(())
+    }
+
+    /// Hidden inputs to embed in the form: the token and the honeypot.
+    ///
+    /// The honeypot is moved off-screen rather than `type="hidden"`, since
+    /// bots skip hidden inputs.
+    pub fn hidden_fields_html(&self, form_id: &str) -> String {
+        format!(
+            concat!(
+                r#"<input type="hidden" name="{tf}" value="{token}">"#,
+                r#"<div style="position:absolute;left:-10000px;" aria-hidden="true">"#,
+                r#"<input type="text" name="{hp}" tabindex="-1" autocomplete="off" value="">"#,
+                "</div>"
+            ),
+            tf = html_attr(&self.token_field),
+            token = self.issue(form_id),
+            hp = html_attr(&self.honeypot_field),
+        )
+    }
+
+    /// Returns the issue time if the token is well-formed and signed for `form_id`.
+    fn verify(&self, form_id: &str, token: &str) -> Option<u64> {
+        let mut parts = token.split('.');
+        let (Some("v1"), Some(ts), Some(mac_b64), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return None;
+        };
+
+        let issued_at: u64 = ts.parse().ok()?;
+        let mac = URL_SAFE_NO_PAD.decode(mac_b64).ok()?;
+        let expected = self.mac(form_id, issued_at);
+
+        (expected.as_slice().ct_eq(&mac).unwrap_u8() == 1).then_some(issued_at)
+    }
+
+    fn mac(&self, form_id: &str, issued_at: u64) -> Vec<u8> {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC key");
+        mac.update(form_id.as_bytes());
+        mac.update(b"\n");
+        mac.update(issued_at.to_string().as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+/// Returns `true` if `s` looks like an email address (`local@domain.tld`).
+///
+/// Intentionally permissive; use a confirmation email for real verification.
+pub fn is_plausible_email(s: &str) -> bool {
+    let s = s.trim();
+    let Some((local, domain)) = s.rsplit_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && s.len() <= 254
+        && !s.chars().any(|c| c.is_whitespace() || c.is_control())
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+}
+
+/// Counts `http://` / `https://` links, a common spam signal in free text.
+pub fn count_links(s: &str) -> usize {
+    let lower = s.to_ascii_lowercase();
+    lower.matches("http://").count() + lower.matches("https://").count()
+}
+
+/// Returns `true` if `s` is non-empty after trimming and at most `max_chars` long.
+pub fn is_within_length(s: &str, max_chars: usize) -> bool {
+    let t = s.trim();
+    !t.is_empty() && t.chars().count() <= max_chars
+}
+
+fn html_attr(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard() -> FormGuard {
+        FormGuard::new([9u8; 32])
+    }
+
+    fn fields(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn accepts_token_after_min_fill_time() {
+        let g = guard();
+        let token = g.issue_at("contact", 100);
+        let f = fields(&[(FORM_TOKEN_FIELD, &token), (HONEYPOT_FIELD, "")]);
+
+        assert_eq!(g.validate_at("contact", &f, 103), Ok(()));
+    }
+
+    #[test]
+    fn rejects_filled_honeypot_first() {
+        let g = guard();
+        let f = fields(&[(HONEYPOT_FIELD, "http://spam")]);
+        assert_eq!(
+            g.validate_at("contact", &f, 0),
+            Err(FormRejection::HoneypotFilled)
+        );
+    }
+
+    #[test]
+    fn rejects_missing_or_forged_tokens() {
+        let g = guard();
+        assert_eq!(
+            g.validate_at("contact", &fields(&[]), 0),
+            Err(FormRejection::MissingToken)
+        );
+
+        let token = g.issue_at("contact", 100);
+        let forged = token.replacen("v1.100.", "v1.50.", 1);
+        for bad in [forged.as_str(), "v1.100", "garbage", "v2.100.abc"] {
+            assert_eq!(
+                g.validate_at("contact", &fields(&[(FORM_TOKEN_FIELD, bad)]), 200),
+                Err(FormRejection::InvalidToken),
+                "{bad}"
+            );
+        }
+
+        let other = FormGuard::new([1u8; 32]).issue_at("contact", 100);
+        assert_eq!(
+            g.validate_at("contact", &fields(&[(FORM_TOKEN_FIELD, &other)]), 200),
+            Err(FormRejection::InvalidToken)
+        );
+    }
+
+    #[test]
+    fn tokens_are_bound_to_form_id() {
+        let g = guard();
+        let token = g.issue_at("signup", 100);
+        assert_eq!(
+            g.validate_at("contact", &fields(&[(FORM_TOKEN_FIELD, &token)]), 200),
+            Err(FormRejection::InvalidToken)
+        );
+    }
+
+    #[test]
+    fn enforces_fill_time_and_max_age() {
+        let g = guard()
+            .min_fill_time(Duration::from_secs(5))
+            .max_age(Duration::from_secs(60));
+        let token = g.issue_at("contact", 100);
+        let f = fields(&[(FORM_TOKEN_FIELD, &token)]);
+
+        assert_eq!(
+            g.validate_at("contact", &f, 90),
+            Err(FormRejection::TooFast)
+        );
+        assert_eq!(
+            g.validate_at("contact", &f, 104),
+            Err(FormRejection::TooFast)
+        );
+        assert_eq!(g.validate_at("contact", &f, 105), Ok(()));
+        assert_eq!(g.validate_at("contact", &f, 160), Ok(()));
+        assert_eq!(
+            g.validate_at("contact", &f, 161),
+            Err(FormRejection::Expired)
+        );
+    }
+
+    #[test]
+    fn custom_field_names_are_used() {
+        let g = guard().honeypot_field("nickname").token_field("t");
+        let token = g.issue_at("c", 0);
+
+        assert_eq!(
+            g.validate_at("c", &fields(&[("t", &token), ("nickname", "bot")]), 10),
+            Err(FormRejection::HoneypotFilled)
+        );
+        assert_eq!(
+            g.validate_at("c", &fields(&[("t", &token), (HONEYPOT_FIELD, "x")]), 10),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn hidden_fields_html_contains_token_and_honeypot() {
+        let html = guard().hidden_fields_html("contact");
+        assert!(html.contains(r#"name="_form_token" value="v1."#));
+        assert!(html.contains(r#"name="website" tabindex="-1""#));
+        assert!(html.contains(r#"aria-hidden="true""#));
+    }
+
+    #[test]
+    fn validation_utilities() {
+        assert!(is_plausible_email("a.b+c@example.co.jp"));
+        assert!(!is_plausible_email("no-at.example.com"));
+        assert!(!is_plausible_email("a@localhost"));
+        assert!(!is_plausible_email("a b@example.com"));
+        assert!(!is_plausible_email("@example.com"));
+
+        assert_eq!(count_links("see HTTPS://a.com and http://b.com"), 2);
+        assert_eq!(count_links("no links"), 0);
+
+        assert!(is_within_length(" hello ", 5));
+        assert!(!is_within_length("   ", 5));
+        assert!(!is_within_length("こんにちは!", 5));
+    }
+}
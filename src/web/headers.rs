@@ -0,0 +1,354 @@
+//! # Response Header Rules
+//!
+//! Declares the headers each path prefix gets, applied by the single
+//! [`apply_header_rules`] middleware instead of header edits in every
+//! handler.
+//!
+//! A [`HeaderRules`] value holds [`HeaderRule`]s mapping a path prefix to a
+//! [`HeaderSet`]. Every rule whose prefix matches applies, in declaration
+//! order, so a narrower rule declared later overrides a group rule. Prefixes
+//! match whole segments, as in [route policies](crate::web::policy).
+//!
+//! Each header in a set is either set (replacing what the handler sent),
+//! defaulted (only when the handler sent none) or removed:
+//!
+//! | Preset | Headers |
+//! |--------|---------|
+//! | [`HeaderSet::no_store`] | `Cache-Control: no-store, no-cache, must-revalidate` (set) |
+//! | [`HeaderSet::public_cache`] | `Cache-Control: public, max-age=<n>` (default) |
+//! | [`HeaderSet::frame_ancestors`] | `Content-Security-Policy: frame-ancestors …` (set), `X-Frame-Options` removed |
+//!
+//! [`HeaderRules::from_config`] builds the usual table for `/static`, `/api`
+//! and `/embed` from [`HeaderPolicyConfig`].
+//!
+//! # Example
+//! ```rust,no_run
+//! use axum::{middleware::from_fn_with_state, routing::get, Router};
+//! use wzs_web::config::headers::HeaderPolicyConfig;
+//! use wzs_web::web::headers::{apply_header_rules, HeaderRules, HeaderSet};
+//!
+//! let rules = HeaderRules::from_config(&HeaderPolicyConfig::from_env())
+//!     .rule("/api/public", HeaderSet::public_cache(60));
+//!
+//! let app: Router = Router::new()
+//!     .route("/api/me", get(|| async { "me" }))
+//!     .route("/api/public/news", get(|| async { "news" }))
+//!     .layer(from_fn_with_state(rules, apply_header_rules));
+//! ```
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_SECURITY_POLICY, X_FRAME_OPTIONS},
+        HeaderMap, HeaderName, HeaderValue,
+    },
+    middleware::Next,
+    response::Response,
+};
+
+use crate::config::headers::HeaderPolicyConfig;
+use crate::web::policy::prefix_matches;
+
+/// What a [`HeaderSet`] does with one header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HeaderAction {
+    /// Replaces any value sent by the handler.
+    Set(HeaderValue),
+    /// Adds the value only when the handler sent none.
+    Default(HeaderValue),
+    /// Removes the header.
+    Remove,
+}
+
+/// Headers applied together.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HeaderSet {
+    entries: Vec<(HeaderName, HeaderAction)>,
+}
+
+impl HeaderSet {
+    /// An empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets `name`, replacing the handler's value.
+    pub fn set(self, name: HeaderName, value: HeaderValue) -> Self {
+        self.with(name, HeaderAction::Set(value))
+    }
+
+    /// Sets `name` unless the handler already did.
+    pub fn default_value(self, name: HeaderName, value: HeaderValue) -> Self {
+        self.with(name, HeaderAction::Default(value))
+    }
+
+    /// Removes `name`.
+    pub fn remove(self, name: HeaderName) -> Self {
+        self.with(name, HeaderAction::Remove)
+    }
+
+    fn with(mut self, name: HeaderName, action: HeaderAction) -> Self {
+        self.entries.push((name, action));
+        self
+    }
+
+    /// Responses that must never be stored (APIs, tokens, personal data).
+    pub fn no_store() -> Self {
+        Self::new().set(
+            CACHE_CONTROL,
+            HeaderValue::from_static("no-store, no-cache, must-revalidate"),
+        )
+    }
+
+    /// Public caching for `max_age` seconds, unless the handler chose a
+    /// policy itself (e.g. `immutable` for fingerprinted assets).
+    pub fn public_cache(max_age: u64) -> Self {
+        let value = HeaderValue::from_str(&format!("public, max-age={max_age}"))
+            .expect("cache-control value is ASCII");
+        Self::new().default_value(CACHE_CONTROL, value)
+    }
+
+    /// Allows framing by `origins` (by the same origin when empty).
+    ///
+    /// `X-Frame-Options` is removed since it cannot express an allow-list
+    /// and would override the CSP in older browsers. Origins that are not
+    /// valid header text are skipped.
+    pub fn frame_ancestors<I, S>(origins: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut sources = vec!["'self'".to_string()];
+        sources.extend(
+            origins
+                .into_iter()
+                .map(|o| o.as_ref().trim().to_string())
+                .filter(|o| !o.is_empty() && !o.contains([';', ',', ' '])),
+        );
+        let csp = format!("frame-ancestors {}", sources.join(" "));
+        let value = HeaderValue::from_str(&csp)
+            .unwrap_or(HeaderValue::from_static("frame-ancestors 'self'"));
+        Self::new()
+            .set(CONTENT_SECURITY_POLICY, value)
+            .remove(X_FRAME_OPTIONS)
+    }
+
+    /// The headers of this set, in order.
+    pub fn entries(&self) -> &[(HeaderName, HeaderAction)] {
+        &self.entries
+    }
+
+    /// Applies the set to `headers`.
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        for (name, action) in &self.entries {
+            match action {
+                HeaderAction::Set(value) => {
+                    headers.insert(name, value.clone());
+                }
+                HeaderAction::Default(value) => {
+                    if !headers.contains_key(name) {
+                        headers.insert(name, value.clone());
+                    }
+                }
+                HeaderAction::Remove => {
+                    headers.remove(name);
+                }
+            }
+        }
+    }
+}
+
+/// Headers applied under a path prefix.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeaderRule {
+    pub prefix: String,
+    pub headers: HeaderSet,
+}
+
+impl HeaderRule {
+    /// Whether the rule covers `path` (whole-segment prefix match).
+    pub fn matches(&self, path: &str) -> bool {
+        prefix_matches(&self.prefix, path)
+    }
+}
+
+/// The header table; state of [`apply_header_rules`].
+///
+/// Cloning is cheap.
+#[derive(Clone, Debug, Default)]
+pub struct HeaderRules {
+    rules: Arc<Vec<HeaderRule>>,
+}
+
+impl HeaderRules {
+    /// An empty table (responses pass unchanged).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `/static` caching, `/api` no-store and `/embed` framing from `cfg`;
+    /// rules with an empty prefix are left out.
+    pub fn from_config(cfg: &HeaderPolicyConfig) -> Self {
+        let rules = [
+            (
+                &cfg.static_prefix,
+                HeaderSet::public_cache(cfg.static_max_age),
+            ),
+            (&cfg.api_prefix, HeaderSet::no_store()),
+            (
+                &cfg.embed_prefix,
+                HeaderSet::frame_ancestors(&cfg.frame_ancestors),
+            ),
+        ];
+        rules
+            .into_iter()
+            .filter(|(prefix, _)| !prefix.is_empty())
+            .fold(Self::new(), |rules, (prefix, set)| {
+                rules.rule(prefix.as_str(), set)
+            })
+    }
+
+    /// Applies `headers` to responses under `prefix`.
+    pub fn rule(mut self, prefix: impl Into<String>, headers: HeaderSet) -> Self {
+        Arc::make_mut(&mut self.rules).push(HeaderRule {
+            prefix: prefix.into(),
+            headers,
+        });
+        self
+    }
+
+    /// All declared rules, in declaration order.
+    pub fn rules(&self) -> &[HeaderRule] {
+        &self.rules
+    }
+
+    /// Applies every rule matching `path` to `headers`.
+    pub fn apply(&self, path: &str, headers: &mut HeaderMap) {
+        for rule in self.rules.iter().filter(|r| r.matches(path)) {
+            rule.headers.apply(headers);
+        }
+    }
+}
+
+/// Middleware applying [`HeaderRules`] to every response:
+/// `from_fn_with_state(rules, apply_header_rules)`.
+///
+/// Rules match the request path as seen by the layer, so mount it on the
+/// outer router rather than inside a `nest`.
+pub async fn apply_header_rules(
+    State(rules): State<HeaderRules>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let path = req.uri().path().to_string();
+    let mut res = next.run(req).await;
+    rules.apply(&path, res.headers_mut());
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{
+        body::Body, http::header::ETAG, middleware::from_fn_with_state, routing::get, Router,
+    };
+    use tower::ServiceExt;
+
+    fn rules() -> HeaderRules {
+        HeaderRules::from_config(&HeaderPolicyConfig {
+            frame_ancestors: vec!["https://portal.example".into()],
+            ..HeaderPolicyConfig::default()
+        })
+        .rule("/api/public", HeaderSet::public_cache(60).remove(ETAG))
+    }
+
+    fn headers(pairs: &[(HeaderName, &'static str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.clone(), HeaderValue::from_static(v)))
+            .collect()
+    }
+
+    #[test]
+    fn sets_defaults_and_removes() {
+        let r = rules();
+
+        let mut h = headers(&[(CACHE_CONTROL, "max-age=60")]);
+        r.apply("/api/users", &mut h);
+        assert_eq!(h[CACHE_CONTROL], "no-store, no-cache, must-revalidate");
+
+        let mut h = headers(&[(CACHE_CONTROL, "public, max-age=31536000, immutable")]);
+        r.apply("/static/app.3f2a.js", &mut h);
+        assert_eq!(h[CACHE_CONTROL], "public, max-age=31536000, immutable");
+        let mut h = HeaderMap::new();
+        r.apply("/static/logo.png", &mut h);
+        assert_eq!(h[CACHE_CONTROL], "public, max-age=3600");
+
+        let mut h = headers(&[(X_FRAME_OPTIONS, "DENY")]);
+        r.apply("/embed/widget", &mut h);
+        assert_eq!(
+            h[CONTENT_SECURITY_POLICY],
+            "frame-ancestors 'self' https://portal.example"
+        );
+        assert!(!h.contains_key(X_FRAME_OPTIONS));
+
+        let mut h = HeaderMap::new();
+        r.apply("/apis", &mut h);
+        r.apply("/", &mut h);
+        assert!(h.is_empty());
+    }
+
+    #[test]
+    fn later_rules_override_earlier_ones() {
+        let mut h = headers(&[(ETAG, "\"v1\"")]);
+        rules().apply("/api/public/news", &mut h);
+        // `/api` sets no-store, so the `/api/public` default does not apply.
+        assert_eq!(h[CACHE_CONTROL], "no-store, no-cache, must-revalidate");
+        assert!(!h.contains_key(ETAG));
+
+        let r = HeaderRules::new().rule("/api", HeaderSet::no_store()).rule(
+            "/api/public",
+            HeaderSet::new().set(CACHE_CONTROL, HeaderValue::from_static("max-age=60")),
+        );
+        let mut h = HeaderMap::new();
+        r.apply("/api/public/news", &mut h);
+        assert_eq!(h[CACHE_CONTROL], "max-age=60");
+    }
+
+    #[test]
+    fn empty_prefixes_are_disabled() {
+        let r = HeaderRules::from_config(&HeaderPolicyConfig {
+            api_prefix: String::new(),
+            ..HeaderPolicyConfig::default()
+        });
+        let prefixes: Vec<&str> = r.rules().iter().map(|r| r.prefix.as_str()).collect();
+        assert_eq!(prefixes, ["/static", "/embed"]);
+        assert_eq!(
+            HeaderSet::frame_ancestors(["https://x.example; script-src *"]).entries()[0].1,
+            HeaderAction::Set(HeaderValue::from_static("frame-ancestors 'self'"))
+        );
+    }
+
+    #[tokio::test]
+    async fn middleware_applies_rules_to_responses() {
+        let app = Router::new()
+            .route("/api/me", get(|| async { "me" }))
+            .route("/about", get(|| async { "about" }))
+            .layer(from_fn_with_state(rules(), apply_header_rules));
+        let get = |uri: &'static str| {
+            app.clone()
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        };
+
+        let res = get("/api/me").await.unwrap();
+        assert_eq!(
+            res.headers()[CACHE_CONTROL],
+            "no-store, no-cache, must-revalidate"
+        );
+        let res = get("/about").await.unwrap();
+        assert!(!res.headers().contains_key(CACHE_CONTROL));
+    }
+}
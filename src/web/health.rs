@@ -0,0 +1,488 @@
+//! # Health Probes
+//!
+//! [`health_handler`] and [`db_health_handler`] are single probes for
+//! `/healthz`. [`health_router`] serves the split Kubernetes-style pair:
+//!
+//! | Route | Meaning |
+//! |-------|---------|
+//! | `GET /livez` | the process serves requests (no dependency checks) |
+//! | `GET /readyz` | every registered [`HealthCheck`] passes |
+//!
+//! Readiness runs the checks concurrently, each bounded by a timeout, and
+//! answers `200` or `503` with a JSON report:
+//!
+//! ```json
+//! {
+//!   "status": "down",
+//!   "checks": [
+//!     { "name": "db", "status": "up", "duration_ms": 2 },
+//!     { "name": "smtp", "status": "down", "duration_ms": 5000, "error": "timed out" }
+//!   ]
+//! }
+//! ```
+//!
+//! Failure details are logged rather than returned. Built-in checks:
+//! [`DbCheck`], [`SmtpCheck`] and [`DiskSpaceCheck`].
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use axum::Router;
+//! use wzs_web::config::db::{create_pool, DbConfig};
+//! use wzs_web::db::{mysql_adapter::MySqlDb, port::Db};
+//! use wzs_web::web::health::{health_router, DbCheck, DiskSpaceCheck, HealthChecks};
+//!
+//! let pool = create_pool(&DbConfig::from_env()).unwrap();
+//! let db: Arc<dyn Db> = Arc::new(MySqlDb::new(pool));
+//!
+//! let checks = HealthChecks::new()
+//!     .check(DbCheck::new(db))
+//!     .check(DiskSpaceCheck::new("/var/app/uploads", 512 * 1024 * 1024));
+//!
+//! let app: Router = Router::new().merge(health_router(checks));
+//! ```
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use axum::{
+    extract::State, http::StatusCode, response::IntoResponse, routing::get, Extension, Json, Router,
+};
+use futures_util::future::join_all;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::db::port::Db;
+use crate::error::app::AppError;
+use crate::notification::smtp::smtp_email_sender::SmtpEmailSender;
+use crate::web::problem::Problem;
+
+/// Path of the liveness probe in [`health_router`].
+pub const LIVEZ_PATH: &str = "/livez";
+/// Path of the readiness probe in [`health_router`].
+pub const READYZ_PATH: &str = "/readyz";
+
+/// Liveness probe handler.
+///
+/// # Overview
+///
+/// Returns `200 OK` with the body `ok` as long as the process can serve
+/// requests. It performs no dependency checks, so orchestrators do not
+/// restart the service when the database is briefly unavailable.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum::{routing::get, Router};
+/// use wzs_web::web::health::health_handler;
+///
+/// let app: Router = Router::new().route("/healthz", get(health_handler));
+/// ```
+pub async fn health_handler() -> impl IntoResponse {
+    (StatusCode::OK, "ok")
+}
+
+/// Readiness probe handler that verifies database connectivity.
+///
+/// # Overview
+///
+/// Runs [`Db::ping`] on the blocking pool and returns `200 OK` / `ok` when it
+/// succeeds, or `503 Service Unavailable` when it fails, so load balancers
+/// stop routing to instances that cannot reach the database.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use axum::{routing::get, Extension, Router};
+/// use wzs_web::config::db::{create_pool, DbConfig};
+/// use wzs_web::db::{mysql_adapter::MySqlDb, port::Db};
+/// use wzs_web::web::health::db_health_handler;
+///
+/// let pool = create_pool(&DbConfig::from_env()).unwrap();
+/// let db: Arc<dyn Db> = Arc::new(MySqlDb::new(pool));
+///
+/// let app: Router = Router::new()
+///     .route("/healthz", get(db_health_handler))
+///     .layer(Extension(db));
+/// ```
+pub async fn db_health_handler(
+    Extension(db): Extension<Arc<dyn Db>>,
+) -> Result<&'static str, AppError> {
+    match tokio::task::spawn_blocking(move || db.ping()).await {
+        Ok(Ok(())) => return Ok("ok"),
+        Ok(Err(e)) => warn!("database health check failed: {e:#}"),
+        Err(e) => warn!("database health check task failed: {e}"),
+    }
+    Err(Problem::new(StatusCode::SERVICE_UNAVAILABLE)
+        .detail("database unavailable")
+        .into())
+}
+
+/// A dependency readiness depends on.
+#[async_trait]
+pub trait HealthCheck: Send + Sync {
+    /// Name in the readiness report (e.g. `"db"`).
+    fn name(&self) -> &str;
+
+    /// `Ok` when the dependency is usable.
+    async fn check(&self) -> Result<()>;
+}
+
+/// Outcome of one check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+/// One entry of a [`Readiness`] report.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CheckReport {
+    pub name: String,
+    pub status: HealthStatus,
+    pub duration_ms: u64,
+    /// `"timed out"` or `"check failed"`; the cause is only logged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<&'static str>,
+}
+
+/// Result of all readiness checks.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Readiness {
+    /// `up` when every check is up.
+    pub status: HealthStatus,
+    pub checks: Vec<CheckReport>,
+}
+
+impl IntoResponse for Readiness {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self.status {
+            HealthStatus::Up => StatusCode::OK,
+            HealthStatus::Down => StatusCode::SERVICE_UNAVAILABLE,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+/// The readiness checks; state of [`health_router`].
+///
+/// Cloning is cheap.
+#[derive(Clone)]
+pub struct HealthChecks {
+    checks: Arc<Vec<Arc<dyn HealthCheck>>>,
+    timeout: Duration,
+}
+
+impl Default for HealthChecks {
+    fn default() -> Self {
+        Self {
+            checks: Arc::new(Vec::new()),
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl HealthChecks {
+    /// No checks (always ready), 5 s timeout.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a check.
+    pub fn check(mut self, check: impl HealthCheck + 'static) -> Self {
+        Arc::make_mut(&mut self.checks).push(Arc::new(check));
+        self
+    }
+
+    /// Time each check may take before it counts as down.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Runs every check concurrently.
+    pub async fn run(&self) -> Readiness {
+        let checks = join_all(self.checks.iter().map(|c| self.run_one(c.as_ref()))).await;
+        let status = if checks.iter().all(|c| c.status == HealthStatus::Up) {
+            HealthStatus::Up
+        } else {
+            HealthStatus::Down
+        };
+        Readiness { status, checks }
+    }
+
+    async fn run_one(&self, check: &dyn HealthCheck) -> CheckReport {
+        let started = Instant::now();
+        let error = match tokio::time::timeout(self.timeout, check.check()).await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => {
+                warn!(check = check.name(), "health check failed: {e:#}");
+                Some("check failed")
+            }
+            Err(_) => {
+                warn!(check = check.name(), "health check timed out");
+                Some("timed out")
+            }
+        };
+        CheckReport {
+            name: check.name().to_string(),
+            status: match error {
+                None => HealthStatus::Up,
+                Some(_) => HealthStatus::Down,
+            },
+            duration_ms: started.elapsed().as_millis() as u64,
+            error,
+        }
+    }
+}
+
+/// Router serving `/livez` ([`health_handler`]) and `/readyz` (`checks`).
+pub fn health_router(checks: HealthChecks) -> Router {
+    Router::new()
+        .route(LIVEZ_PATH, get(health_handler))
+        .route(READYZ_PATH, get(readyz_handler))
+        .with_state(checks)
+}
+
+async fn readyz_handler(State(checks): State<HealthChecks>) -> Readiness {
+    checks.run().await
+}
+
+/// Pings the database on the blocking pool.
+pub struct DbCheck(Arc<dyn Db>);
+
+impl DbCheck {
+    pub fn new(db: Arc<dyn Db>) -> Self {
+        Self(db)
+    }
+}
+
+#[async_trait]
+impl HealthCheck for DbCheck {
+    fn name(&self) -> &str {
+        "db"
+    }
+
+    async fn check(&self) -> Result<()> {
+        let db = self.0.clone();
+        tokio::task::spawn_blocking(move || db.ping()).await?
+    }
+}
+
+/// Opens a connection to the SMTP relay
+/// ([`SmtpEmailSender::test_connection`]).
+pub struct SmtpCheck(SmtpEmailSender);
+
+impl SmtpCheck {
+    pub fn new(sender: SmtpEmailSender) -> Self {
+        Self(sender)
+    }
+}
+
+#[async_trait]
+impl HealthCheck for SmtpCheck {
+    fn name(&self) -> &str {
+        "smtp"
+    }
+
+    async fn check(&self) -> Result<()> {
+        self.0.test_connection().await
+    }
+}
+
+/// Requires a minimum of free space on the file system holding `path`
+/// (typically [`UploadConfig::root`](crate::config::upload::UploadConfig)).
+///
+/// Only supported on Unix; elsewhere the check fails.
+pub struct DiskSpaceCheck {
+    path: PathBuf,
+    min_free_bytes: u64,
+}
+
+impl DiskSpaceCheck {
+    pub fn new(path: impl Into<PathBuf>, min_free_bytes: u64) -> Self {
+        Self {
+            path: path.into(),
+            min_free_bytes,
+        }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for DiskSpaceCheck {
+    fn name(&self) -> &str {
+        "disk"
+    }
+
+    async fn check(&self) -> Result<()> {
+        let path = self.path.clone();
+        let free = tokio::task::spawn_blocking(move || available_bytes(&path)).await??;
+        if free < self.min_free_bytes {
+            bail!(
+                "{} has {free} bytes free, below the minimum of {}",
+                self.path.display(),
+                self.min_free_bytes
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Bytes available to unprivileged users on the file system holding `path`.
+#[cfg(unix)]
+#[allow(clippy::useless_conversion)] // field types differ between platforms
+fn available_bytes(path: &std::path::Path) -> Result<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    // SAFETY: `c_path` is NUL-terminated and `stat` is a valid, writable
+    // `statvfs` that outlives the call.
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        let e = std::io::Error::last_os_error();
+        return Err(anyhow!("statvfs {} failed: {e}", path.display()));
+    }
+    Ok(u64::from(stat.f_bavail).saturating_mul(u64::from(stat.f_frsize)))
+}
+
+#[cfg(not(unix))]
+fn available_bytes(_path: &std::path::Path) -> Result<u64> {
+    bail!("disk space checks are only supported on unix")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::{bail, Result};
+
+    use crate::db::port::{Param, Row};
+
+    struct PingDb(bool);
+
+    impl Db for PingDb {
+        fn fetch_one(&self, _sql: &str, _params: &[Param]) -> Result<Option<Row>> {
+            if self.0 {
+                Ok(Some(Row::default()))
+            } else {
+                bail!("connection refused")
+            }
+        }
+
+        fn fetch_all(&self, _sql: &str, _params: &[Param]) -> Result<Vec<Row>> {
+            Ok(vec![])
+        }
+
+        fn exec(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+
+        fn exec_returning_last_insert_id(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    #[tokio::test]
+    async fn db_health_reports_database_state() {
+        let up: Arc<dyn Db> = Arc::new(PingDb(true));
+        let response = db_health_handler(Extension(up)).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let down: Arc<dyn Db> = Arc::new(PingDb(false));
+        let response = db_health_handler(Extension(down)).await.into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    struct Fixed(&'static str, Result<(), &'static str>, u64);
+
+    #[async_trait]
+    impl HealthCheck for Fixed {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        async fn check(&self) -> Result<()> {
+            tokio::time::sleep(Duration::from_millis(self.2)).await;
+            self.1.map_err(|e| anyhow!(e))
+        }
+    }
+
+    async fn readyz(checks: HealthChecks) -> (StatusCode, serde_json::Value) {
+        use tower::ServiceExt;
+
+        let req = axum::http::Request::get(READYZ_PATH)
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = health_router(checks).oneshot(req).await.unwrap();
+        let status = res.status();
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn readiness_aggregates_checks() {
+        let (status, body) = readyz(HealthChecks::new()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!({ "status": "up", "checks": [] }));
+
+        let checks = HealthChecks::new()
+            .timeout(Duration::from_millis(50))
+            .check(DbCheck::new(Arc::new(PingDb(true))))
+            .check(Fixed("cache", Err("connection refused: secret-host"), 0))
+            .check(Fixed("slow", Ok(()), 1_000));
+        let (status, body) = readyz(checks).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "down");
+        let summary: Vec<(&str, &str, Option<&str>)> = body["checks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| {
+                (
+                    c["name"].as_str().unwrap(),
+                    c["status"].as_str().unwrap(),
+                    c["error"].as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("db", "up", None),
+                ("cache", "down", Some("check failed")),
+                ("slow", "down", Some("timed out")),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn disk_space_check_compares_free_bytes() {
+        let dir = std::env::temp_dir();
+        assert!(DiskSpaceCheck::new(&dir, 0).check().await.is_ok());
+        assert!(DiskSpaceCheck::new(&dir, u64::MAX).check().await.is_err());
+        assert!(DiskSpaceCheck::new("/no/such/dir", 0)
+            .check()
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn returns_ok() {
+        let response = health_handler().await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"ok");
+    }
+}
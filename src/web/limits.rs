@@ -0,0 +1,275 @@
+//! # Request Body Size Limit
+//!
+//! [`build_body_limit`] turns [`HttpConfig::max_body_bytes`] into a
+//! [`BodyLimitLayer`], a tower layer that rejects larger request bodies with
+//! `413 Payload Too Large` as an `application/problem+json` document:
+//!
+//! - a `Content-Length` (or exact body size) over the limit is rejected
+//!   before the handler runs;
+//! - a streamed (chunked) body is counted while it is read; once it passes
+//!   the limit the read fails and the handler's response is replaced by the
+//!   `413`.
+//!
+//! Unlike `DefaultBodyLimit`, the limit covers every body (also handlers
+//! streaming `Body` themselves), and it replaces axum's 2 MB extractor
+//! default for the routes it wraps.
+//!
+//! # Per-route overrides
+//!
+//! A layer only applies to routes added *before* it, so endpoints needing a
+//! different limit — typically the upload endpoint — are merged in after
+//! the limited routes, with their own layer. The outer limit must not be
+//! smaller than the override, so with
+//! [`RouterBuilder`](crate::web::app::RouterBuilder) disable its limit
+//! ([`body_limit(false)`](crate::web::app::RouterBuilder::body_limit)) and
+//! mount the layers yourself.
+//!
+//! # Example
+//! ```rust
+//! use axum::{routing::post, Router};
+//! use wzs_web::config::web::HttpConfig;
+//! use wzs_web::web::limits::{build_body_limit, BodyLimitLayer};
+//!
+//! let http = HttpConfig { max_body_bytes: 1024 * 1024 };
+//!
+//! let api = Router::new()
+//!     .route("/api/comments", post(|body: String| async move { body }))
+//!     .layer(build_body_limit(&http));
+//!
+//! // Uploads may be up to 50 MB.
+//! let uploads = Router::new()
+//!     .route("/upload", post(|body: axum::body::Bytes| async move { body.len().to_string() }))
+//!     .layer(BodyLimitLayer::new(50 * 1024 * 1024));
+//!
+//! let app: Router = api.merge(uploads);
+//! # let _ = app;
+//! ```
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::{DefaultBodyLimit, Request},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures_util::{future::BoxFuture, StreamExt};
+use tower::{Layer, Service};
+
+use crate::config::web::HttpConfig;
+use crate::web::problem::Problem;
+
+/// [`BodyLimitLayer`] enforcing `cfg.max_body_bytes`.
+pub fn build_body_limit(cfg: &HttpConfig) -> BodyLimitLayer {
+    BodyLimitLayer::new(cfg.max_body_bytes)
+}
+
+/// Tower layer limiting request bodies; see the [module docs](self).
+#[derive(Clone, Copy, Debug)]
+pub struct BodyLimitLayer {
+    limit: usize,
+}
+
+impl BodyLimitLayer {
+    /// Rejects bodies larger than `limit` bytes.
+    pub fn new(limit: usize) -> Self {
+        Self { limit }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl<S> Layer<S> for BodyLimitLayer {
+    type Service = BodyLimitService<<DefaultBodyLimit as Layer<S>>::Service>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BodyLimitService {
+            inner: DefaultBodyLimit::disable().layer(inner),
+            limit: self.limit,
+        }
+    }
+}
+
+/// Service produced by [`BodyLimitLayer`].
+#[derive(Clone)]
+pub struct BodyLimitService<S> {
+    inner: S,
+    limit: usize,
+}
+
+impl<S> Service<Request> for BodyLimitService<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let limit = self.limit;
+        let declared = req
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+        let hint = req.body().size_hint();
+        if declared.unwrap_or_default().max(hint.lower()) > limit as u64 {
+            return Box::pin(async move { Ok(too_large(limit)) });
+        }
+        if hint.upper().is_some_and(|n| n <= limit as u64) {
+            return Box::pin(self.inner.call(req));
+        }
+
+        let exceeded = Arc::new(AtomicBool::new(false));
+        let (parts, body) = req.into_parts();
+        let body = counted(body, limit, exceeded.clone());
+        let fut = self.inner.call(Request::from_parts(parts, body));
+        Box::pin(async move {
+            let res = fut.await?;
+            Ok(if exceeded.load(Ordering::Relaxed) {
+                too_large(limit)
+            } else {
+                res
+            })
+        })
+    }
+}
+
+/// `body`, failing (and setting `exceeded`) once more than `limit` bytes
+/// were read.
+fn counted(body: Body, limit: usize, exceeded: Arc<AtomicBool>) -> Body {
+    let mut seen = 0usize;
+    Body::from_stream(body.into_data_stream().map(move |chunk| {
+        let chunk = chunk.map_err(io::Error::other)?;
+        seen = seen.saturating_add(chunk.len());
+        if seen > limit {
+            exceeded.store(true, Ordering::Relaxed);
+            return Err(io::Error::other("request body too large"));
+        }
+        Ok(chunk)
+    }))
+}
+
+fn too_large(limit: usize) -> Response {
+    Problem::new(StatusCode::PAYLOAD_TOO_LARGE)
+        .detail(format!("request body exceeds {limit} bytes"))
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{body::Bytes, routing::post, Router};
+    use futures_util::stream;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    fn app(limit: usize) -> Router {
+        Router::new()
+            .route("/echo", post(|body: String| async move { body }))
+            .route(
+                "/count",
+                post(|body: Body| async move {
+                    match axum::body::to_bytes(body, usize::MAX).await {
+                        Ok(bytes) => (StatusCode::OK, bytes.len().to_string()),
+                        Err(_) => (StatusCode::BAD_REQUEST, "read failed".to_string()),
+                    }
+                }),
+            )
+            .layer(build_body_limit(&HttpConfig {
+                max_body_bytes: limit,
+            }))
+    }
+
+    fn chunked(chunks: &'static [&'static str]) -> Body {
+        Body::from_stream(stream::iter(
+            chunks.iter().map(|c| Ok::<_, io::Error>(Bytes::from(*c))),
+        ))
+    }
+
+    async fn send(app: &Router, uri: &str, body: Body) -> (StatusCode, String) {
+        let res = app
+            .clone()
+            .oneshot(Request::post(uri).body(body).unwrap())
+            .await
+            .unwrap();
+        let status = res.status();
+        let is_problem = res
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .is_some_and(|v| v == "application/problem+json");
+        let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8_lossy(&bytes).into_owned();
+        if status == StatusCode::PAYLOAD_TOO_LARGE {
+            assert!(is_problem, "{text}");
+            let json: Value = serde_json::from_str(&text).unwrap();
+            assert_eq!(json["status"], 413);
+        }
+        (status, text)
+    }
+
+    #[tokio::test]
+    async fn rejects_declared_and_streamed_oversize_bodies() {
+        let app = app(8);
+        assert_eq!(
+            send(&app, "/echo", Body::from("12345678")).await,
+            (StatusCode::OK, "12345678".into())
+        );
+        let (status, _) = send(&app, "/echo", Body::from("123456789")).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+
+        assert_eq!(
+            send(&app, "/count", chunked(&["1234", "5678"])).await,
+            (StatusCode::OK, "8".into())
+        );
+        for uri in ["/echo", "/count"] {
+            let (status, _) = send(&app, uri, chunked(&["1234", "5678", "9"])).await;
+            assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn content_length_header_is_checked_up_front() {
+        let req = Request::post("/echo")
+            .header(header::CONTENT_LENGTH, "100")
+            .body(chunked(&["hi"]))
+            .unwrap();
+        let res = app(8).oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn replaces_the_extractor_default_and_allows_route_overrides() {
+        let big = "x".repeat(3 * 1024 * 1024);
+        let (status, _) = send(&app(4 * 1024 * 1024), "/echo", Body::from(big.clone())).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let app = app(8).merge(
+            Router::new()
+                .route(
+                    "/upload",
+                    post(|body: Bytes| async move { body.len().to_string() }),
+                )
+                .layer(BodyLimitLayer::new(4 * 1024 * 1024)),
+        );
+        assert_eq!(
+            send(&app, "/upload", Body::from(big)).await,
+            (StatusCode::OK, (3 * 1024 * 1024).to_string())
+        );
+        let (status, _) = send(&app, "/echo", Body::from("123456789")).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
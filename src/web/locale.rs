@@ -0,0 +1,250 @@
+//! # Request Locale
+//!
+//! [`Locale`] is an Axum extractor resolving the locale of a request:
+//!
+//! 1. `Accept-Language` tags, highest weight first ([`accept_language`])
+//! 2. matched against [`LocaleConfig::supported`] — exact tag first, then
+//!    primary language (`ja-JP` → `ja`, `en` → `en-US`)
+//! 3. falling back to [`LocaleConfig::default`]
+//!
+//! The configuration is read from an `Extension<LocaleConfig>` (installed by
+//! [`RouterBuilder`](crate::web::app::RouterBuilder)); without one, any
+//! requested tag is accepted and the fallback is `en`.
+//!
+//! In templates, [`Locale::lang`] and [`Locale::dir`] fill the `<html>`
+//! attributes; [`pages`](crate::web::pages) uses them for the built-in pages.
+//!
+//! # Messages
+//!
+//! [`Messages`](messages::Messages) holds per-locale JSON / TOML bundles;
+//! handlers look messages up with [`Locale::t`], templates with the `t`
+//! filter from [`filters`]. Share the catalog as an `Extension<Arc<Messages>>`
+//! or in the application state.
+//!
+//! # Example
+//! ```rust,no_run
+//! use axum::{routing::get, Extension, Router};
+//! use wzs_web::config::locale::LocaleConfig;
+//! use wzs_web::web::locale::Locale;
+//!
+//! async fn hello(locale: Locale) -> &'static str {
+//!     match locale.language() {
+//!         "ja" => "こんにちは",
+//!         _ => "Hello",
+//!     }
+//! }
+//!
+//! let app: Router = Router::new()
+//!     .route("/hello", get(hello))
+//!     .layer(Extension(LocaleConfig {
+//!         default: "en".into(),
+//!         supported: vec!["en".into(), "ja".into()],
+//!     }));
+//! ```
+
+pub mod filters;
+pub mod messages;
+
+use std::convert::Infallible;
+use std::fmt;
+
+use axum::{
+    extract::FromRequestParts,
+    http::{header::ACCEPT_LANGUAGE, request::Parts, HeaderMap},
+};
+
+use crate::config::locale::LocaleConfig;
+use crate::web::locale::messages::Messages;
+
+/// Languages written right to left.
+const RTL_LANGUAGES: [&str; 4] = ["ar", "fa", "he", "ur"];
+
+/// Negotiated locale of the current request (a BCP 47 tag).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Locale(pub String);
+
+impl Locale {
+    /// Resolves the locale for `headers` under `cfg`.
+    pub fn negotiate(headers: &HeaderMap, cfg: &LocaleConfig) -> Self {
+        let requested = accept_language(headers);
+        if cfg.supported.is_empty() {
+            return Self(
+                requested
+                    .into_iter()
+                    .next()
+                    .unwrap_or_else(|| cfg.default.clone()),
+            );
+        }
+        let exact = requested
+            .iter()
+            .find_map(|tag| cfg.supported.iter().find(|s| s.eq_ignore_ascii_case(tag)));
+        let by_language = || {
+            requested.iter().find_map(|tag| {
+                cfg.supported
+                    .iter()
+                    .find(|s| primary(s).eq_ignore_ascii_case(primary(tag)))
+            })
+        };
+        Self(exact.or_else(by_language).unwrap_or(&cfg.default).clone())
+    }
+
+    /// The full tag (e.g. `"ja-JP"`).
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Value for `<html lang>`.
+    pub fn lang(&self) -> &str {
+        &self.0
+    }
+
+    /// Primary language subtag (e.g. `"ja"` for `"ja-JP"`).
+    pub fn language(&self) -> &str {
+        primary(&self.0)
+    }
+
+    /// Value for `<html dir>`: `"rtl"` or `"ltr"`.
+    pub fn dir(&self) -> &'static str {
+        if RTL_LANGUAGES
+            .iter()
+            .any(|l| l.eq_ignore_ascii_case(self.language()))
+        {
+            "rtl"
+        } else {
+            "ltr"
+        }
+    }
+
+    /// Message for `key` in this locale (falling back as described in
+    /// [`messages`]).
+    pub fn t<'a>(&self, messages: &'a Messages, key: &'a str) -> &'a str {
+        messages.lookup(&self.0, key)
+    }
+}
+
+impl AsRef<str> for Locale {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<S> FromRequestParts<S> for Locale
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let locale = match parts.extensions.get::<LocaleConfig>() {
+            Some(cfg) => Self::negotiate(&parts.headers, cfg),
+            None => Self::negotiate(&parts.headers, &LocaleConfig::default()),
+        };
+        Ok(locale)
+    }
+}
+
+/// Language tags of `Accept-Language`, highest weight first (ties keep
+/// header order; `*` and `q=0` are dropped).
+pub fn accept_language(headers: &HeaderMap) -> Vec<String> {
+    let Some(value) = headers.get(ACCEPT_LANGUAGE).and_then(|v| v.to_str().ok()) else {
+        return Vec::new();
+    };
+    let mut tags: Vec<(&str, f32)> = value
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';').map(str::trim);
+            let tag = parts.next().unwrap_or_default();
+            let q = parts
+                .find_map(|p| p.strip_prefix("q="))
+                .map_or(1.0, |q| q.parse().unwrap_or(0.0));
+            (!tag.is_empty() && tag != "*" && q > 0.0).then_some((tag, q))
+        })
+        .collect();
+    tags.sort_by(|a, b| b.1.total_cmp(&a.1));
+    tags.into_iter().map(|(tag, _)| tag.to_string()).collect()
+}
+
+fn primary(tag: &str) -> &str {
+    tag.split(['-', '_']).next().unwrap_or(tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(value: &str) -> HeaderMap {
+        let mut h = HeaderMap::new();
+        h.insert(ACCEPT_LANGUAGE, HeaderValue::from_str(value).unwrap());
+        h
+    }
+
+    fn cfg(default: &str, supported: &[&str]) -> LocaleConfig {
+        LocaleConfig {
+            default: default.into(),
+            supported: supported.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn orders_accept_language_by_weight() {
+        assert_eq!(
+            accept_language(&headers("fr;q=0.5, ja-JP, en;q=0.8, *;q=0.1, de;q=0")),
+            ["ja-JP", "en", "fr"]
+        );
+        assert!(accept_language(&HeaderMap::new()).is_empty());
+    }
+
+    #[test]
+    fn negotiates_against_supported_locales() {
+        let supported = cfg("en", &["en-US", "ja"]);
+        let pick = |h: &str| Locale::negotiate(&headers(h), &supported).0;
+        assert_eq!(pick("ja-JP, en;q=0.5"), "ja");
+        assert_eq!(pick("de, en-US;q=0.2"), "en-US");
+        assert_eq!(pick("en-GB"), "en-US");
+        assert_eq!(pick("de"), "en");
+        assert_eq!(
+            Locale::negotiate(&HeaderMap::new(), &cfg("ja", &[])).0,
+            "ja"
+        );
+        assert_eq!(
+            Locale::negotiate(&headers("pt-BR"), &cfg("en", &[])).0,
+            "pt-BR"
+        );
+    }
+
+    #[test]
+    fn exposes_template_attributes() {
+        let l = Locale("ar-EG".into());
+        assert_eq!((l.lang(), l.language(), l.dir()), ("ar-EG", "ar", "rtl"));
+        assert_eq!(Locale("ja".into()).dir(), "ltr");
+    }
+
+    #[test]
+    fn looks_up_messages() {
+        let m = Messages::new("en").bundle(
+            "en",
+            messages::MessageBundle::default().insert("greeting", "Hello"),
+        );
+        assert_eq!(Locale("ja".into()).t(&m, "greeting"), "Hello");
+        assert_eq!(Locale("ja".into()).to_string(), "ja");
+    }
+
+    #[tokio::test]
+    async fn extracts_with_configured_default() {
+        let req = axum::http::Request::get("/")
+            .extension(cfg("ja", &["ja"]))
+            .header(ACCEPT_LANGUAGE, "de")
+            .body(())
+            .unwrap();
+        let (mut parts, _) = req.into_parts();
+        let Ok(locale) = Locale::from_request_parts(&mut parts, &()).await;
+        assert_eq!(locale.as_str(), "ja");
+    }
+}
@@ -0,0 +1,76 @@
+//! Askama filters looking up [`Messages`].
+//!
+//! Import this module as `filters` next to the template struct (see
+//! [`format::filters`](crate::format::filters)); the locale may be a
+//! [`Locale`](crate::web::locale::Locale) or a plain tag:
+//!
+//! ```rust
+//! use askama::Template;
+//! use wzs_web::web::locale::filters;
+//! use wzs_web::web::locale::messages::{MessageBundle, Messages};
+//! use wzs_web::web::locale::Locale;
+//!
+//! #[derive(Template)]
+//! #[template(source = "<h1>{{ \"nav.home\"|t(messages, locale) }}</h1>", ext = "html")]
+//! struct Page<'a> {
+//!     messages: &'a Messages,
+//!     locale: Locale,
+//! }
+//!
+//! let messages = Messages::new("en")
+//!     .bundle("ja", MessageBundle::default().insert("nav.home", "ホーム"));
+//! let out = Page { messages: &messages, locale: Locale("ja-JP".into()) }
+//!     .render()
+//!     .unwrap();
+//! assert_eq!(out, "<h1>ホーム</h1>");
+//! ```
+
+use crate::web::locale::messages::Messages;
+
+/// `{{ "key"|t(messages, locale) }}`
+pub fn t(
+    key: impl AsRef<str>,
+    _: &dyn askama::Values,
+    messages: &Messages,
+    locale: impl AsRef<str>,
+) -> askama::Result<String> {
+    Ok(messages.lookup(locale.as_ref(), key.as_ref()).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use askama::Template;
+
+    use crate::web::locale::filters;
+    use crate::web::locale::messages::{MessageBundle, Messages};
+    use crate::web::locale::Locale;
+
+    #[derive(Template)]
+    #[template(
+        source = "{{ \"title\"|t(messages, locale) }}|{{ key|t(messages, \"en\") }}",
+        ext = "html"
+    )]
+    struct Page<'a> {
+        messages: &'a Messages,
+        locale: &'a Locale,
+        key: &'a str,
+    }
+
+    #[test]
+    fn translates_and_escapes() {
+        let messages = Messages::new("en")
+            .bundle("en", MessageBundle::default().insert("title", "Q&A"))
+            .bundle("fr", MessageBundle::default().insert("title", "Q/R"));
+        let render = |locale: &str, key: &str| {
+            Page {
+                messages: &messages,
+                locale: &Locale(locale.into()),
+                key,
+            }
+            .render()
+            .unwrap()
+        };
+        assert_eq!(render("fr-CA", "title"), "Q/R|Q&#38;A");
+        assert_eq!(render("de", "nope"), "Q&#38;A|nope");
+    }
+}
@@ -0,0 +1,283 @@
+//! Translated messages loaded from per-locale JSON or TOML bundles.
+//!
+//! A bundle maps keys to strings; nested objects / tables are flattened
+//! into dotted keys, so these two files are equivalent:
+//!
+//! ```text
+//! // locales/ja.json
+//! { "nav": { "home": "ホーム" }, "greeting": "こんにちは" }
+//!
+//! # locales/ja.toml
+//! greeting = "こんにちは"
+//! [nav]
+//! home = "ホーム"
+//! ```
+//!
+//! [`Messages::lookup`] tries the exact locale, then its primary language
+//! (`ja-JP` → `ja`), then the default locale, and finally returns the key
+//! itself, so a missing translation shows up in the page instead of
+//! failing the render.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use toml_edit::{Document, Item, TableLike};
+
+/// Flat `key → message` map for one locale.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MessageBundle(HashMap<String, String>);
+
+impl MessageBundle {
+    /// Parses a JSON object bundle.
+    ///
+    /// # Errors
+    /// Returns an error on invalid JSON, a non-object root, or a value that
+    /// is neither a string nor an object.
+    pub fn from_json(src: &str) -> Result<Self> {
+        let root: Value = serde_json::from_str(src).context("invalid JSON message bundle")?;
+        let Value::Object(map) = root else {
+            bail!("JSON message bundle must be an object");
+        };
+        let mut bundle = Self::default();
+        bundle.flatten_json("", &map)?;
+        Ok(bundle)
+    }
+
+    /// Parses a TOML bundle.
+    ///
+    /// # Errors
+    /// Returns an error on invalid TOML or a value that is neither a string
+    /// nor a table.
+    pub fn from_toml(src: &str) -> Result<Self> {
+        let doc = Document::parse(src).context("invalid TOML message bundle")?;
+        let mut bundle = Self::default();
+        bundle.flatten_toml("", doc.as_table())?;
+        Ok(bundle)
+    }
+
+    /// Reads a bundle, choosing the format by extension (`.json` / `.toml`).
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed, or has another
+    /// extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let src = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let parsed = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::from_json(&src),
+            Some("toml") => Self::from_toml(&src),
+            _ => bail!("unsupported message bundle {}", path.display()),
+        };
+        parsed.with_context(|| format!("in {}", path.display()))
+    }
+
+    /// Adds or replaces a message.
+    pub fn insert(mut self, key: impl Into<String>, message: impl Into<String>) -> Self {
+        self.0.insert(key.into(), message.into());
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn flatten_json(&mut self, prefix: &str, map: &serde_json::Map<String, Value>) -> Result<()> {
+        for (key, value) in map {
+            let key = join(prefix, key);
+            match value {
+                Value::String(s) => {
+                    self.0.insert(key, s.clone());
+                }
+                Value::Object(nested) => self.flatten_json(&key, nested)?,
+                _ => bail!("message `{key}` must be a string"),
+            }
+        }
+        Ok(())
+    }
+
+    fn flatten_toml(&mut self, prefix: &str, table: &dyn TableLike) -> Result<()> {
+        for (key, item) in table.iter() {
+            let key = join(prefix, key);
+            if let Some(nested) = item.as_table_like() {
+                self.flatten_toml(&key, nested)?;
+            } else if let Some(s) = item.as_str() {
+                self.0.insert(key, s.to_string());
+            } else if !matches!(item, Item::None) {
+                bail!("message `{key}` must be a string");
+            }
+        }
+        Ok(())
+    }
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+/// Message bundles of all locales, with a default locale to fall back to.
+#[derive(Clone, Debug, Default)]
+pub struct Messages {
+    default: String,
+    bundles: HashMap<String, MessageBundle>,
+}
+
+impl Messages {
+    /// Empty catalog falling back to `default_locale`.
+    pub fn new(default_locale: impl Into<String>) -> Self {
+        Self {
+            default: default_locale.into(),
+            bundles: HashMap::new(),
+        }
+    }
+
+    /// Loads every `<locale>.json` / `<locale>.toml` file in `dir`.
+    ///
+    /// # Errors
+    /// Returns an error if the directory or one of the bundles cannot be
+    /// read.
+    pub fn load_dir(dir: impl AsRef<Path>, default_locale: impl Into<String>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut messages = Self::new(default_locale);
+        let entries =
+            std::fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            let ext = path.extension().and_then(|e| e.to_str());
+            if !matches!(ext, Some("json" | "toml")) {
+                continue;
+            }
+            let Some(locale) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let locale = locale.to_string();
+            messages = messages.bundle(locale, MessageBundle::load(&path)?);
+        }
+        Ok(messages)
+    }
+
+    /// Adds `bundle` for `locale`, merging into an existing one.
+    pub fn bundle(mut self, locale: impl Into<String>, bundle: MessageBundle) -> Self {
+        self.bundles
+            .entry(locale.into())
+            .or_default()
+            .0
+            .extend(bundle.0);
+        self
+    }
+
+    pub fn default_locale(&self) -> &str {
+        &self.default
+    }
+
+    /// Locales with a bundle.
+    pub fn locales(&self) -> impl Iterator<Item = &str> {
+        self.bundles.keys().map(String::as_str)
+    }
+
+    /// Message for `key` in `locale`, without falling back to the key.
+    pub fn get(&self, locale: &str, key: &str) -> Option<&str> {
+        let language = locale.split(['-', '_']).next().unwrap_or(locale);
+        [locale, language, self.default.as_str()]
+            .into_iter()
+            .find_map(|l| self.find_bundle(l).and_then(|b| b.get(key)))
+    }
+
+    /// Message for `key` in `locale` (see the [module docs](self)).
+    pub fn lookup<'a>(&'a self, locale: &str, key: &'a str) -> &'a str {
+        self.get(locale, key).unwrap_or(key)
+    }
+
+    fn find_bundle(&self, locale: &str) -> Option<&MessageBundle> {
+        self.bundles.get(locale).or_else(|| {
+            self.bundles
+                .iter()
+                .find(|(l, _)| l.eq_ignore_ascii_case(locale))
+                .map(|(_, b)| b)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages() -> Messages {
+        Messages::new("en")
+            .bundle(
+                "en",
+                MessageBundle::from_json(
+                    r#"{"greeting":"Hello","nav":{"home":"Home","help":"Help"}}"#,
+                )
+                .unwrap(),
+            )
+            .bundle(
+                "ja",
+                MessageBundle::from_toml("greeting = \"こんにちは\"\n[nav]\nhome = \"ホーム\"\n")
+                    .unwrap(),
+            )
+            .bundle("ja-JP", MessageBundle::default().insert("greeting", "やあ"))
+    }
+
+    #[test]
+    fn flattens_nested_keys() {
+        let json = MessageBundle::from_json(r#"{"a":{"b":{"c":"x"}},"d":"y"}"#).unwrap();
+        let toml = MessageBundle::from_toml("d = \"y\"\na = { b = { c = \"x\" } }").unwrap();
+        assert_eq!(json, toml);
+        assert_eq!(json.get("a.b.c"), Some("x"));
+        assert_eq!(json.len(), 2);
+    }
+
+    #[test]
+    fn rejects_non_string_messages() {
+        assert!(MessageBundle::from_json(r#"{"n":1}"#).is_err());
+        assert!(MessageBundle::from_json("[]").is_err());
+        assert!(MessageBundle::from_toml("n = 1").is_err());
+        assert!(MessageBundle::from_toml("n = ").is_err());
+    }
+
+    #[test]
+    fn falls_back_to_language_default_and_key() {
+        let m = messages();
+        assert_eq!(m.lookup("ja-JP", "greeting"), "やあ");
+        assert_eq!(m.lookup("ja-jp", "nav.home"), "ホーム");
+        assert_eq!(m.lookup("ja", "nav.help"), "Help");
+        assert_eq!(m.lookup("fr", "greeting"), "Hello");
+        assert_eq!(m.lookup("ja", "missing.key"), "missing.key");
+        assert_eq!(m.get("ja", "missing.key"), None);
+    }
+
+    #[test]
+    fn loads_a_directory_of_bundles() {
+        let dir = std::env::temp_dir().join(format!("wzs-locales-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("en.json"), r#"{"greeting":"Hello"}"#).unwrap();
+        std::fs::write(dir.join("ja.toml"), "greeting = \"こんにちは\"").unwrap();
+        std::fs::write(dir.join("README.md"), "ignored").unwrap();
+
+        let m = Messages::load_dir(&dir, "en").unwrap();
+        let mut locales: Vec<_> = m.locales().collect();
+        locales.sort();
+        assert_eq!(locales, ["en", "ja"]);
+        assert_eq!(m.lookup("ja-JP", "greeting"), "こんにちは");
+
+        std::fs::write(dir.join("de.json"), "{").unwrap();
+        let err = Messages::load_dir(&dir, "en").unwrap_err();
+        assert!(format!("{err:#}").contains("de.json"));
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
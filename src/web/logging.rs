@@ -0,0 +1,356 @@
+//! # HTTP Request Logging
+//!
+//! [`HttpLogLayer`] is a tower layer emitting one structured `tracing` event
+//! per request (target `wzs_web::http`, message `"http request"`):
+//!
+//! | Field | Value |
+//! |-------|-------|
+//! | `method`, `path`, `status` | Request line and response status |
+//! | `latency_ms` | Time until the response head was produced |
+//! | `bytes` | Response body size when known (`Content-Length` or exact size hint) |
+//! | `client_ip` | [`ClientIp`] extension (see [`resolve_client_ip`](crate::web::client_ip::resolve_client_ip)) or peer address, or `-` |
+//! | `request_id` | [`RequestId`] extension, or `-` |
+//! | `user` | Subject of a [`CurrentUser`] extension, or `-` |
+//! | `headers` | Request headers, only with [`log_headers`](HttpLogLayer::log_headers) |
+//!
+//! Responses with a 5xx status are logged at `WARN`, everything else at
+//! `INFO`. Paths under an [`exclude`](HttpLogLayer::exclude) prefix (health
+//! checks, metrics scrapes) are not logged at all.
+//!
+//! Logged headers never include credentials: `Authorization`, `Cookie`,
+//! `X-CSRF-Token`, … (plus [`redact_header`](HttpLogLayer::redact_header))
+//! are written as `[REDACTED]`.
+//!
+//! Mount it inside [`request_id`](crate::web::middleware::request_id::request_id)
+//! and the authentication middleware, so the extensions it reads are set.
+//!
+//! # Example
+//! ```rust,no_run
+//! use axum::{middleware::from_fn, routing::get, Router};
+//! use wzs_web::web::logging::HttpLogLayer;
+//! use wzs_web::web::middleware::request_id::request_id;
+//!
+//! let app: Router = Router::new()
+//!     .route("/", get(|| async { "ok" }))
+//!     .route("/healthz", get(|| async { "ok" }))
+//!     .layer(HttpLogLayer::new().exclude("/healthz").log_headers(true))
+//!     .layer(from_fn(request_id));
+//! ```
+
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use axum::{
+    body::HttpBody,
+    extract::Request,
+    http::{header, HeaderMap},
+    response::Response,
+};
+use futures_util::future::BoxFuture;
+use tower::{Layer, Service};
+
+use crate::auth::CurrentUser;
+use crate::web::client_ip::ClientIp;
+use crate::web::middleware::fixtures::{DEFAULT_REDACTED_HEADERS, REDACTED};
+use crate::web::middleware::request_id::RequestId;
+
+#[derive(Clone, Debug)]
+struct Settings {
+    exclude: Vec<String>,
+    log_headers: bool,
+    redacted: Vec<String>,
+}
+
+/// Tower layer logging requests; see the [module docs](self).
+///
+/// Cloning is cheap; clones share the settings.
+#[derive(Clone, Debug)]
+pub struct HttpLogLayer {
+    settings: Arc<Settings>,
+}
+
+impl Default for HttpLogLayer {
+    fn default() -> Self {
+        Self {
+            settings: Arc::new(Settings {
+                exclude: Vec::new(),
+                log_headers: false,
+                redacted: DEFAULT_REDACTED_HEADERS.map(String::from).to_vec(),
+            }),
+        }
+    }
+}
+
+impl HttpLogLayer {
+    /// Logs every request, without headers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Skips requests whose path is `prefix` or lies below it.
+    pub fn exclude(mut self, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into().trim_end_matches('/').to_string();
+        Arc::make_mut(&mut self.settings).exclude.push(prefix);
+        self
+    }
+
+    /// Includes the (redacted) request headers in each event.
+    pub fn log_headers(mut self, enabled: bool) -> Self {
+        Arc::make_mut(&mut self.settings).log_headers = enabled;
+        self
+    }
+
+    /// Also redacts the header `name`.
+    pub fn redact_header(mut self, name: impl Into<String>) -> Self {
+        let name = name.into().to_ascii_lowercase();
+        Arc::make_mut(&mut self.settings).redacted.push(name);
+        self
+    }
+
+    fn logs(&self, path: &str) -> bool {
+        !self.settings.exclude.iter().any(|prefix| {
+            path.strip_prefix(prefix.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+
+    /// `name: value` pairs joined with `; `, credentials redacted.
+    fn headers(&self, headers: &HeaderMap) -> String {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if self.settings.redacted.iter().any(|r| r == name.as_str()) {
+                    REDACTED.into()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes())
+                };
+                format!("{name}: {value}")
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+impl<S> Layer<S> for HttpLogLayer {
+    type Service = HttpLogService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        HttpLogService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service produced by [`HttpLogLayer`].
+#[derive(Clone)]
+pub struct HttpLogService<S> {
+    inner: S,
+    layer: HttpLogLayer,
+}
+
+impl<S> Service<Request> for HttpLogService<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let path = req.uri().path().to_string();
+        if !self.layer.logs(&path) {
+            return Box::pin(self.inner.call(req));
+        }
+
+        let started = Instant::now();
+        let method = req.method().clone();
+        let client_ip = ClientIp::of(req.extensions()).map(|ip| ip.to_string());
+        let request_id = req.extensions().get::<RequestId>().map(|r| r.0.clone());
+        let user = req
+            .extensions()
+            .get::<CurrentUser>()
+            .map(|u| u.subject.clone());
+        let headers = self
+            .layer
+            .settings
+            .log_headers
+            .then(|| self.layer.headers(req.headers()));
+        let fut = self.inner.call(req);
+
+        Box::pin(async move {
+            let res = fut.await?;
+            let status = res.status().as_u16();
+            let latency_ms = started.elapsed().as_millis() as u64;
+            let bytes = body_size(&res);
+            let client_ip = client_ip.as_deref().unwrap_or("-");
+            let request_id = request_id.as_deref().unwrap_or("-");
+            let user = user.as_deref().unwrap_or("-");
+            let headers = headers.as_deref();
+            if status >= 500 {
+                tracing::warn!(
+                    target: "wzs_web::http",
+                    method = %method,
+                    path = %path,
+                    status,
+                    latency_ms,
+                    bytes,
+                    client_ip,
+                    request_id,
+                    user,
+                    headers,
+                    "http request"
+                );
+            } else {
+                tracing::info!(
+                    target: "wzs_web::http",
+                    method = %method,
+                    path = %path,
+                    status,
+                    latency_ms,
+                    bytes,
+                    client_ip,
+                    request_id,
+                    user,
+                    headers,
+                    "http request"
+                );
+            }
+            Ok(res)
+        })
+    }
+}
+
+/// Body size from `Content-Length`, or the exact size hint of the body.
+fn body_size(res: &Response) -> Option<u64> {
+    res.headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .or_else(|| res.body().size_hint().exact())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fmt::Debug;
+    use std::sync::Mutex;
+
+    use axum::{body::Body, http::StatusCode, routing::get, Extension, Router};
+    use tower::ServiceExt;
+    use tracing::field::{Field, Visit};
+    use tracing::{span, Event, Metadata, Subscriber};
+
+    /// Collects the fields of every event as `name=value`.
+    #[derive(Default)]
+    struct Capture(Mutex<Vec<Vec<String>>>);
+
+    struct Fields(Vec<String>);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0.push(format!("{}={value:?}", field.name()));
+        }
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.push(format!("{}={value}", field.name()));
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+            span::Id::from_u64(1)
+        }
+        fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields(vec![format!("level={}", event.metadata().level())]);
+            event.record(&mut fields);
+            self.0.lock().unwrap().push(fields.0);
+        }
+        fn enter(&self, _: &span::Id) {}
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    fn app(layer: HttpLogLayer) -> Router {
+        Router::new()
+            .route("/hello", get(|| async { "hello" }))
+            .route("/healthz", get(|| async { "ok" }))
+            .route("/boom", get(|| async { StatusCode::INTERNAL_SERVER_ERROR }))
+            .layer(layer)
+            .layer(Extension(CurrentUser::new("42")))
+            .layer(Extension(RequestId("req-1".into())))
+            .layer(Extension(ClientIp("203.0.113.7".parse().unwrap())))
+    }
+
+    async fn logged(layer: HttpLogLayer, req: Request) -> Vec<Vec<String>> {
+        let capture = Arc::new(Capture::default());
+        let _guard = tracing::subscriber::set_default(capture.clone());
+        app(layer).oneshot(req).await.unwrap();
+        capture.0.lock().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn logs_request_fields() {
+        let req = Request::get("/hello").body(Body::empty()).unwrap();
+        let events = logged(HttpLogLayer::new(), req).await;
+        assert_eq!(events.len(), 1);
+        let e = &events[0];
+        for field in [
+            "level=INFO",
+            "method=GET",
+            "path=/hello",
+            "status=200",
+            "bytes=5",
+            "client_ip=203.0.113.7",
+            "request_id=req-1",
+            "user=42",
+        ] {
+            assert!(e.contains(&field.to_string()), "{field} in {e:?}");
+        }
+        assert!(e.iter().any(|f| f.starts_with("latency_ms=")));
+        assert!(!e.iter().any(|f| f.starts_with("headers=")));
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_warnings_and_exclusions_are_silent() {
+        let req = Request::get("/boom").body(Body::empty()).unwrap();
+        let events = logged(HttpLogLayer::new(), req).await;
+        assert!(events[0].contains(&"level=WARN".to_string()));
+
+        let layer = HttpLogLayer::new().exclude("/healthz/");
+        let req = Request::get("/healthz").body(Body::empty()).unwrap();
+        assert!(logged(layer, req).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn logged_headers_are_redacted() {
+        let layer = HttpLogLayer::new()
+            .log_headers(true)
+            .redact_header("X-Tenant-Key");
+        let req = Request::get("/hello")
+            .header("cookie", "jwt=secret")
+            .header("x-tenant-key", "k")
+            .header("accept", "text/plain")
+            .body(Body::empty())
+            .unwrap();
+        let events = logged(layer, req).await;
+        let headers = events[0]
+            .iter()
+            .find(|f| f.starts_with("headers="))
+            .unwrap();
+        assert_eq!(
+            headers,
+            "headers=cookie: [REDACTED]; x-tenant-key: [REDACTED]; accept: text/plain"
+        );
+    }
+}
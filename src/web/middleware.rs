@@ -0,0 +1,14 @@
+//! # HTTP Middleware
+//!
+//! Request-level layers mounted with `axum::middleware::from_fn` or
+//! `axum::middleware::from_fn_with_state`, or as tower layers
+//! ([`csrf::CsrfLayer`]).
+
+pub mod concurrency_limit;
+pub mod csrf;
+pub mod degraded;
+pub mod fixtures;
+pub mod metrics;
+pub mod micro_cache;
+pub mod replay;
+pub mod request_id;
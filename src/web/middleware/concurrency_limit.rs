@@ -0,0 +1,309 @@
+//! # Concurrency Limit Middleware
+//!
+//! Caps the number of requests processed at once so that traffic spikes queue
+//! briefly in front of the application instead of exhausting the DB pool.
+//!
+//! - Up to `max_in_flight` requests run concurrently.
+//! - Further requests wait up to `queue_timeout` for a slot.
+//! - Requests that time out receive `503 Service Unavailable` with a
+//!   `Retry-After` header.
+//!
+//! [`ConcurrencyLimit::stats`] exposes in-flight / queued / rejected gauges
+//! for health endpoints or metrics exporters.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use axum::{middleware::from_fn_with_state, routing::get, Router};
+//! use wzs_web::web::middleware::concurrency_limit::{concurrency_limit, ConcurrencyLimit};
+//!
+//! let limit = ConcurrencyLimit::new(64).queue_timeout(Duration::from_millis(500));
+//!
+//! let app: Router = Router::new()
+//!     .route("/", get(|| async { "ok" }))
+//!     .layer(from_fn_with_state(limit.clone(), concurrency_limit));
+//!
+//! println!("in flight: {}", limit.stats().in_flight);
+//! ```
+
+use std::env as std_env;
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+use std::time::Duration;
+
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tokio::sync::Semaphore;
+
+struct Inner {
+    semaphore: Arc<Semaphore>,
+    max_in_flight: usize,
+    in_flight: AtomicUsize,
+    queued: AtomicUsize,
+    rejected: AtomicU64,
+}
+
+/// Shared concurrency limiter.
+///
+/// Cloning is cheap; all clones share the same slots and gauges.
+#[derive(Clone)]
+pub struct ConcurrencyLimit {
+    inner: Arc<Inner>,
+    queue_timeout: Duration,
+    retry_after_secs: u32,
+}
+
+/// Point-in-time gauge values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConcurrencyStats {
+    /// Configured maximum.
+    pub max_in_flight: usize,
+    /// Requests currently being processed.
+    pub in_flight: usize,
+    /// Requests waiting for a slot.
+    pub queued: usize,
+    /// Requests rejected with 503 since startup.
+    pub rejected: u64,
+}
+
+impl ConcurrencyLimit {
+    /// Creates a limiter allowing `max_in_flight` concurrent requests
+    /// (minimum 1), a 1s queue timeout and `Retry-After: 1`.
+    pub fn new(max_in_flight: usize) -> Self {
+        let max_in_flight = max_in_flight.max(1);
+        Self {
+            inner: Arc::new(Inner {
+                semaphore: Arc::new(Semaphore::new(max_in_flight)),
+                max_in_flight,
+                in_flight: AtomicUsize::new(0),
+                queued: AtomicUsize::new(0),
+                rejected: AtomicU64::new(0),
+            }),
+            queue_timeout: Duration::from_secs(1),
+            retry_after_secs: 1,
+        }
+    }
+
+    /// Loads settings from environment variables.
+    ///
+    /// | Key | Default |
+    /// |-----|---------|
+    /// | `WEB_MAX_IN_FLIGHT` | `256` |
+    /// | `WEB_QUEUE_TIMEOUT_MS` | `1000` |
+    /// | `WEB_RETRY_AFTER_SECS` | `1` |
+    pub fn from_env() -> Self {
+        Self::from_env_with(|k| std_env::var(k).ok())
+    }
+
+    /// Same as [`from_env`](Self::from_env) with a custom provider.
+    pub fn from_env_with<F>(get: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let num = |key: &str, default: u64| {
+            get(key)
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .unwrap_or(default)
+        };
+
+        Self::new(num("WEB_MAX_IN_FLIGHT", 256) as usize)
+            .queue_timeout(Duration::from_millis(num("WEB_QUEUE_TIMEOUT_MS", 1000)))
+            .retry_after(num("WEB_RETRY_AFTER_SECS", 1) as u32)
+    }
+
+    /// Sets how long a request may wait for a slot (zero = reject immediately).
+    pub fn queue_timeout(mut self, timeout: Duration) -> Self {
+        self.queue_timeout = timeout;
+        self
+    }
+
+    /// Sets the `Retry-After` value (seconds) sent with 503 responses.
+    pub fn retry_after(mut self, secs: u32) -> Self {
+        self.retry_after_secs = secs;
+        self
+    }
+
+    /// Returns the current gauge values.
+    pub fn stats(&self) -> ConcurrencyStats {
+        ConcurrencyStats {
+            max_in_flight: self.inner.max_in_flight,
+            in_flight: self.inner.in_flight.load(Ordering::SeqCst),
+            queued: self.inner.queued.load(Ordering::SeqCst),
+            rejected: self.inner.rejected.load(Ordering::SeqCst),
+        }
+    }
+
+    fn overloaded(&self) -> Response {
+        self.inner.rejected.fetch_add(1, Ordering::SeqCst);
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, self.retry_after_secs.to_string())],
+            "server busy",
+        )
+            .into_response()
+    }
+}
+
+/// Decrements the in-flight gauge when the request completes.
+struct InFlight<'a>(&'a Inner);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Middleware enforcing a [`ConcurrencyLimit`].
+///
+/// Mount with `axum::middleware::from_fn_with_state(limit, concurrency_limit)`.
+pub async fn concurrency_limit(
+    State(limit): State<ConcurrencyLimit>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let inner = &limit.inner;
+
+    let permit = match inner.semaphore.clone().try_acquire_owned() {
+        Ok(p) => p,
+        Err(_) if limit.queue_timeout.is_zero() => return limit.overloaded(),
+        Err(_) => {
+            inner.queued.fetch_add(1, Ordering::SeqCst);
+            let waited =
+                tokio::time::timeout(limit.queue_timeout, inner.semaphore.clone().acquire_owned())
+                    .await;
+            inner.queued.fetch_sub(1, Ordering::SeqCst);
+
+            match waited {
+                Ok(Ok(p)) => p,
+                _ => return limit.overloaded(),
+            }
+        }
+    };
+
+    inner.in_flight.fetch_add(1, Ordering::SeqCst);
+    let _in_flight = InFlight(inner);
+    let _permit = permit;
+
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{body::Body, middleware::from_fn_with_state, routing::get, Router};
+    use tokio::sync::Notify;
+    use tower::ServiceExt;
+
+    fn app(limit: ConcurrencyLimit, gate: Arc<Notify>) -> Router {
+        Router::new()
+            .route(
+                "/slow",
+                get(move || {
+                    let gate = gate.clone();
+                    async move {
+                        gate.notified().await;
+                        "done"
+                    }
+                }),
+            )
+            .route("/fast", get(|| async { "fast" }))
+            .layer(from_fn_with_state(limit, concurrency_limit))
+    }
+
+    fn req(uri: &str) -> Request {
+        Request::get(uri).body(Body::empty()).unwrap()
+    }
+
+    async fn wait_until(cond: impl Fn() -> bool) {
+        for _ in 0..200 {
+            if cond() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("condition not reached");
+    }
+
+    #[tokio::test]
+    async fn rejects_with_retry_after_when_queue_times_out() {
+        let limit = ConcurrencyLimit::new(1)
+            .queue_timeout(Duration::from_millis(20))
+            .retry_after(7);
+        let gate = Arc::new(Notify::new());
+        let app = app(limit.clone(), gate.clone());
+
+        let slow = tokio::spawn(app.clone().oneshot(req("/slow")));
+        wait_until(|| limit.stats().in_flight == 1).await;
+
+        let resp = app.clone().oneshot(req("/fast")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(resp.headers()[header::RETRY_AFTER], "7");
+        assert_eq!(limit.stats().rejected, 1);
+
+        gate.notify_one();
+        assert_eq!(slow.await.unwrap().unwrap().status(), StatusCode::OK);
+        assert_eq!(limit.stats().in_flight, 0);
+
+        let resp = app.oneshot(req("/fast")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn queued_request_runs_when_slot_frees() {
+        let limit = ConcurrencyLimit::new(1).queue_timeout(Duration::from_secs(5));
+        let gate = Arc::new(Notify::new());
+        let app = app(limit.clone(), gate.clone());
+
+        let slow = tokio::spawn(app.clone().oneshot(req("/slow")));
+        wait_until(|| limit.stats().in_flight == 1).await;
+
+        let fast = tokio::spawn(app.oneshot(req("/fast")));
+        wait_until(|| limit.stats().queued == 1).await;
+
+        gate.notify_one();
+        assert_eq!(slow.await.unwrap().unwrap().status(), StatusCode::OK);
+        assert_eq!(fast.await.unwrap().unwrap().status(), StatusCode::OK);
+
+        let stats = limit.stats();
+        assert_eq!((stats.in_flight, stats.queued, stats.rejected), (0, 0, 0));
+    }
+
+    #[tokio::test]
+    async fn zero_timeout_rejects_immediately() {
+        let limit = ConcurrencyLimit::new(1).queue_timeout(Duration::ZERO);
+        let gate = Arc::new(Notify::new());
+        let app = app(limit.clone(), gate.clone());
+
+        let slow = tokio::spawn(app.clone().oneshot(req("/slow")));
+        wait_until(|| limit.stats().in_flight == 1).await;
+
+        let resp = app.oneshot(req("/fast")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(limit.stats().queued, 0);
+
+        gate.notify_one();
+        slow.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn from_env_with_reads_settings() {
+        let limit = ConcurrencyLimit::from_env_with(|k| match k {
+            "WEB_MAX_IN_FLIGHT" => Some("8".into()),
+            "WEB_QUEUE_TIMEOUT_MS" => Some("250".into()),
+            "WEB_RETRY_AFTER_SECS" => Some("bogus".into()),
+            _ => None,
+        });
+        assert_eq!(limit.stats().max_in_flight, 8);
+        assert_eq!(limit.queue_timeout, Duration::from_millis(250));
+        assert_eq!(limit.retry_after_secs, 1);
+
+        assert_eq!(ConcurrencyLimit::new(0).stats().max_in_flight, 1);
+    }
+}
@@ -0,0 +1,285 @@
+//! # CSRF Protection Layer
+//!
+//! [`CsrfLayer`] is a tower [`Layer`] that checks the CSRF token pair
+//! ([`validate_csrf`]: `X-CSRF-Token` header matching the `csrf` cookie) on
+//! every `POST`, `PUT`, `PATCH` and `DELETE` request, so new endpoints are
+//! protected without a guard call in the handler. Safe methods (`GET`,
+//! `HEAD`, `OPTIONS`, `TRACE`) pass through.
+//!
+//! Rejected requests get `403 Forbidden` and a `csrf.rejected` audit event
+//! (when an `Arc<dyn AuditStore>` extension is installed outside the layer).
+//!
+//! Routes opt out by path prefix with [`exempt`](CsrfLayer::exempt)
+//! (whole-segment match: `/webhooks` covers `/webhooks/stripe`, not
+//! `/webhooks-old`) — typically webhooks and other endpoints authenticated
+//! by signatures rather than cookies. [`exempt_bearer`](CsrfLayer::exempt_bearer)
+//! skips requests carrying `Authorization: Bearer`, which browsers never add
+//! on their own.
+//!
+//! # Example
+//! ```rust,no_run
+//! use axum::{routing::{get, post}, Router};
+//! use wzs_web::config::csrf::CsrfConfig;
+//! use wzs_web::web::csrf::csrf_handler;
+//! use wzs_web::web::middleware::csrf::CsrfLayer;
+//!
+//! let cfg = CsrfConfig::from_env();
+//! let app: Router = Router::new()
+//!     .route("/csrf", get(csrf_handler))
+//!     .route("/account/email", post(|| async { "ok" }))
+//!     .route("/webhooks/stripe", post(|| async { "ok" }))
+//!     .layer(CsrfLayer::new(cfg.clone()).exempt("/webhooks"))
+//!     .layer(axum::Extension(cfg));
+//! ```
+
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use axum::{
+    extract::Request,
+    http::{header, Method},
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::cookie::CookieJar;
+use futures_util::future::{BoxFuture, Either};
+use tower::{Layer, Service};
+
+use crate::audit::context::AuditContext;
+use crate::audit::event::AuditEvent;
+use crate::audit::store::AuditStore;
+use crate::auth::CurrentUser;
+use crate::config::csrf::CsrfConfig;
+use crate::error::app::AppError;
+use crate::web::csrf::validate_csrf;
+
+/// Tower layer enforcing CSRF tokens on unsafe methods.
+#[derive(Clone)]
+pub struct CsrfLayer {
+    cfg: CsrfConfig,
+    exempt: Arc<Vec<String>>,
+    exempt_bearer: bool,
+}
+
+impl CsrfLayer {
+    /// Protects every unsafe request with tokens signed under `cfg`.
+    pub fn new(cfg: CsrfConfig) -> Self {
+        Self {
+            cfg,
+            exempt: Arc::default(),
+            exempt_bearer: false,
+        }
+    }
+
+    /// Skips requests under the path `prefix`.
+    pub fn exempt(mut self, prefix: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.exempt).push(prefix.into());
+        self
+    }
+
+    /// Skips requests with an `Authorization: Bearer` header (default off).
+    pub fn exempt_bearer(mut self, exempt: bool) -> Self {
+        self.exempt_bearer = exempt;
+        self
+    }
+
+    /// Whether `req` must carry a valid token pair.
+    fn protects(&self, req: &Request) -> bool {
+        if matches!(
+            *req.method(),
+            Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+        ) {
+            return false;
+        }
+        let path = req.uri().path();
+        let exempt = self.exempt.iter().any(|prefix| {
+            let prefix = prefix.trim_end_matches('/');
+            path.strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        });
+        if exempt {
+            return false;
+        }
+        let bearer = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.len() > 7 && v[..7].eq_ignore_ascii_case("bearer "));
+        !(self.exempt_bearer && bearer)
+    }
+
+    /// `403`, after auditing the rejection.
+    async fn reject(audit: AuditContext, path: String) -> Response {
+        audit.emit(AuditEvent::csrf_rejected(path)).await;
+        AppError::forbidden("CSRF token missing or invalid").into_response()
+    }
+}
+
+impl<S> Layer<S> for CsrfLayer {
+    type Service = CsrfService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CsrfService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service produced by [`CsrfLayer`].
+#[derive(Clone)]
+pub struct CsrfService<S> {
+    inner: S,
+    layer: CsrfLayer,
+}
+
+impl<S> Service<Request> for CsrfService<S>
+where
+    S: Service<Request, Response = Response>,
+    S::Error: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Either<BoxFuture<'static, Result<Response, S::Error>>, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        if self.layer.protects(&req)
+            && !validate_csrf(
+                req.headers(),
+                &CookieJar::from_headers(req.headers()),
+                &self.layer.cfg,
+            )
+        {
+            let audit = AuditContext::new(
+                req.extensions().get::<Arc<dyn AuditStore>>().cloned(),
+                req.extensions()
+                    .get::<CurrentUser>()
+                    .map(|u| u.subject.clone()),
+            );
+            let path = req.uri().path().to_string();
+            return Either::Left(Box::pin(
+                async move { Ok(CsrfLayer::reject(audit, path).await) },
+            ));
+        }
+        Either::Right(self.inner.call(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum_extra::extract::cookie::SameSite;
+
+    use axum::{body::Body, routing::post, Extension, Router};
+    use std::sync::Mutex;
+    use tower::ServiceExt;
+
+    use crate::audit::store::AuditQuery;
+    use crate::config::csrf::derive_secret_from_string;
+    use crate::web::csrf::{generate_csrf_token, CSRF_COOKIE_NAME, CSRF_HEADER_NAME};
+
+    #[derive(Default)]
+    struct Events(Mutex<Vec<String>>);
+
+    impl AuditStore for Events {
+        fn record(&self, event: &AuditEvent) -> anyhow::Result<()> {
+            self.0.lock().unwrap().push(event.action.clone());
+            Ok(())
+        }
+
+        fn query(&self, _query: &AuditQuery) -> anyhow::Result<Vec<AuditEvent>> {
+            Ok(vec![])
+        }
+    }
+
+    fn cfg() -> CsrfConfig {
+        CsrfConfig {
+            secret: derive_secret_from_string("layer-secret"),
+            cookie_secure: true,
+            cookie_http_only: true,
+            rotation: Default::default(),
+            token_ttl: None,
+            allowed_origins: None,
+            cookie_same_site: SameSite::Lax,
+            accept_legacy_tokens: false,
+        }
+    }
+
+    fn app(layer: CsrfLayer, audit: Arc<dyn AuditStore>) -> Router {
+        Router::new()
+            .route(
+                "/account",
+                post(|| async { "saved" }).get(|| async { "page" }),
+            )
+            .route("/webhooks/stripe", post(|| async { "hook" }))
+            .route("/webhooks-old", post(|| async { "old" }))
+            .layer(layer)
+            .layer(Extension(audit))
+    }
+
+    async fn status(app: &Router, req: Request) -> StatusCode {
+        app.clone().oneshot(req).await.unwrap().status()
+    }
+
+    fn req(method: Method, uri: &str) -> axum::http::request::Builder {
+        Request::builder().method(method).uri(uri)
+    }
+
+    #[tokio::test]
+    async fn rejects_unsafe_methods_without_a_valid_pair() {
+        let events = Arc::new(Events::default());
+        let app = app(CsrfLayer::new(cfg()), events.clone());
+        let token = generate_csrf_token(&cfg());
+
+        let ok = req(Method::POST, "/account")
+            .header(header::COOKIE, format!("{CSRF_COOKIE_NAME}={token}"))
+            .header(CSRF_HEADER_NAME, &token)
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(status(&app, ok).await, StatusCode::OK);
+
+        let get = req(Method::GET, "/account").body(Body::empty()).unwrap();
+        assert_eq!(status(&app, get).await, StatusCode::OK);
+
+        let missing = req(Method::POST, "/account").body(Body::empty()).unwrap();
+        assert_eq!(status(&app, missing).await, StatusCode::FORBIDDEN);
+
+        let other = generate_csrf_token(&cfg());
+        let mismatched = req(Method::POST, "/account")
+            .header(header::COOKIE, format!("{CSRF_COOKIE_NAME}={token}"))
+            .header(CSRF_HEADER_NAME, other)
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(status(&app, mismatched).await, StatusCode::FORBIDDEN);
+
+        assert_eq!(events.0.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn exempt_prefixes_and_bearer_requests_pass() {
+        let layer = CsrfLayer::new(cfg())
+            .exempt("/webhooks/")
+            .exempt_bearer(true);
+        let app = app(layer, Arc::new(Events::default()));
+
+        let hook = req(Method::POST, "/webhooks/stripe")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(status(&app, hook).await, StatusCode::OK);
+
+        let old = req(Method::POST, "/webhooks-old")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(status(&app, old).await, StatusCode::FORBIDDEN);
+
+        let bearer = req(Method::POST, "/account")
+            .header(header::AUTHORIZATION, "Bearer abc")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(status(&app, bearer).await, StatusCode::OK);
+    }
+}
@@ -0,0 +1,439 @@
+//! # Degraded Mode During Database Outages
+//!
+//! [`degraded_mode`] watches the [`CircuitBreaker`] of a
+//! [`BreakerDb`](crate::db::breaker::BreakerDb) and, while it is open:
+//!
+//! - serves `GET` / `HEAD` requests under the configured
+//!   [`read_path`](DegradedMode::read_path) prefixes from the last good
+//!   response, with `Warning: 110 - "Response is Stale"` and an `Age` header
+//! - answers every other request that would need the database — writes
+//!   (`POST`, `PUT`, `PATCH`, `DELETE`) and reads without a stored copy —
+//!   with a standard `503 Service Unavailable` ([`error_response`], so HTML
+//!   for browsers and an `application/problem+json`
+//!   [`Problem`](crate::web::problem::Problem) for API clients) and a `Retry-After` header
+//!
+//! While the breaker is closed, successful (`200`, no `Set-Cookie`)
+//! responses of the read paths are stored as the fallback copy; bodies of
+//! unknown length or over [`max_body_bytes`](DegradedMode::max_body_bytes)
+//! are passed through without one. A request
+//! that fails with a `5xx` just as the breaker opens gets the same
+//! treatment, with `Warning: 111 - "Revalidation Failed"` on stale copies.
+//! Once the cooldown has passed, requests go through again so the database
+//! can be probed.
+//!
+//! Copies are keyed by method, path, query, `Cookie` and `Authorization`,
+//! so they are never shared across sessions. The crate has no shared cache
+//! subsystem, so they live in process memory (bounded by
+//! [`max_entries`](DegradedMode::max_entries)) and are dropped after
+//! [`max_stale`](DegradedMode::max_stale).
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//! use axum::{middleware::from_fn_with_state, routing::get, Router};
+//! use wzs_web::db::breaker::BreakerDb;
+//! use wzs_web::db::port::Db;
+//! use wzs_web::notification::smtp::circuit_breaker::CircuitBreaker;
+//! use wzs_web::web::middleware::degraded::{degraded_mode, DegradedMode};
+//!
+//! # fn build(mysql: Arc<dyn Db>) -> Router {
+//! let breaker = CircuitBreaker::new(5, Duration::from_secs(10));
+//! let db: Arc<dyn Db> = Arc::new(BreakerDb::new(mysql, breaker.clone()));
+//!
+//! let degraded = DegradedMode::new(breaker)
+//!     .read_path("/api/catalog")
+//!     .read_path("/api/news");
+//!
+//! Router::new()
+//!     .route("/api/news", get(|| async { "latest" }))
+//!     .layer(from_fn_with_state(degraded, degraded_mode))
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::Bytes,
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use sha2::{Digest, Sha256};
+
+use crate::notification::smtp::circuit_breaker::{BreakerState, CircuitBreaker};
+use crate::web::fallback::{error_response, ErrorPages};
+use crate::web::locale::Locale;
+use crate::web::middleware::micro_cache::body_size;
+use crate::web::problem::Problem;
+
+/// `Warning` value on stale copies served while the breaker is open.
+pub const WARNING_STALE: &str = "110 - \"Response is Stale\"";
+
+/// `Warning` value added when a live request failed and a stale copy replaced it.
+pub const WARNING_REVALIDATION_FAILED: &str = "111 - \"Revalidation Failed\"";
+
+type Key = [u8; 32];
+
+struct Stale {
+    at: Instant,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+/// Degraded-mode settings and the stored fallback copies.
+///
+/// Cloning is cheap; all clones share the same copies.
+#[derive(Clone)]
+pub struct DegradedMode {
+    breaker: CircuitBreaker,
+    entries: Arc<Mutex<HashMap<Key, Stale>>>,
+    read_paths: Vec<String>,
+    max_stale: Duration,
+    max_entries: usize,
+    max_body_bytes: usize,
+    retry_after_secs: u32,
+}
+
+impl DegradedMode {
+    /// Follows `breaker`; no read path serves stale copies until added.
+    pub fn new(breaker: CircuitBreaker) -> Self {
+        Self {
+            breaker,
+            entries: Arc::default(),
+            read_paths: Vec::new(),
+            max_stale: Duration::from_secs(3600),
+            max_entries: 1000,
+            max_body_bytes: 256 * 1024,
+            retry_after_secs: 30,
+        }
+    }
+
+    /// Serves stale copies for `GET` / `HEAD` requests under `prefix`.
+    pub fn read_path(mut self, prefix: impl Into<String>) -> Self {
+        self.read_paths.push(prefix.into());
+        self
+    }
+
+    /// Sets how long a copy may be served (default one hour).
+    pub fn max_stale(mut self, age: Duration) -> Self {
+        self.max_stale = age;
+        self
+    }
+
+    /// Sets the maximum number of stored copies (default 1000).
+    pub fn max_entries(mut self, n: usize) -> Self {
+        self.max_entries = n;
+        self
+    }
+
+    /// Sets the largest response body stored (default 256 KiB); larger
+    /// responses are served without storing a copy.
+    pub fn max_body_bytes(mut self, n: usize) -> Self {
+        self.max_body_bytes = n;
+        self
+    }
+
+    /// Sets the `Retry-After` value (seconds) sent with 503 responses.
+    pub fn retry_after(mut self, secs: u32) -> Self {
+        self.retry_after_secs = secs;
+        self
+    }
+
+    /// `true` while the breaker rejects database calls.
+    pub fn is_degraded(&self) -> bool {
+        self.breaker.state() == BreakerState::Open
+    }
+
+    /// Number of stored copies (including expired ones not yet evicted).
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// `true` if nothing is stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn is_read_path(&self, method: &Method, path: &str) -> bool {
+        matches!(*method, Method::GET | Method::HEAD)
+            && self.read_paths.iter().any(|p| path.starts_with(p.as_str()))
+    }
+
+    fn stale(&self, key: &Key, warnings: &[&'static str]) -> Option<Response> {
+        let entries = self.entries.lock().unwrap();
+        let stale = entries
+            .get(key)
+            .filter(|s| s.at.elapsed() < self.max_stale)?;
+        let mut res = (stale.status, stale.body.clone()).into_response();
+        *res.headers_mut() = stale.headers.clone();
+        let headers = res.headers_mut();
+        headers.insert(header::AGE, HeaderValue::from(stale.at.elapsed().as_secs()));
+        for warning in warnings {
+            headers.append(header::WARNING, HeaderValue::from_static(warning));
+        }
+        Some(res)
+    }
+
+    fn store(&self, key: Key, stale: Stale) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            entries.retain(|_, s| s.at.elapsed() < self.max_stale);
+        }
+        if entries.len() < self.max_entries || entries.contains_key(&key) {
+            entries.insert(key, stale);
+        }
+    }
+
+    fn unavailable(
+        &self,
+        pages: Option<&ErrorPages>,
+        headers: &HeaderMap,
+        locale: &Locale,
+    ) -> Response {
+        let mut res = error_response(
+            pages,
+            headers,
+            locale,
+            StatusCode::SERVICE_UNAVAILABLE,
+            Some("database unavailable"),
+        );
+        res.headers_mut().insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(self.retry_after_secs),
+        );
+        res
+    }
+}
+
+fn cache_key(req: &Request) -> Key {
+    let mut h = Sha256::new();
+    let mut part = |bytes: &[u8]| {
+        h.update((bytes.len() as u64).to_be_bytes());
+        h.update(bytes);
+    };
+
+    part(req.method().as_str().as_bytes());
+    part(req.uri().path().as_bytes());
+    part(req.uri().query().unwrap_or_default().as_bytes());
+    for name in [header::COOKIE, header::AUTHORIZATION] {
+        for v in req.headers().get_all(name) {
+            part(v.as_bytes());
+        }
+    }
+
+    h.finalize().into()
+}
+
+/// Middleware applying a [`DegradedMode`].
+///
+/// Mount with `axum::middleware::from_fn_with_state(mode, degraded_mode)`.
+pub async fn degraded_mode(
+    State(mode): State<DegradedMode>,
+    locale: Locale,
+    pages: Option<Extension<Arc<ErrorPages>>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let pages = pages.map(|Extension(p)| p);
+    let pages = pages.as_deref();
+
+    let read = mode.is_read_path(req.method(), req.uri().path());
+    let safe = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    let key = read.then(|| cache_key(&req));
+
+    if mode.is_degraded() {
+        if let Some(res) = key.as_ref().and_then(|k| mode.stale(k, &[WARNING_STALE])) {
+            return res;
+        }
+        if read || !safe {
+            return mode.unavailable(pages, req.headers(), &locale);
+        }
+        return next.run(req).await;
+    }
+
+    let headers = req.headers().clone();
+    let res = next.run(req).await;
+
+    if res.status().is_server_error() && mode.is_degraded() {
+        let warnings = [WARNING_STALE, WARNING_REVALIDATION_FAILED];
+        if let Some(stale) = key.as_ref().and_then(|k| mode.stale(k, &warnings)) {
+            return stale;
+        }
+        return mode.unavailable(pages, &headers, &locale);
+    }
+
+    let Some(key) = key else {
+        return res;
+    };
+    if res.status() != StatusCode::OK || res.headers().contains_key(header::SET_COOKIE) {
+        return res;
+    }
+
+    let (parts, body) = res.into_parts();
+    let fits = body_size(&parts.headers, &body).is_some_and(|n| n <= mode.max_body_bytes as u64);
+    if !fits {
+        return Response::from_parts(parts, body);
+    }
+    let Ok(bytes) = axum::body::to_bytes(body, mode.max_body_bytes).await else {
+        return Problem::internal().into_response();
+    };
+    mode.store(
+        key,
+        Stale {
+            at: Instant::now(),
+            status: parts.status,
+            headers: parts.headers.clone(),
+            body: bytes.clone(),
+        },
+    );
+    Response::from_parts(parts, bytes.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{
+        body::Body,
+        middleware::from_fn_with_state,
+        routing::{get, post},
+        Router,
+    };
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    fn app(mode: DegradedMode, down: Arc<AtomicBool>) -> Router {
+        let breaker = mode.breaker.clone();
+        let calls = Arc::new(AtomicUsize::new(0));
+        Router::new()
+            .route(
+                "/api/news",
+                get(move || {
+                    let (down, breaker) = (down.clone(), breaker.clone());
+                    let n = calls.fetch_add(1, Ordering::SeqCst);
+                    async move {
+                        if down.load(Ordering::SeqCst) {
+                            breaker.on_failure();
+                            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                        }
+                        format!("news {n}").into_response()
+                    }
+                }),
+            )
+            .route("/api/orders", post(|| async { "created" }))
+            .route("/api/other", get(|| async { "other" }))
+            .route("/api/big", get(|| async { "x".repeat(64) }))
+            .route(
+                "/api/stream",
+                get(|| async {
+                    let chunks = ["a", "b"].map(|c| Ok::<_, std::io::Error>(Bytes::from(c)));
+                    Body::from_stream(futures_util::stream::iter(chunks))
+                }),
+            )
+            .layer(from_fn_with_state(mode, degraded_mode))
+    }
+
+    async fn send(app: &Router, req: Request) -> (StatusCode, Vec<String>, String) {
+        let res = app.clone().oneshot(req).await.unwrap();
+        let status = res.status();
+        let warnings = res
+            .headers()
+            .get_all(header::WARNING)
+            .iter()
+            .map(|v| v.to_str().unwrap().to_string())
+            .collect();
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, warnings, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn get_req(uri: &str) -> Request {
+        Request::get(uri).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn serves_stale_reads_and_rejects_writes_while_open() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+        let down = Arc::new(AtomicBool::new(false));
+        let mode = DegradedMode::new(breaker.clone()).read_path("/api/news");
+        let app = app(mode.clone(), down.clone());
+
+        assert_eq!(send(&app, get_req("/api/news")).await.2, "news 0");
+        assert_eq!(mode.len(), 1);
+
+        // The live request fails and opens the breaker: stale copy instead.
+        down.store(true, Ordering::SeqCst);
+        let (status, warnings, body) = send(&app, get_req("/api/news")).await;
+        assert_eq!((status, body.as_str()), (StatusCode::OK, "news 0"));
+        assert_eq!(warnings, [WARNING_STALE, WARNING_REVALIDATION_FAILED]);
+        assert!(mode.is_degraded());
+
+        let (status, warnings, body) = send(&app, get_req("/api/news")).await;
+        assert_eq!((status, body.as_str()), (StatusCode::OK, "news 0"));
+        assert_eq!(warnings, [WARNING_STALE]);
+
+        let res = app
+            .clone()
+            .oneshot(Request::post("/api/orders").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(res.headers()[header::RETRY_AFTER], "30");
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains(r#""title":"Service Unavailable""#));
+
+        // Not a read path: passes through; other sessions have no copy.
+        assert_eq!(send(&app, get_req("/api/other")).await.2, "other");
+        let req = Request::get("/api/news")
+            .header(header::COOKIE, "s=2")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(send(&app, req).await.0, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn large_and_unsized_responses_pass_through_unstored() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+        let mode = DegradedMode::new(breaker)
+            .read_path("/api")
+            .max_body_bytes(16);
+        let app = app(mode.clone(), Arc::new(AtomicBool::new(false)));
+
+        let (status, _, body) = send(&app, get_req("/api/big")).await;
+        assert_eq!((status, body.len()), (StatusCode::OK, 64));
+        let (status, _, body) = send(&app, get_req("/api/stream")).await;
+        assert_eq!((status, body.as_str()), (StatusCode::OK, "ab"));
+        assert!(mode.is_empty());
+
+        assert_eq!(send(&app, get_req("/api/other")).await.2, "other");
+        assert_eq!(mode.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn passes_through_once_the_breaker_closes() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        let mode = DegradedMode::new(breaker.clone()).read_path("/api/news");
+        let app = app(mode.clone(), Arc::new(AtomicBool::new(false)));
+
+        breaker.on_failure();
+        assert!(!mode.is_degraded(), "cooldown elapsed: half-open");
+        let (status, warnings, _) = send(&app, get_req("/api/news")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(warnings.is_empty());
+
+        let res = app
+            .oneshot(Request::post("/api/orders").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
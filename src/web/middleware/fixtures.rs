@@ -0,0 +1,466 @@
+//! # Request Fixture Recording
+//!
+//! [`record_fixtures`] writes each request/response pair to a JSON file, so
+//! API contract fixtures for frontend tests come from real traffic against
+//! a development server instead of being written by hand. The files replay
+//! with [`testkit::replay`](crate::testkit::replay).
+//!
+//! This is a development tool: mount it only when
+//! [`FixtureRecorder::from_env`] returns a recorder (`RECORD_FIXTURES_DIR`
+//! set), never in production.
+//!
+//! Fixtures are sanitized before they are written:
+//!
+//! - headers in the redaction list (`Cookie`, `Set-Cookie`,
+//!   `Authorization`, `X-CSRF-Token`, … plus
+//!   [`redact_header`](FixtureRecorder::redact_header)) keep their name but
+//!   get the value `[REDACTED]`
+//! - JSON body fields whose name contains a redacted word (`password`,
+//!   `token`, `secret`, plus [`redact_field`](FixtureRecorder::redact_field);
+//!   case-insensitive) are replaced the same way, at any depth
+//!
+//! One file per distinct request, named
+//! `<METHOD>_<path>_<hash>.json` where the hash covers the query and request
+//! body, so recording the same call again overwrites its fixture. The file
+//! format is [`Fixture`]:
+//!
+//! ```json
+//! {
+//!   "request": {
+//!     "method": "POST",
+//!     "path": "/api/login",
+//!     "query": null,
+//!     "headers": { "content-type": "application/json", "cookie": "[REDACTED]" },
+//!     "body": { "kind": "json", "value": { "email": "a@example.com", "password": "[REDACTED]" } }
+//!   },
+//!   "response": {
+//!     "status": 200,
+//!     "headers": { "content-type": "application/json" },
+//!     "body": { "kind": "json", "value": { "ok": true } }
+//!   }
+//! }
+//! ```
+//!
+//! # Example
+//! ```rust,no_run
+//! use axum::{middleware::from_fn_with_state, routing::get, Router};
+//! use wzs_web::web::middleware::fixtures::{record_fixtures, FixtureRecorder};
+//!
+//! let mut app: Router = Router::new().route("/api/me", get(|| async { "me" }));
+//! if let Some(recorder) = FixtureRecorder::from_env() {
+//!     app = app.layer(from_fn_with_state(recorder.path("/api"), record_fixtures));
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::web::problem::Problem;
+
+/// Environment variable naming the fixture directory.
+pub const RECORD_FIXTURES_DIR_ENV: &str = "RECORD_FIXTURES_DIR";
+
+/// Replacement for redacted header values and JSON fields.
+pub const REDACTED: &str = "[REDACTED]";
+
+pub(crate) const DEFAULT_REDACTED_HEADERS: [&str; 6] = [
+    "authorization",
+    "cookie",
+    "proxy-authorization",
+    "set-cookie",
+    "x-api-key",
+    "x-csrf-token",
+];
+
+const DEFAULT_REDACTED_FIELDS: [&str; 3] = ["password", "secret", "token"];
+
+/// A recorded request/response pair.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub request: FixtureRequest,
+    pub response: FixtureResponse,
+}
+
+/// Recorded request.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FixtureRequest {
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    /// Lower-case header names; repeated headers joined with `, `.
+    pub headers: BTreeMap<String, String>,
+    pub body: FixtureBody,
+}
+
+/// Recorded response.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FixtureResponse {
+    pub status: u16,
+    /// Lower-case header names; repeated headers joined with `, `.
+    pub headers: BTreeMap<String, String>,
+    pub body: FixtureBody,
+}
+
+/// A recorded body.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "lowercase")]
+pub enum FixtureBody {
+    Empty,
+    /// Bodies with a JSON content type that parse as JSON.
+    Json(Value),
+    /// Other UTF-8 bodies.
+    Text(String),
+    /// Binary bodies, Base64 encoded.
+    Base64(String),
+}
+
+impl FixtureBody {
+    /// Raw bytes of the body.
+    pub fn to_bytes(&self) -> Bytes {
+        match self {
+            Self::Empty => Bytes::new(),
+            Self::Json(v) => Bytes::from(v.to_string()),
+            Self::Text(s) => Bytes::from(s.clone()),
+            Self::Base64(s) => Bytes::from(STANDARD.decode(s).unwrap_or_default()),
+        }
+    }
+}
+
+impl Fixture {
+    /// Reads a fixture file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = fs::read(path).with_context(|| format!("read {}", path.display()))?;
+        serde_json::from_slice(&json).with_context(|| format!("parse {}", path.display()))
+    }
+
+    /// File name the recorder uses for this fixture.
+    pub fn file_name(&self) -> String {
+        let req = &self.request;
+        let mut h = Sha256::new();
+        h.update(req.query.as_deref().unwrap_or_default());
+        h.update([0]);
+        h.update(req.body.to_bytes());
+        let hash: String = h.finalize()[..4]
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        let slug: String = req
+            .path
+            .trim_matches('/')
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        let slug = if slug.is_empty() { "root".into() } else { slug };
+        format!("{}_{slug}_{hash}.json", req.method)
+    }
+}
+
+/// Recording settings: target directory and redaction lists.
+///
+/// Cloning is cheap.
+#[derive(Clone)]
+pub struct FixtureRecorder {
+    inner: Arc<Settings>,
+}
+
+#[derive(Clone)]
+struct Settings {
+    dir: PathBuf,
+    paths: Vec<String>,
+    headers: Vec<String>,
+    fields: Vec<String>,
+    max_body_bytes: usize,
+}
+
+impl FixtureRecorder {
+    /// Records into `dir` (created on first write).
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            inner: Arc::new(Settings {
+                dir: dir.into(),
+                paths: Vec::new(),
+                headers: DEFAULT_REDACTED_HEADERS.map(String::from).to_vec(),
+                fields: DEFAULT_REDACTED_FIELDS.map(String::from).to_vec(),
+                max_body_bytes: 1024 * 1024,
+            }),
+        }
+    }
+
+    /// A recorder for `RECORD_FIXTURES_DIR`, or `None` when it is unset or empty.
+    pub fn from_env() -> Option<Self> {
+        std::env::var(RECORD_FIXTURES_DIR_ENV)
+            .ok()
+            .filter(|d| !d.trim().is_empty())
+            .map(Self::new)
+    }
+
+    /// Only records requests under the path `prefix` (default: all).
+    pub fn path(mut self, prefix: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.inner).paths.push(prefix.into());
+        self
+    }
+
+    /// Also redacts the header `name`.
+    pub fn redact_header(mut self, name: impl Into<String>) -> Self {
+        let name = name.into().to_ascii_lowercase();
+        Arc::make_mut(&mut self.inner).headers.push(name);
+        self
+    }
+
+    /// Also redacts JSON fields whose name contains `word`.
+    pub fn redact_field(mut self, word: impl Into<String>) -> Self {
+        let word = word.into().to_ascii_lowercase();
+        Arc::make_mut(&mut self.inner).fields.push(word);
+        self
+    }
+
+    /// Sets the largest body recorded (default 1 MiB); larger exchanges
+    /// pass through unrecorded.
+    pub fn max_body_bytes(mut self, n: usize) -> Self {
+        Arc::make_mut(&mut self.inner).max_body_bytes = n;
+        self
+    }
+
+    /// The fixture directory.
+    pub fn dir(&self) -> &Path {
+        &self.inner.dir
+    }
+
+    fn records(&self, path: &str) -> bool {
+        self.inner.paths.is_empty()
+            || self
+                .inner
+                .paths
+                .iter()
+                .any(|p| path.starts_with(p.as_str()))
+    }
+
+    fn headers(&self, headers: &HeaderMap) -> BTreeMap<String, String> {
+        let mut out: BTreeMap<String, String> = BTreeMap::new();
+        for (name, value) in headers {
+            let name = name.as_str().to_string();
+            let value = if self.inner.headers.contains(&name) {
+                REDACTED.to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            out.entry(name)
+                .and_modify(|v| {
+                    if v != REDACTED {
+                        v.push_str(", ");
+                        v.push_str(&value);
+                    }
+                })
+                .or_insert(value);
+        }
+        out
+    }
+
+    fn body(&self, headers: &HeaderMap, bytes: &[u8]) -> FixtureBody {
+        if bytes.is_empty() {
+            return FixtureBody::Empty;
+        }
+        let json = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.contains("json"));
+        let parsed = json
+            .then(|| serde_json::from_slice::<Value>(bytes).ok())
+            .flatten();
+        if let Some(mut value) = parsed {
+            self.redact(&mut value);
+            return FixtureBody::Json(value);
+        }
+        match std::str::from_utf8(bytes) {
+            Ok(text) => FixtureBody::Text(text.to_string()),
+            Err(_) => FixtureBody::Base64(STANDARD.encode(bytes)),
+        }
+    }
+
+    fn redact(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, v) in map.iter_mut() {
+                    let key = key.to_ascii_lowercase();
+                    if self.inner.fields.iter().any(|w| key.contains(w.as_str())) {
+                        *v = Value::String(REDACTED.into());
+                    } else {
+                        self.redact(v);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|v| self.redact(v)),
+            _ => {}
+        }
+    }
+
+    fn write(&self, fixture: &Fixture) -> Result<PathBuf> {
+        let dir = &self.inner.dir;
+        fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+        let path = dir.join(fixture.file_name());
+        fs::write(&path, serde_json::to_vec_pretty(fixture)?)
+            .with_context(|| format!("write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+/// Middleware recording request/response pairs with a [`FixtureRecorder`].
+///
+/// Mount with `axum::middleware::from_fn_with_state(recorder, record_fixtures)`.
+pub async fn record_fixtures(
+    State(recorder): State<FixtureRecorder>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if !recorder.records(req.uri().path()) {
+        return next.run(req).await;
+    }
+
+    let (parts, body) = req.into_parts();
+    let Ok(req_bytes) = axum::body::to_bytes(body, recorder.inner.max_body_bytes).await else {
+        return Problem::new(StatusCode::PAYLOAD_TOO_LARGE).into_response();
+    };
+    let request = FixtureRequest {
+        method: parts.method.to_string(),
+        path: parts.uri.path().to_string(),
+        query: parts.uri.query().map(str::to_string),
+        headers: recorder.headers(&parts.headers),
+        body: recorder.body(&parts.headers, &req_bytes),
+    };
+
+    let res = next
+        .run(Request::from_parts(parts, Body::from(req_bytes)))
+        .await;
+    let (parts, body) = res.into_parts();
+    let Ok(res_bytes) = axum::body::to_bytes(body, recorder.inner.max_body_bytes).await else {
+        warn!(path = %request.path, "fixture not recorded: response body too large");
+        return Problem::internal().into_response();
+    };
+    let fixture = Fixture {
+        request,
+        response: FixtureResponse {
+            status: parts.status.as_u16(),
+            headers: recorder.headers(&parts.headers),
+            body: recorder.body(&parts.headers, &res_bytes),
+        },
+    };
+
+    let writer = recorder.clone();
+    let written = tokio::task::spawn_blocking(move || writer.write(&fixture)).await;
+    match written {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => warn!("fixture not recorded: {e:#}"),
+        Err(e) => warn!("fixture not recorded: {e}"),
+    }
+
+    Response::from_parts(parts, Body::from(res_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{middleware::from_fn_with_state, routing::post, Json, Router};
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("wzs-fixtures-{name}-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn records_sanitized_pairs() {
+        let dir = temp_dir("record");
+        let recorder = FixtureRecorder::new(&dir).path("/api").redact_field("card");
+        let app: Router = Router::new()
+            .route(
+                "/api/login",
+                post(|Json(body): Json<Value>| async move {
+                    (
+                        [(header::SET_COOKIE, "session=abc")],
+                        Json(json!({ "user": body["email"], "accessToken": "jwt" })),
+                    )
+                }),
+            )
+            .layer(from_fn_with_state(recorder, record_fixtures));
+
+        let req = Request::post("/api/login?next=%2F")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::COOKIE, "csrf=1")
+            .body(Body::from(
+                json!({ "email": "a@example.com", "password": "pw", "cardNumber": "4242" })
+                    .to_string(),
+            ))
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()[header::SET_COOKIE],
+            "session=abc",
+            "response untouched"
+        );
+
+        let files: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        assert_eq!(files.len(), 1);
+        let name = files[0].file_name().unwrap().to_str().unwrap().to_string();
+        assert!(name.starts_with("POST_api-login_"), "{name}");
+
+        let fixture = Fixture::load(&files[0]).unwrap();
+        assert_eq!(fixture.file_name(), name);
+        assert_eq!(fixture.request.query.as_deref(), Some("next=%2F"));
+        assert_eq!(fixture.request.headers["cookie"], REDACTED);
+        assert_eq!(
+            fixture.request.body,
+            FixtureBody::Json(json!({
+                "email": "a@example.com",
+                "password": REDACTED,
+                "cardNumber": REDACTED,
+            }))
+        );
+        assert_eq!(fixture.response.status, 200);
+        assert_eq!(fixture.response.headers["set-cookie"], REDACTED);
+        assert_eq!(
+            fixture.response.body,
+            FixtureBody::Json(json!({ "user": "a@example.com", "accessToken": REDACTED }))
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn bodies_keep_text_and_binary() {
+        let recorder = FixtureRecorder::new("unused");
+        let headers = HeaderMap::new();
+        assert_eq!(recorder.body(&headers, b""), FixtureBody::Empty);
+        assert_eq!(
+            recorder.body(&headers, b"hi"),
+            FixtureBody::Text("hi".into())
+        );
+        let binary = recorder.body(&headers, &[0xff, 0x00]);
+        assert_eq!(binary, FixtureBody::Base64("/wA=".into()));
+        assert_eq!(binary.to_bytes().as_ref(), &[0xff, 0x00]);
+    }
+}
@@ -0,0 +1,174 @@
+//! # HTTP Metrics Middleware
+//!
+//! In-process request counters rendered in the Prometheus text format.
+//!
+//! [`HttpMetrics`] tracks:
+//!
+//! - `http_requests_total{class="2xx"|"3xx"|"4xx"|"5xx"}`
+//! - `http_request_duration_seconds_sum` / `_count`
+//!
+//! No exporter crate is required; mount [`HttpMetrics::render`] on a route
+//! and point the scraper at it. Background job series are included once a
+//! [`JobMetrics`] is attached with [`jobs`](HttpMetrics::jobs).
+//!
+//! # Example
+//! ```rust,no_run
+//! use axum::{middleware::from_fn_with_state, routing::get, Router};
+//! use wzs_web::web::middleware::metrics::{track_metrics, HttpMetrics};
+//!
+//! let metrics = HttpMetrics::new();
+//! let m = metrics.clone();
+//!
+//! let app: Router = Router::new()
+//!     .route("/", get(|| async { "ok" }))
+//!     .route("/metrics", get(move || async move { m.render() }))
+//!     .layer(from_fn_with_state(metrics, track_metrics));
+//! ```
+
+use std::fmt::Write;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use std::time::Instant;
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::telemetry::jobs::JobMetrics;
+
+const CLASSES: [&str; 4] = ["2xx", "3xx", "4xx", "5xx"];
+
+#[derive(Default)]
+struct Inner {
+    by_class: [AtomicU64; 4],
+    duration_micros: AtomicU64,
+    count: AtomicU64,
+}
+
+/// Shared HTTP counters.
+///
+/// Cloning is cheap; all clones share the same counters.
+#[derive(Clone, Default)]
+pub struct HttpMetrics {
+    inner: Arc<Inner>,
+    jobs: Option<JobMetrics>,
+}
+
+impl HttpMetrics {
+    /// Creates zeroed counters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also renders the series of `jobs`.
+    pub fn jobs(mut self, jobs: JobMetrics) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    /// Records one finished request.
+    pub fn record(&self, status: u16, elapsed_micros: u64) {
+        let idx = match status {
+            200..=299 => Some(0),
+            300..=399 => Some(1),
+            400..=499 => Some(2),
+            500..=599 => Some(3),
+            _ => None,
+        };
+        if let Some(i) = idx {
+            self.inner.by_class[i].fetch_add(1, Ordering::Relaxed);
+        }
+        self.inner
+            .duration_micros
+            .fetch_add(elapsed_micros, Ordering::Relaxed);
+        self.inner.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Total number of recorded requests.
+    pub fn total(&self) -> u64 {
+        self.inner.count.load(Ordering::Relaxed)
+    }
+
+    /// Renders the counters in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# TYPE http_requests_total counter\n");
+        for (class, counter) in CLASSES.iter().zip(&self.inner.by_class) {
+            let _ = writeln!(
+                out,
+                "http_requests_total{{class=\"{class}\"}} {}",
+                counter.load(Ordering::Relaxed)
+            );
+        }
+
+        let secs = self.inner.duration_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        out.push_str("# TYPE http_request_duration_seconds summary\n");
+        let _ = writeln!(out, "http_request_duration_seconds_sum {secs}");
+        let _ = writeln!(out, "http_request_duration_seconds_count {}", self.total());
+        if let Some(jobs) = &self.jobs {
+            out.push_str(&jobs.render());
+        }
+        out
+    }
+}
+
+/// Middleware recording every request in [`HttpMetrics`].
+///
+/// Mount with `axum::middleware::from_fn_with_state(metrics, track_metrics)`.
+pub async fn track_metrics(
+    State(metrics): State<HttpMetrics>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let started = Instant::now();
+    let res = next.run(req).await;
+    metrics.record(res.status().as_u16(), started.elapsed().as_micros() as u64);
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{
+        body::Body, http::StatusCode, middleware::from_fn_with_state, routing::get, Router,
+    };
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn counts_requests_by_status_class() {
+        let metrics = HttpMetrics::new();
+        let app = Router::new()
+            .route("/ok", get(|| async { "ok" }))
+            .route("/fail", get(|| async { StatusCode::INTERNAL_SERVER_ERROR }))
+            .layer(from_fn_with_state(metrics.clone(), track_metrics));
+
+        for uri in ["/ok", "/ok", "/missing", "/fail"] {
+            let req = Request::get(uri).body(Body::empty()).unwrap();
+            app.clone().oneshot(req).await.unwrap();
+        }
+
+        let text = metrics.render();
+        assert_eq!(metrics.total(), 4);
+        assert!(text.contains("http_requests_total{class=\"2xx\"} 2\n"));
+        assert!(text.contains("http_requests_total{class=\"4xx\"} 1\n"));
+        assert!(text.contains("http_requests_total{class=\"5xx\"} 1\n"));
+        assert!(text.contains("http_request_duration_seconds_count 4\n"));
+    }
+
+    #[test]
+    fn renders_attached_job_metrics() {
+        let jobs = JobMetrics::new();
+        let metrics = HttpMetrics::new().jobs(jobs.clone());
+        assert!(!metrics.render().contains("job_runs_total"));
+
+        jobs.record("upload_gc", false, std::time::Duration::from_millis(5));
+        assert!(metrics
+            .render()
+            .contains("job_runs_total{job=\"upload_gc\",outcome=\"failure\"} 1\n"));
+    }
+}
@@ -0,0 +1,481 @@
+//! # Request Deduplication Micro-Cache
+//!
+//! Opt-in, short-TTL (typically 1–5 s) response cache that absorbs bursts of
+//! identical reads, such as every open SPA tab refreshing after a deploy.
+//!
+//! Cached requests:
+//!
+//! - `GET` / `HEAD` requests
+//! - `POST` to the GraphQL path (default `/graphql`) whose selected operation
+//!   is a `query` (mutations and subscriptions always pass through)
+//!
+//! The key is a SHA-256 over method, path, query string, the GraphQL
+//! `query` / `operationName` / `variables`, and the caller's *subject*
+//! (`Cookie` and `Authorization` headers), so responses are never shared
+//! across sessions.
+//!
+//! GraphQL bodies are only buffered when they are `application/json` with a
+//! known length within `max_body_bytes`; anything else (e.g. multipart
+//! uploads) streams to the handler uncached.
+//!
+//! Only `200 OK` responses without `Set-Cookie` and with a known length
+//! within `max_body_bytes` are stored; others stream through unchanged. Concurrent misses for the same key are
+//! coalesced: one request runs, the others wait and reuse its response.
+//! Requests with `Cache-Control: no-cache` bypass the cache.
+//!
+//! The crate has no shared cache subsystem, so entries live in process
+//! memory (bounded by `max_entries`).
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use axum::{middleware::from_fn_with_state, routing::get, Router};
+//! use wzs_web::web::middleware::micro_cache::{micro_cache, MicroCache};
+//!
+//! let cache = MicroCache::new(Duration::from_secs(2));
+//!
+//! let app: Router = Router::new()
+//!     .route("/api/news", get(|| async { "latest" }))
+//!     .layer(from_fn_with_state(cache, micro_cache));
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_graphql::parser::{parse_query, types::OperationType};
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::web::problem::Problem;
+
+/// Response header reporting `hit` or `miss`.
+pub const CACHE_STATUS_HEADER: &str = "x-micro-cache";
+
+type Key = [u8; 32];
+
+#[derive(Clone)]
+struct Cached {
+    at: Instant,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: Mutex<HashMap<Key, Cached>>,
+    in_flight: Mutex<HashMap<Key, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+/// Shared micro-cache.
+///
+/// Cloning is cheap; all clones share the same entries.
+#[derive(Clone)]
+pub struct MicroCache {
+    inner: Arc<Inner>,
+    ttl: Duration,
+    max_entries: usize,
+    max_body_bytes: usize,
+    graphql_path: String,
+}
+
+impl MicroCache {
+    /// Creates a cache with the given TTL.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            inner: Arc::default(),
+            ttl,
+            max_entries: 1000,
+            max_body_bytes: 256 * 1024,
+            graphql_path: "/graphql".into(),
+        }
+    }
+
+    /// Sets the maximum number of entries (default 1000).
+    pub fn max_entries(mut self, n: usize) -> Self {
+        self.max_entries = n;
+        self
+    }
+
+    /// Sets the largest cacheable request or response body (default 256 KiB).
+    pub fn max_body_bytes(mut self, n: usize) -> Self {
+        self.max_body_bytes = n;
+        self
+    }
+
+    /// Sets the path whose `POST` queries are cacheable (default `/graphql`).
+    pub fn graphql_path(mut self, path: impl Into<String>) -> Self {
+        self.graphql_path = path.into();
+        self
+    }
+
+    /// Number of stored entries (including expired ones not yet evicted).
+    pub fn len(&self) -> usize {
+        self.inner.entries.lock().unwrap().len()
+    }
+
+    /// `true` if nothing is stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lookup(&self, key: &Key) -> Option<Cached> {
+        self.inner
+            .entries
+            .lock()
+            .unwrap()
+            .get(key)
+            .filter(|c| c.at.elapsed() < self.ttl)
+            .cloned()
+    }
+
+    fn store(&self, key: Key, cached: Cached) {
+        let mut entries = self.inner.entries.lock().unwrap();
+        if entries.len() >= self.max_entries {
+            entries.retain(|_, c| c.at.elapsed() < self.ttl);
+        }
+        if entries.len() < self.max_entries {
+            entries.insert(key, cached);
+        }
+    }
+
+    fn flight_lock(&self, key: &Key) -> Arc<tokio::sync::Mutex<()>> {
+        self.inner
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(*key)
+            .or_default()
+            .clone()
+    }
+
+    fn end_flight(&self, key: &Key) {
+        self.inner.in_flight.lock().unwrap().remove(key);
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphqlBody {
+    query: String,
+    #[serde(default)]
+    operation_name: Option<String>,
+    #[serde(default)]
+    variables: Option<serde_json::Value>,
+}
+
+/// `true` if the operation that would run is a `query`.
+fn is_graphql_query(body: &GraphqlBody) -> bool {
+    let Ok(doc) = parse_query(&body.query) else {
+        return false;
+    };
+    let mut ops = doc.operations.iter();
+    let selected = match body.operation_name.as_deref() {
+        Some(wanted) => ops.find(|(name, _)| name.is_some_and(|n| n.as_str() == wanted)),
+        None => ops.next().filter(|_| ops.next().is_none()),
+    };
+    selected.is_some_and(|(_, op)| op.node.ty == OperationType::Query)
+}
+
+fn cache_key(req_parts: &axum::http::request::Parts, graphql: Option<&GraphqlBody>) -> Key {
+    let mut h = Sha256::new();
+    let mut part = |bytes: &[u8]| {
+        h.update((bytes.len() as u64).to_be_bytes());
+        h.update(bytes);
+    };
+
+    part(req_parts.method.as_str().as_bytes());
+    part(req_parts.uri.path().as_bytes());
+    part(req_parts.uri.query().unwrap_or_default().as_bytes());
+    for name in [header::COOKIE, header::AUTHORIZATION] {
+        for v in req_parts.headers.get_all(name) {
+            part(v.as_bytes());
+        }
+    }
+    if let Some(g) = graphql {
+        part(g.query.as_bytes());
+        part(g.operation_name.as_deref().unwrap_or_default().as_bytes());
+        let vars = g
+            .variables
+            .as_ref()
+            .map(|v| v.to_string())
+            .unwrap_or_default();
+        part(vars.as_bytes());
+    }
+
+    h.finalize().into()
+}
+
+fn bypass(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CACHE_CONTROL)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("no-cache") || v.contains("no-store"))
+}
+
+/// Body size from `Content-Length`, or the exact size hint of the body.
+pub(crate) fn body_size(headers: &HeaderMap, body: &Body) -> Option<u64> {
+    headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .or_else(|| body.size_hint().exact())
+}
+
+/// Whether the request body is declared as `application/json`.
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .is_some_and(|v| v.trim().eq_ignore_ascii_case("application/json"))
+}
+
+fn respond(cached: Cached, status: &'static str) -> Response {
+    let mut res = (cached.status, cached.body).into_response();
+    *res.headers_mut() = cached.headers;
+    res.headers_mut()
+        .insert(CACHE_STATUS_HEADER, HeaderValue::from_static(status));
+    res
+}
+
+/// Middleware serving repeated reads from a [`MicroCache`].
+///
+/// Mount with `axum::middleware::from_fn_with_state(cache, micro_cache)`.
+pub async fn micro_cache(State(cache): State<MicroCache>, req: Request, next: Next) -> Response {
+    if bypass(req.headers()) {
+        return next.run(req).await;
+    }
+
+    let (parts, body) = req.into_parts();
+    let (key, body) = match parts.method {
+        Method::GET | Method::HEAD => (cache_key(&parts, None), body),
+        Method::POST if parts.uri.path() == cache.graphql_path => {
+            // Only small JSON bodies are buffered; uploads (multipart) and
+            // large queries stream to the handler uncached.
+            let fits =
+                body_size(&parts.headers, &body).is_some_and(|n| n <= cache.max_body_bytes as u64);
+            if !is_json(&parts.headers) || !fits {
+                return next.run(Request::from_parts(parts, body)).await;
+            }
+            let Ok(bytes) = axum::body::to_bytes(body, cache.max_body_bytes).await else {
+                return Problem::new(StatusCode::PAYLOAD_TOO_LARGE).into_response();
+            };
+            let graphql = serde_json::from_slice::<GraphqlBody>(&bytes)
+                .ok()
+                .filter(is_graphql_query);
+            let Some(graphql) = graphql else {
+                return next
+                    .run(Request::from_parts(parts, Body::from(bytes)))
+                    .await;
+            };
+            (cache_key(&parts, Some(&graphql)), Body::from(bytes))
+        }
+        _ => return next.run(Request::from_parts(parts, body)).await,
+    };
+
+    if let Some(hit) = cache.lookup(&key) {
+        return respond(hit, "hit");
+    }
+
+    // Coalesce concurrent misses: the first request runs, the rest wait.
+    let flight = cache.flight_lock(&key);
+    let _guard = flight.lock().await;
+    if let Some(hit) = cache.lookup(&key) {
+        return respond(hit, "hit");
+    }
+
+    let res = next.run(Request::from_parts(parts, body)).await;
+    // Responses of unknown or excessive length are streamed through as is.
+    let cacheable = res.status() == StatusCode::OK
+        && !res.headers().contains_key(header::SET_COOKIE)
+        && body_size(res.headers(), res.body()).is_some_and(|n| n <= cache.max_body_bytes as u64);
+    if !cacheable {
+        cache.end_flight(&key);
+        return res;
+    }
+
+    let (res_parts, res_body) = res.into_parts();
+    let bytes = match axum::body::to_bytes(res_body, cache.max_body_bytes).await {
+        Ok(b) => b,
+        Err(_) => {
+            cache.end_flight(&key);
+            return Problem::internal().into_response();
+        }
+    };
+    let cached = Cached {
+        at: Instant::now(),
+        status: res_parts.status,
+        headers: res_parts.headers,
+        body: bytes,
+    };
+    cache.store(key, cached.clone());
+    cache.end_flight(&key);
+
+    respond(cached, "miss")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{
+        middleware::from_fn_with_state,
+        routing::{get, post},
+        Router,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    fn app(cache: MicroCache, calls: Arc<AtomicUsize>) -> Router {
+        let c1 = calls.clone();
+        let c2 = calls.clone();
+        Router::new()
+            .route(
+                "/news",
+                get(move || {
+                    let n = c1.fetch_add(1, Ordering::SeqCst);
+                    async move {
+                        tokio::time::sleep(Duration::from_millis(20)).await;
+                        format!("news {n}")
+                    }
+                }),
+            )
+            .route(
+                "/graphql",
+                post(move |body: String| {
+                    let n = c2.fetch_add(1, Ordering::SeqCst);
+                    async move { format!("{n}:{}", body.len()) }
+                }),
+            )
+            .route(
+                "/login",
+                get(|| async { ([(header::SET_COOKIE, "s=1")], "x") }),
+            )
+            .route("/big", get(|| async { "x".repeat(64) }))
+            .layer(from_fn_with_state(cache, micro_cache))
+    }
+
+    async fn send(app: &Router, req: Request) -> (String, String) {
+        let res = app.clone().oneshot(req).await.unwrap();
+        let status = res
+            .headers()
+            .get(CACHE_STATUS_HEADER)
+            .map(|v| v.to_str().unwrap().to_string())
+            .unwrap_or_default();
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn get_req(uri: &str, cookie: &str) -> Request {
+        Request::get(uri)
+            .header(header::COOKIE, cookie)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn gql(query: &str) -> Request {
+        Request::post("/graphql")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                serde_json::json!({ "query": query }).to_string(),
+            ))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn caches_get_per_subject_until_ttl() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(MicroCache::new(Duration::from_millis(200)), calls.clone());
+
+        assert_eq!(
+            send(&app, get_req("/news", "a=1")).await,
+            ("miss".into(), "news 0".into())
+        );
+        assert_eq!(
+            send(&app, get_req("/news", "a=1")).await,
+            ("hit".into(), "news 0".into())
+        );
+        assert_eq!(send(&app, get_req("/news", "a=2")).await.1, "news 1");
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(send(&app, get_req("/news", "a=1")).await.1, "news 2");
+    }
+
+    #[tokio::test]
+    async fn coalesces_concurrent_misses() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(MicroCache::new(Duration::from_secs(5)), calls.clone());
+
+        let results =
+            futures::future::join_all((0..10).map(|_| send(&app, get_req("/news", "a=1")))).await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(results.iter().all(|(_, body)| body == "news 0"));
+    }
+
+    #[tokio::test]
+    async fn caches_graphql_queries_but_not_mutations() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(MicroCache::new(Duration::from_secs(5)), calls.clone());
+
+        send(&app, gql("{ me { id } }")).await;
+        assert_eq!(send(&app, gql("{ me { id } }")).await.0, "hit");
+        assert_eq!(send(&app, gql("{ me { name } }")).await.0, "miss");
+
+        assert_eq!(send(&app, gql("mutation { logout }")).await.0, "");
+        assert_eq!(send(&app, gql("mutation { logout }")).await.0, "");
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn skips_set_cookie_responses_and_no_cache_requests() {
+        let cache = MicroCache::new(Duration::from_secs(5));
+        let app = app(cache.clone(), Arc::new(AtomicUsize::new(0)));
+
+        send(&app, get_req("/login", "")).await;
+        assert!(cache.is_empty());
+
+        let req = Request::get("/news")
+            .header(header::CACHE_CONTROL, "no-cache")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(send(&app, req).await.0, "");
+        assert!(cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn streams_uploads_and_oversized_bodies_uncached() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let cache = MicroCache::new(Duration::from_secs(5)).max_body_bytes(16);
+        let app = app(cache.clone(), calls.clone());
+
+        let multipart = Request::post("/graphql")
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=x")
+            .body(Body::from("--x\r\n".repeat(10)))
+            .unwrap();
+        assert_eq!(send(&app, multipart).await, ("".into(), "0:50".into()));
+
+        let large = gql("{ me { id name email } }");
+        let (status, body) = send(&app, large).await;
+        assert_eq!(status, "");
+        assert!(body.starts_with("1:"), "{body}");
+
+        assert_eq!(
+            send(&app, get_req("/big", "")).await,
+            ("".into(), "x".repeat(64))
+        );
+        assert_eq!(send(&app, get_req("/big", "")).await.0, "");
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,428 @@
+//! # Replay Protection Middleware
+//!
+//! Makes links and requests single use: a password reset link, an
+//! unsubscribe link or an internal webhook call carries a signed nonce, and
+//! [`replay_protection`] accepts each nonce only once.
+//!
+//! Nonces have the shape
+//!
+//! ```text
+//! v1.<nonce_b64>.<expires_unix>.<mac_b64>
+//! ```
+//!
+//! where the MAC is HMAC-SHA256 over the nonce, its expiry and the request
+//! it was issued for: the method, the path and the query string without the
+//! `nonce` parameter (decoded pairs, sorted). A nonce therefore only works
+//! for the exact URL it was signed with; changing a parameter (e.g. `user=`
+//! below) invalidates it. The layer reads the nonce from the `nonce` query
+//! parameter or the [`NONCE_HEADER`] header and records it in a
+//! [`ReplayGuard`] (the seen-set port shared with the
+//! [webhook inbox](crate::web::webhook_inbox::inbox)).
+//!
+//! The path is the one seen by the layer, so sign the full path when the
+//! layer is mounted outside any `nest`.
+//!
+//! | Outcome | Response |
+//! |---------|----------|
+//! | First use | handler response |
+//! | Missing, forged or expired nonce | `400 Bad Request` |
+//! | Already used | `409 Conflict` |
+//!
+//! When the handler answers with a `5xx`, the nonce is released so the link
+//! can be retried.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//! use axum::{http::Method, middleware::from_fn_with_state, routing::post, Router};
+//! use wzs_web::config::csrf::derive_secret_from_string;
+//! use wzs_web::web::middleware::replay::{replay_protection, ReplayProtection};
+//! use wzs_web::web::webhook_inbox::inbox::MemoryReplayGuard;
+//!
+//! let ttl = Duration::from_secs(3600);
+//! let replay = ReplayProtection::new(
+//!     derive_secret_from_string("nonce-secret"),
+//!     Arc::new(MemoryReplayGuard::new(ttl)),
+//! )
+//! .ttl(ttl);
+//!
+//! // Sent by email; the link works once within an hour.
+//! let link = replay.sign_url(&Method::POST, "https://example.com/password/reset?user=42");
+//!
+//! let app: Router = Router::new()
+//!     .route("/password/reset", post(|| async { "password changed" }))
+//!     .layer(from_fn_with_state(replay, replay_protection));
+//! ```
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::{
+    extract::{Request, State},
+    http::{Method, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+
+use crate::error::app::AppError;
+use crate::web::webhook_inbox::inbox::ReplayGuard;
+
+/// Header carrying the nonce for non-link callers.
+pub const NONCE_HEADER: &str = "x-request-nonce";
+
+/// Query parameter carrying the nonce in signed links.
+pub const NONCE_PARAM: &str = "nonce";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Issues and checks single-use nonces; state of [`replay_protection`].
+///
+/// The guard must remember nonces at least as long as they are valid
+/// ([`ttl`](Self::ttl)), otherwise an expired entry could be replayed
+/// before the nonce itself expires.
+#[derive(Clone)]
+pub struct ReplayProtection {
+    secret: [u8; 32],
+    guard: Arc<dyn ReplayGuard>,
+    ttl: Duration,
+}
+
+impl ReplayProtection {
+    /// Creates a protector with a 24-hour nonce lifetime.
+    ///
+    /// - `secret`: 32-byte HMAC key (see [`derive_secret_from_string`](crate::config::csrf::derive_secret_from_string))
+    /// - `guard`: seen-set recording used nonces
+    pub fn new(secret: [u8; 32], guard: Arc<dyn ReplayGuard>) -> Self {
+        Self {
+            secret,
+            guard,
+            ttl: Duration::from_secs(24 * 3600),
+        }
+    }
+
+    /// Sets how long issued nonces stay valid.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Issues a fresh signed nonce for a `method` request to `url` (a path
+    /// or absolute URL, with its query string), to be sent in the
+    /// [`NONCE_HEADER`] header.
+    pub fn issue(&self, method: &Method, url: &str) -> String {
+        let (path, query) = split_url(url);
+        self.issue_at(&request_target(method, path, query), now_unix())
+    }
+
+    /// Appends a fresh nonce for a `method` request to `url` as the `nonce`
+    /// query parameter.
+    pub fn sign_url(&self, method: &Method, url: &str) -> String {
+        let sep = if url.contains('?') { '&' } else { '?' };
+        format!("{url}{sep}{NONCE_PARAM}={}", self.issue(method, url))
+    }
+
+    /// Verifies the signature and expiry of `nonce` for a `method` request
+    /// to `uri` at `now_unix`, returning its replay key. Does not consult
+    /// the guard.
+    pub fn verify(&self, nonce: &str, method: &Method, uri: &Uri, now_unix: i64) -> Option<String> {
+        self.verify_target(
+            nonce,
+            &request_target(method, uri.path(), uri.query()),
+            now_unix,
+        )
+    }
+
+    fn verify_target(&self, nonce: &str, target: &str, now_unix: i64) -> Option<String> {
+        let mut parts = nonce.split('.');
+        let (Some(v), Some(id), Some(exp), Some(mac_b64), None) = (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+        ) else {
+            return None;
+        };
+        if v != "v1" {
+            return None;
+        }
+
+        let mac = URL_SAFE_NO_PAD.decode(mac_b64).ok()?;
+        let expected = self.mac(id, exp, target);
+        if expected.as_slice().ct_eq(&mac).unwrap_u8() != 1 {
+            return None;
+        }
+
+        let expires: i64 = exp.parse().ok()?;
+        (now_unix < expires).then(|| format!("nonce:{id}"))
+    }
+
+    /// Checks `nonce` for a `method` request to `uri` at `now_unix` and
+    /// records it as used, returning its replay key.
+    pub fn consume(
+        &self,
+        nonce: Option<&str>,
+        method: &Method,
+        uri: &Uri,
+        now_unix: i64,
+    ) -> Result<String, ReplayRejection> {
+        let Some(key) = nonce.and_then(|n| self.verify(n, method, uri, now_unix)) else {
+            return Err(ReplayRejection::Invalid);
+        };
+        if !self.guard.first_seen(&key) {
+            tracing::info!(%key, "replayed request rejected");
+            return Err(ReplayRejection::Replayed);
+        }
+        Ok(key)
+    }
+
+    fn issue_at(&self, target: &str, now_unix: i64) -> String {
+        let mut raw = [0u8; 16];
+        rand::rng().fill_bytes(&mut raw);
+        let id = URL_SAFE_NO_PAD.encode(raw);
+        let exp = (now_unix + self.ttl.as_secs() as i64).to_string();
+        let mac = self.mac(&id, &exp, target);
+        format!("v1.{id}.{exp}.{}", URL_SAFE_NO_PAD.encode(mac))
+    }
+
+    fn mac(&self, id: &str, exp: &str, target: &str) -> Vec<u8> {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC key");
+        mac.update(id.as_bytes());
+        mac.update(b".");
+        mac.update(exp.as_bytes());
+        mac.update(b"\n");
+        mac.update(target.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+/// Why [`ReplayProtection::consume`] refused a nonce.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayRejection {
+    /// Missing, forged or expired.
+    Invalid,
+    /// Already used.
+    Replayed,
+}
+
+impl IntoResponse for ReplayRejection {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Invalid => AppError::bad_request("invalid or expired nonce"),
+            Self::Replayed => AppError::conflict("request already used"),
+        }
+        .into_response()
+    }
+}
+
+/// Middleware rejecting requests whose nonce is missing, invalid or already
+/// used. Mount with `from_fn_with_state(protection, replay_protection)`.
+pub async fn replay_protection(
+    State(protection): State<ReplayProtection>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let nonce = req
+        .headers()
+        .get(NONCE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .or_else(|| query_nonce(req.uri().query()));
+
+    let key = match protection.consume(nonce.as_deref(), req.method(), req.uri(), now_unix()) {
+        Ok(key) => key,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    let resp = next.run(req).await;
+    if resp.status().is_server_error() {
+        protection.guard.release(&key);
+    }
+    resp
+}
+
+/// Path and query of a path or absolute URL (fragment dropped).
+fn split_url(url: &str) -> (&str, Option<&str>) {
+    let url = url.split('#').next().unwrap_or_default();
+    let target = match url.split_once("://") {
+        Some((_, rest)) => rest.find(['/', '?']).map_or("", |i| &rest[i..]),
+        None => url,
+    };
+    match target.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (target, None),
+    }
+}
+
+/// MAC input identifying the request: method, path and the query pairs
+/// other than [`NONCE_PARAM`], decoded and sorted.
+fn request_target(method: &Method, path: &str, query: Option<&str>) -> String {
+    let mut pairs: Vec<_> = url::form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+        .filter(|(k, _)| k != NONCE_PARAM)
+        .collect();
+    pairs.sort();
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(pairs)
+        .finish();
+    let path = if path.is_empty() { "/" } else { path };
+    format!("{method}\n{path}\n{query}")
+}
+
+fn query_nonce(query: Option<&str>) -> Option<String> {
+    url::form_urlencoded::parse(query?.as_bytes())
+        .find(|(k, _)| k == NONCE_PARAM)
+        .map(|(_, v)| v.into_owned())
+}
+
+fn now_unix() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::{body::Body, http::Request as HttpRequest, middleware::from_fn_with_state};
+    use axum::{routing::post, Router};
+    use tower::ServiceExt;
+
+    use crate::web::webhook_inbox::inbox::MemoryReplayGuard;
+
+    fn protection() -> ReplayProtection {
+        ReplayProtection::new(
+            [7u8; 32],
+            Arc::new(MemoryReplayGuard::new(Duration::from_secs(60))),
+        )
+        .ttl(Duration::from_secs(60))
+    }
+
+    fn uri(s: &str) -> Uri {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn verifies_signature_and_expiry() {
+        let p = protection();
+        let target = request_target(&Method::POST, "/reset", None);
+        let nonce = p.issue_at(&target, 1_000);
+        let reset = uri("/reset");
+
+        assert!(p.verify(&nonce, &Method::POST, &reset, 1_000).is_some());
+        assert!(
+            p.verify(&nonce, &Method::POST, &reset, 1_060).is_none(),
+            "expired"
+        );
+        assert!(p
+            .verify(
+                &nonce.replace(".1060.", ".9999."),
+                &Method::POST,
+                &reset,
+                1_000
+            )
+            .is_none());
+        assert!(p.verify("v1.a.b", &Method::POST, &reset, 1_000).is_none());
+
+        let other = ReplayProtection::new([8u8; 32], p.guard.clone());
+        assert!(other.verify(&nonce, &Method::POST, &reset, 1_000).is_none());
+    }
+
+    #[test]
+    fn nonces_are_bound_to_method_path_and_query() {
+        let p = protection();
+        let link = p.sign_url(
+            &Method::POST,
+            "https://example.com/password/reset?user=42&a=1",
+        );
+        let nonce = query_nonce(link.split_once('?').map(|(_, q)| q)).unwrap();
+        let check =
+            |method: &Method, target: &str| p.verify(&nonce, method, &uri(target), now_unix());
+
+        let own = link.strip_prefix("https://example.com").unwrap();
+        assert!(check(&Method::POST, own).is_some());
+        let reordered = format!("/password/reset?nonce={nonce}&a=1&user=42");
+        assert!(check(&Method::POST, &reordered).is_some());
+
+        let tampered = own.replace("user=42", "user=43");
+        assert!(check(&Method::POST, &tampered).is_none());
+        let extra = format!("{own}&admin=1");
+        assert!(check(&Method::POST, &extra).is_none());
+        assert!(check(&Method::GET, own).is_none());
+        let moved = own.replace("/password/reset", "/account/delete");
+        assert!(check(&Method::POST, &moved).is_none());
+    }
+
+    #[test]
+    fn consume_accepts_each_nonce_once() {
+        let p = protection();
+        let nonce = p.issue_at(&request_target(&Method::POST, "/reset", None), 1_000);
+        let reset = uri("/reset");
+
+        assert!(p
+            .consume(Some(&nonce), &Method::POST, &reset, 1_000)
+            .is_ok());
+        assert_eq!(
+            p.consume(Some(&nonce), &Method::POST, &reset, 1_001),
+            Err(ReplayRejection::Replayed)
+        );
+        assert_eq!(
+            p.consume(None, &Method::POST, &reset, 1_000),
+            Err(ReplayRejection::Invalid)
+        );
+    }
+
+    #[tokio::test]
+    async fn layer_rejects_replays_and_releases_on_server_errors() {
+        let p = protection();
+        let app = Router::new()
+            .route("/reset", post(|| async { "ok" }))
+            .route("/flaky", post(|| async { StatusCode::SERVICE_UNAVAILABLE }))
+            .layer(from_fn_with_state(p.clone(), replay_protection));
+        let call = |uri: String, header: Option<String>| {
+            let app = app.clone();
+            async move {
+                let mut req = HttpRequest::post(uri);
+                if let Some(nonce) = header {
+                    req = req.header(NONCE_HEADER, nonce);
+                }
+                app.oneshot(req.body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+                    .status()
+            }
+        };
+
+        let link = p.sign_url(&Method::POST, "/reset?user=42");
+        assert_eq!(
+            call(link.replace("user=42", "user=1"), None).await,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(call(link.clone(), None).await, StatusCode::OK);
+        assert_eq!(call(link, None).await, StatusCode::CONFLICT);
+        assert_eq!(call("/reset".into(), None).await, StatusCode::BAD_REQUEST);
+
+        let nonce = p.issue(&Method::POST, "/flaky");
+        assert_eq!(
+            call("/reset".into(), Some(nonce.clone())).await,
+            StatusCode::BAD_REQUEST,
+            "issued for another path"
+        );
+        assert_eq!(
+            call("/flaky".into(), Some(nonce.clone())).await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            call("/flaky".into(), Some(nonce)).await,
+            StatusCode::SERVICE_UNAVAILABLE,
+            "nonce released after a server error"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! # Request ID and Access Log Middleware
+//!
+//! [`request_id`] gives every request an identifier:
+//!
+//! - An incoming `X-Request-Id` header is reused when it looks sane
+//!   (1–128 visible ASCII characters), so IDs survive proxies.
+//! - Otherwise a UUID v7 is generated.
+//! - The ID is stored as a [`RequestId`] request extension and echoed in the
+//!   response header.
+//! - [`Problem`] responses without a request ID get this one in their body.
+//!
+//! [`access_log`] emits one `tracing` event per request with method, path,
+//! status, latency and (when present) the request ID.
+//!
+//! # Example
+//! ```rust,no_run
+//! use axum::{middleware::from_fn, routing::get, Router};
+//! use wzs_web::web::middleware::request_id::{access_log, request_id};
+//!
+//! let app: Router = Router::new()
+//!     .route("/", get(|| async { "ok" }))
+//!     .layer(from_fn(access_log))
+//!     .layer(from_fn(request_id)); // outermost, so the log sees the ID
+//! ```
+
+use std::time::Instant;
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::web::client_ip::ClientIp;
+use crate::web::problem::Problem;
+
+/// Header carrying the request ID.
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Identifier of the current request, available as a request extension.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+/// Middleware assigning a [`RequestId`].
+///
+/// Mount with `axum::middleware::from_fn(request_id)`.
+pub async fn request_id(mut req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|s| is_acceptable(s))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::now_v7().to_string());
+
+    req.extensions_mut().insert(RequestId(id.clone()));
+    let mut res = next.run(req).await;
+
+    let problem = res
+        .extensions()
+        .get::<Problem>()
+        .filter(|p| p.request_id.is_none())
+        .cloned();
+    if let Some(problem) = problem {
+        res = problem.request_id(id.clone()).rewrite(res);
+    }
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        res.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    res
+}
+
+/// Middleware logging one line per request.
+///
+/// Mount with `axum::middleware::from_fn(access_log)`.
+pub async fn access_log(req: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let id = req.extensions().get::<RequestId>().map(|r| r.0.clone());
+    let client_ip = ClientIp::of(req.extensions()).map(|ip| ip.to_string());
+
+    let res = next.run(req).await;
+
+    tracing::info!(
+        method = %method,
+        path = %path,
+        status = res.status().as_u16(),
+        elapsed_ms = started.elapsed().as_millis() as u64,
+        request_id = id.as_deref().unwrap_or("-"),
+        client_ip = client_ip.as_deref().unwrap_or("-"),
+        "http request"
+    );
+    res
+}
+
+fn is_acceptable(s: &str) -> bool {
+    !s.is_empty() && s.len() <= 128 && s.bytes().all(|b| b.is_ascii_graphic())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{body::Body, middleware::from_fn, routing::get, Extension, Router};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(|Extension(id): Extension<RequestId>| async move { id.0 }),
+            )
+            .layer(from_fn(access_log))
+            .layer(from_fn(request_id))
+    }
+
+    async fn call(header: Option<&str>) -> (String, String) {
+        let mut req = Request::get("/");
+        if let Some(h) = header {
+            req = req.header("x-request-id", h);
+        }
+        let res = app()
+            .oneshot(req.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let echoed = res.headers()[&REQUEST_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (echoed, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn reuses_incoming_id() {
+        let (echoed, seen) = call(Some("abc-123")).await;
+        assert_eq!(echoed, "abc-123");
+        assert_eq!(seen, "abc-123");
+    }
+
+    #[tokio::test]
+    async fn generates_id_when_missing_or_invalid() {
+        let (echoed, seen) = call(None).await;
+        assert_eq!(echoed, seen);
+        assert!(uuid::Uuid::parse_str(&seen).is_ok());
+
+        let (echoed, _) = call(Some("has space")).await;
+        assert_ne!(echoed, "has space");
+        assert!(!is_acceptable(&"x".repeat(129)));
+    }
+
+    #[tokio::test]
+    async fn adds_the_id_to_problems() {
+        let app = Router::new()
+            .route("/", get(|| async { Problem::not_found("no such page") }))
+            .layer(from_fn(request_id));
+        let req = Request::get("/")
+            .header("x-request-id", "abc-123")
+            .body(Body::empty())
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(res.headers()["content-type"], "application/problem+json");
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["request_id"], "abc-123");
+        assert_eq!(json["detail"], "no such page");
+    }
+}
@@ -0,0 +1,253 @@
+//! # Built-in Pages
+//!
+//! Minimal HTML pages the crate serves itself, rendered in the request's
+//! [`Locale`]:
+//!
+//! | Page | Status | Handler |
+//! |------|--------|---------|
+//! | [`BuiltInPage::NotFound`] | `404` | [`not_found`](crate::web::fallback::not_found) (router fallback) |
+//! | [`BuiltInPage::Error`] | any `4xx` / `5xx` | [`error_page`] |
+//! | [`BuiltInPage::Maintenance`] | `503` | [`maintenance_page`] |
+//!
+//! Text is available in English, Japanese, German, French and Spanish;
+//! other languages get English. The handlers negotiate like
+//! [`fallback`](crate::web::fallback): API clients get the JSON error shape,
+//! and [`ErrorPages`] templates replace these pages.
+//!
+//! # Example
+//! ```rust,no_run
+//! use axum::{routing::get, Router};
+//! use wzs_web::web::fallback::not_found;
+//! use wzs_web::web::pages::maintenance_page;
+//!
+//! let app: Router = Router::new()
+//!     .route("/maintenance", get(maintenance_page))
+//!     .fallback(not_found);
+//! ```
+
+use std::sync::Arc;
+
+use askama::Template;
+use axum::{
+    http::{HeaderMap, StatusCode},
+    response::Response,
+    Extension,
+};
+
+use crate::web::fallback::{error_response, ErrorPages};
+use crate::web::locale::Locale;
+use crate::web::template::render_template_with_status;
+
+/// A page rendered by the crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuiltInPage {
+    NotFound,
+    Error,
+    Maintenance,
+}
+
+/// Localized title and message of a [`BuiltInPage`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PageText {
+    pub title: &'static str,
+    pub message: &'static str,
+}
+
+impl BuiltInPage {
+    /// Text for `locale` (English when the language is not translated).
+    pub fn text(&self, locale: &Locale) -> PageText {
+        let (title, message) = match (self, locale.language()) {
+            (Self::NotFound, "ja") => (
+                "ページが見つかりません",
+                "お探しのページは存在しないか、移動した可能性があります。",
+            ),
+            (Self::NotFound, "de") => (
+                "Seite nicht gefunden",
+                "Die angeforderte Seite existiert nicht oder wurde verschoben.",
+            ),
+            (Self::NotFound, "fr") => (
+                "Page introuvable",
+                "La page demandée n'existe pas ou a été déplacée.",
+            ),
+            (Self::NotFound, "es") => (
+                "Página no encontrada",
+                "La página solicitada no existe o se ha movido.",
+            ),
+            (Self::NotFound, _) => (
+                "Page not found",
+                "The page you are looking for does not exist or has moved.",
+            ),
+            (Self::Error, "ja") => (
+                "エラーが発生しました",
+                "リクエストを処理できませんでした。しばらくしてから再度お試しください。",
+            ),
+            (Self::Error, "de") => (
+                "Ein Fehler ist aufgetreten",
+                "Die Anfrage konnte nicht verarbeitet werden. Bitte versuchen Sie es später erneut.",
+            ),
+            (Self::Error, "fr") => (
+                "Une erreur est survenue",
+                "La requête n'a pas pu être traitée. Veuillez réessayer plus tard.",
+            ),
+            (Self::Error, "es") => (
+                "Se produjo un error",
+                "No se pudo procesar la solicitud. Inténtelo de nuevo más tarde.",
+            ),
+            (Self::Error, _) => (
+                "Something went wrong",
+                "The request could not be processed. Please try again later.",
+            ),
+            (Self::Maintenance, "ja") => (
+                "メンテナンス中",
+                "現在メンテナンスを行っています。しばらくお待ちください。",
+            ),
+            (Self::Maintenance, "de") => (
+                "Wartungsarbeiten",
+                "Wir führen gerade Wartungsarbeiten durch. Bitte schauen Sie bald wieder vorbei.",
+            ),
+            (Self::Maintenance, "fr") => (
+                "Maintenance en cours",
+                "Le service est en maintenance. Merci de revenir un peu plus tard.",
+            ),
+            (Self::Maintenance, "es") => (
+                "En mantenimiento",
+                "Estamos realizando tareas de mantenimiento. Vuelva a intentarlo pronto.",
+            ),
+            (Self::Maintenance, _) => (
+                "Down for maintenance",
+                "We are performing scheduled maintenance. Please check back soon.",
+            ),
+        };
+        PageText { title, message }
+    }
+
+    /// Renders the page with `status`.
+    pub fn render(&self, locale: &Locale, status: StatusCode) -> Response {
+        let text = self.text(locale);
+        render_template_with_status(
+            PageTemplate {
+                lang: locale.lang(),
+                dir: locale.dir(),
+                status: status.as_u16(),
+                title: text.title,
+                message: text.message,
+            },
+            status,
+        )
+    }
+}
+
+#[derive(Template)]
+#[template(
+    source = r#"<!doctype html>
+<html lang="{{ lang }}" dir="{{ dir }}">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{ title }}</title>
+</head>
+<body>
+<main>
+<h1>{{ title }}</h1>
+<p>{{ message }}</p>
+<p><small>{{ status }}</small></p>
+</main>
+</body>
+</html>
+"#,
+    ext = "html"
+)]
+struct PageTemplate<'a> {
+    lang: &'a str,
+    dir: &'a str,
+    status: u16,
+    title: &'a str,
+    message: &'a str,
+}
+
+/// Localized `503 Service Unavailable` maintenance response.
+pub async fn maintenance_page(
+    locale: Locale,
+    headers: HeaderMap,
+    pages: Option<Extension<Arc<ErrorPages>>>,
+) -> Response {
+    let pages = pages.as_ref().map(|Extension(p)| p.as_ref());
+    error_response(
+        pages,
+        &headers,
+        &locale,
+        StatusCode::SERVICE_UNAVAILABLE,
+        None,
+    )
+}
+
+/// Localized error page for `status`.
+pub fn error_page(locale: &Locale, status: StatusCode) -> Response {
+    BuiltInPage::Error.render(locale, status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::header, http::Request, routing::get, Router};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use crate::config::locale::LocaleConfig;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/maintenance", get(maintenance_page))
+            .fallback(crate::web::fallback::not_found)
+            .layer(Extension(LocaleConfig {
+                default: "ja".into(),
+                supported: vec!["ja".into(), "en".into()],
+            }))
+    }
+
+    async fn get_page(uri: &str, accept: &str, lang: Option<&str>) -> (StatusCode, String) {
+        let mut req = Request::get(uri).header(header::ACCEPT, accept);
+        if let Some(lang) = lang {
+            req = req.header(header::ACCEPT_LANGUAGE, lang);
+        }
+        let res = app()
+            .oneshot(req.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = res.status();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn not_found_uses_negotiated_or_default_locale() {
+        let (status, body) = get_page("/nope", "text/html", Some("en-US")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body.contains(r#"<html lang="en" dir="ltr">"#));
+        assert!(body.contains("Page not found"));
+
+        let (_, body) = get_page("/nope", "text/html", Some("fr")).await;
+        assert!(body.contains("ページが見つかりません"));
+
+        let (status, body) = get_page("/nope", "application/json", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body.contains(r#""title":"Not Found""#));
+    }
+
+    #[tokio::test]
+    async fn maintenance_and_error_pages_are_localized() {
+        let (status, body) = get_page("/maintenance", "text/html", Some("en")).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body.contains("Down for maintenance"));
+
+        let res = error_page(&Locale("de-AT".into()), StatusCode::BAD_GATEWAY);
+        assert_eq!(res.status(), StatusCode::BAD_GATEWAY);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("Ein Fehler ist aufgetreten"));
+        assert!(body.contains("502"));
+
+        let text = BuiltInPage::Error.text(&Locale("zh".into()));
+        assert_eq!(text.title, "Something went wrong");
+    }
+}
@@ -0,0 +1,532 @@
+//! # Pagination
+//!
+//! Offset and cursor pagination shared by REST handlers and GraphQL
+//! resolvers.
+//!
+//! **Offset pagination** — [`PageParams`] reads `?page=&per_page=` (1-based
+//! page, `per_page` capped by [`PageLimits`]) and [`Paginated`] wraps one
+//! page with the total count:
+//!
+//! ```json
+//! { "items": [...], "page": 2, "per_page": 20, "total": 45, "total_pages": 3 }
+//! ```
+//!
+//! [`Paginated::respond`] also sets `X-Total-Count` and an RFC 8288 `Link`
+//! header with `first`, `prev`, `next` and `last` relations.
+//!
+//! **Cursor pagination** — [`CursorParams`] reads `?after=&limit=`; the
+//! cursor is an opaque token encoding the sort key of the last item seen
+//! ([`encode_cursor`] / [`decode_cursor`]). Fetch `limit + 1` rows after the
+//! key and build a [`CursorPage`] with [`CursorPage::from_overfetch`]:
+//!
+//! ```json
+//! { "items": [...], "next_cursor": "eyJpZCI6NDJ9", "has_more": true }
+//! ```
+//!
+//! In GraphQL resolvers, build the parameters with [`PageParams::new`] /
+//! [`CursorParams::new`] from the field arguments and expose [`PageInfo`].
+//!
+//! # Example
+//! ```rust
+//! use axum::{http::Uri, response::Response, routing::get, Router};
+//! use wzs_web::web::pagination::{PageParams, Paginated};
+//!
+//! async fn list(page: PageParams, uri: Uri) -> Response {
+//!     let all: Vec<u32> = (1..=45).collect();
+//!     let items = all
+//!         .iter()
+//!         .skip(page.offset() as usize)
+//!         .take(page.limit() as usize)
+//!         .copied()
+//!         .collect();
+//!     Paginated::new(items, &page, all.len() as u64).respond(&uri)
+//! }
+//!
+//! let app: Router = Router::new().route("/numbers", get(list));
+//! # let _ = app;
+//! ```
+
+use axum::{
+    extract::FromRequestParts,
+    http::{request::Parts, HeaderValue, Uri},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::error::app::AppError;
+
+/// Page size bounds; add as a request extension to override the defaults
+/// (20 per page, at most 100).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PageLimits {
+    pub default_per_page: u32,
+    pub max_per_page: u32,
+}
+
+impl Default for PageLimits {
+    fn default() -> Self {
+        Self {
+            default_per_page: 20,
+            max_per_page: 100,
+        }
+    }
+}
+
+impl PageLimits {
+    /// `requested`, or the default when absent or zero, capped at the maximum.
+    pub fn clamp(&self, requested: Option<u32>) -> u32 {
+        match requested {
+            Some(n) if n > 0 => n.min(self.max_per_page),
+            _ => self.default_per_page,
+        }
+    }
+}
+
+/// Offset pagination parameters (`page` is 1-based).
+///
+/// As an extractor, reads `page` and `per_page` from the query string and
+/// rejects non-numeric values with `400`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PageParams {
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl PageParams {
+    /// Parameters from optional arguments, within the default limits.
+    pub fn new(page: Option<u32>, per_page: Option<u32>) -> Self {
+        Self::with_limits(page, per_page, &PageLimits::default())
+    }
+
+    /// Parameters from optional arguments, within `limits`.
+    pub fn with_limits(page: Option<u32>, per_page: Option<u32>, limits: &PageLimits) -> Self {
+        Self {
+            page: page.unwrap_or(1).max(1),
+            per_page: limits.clamp(per_page),
+        }
+    }
+
+    /// Rows to skip (`OFFSET`).
+    pub fn offset(&self) -> u64 {
+        u64::from(self.page - 1) * u64::from(self.per_page)
+    }
+
+    /// Rows to fetch (`LIMIT`).
+    pub fn limit(&self) -> u32 {
+        self.per_page
+    }
+}
+
+impl<S> FromRequestParts<S> for PageParams
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let limits = parts
+            .extensions
+            .get::<PageLimits>()
+            .copied()
+            .unwrap_or_default();
+        Ok(Self::with_limits(
+            query_number(&parts.uri, "page")?,
+            query_number(&parts.uri, "per_page")?,
+            &limits,
+        ))
+    }
+}
+
+/// Page-level metadata, for GraphQL types and custom envelopes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, async_graphql::SimpleObject)]
+pub struct PageInfo {
+    pub page: u32,
+    pub per_page: u32,
+    pub total: u64,
+    pub total_pages: u64,
+}
+
+impl PageInfo {
+    /// Whether a page follows this one.
+    pub fn has_next(&self) -> bool {
+        u64::from(self.page) < self.total_pages
+    }
+
+    /// Whether a page precedes this one.
+    pub fn has_prev(&self) -> bool {
+        self.page > 1
+    }
+}
+
+/// One page of items with the total count.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    #[serde(flatten)]
+    pub info: PageInfo,
+}
+
+impl<T> Paginated<T> {
+    /// Wraps the items of page `params` out of `total`.
+    pub fn new(items: Vec<T>, params: &PageParams, total: u64) -> Self {
+        Self {
+            items,
+            info: PageInfo {
+                page: params.page,
+                per_page: params.per_page,
+                total,
+                total_pages: total.div_ceil(u64::from(params.per_page.max(1))),
+            },
+        }
+    }
+
+    /// Converts the items, keeping the page metadata.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Paginated<U> {
+        Paginated {
+            items: self.items.into_iter().map(f).collect(),
+            info: self.info,
+        }
+    }
+
+    /// `Link` header value for the pages around this one, relative to the
+    /// request `uri` (other query parameters are kept).
+    pub fn link_header(&self, uri: &Uri) -> Option<HeaderValue> {
+        let info = &self.info;
+        let last = info.total_pages.max(1);
+        let mut rels = vec![("first", 1), ("last", last)];
+        if info.has_prev() {
+            rels.insert(1, ("prev", u64::from(info.page - 1).min(last)));
+        }
+        if info.has_next() {
+            rels.push(("next", u64::from(info.page) + 1));
+        }
+        let per_page = info.per_page.to_string();
+        let links: Vec<String> = rels
+            .into_iter()
+            .map(|(rel, page)| {
+                let href = with_query(uri, &[("page", &page.to_string()), ("per_page", &per_page)]);
+                format!("<{href}>; rel=\"{rel}\"")
+            })
+            .collect();
+        HeaderValue::from_str(&links.join(", ")).ok()
+    }
+}
+
+impl<T: Serialize> Paginated<T> {
+    /// JSON response with `X-Total-Count` and `Link` headers.
+    pub fn respond(self, uri: &Uri) -> Response {
+        let link = self.link_header(uri);
+        let total = self.info.total;
+        let mut res = Json(self).into_response();
+        res.headers_mut()
+            .insert("x-total-count", HeaderValue::from(total));
+        if let Some(link) = link {
+            res.headers_mut().insert(axum::http::header::LINK, link);
+        }
+        res
+    }
+}
+
+impl<T: Serialize> IntoResponse for Paginated<T> {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
+/// Cursor pagination parameters.
+///
+/// As an extractor, reads `after` (a cursor from a previous page) and
+/// `limit` from the query string; bad values are rejected with `400`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CursorParams {
+    pub after: Option<String>,
+    pub limit: u32,
+}
+
+impl CursorParams {
+    /// Parameters from optional arguments, within the default limits.
+    pub fn new(after: Option<String>, limit: Option<u32>) -> Self {
+        Self::with_limits(after, limit, &PageLimits::default())
+    }
+
+    /// Parameters from optional arguments, within `limits`.
+    pub fn with_limits(after: Option<String>, limit: Option<u32>, limits: &PageLimits) -> Self {
+        Self {
+            after: after.filter(|a| !a.is_empty()),
+            limit: limits.clamp(limit),
+        }
+    }
+
+    /// The decoded sort key to continue after (`None` on the first page).
+    ///
+    /// # Errors
+    /// [`AppError::BadRequest`] when the cursor is malformed.
+    pub fn key<K: DeserializeOwned>(&self) -> Result<Option<K>, AppError> {
+        self.after.as_deref().map(decode_cursor).transpose()
+    }
+
+    /// Rows to fetch: one more than the page size, to detect a next page.
+    pub fn fetch_limit(&self) -> u32 {
+        self.limit.saturating_add(1)
+    }
+}
+
+impl<S> FromRequestParts<S> for CursorParams
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let limits = parts
+            .extensions
+            .get::<PageLimits>()
+            .copied()
+            .unwrap_or_default();
+        Ok(Self::with_limits(
+            query_value(&parts.uri, "after"),
+            query_number(&parts.uri, "limit")?,
+            &limits,
+        ))
+    }
+}
+
+/// One page of a cursor-paginated list.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    /// Pass as `after` to fetch the next page.
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+}
+
+impl<T> CursorPage<T> {
+    /// Builds a page from up to `params.fetch_limit()` rows, using `key` to
+    /// encode the cursor of the last item kept.
+    pub fn from_overfetch<K: Serialize>(
+        mut items: Vec<T>,
+        params: &CursorParams,
+        key: impl Fn(&T) -> K,
+    ) -> Self {
+        let has_more = items.len() > params.limit as usize;
+        items.truncate(params.limit as usize);
+        let next_cursor = if has_more {
+            items.last().map(|item| encode_cursor(&key(item)))
+        } else {
+            None
+        };
+        Self {
+            items,
+            next_cursor,
+            has_more,
+        }
+    }
+
+    /// `Link` header value pointing at the next page, if any.
+    pub fn link_header(&self, uri: &Uri) -> Option<HeaderValue> {
+        let cursor = self.next_cursor.as_deref()?;
+        let href = with_query(uri, &[("after", cursor)]);
+        HeaderValue::from_str(&format!("<{href}>; rel=\"next\"")).ok()
+    }
+}
+
+impl<T: Serialize> CursorPage<T> {
+    /// JSON response with a `Link` header to the next page.
+    pub fn respond(self, uri: &Uri) -> Response {
+        let link = self.link_header(uri);
+        let mut res = Json(self).into_response();
+        if let Some(link) = link {
+            res.headers_mut().insert(axum::http::header::LINK, link);
+        }
+        res
+    }
+}
+
+impl<T: Serialize> IntoResponse for CursorPage<T> {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
+/// Opaque cursor for a sort key (URL-safe base64 of its JSON).
+pub fn encode_cursor<K: Serialize>(key: &K) -> String {
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(key).unwrap_or_default())
+}
+
+/// Sort key of a cursor made by [`encode_cursor`].
+///
+/// # Errors
+/// [`AppError::BadRequest`] when the cursor is malformed.
+pub fn decode_cursor<K: DeserializeOwned>(cursor: &str) -> Result<K, AppError> {
+    URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| AppError::bad_request("invalid cursor"))
+}
+
+fn query_value(uri: &Uri, name: &str) -> Option<String> {
+    url::form_urlencoded::parse(uri.query()?.as_bytes())
+        .find(|(k, _)| k == name)
+        .map(|(_, v)| v.into_owned())
+}
+
+fn query_number(uri: &Uri, name: &str) -> Result<Option<u32>, AppError> {
+    match query_value(uri, name).filter(|v| !v.is_empty()) {
+        Some(v) => v
+            .parse()
+            .map(Some)
+            .map_err(|_| AppError::bad_request(format!("`{name}` must be a non-negative integer"))),
+        None => Ok(None),
+    }
+}
+
+/// `uri` with `params` replacing any existing values of the same names.
+fn with_query(uri: &Uri, params: &[(&str, &str)]) -> String {
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    if let Some(q) = uri.query() {
+        for (k, v) in url::form_urlencoded::parse(q.as_bytes()) {
+            if !params.iter().any(|(name, _)| *name == k) {
+                query.append_pair(&k, &v);
+            }
+        }
+    }
+    query.extend_pairs(params);
+    format!("{}?{}", uri.path(), query.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{
+        body::Body,
+        http::{header::LINK, Request, StatusCode},
+        routing::get,
+        Extension, Router,
+    };
+    use serde::Deserialize;
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    async fn extract<T: FromRequestParts<()>>(
+        uri: &str,
+        limits: Option<PageLimits>,
+    ) -> Result<T, T::Rejection> {
+        let mut req = Request::get(uri).body(()).unwrap();
+        if let Some(limits) = limits {
+            req.extensions_mut().insert(limits);
+        }
+        let (mut parts, _) = req.into_parts();
+        T::from_request_parts(&mut parts, &()).await
+    }
+
+    #[tokio::test]
+    async fn page_params_are_capped() {
+        let p: PageParams = extract("/x", None).await.unwrap();
+        assert_eq!((p.page, p.per_page, p.offset()), (1, 20, 0));
+
+        let p: PageParams = extract("/x?page=3&per_page=1000", None).await.unwrap();
+        assert_eq!((p.page, p.per_page, p.offset()), (3, 100, 200));
+
+        let limits = PageLimits {
+            default_per_page: 5,
+            max_per_page: 10,
+        };
+        let p: PageParams = extract("/x?page=0&per_page=0", Some(limits)).await.unwrap();
+        assert_eq!((p.page, p.per_page), (1, 5));
+
+        let e = extract::<PageParams>("/x?page=two", None)
+            .await
+            .unwrap_err();
+        assert_eq!(e.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn links_cover_neighbouring_pages() {
+        let uri: Uri = "/users?q=ann&page=2&per_page=10".parse().unwrap();
+        let page = Paginated::new(vec![1, 2], &PageParams::new(Some(2), Some(10)), 45);
+        assert_eq!(page.info.total_pages, 5);
+        assert_eq!(
+            page.link_header(&uri).unwrap(),
+            "</users?q=ann&page=1&per_page=10>; rel=\"first\", \
+             </users?q=ann&page=1&per_page=10>; rel=\"prev\", \
+             </users?q=ann&page=5&per_page=10>; rel=\"last\", \
+             </users?q=ann&page=3&per_page=10>; rel=\"next\""
+        );
+
+        let empty = Paginated::<u8>::new(vec![], &PageParams::new(None, None), 0);
+        assert_eq!(
+            empty.link_header(&"/users".parse().unwrap()).unwrap(),
+            "</users?page=1&per_page=20>; rel=\"first\", </users?page=1&per_page=20>; rel=\"last\""
+        );
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Key {
+        created: i64,
+        id: u64,
+    }
+
+    #[tokio::test]
+    async fn cursors_round_trip() {
+        let first: CursorParams = extract("/x?limit=2", None).await.unwrap();
+        assert_eq!(first.key::<Key>().unwrap(), None);
+        assert_eq!(first.fetch_limit(), 3);
+
+        let rows = vec![(10, 1), (10, 2), (11, 3)];
+        let page = CursorPage::from_overfetch(rows, &first, |&(created, id)| Key { created, id });
+        assert!(page.has_more);
+        assert_eq!(page.items, [(10, 1), (10, 2)]);
+
+        let next: CursorParams = extract(
+            &format!("/x?limit=2&after={}", page.next_cursor.unwrap()),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(next.key::<Key>().unwrap(), Some(Key { created: 10, id: 2 }));
+
+        let last = CursorPage::from_overfetch(vec![(11, 3)], &next, |r| r.1);
+        assert_eq!((last.has_more, last.next_cursor), (false, None));
+
+        let bad = CursorParams::new(Some("not-a-cursor".into()), None);
+        assert_eq!(
+            bad.key::<Key>().unwrap_err().status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn respond_sets_headers_and_envelope() {
+        async fn list(page: PageParams, uri: Uri) -> Response {
+            let items: Vec<u64> = (page.offset()..45).take(page.limit() as usize).collect();
+            Paginated::new(items, &page, 45).respond(&uri)
+        }
+        let app = Router::new()
+            .route("/n", get(list))
+            .layer(Extension(PageLimits {
+                default_per_page: 2,
+                max_per_page: 2,
+            }));
+
+        let res = app
+            .oneshot(Request::get("/n?page=23").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.headers()["x-total-count"], "45");
+        assert!(res.headers()[LINK]
+            .to_str()
+            .unwrap()
+            .contains("page=22&per_page=2>; rel=\"prev\""));
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            json!({ "items": [44], "page": 23, "per_page": 2, "total": 45, "total_pages": 23 })
+        );
+    }
+}
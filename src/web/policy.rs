@@ -0,0 +1,374 @@
+//! # Route Policies
+//!
+//! Declares what each route group requires as data, enforced by the single
+//! [`enforce_policies`] middleware instead of guard calls in every handler.
+//!
+//! A [`Policies`] value holds [`PolicyRule`]s mapping a path prefix to the
+//! [`Policy`]s it requires. Every rule whose prefix matches the request path
+//! applies, so a group rule (`/admin`) and a narrower route rule
+//! (`/admin/billing`) add up. Prefixes match whole segments: `/admin`
+//! covers `/admin` and `/admin/users`, not `/administrator`.
+//!
+//! | Policy | Passes when |
+//! |--------|-------------|
+//! | `authenticated` | a [`CurrentUser`] extension is present |
+//! | `role:<name>` | the principal has the role |
+//! | `scope:<name>` | the principal was granted the scope |
+//! | `tenant-member` | the [`PolicyResolver`] confirms membership of the request's [`TenantId`] |
+//! | `feature:<flag>` | the [`PolicyResolver`] reports the flag as enabled |
+//!
+//! Failures map to [`AuthzError`]: no principal is `401`, anything else
+//! `403`. Tenant and feature policies fail closed when no resolver is set.
+//!
+//! [`Policies::rules`] and the `Serialize` impls make the table available
+//! for documentation, e.g. from an admin endpoint.
+//!
+//! # Example
+//! ```rust,no_run
+//! use axum::{middleware::from_fn_with_state, routing::get, Router};
+//! use wzs_web::web::policy::{enforce_policies, Policies, Policy};
+//!
+//! let policies = Policies::new()
+//!     .rule("/admin", [Policy::role("admin")])
+//!     .rule("/admin/billing", [Policy::scope("billing:write")])
+//!     .rule("/account", [Policy::Authenticated]);
+//!
+//! for rule in policies.rules() {
+//!     println!("{rule}");
+//! }
+//!
+//! let app: Router = Router::new()
+//!     .route("/admin/users", get(|| async { "users" }))
+//!     .route("/account", get(|| async { "me" }))
+//!     .layer(from_fn_with_state(policies, enforce_policies));
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Serialize, Serializer};
+
+use crate::auth::authorize::AuthzError;
+use crate::auth::CurrentUser;
+use crate::graphql::context::TenantId;
+
+/// One requirement a route may declare.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Policy {
+    /// Any authenticated principal.
+    Authenticated,
+    /// Principals with the role.
+    Role(String),
+    /// Principals granted the scope.
+    Scope(String),
+    /// Principals belonging to the request's tenant.
+    TenantMember,
+    /// Only while the feature flag is enabled.
+    Feature(String),
+}
+
+impl Policy {
+    /// [`Policy::Role`].
+    pub fn role(role: impl Into<String>) -> Self {
+        Self::Role(role.into())
+    }
+
+    /// [`Policy::Scope`].
+    pub fn scope(scope: impl Into<String>) -> Self {
+        Self::Scope(scope.into())
+    }
+
+    /// [`Policy::Feature`].
+    pub fn feature(flag: impl Into<String>) -> Self {
+        Self::Feature(flag.into())
+    }
+}
+
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Authenticated => f.write_str("authenticated"),
+            Self::Role(r) => write!(f, "role:{r}"),
+            Self::Scope(s) => write!(f, "scope:{s}"),
+            Self::TenantMember => f.write_str("tenant-member"),
+            Self::Feature(flag) => write!(f, "feature:{flag}"),
+        }
+    }
+}
+
+impl Serialize for Policy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Policies required under a path prefix.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PolicyRule {
+    pub prefix: String,
+    pub policies: Vec<Policy>,
+}
+
+impl PolicyRule {
+    /// Whether the rule covers `path` (whole-segment prefix match).
+    pub fn matches(&self, path: &str) -> bool {
+        prefix_matches(&self.prefix, path)
+    }
+}
+
+/// Whether `path` is `prefix` or below it, matching whole segments.
+pub(crate) fn prefix_matches(prefix: &str, path: &str) -> bool {
+    match path.strip_prefix(prefix.trim_end_matches('/')) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+impl fmt::Display for PolicyRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let policies: Vec<String> = self.policies.iter().map(Policy::to_string).collect();
+        write!(f, "{} => {}", self.prefix, policies.join(", "))
+    }
+}
+
+/// Application hooks for policies the crate cannot decide on its own.
+pub trait PolicyResolver: Send + Sync {
+    /// Whether `user` belongs to `tenant`.
+    fn is_tenant_member(&self, user: &CurrentUser, tenant: &str) -> bool;
+
+    /// Whether feature `flag` is enabled (for `tenant`, when known).
+    fn feature_enabled(&self, flag: &str, tenant: Option<&str>) -> bool;
+}
+
+/// The policy table; state of [`enforce_policies`].
+///
+/// Cloning is cheap.
+#[derive(Clone, Default)]
+pub struct Policies {
+    rules: Arc<Vec<PolicyRule>>,
+    resolver: Option<Arc<dyn PolicyResolver>>,
+}
+
+impl Policies {
+    /// An empty table (every request passes).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires `policies` for paths under `prefix`.
+    pub fn rule(
+        mut self,
+        prefix: impl Into<String>,
+        policies: impl IntoIterator<Item = Policy>,
+    ) -> Self {
+        Arc::make_mut(&mut self.rules).push(PolicyRule {
+            prefix: prefix.into(),
+            policies: policies.into_iter().collect(),
+        });
+        self
+    }
+
+    /// Sets the resolver for tenant and feature policies.
+    pub fn resolver(mut self, resolver: Arc<dyn PolicyResolver>) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
+    /// All declared rules, in declaration order.
+    pub fn rules(&self) -> &[PolicyRule] {
+        &self.rules
+    }
+
+    /// The policies applying to `path`.
+    pub fn for_path(&self, path: &str) -> Vec<&Policy> {
+        self.rules
+            .iter()
+            .filter(|r| r.matches(path))
+            .flat_map(|r| &r.policies)
+            .collect()
+    }
+
+    /// Checks a request to `path` by `user` within `tenant`.
+    ///
+    /// A missing principal is only reported as
+    /// [`Unauthenticated`](AuthzError::Unauthenticated) when some policy
+    /// needs one; feature policies alone do not.
+    pub fn check(
+        &self,
+        path: &str,
+        user: Option<&CurrentUser>,
+        tenant: Option<&str>,
+    ) -> Result<(), AuthzError> {
+        let require_user = || user.ok_or(AuthzError::Unauthenticated);
+        for policy in self.for_path(path) {
+            let ok = match policy {
+                Policy::Authenticated => {
+                    require_user()?;
+                    true
+                }
+                Policy::Role(role) => require_user()?.has_role(role),
+                Policy::Scope(scope) => require_user()?.has_scope(scope),
+                Policy::TenantMember => {
+                    let user = require_user()?;
+                    match (tenant, &self.resolver) {
+                        (Some(tenant), Some(r)) => r.is_tenant_member(user, tenant),
+                        _ => false,
+                    }
+                }
+                Policy::Feature(flag) => self
+                    .resolver
+                    .as_ref()
+                    .is_some_and(|r| r.feature_enabled(flag, tenant)),
+            };
+            if !ok {
+                tracing::debug!(path, %policy, "route policy denied request");
+                return Err(AuthzError::Forbidden);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Middleware applying [`Policies`] to every request.
+///
+/// Reads the [`CurrentUser`] and [`TenantId`] request extensions, so mount it
+/// inside the layers that authenticate the request and resolve the tenant:
+/// `from_fn_with_state(policies, enforce_policies)`.
+pub async fn enforce_policies(
+    State(policies): State<Policies>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let user = req.extensions().get::<CurrentUser>();
+    let tenant = req.extensions().get::<TenantId>().map(|t| t.0.as_str());
+    match policies.check(req.uri().path(), user, tenant) {
+        Ok(()) => next.run(req).await,
+        Err(e) => e.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body, http::StatusCode, middleware::from_fn_with_state, routing::get, Extension,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    struct Resolver;
+
+    impl PolicyResolver for Resolver {
+        fn is_tenant_member(&self, user: &CurrentUser, tenant: &str) -> bool {
+            user.has_role(&format!("member:{tenant}"))
+        }
+
+        fn feature_enabled(&self, flag: &str, tenant: Option<&str>) -> bool {
+            flag == "beta" && tenant == Some("acme")
+        }
+    }
+
+    fn policies() -> Policies {
+        Policies::new()
+            .rule("/admin", [Policy::role("admin")])
+            .rule("/admin/billing/", [Policy::scope("billing:write")])
+            .rule("/t", [Policy::TenantMember])
+            .rule("/beta", [Policy::feature("beta")])
+    }
+
+    #[test]
+    fn rules_accumulate_by_segment_prefix() {
+        let p = policies();
+        assert_eq!(p.for_path("/admin/billing/x").len(), 2);
+        assert_eq!(p.for_path("/admin").len(), 1);
+        assert!(p.for_path("/administrator").is_empty());
+
+        let admin = CurrentUser::new("1").with_roles(["admin"]);
+        assert_eq!(p.check("/public", None, None), Ok(()));
+        assert_eq!(
+            p.check("/admin", None, None),
+            Err(AuthzError::Unauthenticated)
+        );
+        assert_eq!(p.check("/admin/users", Some(&admin), None), Ok(()));
+        assert_eq!(
+            p.check("/admin/billing", Some(&admin), None),
+            Err(AuthzError::Forbidden)
+        );
+        let billing = admin.with_scopes(["billing:write"]);
+        assert_eq!(p.check("/admin/billing", Some(&billing), None), Ok(()));
+    }
+
+    #[test]
+    fn tenant_and_feature_policies_use_the_resolver() {
+        let member = CurrentUser::new("1").with_roles(["member:acme"]);
+        let unresolved = policies();
+        assert_eq!(
+            unresolved.check("/t", Some(&member), Some("acme")),
+            Err(AuthzError::Forbidden)
+        );
+        assert_eq!(
+            unresolved.check("/beta", None, Some("acme")),
+            Err(AuthzError::Forbidden)
+        );
+
+        let p = policies().resolver(Arc::new(Resolver));
+        assert_eq!(p.check("/t", Some(&member), Some("acme")), Ok(()));
+        assert_eq!(
+            p.check("/t", Some(&member), Some("other")),
+            Err(AuthzError::Forbidden)
+        );
+        assert_eq!(
+            p.check("/t", Some(&member), None),
+            Err(AuthzError::Forbidden)
+        );
+        assert_eq!(p.check("/beta", None, Some("acme")), Ok(()));
+    }
+
+    #[test]
+    fn rules_are_introspectable() {
+        let p = policies();
+        assert_eq!(p.rules()[0].to_string(), "/admin => role:admin");
+        let json = serde_json::to_value(p.rules()).unwrap();
+        assert_eq!(json[3]["policies"][0], "feature:beta");
+        assert_eq!(json[2]["prefix"], "/t");
+    }
+
+    #[tokio::test]
+    async fn middleware_reads_user_and_tenant_extensions() {
+        let app = Router::new()
+            .route("/t/page", get(|| async { "ok" }))
+            .layer(from_fn_with_state(
+                policies().resolver(Arc::new(Resolver)),
+                enforce_policies,
+            ));
+        let status = |user: Option<CurrentUser>| {
+            let mut app = app.clone().layer(Extension(TenantId("acme".into())));
+            if let Some(user) = user {
+                app = app.layer(Extension(user));
+            }
+            async move {
+                app.oneshot(
+                    axum::http::Request::get("/t/page")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+                .status()
+            }
+        };
+
+        assert_eq!(status(None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            status(Some(CurrentUser::new("1"))).await,
+            StatusCode::FORBIDDEN
+        );
+        let member = CurrentUser::new("1").with_roles(["member:acme"]);
+        assert_eq!(status(Some(member)).await, StatusCode::OK);
+    }
+}
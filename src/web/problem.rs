@@ -0,0 +1,372 @@
+//! # Problem Details (RFC 7807)
+//!
+//! [`Problem`] is the error type for JSON APIs. It renders as
+//! `application/problem+json`:
+//!
+//! ```json
+//! {
+//!   "type": "about:blank",
+//!   "title": "Unprocessable Entity",
+//!   "status": 422,
+//!   "detail": "The request contains invalid fields.",
+//!   "request_id": "0190f5d2-…",
+//!   "errors": [{ "field": "email", "message": "is not a valid address" }]
+//! }
+//! ```
+//!
+//! Common errors convert with `?`:
+//!
+//! | Source | Status |
+//! |--------|--------|
+//! | [`NotFoundError`] | `404` |
+//! | [`PasswordRejection`] | `422`, one `password` field error per violation |
+//! | [`FormRejection`] | `400` |
+//! | [`anyhow::Error`] | the wrapped `Problem` / `NotFoundError` if any, else `500` |
+//!
+//! Internal errors are logged and answered without detail, so messages from
+//! the database or other backends never reach clients.
+//!
+//! The [`request_id`](crate::web::middleware::request_id::request_id)
+//! middleware adds the request ID to problems that do not carry one.
+//!
+//! # Example
+//! ```rust
+//! use axum::{extract::Path, routing::get, Json, Router};
+//! use wzs_web::error::entity::NotFoundError;
+//! use wzs_web::web::problem::{ApiResult, Problem};
+//!
+//! async fn show(Path(id): Path<u64>) -> ApiResult<Json<u64>> {
+//!     if id == 0 {
+//!         return Err(Problem::bad_request("id must be positive"));
+//!     }
+//!     if id > 100 {
+//!         return Err(NotFoundError::new("User").into());
+//!     }
+//!     Ok(Json(id))
+//! }
+//!
+//! let app: Router = Router::new().route("/users/{id}", get(show));
+//! # let _ = app;
+//! ```
+
+use std::collections::BTreeMap;
+
+use axum::{
+    body::Body,
+    http::{
+        header::{CONTENT_LENGTH, CONTENT_TYPE},
+        HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::auth::password_policy::PasswordRejection;
+use crate::error::entity::NotFoundError;
+use crate::web::forms::FormRejection;
+
+/// Media type of problem responses.
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// `Result` of API handlers.
+pub type ApiResult<T> = Result<T, Problem>;
+
+/// One invalid input field.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    /// Field name as sent by the client (e.g. `"email"`, `"items[0].qty"`).
+    pub field: String,
+    pub message: String,
+    /// Machine-readable reason (e.g. `"too_short"`), for localized messages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// Values the message refers to (e.g. `min`), for localized messages.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+            code: None,
+            params: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the machine-readable reason.
+    pub fn code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// Adds a value the message refers to.
+    pub fn param(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.params.insert(name.into(), value.to_string());
+        self
+    }
+}
+
+/// RFC 7807 problem details; see the [module docs](self).
+#[derive(Clone, Debug, PartialEq, Eq, Error, Serialize)]
+#[error("{title}")]
+pub struct Problem {
+    /// URI identifying the problem type (`about:blank` = the status alone).
+    #[serde(rename = "type")]
+    pub type_uri: String,
+    /// Short summary of the problem type.
+    pub title: String,
+    pub status: u16,
+    /// Explanation specific to this occurrence.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// URI of this occurrence (e.g. the request path).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Invalid fields of a validation problem.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+impl Problem {
+    /// Problem of type `about:blank` titled with the canonical reason of
+    /// `status`.
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            type_uri: "about:blank".into(),
+            title: status.canonical_reason().unwrap_or("Error").into(),
+            status: status.as_u16(),
+            detail: None,
+            instance: None,
+            request_id: None,
+            errors: Vec::new(),
+        }
+    }
+
+    /// `400 Bad Request`.
+    pub fn bad_request(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST).detail(detail)
+    }
+
+    /// `401 Unauthorized`.
+    pub fn unauthorized() -> Self {
+        Self::new(StatusCode::UNAUTHORIZED)
+    }
+
+    /// `403 Forbidden`.
+    pub fn forbidden() -> Self {
+        Self::new(StatusCode::FORBIDDEN)
+    }
+
+    /// `404 Not Found`.
+    pub fn not_found(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND).detail(detail)
+    }
+
+    /// `409 Conflict`.
+    pub fn conflict(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT).detail(detail)
+    }
+
+    /// `422 Unprocessable Entity` listing the invalid fields.
+    pub fn validation(errors: impl IntoIterator<Item = FieldError>) -> Self {
+        let mut problem = Self::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .detail("The request contains invalid fields.");
+        problem.errors = errors.into_iter().collect();
+        problem
+    }
+
+    /// `500 Internal Server Error`, without detail.
+    pub fn internal() -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// Sets the occurrence-specific explanation.
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Sets the problem type URI and title.
+    pub fn type_uri(mut self, uri: impl Into<String>, title: impl Into<String>) -> Self {
+        self.type_uri = uri.into();
+        self.title = title.into();
+        self
+    }
+
+    /// Sets the occurrence URI.
+    pub fn instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Sets the request ID.
+    pub fn request_id(mut self, id: impl Into<String>) -> Self {
+        self.request_id = Some(id.into());
+        self
+    }
+
+    /// Adds an invalid field.
+    pub fn field_error(mut self, error: FieldError) -> Self {
+        self.errors.push(error);
+        self
+    }
+
+    /// HTTP status.
+    pub fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// Replaces the body of `res`, a response rendered from a problem, with
+    /// this problem; headers set since are kept.
+    pub(crate) fn rewrite(self, mut res: Response) -> Response {
+        let body = serde_json::to_vec(&self).unwrap_or_default();
+        res.headers_mut().remove(CONTENT_LENGTH);
+        res.extensions_mut().insert(self);
+        *res.body_mut() = Body::from(body);
+        res
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        let body = serde_json::to_vec(&self).unwrap_or_default();
+        let mut res = (self.status_code(), body).into_response();
+        res.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_CONTENT_TYPE));
+        // Lets outer middleware (request ID) amend the body.
+        res.extensions_mut().insert(self);
+        res
+    }
+}
+
+impl From<NotFoundError> for Problem {
+    fn from(e: NotFoundError) -> Self {
+        Self::not_found(e.to_string())
+    }
+}
+
+impl From<PasswordRejection> for Problem {
+    fn from(e: PasswordRejection) -> Self {
+        Self::validation(e.0.iter().map(|v| {
+            let code = serde_json::to_value(v)
+                .ok()
+                .and_then(|j| j["code"].as_str().map(str::to_string));
+            let error = FieldError::new("password", format!("Password {v}"));
+            match code {
+                Some(code) => error.code(code),
+                None => error,
+            }
+        }))
+    }
+}
+
+impl From<FormRejection> for Problem {
+    fn from(e: FormRejection) -> Self {
+        Self::bad_request(e.to_string())
+    }
+}
+
+impl From<anyhow::Error> for Problem {
+    fn from(e: anyhow::Error) -> Self {
+        let e = match e.downcast::<Problem>() {
+            Ok(problem) => return problem,
+            Err(e) => e,
+        };
+        if let Some(not_found) = e.downcast_ref::<NotFoundError>() {
+            return Self::not_found(not_found.to_string());
+        }
+        tracing::error!(error = %format!("{e:#}"), "internal error");
+        Self::internal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::anyhow;
+    use http_body_util::BodyExt;
+    use serde_json::{json, Value};
+
+    use crate::auth::password_policy::PasswordViolation;
+
+    async fn render(problem: Problem) -> (StatusCode, String, Value) {
+        let res = problem.into_response();
+        let content_type = res.headers()[CONTENT_TYPE].to_str().unwrap().to_string();
+        let status = res.status();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        (status, content_type, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn renders_problem_json() {
+        let (status, content_type, body) = render(
+            Problem::conflict("email already registered")
+                .instance("/users")
+                .request_id("req-1"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(content_type, PROBLEM_CONTENT_TYPE);
+        assert_eq!(
+            body,
+            json!({
+                "type": "about:blank",
+                "title": "Conflict",
+                "status": 409,
+                "detail": "email already registered",
+                "instance": "/users",
+                "request_id": "req-1",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn validation_lists_fields() {
+        let problem = Problem::from(PasswordRejection(vec![PasswordViolation::TooShort {
+            min: 10,
+        }]))
+        .field_error(FieldError::new("email", "is required"));
+        let (status, _, body) = render(problem).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            body["errors"],
+            json!([
+                {
+                    "field": "password",
+                    "message": "Password must be at least 10 characters",
+                    "code": "too_short",
+                },
+                { "field": "email", "message": "is required" },
+            ])
+        );
+    }
+
+    #[test]
+    fn converts_errors() {
+        let p: Problem = NotFoundError::new("User").into();
+        assert_eq!(
+            (p.status, p.detail.as_deref()),
+            (404, Some("User not found"))
+        );
+
+        let p: Problem = anyhow::Error::new(NotFoundError::new("Order"))
+            .context("loading order")
+            .into();
+        assert_eq!(p.status, 404);
+
+        let p: Problem = anyhow::Error::new(Problem::forbidden()).into();
+        assert_eq!(p, Problem::forbidden());
+
+        let p: Problem = anyhow!("db password=hunter2 rejected").into();
+        assert_eq!(p, Problem::internal());
+
+        let p: Problem = FormRejection::TooFast.into();
+        assert_eq!(p.status, 400);
+    }
+}
@@ -0,0 +1,558 @@
+//! # Rate Limiting
+//!
+//! Token-bucket request limiting keyed by client IP, authenticated subject or
+//! API key.
+//!
+//! - [`RateLimit`] — bucket size (`burst`) and the `period` over which an
+//!   empty bucket refills; [`RateLimit::take`] is the token-bucket step
+//! - [`KeyBy`] — how requests are grouped into buckets
+//! - [`RateLimitStore`] — bucket storage port, with the in-process
+//!   [`MemoryRateLimitStore`]; a shared store (Redis, …) implements the same
+//!   trait
+//! - [`RateLimiter`] + [`rate_limit`] — middleware with a default limit and
+//!   per-route overrides; limited requests get `429 Too Many Requests` with
+//!   `Retry-After`
+//!
+//! Routes are matched by path prefix (the longest wins) and each route has
+//! its own buckets. Requests without a key (e.g. anonymous requests under
+//! [`KeyBy::Subject`]) are not limited. If the store fails the request is let
+//! through and the error logged, so an outage of a shared store does not take
+//! the application down.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use axum::{middleware::from_fn_with_state, routing::{get, post}, Router};
+//! use wzs_web::web::rate_limit::{rate_limit, KeyBy, MemoryRateLimitStore, RateLimit, RateLimiter};
+//!
+//! let limiter = RateLimiter::new(Arc::new(MemoryRateLimitStore::new()))
+//!     .limit(RateLimit::per_minute(300))
+//!     .route("/login", RateLimit::per_minute(10))
+//!     .route_keyed("/api", RateLimit::per_second(20), KeyBy::api_key("x-api-key"))
+//!     .exclude("/healthz");
+//!
+//! let app: Router = Router::new()
+//!     .route("/", get(|| async { "ok" }))
+//!     .route("/login", post(|| async { "ok" }))
+//!     .layer(from_fn_with_state(limiter, rate_limit));
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::{
+    extract::{Request, State},
+    http::{header::RETRY_AFTER, HeaderName, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::auth::CurrentUser;
+use crate::web::client_ip::ClientIp;
+use crate::web::problem::Problem;
+
+/// Default number of buckets [`MemoryRateLimitStore`] keeps before dropping
+/// refilled ones.
+pub const DEFAULT_MAX_KEYS: usize = 100_000;
+
+/// Token-bucket parameters: up to `burst` requests at once, refilled at
+/// `burst` per `period`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Bucket size (minimum 1).
+    pub burst: u32,
+    /// Time for an empty bucket to refill completely.
+    pub period: Duration,
+}
+
+impl RateLimit {
+    /// `burst` requests per `period`.
+    pub fn new(burst: u32, period: Duration) -> Self {
+        Self {
+            burst: burst.max(1),
+            period,
+        }
+    }
+
+    /// `n` requests per second.
+    pub fn per_second(n: u32) -> Self {
+        Self::new(n, Duration::from_secs(1))
+    }
+
+    /// `n` requests per minute.
+    pub fn per_minute(n: u32) -> Self {
+        Self::new(n, Duration::from_secs(60))
+    }
+
+    /// `n` requests per hour.
+    pub fn per_hour(n: u32) -> Self {
+        Self::new(n, Duration::from_secs(3600))
+    }
+
+    /// Tokens added per second.
+    fn rate(&self) -> f64 {
+        f64::from(self.burst) / self.period.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// Takes one token from a bucket in `state` (`None` = full) at `now`,
+    /// returning the new state and the decision.
+    pub fn take(&self, state: Option<BucketState>, now: DateTime<Utc>) -> (BucketState, Decision) {
+        let burst = f64::from(self.burst);
+        let tokens = match state {
+            Some(s) => {
+                let elapsed = (now - s.updated).to_std().unwrap_or_default();
+                (s.tokens + elapsed.as_secs_f64() * self.rate()).min(burst)
+            }
+            None => burst,
+        };
+
+        if tokens >= 1.0 {
+            let tokens = tokens - 1.0;
+            let state = BucketState {
+                tokens,
+                updated: now,
+            };
+            let remaining = tokens.floor() as u32;
+            (state, Decision::Allowed { remaining })
+        } else {
+            let state = BucketState {
+                tokens,
+                updated: now,
+            };
+            let retry_after = Duration::from_secs_f64((1.0 - tokens) / self.rate());
+            (state, Decision::Limited { retry_after })
+        }
+    }
+}
+
+/// Stored state of one bucket.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BucketState {
+    /// Tokens left at `updated`.
+    pub tokens: f64,
+    /// Time of the last [`RateLimit::take`].
+    pub updated: DateTime<Utc>,
+}
+
+/// Outcome of taking a token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decision {
+    /// The request may proceed; `remaining` whole tokens are left.
+    Allowed { remaining: u32 },
+    /// The bucket is empty; a token is available after `retry_after`.
+    Limited { retry_after: Duration },
+}
+
+/// Bucket storage.
+///
+/// Implementations must apply [`RateLimit::take`] atomically per key, so
+/// concurrent requests cannot spend the same token.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Takes one token from the bucket `key` at `now`.
+    async fn acquire(&self, key: &str, limit: &RateLimit, now: DateTime<Utc>) -> Result<Decision>;
+}
+
+/// In-process [`RateLimitStore`] (single instance / tests).
+///
+/// Once it holds [`max_keys`](Self::max_keys) buckets, buckets that have
+/// refilled completely are dropped.
+pub struct MemoryRateLimitStore {
+    buckets: Mutex<HashMap<String, (BucketState, Duration)>>,
+    max_keys: usize,
+}
+
+impl Default for MemoryRateLimitStore {
+    fn default() -> Self {
+        Self {
+            buckets: Mutex::new(HashMap::new()),
+            max_keys: DEFAULT_MAX_KEYS,
+        }
+    }
+}
+
+impl MemoryRateLimitStore {
+    /// Creates an empty store keeping up to [`DEFAULT_MAX_KEYS`] buckets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of buckets kept before refilled ones are dropped.
+    pub fn max_keys(mut self, n: usize) -> Self {
+        self.max_keys = n;
+        self
+    }
+
+    /// Number of stored buckets.
+    pub fn len(&self) -> usize {
+        self.buckets.lock().unwrap().len()
+    }
+
+    /// Whether no bucket is stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryRateLimitStore {
+    async fn acquire(&self, key: &str, limit: &RateLimit, now: DateTime<Utc>) -> Result<Decision> {
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= self.max_keys && !buckets.contains_key(key) {
+            buckets.retain(|_, (state, period)| {
+                (now - state.updated).to_std().unwrap_or_default() < *period
+            });
+        }
+        let current = buckets.get(key).map(|(state, _)| *state);
+        let (state, decision) = limit.take(current, now);
+        buckets.insert(key.to_string(), (state, limit.period));
+        Ok(decision)
+    }
+}
+
+type KeyFn = Arc<dyn Fn(&Request) -> Option<String> + Send + Sync>;
+
+/// How requests are grouped into buckets.
+#[derive(Clone)]
+pub enum KeyBy {
+    /// Client address: the [`ClientIp`] extension, else
+    /// `ConnectInfo<SocketAddr>`.
+    Ip,
+    /// Subject of the [`CurrentUser`] extension; anonymous requests are not
+    /// limited.
+    Subject,
+    /// Subject of the [`CurrentUser`] extension, else the client address.
+    SubjectOrIp,
+    /// Value of a request header such as `X-API-Key`. The value is hashed,
+    /// so keys are never written to the store.
+    ApiKey(HeaderName),
+    /// Application-defined key.
+    Custom(KeyFn),
+}
+
+impl std::fmt::Debug for KeyBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ip => f.write_str("Ip"),
+            Self::Subject => f.write_str("Subject"),
+            Self::SubjectOrIp => f.write_str("SubjectOrIp"),
+            Self::ApiKey(name) => f.debug_tuple("ApiKey").field(name).finish(),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+impl KeyBy {
+    /// [`KeyBy::ApiKey`] for the header `name`.
+    ///
+    /// # Panics
+    /// Panics if `name` is not a valid header name.
+    pub fn api_key(name: &str) -> Self {
+        Self::ApiKey(HeaderName::from_bytes(name.as_bytes()).expect("invalid header name"))
+    }
+
+    /// [`KeyBy::Custom`] from a closure.
+    pub fn custom<F>(f: F) -> Self
+    where
+        F: Fn(&Request) -> Option<String> + Send + Sync + 'static,
+    {
+        Self::Custom(Arc::new(f))
+    }
+
+    /// Bucket key of `req`, or `None` if it is not limited.
+    pub fn key(&self, req: &Request) -> Option<String> {
+        match self {
+            Self::Ip => ip(req),
+            Self::Subject => subject(req),
+            Self::SubjectOrIp => subject(req).or_else(|| ip(req)),
+            Self::ApiKey(name) => {
+                let value = req.headers().get(name)?.as_bytes();
+                let digest = Sha256::digest(value);
+                Some(format!("key:{}", URL_SAFE_NO_PAD.encode(&digest[..16])))
+            }
+            Self::Custom(f) => f(req).map(|k| format!("custom:{k}")),
+        }
+    }
+}
+
+fn ip(req: &Request) -> Option<String> {
+    ClientIp::of(req.extensions()).map(|ip| format!("ip:{ip}"))
+}
+
+fn subject(req: &Request) -> Option<String> {
+    req.extensions()
+        .get::<CurrentUser>()
+        .map(|u| format!("sub:{}", u.subject))
+}
+
+#[derive(Clone, Debug)]
+struct Rule {
+    prefix: String,
+    limit: Option<RateLimit>,
+    key_by: Option<KeyBy>,
+}
+
+/// Limiter configuration and store; the state of [`rate_limit`].
+///
+/// Cloning is cheap; clones share the store.
+#[derive(Clone)]
+pub struct RateLimiter {
+    store: Arc<dyn RateLimitStore>,
+    limit: Option<RateLimit>,
+    key_by: KeyBy,
+    rules: Vec<Rule>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter keyed by [`KeyBy::Ip`] with no default limit.
+    pub fn new(store: Arc<dyn RateLimitStore>) -> Self {
+        Self {
+            store,
+            limit: None,
+            key_by: KeyBy::Ip,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Limit for requests not matching a [`route`](Self::route).
+    pub fn limit(mut self, limit: RateLimit) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Key for the default limit and for routes without their own.
+    pub fn key_by(mut self, key_by: KeyBy) -> Self {
+        self.key_by = key_by;
+        self
+    }
+
+    /// Applies `limit` to paths that are `prefix` or lie below it.
+    pub fn route(self, prefix: impl Into<String>, limit: RateLimit) -> Self {
+        self.rule(prefix.into(), Some(limit), None)
+    }
+
+    /// [`route`](Self::route) with its own key.
+    pub fn route_keyed(self, prefix: impl Into<String>, limit: RateLimit, key_by: KeyBy) -> Self {
+        self.rule(prefix.into(), Some(limit), Some(key_by))
+    }
+
+    /// Exempts paths that are `prefix` or lie below it.
+    pub fn exclude(self, prefix: impl Into<String>) -> Self {
+        self.rule(prefix.into(), None, None)
+    }
+
+    fn rule(mut self, prefix: String, limit: Option<RateLimit>, key_by: Option<KeyBy>) -> Self {
+        let prefix = prefix.trim_end_matches('/').to_string();
+        self.rules.retain(|r| r.prefix != prefix);
+        self.rules.push(Rule {
+            prefix,
+            limit,
+            key_by,
+        });
+        self
+    }
+
+    /// Scope, limit and key applying to `path`.
+    fn lookup(&self, path: &str) -> Option<(&str, RateLimit, &KeyBy)> {
+        let rule = self
+            .rules
+            .iter()
+            .filter(|r| {
+                path.strip_prefix(r.prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .max_by_key(|r| r.prefix.len());
+        match rule {
+            Some(rule) => Some((
+                rule.prefix.as_str(),
+                rule.limit?,
+                rule.key_by.as_ref().unwrap_or(&self.key_by),
+            )),
+            None => Some(("*", self.limit?, &self.key_by)),
+        }
+    }
+
+    /// Bucket key and limit applying to `req`; `None` when the request is
+    /// not limited.
+    pub fn bucket(&self, req: &Request) -> Option<(String, RateLimit)> {
+        let (scope, limit, key_by) = self.lookup(req.uri().path())?;
+        let key = key_by.key(req)?;
+        Some((format!("{scope}|{key}"), limit))
+    }
+
+    /// Takes a token from the bucket `key` at `now`.
+    pub async fn acquire(
+        &self,
+        key: &str,
+        limit: &RateLimit,
+        now: DateTime<Utc>,
+    ) -> Result<Decision> {
+        self.store.acquire(key, limit, now).await
+    }
+}
+
+/// Middleware enforcing a [`RateLimiter`].
+///
+/// Mount with `from_fn_with_state(limiter, rate_limit)`, inside the
+/// middleware setting [`ClientIp`] / [`CurrentUser`] when keying by them.
+pub async fn rate_limit(State(limiter): State<RateLimiter>, req: Request, next: Next) -> Response {
+    let Some((key, limit)) = limiter.bucket(&req) else {
+        return next.run(req).await;
+    };
+    match limiter.acquire(&key, &limit, Utc::now()).await {
+        Ok(Decision::Limited { retry_after }) => limited(retry_after),
+        Ok(Decision::Allowed { .. }) => next.run(req).await,
+        Err(e) => {
+            tracing::error!(error = %e, "rate limit store failed");
+            next.run(req).await
+        }
+    }
+}
+
+fn limited(retry_after: Duration) -> Response {
+    let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    (
+        [(RETRY_AFTER, secs.max(1).to_string())],
+        Problem::new(StatusCode::TOO_MANY_REQUESTS).detail("too many requests"),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{body::Body, middleware::from_fn_with_state, routing::get, Extension, Router};
+    use chrono::TimeZone;
+    use tower::ServiceExt;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    #[test]
+    fn bucket_allows_burst_then_refills() {
+        let limit = RateLimit::new(2, Duration::from_secs(2));
+
+        let (s, d) = limit.take(None, at(0));
+        assert_eq!(d, Decision::Allowed { remaining: 1 });
+        let (s, d) = limit.take(Some(s), at(0));
+        assert_eq!(d, Decision::Allowed { remaining: 0 });
+        let (s, d) = limit.take(Some(s), at(0));
+        assert_eq!(
+            d,
+            Decision::Limited {
+                retry_after: Duration::from_secs(1)
+            }
+        );
+
+        let (s, d) = limit.take(Some(s), at(1));
+        assert_eq!(d, Decision::Allowed { remaining: 0 });
+        let (_, d) = limit.take(Some(s), at(100));
+        assert_eq!(d, Decision::Allowed { remaining: 1 });
+    }
+
+    #[tokio::test]
+    async fn memory_store_drops_refilled_buckets_when_full() {
+        let store = MemoryRateLimitStore::new().max_keys(2);
+        let limit = RateLimit::per_second(1);
+        store.acquire("a", &limit, at(0)).await.unwrap();
+        store.acquire("b", &limit, at(5)).await.unwrap();
+        assert_eq!(
+            store.acquire("b", &limit, at(5)).await.unwrap(),
+            Decision::Limited {
+                retry_after: Duration::from_secs(1)
+            }
+        );
+
+        store.acquire("c", &limit, at(5)).await.unwrap();
+        assert_eq!(store.len(), 2, "a was full again and dropped");
+    }
+
+    #[test]
+    fn keys_hash_api_keys() {
+        let req = Request::get("/")
+            .header("x-api-key", "secret")
+            .extension(ClientIp("203.0.113.7".parse().unwrap()))
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(KeyBy::Ip.key(&req).as_deref(), Some("ip:203.0.113.7"));
+        assert_eq!(KeyBy::Subject.key(&req), None);
+        assert_eq!(KeyBy::SubjectOrIp.key(&req), KeyBy::Ip.key(&req));
+
+        let key = KeyBy::api_key("X-API-Key").key(&req).unwrap();
+        assert!(key.starts_with("key:"));
+        assert!(!key.contains("secret"));
+
+        let custom = KeyBy::custom(|r| Some(r.uri().path().to_string()));
+        assert_eq!(custom.key(&req).as_deref(), Some("custom:/"));
+    }
+
+    fn app(limiter: RateLimiter) -> Router {
+        Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route("/login", get(|| async { "ok" }))
+            .route("/login/verify", get(|| async { "ok" }))
+            .route("/healthz", get(|| async { "ok" }))
+            .layer(from_fn_with_state(limiter, rate_limit))
+            .layer(Extension(ClientIp("203.0.113.7".parse().unwrap())))
+    }
+
+    async fn status(app: &Router, path: &str) -> Response {
+        let req = Request::get(path).body(Body::empty()).unwrap();
+        app.clone().oneshot(req).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn routes_have_their_own_limits() {
+        let app = app(RateLimiter::new(Arc::new(MemoryRateLimitStore::new()))
+            .limit(RateLimit::per_minute(2))
+            .route("/login/", RateLimit::per_minute(1))
+            .exclude("/healthz"));
+
+        assert_eq!(status(&app, "/login").await.status(), StatusCode::OK);
+        let res = status(&app, "/login/verify").await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers()[RETRY_AFTER], "60");
+
+        assert_eq!(status(&app, "/").await.status(), StatusCode::OK);
+        assert_eq!(status(&app, "/").await.status(), StatusCode::OK);
+        assert_eq!(
+            status(&app, "/").await.status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        for _ in 0..5 {
+            assert_eq!(status(&app, "/healthz").await.status(), StatusCode::OK);
+        }
+    }
+
+    #[tokio::test]
+    async fn unkeyed_requests_and_store_failures_pass() {
+        struct Broken;
+
+        #[async_trait]
+        impl RateLimitStore for Broken {
+            async fn acquire(&self, _: &str, _: &RateLimit, _: DateTime<Utc>) -> Result<Decision> {
+                anyhow::bail!("store down")
+            }
+        }
+
+        let broken = app(RateLimiter::new(Arc::new(Broken)).limit(RateLimit::per_minute(1)));
+        for _ in 0..3 {
+            assert_eq!(status(&broken, "/").await.status(), StatusCode::OK);
+        }
+
+        let anonymous = app(RateLimiter::new(Arc::new(MemoryRateLimitStore::new()))
+            .limit(RateLimit::per_minute(1))
+            .key_by(KeyBy::Subject));
+        for _ in 0..3 {
+            assert_eq!(status(&anonymous, "/").await.status(), StatusCode::OK);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! # Streaming Responses
+//!
+//! [`ndjson_stream`] turns a [`Stream`] of serializable items into a chunked
+//! `application/x-ndjson` response (one JSON document per line), for large
+//! export and sync endpoints that should not buffer the whole result.
+//!
+//! Backpressure: the body is polled only as fast as the client reads, so the
+//! source stream is never drained ahead of the socket. For blocking sources
+//! such as [`Db::fetch_stream`](crate::db::port::Db::fetch_stream),
+//! [`spawn_blocking_stream`] runs the producer on the blocking pool behind a
+//! bounded channel; the producer blocks when the channel is full and stops
+//! once the client disconnects.
+//!
+//! The status line is sent before the first item, so an error part-way
+//! through cannot change it; the error is logged and the connection is
+//! closed, leaving the client with a truncated body (no trailing newline).
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use axum::{response::Response, Extension};
+//! use serde::Serialize;
+//! use wzs_web::db::port::Db;
+//! use wzs_web::web::respond::{ndjson_stream, spawn_blocking_stream};
+//!
+//! #[derive(Serialize)]
+//! struct User {
+//!     id: u64,
+//!     email: String,
+//! }
+//!
+//! async fn export_users(Extension(db): Extension<Arc<dyn Db>>) -> Response {
+//!     let users = spawn_blocking_stream(256, move |tx| {
+//!         for row in db.fetch_stream("SELECT id, email FROM users ORDER BY id", &[])? {
+//!             let row = row?;
+//!             let user = User { id: row.get_u64("id")?, email: row.get_string("email")? };
+//!             if !tx.send(user) {
+//!                 break; // client went away
+//!             }
+//!         }
+//!         Ok(())
+//!     });
+//!     ndjson_stream(users)
+//! }
+//! ```
+
+use axum::{
+    body::{Body, Bytes},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use futures_util::{stream, Stream, StreamExt};
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+/// Content type of [`ndjson_stream`] responses.
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Builds a chunked NDJSON response from `items`.
+///
+/// Each item is serialized as one line. A stream error or serialization
+/// failure aborts the body (see the [module docs](self)).
+pub fn ndjson_stream<S, T, E>(items: S) -> Response
+where
+    S: Stream<Item = Result<T, E>> + Send + 'static,
+    T: Serialize,
+    E: std::fmt::Display,
+{
+    let lines = items.map(|item| -> Result<Bytes, std::io::Error> {
+        let item = item.map_err(|e| {
+            warn!("ndjson stream aborted: {e}");
+            std::io::Error::other(e.to_string())
+        })?;
+        let mut line = serde_json::to_vec(&item).map_err(|e| {
+            warn!("ndjson serialization failed: {e}");
+            std::io::Error::other(e)
+        })?;
+        line.push(b'\n');
+        Ok(Bytes::from(line))
+    });
+
+    (
+        [
+            (header::CONTENT_TYPE, NDJSON_CONTENT_TYPE),
+            (header::CACHE_CONTROL, "no-store"),
+        ],
+        Body::from_stream(lines),
+    )
+        .into_response()
+}
+
+/// Producer handle passed to [`spawn_blocking_stream`].
+pub struct StreamSender<T> {
+    tx: mpsc::Sender<anyhow::Result<T>>,
+}
+
+impl<T> StreamSender<T> {
+    /// Sends one item, blocking while the buffer is full.
+    ///
+    /// Returns `false` once the consumer is gone; the producer should stop.
+    pub fn send(&self, item: T) -> bool {
+        self.tx.blocking_send(Ok(item)).is_ok()
+    }
+}
+
+/// Runs `produce` on the blocking thread pool and streams what it sends.
+///
+/// At most `buffer` items are queued ahead of the consumer. An error
+/// returned by `produce` becomes the stream's last item.
+pub fn spawn_blocking_stream<T, F>(
+    buffer: usize,
+    produce: F,
+) -> impl Stream<Item = anyhow::Result<T>> + Send + 'static
+where
+    T: Send + 'static,
+    F: FnOnce(&StreamSender<T>) -> anyhow::Result<()> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(buffer.max(1));
+    tokio::task::spawn_blocking(move || {
+        let sender = StreamSender { tx };
+        if let Err(e) = produce(&sender) {
+            let _ = sender.tx.blocking_send(Err(e));
+        }
+    });
+    stream::unfold(rx, |mut rx| async move {
+        let item = rx.recv().await?;
+        Some((item, rx))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use anyhow::anyhow;
+    use http_body_util::BodyExt;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Item {
+        id: u32,
+    }
+
+    #[tokio::test]
+    async fn writes_one_json_document_per_line() {
+        let items = stream::iter((1..=3).map(|id| Ok::<_, anyhow::Error>(Item { id })));
+        let res = ndjson_stream(items);
+
+        assert_eq!(res.headers()[header::CONTENT_TYPE], NDJSON_CONTENT_TYPE);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"{\"id\":1}\n{\"id\":2}\n{\"id\":3}\n");
+    }
+
+    #[tokio::test]
+    async fn stream_error_aborts_the_body() {
+        let items = stream::iter(vec![Ok(Item { id: 1 }), Err(anyhow!("db gone"))]);
+        let res = ndjson_stream(items);
+        assert!(res.into_body().collect().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn blocking_producer_streams_items_and_errors() {
+        let items = spawn_blocking_stream(2, |tx| {
+            for id in 0..5 {
+                tx.send(id);
+            }
+            Err(anyhow!("boom"))
+        });
+        let got: Vec<_> = items.collect().await;
+        assert_eq!(got.len(), 6);
+        assert_eq!(*got[4].as_ref().unwrap(), 4);
+        assert!(got[5].is_err());
+    }
+
+    #[tokio::test]
+    async fn blocking_producer_stops_when_consumer_drops() {
+        let produced = Arc::new(AtomicUsize::new(0));
+        let counter = produced.clone();
+        let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+
+        let items = spawn_blocking_stream(1, move |tx| {
+            for id in 0..1_000 {
+                if !tx.send(id) {
+                    break;
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+            let _ = done_tx.send(());
+            Ok(())
+        });
+        let first: Vec<_> = Box::pin(items).take(1).collect().await;
+        assert_eq!(first.len(), 1);
+
+        done_rx.await.unwrap();
+        assert!(produced.load(Ordering::SeqCst) < 10);
+    }
+}
@@ -0,0 +1,346 @@
+//! # Signed and Encrypted Cookies
+//!
+//! Protects cookie values the browser should not read or forge, e.g. a JWT
+//! that would otherwise sit in a plaintext `{"token": ...}` cookie.
+//!
+//! [`SecureCookies`] holds a key ring; the first key signs and encrypts new
+//! values, older keys are kept only to read existing cookies, so secrets
+//! can be rotated without logging everyone out.
+//!
+//! | Kind | Cookie value | Browser can read it |
+//! |------|--------------|---------------------|
+//! | signed (HMAC-SHA256) | `<key_id>.<value_b64>.<mac_b64>` | yes |
+//! | encrypted (AES-256-GCM) | `<key_id>.<b64(nonce ‖ ciphertext ‖ tag)>` | no |
+//!
+//! Both bind the cookie name into the MAC / associated data, so a value
+//! cannot be moved to a different cookie. Signing and encryption use
+//! separate keys derived from each secret.
+//!
+//! # Example
+//! ```rust
+//! use axum_extra::extract::cookie::CookieJar;
+//! use wzs_web::config::csrf::derive_secret_from_string;
+//! use wzs_web::web::secure_cookie::SecureCookies;
+//!
+//! let cookies = SecureCookies::new("k2", derive_secret_from_string("new-secret"))
+//!     .with_previous_key("k1", derive_secret_from_string("old-secret"));
+//!
+//! let jar = CookieJar::new().add(cookies.encrypted_cookie("auth", "<jwt>").unwrap());
+//! assert_eq!(cookies.get_encrypted(&jar, "auth").as_deref(), Some("<jwt>"));
+//! assert!(!jar.get("auth").unwrap().value().contains("<jwt>"));
+//! ```
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use anyhow::{anyhow, Result};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const NONCE_LEN: usize = 12;
+
+#[derive(Clone)]
+struct CookieKey {
+    id: String,
+    sign: [u8; 32],
+    aead: Aes256Gcm,
+}
+
+impl CookieKey {
+    fn new(id: String, secret: [u8; 32]) -> Self {
+        assert!(
+            !id.is_empty() && !id.contains('.'),
+            "invalid cookie key id: {id:?}"
+        );
+        Self {
+            sign: derive(&secret, b"wzs-cookie/sign"),
+            aead: Aes256Gcm::new(&derive(&secret, b"wzs-cookie/encrypt").into()),
+            id,
+        }
+    }
+
+    fn mac(&self, name: &str, payload: &str) -> Vec<u8> {
+        let mut mac = <HmacSha256 as Mac>::new_from_slice(&self.sign).expect("HMAC key");
+        mac.update(name.as_bytes());
+        mac.update(b"\0");
+        mac.update(payload.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+/// Signs, encrypts and verifies cookie values with a rotating key ring.
+///
+/// Cloning is cheap enough to keep one per router state.
+#[derive(Clone)]
+pub struct SecureCookies {
+    keys: Vec<CookieKey>,
+    same_site: SameSite,
+}
+
+impl SecureCookies {
+    /// Creates a ring whose active key is `secret`, identified by `key_id`.
+    ///
+    /// # Panics
+    /// Panics if `key_id` is empty or contains `.`.
+    pub fn new(key_id: impl Into<String>, secret: [u8; 32]) -> Self {
+        Self {
+            keys: vec![CookieKey::new(key_id.into(), secret)],
+            same_site: SameSite::Lax,
+        }
+    }
+
+    /// Keeps accepting cookies written with a retired key.
+    ///
+    /// # Panics
+    /// Panics if `key_id` is empty or contains `.`.
+    pub fn with_previous_key(mut self, key_id: impl Into<String>, secret: [u8; 32]) -> Self {
+        self.keys.push(CookieKey::new(key_id.into(), secret));
+        self
+    }
+
+    /// Sets the `SameSite` attribute of built cookies (default `Lax`).
+    ///
+    /// See [`CrossSiteConfig::apply_cookies`](crate::config::cross_site::CrossSiteConfig::apply_cookies)
+    /// for `SameSite=None`.
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = same_site;
+        self
+    }
+
+    /// Id of the key used for new values.
+    pub fn active_key_id(&self) -> &str {
+        &self.keys[0].id
+    }
+
+    /// Signs `value` for the cookie `name`.
+    pub fn sign(&self, name: &str, value: &str) -> String {
+        let key = &self.keys[0];
+        let payload = format!("{}.{}", key.id, URL_SAFE_NO_PAD.encode(value));
+        let mac = URL_SAFE_NO_PAD.encode(key.mac(name, &payload));
+        format!("{payload}.{mac}")
+    }
+
+    /// Returns the value of a cookie produced by [`sign`](Self::sign), or
+    /// `None` if it is malformed, forged or signed with an unknown key.
+    pub fn verify(&self, name: &str, cookie_value: &str) -> Option<String> {
+        let (payload, mac) = cookie_value.rsplit_once('.')?;
+        let (id, value) = payload.split_once('.')?;
+        let key = self.key(id)?;
+        let mac = URL_SAFE_NO_PAD.decode(mac).ok()?;
+        if key.mac(name, payload).ct_eq(&mac).unwrap_u8() != 1 {
+            return None;
+        }
+        String::from_utf8(URL_SAFE_NO_PAD.decode(value).ok()?).ok()
+    }
+
+    /// Encrypts `value` for the cookie `name`.
+    pub fn encrypt(&self, name: &str, value: &str) -> Result<String> {
+        let key = &self.keys[0];
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rng().fill_bytes(&mut nonce);
+
+        let ct = key
+            .aead
+            .encrypt(
+                &Nonce::from(nonce),
+                Payload {
+                    msg: value.as_bytes(),
+                    aad: name.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow!("cookie encryption failed"))?;
+
+        let mut blob = Vec::with_capacity(NONCE_LEN + ct.len());
+        blob.extend_from_slice(&nonce);
+        blob.extend_from_slice(&ct);
+        Ok(format!("{}.{}", key.id, URL_SAFE_NO_PAD.encode(blob)))
+    }
+
+    /// Returns the value of a cookie produced by [`encrypt`](Self::encrypt),
+    /// or `None` if it is malformed, tampered with or encrypted with an
+    /// unknown key.
+    pub fn decrypt(&self, name: &str, cookie_value: &str) -> Option<String> {
+        let (id, b64) = cookie_value.split_once('.')?;
+        let key = self.key(id)?;
+        let blob = URL_SAFE_NO_PAD.decode(b64).ok()?;
+        if blob.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ct) = blob.split_at(NONCE_LEN);
+        let nonce: [u8; NONCE_LEN] = nonce.try_into().ok()?;
+        let pt = key
+            .aead
+            .decrypt(
+                &Nonce::from(nonce),
+                Payload {
+                    msg: ct,
+                    aad: name.as_bytes(),
+                },
+            )
+            .ok()?;
+        String::from_utf8(pt).ok()
+    }
+
+    /// Encrypts `value` serialized as JSON.
+    pub fn encrypt_json<T: Serialize>(&self, name: &str, value: &T) -> Result<String> {
+        self.encrypt(name, &serde_json::to_string(value)?)
+    }
+
+    /// Decrypts and deserializes a value written by
+    /// [`encrypt_json`](Self::encrypt_json).
+    pub fn decrypt_json<T: DeserializeOwned>(&self, name: &str, cookie_value: &str) -> Option<T> {
+        serde_json::from_str(&self.decrypt(name, cookie_value)?).ok()
+    }
+
+    /// Whether `cookie_value` was written with a retired key and should be
+    /// re-issued.
+    pub fn needs_rotation(&self, cookie_value: &str) -> bool {
+        cookie_value
+            .split_once('.')
+            .is_none_or(|(id, _)| id != self.active_key_id())
+    }
+
+    /// Reads and verifies the signed cookie `name` from `jar`.
+    pub fn get_signed(&self, jar: &CookieJar, name: &str) -> Option<String> {
+        self.verify(name, jar.get(name)?.value())
+    }
+
+    /// Reads and decrypts the encrypted cookie `name` from `jar`.
+    pub fn get_encrypted(&self, jar: &CookieJar, name: &str) -> Option<String> {
+        self.decrypt(name, jar.get(name)?.value())
+    }
+
+    /// Builds a signed cookie: `Path=/`, `HttpOnly`, `Secure`, `SameSite=Lax`
+    /// (or as set by [`same_site`](Self::same_site)).
+    pub fn signed_cookie(&self, name: &str, value: &str) -> Cookie<'static> {
+        cookie(name, self.sign(name, value), self.same_site)
+    }
+
+    /// Builds an encrypted cookie with the same attributes as
+    /// [`signed_cookie`](Self::signed_cookie).
+    pub fn encrypted_cookie(&self, name: &str, value: &str) -> Result<Cookie<'static>> {
+        Ok(cookie(name, self.encrypt(name, value)?, self.same_site))
+    }
+
+    fn key(&self, id: &str) -> Option<&CookieKey> {
+        self.keys.iter().find(|k| k.id == id)
+    }
+}
+
+fn cookie(name: &str, value: String, same_site: SameSite) -> Cookie<'static> {
+    Cookie::build((name.to_string(), value))
+        .path("/")
+        .http_only(true)
+        .secure(true)
+        .same_site(same_site)
+        .build()
+}
+
+/// Derives a purpose-specific key from `secret`.
+fn derive(secret: &[u8; 32], purpose: &[u8]) -> [u8; 32] {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(secret).expect("HMAC key");
+    mac.update(purpose);
+    mac.finalize().into_bytes().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    fn cookies() -> SecureCookies {
+        SecureCookies::new("k1", [1u8; 32])
+    }
+
+    #[test]
+    fn signed_values_roundtrip_and_reject_tampering() {
+        let c = cookies();
+        let signed = c.sign("prefs", "theme=dark");
+        assert!(signed.starts_with("k1."));
+        assert_eq!(c.verify("prefs", &signed).as_deref(), Some("theme=dark"));
+
+        assert_eq!(c.verify("other", &signed), None, "bound to the name");
+        let forged = signed.replacen(
+            &URL_SAFE_NO_PAD.encode("theme=dark"),
+            &URL_SAFE_NO_PAD.encode("theme=lite"),
+            1,
+        );
+        assert_eq!(c.verify("prefs", &forged), None);
+        assert_eq!(c.verify("prefs", "garbage"), None);
+    }
+
+    #[test]
+    fn encrypted_values_are_opaque_and_bound_to_the_name() {
+        let c = cookies();
+        let a = c.encrypt("auth", "eyJhbGciOi").unwrap();
+        let b = c.encrypt("auth", "eyJhbGciOi").unwrap();
+        assert_ne!(a, b);
+        assert!(!a.contains("eyJ"));
+
+        assert_eq!(c.decrypt("auth", &a).as_deref(), Some("eyJhbGciOi"));
+        assert_eq!(c.decrypt("session", &a), None);
+        assert_eq!(c.decrypt("auth", &a.replace("k1.", "k1.A")), None);
+        assert_eq!(c.decrypt("auth", &c.sign("auth", "x")), None);
+    }
+
+    #[test]
+    fn previous_keys_still_read_after_rotation() {
+        let old = cookies();
+        let signed = old.sign("prefs", "v");
+        let sealed = old.encrypt("auth", "t").unwrap();
+
+        let rotated = SecureCookies::new("k2", [2u8; 32]).with_previous_key("k1", [1u8; 32]);
+        assert_eq!(rotated.active_key_id(), "k2");
+        assert_eq!(rotated.verify("prefs", &signed).as_deref(), Some("v"));
+        assert_eq!(rotated.decrypt("auth", &sealed).as_deref(), Some("t"));
+        assert!(rotated.needs_rotation(&sealed));
+        assert!(!rotated.needs_rotation(&rotated.encrypt("auth", "t").unwrap()));
+
+        let retired = SecureCookies::new("k2", [2u8; 32]);
+        assert_eq!(retired.decrypt("auth", &sealed), None);
+    }
+
+    #[test]
+    fn jar_helpers_and_json_payloads() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Token {
+            token: String,
+        }
+
+        let c = cookies();
+        let sealed = c
+            .encrypt_json(
+                "auth",
+                &Token {
+                    token: "jwt".into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            c.decrypt_json::<Token>("auth", &sealed),
+            Some(Token {
+                token: "jwt".into()
+            })
+        );
+
+        let cookie = c.signed_cookie("prefs", "v");
+        let rendered = cookie.to_string();
+        assert!(rendered.contains("HttpOnly") && rendered.contains("Secure"));
+        assert!(rendered.contains("SameSite=Lax") && rendered.contains("Path=/"));
+
+        let jar = CookieJar::new()
+            .add(cookie)
+            .add(c.encrypted_cookie("auth", "t").unwrap());
+        assert_eq!(c.get_signed(&jar, "prefs").as_deref(), Some("v"));
+        assert_eq!(c.get_encrypted(&jar, "auth").as_deref(), Some("t"));
+        assert_eq!(c.get_encrypted(&jar, "prefs"), None);
+    }
+}
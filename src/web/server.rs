@@ -0,0 +1,322 @@
+//! # Server Startup and Graceful Shutdown
+//!
+//! [`Server`] runs a [`Router`] until `SIGTERM` or `SIGINT` (Ctrl-C), then:
+//!
+//! 1. stops accepting connections and lets in-flight requests finish, for
+//!    at most the [drain timeout](Server::drain_timeout) (default 30 s;
+//!    requests still running afterwards are no longer waited for);
+//! 2. runs the [shutdown hooks](Server::on_shutdown) in registration order,
+//!    each bounded by the [hook timeout](Server::hook_timeout) (default
+//!    10 s). A failing hook is logged and the next one still runs.
+//!
+//! Connections are served with `ConnectInfo<SocketAddr>`, which client IP
+//! extractors and rate limiting read.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use axum::{routing::get, Router};
+//! use wzs_web::config::db::DbConfig;
+//! use wzs_web::db::{connection::get_pool, mysql_adapter::MySqlDb};
+//! use wzs_web::web::server::Server;
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let db = MySqlDb::new(get_pool(&DbConfig::from_env()));
+//! let app = Router::new().route("/", get(|| async { "hello" }));
+//!
+//! Server::new(app)
+//!     .drain_timeout(Duration::from_secs(20))
+//!     .drain_on_shutdown(db.drain_handle(), Duration::from_secs(5))
+//!     .serve("0.0.0.0:8080")
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::future::{Future, IntoFuture};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use axum::Router;
+use futures_util::future::BoxFuture;
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+use crate::db::drain::PoolDrain;
+
+type Hook = Box<dyn FnOnce() -> BoxFuture<'static, Result<()>> + Send>;
+
+/// An HTTP server with graceful shutdown; see the [module docs](self).
+pub struct Server {
+    router: Router,
+    signal: BoxFuture<'static, ()>,
+    drain_timeout: Duration,
+    hook_timeout: Duration,
+    hooks: Vec<(String, Hook)>,
+}
+
+impl Server {
+    /// Serves `router`, stopping on `SIGTERM` / `SIGINT`.
+    pub fn new(router: Router) -> Self {
+        Self {
+            router,
+            signal: Box::pin(shutdown_signal()),
+            drain_timeout: Duration::from_secs(30),
+            hook_timeout: Duration::from_secs(10),
+            hooks: Vec::new(),
+        }
+    }
+
+    /// Stops when `signal` completes instead of on OS signals.
+    pub fn shutdown_signal<F>(mut self, signal: F) -> Self
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.signal = Box::pin(signal);
+        self
+    }
+
+    /// How long in-flight requests may take once shutdown starts.
+    pub fn drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = timeout;
+        self
+    }
+
+    /// How long each shutdown hook may take.
+    pub fn hook_timeout(mut self, timeout: Duration) -> Self {
+        self.hook_timeout = timeout;
+        self
+    }
+
+    /// Runs `hook` after requests have drained (closing pools, flushing
+    /// queues).
+    pub fn on_shutdown<F, Fut>(mut self, name: impl Into<String>, hook: F) -> Self
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.hooks
+            .push((name.into(), Box::new(move || Box::pin(hook()))));
+        self
+    }
+
+    /// Drains a database pool as a shutdown hook: new queries are refused
+    /// and in-flight ones get `timeout` to finish (see
+    /// [`drain`](crate::db::drain)).
+    pub fn drain_on_shutdown(self, drain: PoolDrain, timeout: Duration) -> Self {
+        self.on_shutdown("db pool drain", move || async move {
+            drain.drain(timeout).await
+        })
+    }
+
+    /// Binds `addr` and serves until shutdown completes.
+    pub async fn serve(self, addr: impl ToSocketAddrs) -> Result<()> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .context("failed to bind listener")?;
+        self.serve_listener(listener).await
+    }
+
+    /// Serves on an already bound `listener` until shutdown completes.
+    ///
+    /// # Errors
+    /// Returns an error if the server fails; a drain timeout and failing
+    /// hooks are only logged.
+    pub async fn serve_listener(self, listener: TcpListener) -> Result<()> {
+        if let Ok(addr) = listener.local_addr() {
+            info!(%addr, "listening");
+        }
+        let (stopping_tx, stopping_rx) = oneshot::channel();
+        let signal = self.signal;
+        let server = axum::serve(
+            listener,
+            self.router
+                .into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(async move {
+            signal.await;
+            let _ = stopping_tx.send(());
+        })
+        .into_future();
+        tokio::pin!(server);
+
+        tokio::select! {
+            result = &mut server => result.context("server failed")?,
+            _ = stopping_rx => {
+                info!("shutdown started; draining in-flight requests");
+                match tokio::time::timeout(self.drain_timeout, &mut server).await {
+                    Ok(result) => result.context("server failed")?,
+                    Err(_) => warn!(
+                        "requests still in flight after {} ms; not waiting any longer",
+                        self.drain_timeout.as_millis()
+                    ),
+                }
+            }
+        }
+
+        run_hooks(self.hooks, self.hook_timeout).await;
+        info!("shutdown complete");
+        Ok(())
+    }
+}
+
+async fn run_hooks(hooks: Vec<(String, Hook)>, timeout: Duration) {
+    for (name, hook) in hooks {
+        let result = tokio::time::timeout(timeout, hook())
+            .await
+            .unwrap_or_else(|_| Err(anyhow!("timed out after {} ms", timeout.as_millis())));
+        match result {
+            Ok(()) => info!(hook = %name, "shutdown hook finished"),
+            Err(e) => warn!(hook = %name, "shutdown hook failed: {e:#}"),
+        }
+    }
+}
+
+/// Completes on Ctrl-C (`SIGINT`) or, on Unix, `SIGTERM`.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("failed to listen for Ctrl-C: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                warn!("failed to listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("received SIGINT"),
+        _ = terminate => info!("received SIGTERM"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use anyhow::bail;
+    use axum::routing::get;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    async fn get_path(addr: SocketAddr, path: &str) -> std::io::Result<String> {
+        let mut stream = TcpStream::connect(addr).await?;
+        let req = format!("GET {path} HTTP/1.1\r\nHost: test\r\nConnection: close\r\n\r\n");
+        stream.write_all(req.as_bytes()).await?;
+        let mut res = String::new();
+        stream.read_to_string(&mut res).await?;
+        Ok(res)
+    }
+
+    struct Running {
+        addr: SocketAddr,
+        stop: oneshot::Sender<()>,
+        done: tokio::task::JoinHandle<Result<()>>,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    async fn start(drain: Duration) -> Running {
+        let app = Router::new().route(
+            "/slow",
+            get(|| async {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                "done"
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (stop, stopped) = oneshot::channel::<()>();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let (first, second) = (log.clone(), log.clone());
+
+        let server = Server::new(app)
+            .shutdown_signal(async move {
+                let _ = stopped.await;
+            })
+            .drain_timeout(drain)
+            .hook_timeout(Duration::from_millis(50))
+            .on_shutdown("failing", move || async move {
+                first.lock().unwrap().push("failing".to_string());
+                bail!("flush failed")
+            })
+            .on_shutdown("pool", move || async move {
+                second.lock().unwrap().push("pool".to_string());
+                Ok(())
+            });
+        let done = tokio::spawn(server.serve_listener(listener));
+        Running {
+            addr,
+            stop,
+            done,
+            log,
+        }
+    }
+
+    #[tokio::test]
+    async fn drains_requests_then_runs_hooks_in_order() {
+        let server = start(Duration::from_secs(5)).await;
+        let request = tokio::spawn(get_path(server.addr, "/slow"));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        server.stop.send(()).unwrap();
+        let res = request.await.unwrap().unwrap();
+        assert!(res.starts_with("HTTP/1.1 200"));
+        assert!(res.ends_with("done"));
+
+        server.done.await.unwrap().unwrap();
+        assert_eq!(*server.log.lock().unwrap(), ["failing", "pool"]);
+        assert!(get_path(server.addr, "/slow").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn drains_the_db_pool_on_shutdown() {
+        let drain = PoolDrain::new();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let (stop, stopped) = oneshot::channel::<()>();
+        let server = Server::new(Router::new())
+            .shutdown_signal(async move {
+                let _ = stopped.await;
+            })
+            .drain_on_shutdown(drain.clone(), Duration::from_millis(50));
+        let done = tokio::spawn(server.serve_listener(listener));
+
+        assert!(drain.enter().is_ok());
+        stop.send(()).unwrap();
+        done.await.unwrap().unwrap();
+        assert!(drain.is_draining());
+        assert!(drain.enter().is_err());
+    }
+
+    #[tokio::test]
+    async fn drain_timeout_bounds_shutdown() {
+        let server = start(Duration::from_millis(20)).await;
+        let request = tokio::spawn(get_path(server.addr, "/slow"));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        server.stop.send(()).unwrap();
+        tokio::time::timeout(Duration::from_millis(150), server.done)
+            .await
+            .expect("shutdown waited for the slow request")
+            .unwrap()
+            .unwrap();
+        assert_eq!(server.log.lock().unwrap().len(), 2);
+        request.abort();
+    }
+}
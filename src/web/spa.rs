@@ -1,3 +1,6 @@
+pub mod bootstrap;
 pub mod entry;
+pub mod router;
 
 pub use entry::spa_entry_handler;
+pub use router::{spa_router, SpaRouter};
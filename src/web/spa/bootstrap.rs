@@ -0,0 +1,157 @@
+//! # SPA Bootstrap Script
+//!
+//! Builds a small inline `<script>` that exposes the CSRF token and API base
+//! to the frontend as `window.__APP__`, plus a CSRF-aware `fetch` wrapper:
+//!
+//! ```js
+//! window.__APP__.csrfToken   // current token
+//! window.__APP__.csrfHeader  // "X-CSRF-Token"
+//! window.__APP__.apiBase     // e.g. "/api"
+//! window.__APP__.fetch("users", { method: "POST", body })  // -> /api/users
+//! ```
+//!
+//! The wrapper sends `credentials: "same-origin"`, adds the CSRF header to
+//! non-safe methods, and prefixes `apiBase` to relative paths (absolute
+//! paths and full URLs are left untouched).
+//!
+//! [`spa_entry_handler`](crate::web::spa::spa_entry_handler) injects the
+//! script when an `Extension<SpaBootstrap>` is present. If a CSP middleware
+//! stores a [`CspNonce`] in the request extensions, the script carries it.
+//!
+//! # Example
+//! ```rust
+//! use wzs_web::web::spa::bootstrap::SpaBootstrap;
+//!
+//! let script = SpaBootstrap::new("/api").script("v1.tok.mac", Some("abc123"));
+//! assert!(script.starts_with(r#"<script nonce="abc123">"#));
+//! assert!(script.contains(r#""apiBase":"/api""#));
+//! ```
+
+use serde::Serialize;
+
+use crate::web::csrf::CSRF_HEADER_NAME;
+
+/// Placeholder replaced by the bootstrap script, if present in the template.
+///
+/// Without it, the script is inserted before `</head>` (or prepended).
+pub const BOOTSTRAP_PLACEHOLDER: &str = "{{ app_bootstrap }}";
+
+/// Per-request CSP nonce, inserted into request extensions by CSP middleware.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CspNonce(pub String);
+
+/// Server-side settings exposed to the SPA.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpaBootstrap {
+    /// Base path or URL for API requests (e.g. `"/api"`).
+    pub api_base: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppGlobals<'a> {
+    csrf_token: &'a str,
+    csrf_header: &'a str,
+    api_base: &'a str,
+}
+
+/// Client-side helper; `c` is the globals object.
+const FETCH_WRAPPER: &str = r#"c.fetch=function(i,o){o=Object.assign({credentials:"same-origin"},o||{});var h=new Headers(o.headers||{});if(!/^(GET|HEAD|OPTIONS)$/i.test(o.method||"GET"))h.set(c.csrfHeader,c.csrfToken);o.headers=h;if(typeof i==="string"&&i.indexOf("://")<0&&i.charAt(0)!=="/")i=c.apiBase.replace(/\/+$/,"")+"/"+i;return fetch(i,o)};"#;
+
+impl SpaBootstrap {
+    /// Creates a bootstrap config with the given API base.
+    pub fn new(api_base: impl Into<String>) -> Self {
+        Self {
+            api_base: api_base.into(),
+        }
+    }
+
+    /// Renders the `<script>` element for `csrf_token`.
+    pub fn script(&self, csrf_token: &str, nonce: Option<&str>) -> String {
+        let globals = AppGlobals {
+            csrf_token,
+            csrf_header: CSRF_HEADER_NAME,
+            api_base: &self.api_base,
+        };
+        let json = script_safe_json(&globals);
+        let nonce_attr = nonce
+            .map(|n| format!(r#" nonce="{}""#, attr_escape(n)))
+            .unwrap_or_default();
+
+        format!(
+            "<script{nonce_attr}>(function(c){{{FETCH_WRAPPER}window.__APP__=Object.freeze(c)}})({json});</script>"
+        )
+    }
+
+    /// Injects the script into `html` (see [`BOOTSTRAP_PLACEHOLDER`]).
+    pub fn inject(&self, html: &str, csrf_token: &str, nonce: Option<&str>) -> String {
+        let script = self.script(csrf_token, nonce);
+        if html.contains(BOOTSTRAP_PLACEHOLDER) {
+            return html.replace(BOOTSTRAP_PLACEHOLDER, &script);
+        }
+        match html.find("</head>") {
+            Some(pos) => format!("{}{script}{}", &html[..pos], &html[pos..]),
+            None => format!("{script}{html}"),
+        }
+    }
+}
+
+/// JSON that cannot terminate the surrounding `<script>` element.
+fn script_safe_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value)
+        .expect("bootstrap globals are serializable")
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .replace('&', "\\u0026")
+        .replace('\u{2028}', "\\u2028")
+        .replace('\u{2029}', "\\u2029")
+}
+
+fn attr_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_exposes_globals_and_fetch_wrapper() {
+        let s = SpaBootstrap::new("/api").script("v1.a.b", None);
+
+        assert!(s.starts_with("<script>"));
+        assert!(s.ends_with("</script>"));
+        assert!(
+            s.contains(r#"{"csrfToken":"v1.a.b","csrfHeader":"X-CSRF-Token","apiBase":"/api"}"#)
+        );
+        assert!(s.contains("window.__APP__=Object.freeze(c)"));
+        assert!(s.contains("c.fetch=function"));
+    }
+
+    #[test]
+    fn values_cannot_break_out_of_script_or_attribute() {
+        let s = SpaBootstrap::new("</script><script>alert(1)").script("t", Some(r#""><x"#));
+
+        assert_eq!(s.matches("</script>").count(), 1);
+        assert!(s.contains("\\u003c/script\\u003e"));
+        assert!(s.starts_with(r#"<script nonce="&quot;&gt;&lt;x">"#));
+    }
+
+    #[test]
+    fn inject_prefers_placeholder_then_head() {
+        let b = SpaBootstrap::new("/api");
+
+        let html = b.inject("<head></head><body>{{ app_bootstrap }}</body>", "t", None);
+        assert!(html.starts_with("<head></head><body><script>"));
+
+        let html = b.inject("<html><head><title>x</title></head></html>", "t", None);
+        assert!(html.contains("<title>x</title><script>"));
+        assert!(html.ends_with("</script></head></html>"));
+
+        let html = b.inject("<div id=app></div>", "t", None);
+        assert!(html.starts_with("<script>") && html.ends_with("<div id=app></div>"));
+    }
+}
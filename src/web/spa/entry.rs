@@ -7,7 +7,8 @@ use axum::{
 use axum_extra::extract::cookie::CookieJar;
 
 use crate::config::csrf::CsrfConfig;
-use crate::web::csrf::{generate_csrf_token, set_csrf_cookie};
+use crate::web::csrf::{issue_csrf_token, set_csrf_cookie};
+use crate::web::spa::bootstrap::{CspNonce, SpaBootstrap};
 
 /// SPA (Single Page Application) entry-point handler with CSRF protection.
 ///
@@ -17,6 +18,7 @@ use crate::web::csrf::{generate_csrf_token, set_csrf_cookie};
 /// - Generate a CSRF token
 /// - Store the CSRF token in a cookie
 /// - Inject the CSRF token into an HTML template
+/// - Optionally inject a `window.__APP__` bootstrap script
 ///
 /// It does **not** depend on any business domain concepts
 /// (e.g. registration, members, admin).
@@ -37,6 +39,14 @@ use crate::web::csrf::{generate_csrf_token, set_csrf_cookie};
 ///
 /// which will be replaced with the generated CSRF token.
 ///
+/// # Bootstrap script (optional)
+///
+/// When an `Extension<SpaBootstrap>` is present, a script exposing the
+/// token, header name and API base as `window.__APP__` is injected (see
+/// [`bootstrap`](crate::web::spa::bootstrap)). It replaces
+/// `{{ app_bootstrap }}` if present, otherwise it goes before `</head>`.
+/// A [`CspNonce`] in the request extensions is added as the `nonce` attribute.
+///
 /// # Required Extensions
 ///
 /// The following `Extension`s must be injected into the router:
@@ -66,10 +76,12 @@ use crate::web::csrf::{generate_csrf_token, set_csrf_cookie};
 pub async fn spa_entry_handler(
     Extension(csrf_cfg): Extension<CsrfConfig>,
     Extension(template_html): Extension<Arc<String>>,
+    bootstrap: Option<Extension<SpaBootstrap>>,
+    nonce: Option<Extension<CspNonce>>,
     jar: CookieJar,
 ) -> impl IntoResponse {
     // Generate a new CSRF token
-    let token = generate_csrf_token(&csrf_cfg);
+    let token = issue_csrf_token(&csrf_cfg);
 
     // Store CSRF token in a cookie
     let jar = set_csrf_cookie(jar, &csrf_cfg, &token);
@@ -77,14 +89,23 @@ pub async fn spa_entry_handler(
     // Replace CSRF placeholder in HTML template
     let html_with_token = template_html.replace("{{ csrf_token }}", &token);
 
-    (jar, Html(html_with_token))
+    // Inject the bootstrap script when configured
+    let html = match bootstrap {
+        Some(Extension(bootstrap)) => {
+            let nonce = nonce.as_ref().map(|Extension(n)| n.0.as_str());
+            bootstrap.inject(&html_with_token, &token, nonce)
+        }
+        None => html_with_token,
+    };
+
+    (jar, Html(html))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use axum::Extension;
-    use axum_extra::extract::cookie::CookieJar;
+    use axum_extra::extract::cookie::{CookieJar, SameSite};
 
     fn test_csrf_config() -> CsrfConfig {
         // Deterministic CSRF configuration for testing
@@ -92,6 +113,11 @@ mod tests {
             secret: [0u8; 32],
             cookie_secure: false,
             cookie_http_only: true,
+            rotation: Default::default(),
+            token_ttl: None,
+            allowed_origins: None,
+            cookie_same_site: SameSite::Lax,
+            accept_legacy_tokens: false,
         }
     }
 
@@ -102,9 +128,15 @@ mod tests {
 
         let jar = CookieJar::new();
 
-        let response = spa_entry_handler(Extension(csrf_cfg), Extension(template_html), jar)
-            .await
-            .into_response();
+        let response = spa_entry_handler(
+            Extension(csrf_cfg),
+            Extension(template_html),
+            None,
+            None,
+            jar,
+        )
+        .await
+        .into_response();
 
         let body = axum::body::to_bytes(response.into_body(), usize::MAX)
             .await
@@ -131,9 +163,15 @@ mod tests {
 
         let jar = CookieJar::new();
 
-        let response = spa_entry_handler(Extension(csrf_cfg), Extension(template_html), jar)
-            .await
-            .into_response();
+        let response = spa_entry_handler(
+            Extension(csrf_cfg),
+            Extension(template_html),
+            None,
+            None,
+            jar,
+        )
+        .await
+        .into_response();
 
         let headers = response.headers();
 
@@ -147,4 +185,30 @@ mod tests {
             "Response should contain a CSRF Set-Cookie header"
         );
     }
+
+    #[tokio::test]
+    async fn spa_entry_handler_injects_bootstrap_with_nonce() {
+        let csrf_cfg = test_csrf_config();
+        let template_html = Arc::new("<html><head></head><body></body></html>".to_string());
+
+        let response = spa_entry_handler(
+            Extension(csrf_cfg),
+            Extension(template_html),
+            Some(Extension(SpaBootstrap::new("/api"))),
+            Some(Extension(CspNonce("n0nce".into()))),
+            CookieJar::new(),
+        )
+        .await
+        .into_response();
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body_str = std::str::from_utf8(&body).unwrap();
+
+        assert!(body_str.contains(r#"<script nonce="n0nce">"#));
+        assert!(body_str.contains(r#""csrfToken":"v1."#));
+        assert!(body_str.contains(r#""apiBase":"/api""#));
+        assert!(body_str.ends_with("</script></head><body></body></html>"));
+    }
 }
@@ -0,0 +1,252 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::Request,
+    handler::Handler,
+    http::StatusCode,
+    middleware::{from_fn, from_fn_with_state, map_response, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Extension, Router,
+};
+use tower_http::services::ServeDir;
+
+use crate::web::headers::HeaderSet;
+use crate::web::spa::entry::spa_entry_handler;
+use crate::web::upload::cache_policy::{static_cache_headers, CachePolicy, IMMUTABLE};
+
+/// Router serving a built SPA from `dist_dir` (see [`SpaRouter`]).
+///
+/// # Errors
+/// Returns an error if `dist_dir/index.html` cannot be read.
+pub fn spa_router(dist_dir: impl Into<PathBuf>) -> Result<Router> {
+    SpaRouter::new(dist_dir).build()
+}
+
+/// Builder for a router serving a bundler's output directory.
+///
+/// # Overview
+///
+/// - Files under the assets directory (`assets/`, where Vite and similar
+///   bundlers emit content-hashed names) are served with
+///   `Cache-Control: public, max-age=31536000, immutable`; a missing asset is
+///   `404`.
+/// - Other files (`favicon.ico`, `robots.txt`) are served with `no-cache`.
+/// - Precompressed `.br` / `.gz` siblings are served when the client
+///   accepts them.
+/// - Any other path without a file extension gets the entry HTML through
+///   [`spa_entry_handler`] (CSRF token injected, `Cache-Control: no-store`),
+///   so client-side routes survive a reload. `index.html` itself is never
+///   served raw.
+///
+/// # Required Extensions
+///
+/// - `CsrfConfig` (read by [`spa_entry_handler`]; an `SpaBootstrap` is used
+///   when present)
+///
+/// # Example
+///
+/// ```no_run
+/// use axum::{routing::get, Extension, Router};
+/// use wzs_web::config::csrf::CsrfConfig;
+/// use wzs_web::web::spa::SpaRouter;
+///
+/// let spa = SpaRouter::new("./frontend/dist")
+///     .assets_dir("static")
+///     .build()
+///     .expect("frontend build is missing");
+///
+/// let app: Router = Router::new()
+///     .route("/api/hello", get(|| async { "hello" }))
+///     .merge(spa)
+///     .layer(Extension(CsrfConfig::from_env()));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SpaRouter {
+    dist_dir: PathBuf,
+    assets_dir: String,
+    index_file: String,
+}
+
+impl SpaRouter {
+    /// Serves `dist_dir` with the defaults (`assets/`, `index.html`).
+    pub fn new(dist_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dist_dir: dist_dir.into(),
+            assets_dir: "assets".into(),
+            index_file: "index.html".into(),
+        }
+    }
+
+    /// Directory (relative to the dist directory) holding hashed assets.
+    pub fn assets_dir(mut self, dir: impl Into<String>) -> Self {
+        self.assets_dir = dir.into().trim_matches('/').to_string();
+        self
+    }
+
+    /// Entry HTML file (relative to the dist directory).
+    pub fn index_file(mut self, file: impl Into<String>) -> Self {
+        self.index_file = file.into().trim_matches('/').to_string();
+        self
+    }
+
+    /// Reads the entry HTML and builds the router.
+    ///
+    /// # Errors
+    /// Returns an error if the entry HTML cannot be read.
+    pub fn build(self) -> Result<Router> {
+        let index = self.dist_dir.join(&self.index_file);
+        let html = std::fs::read_to_string(&index)
+            .with_context(|| format!("failed to read SPA entry {}", index.display()))?;
+
+        let entry = spa_entry_handler
+            .layer(map_response(no_store))
+            .layer(Extension(Arc::new(html)))
+            .layer(from_fn(navigations_only));
+        let policy = CachePolicy::new()
+            .dir("", "no-cache")
+            .dir(self.assets_dir.as_str(), IMMUTABLE);
+
+        Ok(Router::new()
+            .nest_service(
+                &format!("/{}", self.assets_dir),
+                serve_dir(&self.dist_dir.join(&self.assets_dir)),
+            )
+            .route(&format!("/{}", self.index_file), get(entry.clone()))
+            .fallback_service(serve_dir(&self.dist_dir).fallback(entry.with_state(())))
+            .layer(from_fn_with_state(policy, static_cache_headers)))
+    }
+}
+
+fn serve_dir(dir: &Path) -> ServeDir {
+    ServeDir::new(dir)
+        .append_index_html_on_directories(false)
+        .precompressed_br()
+        .precompressed_gzip()
+}
+
+async fn no_store(mut res: Response) -> Response {
+    HeaderSet::no_store().apply(res.headers_mut());
+    res
+}
+
+/// `404` for paths that look like files (`/logo.png`), so missing files are
+/// not answered with HTML.
+async fn navigations_only(req: Request, next: Next) -> Response {
+    let last = req.uri().path().rsplit('/').next().unwrap_or_default();
+    if last.contains('.') && !last.ends_with(".html") {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{
+        body::Body,
+        http::header::{ACCEPT_ENCODING, CACHE_CONTROL, CONTENT_ENCODING, CONTENT_TYPE},
+    };
+    use axum_extra::extract::cookie::SameSite;
+    use tower::ServiceExt;
+
+    use crate::config::csrf::CsrfConfig;
+
+    fn dist() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wzs-spa-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("assets")).unwrap();
+        std::fs::write(
+            dir.join("index.html"),
+            "<html><body>{{ csrf_token }}</body></html>",
+        )
+        .unwrap();
+        std::fs::write(dir.join("assets/app-3f2a9c1e.js"), "console.log(1)").unwrap();
+        std::fs::write(dir.join("assets/app-3f2a9c1e.js.gz"), b"\x1f\x8bgz").unwrap();
+        std::fs::write(dir.join("robots.txt"), "User-agent: *").unwrap();
+        dir
+    }
+
+    fn app(dist: &Path) -> Router {
+        spa_router(dist).unwrap().layer(Extension(CsrfConfig {
+            secret: [0u8; 32],
+            cookie_secure: false,
+            cookie_http_only: true,
+            rotation: Default::default(),
+            token_ttl: None,
+            allowed_origins: None,
+            cookie_same_site: SameSite::Lax,
+            accept_legacy_tokens: false,
+        }))
+    }
+
+    async fn get_with(app: &Router, uri: &str, encoding: Option<&str>) -> Response {
+        let mut req = Request::get(uri);
+        if let Some(encoding) = encoding {
+            req = req.header(ACCEPT_ENCODING, encoding);
+        }
+        app.clone()
+            .oneshot(req.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    async fn text(res: Response) -> String {
+        let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    #[tokio::test]
+    async fn serves_assets_with_long_lived_caching() {
+        let dist = dist();
+        let app = app(&dist);
+
+        let res = get_with(&app, "/assets/app-3f2a9c1e.js", None).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[CACHE_CONTROL], IMMUTABLE);
+        assert_eq!(text(res).await, "console.log(1)");
+
+        let res = get_with(&app, "/assets/app-3f2a9c1e.js", Some("gzip, br")).await;
+        assert_eq!(res.headers()[CONTENT_ENCODING], "gzip");
+
+        let res = get_with(&app, "/robots.txt", None).await;
+        assert_eq!(res.headers()[CACHE_CONTROL], "no-cache");
+
+        for missing in ["/assets/old-1234abcd.js", "/logo.png"] {
+            let res = get_with(&app, missing, None).await;
+            assert_eq!(res.status(), StatusCode::NOT_FOUND, "{missing}");
+        }
+        std::fs::remove_dir_all(dist).ok();
+    }
+
+    #[tokio::test]
+    async fn unknown_routes_get_the_entry_html() {
+        let dist = dist();
+        let app = app(&dist);
+
+        for path in ["/", "/members/42", "/index.html"] {
+            let res = get_with(&app, path, None).await;
+            assert_eq!(res.status(), StatusCode::OK, "{path}");
+            assert!(res.headers()[CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("text/html"));
+            assert_eq!(
+                res.headers()[CACHE_CONTROL],
+                "no-store, no-cache, must-revalidate"
+            );
+            assert!(text(res).await.starts_with("<html><body>v1."));
+        }
+        std::fs::remove_dir_all(dist).ok();
+    }
+
+    #[test]
+    fn missing_entry_html_is_an_error() {
+        let err = spa_router("/no/such/dist").unwrap_err();
+        assert!(err.to_string().contains("index.html"));
+    }
+}
@@ -23,6 +23,17 @@
 //!     render_template_with_status(tmpl, StatusCode::OK)
 //! }
 //! ```
+//!
+//! Application-wide HTML rewrites (CDN URLs, CSP nonces, link `rel`) are
+//! configured with a [`transform::TemplatePipeline`]; once
+//! [`transform::install`]ed, [`render_template`] applies it.
+//!
+//! Pages sharing a layout extract a [`context::TemplateResponder`], which
+//! collects the CSRF token, current user, locale, CSP nonce and flash
+//! messages from the request.
+
+pub mod context;
+pub mod transform;
 
 use askama::Template;
 use axum::{
@@ -35,6 +46,9 @@ use axum::{
 /// On success, returns a response with status `200 OK` and content type `text/html`.
 /// On failure (template render error), returns `500 Internal Server Error`.
 ///
+/// The [installed](transform::install) pipeline, if any, is applied with an
+/// empty [`TransformContext`](transform::TransformContext).
+///
 /// # Example
 /// ```rust,no_run
 /// use askama::Template;
@@ -49,6 +63,10 @@ use axum::{
 /// assert_eq!(resp.status(), axum::http::StatusCode::OK);
 /// ```
 pub fn render_template<T: Template>(template: T) -> AxumResponse {
+    if let Some(pipeline) = transform::installed() {
+        return pipeline.render(template, &transform::TransformContext::default());
+    }
+
     match template.render() {
         Ok(html) => Response::builder()
             .header("Content-Type", "text/html")
@@ -0,0 +1,267 @@
+//! # Shared Template Context
+//!
+//! [`TemplateResponder`] is an extractor collecting the data every page
+//! layout needs from the request, so handlers don't thread it through by
+//! hand:
+//!
+//! | Field | Source |
+//! |---|---|
+//! | `csrf_token` | generated when an `Extension<CsrfConfig>` is present; the cookie is set on the rendered response |
+//! | `user` | the [`CurrentUser`] extension inserted by the authentication layer |
+//! | `locale` | the [`Locale`] extractor (`Extension<LocaleConfig>`) |
+//! | `nonce` | the [`CspNonce`] extension |
+//! | `flashes` | a `Vec<FlashMessage>` extension, inserted by [`load_flashes`](crate::web::flash::load_flashes) |
+//!
+//! Templates take the [`TemplateContext`] as a field (conventionally `ctx`).
+//! With Askama inheritance the layout sees the child's fields, so a shared
+//! `layout.html` can use them directly:
+//!
+//! ```text
+//! <html lang="{{ ctx.locale.lang() }}" dir="{{ ctx.locale.dir() }}">
+//!   {% for flash in ctx.flashes %}<p class="{{ flash.level }}">{{ flash.message }}</p>{% endfor %}
+//!   {% if let Some(user) = ctx.user %}<span>{{ user.subject }}</span>{% endif %}
+//!   {% block content %}{% endblock %}
+//! ```
+//!
+//! [`TemplateResponder::render`] also applies the installed
+//! [`TemplatePipeline`](crate::web::template::transform::TemplatePipeline)
+//! with the request's nonce.
+//!
+//! # Example
+//! ```rust,no_run
+//! use askama::Template;
+//! use axum::response::Response;
+//! use wzs_web::web::template::context::{TemplateContext, TemplateResponder};
+//!
+//! #[derive(Template)]
+//! #[template(
+//!     source = r#"<form method="post"><input type="hidden" name="csrf_token" value="{{ ctx.csrf_token.as_deref().unwrap_or_default() }}"></form>"#,
+//!     ext = "html"
+//! )]
+//! struct NewPost<'a> {
+//!     ctx: &'a TemplateContext,
+//! }
+//!
+//! async fn new_post(page: TemplateResponder) -> Response {
+//!     page.render(NewPost { ctx: page.ctx() })
+//! }
+//! ```
+
+use std::convert::Infallible;
+
+use askama::Template;
+use axum::{
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::cookie::CookieJar;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::CurrentUser;
+use crate::config::csrf::CsrfConfig;
+use crate::config::locale::LocaleConfig;
+use crate::web::csrf::{issue_csrf_token, set_csrf_cookie};
+use crate::web::locale::Locale;
+use crate::web::spa::bootstrap::CspNonce;
+use crate::web::template::render_template;
+use crate::web::template::transform::{self, TransformContext};
+
+/// A one-time message shown on the next rendered page (see
+/// [`flash`](crate::web::flash)).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlashMessage {
+    /// Free-form level, typically `"success"`, `"info"` or `"error"`; used
+    /// as a CSS class by most layouts.
+    pub level: String,
+    pub message: String,
+}
+
+impl FlashMessage {
+    pub fn new(level: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            level: level.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Request data shared by page layouts and partials.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TemplateContext {
+    /// CSRF token for forms (`None` without a `CsrfConfig`).
+    pub csrf_token: Option<String>,
+    pub user: Option<CurrentUser>,
+    pub locale: Locale,
+    /// CSP nonce for inline scripts and styles.
+    pub nonce: Option<String>,
+    pub flashes: Vec<FlashMessage>,
+}
+
+impl TemplateContext {
+    /// Context of `parts`, generating a CSRF token under `csrf`.
+    pub fn from_parts(parts: &Parts, csrf: Option<&CsrfConfig>) -> Self {
+        let extensions = &parts.extensions;
+        let locale = match extensions.get::<LocaleConfig>() {
+            Some(cfg) => Locale::negotiate(&parts.headers, cfg),
+            None => Locale::negotiate(&parts.headers, &LocaleConfig::default()),
+        };
+        Self {
+            csrf_token: csrf.map(issue_csrf_token),
+            user: extensions.get::<CurrentUser>().cloned(),
+            locale,
+            nonce: extensions.get::<CspNonce>().map(|n| n.0.clone()),
+            flashes: extensions
+                .get::<Vec<FlashMessage>>()
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn is_authenticated(&self) -> bool {
+        self.user.is_some()
+    }
+}
+
+/// Extractor holding the [`TemplateContext`] of the request; see the
+/// [module docs](self).
+#[derive(Clone, Debug)]
+pub struct TemplateResponder {
+    ctx: TemplateContext,
+    /// The CSRF cookie, when a token was generated.
+    jar: Option<CookieJar>,
+}
+
+impl TemplateResponder {
+    pub fn ctx(&self) -> &TemplateContext {
+        &self.ctx
+    }
+
+    /// Renders `template` as `200 OK` HTML.
+    pub fn render<T: Template>(&self, template: T) -> Response {
+        self.render_with_status(template, StatusCode::OK)
+    }
+
+    /// Renders `template` with `status`, setting the CSRF cookie and
+    /// applying the installed pipeline with the request's nonce.
+    pub fn render_with_status<T: Template>(&self, template: T, status: StatusCode) -> Response {
+        let mut res = match transform::installed() {
+            Some(pipeline) => {
+                let tctx = TransformContext {
+                    nonce: self.ctx.nonce.clone(),
+                };
+                pipeline.render(template, &tctx)
+            }
+            None => render_template(template),
+        };
+        if res.status().is_success() {
+            *res.status_mut() = status;
+        }
+        match &self.jar {
+            Some(jar) => (jar.clone(), res).into_response(),
+            None => res,
+        }
+    }
+}
+
+impl<S> FromRequestParts<S> for TemplateResponder
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let csrf = parts.extensions.get::<CsrfConfig>();
+        let ctx = TemplateContext::from_parts(parts, csrf);
+        let jar = csrf
+            .zip(ctx.csrf_token.as_deref())
+            .map(|(cfg, token)| set_csrf_cookie(CookieJar::new(), cfg, token));
+        Ok(Self { ctx, jar })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{
+        body::Body,
+        extract::Request,
+        http::header::{ACCEPT_LANGUAGE, SET_COOKIE},
+        routing::get,
+        Extension, Router,
+    };
+    use axum_extra::extract::cookie::SameSite;
+    use tower::ServiceExt;
+
+    use crate::web::csrf::verify_token;
+
+    #[derive(Template)]
+    #[template(
+        source = "{{ ctx.locale.lang() }}|{% if let Some(user) = ctx.user %}{{ user.subject }}{% endif %}|{% for f in ctx.flashes %}{{ f.level }}:{{ f.message }};{% endfor %}|{{ ctx.nonce.as_deref().unwrap_or_default() }}|{{ ctx.csrf_token.as_deref().unwrap_or_default() }}",
+        ext = "html"
+    )]
+    struct Layout<'a> {
+        ctx: &'a TemplateContext,
+    }
+
+    async fn page(page: TemplateResponder) -> Response {
+        page.render_with_status(Layout { ctx: page.ctx() }, StatusCode::CREATED)
+    }
+
+    fn csrf() -> CsrfConfig {
+        CsrfConfig {
+            secret: [7u8; 32],
+            cookie_secure: false,
+            cookie_http_only: true,
+            rotation: Default::default(),
+            token_ttl: None,
+            allowed_origins: None,
+            cookie_same_site: SameSite::Lax,
+            accept_legacy_tokens: false,
+        }
+    }
+
+    async fn send(app: Router) -> (Response, String) {
+        let req = Request::get("/")
+            .header(ACCEPT_LANGUAGE, "ja-JP")
+            .body(Body::empty())
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        let (parts, body) = res.into_parts();
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        (
+            Response::from_parts(parts, Body::empty()),
+            String::from_utf8(bytes.to_vec()).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn collects_context_from_extensions() {
+        let app = Router::new()
+            .route("/", get(page))
+            .layer(Extension(CurrentUser::new("42")))
+            .layer(Extension(vec![FlashMessage::new("success", "Saved")]))
+            .layer(Extension(CspNonce("n0nce".into())))
+            .layer(Extension(LocaleConfig {
+                default: "en".into(),
+                supported: vec!["en".into(), "ja".into()],
+            }))
+            .layer(Extension(csrf()));
+        let (res, body) = send(app).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+
+        let fields: Vec<&str> = body.split('|').collect();
+        assert_eq!(fields[..4], ["ja", "42", "success:Saved;", "n0nce"]);
+        assert!(verify_token(&csrf(), fields[4]));
+        let cookie = res.headers()[SET_COOKIE].to_str().unwrap();
+        assert!(cookie.contains(fields[4]), "{cookie}");
+    }
+
+    #[tokio::test]
+    async fn works_without_optional_extensions() {
+        let (res, body) = send(Router::new().route("/", get(page))).await;
+        assert_eq!(body, "ja-JP||||");
+        assert!(res.headers().get(SET_COOKIE).is_none());
+    }
+}
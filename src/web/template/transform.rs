@@ -0,0 +1,476 @@
+//! # Post-Render HTML Transforms
+//!
+//! A [`TemplatePipeline`] runs [`HtmlTransform`]s over rendered HTML before
+//! it is sent, so application-wide rewrites don't require forking
+//! [`render_template`](crate::web::template::render_template).
+//!
+//! Built-in transforms:
+//!
+//! - [`CdnRewrite`] — rewrites `src` / `href` under asset prefixes to a CDN host
+//! - [`NonceInjector`] — adds the request's CSP `nonce` to `<script>` / `<style>`
+//! - [`ExternalLinkRel`] — adds `rel="noopener noreferrer"` to external or
+//!   `target="_blank"` links
+//!
+//! A pipeline can be used directly ([`TemplatePipeline::render`]) or
+//! installed once at startup ([`install`]); `render_template` then applies
+//! it with an empty [`TransformContext`].
+//!
+//! # Example
+//! ```rust
+//! use askama::Template;
+//! use wzs_web::web::template::transform::{
+//!     CdnRewrite, NonceInjector, TemplatePipeline, TransformContext,
+//! };
+//!
+//! #[derive(Template)]
+//! #[template(source = r#"<script src="/assets/app.js"></script>"#, ext = "html")]
+//! struct Page;
+//!
+//! let pipeline = TemplatePipeline::new()
+//!     .with(CdnRewrite::new("https://cdn.example.com"))
+//!     .with(NonceInjector);
+//!
+//! let html = pipeline
+//!     .render_string(Page, &TransformContext::default().nonce("abc"))
+//!     .unwrap();
+//! assert_eq!(
+//!     html,
+//!     r#"<script nonce="abc" src="https://cdn.example.com/assets/app.js"></script>"#
+//! );
+//! ```
+
+use std::ops::Range;
+use std::sync::{Arc, OnceLock};
+
+use askama::Template;
+use axum::{
+    body::Body,
+    http::{Response, StatusCode},
+    response::Response as AxumResponse,
+};
+
+/// Per-request data available to transforms.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransformContext {
+    /// CSP nonce for inline scripts and styles.
+    pub nonce: Option<String>,
+}
+
+impl TransformContext {
+    /// Sets the CSP nonce.
+    pub fn nonce(mut self, nonce: impl Into<String>) -> Self {
+        self.nonce = Some(nonce.into());
+        self
+    }
+}
+
+/// A rewrite applied to rendered HTML.
+pub trait HtmlTransform: Send + Sync {
+    fn transform(&self, html: String, ctx: &TransformContext) -> String;
+}
+
+impl<F> HtmlTransform for F
+where
+    F: Fn(String, &TransformContext) -> String + Send + Sync,
+{
+    fn transform(&self, html: String, ctx: &TransformContext) -> String {
+        self(html, ctx)
+    }
+}
+
+/// Ordered list of [`HtmlTransform`]s.
+#[derive(Clone, Default)]
+pub struct TemplatePipeline {
+    transforms: Vec<Arc<dyn HtmlTransform>>,
+}
+
+impl TemplatePipeline {
+    /// Creates an empty pipeline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a transform.
+    pub fn with(mut self, transform: impl HtmlTransform + 'static) -> Self {
+        self.transforms.push(Arc::new(transform));
+        self
+    }
+
+    /// Runs every transform in order.
+    pub fn apply(&self, html: String, ctx: &TransformContext) -> String {
+        self.transforms
+            .iter()
+            .fold(html, |html, t| t.transform(html, ctx))
+    }
+
+    /// Renders `template` and applies the pipeline.
+    pub fn render_string<T: Template>(
+        &self,
+        template: T,
+        ctx: &TransformContext,
+    ) -> askama::Result<String> {
+        Ok(self.apply(template.render()?, ctx))
+    }
+
+    /// Like [`render_template`](crate::web::template::render_template), with
+    /// the pipeline applied.
+    pub fn render<T: Template>(&self, template: T, ctx: &TransformContext) -> AxumResponse {
+        match self.render_string(template, ctx) {
+            Ok(html) => Response::builder()
+                .header("Content-Type", "text/html")
+                .body(Body::from(html))
+                .unwrap(),
+            Err(_) => Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::from("Internal Server Error"))
+                .unwrap(),
+        }
+    }
+}
+
+static INSTALLED: OnceLock<TemplatePipeline> = OnceLock::new();
+
+/// Installs the pipeline used by `render_template`.
+///
+/// Returns the pipeline back if one is already installed.
+pub fn install(pipeline: TemplatePipeline) -> Result<(), TemplatePipeline> {
+    INSTALLED.set(pipeline)
+}
+
+/// Returns the installed pipeline, if any.
+pub fn installed() -> Option<&'static TemplatePipeline> {
+    INSTALLED.get()
+}
+
+/// Rewrites asset URLs to a CDN host.
+///
+/// Matches `src` and `href` values starting with one of the prefixes
+/// (default `/assets/`); protocol-relative `//` URLs are left alone.
+#[derive(Clone, Debug)]
+pub struct CdnRewrite {
+    host: String,
+    prefixes: Vec<String>,
+}
+
+impl CdnRewrite {
+    /// Rewrites to `host` (e.g. `"https://cdn.example.com"`).
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into().trim_end_matches('/').to_string(),
+            prefixes: vec!["/assets/".into()],
+        }
+    }
+
+    /// Replaces the path prefixes to rewrite.
+    pub fn prefixes<I, S>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.prefixes = prefixes.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+impl HtmlTransform for CdnRewrite {
+    fn transform(&self, html: String, _ctx: &TransformContext) -> String {
+        rewrite_tags(
+            &html,
+            &["img", "script", "link", "source", "video", "audio"],
+            |tag| {
+                let edits: Vec<(Range<usize>, String)> = attributes(tag)
+                    .into_iter()
+                    .filter(|a| a.name == "src" || a.name == "href")
+                    .filter_map(|a| {
+                        let range = a.value?;
+                        let value = &tag[range.clone()];
+                        let matched = !value.starts_with("//")
+                            && self.prefixes.iter().any(|p| value.starts_with(p.as_str()));
+                        matched.then(|| (range, format!("{}{value}", self.host)))
+                    })
+                    .collect();
+                (!edits.is_empty()).then(|| splice(tag, edits))
+            },
+        )
+    }
+}
+
+/// Adds `nonce="<ctx.nonce>"` to `<script>` and `<style>` tags without one.
+///
+/// Does nothing when the context has no nonce.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NonceInjector;
+
+impl HtmlTransform for NonceInjector {
+    fn transform(&self, html: String, ctx: &TransformContext) -> String {
+        let Some(nonce) = ctx.nonce.as_deref() else {
+            return html;
+        };
+        let attr = format!(r#" nonce="{}""#, attr_escape(nonce));
+        rewrite_tags(&html, &["script", "style"], |tag| {
+            if attributes(tag).iter().any(|a| a.name == "nonce") {
+                return None;
+            }
+            let at = tag_name_end(tag);
+            Some(format!("{}{attr}{}", &tag[..at], &tag[at..]))
+        })
+    }
+}
+
+/// Adds `rel="noopener noreferrer"` to `<a>` tags without `rel` that open
+/// in a new tab or point to a host not listed as internal.
+#[derive(Clone, Debug, Default)]
+pub struct ExternalLinkRel {
+    internal_hosts: Vec<String>,
+}
+
+impl ExternalLinkRel {
+    /// Treats absolute links to `hosts` as internal.
+    pub fn new<I, S>(hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            internal_hosts: hosts
+                .into_iter()
+                .map(|h| h.into().to_ascii_lowercase())
+                .collect(),
+        }
+    }
+
+    fn is_external(&self, href: &str) -> bool {
+        let rest = match href.split_once("://") {
+            Some((scheme, rest))
+                if scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https") =>
+            {
+                rest
+            }
+            _ => match href.strip_prefix("//") {
+                Some(rest) => rest,
+                None => return false,
+            },
+        };
+        let host = rest
+            .split(['/', '?', '#'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        !self.internal_hosts.contains(&host)
+    }
+}
+
+impl HtmlTransform for ExternalLinkRel {
+    fn transform(&self, html: String, _ctx: &TransformContext) -> String {
+        rewrite_tags(&html, &["a"], |tag| {
+            let attrs = attributes(tag);
+            let value = |name: &str| {
+                attrs
+                    .iter()
+                    .find(|a| a.name == name)
+                    .map(|a| a.value.clone().map(|r| &tag[r]).unwrap_or_default())
+            };
+            if value("rel").is_some() {
+                return None;
+            }
+            let new_tab = value("target").is_some_and(|t| t.eq_ignore_ascii_case("_blank"));
+            let external = value("href").is_some_and(|h| self.is_external(h));
+            if !(new_tab || external) {
+                return None;
+            }
+            let at = tag_name_end(tag);
+            Some(format!(
+                r#"{} rel="noopener noreferrer"{}"#,
+                &tag[..at],
+                &tag[at..]
+            ))
+        })
+    }
+}
+
+/// An attribute in an opening tag: lowercase name and value byte range.
+struct Attribute {
+    name: String,
+    value: Option<Range<usize>>,
+}
+
+/// Calls `f` on every opening tag named in `names` (e.g. `<script ...>`),
+/// replacing the tag with the returned string.
+fn rewrite_tags(html: &str, names: &[&str], mut f: impl FnMut(&str) -> Option<String>) -> String {
+    let bytes = html.as_bytes();
+    let mut out = String::with_capacity(html.len());
+    let mut copied = 0;
+    let mut i = 0;
+
+    while let Some(off) = html[i..].find('<') {
+        let start = i + off;
+        let name_end = start + tag_name_end(&html[start..]);
+        let name = &html[start + 1..name_end];
+        i = start + 1;
+
+        if !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+            continue;
+        }
+        let Some(end) = find_tag_end(bytes, name_end) else {
+            break;
+        };
+        let tag = &html[start..end];
+        if let Some(replacement) = f(tag) {
+            out.push_str(&html[copied..start]);
+            out.push_str(&replacement);
+            copied = end;
+        }
+        i = end;
+    }
+
+    out.push_str(&html[copied..]);
+    out
+}
+
+/// Byte offset just past the tag name in `tag` (which starts with `<`).
+fn tag_name_end(tag: &str) -> usize {
+    1 + tag[1..]
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(tag.len() - 1)
+}
+
+/// Index just past the `>` closing the tag, honoring quoted values.
+fn find_tag_end(bytes: &[u8], from: usize) -> Option<usize> {
+    let mut quote = None;
+    for (i, &b) in bytes.iter().enumerate().skip(from) {
+        match (quote, b) {
+            (Some(q), _) if b == q => quote = None,
+            (Some(_), _) => {}
+            (None, b'"' | b'\'') => quote = Some(b),
+            (None, b'>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn attributes(tag: &str) -> Vec<Attribute> {
+    let b = tag.as_bytes();
+    let end = tag.len() - 1; // skip '>'
+    let mut i = tag_name_end(tag);
+    let mut attrs = Vec::new();
+
+    while i < end {
+        while i < end && (b[i].is_ascii_whitespace() || b[i] == b'/') {
+            i += 1;
+        }
+        let name_start = i;
+        while i < end && !b[i].is_ascii_whitespace() && !matches!(b[i], b'=' | b'>' | b'/') {
+            i += 1;
+        }
+        if i == name_start {
+            break;
+        }
+        let name = tag[name_start..i].to_ascii_lowercase();
+
+        let mut value = None;
+        if i < end && b[i] == b'=' {
+            i += 1;
+            if i < end && matches!(b[i], b'"' | b'\'') {
+                let q = b[i];
+                let v_start = i + 1;
+                let v_end = tag[v_start..end]
+                    .bytes()
+                    .position(|c| c == q)
+                    .map(|p| v_start + p)
+                    .unwrap_or(end);
+                value = Some(v_start..v_end);
+                i = v_end + 1;
+            } else {
+                let v_start = i;
+                while i < end && !b[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+                value = Some(v_start..i);
+            }
+        }
+        attrs.push(Attribute { name, value });
+    }
+    attrs
+}
+
+/// Replaces byte ranges of `tag` (ranges must not overlap).
+fn splice(tag: &str, mut edits: Vec<(Range<usize>, String)>) -> String {
+    edits.sort_by_key(|(r, _)| std::cmp::Reverse(r.start));
+    let mut out = tag.to_string();
+    for (range, text) in edits {
+        out.replace_range(range, &text);
+    }
+    out
+}
+
+fn attr_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> TransformContext {
+        TransformContext::default()
+    }
+
+    #[test]
+    fn cdn_rewrite_only_touches_asset_urls() {
+        let t = CdnRewrite::new("https://cdn.example.com/").prefixes(["/assets/", "/media/"]);
+        let html = r#"<img alt="a > b" src="/assets/a.png"><link rel=stylesheet href=/media/x.css><a href="/assets/doc.pdf">d</a><script src="//other/x.js"></script><IMG SRC='/static/b.png'>"#;
+
+        assert_eq!(
+            t.transform(html.into(), &ctx()),
+            r#"<img alt="a > b" src="https://cdn.example.com/assets/a.png"><link rel=stylesheet href=https://cdn.example.com/media/x.css><a href="/assets/doc.pdf">d</a><script src="//other/x.js"></script><IMG SRC='/static/b.png'>"#
+        );
+    }
+
+    #[test]
+    fn nonce_injector_skips_tags_with_nonce_and_missing_context() {
+        let html = r#"<script>a()</script><style nonce="x">b{}</style><Script type="module"></Script><scripts>"#;
+
+        assert_eq!(NonceInjector.transform(html.into(), &ctx()), html);
+        assert_eq!(
+            NonceInjector.transform(html.into(), &ctx().nonce("n\"1")),
+            r#"<script nonce="n&quot;1">a()</script><style nonce="x">b{}</style><Script nonce="n&quot;1" type="module"></Script><scripts>"#
+        );
+    }
+
+    #[test]
+    fn external_link_rel_annotates_external_and_new_tab_links() {
+        let t = ExternalLinkRel::new(["example.com"]);
+        let html = concat!(
+            r#"<a href="https://other.org/x">1</a>"#,
+            r#"<a href="https://EXAMPLE.com/y">2</a>"#,
+            r#"<a href="/local" target="_blank">3</a>"#,
+            r#"<a href="//cdn.net" rel="me">4</a>"#,
+            r#"<abbr title="https://x.org">5</abbr>"#,
+        );
+
+        assert_eq!(
+            t.transform(html.into(), &ctx()),
+            concat!(
+                r#"<a rel="noopener noreferrer" href="https://other.org/x">1</a>"#,
+                r#"<a href="https://EXAMPLE.com/y">2</a>"#,
+                r#"<a rel="noopener noreferrer" href="/local" target="_blank">3</a>"#,
+                r#"<a href="//cdn.net" rel="me">4</a>"#,
+                r#"<abbr title="https://x.org">5</abbr>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn pipeline_runs_transforms_in_order_including_closures() {
+        let pipeline = TemplatePipeline::new()
+            .with(|html: String, _: &TransformContext| html.replace("a", "b"))
+            .with(|html: String, _: &TransformContext| html.replace("b", "c"));
+
+        assert_eq!(pipeline.apply("a".into(), &ctx()), "c");
+        assert_eq!(TemplatePipeline::new().apply("a".into(), &ctx()), "a");
+    }
+}
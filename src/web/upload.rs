@@ -1,4 +1,14 @@
+pub mod blob_transfer;
+pub mod cache_policy;
+pub mod download;
+pub mod gc;
+pub mod integrity;
+pub mod keys;
 pub mod local_storage;
+pub mod media;
+pub mod metadata;
+pub mod migrate;
+pub mod quarantine;
 pub mod storage;
 pub mod upload_handler;
 pub mod uploader;
@@ -0,0 +1,300 @@
+//! # BLOB ⇄ Storage Transfer
+//!
+//! Moves file contents between a database `BLOB` column and a
+//! [`FileStorage`] backend chunk by chunk, e.g. to migrate legacy images
+//! kept in the database into object storage:
+//!
+//! - [`blob_to_storage`] / [`storage_to_blob`] copy one value
+//! - [`migrate_blobs`] copies every non-`NULL` value of a column
+//!
+//! Reads use [`BlobReader`](crate::db::blob::BlobReader) and storage writes
+//! use [`FileStorage::save_from`]; the reverse direction uses
+//! [`FileStorage::open`] and a [`BlobWriter`](crate::db::blob::BlobWriter).
+//! Memory use is bounded by the column's chunk size for backends that
+//! override those methods (e.g. [`LocalFileStorage`](crate::web::upload::local_storage::LocalFileStorage)).
+//!
+//! All functions block; call them from a job or `spawn_blocking`.
+//!
+//! # Example
+//! ```rust,no_run
+//! use wzs_web::config::db::DbConfig;
+//! use wzs_web::db::{blob::BlobColumn, connection::get_pool, mysql_adapter::MySqlDb};
+//! use wzs_web::web::upload::blob_transfer::migrate_blobs;
+//! use wzs_web::web::upload::local_storage::LocalFileStorage;
+//!
+//! # fn run() -> anyhow::Result<()> {
+//! let db = MySqlDb::new(get_pool(&DbConfig::from_env()));
+//! let storage = LocalFileStorage::new("./uploads");
+//! let images = BlobColumn::new("legacy_images", "data", "id")?;
+//!
+//! for moved in migrate_blobs(&db, &images, &storage, |id| format!("legacy/{id}.png"))? {
+//!     println!("{} -> {}", moved.key, moved.path);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io;
+
+use anyhow::{bail, Context, Result};
+
+use crate::db::blob::BlobColumn;
+use crate::db::port::{Db, Value};
+use crate::web::upload::storage::FileStorage;
+
+/// One value copied by [`migrate_blobs`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MigratedBlob {
+    /// Key of the source row.
+    pub key: String,
+    /// Path returned by [`FileStorage::save_from`].
+    pub path: String,
+}
+
+/// Copies the value of row `key` to `rel_path` in `storage`.
+///
+/// Returns the stored path, or `None` if the row is missing or the value is
+/// `NULL`.
+pub fn blob_to_storage(
+    db: &dyn Db,
+    column: &BlobColumn,
+    key: &str,
+    storage: &dyn FileStorage,
+    rel_path: &str,
+) -> Result<Option<String>> {
+    if column.len(db, key)?.is_none() {
+        return Ok(None);
+    }
+    let mut reader = column.reader(db, key);
+    let path = storage
+        .save_from(rel_path, &mut reader)
+        .with_context(|| format!("copy {}:{key} to {rel_path}", column.table()))?;
+    Ok(Some(path))
+}
+
+/// Copies `rel_path` from `storage` into the value of row `key`, replacing it.
+///
+/// Returns the number of bytes written, or `None` if nothing is stored at
+/// `rel_path`.
+///
+/// # Errors
+/// Returns an error if the row does not exist. A failure part-way through
+/// leaves the value truncated.
+pub fn storage_to_blob(
+    storage: &dyn FileStorage,
+    rel_path: &str,
+    db: &dyn Db,
+    column: &BlobColumn,
+    key: &str,
+) -> Result<Option<u64>> {
+    let Some(mut reader) = storage.open(rel_path)? else {
+        return Ok(None);
+    };
+    let mut writer = column.writer(db, key)?;
+    io::copy(&mut reader, &mut writer)
+        .with_context(|| format!("copy {rel_path} to {}:{key}", column.table()))?;
+    Ok(Some(writer.finish()?))
+}
+
+/// Copies every non-`NULL` value of `column` into `storage`, at the path
+/// `rel_path_for(key)`.
+///
+/// Only the keys are loaded up front; values are streamed one at a time.
+/// The source rows are left untouched, so the caller can record the
+/// returned paths and clear the column afterwards.
+pub fn migrate_blobs(
+    db: &dyn Db,
+    column: &BlobColumn,
+    storage: &dyn FileStorage,
+    rel_path_for: impl Fn(&str) -> String,
+) -> Result<Vec<MigratedBlob>> {
+    let sql = format!(
+        "SELECT {key} AS k FROM {} WHERE {} IS NOT NULL ORDER BY {key}",
+        column.table(),
+        column.column(),
+        key = column.key_column()
+    );
+    let keys = db
+        .fetch_all(&sql, &[])?
+        .iter()
+        .map(|row| match row.get("k") {
+            Some(Value::I64(v)) => Ok(v.to_string()),
+            Some(Value::U64(v)) => Ok(v.to_string()),
+            Some(Value::Str(s)) => Ok(s.clone()),
+            other => bail!("unsupported key value: {other:?}"),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut migrated = Vec::with_capacity(keys.len());
+    for key in keys {
+        let rel_path = rel_path_for(&key);
+        if let Some(path) = blob_to_storage(db, column, &key, storage, &rel_path)? {
+            tracing::debug!(%key, %path, "migrated blob to storage");
+            migrated.push(MigratedBlob { key, path });
+        }
+    }
+    tracing::info!(
+        table = column.table(),
+        count = migrated.len(),
+        "blob migration finished"
+    );
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::io::Read;
+    use std::sync::Mutex;
+
+    use crate::db::port::{Param, Row};
+
+    /// Rows `id -> data` interpreting the statements issued by `BlobColumn`.
+    #[derive(Default)]
+    struct BlobDb(Mutex<BTreeMap<String, Option<Vec<u8>>>>);
+
+    impl Db for BlobDb {
+        fn fetch_one(&self, sql: &str, params: &[Param]) -> Result<Option<Row>> {
+            let Some(Param::Str(key)) = params.last() else {
+                bail!("key not bound");
+            };
+            let Some(data) = self.0.lock().unwrap().get(*key).cloned() else {
+                return Ok(None);
+            };
+            let mut row = Row::default();
+            if sql.contains("OCTET_LENGTH") {
+                let len = data.map_or(Value::Null, |d| Value::U64(d.len() as u64));
+                row.insert("len", len);
+            } else if sql.contains("SUBSTRING") {
+                let (Param::U64(start), Param::U64(len)) = (&params[0], &params[1]) else {
+                    bail!("bad params");
+                };
+                let d = data.unwrap_or_default();
+                let start = (*start as usize - 1).min(d.len());
+                let end = (start + *len as usize).min(d.len());
+                row.insert("chunk", Value::Bin(d[start..end].to_vec()));
+            }
+            Ok(Some(row))
+        }
+
+        fn fetch_all(&self, _sql: &str, _params: &[Param]) -> Result<Vec<Row>> {
+            Ok(self
+                .0
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, v)| v.is_some())
+                .map(|(k, _)| {
+                    let mut row = Row::default();
+                    row.insert("k", Value::I64(k.parse().unwrap()));
+                    row
+                })
+                .collect())
+        }
+
+        fn exec(&self, _sql: &str, params: &[Param]) -> Result<u64> {
+            let mut rows = self.0.lock().unwrap();
+            match params {
+                [Param::Bin(b), Param::Str(key)] => rows
+                    .get_mut(*key)
+                    .unwrap()
+                    .get_or_insert_with(Vec::new)
+                    .extend_from_slice(b),
+                [Param::Str(key)] => {
+                    rows.insert(key.to_string(), Some(Vec::new()));
+                }
+                _ => bail!("unexpected statement"),
+            }
+            Ok(1)
+        }
+
+        fn exec_returning_last_insert_id(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    #[derive(Default)]
+    struct MemStorage(Mutex<BTreeMap<String, Vec<u8>>>);
+
+    impl FileStorage for MemStorage {
+        fn save(&self, rel_path: &str, bytes: &[u8]) -> Result<String> {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(rel_path.into(), bytes.to_vec());
+            Ok(format!("/mem/{rel_path}"))
+        }
+
+        fn load(&self, rel_path: &str) -> Result<Option<Vec<u8>>> {
+            Ok(self.0.lock().unwrap().get(rel_path).cloned())
+        }
+    }
+
+    fn setup() -> (BlobDb, BlobColumn, MemStorage) {
+        let db = BlobDb::default();
+        {
+            let mut rows = db.0.lock().unwrap();
+            rows.insert("1".into(), Some(b"first image".to_vec()));
+            rows.insert("2".into(), None);
+            rows.insert("3".into(), Some(vec![0xff, 0x00, 0xfe]));
+        }
+        let column = BlobColumn::new("legacy_images", "data", "id")
+            .unwrap()
+            .chunk_size(4);
+        (db, column, MemStorage::default())
+    }
+
+    #[test]
+    fn copies_values_in_both_directions() {
+        let (db, column, storage) = setup();
+
+        let path = blob_to_storage(&db, &column, "1", &storage, "a.png").unwrap();
+        assert_eq!(path.as_deref(), Some("/mem/a.png"));
+        assert_eq!(
+            storage.load("a.png").unwrap(),
+            Some(b"first image".to_vec())
+        );
+        assert_eq!(
+            blob_to_storage(&db, &column, "2", &storage, "b.png").unwrap(),
+            None
+        );
+
+        storage.save("new.png", b"replacement").unwrap();
+        let written = storage_to_blob(&storage, "new.png", &db, &column, "3").unwrap();
+        assert_eq!(written, Some(11));
+        let mut back = Vec::new();
+        column.reader(&db, "3").read_to_end(&mut back).unwrap();
+        assert_eq!(back, b"replacement");
+
+        assert_eq!(
+            storage_to_blob(&storage, "missing.png", &db, &column, "3").unwrap(),
+            None
+        );
+        assert!(storage_to_blob(&storage, "new.png", &db, &column, "9").is_err());
+    }
+
+    #[test]
+    fn migrate_copies_every_non_null_value() {
+        let (db, column, storage) = setup();
+
+        let moved = migrate_blobs(&db, &column, &storage, |id| format!("legacy/{id}.bin")).unwrap();
+        assert_eq!(
+            moved,
+            vec![
+                MigratedBlob {
+                    key: "1".into(),
+                    path: "/mem/legacy/1.bin".into()
+                },
+                MigratedBlob {
+                    key: "3".into(),
+                    path: "/mem/legacy/3.bin".into()
+                },
+            ]
+        );
+        assert_eq!(
+            storage.load("legacy/3.bin").unwrap(),
+            Some(vec![0xff, 0x00, 0xfe])
+        );
+    }
+}
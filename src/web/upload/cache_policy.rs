@@ -0,0 +1,340 @@
+//! # Per-directory Cache Headers
+//!
+//! [`CachePolicy`] decides `Cache-Control` and `ETag` for stored files,
+//! keyed by directory prefix.
+//!
+//! Directories registered with [`content_addressed`](CachePolicy::content_addressed)
+//! hold files named after a hash of their contents (e.g.
+//! `assets/3f2a9c…e1.js`). Such a file never changes under its key, so it is
+//! served with [`IMMUTABLE`] and the hash itself as a strong `ETag` (no need
+//! to read the body). Keys in those directories whose file stem is not a hash
+//! fall back to the directory-independent default.
+//!
+//! Used by [`download_handler`](crate::web::upload::download::download_handler)
+//! and by the [`static_cache_headers`] middleware in front of
+//! `tower_http::services::ServeDir`.
+//!
+//! # Example
+//! ```rust,no_run
+//! use axum::{middleware::from_fn_with_state, Router};
+//! use tower_http::services::ServeDir;
+//! use wzs_web::web::upload::cache_policy::{static_cache_headers, CachePolicy};
+//!
+//! let policy = CachePolicy::new()
+//!     .content_addressed("assets")
+//!     .dir("img/avatars", "public, max-age=300");
+//!
+//! let app: Router = Router::new()
+//!     .fallback_service(ServeDir::new("./public"))
+//!     .layer(from_fn_with_state(policy, static_cache_headers));
+//! ```
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+/// `Cache-Control` for content-addressed files: cache for a year, never revalidate.
+pub const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// Shortest file stem treated as a content hash (64 bits of hex).
+const MIN_HASH_LEN: usize = 16;
+
+#[derive(Clone, Debug)]
+struct DirRule {
+    prefix: String,
+    cache_control: String,
+    content_addressed: bool,
+}
+
+/// Cache headers chosen for one file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CacheHeaders {
+    pub cache_control: String,
+    /// Quoted strong entity tag.
+    pub etag: String,
+}
+
+/// Maps directory prefixes to cache headers.
+///
+/// The longest matching prefix wins; prefixes match whole path segments.
+#[derive(Clone, Debug)]
+pub struct CachePolicy {
+    rules: Vec<DirRule>,
+    default_cache_control: String,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CachePolicy {
+    /// Creates a policy that serves everything with `no-cache` (revalidate
+    /// with the `ETag` on every use).
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            default_cache_control: "no-cache".into(),
+        }
+    }
+
+    /// Sets `Cache-Control` for files no rule matches.
+    pub fn default_cache_control(mut self, value: impl Into<String>) -> Self {
+        self.default_cache_control = value.into();
+        self
+    }
+
+    /// Serves files under `prefix` with a fixed `Cache-Control`.
+    pub fn dir(mut self, prefix: impl Into<String>, cache_control: impl Into<String>) -> Self {
+        self.rules.push(DirRule {
+            prefix: normalize(&prefix.into()),
+            cache_control: cache_control.into(),
+            content_addressed: false,
+        });
+        self
+    }
+
+    /// Marks `prefix` as holding content-hash keys (see the [module docs](self)).
+    pub fn content_addressed(mut self, prefix: impl Into<String>) -> Self {
+        self.rules.push(DirRule {
+            prefix: normalize(&prefix.into()),
+            cache_control: IMMUTABLE.into(),
+            content_addressed: true,
+        });
+        self
+    }
+
+    /// Returns the content hash of `key` if it is an immutable,
+    /// content-addressed file under this policy.
+    pub fn content_hash<'k>(&self, key: &'k str) -> Option<&'k str> {
+        self.rule(key)
+            .filter(|r| r.content_addressed)
+            .and_then(|_| hash_stem(key))
+    }
+
+    /// Headers for `key`; `body` is hashed only when the key carries no hash.
+    pub fn headers_for(&self, key: &str, body: &[u8]) -> CacheHeaders {
+        if let Some(hash) = self.content_hash(key) {
+            return CacheHeaders {
+                cache_control: IMMUTABLE.into(),
+                etag: format!("\"{hash}\""),
+            };
+        }
+        let cache_control = match self.rule(key) {
+            Some(r) if !r.content_addressed => r.cache_control.clone(),
+            _ => self.default_cache_control.clone(),
+        };
+        CacheHeaders {
+            cache_control,
+            etag: body_etag(body),
+        }
+    }
+
+    fn rule(&self, key: &str) -> Option<&DirRule> {
+        let key = key.trim_start_matches('/');
+        self.rules
+            .iter()
+            .filter(|r| {
+                r.prefix.is_empty()
+                    || key
+                        .strip_prefix(r.prefix.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .max_by_key(|r| r.prefix.len())
+    }
+}
+
+/// Strong `ETag` derived from a SHA-256 of `body` (first 128 bits).
+pub fn body_etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    let hex: String = digest[..16].iter().map(|b| format!("{b:02x}")).collect();
+    format!("\"{hex}\"")
+}
+
+/// Returns whether the request's `If-None-Match` matches `etag`
+/// (weak comparison, `*` matches anything).
+pub fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque = |t: &str| t.trim().trim_start_matches("W/").to_string();
+    let want = opaque(etag);
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|t| t.trim() == "*" || opaque(t) == want)
+}
+
+/// `304 Not Modified` carrying the validators the client cached.
+pub fn not_modified(h: &CacheHeaders) -> Response {
+    let mut res = StatusCode::NOT_MODIFIED.into_response();
+    set_headers(res.headers_mut(), h);
+    res
+}
+
+/// Middleware applying a [`CachePolicy`] to successful static responses,
+/// matched on the request path.
+///
+/// Content-addressed paths get [`IMMUTABLE`] and a hash `ETag`, and a
+/// matching `If-None-Match` is answered with `304` without reaching the
+/// inner service. Other paths only get `Cache-Control` when a
+/// [`dir`](CachePolicy::dir) rule matches; responses that already carry
+/// `Cache-Control` are left alone.
+pub async fn static_cache_headers(
+    State(policy): State<CachePolicy>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let path = req.uri().path().to_string();
+    let hashed = policy.content_hash(&path).map(|hash| CacheHeaders {
+        cache_control: IMMUTABLE.into(),
+        etag: format!("\"{hash}\""),
+    });
+    if let Some(h) = hashed
+        .as_ref()
+        .filter(|h| etag_matches(req.headers(), &h.etag))
+    {
+        return not_modified(h);
+    }
+
+    let mut res = next.run(req).await;
+    if !res.status().is_success() || res.headers().contains_key(header::CACHE_CONTROL) {
+        return res;
+    }
+    match hashed {
+        Some(h) => set_headers(res.headers_mut(), &h),
+        None => {
+            let value = policy
+                .rule(&path)
+                .filter(|r| !r.content_addressed)
+                .and_then(|r| HeaderValue::from_str(&r.cache_control).ok());
+            if let Some(value) = value {
+                res.headers_mut().insert(header::CACHE_CONTROL, value);
+            }
+        }
+    }
+    res
+}
+
+fn set_headers(headers: &mut HeaderMap, h: &CacheHeaders) {
+    if let Ok(v) = HeaderValue::from_str(&h.cache_control) {
+        headers.insert(header::CACHE_CONTROL, v);
+    }
+    if let Ok(v) = HeaderValue::from_str(&h.etag) {
+        headers.insert(header::ETAG, v);
+    }
+}
+
+fn normalize(prefix: &str) -> String {
+    prefix.trim_matches('/').to_string()
+}
+
+/// File stem (before the first `.`) if it looks like a hex or base64url hash.
+fn hash_stem(key: &str) -> Option<&str> {
+    let name = key.rsplit('/').next()?;
+    let stem = name.split('.').next()?;
+    let is_hash = stem.len() >= MIN_HASH_LEN
+        && stem
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        && stem.bytes().any(|b| b.is_ascii_digit());
+    is_hash.then_some(stem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use tower::ServiceExt;
+
+    const HASH: &str = "3f2a9c0d8e7b6a5f4e3d2c1b";
+
+    fn policy() -> CachePolicy {
+        CachePolicy::new()
+            .content_addressed("assets")
+            .dir("img", "public, max-age=300")
+            .dir("img/avatars", "private, max-age=60")
+    }
+
+    #[test]
+    fn content_addressed_keys_are_immutable_with_hash_etag() {
+        let h = policy().headers_for(&format!("assets/{HASH}.js"), b"ignored");
+        assert_eq!(h.cache_control, IMMUTABLE);
+        assert_eq!(h.etag, format!("\"{HASH}\""));
+
+        let h = policy().headers_for("assets/app.js", b"body");
+        assert_eq!(h.cache_control, "no-cache");
+        assert_eq!(h.etag, body_etag(b"body"));
+    }
+
+    #[test]
+    fn longest_directory_prefix_wins_on_segment_boundaries() {
+        let p = policy();
+        assert_eq!(
+            p.headers_for("img/a.png", b"").cache_control,
+            "public, max-age=300"
+        );
+        assert_eq!(
+            p.headers_for("/img/avatars/u.png", b"").cache_control,
+            "private, max-age=60"
+        );
+        assert_eq!(p.headers_for("imgs/a.png", b"").cache_control, "no-cache");
+        assert_eq!(p.content_hash(&format!("assetsx/{HASH}.js")), None);
+    }
+
+    #[test]
+    fn if_none_match_uses_weak_comparison_and_lists() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_static("\"other\", W/\"abc\""),
+        );
+        assert!(etag_matches(&headers, "\"abc\""));
+        assert!(!etag_matches(&headers, "\"abd\""));
+        assert!(!etag_matches(&HeaderMap::new(), "\"abc\""));
+    }
+
+    #[tokio::test]
+    async fn middleware_sets_headers_and_short_circuits_revalidation() {
+        let app = Router::new()
+            .route("/assets/{name}", get(|| async { "js" }))
+            .route("/img/{name}", get(|| async { "png" }))
+            .layer(axum::middleware::from_fn_with_state(
+                policy(),
+                static_cache_headers,
+            ));
+
+        let path = format!("/assets/{HASH}.js");
+        let res = app
+            .clone()
+            .oneshot(Request::get(&path).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.headers()[header::CACHE_CONTROL], IMMUTABLE);
+        let etag = res.headers()[header::ETAG].clone();
+
+        let res = app
+            .clone()
+            .oneshot(
+                Request::get(&path)
+                    .header(header::IF_NONE_MATCH, etag)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        let res = app
+            .oneshot(Request::get("/img/a.png").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.headers()[header::CACHE_CONTROL], "public, max-age=300");
+        assert!(!res.headers().contains_key(header::ETAG));
+    }
+}
@@ -0,0 +1,469 @@
+//! # File Downloads
+//!
+//! Serves stored originals from a [`FileStorage`]:
+//!
+//! ```text
+//! GET /files/{key}
+//! ```
+//!
+//! Responses carry `Cache-Control` and `ETag` from a [`CachePolicy`], so
+//! content-hash keys are cached by browsers and CDNs as immutable, and a
+//! matching `If-None-Match` is answered with `304 Not Modified`.
+//!
+//! Only raster images (PNG, JPEG, GIF, WebP) are served inline; anything
+//! else (HTML, SVG, PDF, ...) is sent as `Content-Disposition: attachment`.
+//! Every download carries [`DOWNLOAD_CSP`], so a stored file opened
+//! directly cannot run script against the site's origin.
+//!
+//! With a [`QuarantineStore`] configured, quarantined keys are answered with
+//! `410` / `451` and nothing under
+//! [`QUARANTINE_PREFIX`](crate::web::upload::quarantine::QUARANTINE_PREFIX)
+//! is served (see [`quarantine`](crate::web::upload::quarantine)).
+//!
+//! [`mtime_download_handler`] validates by modification time instead of
+//! hashing the body, for storages that implement
+//! [`stat`](FileStorage::stat).
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use axum::{routing::get, Extension, Router};
+//! use wzs_web::web::upload::cache_policy::CachePolicy;
+//! use wzs_web::web::upload::download::{download_handler, DownloadService};
+//! use wzs_web::web::upload::local_storage::LocalFileStorage;
+//!
+//! let downloads = Arc::new(DownloadService::new(
+//!     Arc::new(LocalFileStorage::new("./uploads")),
+//!     CachePolicy::new().content_addressed("blobs"),
+//! ));
+//!
+//! let app: Router = Router::new()
+//!     .route("/files/{*key}", get(download_handler))
+//!     .layer(Extension(downloads));
+//! ```
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use axum::{
+    extract::Path,
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    Extension,
+};
+
+use crate::error::app::AppError;
+use crate::web::etag::ETag;
+use crate::web::problem::Problem;
+use crate::web::upload::cache_policy::{etag_matches, not_modified, CacheHeaders, CachePolicy};
+use crate::web::upload::media::{is_safe_key, raster_content_type};
+use crate::web::upload::quarantine::{QuarantineStore, QUARANTINE_PREFIX};
+use crate::web::upload::storage::{FileStorage, StoredObject};
+
+/// `Content-Security-Policy` sent with every download.
+pub const DOWNLOAD_CSP: &str = "default-src 'none'; sandbox";
+
+/// Loads stored files and picks their cache headers.
+pub struct DownloadService {
+    storage: Arc<dyn FileStorage>,
+    policy: CachePolicy,
+    quarantine: Option<Arc<dyn QuarantineStore>>,
+}
+
+impl DownloadService {
+    /// Creates a new service.
+    pub fn new(storage: Arc<dyn FileStorage>, policy: CachePolicy) -> Self {
+        Self {
+            storage,
+            policy,
+            quarantine: None,
+        }
+    }
+
+    /// Refuses quarantined keys and hides the quarantine prefix.
+    pub fn quarantine(mut self, store: Arc<dyn QuarantineStore>) -> Self {
+        self.quarantine = Some(store);
+        self
+    }
+
+    /// Returns the cache policy.
+    pub fn policy(&self) -> &CachePolicy {
+        &self.policy
+    }
+
+    /// Loads `key`; `Ok(None)` if it does not exist.
+    pub fn load(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        self.storage.load(key)
+    }
+
+    /// Size and modification time of `key`; `Ok(None)` if it does not
+    /// exist.
+    pub fn stat(&self, key: &str) -> Result<Option<StoredObject>> {
+        self.storage.stat(key)
+    }
+
+    /// Rejects unsafe, quarantined and quarantine-prefixed keys.
+    async fn check_key(&self, key: &str) -> Result<(), AppError> {
+        if !is_safe_key(key) {
+            return Err(AppError::bad_request("invalid file key"));
+        }
+
+        if let Some(store) = self.quarantine.clone() {
+            if key.split('/').next() == Some(QUARANTINE_PREFIX) {
+                return Err(AppError::not_found("not found"));
+            }
+            let lookup = key.to_string();
+            let quarantined = tokio::task::spawn_blocking(move || store.quarantine(&lookup))
+                .await
+                .map_err(|e| AppError::Internal(anyhow!("download task failed: {e}")))?
+                .map_err(|e| AppError::Internal(e.context("quarantine lookup failed")))?;
+            if let Some(q) = quarantined {
+                return Err(Problem::new(q.reason.status()).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Serves a stored file.
+///
+/// # Required Extensions
+/// - `Arc<DownloadService>`
+///
+/// # Returns
+/// - `200 OK` with the file, its guessed content type and cache headers;
+///   non-image files as an attachment
+/// - `304 NOT MODIFIED` when `If-None-Match` matches the `ETag`
+/// - `400 BAD REQUEST` for keys with `..`, backslashes or empty segments
+/// - `404 NOT FOUND` if nothing is stored under the key (or it lies under
+///   the quarantine prefix)
+/// - `410 GONE` / `451 UNAVAILABLE FOR LEGAL REASONS` for quarantined keys
+/// - `500 INTERNAL SERVER ERROR` when loading fails
+///
+/// Errors are [`AppError`] problem documents (`application/problem+json`).
+pub async fn download_handler(
+    Extension(downloads): Extension<Arc<DownloadService>>,
+    Path(key): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    downloads.check_key(&key).await?;
+
+    // Content-hash keys can be revalidated without touching storage.
+    if downloads.policy.content_hash(&key).is_some() {
+        let h = downloads.policy.headers_for(&key, &[]);
+        if etag_matches(&headers, &h.etag) {
+            return Ok(not_modified(&h));
+        }
+    }
+
+    let worker = downloads.clone();
+    let lookup = key.clone();
+    let bytes = tokio::task::spawn_blocking(move || worker.load(&lookup))
+        .await
+        .map_err(|e| AppError::Internal(anyhow!("download task failed: {e}")))?
+        .map_err(|e| AppError::Internal(e.context("download error")))?
+        .ok_or_else(|| AppError::not_found("not found"))?;
+
+    let h = downloads.policy.headers_for(&key, &bytes);
+    if etag_matches(&headers, &h.etag) {
+        return Ok(not_modified(&h));
+    }
+
+    Ok(file_response(&key, h, bytes))
+}
+
+/// Serves a stored file, revalidated by its modification time.
+///
+/// Like [`download_handler`], but keys that are not content-addressed get
+/// a weak [`ETag::for_file`] from the storage's
+/// [`stat`](FileStorage::stat), so a matching `If-None-Match` is answered
+/// without reading the file. The storage must implement `stat`.
+///
+/// # Required Extensions
+/// - `Arc<DownloadService>`
+///
+/// # Returns
+/// The same statuses as [`download_handler`].
+pub async fn mtime_download_handler(
+    Extension(downloads): Extension<Arc<DownloadService>>,
+    Path(key): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    downloads.check_key(&key).await?;
+
+    let mut h = downloads.policy.headers_for(&key, &[]);
+    if downloads.policy.content_hash(&key).is_none() {
+        let worker = downloads.clone();
+        let lookup = key.clone();
+        let stat = tokio::task::spawn_blocking(move || worker.stat(&lookup))
+            .await
+            .map_err(|e| AppError::Internal(anyhow!("download task failed: {e}")))?
+            .map_err(|e| AppError::Internal(e.context("stat error")))?
+            .ok_or_else(|| AppError::not_found("not found"))?;
+        h.etag = ETag::for_file(stat.modified, stat.bytes).to_string();
+    }
+    if etag_matches(&headers, &h.etag) {
+        return Ok(not_modified(&h));
+    }
+
+    let worker = downloads.clone();
+    let lookup = key.clone();
+    let bytes = tokio::task::spawn_blocking(move || worker.load(&lookup))
+        .await
+        .map_err(|e| AppError::Internal(anyhow!("download task failed: {e}")))?
+        .map_err(|e| AppError::Internal(e.context("download error")))?
+        .ok_or_else(|| AppError::not_found("not found"))?;
+    Ok(file_response(&key, h, bytes))
+}
+
+fn file_response(key: &str, h: CacheHeaders, bytes: Vec<u8>) -> Response {
+    let content_type = mime_guess::from_path(key).first_or_octet_stream();
+    let disposition = if raster_content_type(key).is_some() {
+        "inline"
+    } else {
+        "attachment"
+    };
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, disposition.to_string()),
+            (header::CACHE_CONTROL, h.cache_control),
+            (header::ETAG, h.etag),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+            (header::CONTENT_SECURITY_POLICY, DOWNLOAD_CSP.to_string()),
+        ],
+        bytes,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    use crate::web::upload::cache_policy::IMMUTABLE;
+    use crate::web::upload::quarantine::{Quarantine, QuarantineReason};
+
+    const HASHED: &str = "blobs/9b74c9897bac770ffc029102a200c5de.pdf";
+
+    #[derive(Default)]
+    struct MemoryStorage {
+        files: Mutex<HashMap<String, Vec<u8>>>,
+        loads: Mutex<usize>,
+    }
+
+    impl FileStorage for MemoryStorage {
+        fn save(&self, rel_path: &str, bytes: &[u8]) -> Result<String> {
+            self.files
+                .lock()
+                .unwrap()
+                .insert(rel_path.to_string(), bytes.to_vec());
+            Ok(rel_path.to_string())
+        }
+
+        fn load(&self, rel_path: &str) -> Result<Option<Vec<u8>>> {
+            *self.loads.lock().unwrap() += 1;
+            Ok(self.files.lock().unwrap().get(rel_path).cloned())
+        }
+
+        fn stat(&self, rel_path: &str) -> Result<Option<StoredObject>> {
+            Ok(self
+                .files
+                .lock()
+                .unwrap()
+                .get(rel_path)
+                .map(|f| StoredObject {
+                    path: rel_path.to_string(),
+                    bytes: f.len() as u64,
+                    modified: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+                }))
+        }
+    }
+
+    fn app() -> (Router, Arc<MemoryStorage>) {
+        let storage = Arc::new(MemoryStorage::default());
+        storage.save(HASHED, b"%PDF").unwrap();
+        storage.save("files/report.txt", b"hello").unwrap();
+        let svc = Arc::new(DownloadService::new(
+            storage.clone(),
+            CachePolicy::new().content_addressed("blobs"),
+        ));
+        let app = Router::new()
+            .route("/files/{*key}", get(download_handler))
+            .layer(Extension(svc));
+        (app, storage)
+    }
+
+    async fn send(app: Router, uri: &str, if_none_match: Option<&str>) -> Response {
+        let mut req = Request::get(uri);
+        if let Some(tag) = if_none_match {
+            req = req.header(header::IF_NONE_MATCH, tag);
+        }
+        app.oneshot(req.body(Body::empty()).unwrap()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn hashed_keys_are_immutable_and_revalidate_without_loading() {
+        let (app, storage) = app();
+        let res = send(app.clone(), &format!("/files/{HASHED}"), None).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "application/pdf");
+        assert_eq!(res.headers()[header::CACHE_CONTROL], IMMUTABLE);
+        let etag = res.headers()[header::ETAG].to_str().unwrap().to_string();
+        assert_eq!(etag, "\"9b74c9897bac770ffc029102a200c5de\"");
+
+        let res = send(app, &format!("/files/{HASHED}"), Some(&etag)).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(*storage.loads.lock().unwrap(), 1);
+    }
+
+    struct Quarantined;
+
+    impl QuarantineStore for Quarantined {
+        fn quarantine(&self, key: &str) -> Result<Option<Quarantine>> {
+            let reason = match key {
+                "files/report.txt" => QuarantineReason::Malware,
+                k if k == HASHED => QuarantineReason::Legal,
+                _ => return Ok(None),
+            };
+            Ok(Some(Quarantine {
+                reason,
+                detail: String::new(),
+            }))
+        }
+
+        fn mark_quarantined(&self, _key: &str, _quarantine: &Quarantine) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn quarantined_keys_are_refused_and_the_prefix_hidden() {
+        let storage = Arc::new(MemoryStorage::default());
+        storage.save("files/report.txt", b"hello").unwrap();
+        storage.save("quarantine/files/x.txt", b"bad").unwrap();
+        storage.save("files/ok.txt", b"ok").unwrap();
+        let svc = Arc::new(
+            DownloadService::new(storage, CachePolicy::new().content_addressed("blobs"))
+                .quarantine(Arc::new(Quarantined)),
+        );
+        let app = Router::new()
+            .route("/files/{*key}", get(download_handler))
+            .layer(Extension(svc));
+
+        let status = |uri: &'static str| {
+            let app = app.clone();
+            async move { send(app, uri, None).await.status() }
+        };
+        assert_eq!(status("/files/files/report.txt").await, StatusCode::GONE);
+        assert_eq!(
+            send(app.clone(), &format!("/files/{HASHED}"), Some("*"))
+                .await
+                .status(),
+            StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS
+        );
+        assert_eq!(
+            status("/files/quarantine/files/x.txt").await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(status("/files/files/ok.txt").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn other_keys_get_default_policy_and_body_etag() {
+        let (app, _) = app();
+        let res = send(app.clone(), "/files/files/report.txt", None).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CACHE_CONTROL], "no-cache");
+        let etag = res.headers()[header::ETAG].to_str().unwrap().to_string();
+
+        let res = send(app.clone(), "/files/files/report.txt", Some(&etag)).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        assert_eq!(
+            send(app.clone(), "/files/files/missing.txt", None)
+                .await
+                .status(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            send(app, "/files/files/../secret", None).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn only_raster_images_are_served_inline() {
+        let (app, storage) = app();
+        storage.save("files/page.html", b"<script>").unwrap();
+        storage.save("files/logo.svg", b"<svg/>").unwrap();
+        storage.save("files/photo.JPG", b"jpeg").unwrap();
+
+        for (key, disposition) in [
+            ("files/page.html", "attachment"),
+            ("files/logo.svg", "attachment"),
+            (HASHED, "attachment"),
+            ("files/report.txt", "attachment"),
+            ("files/photo.JPG", "inline"),
+        ] {
+            let res = send(app.clone(), &format!("/files/{key}"), None).await;
+            assert_eq!(res.status(), StatusCode::OK, "{key}");
+            assert_eq!(
+                res.headers()[header::CONTENT_DISPOSITION],
+                disposition,
+                "{key}"
+            );
+            assert_eq!(res.headers()[header::CONTENT_SECURITY_POLICY], DOWNLOAD_CSP);
+            assert_eq!(res.headers()[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+        }
+    }
+
+    #[tokio::test]
+    async fn mtime_handler_revalidates_without_loading() {
+        let (_, storage) = app();
+        let svc = Arc::new(DownloadService::new(
+            storage.clone(),
+            CachePolicy::new().content_addressed("blobs"),
+        ));
+        let app = Router::new()
+            .route("/files/{*key}", get(mtime_download_handler))
+            .layer(Extension(svc));
+
+        let res = send(app.clone(), "/files/files/report.txt", None).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::ETAG], "W/\"6553f100.0-5\"");
+        assert_eq!(*storage.loads.lock().unwrap(), 1);
+
+        let res = send(
+            app.clone(),
+            "/files/files/report.txt",
+            Some("W/\"6553f100.0-5\""),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        let res = send(
+            app.clone(),
+            &format!("/files/{HASHED}"),
+            Some("\"9b74c9897bac770ffc029102a200c5de\""),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(*storage.loads.lock().unwrap(), 1);
+
+        assert_eq!(
+            send(app, "/files/files/missing.txt", None).await.status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+}
@@ -0,0 +1,588 @@
+//! # Upload Garbage Collection
+//!
+//! Reconciles [`FileStorage`] against the upload metadata table:
+//!
+//! - **Orphans**: files under the scanned prefixes with no metadata row
+//!   (older than `min_age`, so uploads still being committed are spared).
+//! - **Expired temp uploads**: files under `temp_prefix` older than `temp_ttl`.
+//!
+//! Runs in dry-run mode by default; the [`GcReport`] lists what was (or would
+//! be) deleted and can be mailed through an [`EmailSender`].
+//!
+//! Expected metadata schema (MySQL, only the path column is read):
+//!
+//! ```sql
+//! CREATE TABLE uploads (
+//!     id   BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+//!     path VARCHAR(512) NOT NULL,
+//!     UNIQUE KEY uq_uploads_path (path)
+//! );
+//! ```
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::{sync::Arc, time::Duration};
+//! use wzs_web::config::db::DbConfig;
+//! use wzs_web::db::{connection::get_pool, mysql_adapter::MySqlDb};
+//! use wzs_web::web::upload::gc::{DbUploadIndex, UploadGc, UploadGcConfig};
+//! use wzs_web::web::upload::local_storage::LocalFileStorage;
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let db = Arc::new(MySqlDb::new(get_pool(&DbConfig::from_env())));
+//! let gc = Arc::new(UploadGc::new(
+//!     Arc::new(LocalFileStorage::new("./uploads")),
+//!     Arc::new(DbUploadIndex::new(db)),
+//!     UploadGcConfig::default().dry_run(false),
+//! ));
+//!
+//! let report = gc.run().await?;
+//! println!("{}", report.summary());
+//!
+//! // Or run hourly in the background:
+//! let _handle = gc.spawn_every(Duration::from_secs(3600));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use lettre::message::Mailbox;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::db::port::Db;
+use crate::db::repository::ident;
+use crate::notification::email::{Email, EmailBody};
+use crate::notification::email_sender::EmailSender;
+use crate::telemetry::jobs::{observe_job, JobMetrics};
+use crate::web::upload::storage::{FileStorage, StoredObject};
+
+/// Source of truth for which stored paths are still referenced.
+pub trait UploadIndex: Send + Sync {
+    /// Returns every path recorded in the metadata table.
+    fn known_paths(&self) -> Result<HashSet<String>>;
+}
+
+/// [`UploadIndex`] reading a path column from a SQL table.
+#[derive(Clone)]
+pub struct DbUploadIndex {
+    db: Arc<dyn Db>,
+    table: String,
+    column: String,
+}
+
+impl DbUploadIndex {
+    /// Default table name.
+    pub const DEFAULT_TABLE: &'static str = "uploads";
+    /// Default path column.
+    pub const DEFAULT_COLUMN: &'static str = "path";
+
+    /// Creates an index over `uploads.path`.
+    pub fn new(db: Arc<dyn Db>) -> Self {
+        Self {
+            db,
+            table: Self::DEFAULT_TABLE.into(),
+            column: Self::DEFAULT_COLUMN.into(),
+        }
+    }
+
+    /// Creates an index over a custom table and column.
+    ///
+    /// # Errors
+    /// Returns an error if either name is not a plain SQL identifier.
+    pub fn with_table(
+        db: Arc<dyn Db>,
+        table: impl Into<String>,
+        column: impl Into<String>,
+    ) -> Result<Self> {
+        let (table, column) = (table.into(), column.into());
+        for name in [&table, &column] {
+            ident(name)?;
+        }
+        Ok(Self { db, table, column })
+    }
+}
+
+impl UploadIndex for DbUploadIndex {
+    fn known_paths(&self) -> Result<HashSet<String>> {
+        let sql = format!("SELECT {} AS path FROM {}", self.column, self.table);
+        self.db
+            .fetch_all(&sql, &[])?
+            .iter()
+            .map(|r| r.get_string("path"))
+            .collect()
+    }
+}
+
+/// Garbage collection settings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UploadGcConfig {
+    /// Prefixes reconciled against the index (default: `images`, `files`).
+    pub prefixes: Vec<String>,
+    /// Prefix for temporary uploads (default: `tmp`).
+    pub temp_prefix: String,
+    /// Age after which temporary uploads are deleted (default: 24h).
+    pub temp_ttl: Duration,
+    /// Orphans younger than this are kept (default: 1h).
+    pub min_age: Duration,
+    /// Report only, delete nothing (default: `true`).
+    pub dry_run: bool,
+}
+
+impl Default for UploadGcConfig {
+    fn default() -> Self {
+        Self {
+            prefixes: vec!["images".into(), "files".into()],
+            temp_prefix: "tmp".into(),
+            temp_ttl: Duration::from_secs(24 * 3600),
+            min_age: Duration::from_secs(3600),
+            dry_run: true,
+        }
+    }
+}
+
+impl UploadGcConfig {
+    /// Sets the reconciled prefixes.
+    pub fn prefixes<I, S>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.prefixes = prefixes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the temporary upload prefix and TTL.
+    pub fn temp(mut self, prefix: impl Into<String>, ttl: Duration) -> Self {
+        self.temp_prefix = prefix.into();
+        self.temp_ttl = ttl;
+        self
+    }
+
+    /// Sets the grace period for orphans.
+    pub fn min_age(mut self, age: Duration) -> Self {
+        self.min_age = age;
+        self
+    }
+
+    /// Enables or disables dry-run mode.
+    pub fn dry_run(mut self, on: bool) -> Self {
+        self.dry_run = on;
+        self
+    }
+}
+
+/// Outcome of one garbage collection run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GcReport {
+    pub dry_run: bool,
+    /// Files examined.
+    pub scanned: usize,
+    /// Files without a metadata row.
+    pub orphaned: Vec<String>,
+    /// Temporary uploads past their TTL.
+    pub expired: Vec<String>,
+    /// Files actually removed.
+    pub deleted: usize,
+    /// Bytes removed (or that would be removed in dry-run mode).
+    pub reclaimed_bytes: u64,
+    /// `(path, error)` for failed deletions.
+    pub failed: Vec<(String, String)>,
+}
+
+impl GcReport {
+    /// Multi-line, human-readable summary.
+    pub fn summary(&self) -> String {
+        let mode = if self.dry_run { " (dry run)" } else { "" };
+        let mut out = format!(
+            "Upload GC{mode}: scanned {}, orphaned {}, expired {}, deleted {}, reclaimed {} bytes, failed {}",
+            self.scanned,
+            self.orphaned.len(),
+            self.expired.len(),
+            self.deleted,
+            self.reclaimed_bytes,
+            self.failed.len()
+        );
+        for (title, paths) in [("Orphaned", &self.orphaned), ("Expired", &self.expired)] {
+            if !paths.is_empty() {
+                out.push_str(&format!("\n\n{title}:"));
+                for p in paths {
+                    out.push_str(&format!("\n  {p}"));
+                }
+            }
+        }
+        if !self.failed.is_empty() {
+            out.push_str("\n\nFailed:");
+            for (p, e) in &self.failed {
+                out.push_str(&format!("\n  {p}: {e}"));
+            }
+        }
+        out
+    }
+
+    /// Builds a text email carrying the summary.
+    pub fn to_email(&self, to: Vec<Mailbox>) -> Email {
+        let mode = if self.dry_run { " (dry run)" } else { "" };
+        Email {
+            subject: format!(
+                "Upload GC{mode}: {} orphaned, {} expired",
+                self.orphaned.len(),
+                self.expired.len()
+            ),
+            body: EmailBody::Text(self.summary()),
+            to,
+            cc: vec![],
+            bcc: vec![],
+            headers: vec![],
+        }
+    }
+}
+
+/// Upload garbage collector.
+pub struct UploadGc {
+    storage: Arc<dyn FileStorage>,
+    index: Arc<dyn UploadIndex>,
+    config: UploadGcConfig,
+    notifier: Option<(Arc<dyn EmailSender>, Vec<Mailbox>)>,
+    metrics: Option<JobMetrics>,
+}
+
+impl UploadGc {
+    /// Creates a collector.
+    pub fn new(
+        storage: Arc<dyn FileStorage>,
+        index: Arc<dyn UploadIndex>,
+        config: UploadGcConfig,
+    ) -> Self {
+        Self {
+            storage,
+            index,
+            config,
+            notifier: None,
+            metrics: None,
+        }
+    }
+
+    /// Mails the report of each [`run`](Self::run) to `to`.
+    pub fn notify(mut self, sender: Arc<dyn EmailSender>, to: Vec<Mailbox>) -> Self {
+        self.notifier = Some((sender, to));
+        self
+    }
+
+    /// Runs one pass synchronously, using the current time.
+    pub fn run_once(&self) -> Result<GcReport> {
+        self.run_once_at(SystemTime::now())
+    }
+
+    /// Runs one pass as of `now`.
+    pub fn run_once_at(&self, now: SystemTime) -> Result<GcReport> {
+        let cfg = &self.config;
+        let known = self.index.known_paths().context("load upload index")?;
+        let age = |o: &StoredObject| now.duration_since(o.modified).unwrap_or_default();
+
+        let mut report = GcReport {
+            dry_run: cfg.dry_run,
+            ..GcReport::default()
+        };
+        let mut doomed = Vec::new();
+
+        for prefix in &cfg.prefixes {
+            for obj in self.storage.list(prefix)? {
+                report.scanned += 1;
+                if !known.contains(&obj.path) && age(&obj) >= cfg.min_age {
+                    report.orphaned.push(obj.path.clone());
+                    doomed.push(obj);
+                }
+            }
+        }
+
+        for obj in self.storage.list(&cfg.temp_prefix)? {
+            report.scanned += 1;
+            if age(&obj) >= cfg.temp_ttl {
+                report.expired.push(obj.path.clone());
+                doomed.push(obj);
+            }
+        }
+
+        for obj in doomed {
+            if cfg.dry_run {
+                report.reclaimed_bytes += obj.bytes;
+                continue;
+            }
+            match self.storage.delete(&obj.path) {
+                Ok(_) => {
+                    report.deleted += 1;
+                    report.reclaimed_bytes += obj.bytes;
+                }
+                Err(e) => report.failed.push((obj.path, format!("{e:#}"))),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Runs one pass on the blocking pool and sends the report, if configured.
+    ///
+    /// A failed notification is logged and does not fail the run.
+    pub async fn run(self: &Arc<Self>) -> Result<GcReport> {
+        let gc = self.clone();
+        let report = tokio::task::spawn_blocking(move || gc.run_once())
+            .await
+            .context("upload gc task failed")??;
+
+        info!("{}", report.summary().lines().next().unwrap_or_default());
+        if let Some((sender, to)) = &self.notifier {
+            let sent = sender.send(report.to_email(to.clone())).await;
+            if let Err(e) = sent {
+                warn!("upload gc report could not be sent: {e:#}");
+            }
+        }
+        Ok(report)
+    }
+
+    /// Records the runs of [`spawn_every`](Self::spawn_every) in `metrics`
+    /// (job `upload_gc`).
+    pub fn metrics(mut self, metrics: JobMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Spawns a task running [`run`](Self::run) every `every`.
+    ///
+    /// Each run is observed as job `upload_gc` (span, success/failure event,
+    /// [`metrics`](Self::metrics)).
+    pub fn spawn_every(self: Arc<Self>, every: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let _ = observe_job(self.metrics.as_ref(), "upload_gc", self.run()).await;
+                if let Some(metrics) = &self.metrics {
+                    metrics.set_next_run("upload_gc", Utc::now() + every);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+
+    use crate::db::port::{Param, Row, Value};
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    fn t0() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+    }
+
+    /// In-memory storage keyed by path, with modification times.
+    #[derive(Default)]
+    struct MemStorage {
+        files: Mutex<BTreeMap<String, (u64, SystemTime)>>,
+        undeletable: Option<String>,
+    }
+
+    impl MemStorage {
+        fn put(&self, path: &str, bytes: u64, modified: SystemTime) {
+            self.files
+                .lock()
+                .unwrap()
+                .insert(path.into(), (bytes, modified));
+        }
+
+        fn paths(&self) -> Vec<String> {
+            self.files.lock().unwrap().keys().cloned().collect()
+        }
+    }
+
+    impl FileStorage for MemStorage {
+        fn save(&self, rel_path: &str, bytes: &[u8]) -> Result<String> {
+            self.put(rel_path, bytes.len() as u64, SystemTime::now());
+            Ok(rel_path.into())
+        }
+
+        fn list(&self, prefix: &str) -> Result<Vec<StoredObject>> {
+            Ok(self
+                .files
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(p, _)| p.starts_with(&format!("{prefix}/")))
+                .map(|(p, (bytes, modified))| StoredObject {
+                    path: p.clone(),
+                    bytes: *bytes,
+                    modified: *modified,
+                })
+                .collect())
+        }
+
+        fn delete(&self, rel_path: &str) -> Result<bool> {
+            if self.undeletable.as_deref() == Some(rel_path) {
+                anyhow::bail!("permission denied");
+            }
+            Ok(self.files.lock().unwrap().remove(rel_path).is_some())
+        }
+    }
+
+    struct StaticIndex(HashSet<String>);
+
+    impl UploadIndex for StaticIndex {
+        fn known_paths(&self) -> Result<HashSet<String>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn fixture(undeletable: Option<&str>) -> (Arc<MemStorage>, Arc<StaticIndex>) {
+        let storage = MemStorage {
+            undeletable: undeletable.map(String::from),
+            ..MemStorage::default()
+        };
+        let old = t0() - 48 * HOUR;
+        storage.put("images/202601/kept.png", 10, old);
+        storage.put("images/202601/orphan.png", 20, old);
+        storage.put("files/202601/fresh-orphan.bin", 30, t0() - HOUR / 2);
+        storage.put("tmp/old.part", 40, old);
+        storage.put("tmp/new.part", 50, t0() - HOUR);
+        storage.put("cache/media/x.png", 60, old);
+
+        let index = StaticIndex(HashSet::from(["images/202601/kept.png".to_string()]));
+        (Arc::new(storage), Arc::new(index))
+    }
+
+    #[test]
+    fn dry_run_reports_without_deleting() {
+        let (storage, index) = fixture(None);
+        let gc = UploadGc::new(storage.clone(), index, UploadGcConfig::default());
+
+        let report = gc.run_once_at(t0()).unwrap();
+
+        assert!(report.dry_run);
+        assert_eq!(report.scanned, 5);
+        assert_eq!(report.orphaned, vec!["images/202601/orphan.png"]);
+        assert_eq!(report.expired, vec!["tmp/old.part"]);
+        assert_eq!(report.deleted, 0);
+        assert_eq!(report.reclaimed_bytes, 60);
+        assert_eq!(storage.paths().len(), 6);
+    }
+
+    #[test]
+    fn deletes_orphans_and_expired_temp_files() {
+        let (storage, index) = fixture(Some("tmp/old.part"));
+        let gc = UploadGc::new(
+            storage.clone(),
+            index,
+            UploadGcConfig::default().dry_run(false),
+        );
+
+        let report = gc.run_once_at(t0()).unwrap();
+
+        assert_eq!(report.deleted, 1);
+        assert_eq!(report.reclaimed_bytes, 20);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, "tmp/old.part");
+        assert!(!storage.paths().contains(&"images/202601/orphan.png".into()));
+        assert!(storage
+            .paths()
+            .contains(&"files/202601/fresh-orphan.bin".into()));
+    }
+
+    #[test]
+    fn summary_and_email_list_paths() {
+        let report = GcReport {
+            dry_run: true,
+            scanned: 3,
+            orphaned: vec!["images/a.png".into()],
+            expired: vec![],
+            deleted: 0,
+            reclaimed_bytes: 5,
+            failed: vec![],
+        };
+
+        let summary = report.summary();
+        assert!(summary.starts_with("Upload GC (dry run): scanned 3, orphaned 1"));
+        assert!(summary.contains("Orphaned:\n  images/a.png"));
+        assert!(!summary.contains("Expired:"));
+
+        let to: Mailbox = "ops@example.com".parse().unwrap();
+        let email = report.to_email(vec![to]);
+        assert_eq!(email.subject, "Upload GC (dry run): 1 orphaned, 0 expired");
+        assert!(matches!(email.body, EmailBody::Text(ref t) if *t == summary));
+    }
+
+    #[derive(Default)]
+    struct CapturingSender {
+        sent: Mutex<Vec<Email>>,
+    }
+
+    #[async_trait]
+    impl EmailSender for CapturingSender {
+        async fn send(&self, email: Email) -> Result<()> {
+            self.sent.lock().unwrap().push(email);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn run_sends_report_to_notifier() {
+        let (storage, index) = fixture(None);
+        let sender = Arc::new(CapturingSender::default());
+        let gc = Arc::new(
+            UploadGc::new(storage, index, UploadGcConfig::default())
+                .notify(sender.clone(), vec!["ops@example.com".parse().unwrap()]),
+        );
+
+        let report = gc.run().await.unwrap();
+
+        let sent = sender.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].subject.starts_with("Upload GC (dry run)"));
+        assert_eq!(report.scanned, 6 - 1);
+    }
+
+    struct PathsDb;
+
+    impl Db for PathsDb {
+        fn fetch_one(&self, _sql: &str, _params: &[Param]) -> Result<Option<Row>> {
+            Ok(None)
+        }
+
+        fn fetch_all(&self, sql: &str, _params: &[Param]) -> Result<Vec<Row>> {
+            assert_eq!(sql, "SELECT file_path AS path FROM media_files");
+            let mut r = Row::default();
+            r.insert("path", Value::Str("images/a.png".into()));
+            Ok(vec![r])
+        }
+
+        fn exec(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+
+        fn exec_returning_last_insert_id(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn db_index_reads_configured_column() {
+        let index =
+            DbUploadIndex::with_table(Arc::new(PathsDb), "media_files", "file_path").unwrap();
+        assert_eq!(
+            index.known_paths().unwrap(),
+            HashSet::from(["images/a.png".to_string()])
+        );
+
+        assert!(DbUploadIndex::with_table(Arc::new(PathsDb), "uploads; DROP", "path").is_err());
+        assert!(DbUploadIndex::with_table(Arc::new(PathsDb), "uploads", "").is_err());
+    }
+}
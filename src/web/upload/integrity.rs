@@ -0,0 +1,472 @@
+//! # Stored File Integrity
+//!
+//! Detects silent corruption of stored files (bit rot, truncated writes,
+//! manual edits on a local disk):
+//!
+//! - [`ChecksummedStorage`] wraps a [`FileStorage`], records the SHA-256 of
+//!   every saved file in a [`ChecksumStore`] and implements
+//!   [`FileStorage::verify`] by re-hashing the stored content.
+//! - [`IntegrityAudit`] verifies every file under the configured prefixes,
+//!   on demand or on a schedule, and reports mismatches through a
+//!   [`Notifier`].
+//!
+//! Files saved before checksums were recorded are reported as
+//! [`IntegrityStatus::Unknown`] and counted, not flagged.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::{sync::Arc, time::Duration};
+//! use wzs_web::config::db::DbConfig;
+//! use wzs_web::db::{connection::get_pool, mysql_adapter::MySqlDb};
+//! use wzs_web::notification::notifier::Notifier;
+//! use wzs_web::web::upload::integrity::{ChecksummedStorage, IntegrityAudit};
+//! use wzs_web::web::upload::local_storage::LocalFileStorage;
+//! use wzs_web::web::upload::metadata::DbChecksumStore;
+//!
+//! # fn run(notifier: Arc<dyn Notifier>) {
+//! let db = Arc::new(MySqlDb::new(get_pool(&DbConfig::from_env())));
+//! let storage = Arc::new(ChecksummedStorage::new(
+//!     Arc::new(LocalFileStorage::new("./uploads")),
+//!     Arc::new(DbChecksumStore::new(db)),
+//! ));
+//!
+//! // Hand `storage` to the Uploader; audit nightly.
+//! let audit = Arc::new(IntegrityAudit::new(storage.clone()).notify(notifier));
+//! let _handle = audit.spawn_every(Duration::from_secs(24 * 3600));
+//! # }
+//! ```
+
+use std::io::{self, Read};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::notification::notifier::Notifier;
+use crate::telemetry::jobs::{observe_job, JobMetrics};
+use crate::web::upload::metadata::ChecksumStore;
+use crate::web::upload::storage::{FileStorage, IntegrityStatus, StoredObject};
+
+/// Lowercase hex SHA-256 of `bytes`.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    to_hex(&Sha256::digest(bytes))
+}
+
+fn to_hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// [`FileStorage`] decorator recording a checksum for each saved file.
+pub struct ChecksummedStorage {
+    inner: Arc<dyn FileStorage>,
+    checksums: Arc<dyn ChecksumStore>,
+}
+
+impl ChecksummedStorage {
+    /// Wraps `inner`, recording checksums in `checksums`.
+    pub fn new(inner: Arc<dyn FileStorage>, checksums: Arc<dyn ChecksumStore>) -> Self {
+        Self { inner, checksums }
+    }
+}
+
+/// Feeds everything read through it into a SHA-256 hasher.
+struct HashingReader<'a> {
+    inner: &'a mut dyn Read,
+    hasher: Sha256,
+}
+
+impl Read for HashingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl FileStorage for ChecksummedStorage {
+    /// Saves the file, then records its checksum.
+    ///
+    /// Fails if the checksum cannot be recorded, so a stored file never
+    /// silently lacks one.
+    fn save(&self, rel_path: &str, bytes: &[u8]) -> Result<String> {
+        let saved = self.inner.save(rel_path, bytes)?;
+        self.checksums
+            .set_checksum(rel_path, &sha256_hex(bytes))
+            .with_context(|| format!("record checksum for {rel_path}"))?;
+        Ok(saved)
+    }
+
+    /// Streams the file into the inner storage, hashing it on the way.
+    fn save_from(&self, rel_path: &str, reader: &mut dyn Read) -> Result<String> {
+        let mut hashing = HashingReader {
+            inner: reader,
+            hasher: Sha256::new(),
+        };
+        let saved = self.inner.save_from(rel_path, &mut hashing)?;
+        let digest = to_hex(&hashing.hasher.finalize());
+        self.checksums
+            .set_checksum(rel_path, &digest)
+            .with_context(|| format!("record checksum for {rel_path}"))?;
+        Ok(saved)
+    }
+
+    fn load(&self, rel_path: &str) -> Result<Option<Vec<u8>>> {
+        self.inner.load(rel_path)
+    }
+
+    fn open(&self, rel_path: &str) -> Result<Option<Box<dyn Read + Send>>> {
+        self.inner.open(rel_path)
+    }
+
+    fn stat(&self, rel_path: &str) -> Result<Option<StoredObject>> {
+        self.inner.stat(rel_path)
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<StoredObject>> {
+        self.inner.list(prefix)
+    }
+
+    fn delete(&self, rel_path: &str) -> Result<bool> {
+        self.inner.delete(rel_path)
+    }
+
+    fn verify(&self, rel_path: &str) -> Result<IntegrityStatus> {
+        let Some(expected) = self.checksums.checksum(rel_path)? else {
+            return Ok(IntegrityStatus::Unknown);
+        };
+        let Some(bytes) = self.inner.load(rel_path)? else {
+            return Ok(IntegrityStatus::Missing);
+        };
+        let actual = sha256_hex(&bytes);
+        Ok(if actual.eq_ignore_ascii_case(&expected) {
+            IntegrityStatus::Ok
+        } else {
+            IntegrityStatus::Mismatch { expected, actual }
+        })
+    }
+}
+
+/// Outcome of one integrity audit.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IntegrityReport {
+    /// Files examined.
+    pub scanned: usize,
+    /// Files matching their checksum.
+    pub ok: usize,
+    /// Files without a recorded checksum.
+    pub unknown: usize,
+    /// `(path, status)` for mismatched or missing files.
+    pub problems: Vec<(String, IntegrityStatus)>,
+    /// `(path, error)` for files that could not be checked.
+    pub failed: Vec<(String, String)>,
+}
+
+impl IntegrityReport {
+    /// Whether anything needs attention.
+    pub fn has_problems(&self) -> bool {
+        !self.problems.is_empty() || !self.failed.is_empty()
+    }
+
+    /// Multi-line, human-readable summary.
+    pub fn summary(&self) -> String {
+        let mut out = format!(
+            "Integrity audit: scanned {}, ok {}, unknown {}, problems {}, failed {}",
+            self.scanned,
+            self.ok,
+            self.unknown,
+            self.problems.len(),
+            self.failed.len()
+        );
+        if !self.problems.is_empty() {
+            out.push_str("\n\nProblems:");
+            for (p, status) in &self.problems {
+                match status {
+                    IntegrityStatus::Mismatch { expected, actual } => out.push_str(&format!(
+                        "\n  {p}: checksum mismatch (expected {expected}, got {actual})"
+                    )),
+                    other => out.push_str(&format!("\n  {p}: {other:?}")),
+                }
+            }
+        }
+        if !self.failed.is_empty() {
+            out.push_str("\n\nFailed:");
+            for (p, e) in &self.failed {
+                out.push_str(&format!("\n  {p}: {e}"));
+            }
+        }
+        out
+    }
+}
+
+/// Verifies stored files against their recorded checksums.
+pub struct IntegrityAudit {
+    storage: Arc<dyn FileStorage>,
+    prefixes: Vec<String>,
+    notifier: Option<Arc<dyn Notifier>>,
+    metrics: Option<JobMetrics>,
+}
+
+impl IntegrityAudit {
+    /// Audits the `images` and `files` prefixes of `storage`.
+    pub fn new(storage: Arc<dyn FileStorage>) -> Self {
+        Self {
+            storage,
+            prefixes: vec!["images".into(), "files".into()],
+            notifier: None,
+            metrics: None,
+        }
+    }
+
+    /// Sets the audited prefixes.
+    pub fn prefixes<I, S>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.prefixes = prefixes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Reports each [`run`](Self::run) that finds problems to `notifier`.
+    pub fn notify(mut self, notifier: Arc<dyn Notifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Runs one audit synchronously.
+    pub fn run_once(&self) -> Result<IntegrityReport> {
+        let mut report = IntegrityReport::default();
+        for prefix in &self.prefixes {
+            for obj in self.storage.list(prefix)? {
+                report.scanned += 1;
+                match self.storage.verify(&obj.path) {
+                    Ok(IntegrityStatus::Ok) => report.ok += 1,
+                    Ok(IntegrityStatus::Unknown) => report.unknown += 1,
+                    Ok(status) => report.problems.push((obj.path, status)),
+                    Err(e) => report.failed.push((obj.path, format!("{e:#}"))),
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Runs one audit on the blocking pool and notifies about problems, if
+    /// configured.
+    ///
+    /// A failed notification is logged and does not fail the run.
+    pub async fn run(self: &Arc<Self>) -> Result<IntegrityReport> {
+        let audit = self.clone();
+        let report = tokio::task::spawn_blocking(move || audit.run_once())
+            .await
+            .context("integrity audit task failed")??;
+
+        let headline = report
+            .summary()
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        if !report.has_problems() {
+            info!("{headline}");
+            return Ok(report);
+        }
+        warn!("{headline}");
+        if let Some(notifier) = &self.notifier {
+            let subject = format!(
+                "Integrity audit: {} problem(s), {} failed",
+                report.problems.len(),
+                report.failed.len()
+            );
+            if let Err(e) = notifier.notify(&subject, &report.summary()).await {
+                warn!("integrity audit report could not be sent: {e:#}");
+            }
+        }
+        Ok(report)
+    }
+
+    /// Records the runs of [`spawn_every`](Self::spawn_every) in `metrics`
+    /// (job `integrity_audit`).
+    pub fn metrics(mut self, metrics: JobMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Spawns a task running [`run`](Self::run) every `every`.
+    ///
+    /// Each run is observed as job `integrity_audit` (span, success/failure event,
+    /// [`metrics`](Self::metrics)).
+    pub fn spawn_every(self: Arc<Self>, every: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let _ = observe_job(self.metrics.as_ref(), "integrity_audit", self.run()).await;
+                if let Some(metrics) = &self.metrics {
+                    metrics.set_next_run("integrity_audit", Utc::now() + every);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeMap, HashMap};
+    use std::sync::Mutex;
+    use std::time::SystemTime;
+
+    use async_trait::async_trait;
+
+    #[derive(Default)]
+    struct MemStorage {
+        files: Mutex<BTreeMap<String, Vec<u8>>>,
+    }
+
+    impl FileStorage for MemStorage {
+        fn save(&self, rel_path: &str, bytes: &[u8]) -> Result<String> {
+            self.files
+                .lock()
+                .unwrap()
+                .insert(rel_path.into(), bytes.to_vec());
+            Ok(rel_path.into())
+        }
+
+        fn load(&self, rel_path: &str) -> Result<Option<Vec<u8>>> {
+            Ok(self.files.lock().unwrap().get(rel_path).cloned())
+        }
+
+        fn list(&self, prefix: &str) -> Result<Vec<StoredObject>> {
+            Ok(self
+                .files
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(p, _)| p.starts_with(&format!("{prefix}/")))
+                .map(|(p, b)| StoredObject {
+                    path: p.clone(),
+                    bytes: b.len() as u64,
+                    modified: SystemTime::now(),
+                })
+                .collect())
+        }
+    }
+
+    #[derive(Default)]
+    struct MemChecksums(Mutex<HashMap<String, String>>);
+
+    impl ChecksumStore for MemChecksums {
+        fn checksum(&self, key: &str) -> Result<Option<String>> {
+            Ok(self.0.lock().unwrap().get(key).cloned())
+        }
+
+        fn set_checksum(&self, key: &str, sha256: &str) -> Result<()> {
+            self.0.lock().unwrap().insert(key.into(), sha256.into());
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingNotifier(Mutex<Vec<(String, String)>>);
+
+    #[async_trait]
+    impl Notifier for RecordingNotifier {
+        async fn notify(&self, subject: &str, message: &str) -> Result<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .push((subject.into(), message.into()));
+            Ok(())
+        }
+    }
+
+    fn setup() -> (Arc<MemStorage>, Arc<MemChecksums>, Arc<ChecksummedStorage>) {
+        let inner = Arc::new(MemStorage::default());
+        let checksums = Arc::new(MemChecksums::default());
+        let storage = Arc::new(ChecksummedStorage::new(inner.clone(), checksums.clone()));
+        (inner, checksums, storage)
+    }
+
+    #[test]
+    fn save_records_checksum_and_verify_detects_changes() {
+        let (inner, checksums, storage) = setup();
+        storage.save("files/a.txt", b"abc").unwrap();
+        assert_eq!(
+            checksums.checksum("files/a.txt").unwrap().as_deref(),
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(storage.verify("files/a.txt").unwrap(), IntegrityStatus::Ok);
+
+        inner.save("files/a.txt", b"abd").unwrap();
+        assert!(matches!(
+            storage.verify("files/a.txt").unwrap(),
+            IntegrityStatus::Mismatch { .. }
+        ));
+
+        inner.files.lock().unwrap().remove("files/a.txt");
+        assert_eq!(
+            storage.verify("files/a.txt").unwrap(),
+            IntegrityStatus::Missing
+        );
+        assert_eq!(
+            storage.verify("files/b.txt").unwrap(),
+            IntegrityStatus::Unknown
+        );
+    }
+
+    #[test]
+    fn save_from_hashes_the_stream() {
+        let (inner, checksums, storage) = setup();
+        storage.save_from("files/a.txt", &mut &b"abc"[..]).unwrap();
+        assert_eq!(inner.load("files/a.txt").unwrap(), Some(b"abc".to_vec()));
+        assert_eq!(
+            checksums.checksum("files/a.txt").unwrap(),
+            Some(sha256_hex(b"abc"))
+        );
+    }
+
+    #[tokio::test]
+    async fn audit_reports_mismatches_through_the_notifier() {
+        let (inner, _, storage) = setup();
+        storage.save("files/good.txt", b"good").unwrap();
+        storage.save("images/bad.png", b"png").unwrap();
+        inner.save("images/bad.png", b"rotted").unwrap();
+        inner.save("files/legacy.txt", b"old").unwrap();
+
+        let notifier = Arc::new(RecordingNotifier::default());
+        let audit = Arc::new(IntegrityAudit::new(storage).notify(notifier.clone()));
+        let report = audit.run().await.unwrap();
+
+        assert_eq!((report.scanned, report.ok, report.unknown), (3, 1, 1));
+        assert_eq!(report.problems.len(), 1);
+        assert_eq!(report.problems[0].0, "images/bad.png");
+        let sent = notifier.0.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].0.contains("1 problem(s)"));
+        assert!(sent[0].1.contains("images/bad.png: checksum mismatch"));
+    }
+
+    #[tokio::test]
+    async fn clean_audit_sends_nothing_and_unsupported_verify_fails() {
+        let (_, _, storage) = setup();
+        storage.save("files/a.txt", b"a").unwrap();
+        let notifier = Arc::new(RecordingNotifier::default());
+        let audit = Arc::new(IntegrityAudit::new(storage).notify(notifier.clone()));
+        assert!(!audit.run().await.unwrap().has_problems());
+        assert!(notifier.0.lock().unwrap().is_empty());
+
+        let inner = Arc::new(MemStorage::default());
+        inner.save("files/a.txt", b"a").unwrap();
+        let report = IntegrityAudit::new(inner)
+            .prefixes(["files"])
+            .run_once()
+            .unwrap();
+        assert_eq!(report.failed.len(), 1);
+        assert!(report.failed[0].1.contains("not supported"));
+    }
+}
@@ -0,0 +1,246 @@
+//! Storage key naming strategies for uploads.
+//!
+//! [`UploadService`](super::uploader::UploadService) asks a [`KeyStrategy`]
+//! where to store each upload. Apps disagree on what a good key is — stable
+//! URLs for identical content, per-tenant prefixes for bucket policies,
+//! readable names for SEO — so the scheme is pluggable and selected by
+//! [`UploadConfig::key_scheme`](crate::config::upload::UploadConfig::key_scheme):
+//!
+//! | [`KeyScheme`] | Strategy | Example key |
+//! |---------------|----------|-------------|
+//! | `dated` (default) | [`DatedKeys`] | `images/202501/0b9c….png`, `files/202501/report.pdf` |
+//! | `content-hash` | [`ContentHashKeys`] | `images/3f/3fa9….png` |
+//! | `tenant` | [`TenantKeys`] | `acme/images/202501/0b9c….png` |
+//! | `slug` | [`SlugKeys`] | `files/202501/q1-report-5e1a0c2f.pdf` |
+//!
+//! Changing the scheme only affects new uploads; existing keys stay valid.
+
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::config::upload::KeyScheme;
+use crate::web::upload::integrity::sha256_hex;
+
+/// What a [`KeyStrategy`] knows about an upload.
+#[derive(Clone, Copy, Debug)]
+pub struct KeyInput<'a> {
+    /// Directory prefix (`image_dir` or `file_dir`).
+    pub dir: &'a str,
+    /// Sanitized original file name (may be empty).
+    pub filename: &'a str,
+    /// Extension without the dot (`"png"`, `"bin"`, …).
+    pub ext: &'a str,
+    /// Bytes as they will be stored (after image processing).
+    pub bytes: &'a [u8],
+    /// `true` for processed images, which never keep their original name.
+    pub is_image: bool,
+    /// Tenant of the request, if any.
+    pub tenant: Option<&'a str>,
+    /// Upload time.
+    pub now: DateTime<Utc>,
+}
+
+/// Chooses the storage key for an upload.
+pub trait KeyStrategy: Send + Sync {
+    /// Returns the key (relative path with `/` separators).
+    fn key(&self, input: &KeyInput<'_>) -> Result<String>;
+}
+
+/// `dir/YYYYMM/<uuid>.<ext>`; regular files keep their sanitized name.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DatedKeys;
+
+impl KeyStrategy for DatedKeys {
+    fn key(&self, input: &KeyInput<'_>) -> Result<String> {
+        let name = if input.is_image || input.filename.is_empty() {
+            format!("{}.{}", Uuid::new_v4(), input.ext)
+        } else {
+            input.filename.to_string()
+        };
+        Ok(format!("{}/{}/{name}", input.dir, yyyymm(input)))
+    }
+}
+
+/// `dir/<first two hex digits>/<sha256>.<ext>`.
+///
+/// Identical content always maps to the same key (and URL), so re-uploads
+/// overwrite instead of piling up.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ContentHashKeys;
+
+impl KeyStrategy for ContentHashKeys {
+    fn key(&self, input: &KeyInput<'_>) -> Result<String> {
+        let hash = sha256_hex(input.bytes);
+        Ok(format!("{}/{}/{hash}.{}", input.dir, &hash[..2], input.ext))
+    }
+}
+
+/// Prefixes another strategy's key with the tenant id.
+///
+/// Uploads without a tenant fail, so files never land outside a tenant's
+/// prefix by accident.
+#[derive(Clone)]
+pub struct TenantKeys {
+    inner: Arc<dyn KeyStrategy>,
+}
+
+impl TenantKeys {
+    /// Prefixes the keys of `inner`.
+    pub fn new(inner: Arc<dyn KeyStrategy>) -> Self {
+        Self { inner }
+    }
+}
+
+impl Default for TenantKeys {
+    fn default() -> Self {
+        Self::new(Arc::new(DatedKeys))
+    }
+}
+
+impl KeyStrategy for TenantKeys {
+    fn key(&self, input: &KeyInput<'_>) -> Result<String> {
+        let Some(tenant) = input.tenant else {
+            bail!("tenant-prefixed upload keys need a tenant");
+        };
+        let valid = !tenant.is_empty()
+            && tenant
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            bail!("invalid tenant id for upload key: {tenant}");
+        }
+        Ok(format!("{tenant}/{}", self.inner.key(input)?))
+    }
+}
+
+/// `dir/YYYYMM/<slug>-<8 hex>.<ext>`, the slug made from the original name.
+///
+/// The random suffix keeps keys unique; names without ASCII letters or
+/// digits become `file`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SlugKeys;
+
+/// Maximum length of the slug part.
+const MAX_SLUG_CHARS: usize = 60;
+
+impl KeyStrategy for SlugKeys {
+    fn key(&self, input: &KeyInput<'_>) -> Result<String> {
+        let stem = match input.filename.rsplit_once('.') {
+            Some((stem, _)) if !stem.is_empty() => stem,
+            _ => input.filename,
+        };
+        let mut slug = slugify(stem);
+        if slug.is_empty() {
+            slug = "file".into();
+        }
+        let suffix = &Uuid::new_v4().simple().to_string()[..8];
+        Ok(format!(
+            "{}/{}/{slug}-{suffix}.{}",
+            input.dir,
+            yyyymm(input),
+            input.ext
+        ))
+    }
+}
+
+/// Returns the built-in strategy for `scheme`.
+pub fn strategy_for(scheme: KeyScheme) -> Arc<dyn KeyStrategy> {
+    match scheme {
+        KeyScheme::Dated => Arc::new(DatedKeys),
+        KeyScheme::ContentHash => Arc::new(ContentHashKeys),
+        KeyScheme::TenantPrefixed => Arc::new(TenantKeys::default()),
+        KeyScheme::NameSlug => Arc::new(SlugKeys),
+    }
+}
+
+fn yyyymm(input: &KeyInput<'_>) -> String {
+    input.now.format("%Y%m").to_string()
+}
+
+/// Lowercase ASCII letters and digits joined by single hyphens.
+fn slugify(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c.to_ascii_lowercase());
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+        if out.len() >= MAX_SLUG_CHARS {
+            break;
+        }
+    }
+    out.trim_end_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn input<'a>(filename: &'a str, is_image: bool, tenant: Option<&'a str>) -> KeyInput<'a> {
+        KeyInput {
+            dir: if is_image { "images" } else { "files" },
+            filename,
+            ext: if is_image { "png" } else { "pdf" },
+            bytes: b"hello",
+            is_image,
+            tenant,
+            now: Utc.with_ymd_and_hms(2025, 1, 15, 0, 0, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn dated_keys_match_the_original_layout() {
+        let key = DatedKeys.key(&input("a.png", true, None)).unwrap();
+        assert!(key.starts_with("images/202501/"));
+        assert!(key.ends_with(".png"));
+        assert_eq!(key.len(), "images/202501/".len() + 36 + ".png".len());
+
+        let key = DatedKeys.key(&input("Q1 report.pdf", false, None)).unwrap();
+        assert_eq!(key, "files/202501/Q1 report.pdf");
+    }
+
+    #[test]
+    fn content_hash_keys_are_stable() {
+        let a = ContentHashKeys.key(&input("a.png", true, None)).unwrap();
+        let b = ContentHashKeys.key(&input("b.png", true, None)).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(
+            a,
+            "images/2c/2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824.png"
+        );
+    }
+
+    #[test]
+    fn tenant_keys_require_a_safe_tenant() {
+        let keys = TenantKeys::new(Arc::new(ContentHashKeys));
+        let key = keys.key(&input("a.png", true, Some("acme"))).unwrap();
+        assert!(key.starts_with("acme/images/2c/"));
+
+        assert!(keys.key(&input("a.png", true, None)).is_err());
+        assert!(keys.key(&input("a.png", true, Some("../etc"))).is_err());
+    }
+
+    #[test]
+    fn slug_keys_are_readable_and_unique() {
+        let key = SlugKeys
+            .key(&input("Q1 Report (final).pdf", false, None))
+            .unwrap();
+        let (prefix, rest) = key.split_at("files/202501/q1-report-final-".len());
+        assert_eq!(prefix, "files/202501/q1-report-final-");
+        assert_eq!(rest.len(), "5e1a0c2f.pdf".len());
+        assert_ne!(
+            key,
+            SlugKeys
+                .key(&input("Q1 Report (final).pdf", false, None))
+                .unwrap()
+        );
+
+        let key = SlugKeys.key(&input("報告書.pdf", false, None)).unwrap();
+        assert!(key.starts_with("files/202501/file-"));
+    }
+}
@@ -1,9 +1,10 @@
-use std::fs;
+use std::fs::{self, File};
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 
-use super::storage::FileStorage;
+use super::storage::{FileStorage, StoredObject};
 
 /// Stores uploaded files on the local filesystem.
 ///
@@ -28,8 +29,7 @@ impl LocalFileStorage {
     /// - Replaces `..` with `_` to avoid directory traversal
     /// - Returns the absolute file path as `String`
     pub fn save_file(&self, rel_path: &str, bytes: &[u8]) -> Result<String> {
-        let safe = rel_path.trim_start_matches('/').replace("..", "_");
-        let full = self.root.join(&safe);
+        let full = self.resolve(rel_path);
 
         if let Some(dir) = full.parent() {
             fs::create_dir_all(dir).with_context(|| format!("create_dir_all {:?}", dir))?;
@@ -39,6 +39,115 @@ impl LocalFileStorage {
         Ok(full.to_string_lossy().into_owned())
     }
 
+    /// Streams `reader` into a file under the root directory.
+    ///
+    /// Same path handling as [`save_file`](Self::save_file), without holding
+    /// the contents in memory.
+    pub fn save_file_from(&self, rel_path: &str, reader: &mut dyn Read) -> Result<String> {
+        let full = self.resolve(rel_path);
+
+        if let Some(dir) = full.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("create_dir_all {:?}", dir))?;
+        }
+
+        let mut file = File::create(&full).with_context(|| format!("create {:?}", &full))?;
+        io::copy(reader, &mut file).with_context(|| format!("write {:?}", &full))?;
+        Ok(full.to_string_lossy().into_owned())
+    }
+
+    /// Reads a file under the root directory.
+    ///
+    /// Applies the same sanitization as [`save_file`](Self::save_file) and
+    /// returns `Ok(None)` if the file does not exist.
+    pub fn load_file(&self, rel_path: &str) -> Result<Option<Vec<u8>>> {
+        let full = self.resolve(rel_path);
+        match fs::read(&full) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("read {:?}", &full)),
+        }
+    }
+
+    /// Opens a file under the root directory for reading; `Ok(None)` if it
+    /// does not exist.
+    pub fn open_file(&self, rel_path: &str) -> Result<Option<File>> {
+        let full = self.resolve(rel_path);
+        match File::open(&full) {
+            Ok(file) => Ok(Some(file)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("open {:?}", &full)),
+        }
+    }
+
+    /// Returns size and modification time of a file under the root;
+    /// `Ok(None)` if it does not exist or is not a regular file.
+    pub fn stat_file(&self, rel_path: &str) -> Result<Option<StoredObject>> {
+        let full = self.resolve(rel_path);
+        let meta = match fs::metadata(&full) {
+            Ok(meta) if meta.is_file() => meta,
+            Ok(_) => return Ok(None),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("metadata {:?}", &full)),
+        };
+        Ok(Some(StoredObject {
+            path: rel_path.trim_start_matches('/').to_string(),
+            bytes: meta.len(),
+            modified: meta.modified()?,
+        }))
+    }
+
+    /// Recursively lists files under `prefix`, with paths relative to the root.
+    ///
+    /// A missing prefix directory yields an empty list.
+    pub fn list_files(&self, prefix: &str) -> Result<Vec<StoredObject>> {
+        let mut out = Vec::new();
+        let start = self.resolve(prefix);
+        if start.is_dir() {
+            self.walk(&start, &mut out)?;
+        }
+        out.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(out)
+    }
+
+    /// Deletes a file under the root; returns `false` if it did not exist.
+    pub fn delete_file(&self, rel_path: &str) -> Result<bool> {
+        let full = self.resolve(rel_path);
+        match fs::remove_file(&full) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e).with_context(|| format!("remove_file {:?}", &full)),
+        }
+    }
+
+    fn walk(&self, dir: &Path, out: &mut Vec<StoredObject>) -> Result<()> {
+        for entry in fs::read_dir(dir).with_context(|| format!("read_dir {:?}", dir))? {
+            let entry = entry?;
+            let path = entry.path();
+            let meta = entry.metadata()?;
+            if meta.is_dir() {
+                self.walk(&path, out)?;
+            } else if meta.is_file() {
+                let rel = path.strip_prefix(&self.root).unwrap_or(&path);
+                out.push(StoredObject {
+                    path: rel
+                        .components()
+                        .map(|c| c.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/"),
+                    bytes: meta.len(),
+                    modified: meta.modified()?,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Maps a relative path to a sanitized absolute path under the root.
+    fn resolve(&self, rel_path: &str) -> PathBuf {
+        let safe = rel_path.trim_start_matches('/').replace("..", "_");
+        self.root.join(safe)
+    }
+
     /// Returns the configured root path.
     pub fn root(&self) -> &Path {
         &self.root
@@ -49,6 +158,32 @@ impl FileStorage for LocalFileStorage {
     fn save(&self, rel_path: &str, bytes: &[u8]) -> Result<String> {
         self.save_file(rel_path, bytes)
     }
+
+    fn save_from(&self, rel_path: &str, reader: &mut dyn Read) -> Result<String> {
+        self.save_file_from(rel_path, reader)
+    }
+
+    fn load(&self, rel_path: &str) -> Result<Option<Vec<u8>>> {
+        self.load_file(rel_path)
+    }
+
+    fn open(&self, rel_path: &str) -> Result<Option<Box<dyn Read + Send>>> {
+        Ok(self
+            .open_file(rel_path)?
+            .map(|f| Box::new(f) as Box<dyn Read + Send>))
+    }
+
+    fn stat(&self, rel_path: &str) -> Result<Option<StoredObject>> {
+        self.stat_file(rel_path)
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<StoredObject>> {
+        self.list_files(prefix)
+    }
+
+    fn delete(&self, rel_path: &str) -> Result<bool> {
+        self.delete_file(rel_path)
+    }
 }
 #[cfg(test)]
 mod tests {
@@ -142,4 +277,82 @@ mod tests {
         let _ = fs::remove_dir_all(&root);
         Ok(())
     }
+
+    #[test]
+    fn load_returns_saved_bytes_or_none() -> Result<()> {
+        let root = unique_temp_root();
+        let storage = LocalFileStorage::new(&root);
+
+        storage.save("a/b.bin", b"data")?;
+        assert_eq!(storage.load("a/b.bin")?, Some(b"data".to_vec()));
+        assert_eq!(storage.load("/a/b.bin")?, Some(b"data".to_vec()));
+        assert_eq!(storage.load("a/missing.bin")?, None);
+
+        let _ = fs::remove_dir_all(&root);
+        Ok(())
+    }
+
+    #[test]
+    fn save_from_and_open_stream_file_contents() -> Result<()> {
+        let root = unique_temp_root();
+        let storage = LocalFileStorage::new(&root);
+
+        let abs = storage.save_from("s/c.bin", &mut &b"streamed"[..])?;
+        assert_eq!(Path::new(&abs), root.join("s/c.bin"));
+
+        let mut read = Vec::new();
+        storage.open("s/c.bin")?.unwrap().read_to_end(&mut read)?;
+        assert_eq!(read, b"streamed");
+        assert!(storage.open("s/missing.bin")?.is_none());
+
+        let _ = fs::remove_dir_all(&root);
+        Ok(())
+    }
+
+    #[test]
+    fn stat_reports_size_and_mtime() -> Result<()> {
+        let root = unique_temp_root();
+        let storage = LocalFileStorage::new(&root);
+
+        storage.save("s/d.bin", b"four")?;
+        let stat = storage.stat("/s/d.bin")?.unwrap();
+        assert_eq!((stat.path.as_str(), stat.bytes), ("s/d.bin", 4));
+        assert!(stat.modified <= SystemTime::now());
+        assert!(storage.stat("s")?.is_none());
+        assert!(storage.stat("s/missing.bin")?.is_none());
+
+        let _ = fs::remove_dir_all(&root);
+        Ok(())
+    }
+
+    #[test]
+    fn list_and_delete_files() -> Result<()> {
+        let root = unique_temp_root();
+        let storage = LocalFileStorage::new(&root);
+
+        storage.save("files/202601/a.txt", b"aa")?;
+        storage.save("files/b.txt", b"b")?;
+        storage.save("images/c.png", b"c")?;
+
+        let listed: Vec<(String, u64)> = storage
+            .list("files")?
+            .into_iter()
+            .map(|o| (o.path, o.bytes))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("files/202601/a.txt".to_string(), 2),
+                ("files/b.txt".to_string(), 1)
+            ]
+        );
+        assert!(storage.list("missing")?.is_empty());
+
+        assert!(storage.delete("files/b.txt")?);
+        assert!(!storage.delete("files/b.txt")?);
+        assert_eq!(storage.list("files")?.len(), 1);
+
+        let _ = fs::remove_dir_all(&root);
+        Ok(())
+    }
 }
@@ -0,0 +1,714 @@
+//! # On-the-fly Image Variants
+//!
+//! Serves resized variants of stored images:
+//!
+//! ```text
+//! GET /media/{key}?w=400&h=300&fit=cover&sig=<signature>
+//! ```
+//!
+//! - Sizes and resize modes must be on an allowlist ([`MediaConfig`]).
+//! - URLs are HMAC-signed ([`MediaService::signed_url`]) so clients cannot
+//!   request arbitrary variants and amplify CPU / storage usage.
+//! - Generated variants are cached in the same [`FileStorage`] under
+//!   `cache_prefix`, so each variant is resized once. Wrap the processor in
+//!   a [`CachedImageProcessor`](crate::image::cache::CachedImageProcessor)
+//!   to also share results between keys holding identical content.
+//! - `cover` crops keep the upload's [`FocalPoint`] in frame when a
+//!   [`FocalPointStore`] is configured ([`MediaService::focal_points`]).
+//! - With a [`QuarantineStore`] configured ([`MediaService::quarantine`]),
+//!   quarantined originals are answered with `404`, cached variants
+//!   included. [`QuarantineService`](crate::web::upload::quarantine::QuarantineService)
+//!   also deletes the cached variants of the keys it quarantines
+//!   ([`purge_variants`]).
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use axum::{routing::get, Extension, Router};
+//! use wzs_web::config::csrf::derive_secret_from_string;
+//! use wzs_web::image::image_rs_processor::ImageRsProcessor;
+//! use wzs_web::image::processor::ResizeMode;
+//! use wzs_web::web::upload::local_storage::LocalFileStorage;
+//! use wzs_web::web::upload::media::{media_handler, MediaConfig, MediaService};
+//!
+//! let cfg = MediaConfig::new(derive_secret_from_string("media-secret"), vec![(400, 300), (800, 600)]);
+//! let media = Arc::new(MediaService::new(
+//!     Arc::new(LocalFileStorage::new("./uploads")),
+//!     Arc::new(ImageRsProcessor::default()),
+//!     cfg,
+//! ));
+//!
+//! let url = media.signed_url("images/202603/a.png", 400, 300, ResizeMode::Cover).unwrap();
+//!
+//! let app: Router = Router::new()
+//!     .route("/media/{*key}", get(media_handler))
+//!     .layer(Extension(media));
+//! ```
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+use axum::{
+    extract::{Path, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use tracing::warn;
+
+use crate::error::app::AppError;
+use crate::image::processor::{BgColor, FocalPoint, ImageProcessor, ResizeMode, ResizeOpts};
+use crate::web::problem::Problem;
+use crate::web::upload::metadata::FocalPointStore;
+use crate::web::upload::quarantine::{QuarantineStore, QUARANTINE_PREFIX};
+use crate::web::upload::storage::FileStorage;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Default storage prefix for cached variants.
+pub const DEFAULT_CACHE_PREFIX: &str = "cache/media";
+
+/// Allowlist and signing configuration for image variants.
+#[derive(Clone, Debug)]
+pub struct MediaConfig {
+    /// HMAC key for URL signatures.
+    pub secret: [u8; 32],
+    /// Allowed `(width, height)` pairs.
+    pub allowed_sizes: Vec<(u32, u32)>,
+    /// Allowed resize modes (default: all).
+    pub allowed_modes: Vec<ResizeMode>,
+    /// Storage prefix for cached variants (default: `"cache/media"`).
+    pub cache_prefix: String,
+    /// Public URL prefix the handler is mounted at (default: `"/media"`).
+    pub url_prefix: String,
+    /// `Cache-Control` header for served variants.
+    pub cache_control: String,
+}
+
+impl MediaConfig {
+    /// Creates a configuration with the given secret and size allowlist.
+    pub fn new(secret: [u8; 32], allowed_sizes: Vec<(u32, u32)>) -> Self {
+        Self {
+            secret,
+            allowed_sizes,
+            allowed_modes: vec![ResizeMode::Fit, ResizeMode::Contain, ResizeMode::Cover],
+            cache_prefix: DEFAULT_CACHE_PREFIX.into(),
+            url_prefix: "/media".into(),
+            cache_control: "public, max-age=31536000, immutable".into(),
+        }
+    }
+}
+
+/// A resized image ready to serve.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MediaFile {
+    pub bytes: Vec<u8>,
+    pub content_type: String,
+}
+
+/// Signs, validates and renders image variants.
+pub struct MediaService {
+    storage: Arc<dyn FileStorage>,
+    processor: Arc<dyn ImageProcessor>,
+    config: MediaConfig,
+    focal_points: Option<Arc<dyn FocalPointStore>>,
+    quarantine: Option<Arc<dyn QuarantineStore>>,
+}
+
+impl MediaService {
+    /// Creates a new service.
+    pub fn new(
+        storage: Arc<dyn FileStorage>,
+        processor: Arc<dyn ImageProcessor>,
+        config: MediaConfig,
+    ) -> Self {
+        Self {
+            storage,
+            processor,
+            config,
+            focal_points: None,
+            quarantine: None,
+        }
+    }
+
+    /// Crops `cover` variants around focal points recorded in `store`.
+    pub fn focal_points(mut self, store: Arc<dyn FocalPointStore>) -> Self {
+        self.focal_points = Some(store);
+        self
+    }
+
+    /// Refuses quarantined originals and their cached variants.
+    pub fn quarantine(mut self, store: Arc<dyn QuarantineStore>) -> Self {
+        self.quarantine = Some(store);
+        self
+    }
+
+    /// Returns a signed URL for a variant.
+    ///
+    /// # Errors
+    /// Returns an error if the key, size or mode is not allowed.
+    pub fn signed_url(&self, key: &str, w: u32, h: u32, mode: ResizeMode) -> Result<String> {
+        self.check_allowed(key, w, h, mode)?;
+        Ok(format!(
+            "{}/{}?w={w}&h={h}&fit={mode}&sig={}",
+            self.config.url_prefix.trim_end_matches('/'),
+            key,
+            self.sign(key, w, h, mode)
+        ))
+    }
+
+    /// Verifies a variant signature.
+    pub fn verify(&self, key: &str, w: u32, h: u32, mode: ResizeMode, sig: &str) -> bool {
+        let Ok(given) = URL_SAFE_NO_PAD.decode(sig) else {
+            return false;
+        };
+        let expected = self.mac(key, w, h, mode);
+        expected.as_slice().ct_eq(&given).unwrap_u8() == 1
+    }
+
+    /// Returns the variant, generating and caching it on first request.
+    ///
+    /// Returns `Ok(None)` if the original does not exist or is quarantined
+    /// (checked before the cache, so variants rendered earlier are refused
+    /// too).
+    pub fn render(&self, key: &str, w: u32, h: u32, mode: ResizeMode) -> Result<Option<MediaFile>> {
+        self.check_allowed(key, w, h, mode)?;
+        let Some(content_type) = raster_content_type(key) else {
+            bail!("unsupported media type: {key}");
+        };
+        if self.is_quarantined(key)? {
+            return Ok(None);
+        }
+
+        let focal = self.focal_for(key, mode)?;
+        // Non-default focal points get their own variant, so moving the
+        // focal point never serves a stale crop.
+        let variant = match focal {
+            Some(f) => format!("{w}x{h}-{mode}@{f}"),
+            None => format!("{w}x{h}-{mode}"),
+        };
+        let cache_key = format!(
+            "{}/{variant}/{key}",
+            self.config.cache_prefix.trim_end_matches('/')
+        );
+        if let Some(bytes) = self.storage.load(&cache_key)? {
+            return Ok(Some(MediaFile {
+                bytes,
+                content_type: content_type.into(),
+            }));
+        }
+
+        let Some(original) = self.storage.load(key)? else {
+            return Ok(None);
+        };
+
+        let opts = ResizeOpts::new(w, h, false, mode, BgColor::white())
+            .with_focal(focal.unwrap_or_default());
+        let bytes = self
+            .processor
+            .resize_same_format(&original, content_type, opts)?;
+
+        if let Err(e) = self.storage.save(&cache_key, &bytes) {
+            warn!("media cache write failed for {cache_key}: {e:#}");
+        }
+
+        Ok(Some(MediaFile {
+            bytes,
+            content_type: content_type.into(),
+        }))
+    }
+
+    /// Deletes every cached variant of `key`; see [`purge_variants`].
+    pub fn purge(&self, key: &str) -> Result<usize> {
+        purge_variants(self.storage.as_ref(), &self.config.cache_prefix, key)
+    }
+
+    /// Whether `key` lies under the quarantine prefix or is marked in the
+    /// [`QuarantineStore`].
+    fn is_quarantined(&self, key: &str) -> Result<bool> {
+        if key.split('/').next() == Some(QUARANTINE_PREFIX) {
+            return Ok(true);
+        }
+        match &self.quarantine {
+            Some(store) => Ok(store.quarantine(key)?.is_some()),
+            None => Ok(false),
+        }
+    }
+
+    /// Focal point for `cover` crops; `None` means the default center.
+    fn focal_for(&self, key: &str, mode: ResizeMode) -> Result<Option<FocalPoint>> {
+        let Some(store) = self.focal_points.as_ref() else {
+            return Ok(None);
+        };
+        if mode != ResizeMode::Cover {
+            return Ok(None);
+        }
+        Ok(store.focal_point(key)?.filter(|f| *f != FocalPoint::CENTER))
+    }
+
+    fn check_allowed(&self, key: &str, w: u32, h: u32, mode: ResizeMode) -> Result<()> {
+        if !is_safe_key(key) {
+            bail!("invalid media key: {key}");
+        }
+        if !self.config.allowed_sizes.contains(&(w, h)) {
+            bail!("size not allowed: {w}x{h}");
+        }
+        if !self.config.allowed_modes.contains(&mode) {
+            bail!("resize mode not allowed: {mode}");
+        }
+        Ok(())
+    }
+
+    fn sign(&self, key: &str, w: u32, h: u32, mode: ResizeMode) -> String {
+        URL_SAFE_NO_PAD.encode(self.mac(key, w, h, mode))
+    }
+
+    fn mac(&self, key: &str, w: u32, h: u32, mode: ResizeMode) -> Vec<u8> {
+        let mut mac = HmacSha256::new_from_slice(&self.config.secret).expect("HMAC key");
+        mac.update(format!("{key}\n{w}\n{h}\n{mode}").as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+/// Query parameters accepted by [`media_handler`].
+#[derive(Debug, Deserialize)]
+pub struct MediaQuery {
+    pub w: u32,
+    pub h: u32,
+    #[serde(default)]
+    pub fit: Option<String>,
+    #[serde(default)]
+    pub sig: String,
+}
+
+/// Serves a resized image variant.
+///
+/// # Required Extensions
+/// - `Arc<MediaService>`
+///
+/// # Returns
+/// - `200 OK` with the image bytes
+/// - `400 BAD REQUEST` for an unknown mode or a size/mode/key not on the allowlist
+/// - `403 FORBIDDEN` for a missing or invalid signature
+/// - `404 NOT FOUND` if the original image does not exist or is quarantined
+/// - `415 UNSUPPORTED MEDIA TYPE` for non-image keys
+/// - `500 INTERNAL SERVER ERROR` when loading or resizing fails
+///
+/// Errors are [`AppError`] problem documents (`application/problem+json`).
+pub async fn media_handler(
+    Extension(media): Extension<Arc<MediaService>>,
+    Path(key): Path<String>,
+    Query(q): Query<MediaQuery>,
+) -> Result<Response, AppError> {
+    let mode = q
+        .fit
+        .as_deref()
+        .unwrap_or("fit")
+        .parse::<ResizeMode>()
+        .map_err(|e| AppError::bad_request(e.to_string()))?;
+
+    if !media.verify(&key, q.w, q.h, mode, &q.sig) {
+        return Err(AppError::forbidden("invalid media signature"));
+    }
+    media
+        .check_allowed(&key, q.w, q.h, mode)
+        .map_err(|e| AppError::bad_request(e.to_string()))?;
+    if raster_content_type(&key).is_none() {
+        return Err(Problem::new(StatusCode::UNSUPPORTED_MEDIA_TYPE).into());
+    }
+
+    let worker = media.clone();
+    let file = tokio::task::spawn_blocking(move || worker.render(&key, q.w, q.h, mode))
+        .await
+        .map_err(|e| AppError::Internal(anyhow!("media task failed: {e}")))?
+        .map_err(|e| AppError::Internal(e.context("media error")))?
+        .ok_or_else(|| AppError::not_found("not found"))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, file.content_type),
+            (header::CACHE_CONTROL, media.config.cache_control.clone()),
+        ],
+        file.bytes,
+    )
+        .into_response())
+}
+
+/// Deletes every variant of `key` cached under `cache_prefix`
+/// (`{cache_prefix}/{variant}/{key}`); returns how many were removed.
+///
+/// # Errors
+/// Returns an error if the storage cannot list or delete files.
+pub fn purge_variants(storage: &dyn FileStorage, cache_prefix: &str, key: &str) -> Result<usize> {
+    let prefix = format!("{}/", cache_prefix.trim_end_matches('/'));
+    let mut removed = 0;
+    for object in storage.list(&prefix)? {
+        let Some((_variant, rest)) = object
+            .path
+            .strip_prefix(&prefix)
+            .and_then(|p| p.split_once('/'))
+        else {
+            continue;
+        };
+        if rest == key && storage.delete(&object.path)? {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Infers the image content type from the key's extension; `None` for
+/// anything but the raster formats served here.
+pub(crate) fn raster_content_type(key: &str) -> Option<&'static str> {
+    let ext = key.rsplit_once('.')?.1.to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Rejects empty keys, absolute paths, backslashes and `..` segments.
+pub(crate) fn is_safe_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with('/')
+        && !key.contains('\\')
+        && key.split('/').all(|seg| !seg.is_empty() && seg != "..")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use tower::ServiceExt;
+
+    use crate::web::upload::quarantine::{
+        Quarantine, QuarantineReason, QuarantineService, ScanVerdict, VirusScanner,
+    };
+    use crate::web::upload::storage::StoredObject;
+
+    #[derive(Default)]
+    struct MemoryStorage {
+        files: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    impl FileStorage for MemoryStorage {
+        fn save(&self, rel_path: &str, bytes: &[u8]) -> Result<String> {
+            self.files
+                .lock()
+                .unwrap()
+                .insert(rel_path.to_string(), bytes.to_vec());
+            Ok(rel_path.to_string())
+        }
+
+        fn load(&self, rel_path: &str) -> Result<Option<Vec<u8>>> {
+            Ok(self.files.lock().unwrap().get(rel_path).cloned())
+        }
+
+        fn list(&self, prefix: &str) -> Result<Vec<StoredObject>> {
+            Ok(self
+                .files
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(path, _)| path.starts_with(prefix))
+                .map(|(path, bytes)| StoredObject {
+                    path: path.clone(),
+                    bytes: bytes.len() as u64,
+                    modified: std::time::SystemTime::UNIX_EPOCH,
+                })
+                .collect())
+        }
+
+        fn delete(&self, rel_path: &str) -> Result<bool> {
+            Ok(self.files.lock().unwrap().remove(rel_path).is_some())
+        }
+    }
+
+    /// Appends the requested size to the input so tests can see what ran.
+    #[derive(Default)]
+    struct TaggingProcessor {
+        calls: Mutex<usize>,
+    }
+
+    impl ImageProcessor for TaggingProcessor {
+        fn is_supported(&self, content_type: &str) -> bool {
+            content_type.starts_with("image/")
+        }
+
+        fn resize_same_format(
+            &self,
+            img_bytes: &[u8],
+            _content_type: &str,
+            opts: ResizeOpts,
+        ) -> Result<Vec<u8>> {
+            *self.calls.lock().unwrap() += 1;
+            let mut out = img_bytes.to_vec();
+            out.extend_from_slice(format!(":{}x{}", opts.max_w, opts.max_h).as_bytes());
+            Ok(out)
+        }
+    }
+
+    fn service() -> (Arc<MediaService>, Arc<MemoryStorage>, Arc<TaggingProcessor>) {
+        let storage = Arc::new(MemoryStorage::default());
+        storage.save("images/a.png", b"png").unwrap();
+        storage.save("files/a.txt", b"txt").unwrap();
+        let processor = Arc::new(TaggingProcessor::default());
+        let svc = MediaService::new(
+            storage.clone(),
+            processor.clone(),
+            MediaConfig::new([3u8; 32], vec![(400, 300)]),
+        );
+        (Arc::new(svc), storage, processor)
+    }
+
+    fn app(svc: Arc<MediaService>) -> Router {
+        Router::new()
+            .route("/media/{*key}", get(media_handler))
+            .layer(Extension(svc))
+    }
+
+    async fn get_status(app: Router, uri: &str) -> (StatusCode, Vec<u8>) {
+        let resp = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = resp.status();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, body.to_vec())
+    }
+
+    #[test]
+    fn signed_url_respects_allowlist() {
+        let (svc, _, _) = service();
+        let url = svc
+            .signed_url("images/a.png", 400, 300, ResizeMode::Cover)
+            .unwrap();
+        assert!(url.starts_with("/media/images/a.png?w=400&h=300&fit=cover&sig="));
+
+        assert!(svc
+            .signed_url("images/a.png", 401, 300, ResizeMode::Cover)
+            .is_err());
+        assert!(svc
+            .signed_url("../etc/passwd.png", 400, 300, ResizeMode::Cover)
+            .is_err());
+    }
+
+    #[test]
+    fn render_caches_variants() {
+        let (svc, storage, processor) = service();
+
+        let first = svc
+            .render("images/a.png", 400, 300, ResizeMode::Cover)
+            .unwrap()
+            .unwrap();
+        let second = svc
+            .render("images/a.png", 400, 300, ResizeMode::Cover)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(first.bytes, b"png:400x300");
+        assert_eq!(first, second);
+        assert_eq!(first.content_type, "image/png");
+        assert_eq!(*processor.calls.lock().unwrap(), 1);
+        assert!(storage
+            .files
+            .lock()
+            .unwrap()
+            .contains_key("cache/media/400x300-cover/images/a.png"));
+    }
+
+    struct StaticFocal(FocalPoint);
+
+    impl FocalPointStore for StaticFocal {
+        fn focal_point(&self, _key: &str) -> Result<Option<FocalPoint>> {
+            Ok(Some(self.0))
+        }
+
+        fn set_focal_point(&self, _key: &str, _focal: FocalPoint) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn render_caches_cover_crops_per_focal_point() {
+        let storage = Arc::new(MemoryStorage::default());
+        storage.save("images/a.png", b"png").unwrap();
+        let svc = MediaService::new(
+            storage.clone(),
+            Arc::new(TaggingProcessor::default()),
+            MediaConfig::new([3u8; 32], vec![(400, 300)]),
+        )
+        .focal_points(Arc::new(StaticFocal(FocalPoint::new(0.2, 0.3).unwrap())));
+
+        svc.render("images/a.png", 400, 300, ResizeMode::Cover)
+            .unwrap()
+            .unwrap();
+        svc.render("images/a.png", 400, 300, ResizeMode::Fit)
+            .unwrap()
+            .unwrap();
+
+        let files = storage.files.lock().unwrap();
+        assert!(files.contains_key("cache/media/400x300-cover@0.2,0.3/images/a.png"));
+        assert!(files.contains_key("cache/media/400x300-fit/images/a.png"));
+    }
+
+    #[test]
+    fn render_returns_none_for_missing_original() {
+        let (svc, _, _) = service();
+        assert_eq!(
+            svc.render("images/missing.png", 400, 300, ResizeMode::Fit)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn handler_serves_signed_variant() {
+        let (svc, _, _) = service();
+        let url = svc
+            .signed_url("images/a.png", 400, 300, ResizeMode::Cover)
+            .unwrap();
+
+        let resp = app(svc)
+            .oneshot(Request::get(&url).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[header::CONTENT_TYPE], "image/png");
+        assert!(resp.headers()[header::CACHE_CONTROL]
+            .to_str()
+            .unwrap()
+            .contains("immutable"));
+    }
+
+    #[tokio::test]
+    async fn handler_rejects_bad_signatures_and_params() {
+        let (svc, _, processor) = service();
+        let url = svc
+            .signed_url("images/a.png", 400, 300, ResizeMode::Cover)
+            .unwrap();
+
+        let tampered = url.replace("fit=cover", "fit=contain");
+        assert_eq!(
+            get_status(app(svc.clone()), &tampered).await.0,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            get_status(app(svc.clone()), "/media/images/a.png?w=400&h=300")
+                .await
+                .0,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            get_status(
+                app(svc.clone()),
+                "/media/images/a.png?w=400&h=300&fit=stretch&sig=x"
+            )
+            .await
+            .0,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(*processor.calls.lock().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn handler_maps_missing_and_unsupported() {
+        let (svc, _, _) = service();
+
+        let sig = svc.sign("images/none.png", 400, 300, ResizeMode::Fit);
+        let uri = format!("/media/images/none.png?w=400&h=300&fit=fit&sig={sig}");
+        assert_eq!(
+            get_status(app(svc.clone()), &uri).await.0,
+            StatusCode::NOT_FOUND
+        );
+
+        let sig = svc.sign("files/a.txt", 400, 300, ResizeMode::Fit);
+        let uri = format!("/media/files/a.txt?w=400&h=300&sig={sig}");
+        assert_eq!(
+            get_status(app(svc), &uri).await.0,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+    }
+
+    #[derive(Default)]
+    struct MemoryQuarantineStore {
+        records: Mutex<HashMap<String, Quarantine>>,
+    }
+
+    impl QuarantineStore for MemoryQuarantineStore {
+        fn quarantine(&self, key: &str) -> Result<Option<Quarantine>> {
+            Ok(self.records.lock().unwrap().get(key).cloned())
+        }
+
+        fn mark_quarantined(&self, key: &str, quarantine: &Quarantine) -> Result<()> {
+            self.records
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), quarantine.clone());
+            Ok(())
+        }
+    }
+
+    struct CleanScanner;
+
+    impl VirusScanner for CleanScanner {
+        fn scan(&self, _key: &str, _bytes: &[u8]) -> Result<ScanVerdict> {
+            Ok(ScanVerdict::Clean)
+        }
+    }
+
+    #[tokio::test]
+    async fn quarantined_originals_and_cached_variants_are_not_served() {
+        let storage = Arc::new(MemoryStorage::default());
+        storage.save("images/a.png", b"png").unwrap();
+        storage.save("images/b.png", b"png").unwrap();
+        let store = Arc::new(MemoryQuarantineStore::default());
+        let svc = Arc::new(
+            MediaService::new(
+                storage.clone(),
+                Arc::new(TaggingProcessor::default()),
+                MediaConfig::new([3u8; 32], vec![(400, 300)]),
+            )
+            .quarantine(store.clone()),
+        );
+        let url = |key: &str| svc.signed_url(key, 400, 300, ResizeMode::Cover).unwrap();
+        for key in ["images/a.png", "images/b.png"] {
+            assert_eq!(
+                get_status(app(svc.clone()), &url(key)).await.0,
+                StatusCode::OK
+            );
+        }
+
+        QuarantineService::new(storage.clone(), Arc::new(CleanScanner), store)
+            .quarantine("images/a.png", QuarantineReason::Legal, "takedown")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            get_status(app(svc.clone()), &url("images/a.png")).await.0,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            get_status(app(svc.clone()), &url("quarantine/images/a.png"))
+                .await
+                .0,
+            StatusCode::NOT_FOUND
+        );
+        let files = storage.files.lock().unwrap();
+        assert!(!files.contains_key("cache/media/400x300-cover/images/a.png"));
+        assert!(files.contains_key("cache/media/400x300-cover/images/b.png"));
+    }
+}
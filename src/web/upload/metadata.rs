@@ -0,0 +1,295 @@
+//! # Upload Metadata
+//!
+//! Per-upload image metadata kept alongside the path in the upload metadata
+//! table:
+//!
+//! - the [`FocalPoint`] supplied at upload time, which
+//!   [`MediaService`](crate::web::upload::media::MediaService) honors when
+//!   cropping `cover` variants ([`FocalPointStore`]);
+//! - the SHA-256 of the content at save time, checked by
+//!   [`ChecksummedStorage`](crate::web::upload::integrity::ChecksummedStorage)
+//!   ([`ChecksumStore`]).
+//!
+//! Expected columns (MySQL; `path` is the key shared with
+//! [`DbUploadIndex`](crate::web::upload::gc::DbUploadIndex)):
+//!
+//! ```sql
+//! ALTER TABLE uploads
+//!     ADD COLUMN focal_x FLOAT NULL,
+//!     ADD COLUMN focal_y FLOAT NULL,
+//!     ADD COLUMN sha256 CHAR(64) NULL;
+//! ```
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use wzs_web::config::db::DbConfig;
+//! use wzs_web::db::{connection::get_pool, mysql_adapter::MySqlDb};
+//! use wzs_web::image::processor::FocalPoint;
+//! use wzs_web::web::upload::metadata::{DbFocalPointStore, FocalPointStore};
+//!
+//! # fn run() -> anyhow::Result<()> {
+//! let db = Arc::new(MySqlDb::new(get_pool(&DbConfig::from_env())));
+//! let store = DbFocalPointStore::new(db);
+//!
+//! store.set_focal_point("images/202603/a.png", FocalPoint::new(0.3, 0.2)?)?;
+//! assert!(store.focal_point("images/202603/a.png")?.is_some());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+
+use crate::db::port::{Db, Param, Value};
+use crate::db::repository::ident;
+use crate::image::processor::FocalPoint;
+
+/// Reads and records focal points by storage key.
+pub trait FocalPointStore: Send + Sync {
+    /// Returns the focal point recorded for `key`, if any.
+    fn focal_point(&self, key: &str) -> Result<Option<FocalPoint>>;
+
+    /// Records the focal point for `key`, replacing any previous one.
+    fn set_focal_point(&self, key: &str, focal: FocalPoint) -> Result<()>;
+}
+
+/// [`FocalPointStore`] backed by the upload metadata table.
+#[derive(Clone)]
+pub struct DbFocalPointStore {
+    db: Arc<dyn Db>,
+    table: String,
+}
+
+impl DbFocalPointStore {
+    /// Default table name.
+    pub const DEFAULT_TABLE: &'static str = "uploads";
+
+    /// Creates a store over the `uploads` table.
+    pub fn new(db: Arc<dyn Db>) -> Self {
+        Self {
+            db,
+            table: Self::DEFAULT_TABLE.into(),
+        }
+    }
+
+    /// Creates a store over a custom table.
+    ///
+    /// # Errors
+    /// Returns an error if `table` is not a plain SQL identifier.
+    pub fn with_table(db: Arc<dyn Db>, table: impl Into<String>) -> Result<Self> {
+        let table = table.into();
+        ident(&table)?;
+        Ok(Self { db, table })
+    }
+}
+
+impl FocalPointStore for DbFocalPointStore {
+    fn focal_point(&self, key: &str) -> Result<Option<FocalPoint>> {
+        let sql = format!(
+            "SELECT focal_x, focal_y FROM {} WHERE path = ? LIMIT 1",
+            self.table
+        );
+        let Some(row) = self.db.fetch_one(&sql, &[Param::Str(key)])? else {
+            return Ok(None);
+        };
+        let (Some(x), Some(y)) = (ratio(row.get("focal_x")), ratio(row.get("focal_y"))) else {
+            return Ok(None);
+        };
+        FocalPoint::new(x, y)
+            .map(Some)
+            .with_context(|| format!("stored focal point for {key}"))
+    }
+
+    fn set_focal_point(&self, key: &str, focal: FocalPoint) -> Result<()> {
+        let sql = format!(
+            "INSERT INTO {} (path, focal_x, focal_y) VALUES (?, ?, ?) \
+             ON DUPLICATE KEY UPDATE focal_x = VALUES(focal_x), focal_y = VALUES(focal_y)",
+            self.table
+        );
+        self.db.exec(
+            &sql,
+            &[
+                Param::Str(key),
+                Param::F32(focal.x()),
+                Param::F32(focal.y()),
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+/// Reads and records content checksums (lowercase hex SHA-256) by storage key.
+pub trait ChecksumStore: Send + Sync {
+    /// Returns the checksum recorded for `key`, if any.
+    fn checksum(&self, key: &str) -> Result<Option<String>>;
+
+    /// Records the checksum for `key`, replacing any previous one.
+    fn set_checksum(&self, key: &str, sha256: &str) -> Result<()>;
+}
+
+/// [`ChecksumStore`] backed by the `sha256` column of the upload metadata
+/// table.
+#[derive(Clone)]
+pub struct DbChecksumStore {
+    db: Arc<dyn Db>,
+    table: String,
+}
+
+impl DbChecksumStore {
+    /// Default table name.
+    pub const DEFAULT_TABLE: &'static str = "uploads";
+
+    /// Creates a store over the `uploads` table.
+    pub fn new(db: Arc<dyn Db>) -> Self {
+        Self {
+            db,
+            table: Self::DEFAULT_TABLE.into(),
+        }
+    }
+
+    /// Creates a store over a custom table.
+    ///
+    /// # Errors
+    /// Returns an error if `table` is not a plain SQL identifier.
+    pub fn with_table(db: Arc<dyn Db>, table: impl Into<String>) -> Result<Self> {
+        let table = table.into();
+        ident(&table)?;
+        Ok(Self { db, table })
+    }
+}
+
+impl ChecksumStore for DbChecksumStore {
+    fn checksum(&self, key: &str) -> Result<Option<String>> {
+        let sql = format!("SELECT sha256 FROM {} WHERE path = ? LIMIT 1", self.table);
+        let row = self.db.fetch_one(&sql, &[Param::Str(key)])?;
+        Ok(match row.as_ref().and_then(|r| r.get("sha256")) {
+            Some(Value::Str(s)) if !s.is_empty() => Some(s.clone()),
+            _ => None,
+        })
+    }
+
+    fn set_checksum(&self, key: &str, sha256: &str) -> Result<()> {
+        let sql = format!(
+            "INSERT INTO {} (path, sha256) VALUES (?, ?) \
+             ON DUPLICATE KEY UPDATE sha256 = VALUES(sha256)",
+            self.table
+        );
+        self.db.exec(&sql, &[Param::Str(key), Param::Str(sha256)])?;
+        Ok(())
+    }
+}
+
+/// Reads a `FLOAT` / `DOUBLE` ratio column; `NULL` or missing is `None`.
+fn ratio(v: Option<&Value>) -> Option<f32> {
+    match v? {
+        Value::F32(x) => Some(*x),
+        Value::F64(x) => Some(*x as f32),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::db::port::Row;
+
+    #[derive(Default)]
+    struct RecordingDb {
+        row: Mutex<Option<Row>>,
+        execs: Mutex<Vec<(String, Vec<Value>)>>,
+    }
+
+    impl Db for RecordingDb {
+        fn fetch_one(&self, _sql: &str, _params: &[Param]) -> Result<Option<Row>> {
+            Ok(self.row.lock().unwrap().clone())
+        }
+
+        fn fetch_all(&self, _sql: &str, _params: &[Param]) -> Result<Vec<Row>> {
+            Ok(vec![])
+        }
+
+        fn exec(&self, sql: &str, params: &[Param]) -> Result<u64> {
+            self.execs.lock().unwrap().push((
+                sql.to_string(),
+                params.iter().map(Param::to_value).collect(),
+            ));
+            Ok(1)
+        }
+
+        fn exec_returning_last_insert_id(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn set_focal_point_upserts_by_path() {
+        let db = Arc::new(RecordingDb::default());
+        let store = DbFocalPointStore::with_table(db.clone(), "media_files").unwrap();
+
+        store
+            .set_focal_point("images/a.png", FocalPoint::new(0.25, 0.75).unwrap())
+            .unwrap();
+
+        let execs = db.execs.lock().unwrap();
+        assert!(execs[0]
+            .0
+            .starts_with("INSERT INTO media_files (path, focal_x, focal_y)"));
+        assert!(execs[0].0.contains("ON DUPLICATE KEY UPDATE"));
+        assert!(matches!(&execs[0].1[0], Value::Str(p) if p == "images/a.png"));
+        assert!(matches!(execs[0].1[1], Value::F32(x) if x == 0.25));
+    }
+
+    #[test]
+    fn focal_point_reads_row_and_treats_null_as_unset() {
+        let db = Arc::new(RecordingDb::default());
+        let store = DbFocalPointStore::new(db.clone());
+        assert_eq!(store.focal_point("images/a.png").unwrap(), None);
+
+        let mut row = Row::default();
+        row.insert("focal_x", Value::Null);
+        row.insert("focal_y", Value::Null);
+        *db.row.lock().unwrap() = Some(row.clone());
+        assert_eq!(store.focal_point("images/a.png").unwrap(), None);
+
+        row.insert("focal_x", Value::F32(0.2));
+        row.insert("focal_y", Value::F64(0.4));
+        *db.row.lock().unwrap() = Some(row);
+        let focal = store.focal_point("images/a.png").unwrap().unwrap();
+        assert_eq!((focal.x(), focal.y()), (0.2, 0.4));
+    }
+
+    #[test]
+    fn with_table_rejects_non_identifiers() {
+        let db: Arc<dyn Db> = Arc::new(RecordingDb::default());
+        assert!(DbFocalPointStore::with_table(db.clone(), "uploads; DROP").is_err());
+        assert!(DbFocalPointStore::with_table(db.clone(), "").is_err());
+        assert!(DbChecksumStore::with_table(db, "a-b").is_err());
+    }
+
+    #[test]
+    fn checksums_upsert_and_read_back() {
+        let db = Arc::new(RecordingDb::default());
+        let store = DbChecksumStore::new(db.clone());
+        assert_eq!(store.checksum("files/a.pdf").unwrap(), None);
+
+        store.set_checksum("files/a.pdf", "abc123").unwrap();
+        let execs = db.execs.lock().unwrap();
+        assert!(execs[0].0.starts_with("INSERT INTO uploads (path, sha256)"));
+        assert!(matches!(&execs[0].1[1], Value::Str(s) if s == "abc123"));
+
+        let mut row = Row::default();
+        row.insert("sha256", Value::Null);
+        *db.row.lock().unwrap() = Some(row.clone());
+        assert_eq!(store.checksum("files/a.pdf").unwrap(), None);
+        row.insert("sha256", Value::Str("abc123".into()));
+        *db.row.lock().unwrap() = Some(row);
+        assert_eq!(
+            store.checksum("files/a.pdf").unwrap().as_deref(),
+            Some("abc123")
+        );
+    }
+}
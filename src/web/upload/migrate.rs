@@ -0,0 +1,471 @@
+//! # Storage Migration
+//!
+//! Copies (or moves) every object under a set of prefixes from one
+//! [`FileStorage`] backend to another, e.g. from
+//! [`LocalFileStorage`](crate::web::upload::local_storage::LocalFileStorage)
+//! on a single host to an object store:
+//!
+//! - objects are transferred [`concurrency`](StorageMigration::concurrency)
+//!   at a time on the blocking pool
+//! - each copy is read back from the target and compared by SHA-256 before
+//!   it counts as migrated (and, in [`MigrationMode::Move`], before the
+//!   source is deleted)
+//! - keys can be rewritten on the way ([`rewrite_prefix`](StorageMigration::rewrite_prefix));
+//!   [`MigrationReport::rewrite_map`] lists every changed key so stored
+//!   references can be updated afterwards
+//! - objects already present in the target with the same content are
+//!   skipped, so an interrupted run can simply be repeated
+//! - a [`progress`](StorageMigration::progress) callback is invoked after
+//!   every object
+//!
+//! Both backends must implement [`FileStorage::list`] (source) and
+//! [`FileStorage::load`] (source and target).
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use wzs_web::web::upload::local_storage::LocalFileStorage;
+//! use wzs_web::web::upload::migrate::{MigrationMode, StorageMigration};
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let migration = StorageMigration::new(
+//!     Arc::new(LocalFileStorage::new("./var/uploads")),
+//!     Arc::new(LocalFileStorage::new("/mnt/shared/uploads")),
+//! )
+//! .mode(MigrationMode::Move)
+//! .concurrency(8)
+//! .rewrite_prefix("images/", "media/images/")
+//! .progress(|p| eprintln!("{}/{} ({} bytes)", p.done, p.total, p.bytes));
+//!
+//! let report = migration.run().await?;
+//! println!("{}", report.summary());
+//! for (old, new) in report.rewrite_map() {
+//!     println!("UPDATE uploads SET path = '{new}' WHERE path = '{old}';");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use tokio::task::JoinSet;
+
+use crate::web::upload::integrity::sha256_hex;
+use crate::web::upload::storage::FileStorage;
+
+/// Whether the source object is kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MigrationMode {
+    /// Leave the source untouched.
+    #[default]
+    Copy,
+    /// Delete the source once the copy is verified.
+    Move,
+}
+
+/// Progress after one object, passed to the
+/// [`progress`](StorageMigration::progress) callback.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MigrationProgress {
+    /// Objects handled so far (migrated, skipped or failed).
+    pub done: usize,
+    /// Objects found under the prefixes.
+    pub total: usize,
+    /// Bytes copied so far.
+    pub bytes: u64,
+}
+
+/// One object copied by [`StorageMigration::run`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MigratedObject {
+    /// Key in the source storage.
+    pub from: String,
+    /// Key in the target storage.
+    pub to: String,
+    /// Size in bytes.
+    pub bytes: u64,
+    /// Hex SHA-256 digest of the content.
+    pub sha256: String,
+}
+
+/// Outcome of a migration.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// Objects copied (and verified).
+    pub migrated: Vec<MigratedObject>,
+    /// Objects already present in the target with the same content.
+    pub skipped: Vec<MigratedObject>,
+    /// `(key, error)` for objects that could not be migrated.
+    pub failed: Vec<(String, String)>,
+}
+
+impl MigrationReport {
+    /// Source key → target key for every object whose key changed,
+    /// including skipped ones.
+    pub fn rewrite_map(&self) -> BTreeMap<String, String> {
+        self.migrated
+            .iter()
+            .chain(&self.skipped)
+            .filter(|o| o.from != o.to)
+            .map(|o| (o.from.clone(), o.to.clone()))
+            .collect()
+    }
+
+    /// Total bytes copied.
+    pub fn bytes(&self) -> u64 {
+        self.migrated.iter().map(|o| o.bytes).sum()
+    }
+
+    /// Multi-line, human-readable summary.
+    pub fn summary(&self) -> String {
+        let mut out = format!(
+            "Storage migration: migrated {} ({} bytes), skipped {}, failed {}",
+            self.migrated.len(),
+            self.bytes(),
+            self.skipped.len(),
+            self.failed.len()
+        );
+        if !self.failed.is_empty() {
+            out.push_str("\n\nFailed:");
+            for (p, e) in &self.failed {
+                out.push_str(&format!("\n  {p}: {e}"));
+            }
+        }
+        out
+    }
+}
+
+type ProgressFn = Arc<dyn Fn(&MigrationProgress) + Send + Sync>;
+
+enum Outcome {
+    Migrated(MigratedObject),
+    Skipped(MigratedObject),
+}
+
+/// Copies objects between two storages; see the [module docs](self).
+pub struct StorageMigration {
+    source: Arc<dyn FileStorage>,
+    target: Arc<dyn FileStorage>,
+    prefixes: Vec<String>,
+    mode: MigrationMode,
+    concurrency: usize,
+    rewrites: Vec<(String, String)>,
+    progress: Option<ProgressFn>,
+}
+
+impl StorageMigration {
+    /// Copies the `images` and `files` prefixes, four objects at a time,
+    /// keeping keys unchanged.
+    pub fn new(source: Arc<dyn FileStorage>, target: Arc<dyn FileStorage>) -> Self {
+        Self {
+            source,
+            target,
+            prefixes: vec!["images".into(), "files".into()],
+            mode: MigrationMode::Copy,
+            concurrency: 4,
+            rewrites: Vec::new(),
+            progress: None,
+        }
+    }
+
+    /// Sets the migrated prefixes.
+    pub fn prefixes<I, S>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.prefixes = prefixes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Copies or moves objects (default [`MigrationMode::Copy`]).
+    pub fn mode(mut self, mode: MigrationMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Maximum number of objects transferred at once (at least 1).
+    pub fn concurrency(mut self, n: usize) -> Self {
+        self.concurrency = n.max(1);
+        self
+    }
+
+    /// Stores keys starting with `from` under `to` instead.
+    ///
+    /// Rules are tried in the order they were added; the first match wins.
+    pub fn rewrite_prefix(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.rewrites.push((from.into(), to.into()));
+        self
+    }
+
+    /// Calls `f` after every object.
+    pub fn progress(mut self, f: impl Fn(&MigrationProgress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(f));
+        self
+    }
+
+    /// Target key for the source key `key`.
+    pub fn target_key(&self, key: &str) -> String {
+        self.rewrites
+            .iter()
+            .find_map(|(from, to)| {
+                key.strip_prefix(from.as_str())
+                    .map(|rest| format!("{to}{rest}"))
+            })
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    /// Runs the migration.
+    ///
+    /// Failures of single objects are collected in the report; the run only
+    /// fails if the source cannot be listed.
+    pub async fn run(&self) -> Result<MigrationReport> {
+        let mut keys = Vec::new();
+        for prefix in &self.prefixes {
+            let source = self.source.clone();
+            let prefix = prefix.clone();
+            let listed = tokio::task::spawn_blocking(move || source.list(&prefix))
+                .await
+                .context("storage listing task failed")??;
+            keys.extend(listed.into_iter().map(|o| o.path));
+        }
+
+        let mut run = Run {
+            report: MigrationReport::default(),
+            progress: MigrationProgress {
+                done: 0,
+                total: keys.len(),
+                bytes: 0,
+            },
+        };
+        let mut tasks = JoinSet::new();
+        for key in keys {
+            while tasks.len() >= self.concurrency {
+                let Some(joined) = tasks.join_next().await else {
+                    break;
+                };
+                self.record(&mut run, joined.context("storage migration task failed")?);
+            }
+            let source = self.source.clone();
+            let target = self.target.clone();
+            let to = self.target_key(&key);
+            let mode = self.mode;
+            tasks.spawn_blocking(move || {
+                let outcome = migrate_one(source.as_ref(), target.as_ref(), &key, &to, mode);
+                (key, outcome)
+            });
+        }
+        while let Some(joined) = tasks.join_next().await {
+            self.record(&mut run, joined.context("storage migration task failed")?);
+        }
+
+        let mut report = run.report;
+        report.migrated.sort_by(|a, b| a.from.cmp(&b.from));
+        report.skipped.sort_by(|a, b| a.from.cmp(&b.from));
+        report.failed.sort();
+        Ok(report)
+    }
+
+    fn record(&self, run: &mut Run, (key, outcome): (String, Result<Outcome>)) {
+        match outcome {
+            Ok(Outcome::Migrated(obj)) => {
+                run.progress.bytes += obj.bytes;
+                run.report.migrated.push(obj);
+            }
+            Ok(Outcome::Skipped(obj)) => run.report.skipped.push(obj),
+            Err(e) => run.report.failed.push((key, format!("{e:#}"))),
+        }
+        run.progress.done += 1;
+        if let Some(f) = &self.progress {
+            f(&run.progress);
+        }
+    }
+}
+
+/// State of a running migration.
+struct Run {
+    report: MigrationReport,
+    progress: MigrationProgress,
+}
+
+fn migrate_one(
+    source: &dyn FileStorage,
+    target: &dyn FileStorage,
+    from: &str,
+    to: &str,
+    mode: MigrationMode,
+) -> Result<Outcome> {
+    let Some(bytes) = source.load(from)? else {
+        bail!("vanished from the source");
+    };
+    let obj = MigratedObject {
+        from: from.to_string(),
+        to: to.to_string(),
+        bytes: bytes.len() as u64,
+        sha256: sha256_hex(&bytes),
+    };
+
+    let present = target.load(to)?.map(|b| sha256_hex(&b));
+    let outcome = if present.as_deref() == Some(obj.sha256.as_str()) {
+        Outcome::Skipped(obj)
+    } else {
+        target.save(to, &bytes)?;
+        let copied = target
+            .load(to)?
+            .context("copy is missing from the target")?;
+        let actual = sha256_hex(&copied);
+        if actual != obj.sha256 {
+            bail!("checksum mismatch (expected {}, got {actual})", obj.sha256);
+        }
+        Outcome::Migrated(obj)
+    };
+
+    if mode == MigrationMode::Move {
+        source.delete(from).context("delete source")?;
+    }
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::time::SystemTime;
+
+    use crate::web::upload::storage::StoredObject;
+
+    #[derive(Default)]
+    struct MemStorage {
+        files: Mutex<BTreeMap<String, Vec<u8>>>,
+        /// Corrupts every saved file (to exercise verification).
+        corrupt: bool,
+    }
+
+    impl MemStorage {
+        fn with(files: &[(&str, &str)]) -> Self {
+            let storage = Self::default();
+            for (path, content) in files {
+                storage.save(path, content.as_bytes()).unwrap();
+            }
+            storage
+        }
+
+        fn paths(&self) -> Vec<String> {
+            self.files.lock().unwrap().keys().cloned().collect()
+        }
+    }
+
+    impl FileStorage for MemStorage {
+        fn save(&self, rel_path: &str, bytes: &[u8]) -> Result<String> {
+            let mut bytes = bytes.to_vec();
+            if self.corrupt {
+                bytes.push(0);
+            }
+            self.files.lock().unwrap().insert(rel_path.into(), bytes);
+            Ok(rel_path.into())
+        }
+
+        fn load(&self, rel_path: &str) -> Result<Option<Vec<u8>>> {
+            Ok(self.files.lock().unwrap().get(rel_path).cloned())
+        }
+
+        fn list(&self, prefix: &str) -> Result<Vec<StoredObject>> {
+            Ok(self
+                .files
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(p, _)| p.starts_with(&format!("{prefix}/")))
+                .map(|(p, b)| StoredObject {
+                    path: p.clone(),
+                    bytes: b.len() as u64,
+                    modified: SystemTime::now(),
+                })
+                .collect())
+        }
+
+        fn delete(&self, rel_path: &str) -> Result<bool> {
+            Ok(self.files.lock().unwrap().remove(rel_path).is_some())
+        }
+    }
+
+    fn source() -> Arc<MemStorage> {
+        Arc::new(MemStorage::with(&[
+            ("images/a.png", "aaa"),
+            ("files/b.pdf", "bb"),
+            ("other/c.txt", "c"),
+        ]))
+    }
+
+    #[tokio::test]
+    async fn copies_with_rewrites_and_progress() {
+        let (src, dst) = (source(), Arc::new(MemStorage::default()));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let seen = calls.clone();
+
+        let report = StorageMigration::new(src.clone(), dst.clone())
+            .concurrency(2)
+            .rewrite_prefix("images/", "media/")
+            .progress(move |p| {
+                seen.fetch_add(1, Ordering::SeqCst);
+                assert_eq!(p.total, 2);
+            })
+            .run()
+            .await
+            .unwrap();
+
+        assert_eq!(dst.paths(), ["files/b.pdf", "media/a.png"]);
+        assert_eq!(src.paths().len(), 3, "copy keeps the source");
+        assert_eq!(report.migrated.len(), 2);
+        assert_eq!(report.bytes(), 5);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            report.rewrite_map(),
+            BTreeMap::from([("images/a.png".to_string(), "media/a.png".to_string())])
+        );
+    }
+
+    #[tokio::test]
+    async fn move_deletes_sources_and_reruns_skip() {
+        let (src, dst) = (source(), Arc::new(MemStorage::default()));
+        let _ = dst.save("files/b.pdf", b"bb");
+
+        let report = StorageMigration::new(src.clone(), dst.clone())
+            .mode(MigrationMode::Move)
+            .run()
+            .await
+            .unwrap();
+
+        assert_eq!(report.migrated.len(), 1);
+        assert_eq!(report.skipped[0].from, "files/b.pdf");
+        assert_eq!(src.paths(), ["other/c.txt"]);
+        assert!(report
+            .summary()
+            .starts_with("Storage migration: migrated 1 (3 bytes), skipped 1"));
+    }
+
+    #[tokio::test]
+    async fn checksum_mismatch_fails_and_keeps_source() {
+        let src = source();
+        let dst = Arc::new(MemStorage {
+            corrupt: true,
+            ..MemStorage::default()
+        });
+
+        let report = StorageMigration::new(src.clone(), dst)
+            .mode(MigrationMode::Move)
+            .prefixes(["images"])
+            .run()
+            .await
+            .unwrap();
+
+        assert!(report.migrated.is_empty());
+        assert_eq!(report.failed[0].0, "images/a.png");
+        assert!(report.failed[0].1.contains("checksum mismatch"));
+        assert_eq!(src.paths().len(), 3);
+    }
+}
@@ -0,0 +1,590 @@
+//! # Upload Scanning and Quarantine
+//!
+//! Stored uploads are scanned after the fact by a background worker fed
+//! through a [`ScanQueue`]. A file the [`VirusScanner`] flags is
+//! quarantined:
+//!
+//! 1. its metadata row is marked ([`QuarantineStore`]), so downloads are
+//!    refused from that moment on,
+//! 2. the bytes are moved under [`QUARANTINE_PREFIX`] (kept for review,
+//!    never served) and its cached [`media`](crate::web::upload::media)
+//!    variants are deleted,
+//! 3. admins are told via a [`Notifier`].
+//!
+//! [`quarantine`](QuarantineService::quarantine) can also be called
+//! directly, e.g. for a legal takedown. The
+//! [`download_handler`](crate::web::upload::download::download_handler)
+//! answers quarantined keys with the reason's status
+//! ([`QuarantineReason::status`]): `410 Gone` for malware and
+//! `451 Unavailable For Legal Reasons` for legal holds.
+//!
+//! Expected columns on the upload metadata table (MySQL):
+//!
+//! ```sql
+//! ALTER TABLE uploads
+//!     ADD COLUMN quarantine_reason VARCHAR(16) NULL,
+//!     ADD COLUMN quarantine_detail VARCHAR(255) NULL,
+//!     ADD COLUMN quarantined_at DATETIME NULL;
+//! ```
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use wzs_web::db::port::Db;
+//! use wzs_web::notification::notifier::Notifier;
+//! use wzs_web::web::upload::local_storage::LocalFileStorage;
+//! use wzs_web::web::upload::quarantine::{DbQuarantineStore, QuarantineService, VirusScanner};
+//!
+//! # async fn run(
+//! #     db: Arc<dyn Db>,
+//! #     scanner: Arc<dyn VirusScanner>,
+//! #     admins: Arc<dyn Notifier>,
+//! # ) -> anyhow::Result<()> {
+//! let service = QuarantineService::new(
+//!     Arc::new(LocalFileStorage::new("./uploads")),
+//!     scanner,
+//!     Arc::new(DbQuarantineStore::new(db)),
+//! )
+//! .notifier(admins);
+//!
+//! let queue = Arc::new(service).spawn_worker(256);
+//! // after each successful upload:
+//! queue.enqueue("files/202603/a.pdf").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use axum::http::StatusCode;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::db::port::{Db, Param, Value};
+use crate::db::repository::ident;
+use crate::notification::notifier::Notifier;
+use crate::web::upload::media::{purge_variants, DEFAULT_CACHE_PREFIX};
+use crate::web::upload::storage::FileStorage;
+
+/// Storage prefix quarantined files are moved under.
+pub const QUARANTINE_PREFIX: &str = "quarantine";
+
+/// Why a file was quarantined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuarantineReason {
+    /// Flagged by the virus scanner.
+    Malware,
+    /// Withdrawn for legal reasons (takedown, court order, …).
+    Legal,
+}
+
+impl QuarantineReason {
+    /// Stored representation.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QuarantineReason::Malware => "malware",
+            QuarantineReason::Legal => "legal",
+        }
+    }
+
+    /// Parses the stored representation.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "malware" => Some(QuarantineReason::Malware),
+            "legal" => Some(QuarantineReason::Legal),
+            _ => None,
+        }
+    }
+
+    /// HTTP status served in place of the file.
+    pub fn status(self) -> StatusCode {
+        match self {
+            QuarantineReason::Malware => StatusCode::GONE,
+            QuarantineReason::Legal => StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS,
+        }
+    }
+}
+
+/// Quarantine record of one upload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Quarantine {
+    pub reason: QuarantineReason,
+    /// Scanner signature or takedown reference.
+    pub detail: String,
+}
+
+/// Result of scanning one file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScanVerdict {
+    Clean,
+    /// Flagged, with the matched signature name.
+    Infected(String),
+}
+
+/// Virus scanner (ClamAV, a SaaS API, …).
+pub trait VirusScanner: Send + Sync {
+    /// Scans the contents of the file stored under `key`.
+    fn scan(&self, key: &str, bytes: &[u8]) -> Result<ScanVerdict>;
+}
+
+/// Reads and records quarantine state by storage key.
+pub trait QuarantineStore: Send + Sync {
+    /// Returns the quarantine record for `key`, if any.
+    fn quarantine(&self, key: &str) -> Result<Option<Quarantine>>;
+
+    /// Marks `key` as quarantined, replacing any previous record.
+    fn mark_quarantined(&self, key: &str, quarantine: &Quarantine) -> Result<()>;
+}
+
+/// [`QuarantineStore`] backed by the upload metadata table.
+#[derive(Clone)]
+pub struct DbQuarantineStore {
+    db: Arc<dyn Db>,
+    table: String,
+}
+
+impl DbQuarantineStore {
+    /// Default table name.
+    pub const DEFAULT_TABLE: &'static str = "uploads";
+
+    /// Creates a store over the `uploads` table.
+    pub fn new(db: Arc<dyn Db>) -> Self {
+        Self {
+            db,
+            table: Self::DEFAULT_TABLE.into(),
+        }
+    }
+
+    /// Creates a store over a custom table.
+    ///
+    /// # Errors
+    /// Returns an error if `table` is not a plain SQL identifier.
+    pub fn with_table(db: Arc<dyn Db>, table: impl Into<String>) -> Result<Self> {
+        let table = table.into();
+        ident(&table)?;
+        Ok(Self { db, table })
+    }
+}
+
+impl QuarantineStore for DbQuarantineStore {
+    fn quarantine(&self, key: &str) -> Result<Option<Quarantine>> {
+        let sql = format!(
+            "SELECT quarantine_reason, quarantine_detail FROM {} WHERE path = ? LIMIT 1",
+            self.table
+        );
+        let Some(row) = self.db.fetch_one(&sql, &[Param::Str(key)])? else {
+            return Ok(None);
+        };
+        let reason = match row.get("quarantine_reason") {
+            Some(Value::Str(s)) => QuarantineReason::parse(s)
+                .ok_or_else(|| anyhow!("unknown quarantine reason `{s}` for {key}"))?,
+            _ => return Ok(None),
+        };
+        Ok(Some(Quarantine {
+            reason,
+            detail: row.get_string_opt("quarantine_detail")?.unwrap_or_default(),
+        }))
+    }
+
+    fn mark_quarantined(&self, key: &str, quarantine: &Quarantine) -> Result<()> {
+        let sql = format!(
+            "INSERT INTO {} (path, quarantine_reason, quarantine_detail, quarantined_at) \
+             VALUES (?, ?, ?, UTC_TIMESTAMP()) \
+             ON DUPLICATE KEY UPDATE quarantine_reason = VALUES(quarantine_reason), \
+             quarantine_detail = VALUES(quarantine_detail), quarantined_at = VALUES(quarantined_at)",
+            self.table
+        );
+        self.db.exec(
+            &sql,
+            &[
+                Param::Str(key),
+                Param::Str(quarantine.reason.as_str()),
+                Param::Str(&quarantine.detail),
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+/// Scans uploads and quarantines flagged ones.
+pub struct QuarantineService {
+    storage: Arc<dyn FileStorage>,
+    scanner: Arc<dyn VirusScanner>,
+    store: Arc<dyn QuarantineStore>,
+    notifier: Option<Arc<dyn Notifier>>,
+    variant_cache: String,
+}
+
+impl QuarantineService {
+    /// Creates a service without admin notifications.
+    pub fn new(
+        storage: Arc<dyn FileStorage>,
+        scanner: Arc<dyn VirusScanner>,
+        store: Arc<dyn QuarantineStore>,
+    ) -> Self {
+        Self {
+            storage,
+            scanner,
+            store,
+            notifier: None,
+            variant_cache: DEFAULT_CACHE_PREFIX.into(),
+        }
+    }
+
+    /// Notifies admins of every quarantined file.
+    pub fn notifier(mut self, notifier: Arc<dyn Notifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Storage prefix of cached media variants to purge (default
+    /// [`DEFAULT_CACHE_PREFIX`]); match
+    /// [`MediaConfig::cache_prefix`](crate::web::upload::media::MediaConfig::cache_prefix).
+    pub fn variant_cache(mut self, prefix: impl Into<String>) -> Self {
+        self.variant_cache = prefix.into();
+        self
+    }
+
+    /// Storage key a quarantined file is moved to.
+    pub fn quarantined_key(key: &str) -> String {
+        format!("{QUARANTINE_PREFIX}/{}", key.trim_start_matches('/'))
+    }
+
+    /// Scans the file stored under `key`, quarantining it if flagged.
+    ///
+    /// A missing file counts as clean (it may have been deleted since it
+    /// was queued).
+    pub async fn scan(&self, key: &str) -> Result<ScanVerdict> {
+        let (storage, scanner) = (self.storage.clone(), self.scanner.clone());
+        let lookup = key.to_string();
+        let verdict = tokio::task::spawn_blocking(move || match storage.load(&lookup)? {
+            Some(bytes) => scanner.scan(&lookup, &bytes),
+            None => Ok(ScanVerdict::Clean),
+        })
+        .await
+        .context("scan task failed")??;
+
+        if let ScanVerdict::Infected(signature) = &verdict {
+            self.quarantine(key, QuarantineReason::Malware, signature)
+                .await?;
+        }
+        Ok(verdict)
+    }
+
+    /// Quarantines `key`: marks it, moves it under [`QUARANTINE_PREFIX`],
+    /// deletes its cached media variants and notifies admins.
+    ///
+    /// Variant purge and notification failures are logged, not returned.
+    pub async fn quarantine(
+        &self,
+        key: &str,
+        reason: QuarantineReason,
+        detail: &str,
+    ) -> Result<Quarantine> {
+        let record = Quarantine {
+            reason,
+            detail: detail.to_string(),
+        };
+        let (storage, store) = (self.storage.clone(), self.store.clone());
+        let (from, to) = (key.to_string(), Self::quarantined_key(key));
+        let marked = record.clone();
+        let cache = self.variant_cache.clone();
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            store.mark_quarantined(&from, &marked)?;
+            if let Some(bytes) = storage.load(&from)? {
+                storage.save(&to, &bytes)?;
+                storage.delete(&from)?;
+            }
+            if let Err(e) = purge_variants(storage.as_ref(), &cache, &from) {
+                warn!(key = from, error = %e, "failed to purge cached media variants");
+            }
+            Ok(())
+        })
+        .await
+        .context("quarantine task failed")??;
+
+        info!(key, reason = reason.as_str(), detail, "upload quarantined");
+        if let Some(notifier) = &self.notifier {
+            let message = format!(
+                "The upload `{key}` was quarantined ({}: {detail}).\n\
+                 It is no longer served and was moved to `{}`.\n",
+                reason.as_str(),
+                Self::quarantined_key(key)
+            );
+            if let Err(e) = notifier.notify("Upload quarantined", &message).await {
+                warn!(key, error = %e, "failed to notify admins of quarantined upload");
+            }
+        }
+        Ok(record)
+    }
+
+    /// Starts a background worker scanning queued keys one at a time.
+    ///
+    /// The worker stops once every [`ScanQueue`] handle is dropped.
+    pub fn spawn_worker(self: Arc<Self>, capacity: usize) -> ScanQueue {
+        let (tx, mut rx) = mpsc::channel::<String>(capacity.max(1));
+        tokio::spawn(async move {
+            while let Some(key) = rx.recv().await {
+                if let Err(e) = self.scan(&key).await {
+                    warn!(key, error = %e, "upload scan failed");
+                }
+            }
+        });
+        ScanQueue { tx }
+    }
+}
+
+/// Handle for queueing scan jobs; cheap to clone.
+#[derive(Clone)]
+pub struct ScanQueue {
+    tx: mpsc::Sender<String>,
+}
+
+impl ScanQueue {
+    /// Queues `key` for scanning, waiting while the queue is full.
+    ///
+    /// # Errors
+    /// Returns an error if the worker has stopped.
+    pub async fn enqueue(&self, key: impl Into<String>) -> Result<()> {
+        self.tx
+            .send(key.into())
+            .await
+            .map_err(|_| anyhow!("scan worker has stopped"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+
+    use crate::db::port::Row;
+
+    #[derive(Default)]
+    struct MemoryStorage {
+        files: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    impl FileStorage for MemoryStorage {
+        fn save(&self, rel_path: &str, bytes: &[u8]) -> Result<String> {
+            self.files
+                .lock()
+                .unwrap()
+                .insert(rel_path.to_string(), bytes.to_vec());
+            Ok(rel_path.to_string())
+        }
+
+        fn load(&self, rel_path: &str) -> Result<Option<Vec<u8>>> {
+            Ok(self.files.lock().unwrap().get(rel_path).cloned())
+        }
+
+        fn delete(&self, rel_path: &str) -> Result<bool> {
+            Ok(self.files.lock().unwrap().remove(rel_path).is_some())
+        }
+    }
+
+    /// Flags files containing `EICAR`.
+    struct SubstringScanner;
+
+    impl VirusScanner for SubstringScanner {
+        fn scan(&self, _key: &str, bytes: &[u8]) -> Result<ScanVerdict> {
+            Ok(if bytes.windows(5).any(|w| w == b"EICAR") {
+                ScanVerdict::Infected("Eicar-Test-Signature".into())
+            } else {
+                ScanVerdict::Clean
+            })
+        }
+    }
+
+    #[derive(Default)]
+    struct MemoryQuarantineStore {
+        records: Mutex<HashMap<String, Quarantine>>,
+    }
+
+    impl QuarantineStore for MemoryQuarantineStore {
+        fn quarantine(&self, key: &str) -> Result<Option<Quarantine>> {
+            Ok(self.records.lock().unwrap().get(key).cloned())
+        }
+
+        fn mark_quarantined(&self, key: &str, quarantine: &Quarantine) -> Result<()> {
+            self.records
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), quarantine.clone());
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingNotifier {
+        notices: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl Notifier for RecordingNotifier {
+        async fn notify(&self, subject: &str, message: &str) -> Result<()> {
+            self.notices
+                .lock()
+                .unwrap()
+                .push((subject.to_string(), message.to_string()));
+            Ok(())
+        }
+    }
+
+    struct Fixture {
+        storage: Arc<MemoryStorage>,
+        store: Arc<MemoryQuarantineStore>,
+        notifier: Arc<RecordingNotifier>,
+        service: QuarantineService,
+    }
+
+    fn fixture() -> Fixture {
+        let storage = Arc::new(MemoryStorage::default());
+        storage.save("files/ok.txt", b"hello").unwrap();
+        storage.save("files/bad.txt", b"xxEICARxx").unwrap();
+        let store = Arc::new(MemoryQuarantineStore::default());
+        let notifier = Arc::new(RecordingNotifier::default());
+        let service =
+            QuarantineService::new(storage.clone(), Arc::new(SubstringScanner), store.clone())
+                .notifier(notifier.clone());
+        Fixture {
+            storage,
+            store,
+            notifier,
+            service,
+        }
+    }
+
+    #[tokio::test]
+    async fn infected_files_are_marked_moved_and_reported() {
+        let f = fixture();
+        assert_eq!(
+            f.service.scan("files/ok.txt").await.unwrap(),
+            ScanVerdict::Clean
+        );
+        assert_eq!(
+            f.service.scan("files/bad.txt").await.unwrap(),
+            ScanVerdict::Infected("Eicar-Test-Signature".into())
+        );
+
+        let files = f.storage.files.lock().unwrap();
+        assert!(files.contains_key("files/ok.txt"));
+        assert!(!files.contains_key("files/bad.txt"));
+        assert_eq!(files["quarantine/files/bad.txt"], b"xxEICARxx");
+
+        let record = f.store.quarantine("files/bad.txt").unwrap().unwrap();
+        assert_eq!(record.reason, QuarantineReason::Malware);
+        assert_eq!(record.reason.status(), StatusCode::GONE);
+        assert_eq!(f.store.quarantine("files/ok.txt").unwrap(), None);
+
+        let notices = f.notifier.notices.lock().unwrap();
+        assert_eq!(notices.len(), 1);
+        assert!(notices[0].1.contains("files/bad.txt"));
+        assert!(notices[0].1.contains("Eicar-Test-Signature"));
+    }
+
+    #[tokio::test]
+    async fn queued_keys_are_scanned_by_the_worker() {
+        let f = fixture();
+        let store = f.store.clone();
+        let queue = Arc::new(f.service).spawn_worker(8);
+        queue.enqueue("files/bad.txt").await.unwrap();
+        queue.enqueue("files/missing.txt").await.unwrap();
+
+        for _ in 0..100 {
+            if store.quarantine("files/bad.txt").unwrap().is_some() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("queued file was not quarantined");
+    }
+
+    #[tokio::test]
+    async fn legal_holds_use_451() {
+        let f = fixture();
+        let record = f
+            .service
+            .quarantine("files/ok.txt", QuarantineReason::Legal, "DMCA-1234")
+            .await
+            .unwrap();
+        assert_eq!(
+            record.reason.status(),
+            StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS
+        );
+        assert!(f
+            .storage
+            .files
+            .lock()
+            .unwrap()
+            .contains_key("quarantine/files/ok.txt"));
+    }
+
+    #[derive(Default)]
+    struct RecordingDb {
+        row: Mutex<Option<Row>>,
+        execs: Mutex<Vec<(String, Vec<Value>)>>,
+    }
+
+    impl Db for RecordingDb {
+        fn fetch_one(&self, _sql: &str, _params: &[Param]) -> Result<Option<Row>> {
+            Ok(self.row.lock().unwrap().clone())
+        }
+
+        fn fetch_all(&self, _sql: &str, _params: &[Param]) -> Result<Vec<Row>> {
+            Ok(vec![])
+        }
+
+        fn exec(&self, sql: &str, params: &[Param]) -> Result<u64> {
+            self.execs.lock().unwrap().push((
+                sql.to_string(),
+                params.iter().map(Param::to_value).collect(),
+            ));
+            Ok(1)
+        }
+
+        fn exec_returning_last_insert_id(&self, _sql: &str, _params: &[Param]) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn db_store_upserts_and_reads_by_path() {
+        let db = Arc::new(RecordingDb::default());
+        let store = DbQuarantineStore::with_table(db.clone(), "media_files").unwrap();
+        store
+            .mark_quarantined(
+                "files/a.pdf",
+                &Quarantine {
+                    reason: QuarantineReason::Malware,
+                    detail: "Eicar".into(),
+                },
+            )
+            .unwrap();
+        let execs = db.execs.lock().unwrap();
+        assert!(execs[0]
+            .0
+            .starts_with("INSERT INTO media_files (path, quarantine_reason"));
+        assert!(matches!(&execs[0].1[1], Value::Str(r) if r == "malware"));
+
+        let mut row = Row::default();
+        row.insert("quarantine_reason", Value::Null);
+        row.insert("quarantine_detail", Value::Null);
+        *db.row.lock().unwrap() = Some(row.clone());
+        assert_eq!(store.quarantine("files/a.pdf").unwrap(), None);
+
+        row.insert("quarantine_reason", Value::Str("legal".into()));
+        row.insert("quarantine_detail", Value::Str("court order".into()));
+        *db.row.lock().unwrap() = Some(row);
+        let record = store.quarantine("files/a.pdf").unwrap().unwrap();
+        assert_eq!(record.reason, QuarantineReason::Legal);
+        assert_eq!(record.detail, "court order");
+
+        assert!(DbQuarantineStore::with_table(db.clone(), "uploads; DROP").is_err());
+    }
+}
@@ -23,6 +23,9 @@
 //! assert_eq!(saved.content_type, "text/plain");
 //! ```
 
+use std::io::{Cursor, Read};
+use std::time::SystemTime;
+
 use anyhow::Result;
 
 /// Metadata for a saved file.
@@ -49,6 +52,30 @@ impl SavedFile {
     }
 }
 
+/// An entry returned by [`FileStorage::list`] and [`FileStorage::stat`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoredObject {
+    /// Path relative to the storage root, `/`-separated.
+    pub path: String,
+    /// File size in bytes.
+    pub bytes: u64,
+    /// Last modification time.
+    pub modified: SystemTime,
+}
+
+/// Result of [`FileStorage::verify`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IntegrityStatus {
+    /// The content matches the checksum recorded at save time.
+    Ok,
+    /// The content changed since it was saved (hex SHA-256 digests).
+    Mismatch { expected: String, actual: String },
+    /// A checksum is recorded but the file is gone.
+    Missing,
+    /// No checksum was recorded for the file.
+    Unknown,
+}
+
 /// A trait defining a generic file storage backend.
 ///
 /// Implementors are responsible for saving file data and returning
@@ -63,6 +90,64 @@ pub trait FileStorage: Send + Sync {
     /// # Returns
     /// The full or relative path of the saved file.
     fn save(&self, rel_path: &str, bytes: &[u8]) -> Result<String>;
+
+    /// Saves a file by streaming its contents from `reader`.
+    ///
+    /// The default implementation buffers the whole stream and calls
+    /// [`save`](Self::save); backends that can write incrementally override it.
+    fn save_from(&self, rel_path: &str, reader: &mut dyn Read) -> Result<String> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        self.save(rel_path, &bytes)
+    }
+
+    /// Loads a previously saved file.
+    ///
+    /// Returns `Ok(None)` if nothing is stored at `rel_path`.
+    /// The default implementation reports that reading is unsupported.
+    fn load(&self, rel_path: &str) -> Result<Option<Vec<u8>>> {
+        anyhow::bail!("load is not supported by this storage: {rel_path}")
+    }
+
+    /// Opens a previously saved file for streaming reads.
+    ///
+    /// Returns `Ok(None)` if nothing is stored at `rel_path`.
+    /// The default implementation wraps [`load`](Self::load).
+    fn open(&self, rel_path: &str) -> Result<Option<Box<dyn Read + Send>>> {
+        Ok(self
+            .load(rel_path)?
+            .map(|bytes| Box::new(Cursor::new(bytes)) as Box<dyn Read + Send>))
+    }
+
+    /// Returns size and modification time of a file; `Ok(None)` if nothing
+    /// is stored at `rel_path`.
+    ///
+    /// The default implementation reports that this is unsupported.
+    fn stat(&self, rel_path: &str) -> Result<Option<StoredObject>> {
+        anyhow::bail!("stat is not supported by this storage: {rel_path}")
+    }
+
+    /// Lists all files under `prefix` (recursively).
+    ///
+    /// The default implementation reports that listing is unsupported.
+    fn list(&self, prefix: &str) -> Result<Vec<StoredObject>> {
+        anyhow::bail!("list is not supported by this storage: {prefix}")
+    }
+
+    /// Deletes a file; returns `Ok(false)` if it did not exist.
+    ///
+    /// The default implementation reports that deletion is unsupported.
+    fn delete(&self, rel_path: &str) -> Result<bool> {
+        anyhow::bail!("delete is not supported by this storage: {rel_path}")
+    }
+
+    /// Checks a file against the checksum recorded when it was saved.
+    ///
+    /// The default implementation reports that verification is unsupported;
+    /// see [`ChecksummedStorage`](crate::web::upload::integrity::ChecksummedStorage).
+    fn verify(&self, rel_path: &str) -> Result<IntegrityStatus> {
+        anyhow::bail!("verify is not supported by this storage: {rel_path}")
+    }
 }
 
 #[cfg(test)]
@@ -148,4 +233,30 @@ mod tests {
     fn dyn_filestorage_is_send_sync() {
         assert_send_sync::<dyn FileStorage>();
     }
+
+    #[test]
+    fn filestorage_load_defaults_to_unsupported() {
+        let storage = MockStorage::new("/root");
+        let err = storage.load("files/a.txt").unwrap_err();
+        assert!(format!("{err:#}").contains("not supported"));
+    }
+
+    #[test]
+    fn filestorage_list_and_delete_default_to_unsupported() {
+        let storage = MockStorage::new("/root");
+        assert!(storage.list("files").is_err());
+        assert!(storage.delete("files/a.txt").is_err());
+        assert!(storage.verify("files/a.txt").is_err());
+    }
+
+    #[test]
+    fn filestorage_save_from_defaults_to_buffered_save() {
+        let storage = MockStorage::new("/root");
+        let path = storage
+            .save_from("files/a.bin", &mut &b"streamed"[..])
+            .unwrap();
+        assert_eq!(path, "/root/files/a.bin");
+        assert_eq!(storage.calls(), vec![("files/a.bin".to_string(), 8)]);
+        assert!(storage.open("files/a.bin").is_err());
+    }
 }
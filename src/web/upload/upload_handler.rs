@@ -12,14 +12,18 @@
 use std::sync::Arc;
 
 use axum::{
-    http::{HeaderMap, StatusCode},
-    response::IntoResponse,
+    http::HeaderMap,
+    response::{IntoResponse, Response},
     Extension, Json,
 };
 use axum_extra::extract::{cookie::CookieJar, Multipart};
 use serde::Serialize;
 
+use crate::audit::context::AuditContext;
+use crate::audit::event::{actions, AuditEvent};
 use crate::config::csrf::CsrfConfig;
+use crate::error::app::AppError;
+use crate::graphql::context::TenantId;
 use crate::web::csrf;
 use crate::web::upload::uploader::{UploadImageParamsInput, UploadService};
 
@@ -35,6 +39,9 @@ struct UploadResp {
     bytes: u64,
     /// Final content type returned by the upload service.
     content_type: String,
+    /// Focal point (`"x,y"`) when one was supplied with an image.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    focal_point: Option<String>,
 }
 
 /// HTTP handler for multipart file uploads.
@@ -42,9 +49,12 @@ struct UploadResp {
 /// Behavior:
 ///
 /// - validates CSRF when enabled
+/// - records an audit event for CSRF rejections and upload results
+///   (when an `Arc<dyn AuditStore>` extension is present)
 /// - reads the `file` multipart field
 /// - optionally reads image resize parameters
-/// - delegates the actual upload to [`UploadService`]
+/// - delegates the actual upload to [`UploadService`], passing the
+///   [`TenantId`] extension (if any) to its key strategy
 /// - returns a JSON response on success
 ///
 /// # Returns
@@ -53,19 +63,25 @@ struct UploadResp {
 /// - `400 BAD REQUEST` for malformed multipart data or invalid image params
 /// - `401 UNAUTHORIZED` when CSRF validation fails
 /// - `500 INTERNAL SERVER ERROR` when the upload service fails
+///
+/// Errors are [`AppError`] problem documents (`application/problem+json`).
 pub async fn upload_handler(
     Extension(upload_uc): Extension<Arc<UploadService>>,
     Extension(enable_csrf): Extension<bool>,
     Extension(csrf_cfg): Extension<CsrfConfig>,
-    jar: CookieJar,
+    tenant: Option<Extension<TenantId>>,
+    audit: AuditContext,
     headers: HeaderMap,
     multipart: Multipart,
-) -> impl IntoResponse {
+) -> Result<Response, AppError> {
+    let jar = CookieJar::from_headers(&headers);
     if enable_csrf && !csrf::validate_csrf(&headers, &jar, &csrf_cfg) {
-        return (StatusCode::UNAUTHORIZED, "CSRF token missing or invalid").into_response();
+        audit.emit(AuditEvent::csrf_rejected(audit.path())).await;
+        return Err(AppError::unauthorized("CSRF token missing or invalid"));
     }
 
-    run_upload(upload_uc.as_ref(), multipart).await
+    let tenant = tenant.map(|Extension(TenantId(id))| id);
+    run_upload(upload_uc.as_ref(), tenant.as_deref(), &audit, multipart).await
 }
 
 /// A small trait used to make the upload execution path testable.
@@ -77,6 +93,7 @@ trait UploadUsecase: Send + Sync {
     /// Performs the upload and returns the upload result on success.
     fn upload(
         &self,
+        tenant: Option<&str>,
         filename: &str,
         content_type: &str,
         bytes: &[u8],
@@ -87,12 +104,13 @@ trait UploadUsecase: Send + Sync {
 impl UploadUsecase for UploadService {
     fn upload(
         &self,
+        tenant: Option<&str>,
         filename: &str,
         content_type: &str,
         bytes: &[u8],
         image_params: Option<crate::web::upload::uploader::UploadImageParams>,
     ) -> anyhow::Result<crate::web::upload::uploader::UploadResult> {
-        UploadService::upload(self, filename, content_type, bytes, image_params)
+        self.upload_for_tenant(tenant, filename, content_type, bytes, image_params)
     }
 }
 
@@ -103,8 +121,13 @@ impl UploadUsecase for UploadService {
 /// same logic with a mock upload use case.
 async fn run_upload(
     upload_uc: &dyn UploadUsecase,
+    tenant: Option<&str>,
+    audit: &AuditContext,
     mut multipart: Multipart,
-) -> axum::response::Response {
+) -> Result<Response, AppError> {
+    let read_error = |field: &str, e: &dyn std::fmt::Display| {
+        AppError::bad_request(format!("read {field} error: {e}"))
+    };
     let mut file_name = String::from("upload.bin");
     let mut content_type = String::new();
     let mut file_bytes: Option<Vec<u8>> = None;
@@ -126,98 +149,88 @@ async fn run_upload(
                     .map(|s| s.to_string())
                     .unwrap_or_else(|| "upload.bin".into());
 
-                match field.bytes().await {
-                    Ok(b) => file_bytes = Some(b.to_vec()),
-                    Err(e) => {
-                        return (
-                            StatusCode::BAD_REQUEST,
-                            format!("read file body error: {e}"),
-                        )
-                            .into_response();
-                    }
-                }
+                let bytes = field
+                    .bytes()
+                    .await
+                    .map_err(|e| read_error("file body", &e))?;
+                file_bytes = Some(bytes.to_vec());
+            }
+            "maxWidth" => {
+                let value = field.text().await.map_err(|e| read_error("maxWidth", &e))?;
+                image_params.max_width = Some(value);
+            }
+            "maxHeight" => {
+                let value = field
+                    .text()
+                    .await
+                    .map_err(|e| read_error("maxHeight", &e))?;
+                image_params.max_height = Some(value);
+            }
+            "upscale" => {
+                let value = field.text().await.map_err(|e| read_error("upscale", &e))?;
+                image_params.upscale = Some(value);
+            }
+            "resizeMode" => {
+                let value = field
+                    .text()
+                    .await
+                    .map_err(|e| read_error("resizeMode", &e))?;
+                image_params.resize_mode = Some(value);
+            }
+            "background" => {
+                let value = field
+                    .text()
+                    .await
+                    .map_err(|e| read_error("background", &e))?;
+                image_params.background = Some(value);
+            }
+            "focalPoint" => {
+                let value = field
+                    .text()
+                    .await
+                    .map_err(|e| read_error("focalPoint", &e))?;
+                image_params.focal_point = Some(value);
             }
-            "maxWidth" => match field.text().await {
-                Ok(v) => image_params.max_width = Some(v),
-                Err(e) => {
-                    return (StatusCode::BAD_REQUEST, format!("read maxWidth error: {e}"))
-                        .into_response();
-                }
-            },
-            "maxHeight" => match field.text().await {
-                Ok(v) => image_params.max_height = Some(v),
-                Err(e) => {
-                    return (
-                        StatusCode::BAD_REQUEST,
-                        format!("read maxHeight error: {e}"),
-                    )
-                        .into_response();
-                }
-            },
-            "upscale" => match field.text().await {
-                Ok(v) => image_params.upscale = Some(v),
-                Err(e) => {
-                    return (StatusCode::BAD_REQUEST, format!("read upscale error: {e}"))
-                        .into_response();
-                }
-            },
-            "resizeMode" => match field.text().await {
-                Ok(v) => image_params.resize_mode = Some(v),
-                Err(e) => {
-                    return (
-                        StatusCode::BAD_REQUEST,
-                        format!("read resizeMode error: {e}"),
-                    )
-                        .into_response();
-                }
-            },
-            "background" => match field.text().await {
-                Ok(v) => image_params.background = Some(v),
-                Err(e) => {
-                    return (
-                        StatusCode::BAD_REQUEST,
-                        format!("read background error: {e}"),
-                    )
-                        .into_response();
-                }
-            },
             _ => {
                 // Ignore unknown multipart fields for forward compatibility.
             }
         }
     }
 
-    let data = match file_bytes {
-        Some(b) => b,
-        None => return (StatusCode::BAD_REQUEST, "no file").into_response(),
-    };
+    let data = file_bytes.ok_or_else(|| AppError::bad_request("no file"))?;
 
-    let parsed_params = match image_params.parse() {
-        Ok(v) => v,
-        Err(e) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                format!("invalid image params: {e}"),
-            )
-                .into_response();
-        }
-    };
+    let parsed_params = image_params
+        .parse()
+        .map_err(|e| AppError::bad_request(format!("invalid image params: {e}")))?;
 
-    match upload_uc.upload(&file_name, &content_type, &data, parsed_params) {
+    match upload_uc.upload(tenant, &file_name, &content_type, &data, parsed_params) {
         Ok(saved) => {
+            audit
+                .emit(
+                    AuditEvent::new(actions::UPLOAD)
+                        .entity("upload", saved.key.clone())
+                        .detail(file_name.clone()),
+                )
+                .await;
             let resp = UploadResp {
                 path: format!("/{}", saved.key),
                 original_filename: file_name,
                 bytes: saved.bytes,
                 content_type: saved.content_type,
+                focal_point: saved.focal.map(|f| f.to_string()),
             };
-            Json(resp).into_response()
+            Ok(Json(resp).into_response())
+        }
+        Err(e) => {
+            audit
+                .emit(
+                    AuditEvent::new(actions::UPLOAD)
+                        .failed()
+                        .detail(format!("{file_name}: {e}")),
+                )
+                .await;
+            Err(AppError::Internal(e.context("save error")))
         }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("save error: {e}"),
-        )
-            .into_response(),
     }
 }
 
@@ -236,7 +249,7 @@ mod tests {
     use tower::ServiceExt;
 
     use crate::config::csrf::CsrfConfig;
-    use crate::image::processor::{BgColor, ResizeMode};
+    use crate::image::processor::{BgColor, FocalPoint, ResizeMode};
     use crate::web::upload::uploader::{UploadImageParams, UploadResult};
 
     /// Mock outcome for the upload use case.
@@ -255,6 +268,7 @@ mod tests {
     /// Recorded upload invocation.
     #[derive(Clone, Debug, PartialEq, Eq)]
     struct UploadCall {
+        tenant: Option<String>,
         filename: String,
         content_type: String,
         bytes: Vec<u8>,
@@ -287,12 +301,14 @@ mod tests {
     impl UploadUsecase for MockUploadService {
         fn upload(
             &self,
+            tenant: Option<&str>,
             filename: &str,
             content_type: &str,
             bytes: &[u8],
             image_params: Option<UploadImageParams>,
         ) -> anyhow::Result<UploadResult> {
             self.calls.lock().expect("lock calls").push(UploadCall {
+                tenant: tenant.map(str::to_string),
                 filename: filename.to_string(),
                 content_type: content_type.to_string(),
                 bytes: bytes.to_vec(),
@@ -321,15 +337,19 @@ mod tests {
             Extension(upload_uc): Extension<Arc<MockUploadService>>,
             Extension(enable_csrf): Extension<bool>,
             Extension(csrf_cfg): Extension<CsrfConfig>,
-            jar: CookieJar,
+            tenant: Option<Extension<TenantId>>,
+            audit: AuditContext,
             headers: HeaderMap,
             multipart: Multipart,
-        ) -> impl IntoResponse {
+        ) -> Result<Response, AppError> {
+            let jar = CookieJar::from_headers(&headers);
             if enable_csrf && !crate::web::csrf::validate_csrf(&headers, &jar, &csrf_cfg) {
-                return (StatusCode::UNAUTHORIZED, "CSRF token missing or invalid").into_response();
+                audit.emit(AuditEvent::csrf_rejected(audit.path())).await;
+                return Err(AppError::unauthorized("CSRF token missing or invalid"));
             }
 
-            run_upload(upload_uc.as_ref(), multipart).await
+            let tenant = tenant.map(|Extension(TenantId(id))| id);
+            run_upload(upload_uc.as_ref(), tenant.as_deref(), &audit, multipart).await
         }
 
         Router::new()
@@ -410,6 +430,7 @@ mod tests {
             abs_path: "/tmp/files/202603/test.txt".into(),
             bytes: 5,
             content_type: "text/plain".into(),
+            focal: None,
         }
     }
 
@@ -420,6 +441,7 @@ mod tests {
             abs_path: "/tmp/images/202603/test.png".into(),
             bytes: 12,
             content_type: "image/png".into(),
+            focal: None,
         }
     }
 
@@ -464,6 +486,41 @@ mod tests {
         assert_eq!(calls[0].content_type, "text/plain");
         assert_eq!(calls[0].bytes, b"hello");
         assert_eq!(calls[0].image_params, None);
+        assert_eq!(calls[0].tenant, None);
+    }
+
+    #[tokio::test]
+    async fn upload_handler_passes_tenant_extension() {
+        let upload_service = Arc::new(MockUploadService::ok(ok_result()));
+        let app = make_app_for_test(upload_service.clone(), false, test_csrf_config())
+            .layer(Extension(TenantId("acme".into())));
+
+        let boundary = "X-BOUNDARY";
+        let body = make_multipart_body(
+            boundary,
+            &[MultipartPart::File {
+                name: "file",
+                filename: "hello.txt",
+                content_type: "text/plain",
+                bytes: b"hello",
+            }],
+        );
+        let req = Request::builder()
+            .method("POST")
+            .uri("/upload")
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .expect("request");
+
+        let resp = app.oneshot(req).await.expect("response");
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            upload_service.take_calls()[0].tenant.as_deref(),
+            Some("acme")
+        );
     }
 
     #[tokio::test]
@@ -536,10 +593,78 @@ mod tests {
                 upscale: true,
                 resize_mode: ResizeMode::Contain,
                 background: BgColor::white(),
+                focal: None,
             })
         );
     }
 
+    #[tokio::test]
+    async fn upload_handler_passes_focal_point_and_echoes_it() {
+        let focal = FocalPoint::new(0.25, 0.5).unwrap();
+        let upload_service = Arc::new(MockUploadService::ok(UploadResult {
+            focal: Some(focal),
+            ..ok_image_result()
+        }));
+        let app = make_app_for_test(upload_service.clone(), false, test_csrf_config());
+
+        let boundary = "X-BOUNDARY";
+        let body = make_multipart_body(
+            boundary,
+            &[
+                MultipartPart::Text {
+                    name: "maxWidth",
+                    value: "400",
+                },
+                MultipartPart::Text {
+                    name: "maxHeight",
+                    value: "300",
+                },
+                MultipartPart::Text {
+                    name: "upscale",
+                    value: "false",
+                },
+                MultipartPart::Text {
+                    name: "resizeMode",
+                    value: "cover",
+                },
+                MultipartPart::Text {
+                    name: "background",
+                    value: "#ffffff",
+                },
+                MultipartPart::Text {
+                    name: "focalPoint",
+                    value: "0.25,0.5",
+                },
+                MultipartPart::File {
+                    name: "file",
+                    filename: "face.png",
+                    content_type: "image/png",
+                    bytes: b"png-bytes",
+                },
+            ],
+        );
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/upload")
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .expect("request");
+
+        let resp = app.oneshot(req).await.expect("response");
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(body_text(resp)
+            .await
+            .contains("\"focalPoint\":\"0.25,0.5\""));
+
+        let calls = upload_service.take_calls();
+        let params = calls[0].image_params.as_ref().expect("image params");
+        assert_eq!(params.focal, Some(focal));
+    }
+
     #[tokio::test]
     async fn upload_handler_returns_bad_request_when_file_is_missing() {
         let upload_service = Arc::new(MockUploadService::ok(ok_result()));
@@ -568,7 +693,7 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
 
         let body = body_text(resp).await;
-        assert_eq!(body, "no file");
+        assert!(body.contains("\"detail\":\"no file\""));
 
         let calls = upload_service.take_calls();
         assert!(calls.is_empty());
@@ -646,7 +771,10 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
 
         let body = body_text(resp).await;
-        assert!(body.contains("save error: disk full"));
+        assert!(
+            !body.contains("disk full"),
+            "internal errors are not exposed"
+        );
 
         let calls = upload_service.take_calls();
         assert_eq!(calls.len(), 1);
@@ -682,7 +810,7 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
 
         let body = body_text(resp).await;
-        assert_eq!(body, "CSRF token missing or invalid");
+        assert!(body.contains("\"detail\":\"CSRF token missing or invalid\""));
 
         let calls = upload_service.take_calls();
         assert!(calls.is_empty());
@@ -727,4 +855,116 @@ mod tests {
         assert_eq!(calls.len(), 1);
         assert_eq!(calls[0].filename, "hello.txt");
     }
+
+    /// In-memory audit store used to observe emitted events.
+    #[derive(Default)]
+    struct MemoryAuditStore {
+        events: Mutex<Vec<AuditEvent>>,
+    }
+
+    impl crate::audit::store::AuditStore for MemoryAuditStore {
+        fn record(&self, event: &AuditEvent) -> anyhow::Result<()> {
+            self.events.lock().expect("lock events").push(event.clone());
+            Ok(())
+        }
+
+        fn query(
+            &self,
+            query: &crate::audit::store::AuditQuery,
+        ) -> anyhow::Result<Vec<AuditEvent>> {
+            Ok(self
+                .events
+                .lock()
+                .expect("lock events")
+                .iter()
+                .filter(|e| query.matches(e))
+                .cloned()
+                .collect())
+        }
+    }
+
+    /// Builds a single-file upload request.
+    fn hello_upload_request() -> Request<Body> {
+        let boundary = "X-BOUNDARY";
+        let body = make_multipart_body(
+            boundary,
+            &[MultipartPart::File {
+                name: "file",
+                filename: "hello.txt",
+                content_type: "text/plain",
+                bytes: b"hello",
+            }],
+        );
+
+        Request::builder()
+            .method("POST")
+            .uri("/upload")
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .expect("request")
+    }
+
+    #[tokio::test]
+    async fn upload_handler_audits_successful_upload_with_actor() {
+        let upload_service = Arc::new(MockUploadService::ok(ok_result()));
+        let store = Arc::new(MemoryAuditStore::default());
+        let app = make_app_for_test(upload_service, false, test_csrf_config())
+            .layer(Extension(
+                store.clone() as Arc<dyn crate::audit::store::AuditStore>
+            ))
+            .layer(Extension(crate::auth::CurrentUser::new("42")));
+
+        let resp = app.oneshot(hello_upload_request()).await.expect("response");
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let events = store.events.lock().expect("lock events");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].action, actions::UPLOAD);
+        assert_eq!(events[0].actor.as_deref(), Some("42"));
+        assert_eq!(
+            events[0].entity_id.as_deref(),
+            Some("files/202603/test.txt")
+        );
+        assert_eq!(events[0].detail.as_deref(), Some("hello.txt"));
+    }
+
+    #[tokio::test]
+    async fn upload_handler_audits_failed_upload() {
+        let upload_service = Arc::new(MockUploadService::err("disk full"));
+        let store = Arc::new(MemoryAuditStore::default());
+        let app = make_app_for_test(upload_service, false, test_csrf_config()).layer(Extension(
+            store.clone() as Arc<dyn crate::audit::store::AuditStore>,
+        ));
+
+        let resp = app.oneshot(hello_upload_request()).await.expect("response");
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let events = store.events.lock().expect("lock events");
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].outcome,
+            crate::audit::event::AuditOutcome::Failure
+        );
+        assert_eq!(events[0].detail.as_deref(), Some("hello.txt: disk full"));
+    }
+
+    #[tokio::test]
+    async fn upload_handler_audits_csrf_rejection() {
+        let upload_service = Arc::new(MockUploadService::ok(ok_result()));
+        let store = Arc::new(MemoryAuditStore::default());
+        let app = make_app_for_test(upload_service, true, test_csrf_config()).layer(Extension(
+            store.clone() as Arc<dyn crate::audit::store::AuditStore>,
+        ));
+
+        let resp = app.oneshot(hello_upload_request()).await.expect("response");
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let events = store.events.lock().expect("lock events");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].action, actions::CSRF_REJECTED);
+        assert_eq!(events[0].detail.as_deref(), Some("/upload"));
+    }
 }
@@ -22,8 +22,11 @@
 //! - If `image_params` are not provided, the upload is stored as a regular file.
 //! - Image uploads are resized before saving.
 //! - Regular files are stored as-is.
-//! - Image uploads are stored under `image_dir/YYYYMM/...`.
-//! - Regular files are stored under `file_dir/YYYYMM/...`.
+//! - Image uploads are stored under `image_dir`, regular files under
+//!   `file_dir`; the rest of the key comes from the [`KeyStrategy`]
+//!   (`YYYYMM/...` by default, see [`keys`](super::keys)).
+//! - An optional focal point steers `cover` crops and, when a
+//!   [`FocalPointStore`] is configured, is recorded for later variants.
 
 use std::path::Path;
 use std::str::FromStr;
@@ -31,10 +34,12 @@ use std::sync::Arc;
 
 use anyhow::{bail, Context, Result};
 use chrono::Utc;
-use uuid::Uuid;
 
+use super::keys::{strategy_for, DatedKeys, KeyInput, KeyStrategy};
+use super::metadata::FocalPointStore;
 use super::storage::FileStorage;
-use crate::image::processor::{BgColor, ImageProcessor, ResizeMode, ResizeOpts};
+use crate::config::upload::UploadConfig;
+use crate::image::processor::{BgColor, FocalPoint, ImageProcessor, ResizeMode, ResizeOpts};
 
 /// Directory configuration for uploaded media.
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -77,6 +82,8 @@ pub struct UploadImageParams {
     pub resize_mode: ResizeMode,
     /// Background color used for contain mode padding.
     pub background: BgColor,
+    /// Subject position kept in frame by cover crops.
+    pub focal: Option<FocalPoint>,
 }
 
 impl UploadImageParams {
@@ -89,6 +96,7 @@ impl UploadImageParams {
             self.resize_mode,
             self.background,
         )
+        .with_focal(self.focal.unwrap_or_default())
     }
 }
 
@@ -109,6 +117,8 @@ pub struct UploadImageParamsInput {
     pub resize_mode: Option<String>,
     /// Raw `background` input.
     pub background: Option<String>,
+    /// Raw `focalPoint` input (`"x,y"` ratios, optional).
+    pub focal_point: Option<String>,
 }
 
 impl UploadImageParamsInput {
@@ -119,6 +129,7 @@ impl UploadImageParamsInput {
             || self.upscale.is_some()
             || self.resize_mode.is_some()
             || self.background.is_some()
+            || self.focal_point.is_some()
     }
 
     /// Parses raw input into typed image parameters.
@@ -138,6 +149,13 @@ impl UploadImageParamsInput {
         let upscale = parse_required_bool(self.upscale.as_deref(), "upscale")?;
         let resize_mode = parse_required_resize_mode(self.resize_mode.as_deref(), "resizeMode")?;
         let background = parse_required_bg_color(self.background.as_deref(), "background")?;
+        let focal = self
+            .focal_point
+            .as_deref()
+            .map(|raw| {
+                FocalPoint::from_str(raw).with_context(|| format!("invalid focalPoint: {raw}"))
+            })
+            .transpose()?;
 
         Ok(Some(UploadImageParams {
             max_width,
@@ -145,6 +163,7 @@ impl UploadImageParamsInput {
             upscale,
             resize_mode,
             background,
+            focal,
         }))
     }
 }
@@ -201,6 +220,8 @@ pub struct UploadResult {
     pub bytes: u64,
     /// Final content type recorded for the upload.
     pub content_type: String,
+    /// Focal point supplied with an image upload.
+    pub focal: Option<FocalPoint>,
 }
 
 /// Service for handling regular file uploads and image uploads.
@@ -219,6 +240,8 @@ pub struct UploadService {
     storage: Arc<dyn FileStorage>,
     image: Arc<dyn ImageProcessor>,
     dirs: MediaDirs,
+    keys: Arc<dyn KeyStrategy>,
+    focal_points: Option<Arc<dyn FocalPointStore>>,
 }
 
 impl UploadService {
@@ -228,6 +251,8 @@ impl UploadService {
             storage,
             image,
             dirs: MediaDirs::default(),
+            keys: Arc::new(DatedKeys),
+            focal_points: None,
         }
     }
 
@@ -241,9 +266,37 @@ impl UploadService {
             storage,
             image,
             dirs,
+            keys: Arc::new(DatedKeys),
+            focal_points: None,
         }
     }
 
+    /// Creates a new upload service with the directories and key scheme of `cfg`.
+    pub fn from_config(
+        storage: Arc<dyn FileStorage>,
+        image: Arc<dyn ImageProcessor>,
+        cfg: &UploadConfig,
+    ) -> Self {
+        Self::with_dirs(
+            storage,
+            image,
+            MediaDirs::new(cfg.image_dir(), cfg.file_dir()),
+        )
+        .key_strategy(strategy_for(cfg.key_scheme))
+    }
+
+    /// Names storage keys with `keys` instead of the dated default.
+    pub fn key_strategy(mut self, keys: Arc<dyn KeyStrategy>) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// Records focal points supplied with image uploads in `store`.
+    pub fn focal_points(mut self, store: Arc<dyn FocalPointStore>) -> Self {
+        self.focal_points = Some(store);
+        self
+    }
+
     /// Returns the configured media directories.
     pub fn dirs(&self) -> &MediaDirs {
         &self.dirs
@@ -259,10 +312,23 @@ impl UploadService {
         content_type: &str,
         bytes: &[u8],
         image_params: Option<UploadImageParams>,
+    ) -> Result<UploadResult> {
+        self.upload_for_tenant(None, filename, content_type, bytes, image_params)
+    }
+
+    /// Like [`upload`](Self::upload), passing the request's tenant to the
+    /// [`KeyStrategy`].
+    pub fn upload_for_tenant(
+        &self,
+        tenant: Option<&str>,
+        filename: &str,
+        content_type: &str,
+        bytes: &[u8],
+        image_params: Option<UploadImageParams>,
     ) -> Result<UploadResult> {
         match image_params {
-            Some(params) => self.upload_image(content_type, bytes, params),
-            None => self.upload_file(filename, content_type, bytes),
+            Some(params) => self.upload_image(tenant, filename, content_type, bytes, params),
+            None => self.upload_file(tenant, filename, content_type, bytes),
         }
     }
 
@@ -274,9 +340,13 @@ impl UploadService {
     ///
     /// - the content type is not supported as an image
     /// - image processing fails
+    /// - no storage key can be chosen
     /// - file persistence fails
+    /// - recording the focal point fails
     fn upload_image(
         &self,
+        tenant: Option<&str>,
+        filename: &str,
         content_type: &str,
         bytes: &[u8],
         params: UploadImageParams,
@@ -285,23 +355,36 @@ impl UploadService {
             bail!("content type is not supported as an image: {content_type}");
         }
 
-        let id = Uuid::new_v4().to_string();
-        let yyyymm = Utc::now().format("%Y%m").to_string();
-
         let (ext, norm_ct) = normalize_image_type(content_type);
         let resized = self
             .image
             .resize_same_format(bytes, norm_ct, params.to_resize_opts())
             .with_context(|| format!("process image as {norm_ct}"))?;
 
-        let key = format!("{}/{}/{}.{}", self.dirs.image_dir, yyyymm, id, ext);
+        let safe_name = sanitize_filename(filename);
+        let key = self.keys.key(&KeyInput {
+            dir: &self.dirs.image_dir,
+            filename: &safe_name,
+            ext,
+            bytes: &resized,
+            is_image: true,
+            tenant,
+            now: Utc::now(),
+        })?;
         let abs = self.storage.save(&key, &resized)?;
 
+        if let (Some(store), Some(focal)) = (&self.focal_points, params.focal) {
+            store
+                .set_focal_point(&key, focal)
+                .with_context(|| format!("record focal point for {key}"))?;
+        }
+
         Ok(UploadResult {
             key,
             abs_path: abs,
             bytes: resized.len() as u64,
             content_type: norm_ct.to_string(),
+            focal: params.focal,
         })
     }
 
@@ -309,24 +392,25 @@ impl UploadService {
     ///
     /// # Errors
     ///
-    /// Returns an error if file persistence fails.
+    /// Returns an error if no storage key can be chosen or file persistence fails.
     fn upload_file(
         &self,
+        tenant: Option<&str>,
         filename: &str,
         content_type: &str,
         bytes: &[u8],
     ) -> Result<UploadResult> {
-        let id = Uuid::new_v4().to_string();
-        let yyyymm = Utc::now().format("%Y%m").to_string();
-
         let safe_name = sanitize_filename(filename);
-        let final_name = if safe_name.is_empty() {
-            format!("{id}.bin")
-        } else {
-            safe_name
-        };
-
-        let key = format!("{}/{}/{}", self.dirs.file_dir, yyyymm, final_name);
+        let ext = file_extension(&safe_name);
+        let key = self.keys.key(&KeyInput {
+            dir: &self.dirs.file_dir,
+            filename: &safe_name,
+            ext: &ext,
+            bytes,
+            is_image: false,
+            tenant,
+            now: Utc::now(),
+        })?;
         let abs = self.storage.save(&key, bytes)?;
 
         Ok(UploadResult {
@@ -334,6 +418,7 @@ impl UploadService {
             abs_path: abs,
             bytes: bytes.len() as u64,
             content_type: content_type.to_string(),
+            focal: None,
         })
     }
 }
@@ -350,6 +435,17 @@ fn normalize_image_type(content_type: &str) -> (&'static str, &'static str) {
     }
 }
 
+/// Returns the lowercase extension of `filename`, or `"bin"` if it has none
+/// or it is not plain ASCII alphanumerics.
+fn file_extension(filename: &str) -> String {
+    Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .filter(|e| !e.is_empty() && e.len() <= 10 && e.chars().all(|c| c.is_ascii_alphanumeric()))
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_else(|| "bin".into())
+}
+
 /// Sanitizes a filename for safe storage.
 ///
 /// This function:
@@ -544,6 +640,7 @@ mod tests {
             upscale: true,
             resize_mode: ResizeMode::Contain,
             background: BgColor::white(),
+            focal: None,
         };
 
         let opts = params.to_resize_opts();
@@ -569,6 +666,7 @@ mod tests {
             upscale: Some("true".into()),
             resize_mode: Some("contain".into()),
             background: Some("#ffffffff".into()),
+            focal_point: None,
         };
 
         let parsed = input.parse().expect("parse").expect("some");
@@ -587,6 +685,7 @@ mod tests {
             upscale: Some("yes".into()),
             resize_mode: Some("fit".into()),
             background: Some("#00000000".into()),
+            focal_point: None,
         };
 
         let parsed = input.parse().expect("parse").expect("some");
@@ -612,6 +711,7 @@ mod tests {
             upscale: Some("true".into()),
             resize_mode: Some("contain".into()),
             background: Some("#ffffffff".into()),
+            focal_point: None,
         };
 
         let err = input.parse().expect_err("must reject invalid width");
@@ -626,6 +726,7 @@ mod tests {
             upscale: Some("maybe".into()),
             resize_mode: Some("contain".into()),
             background: Some("#ffffffff".into()),
+            focal_point: None,
         };
 
         let err = input.parse().expect_err("must reject invalid bool");
@@ -640,6 +741,7 @@ mod tests {
             upscale: Some("true".into()),
             resize_mode: Some("stretch".into()),
             background: Some("#ffffffff".into()),
+            focal_point: None,
         };
 
         let err = input.parse().expect_err("must reject invalid resize mode");
@@ -654,6 +756,7 @@ mod tests {
             upscale: Some("true".into()),
             resize_mode: Some("contain".into()),
             background: Some("white".into()),
+            focal_point: None,
         };
 
         let err = input.parse().expect_err("must reject invalid background");
@@ -682,6 +785,7 @@ mod tests {
             upscale: true,
             resize_mode: ResizeMode::Contain,
             background: BgColor::white(),
+            focal: None,
         };
 
         let out = svc
@@ -709,6 +813,76 @@ mod tests {
         assert_eq!(storage_calls[0].1, b"processed");
     }
 
+    /// Records focal points written by the upload service.
+    #[derive(Default)]
+    struct MockFocalPoints {
+        saved: Mutex<Vec<(String, FocalPoint)>>,
+    }
+
+    impl FocalPointStore for MockFocalPoints {
+        fn focal_point(&self, _key: &str) -> Result<Option<FocalPoint>> {
+            Ok(None)
+        }
+
+        fn set_focal_point(&self, key: &str, focal: FocalPoint) -> Result<()> {
+            self.saved
+                .lock()
+                .expect("lock saved")
+                .push((key.to_string(), focal));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn upload_image_applies_and_records_focal_point() {
+        let storage = Arc::new(MockStorage::new("/tmp/images/saved.png"));
+        let image = Arc::new(MockImageProcessor::new(true, b"processed".to_vec()));
+        let focal_points = Arc::new(MockFocalPoints::default());
+        let svc = make_service_with(storage, image.clone()).focal_points(focal_points.clone());
+
+        let focal = FocalPoint::new(0.2, 0.3).unwrap();
+        let params = UploadImageParams {
+            max_width: 400,
+            max_height: 300,
+            upscale: false,
+            resize_mode: ResizeMode::Cover,
+            background: BgColor::white(),
+            focal: Some(focal),
+        };
+
+        let out = svc
+            .upload("a.png", "image/png", b"raw-image", Some(params))
+            .expect("upload");
+
+        assert_eq!(out.focal, Some(focal));
+        assert_eq!(image.resize_calls()[0].2.focal, focal);
+        assert_eq!(
+            *focal_points.saved.lock().unwrap(),
+            vec![(out.key.clone(), focal)]
+        );
+    }
+
+    #[test]
+    fn parse_image_params_reads_optional_focal_point() {
+        let input = UploadImageParamsInput {
+            max_width: Some("400".into()),
+            max_height: Some("300".into()),
+            upscale: Some("false".into()),
+            resize_mode: Some("cover".into()),
+            background: Some("#ffffff".into()),
+            focal_point: Some("0.5,0.1".into()),
+        };
+        let parsed = input.clone().parse().expect("parse").expect("some");
+        assert_eq!(parsed.focal, Some(FocalPoint::new(0.5, 0.1).unwrap()));
+
+        let bad = UploadImageParamsInput {
+            focal_point: Some("2,0".into()),
+            ..input
+        };
+        let err = bad.parse().expect_err("out of range focal point");
+        assert!(err.to_string().contains("invalid focalPoint"));
+    }
+
     #[test]
     fn upload_image_normalizes_jpg_content_type() {
         let storage = Arc::new(MockStorage::new("/tmp/images/saved.jpg"));
@@ -721,6 +895,7 @@ mod tests {
             upscale: false,
             resize_mode: ResizeMode::Fit,
             background: BgColor::white(),
+            focal: None,
         };
 
         let out = svc
@@ -780,6 +955,7 @@ mod tests {
             upscale: true,
             resize_mode: ResizeMode::Contain,
             background: BgColor::white(),
+            focal: None,
         };
 
         let err = svc
@@ -812,6 +988,7 @@ mod tests {
             upscale: true,
             resize_mode: ResizeMode::Contain,
             background: BgColor::white(),
+            focal: None,
         };
 
         let err = svc
@@ -843,6 +1020,7 @@ mod tests {
             upscale: true,
             resize_mode: ResizeMode::Contain,
             background: BgColor::white(),
+            focal: None,
         };
 
         let err = svc
@@ -924,6 +1102,37 @@ mod tests {
         assert_eq!(storage_calls[0].1, b"pdf");
     }
 
+    #[test]
+    fn from_config_uses_configured_dirs_and_key_scheme() {
+        use crate::config::upload::KeyScheme;
+
+        let storage = Arc::new(MockStorage::new("/tmp/unused"));
+        let image = Arc::new(MockImageProcessor::new(true, b"processed".to_vec()));
+        let cfg =
+            UploadConfig::new("/tmp", "img", "docs").with_key_scheme(KeyScheme::TenantPrefixed);
+        let svc = UploadService::from_config(storage.clone(), image, &cfg);
+
+        let out = svc
+            .upload_for_tenant(Some("acme"), "Report.PDF", "application/pdf", b"pdf", None)
+            .expect("upload");
+        assert!(out.key.starts_with("acme/docs/"));
+        assert!(out.key.ends_with("/Report.PDF"));
+
+        let err = svc
+            .upload("Report.PDF", "application/pdf", b"pdf", None)
+            .expect_err("tenant required");
+        assert!(err.to_string().contains("tenant"));
+        assert_eq!(storage.calls().len(), 1);
+    }
+
+    #[test]
+    fn file_extension_falls_back_to_bin() {
+        assert_eq!(file_extension("Report.PDF"), "pdf");
+        assert_eq!(file_extension("archive.tar.gz"), "gz");
+        assert_eq!(file_extension("README"), "bin");
+        assert_eq!(file_extension("x.we ird"), "bin");
+    }
+
     #[test]
     fn sanitize_filename_removes_dangerous_characters() {
         assert_eq!(sanitize_filename("../../etc/passwd"), "passwd");
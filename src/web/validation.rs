@@ -0,0 +1,389 @@
+//! # Input Validation
+//!
+//! Field-level validation for request payloads:
+//!
+//! - [`Validate`] — implemented by payload types, usually with a
+//!   [`Validator`] collecting every failed check.
+//! - [`ValidatedJson`] — extractor deserializing JSON and validating it;
+//!   failures are answered with `422` and one [`FieldError`] per field.
+//! - [`Translator`] — hook localizing messages from the error `code` and
+//!   `params`, using the request [`Locale`].
+//!
+//! | Check | Code | Params |
+//! |-------|------|--------|
+//! | [`Validator::required`] | `required` | |
+//! | [`Validator::length`] | `too_short` / `too_long` | `min` / `max` |
+//! | [`Validator::email`] | `invalid_email` | |
+//! | [`Validator::range`] | `out_of_range` | `min`, `max` |
+//!
+//! Built-in messages are English; a translator returning `None` keeps them.
+//!
+//! # Example
+//! ```rust
+//! use std::sync::Arc;
+//! use axum::{routing::post, Extension, Router};
+//! use serde::Deserialize;
+//! use wzs_web::web::locale::Locale;
+//! use wzs_web::web::problem::FieldError;
+//! use wzs_web::web::validation::{
+//!     Translator, ValidatedJson, Validate, ValidationErrors, Validator,
+//! };
+//!
+//! #[derive(Deserialize)]
+//! struct SignUp {
+//!     email: String,
+//!     name: String,
+//! }
+//!
+//! impl Validate for SignUp {
+//!     fn validate(&self) -> Result<(), ValidationErrors> {
+//!         Validator::new()
+//!             .email("email", &self.email)
+//!             .length("name", &self.name, 1, 50)
+//!             .finish()
+//!     }
+//! }
+//!
+//! async fn sign_up(ValidatedJson(form): ValidatedJson<SignUp>) -> String {
+//!     form.email
+//! }
+//!
+//! let translator = |locale: &Locale, e: &FieldError| match (locale.language(), e.code.as_deref()) {
+//!     ("ja", Some("required")) => Some("入力してください".to_string()),
+//!     _ => None,
+//! };
+//!
+//! let app: Router = Router::new()
+//!     .route("/sign-up", post(sign_up))
+//!     .layer(Extension(Arc::new(translator) as Arc<dyn Translator>));
+//! # let _ = app;
+//! ```
+
+use std::fmt::Display;
+use std::sync::Arc;
+
+use axum::{
+    extract::{FromRequest, FromRequestParts, Request},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+use crate::error::app::AppError;
+use crate::web::forms::is_plausible_email;
+use crate::web::locale::Locale;
+use crate::web::problem::{FieldError, Problem};
+
+/// Failed checks of a payload.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Error)]
+#[error("{} invalid field(s)", .0.len())]
+pub struct ValidationErrors(pub Vec<FieldError>);
+
+impl ValidationErrors {
+    /// Replaces messages with `translator`'s text for `locale` where it has
+    /// one.
+    pub fn translate(mut self, translator: &dyn Translator, locale: &Locale) -> Self {
+        for error in &mut self.0 {
+            if let Some(message) = translator.translate(locale, error) {
+                error.message = message;
+            }
+        }
+        self
+    }
+}
+
+impl From<ValidationErrors> for AppError {
+    fn from(e: ValidationErrors) -> Self {
+        Self::Validation(e.0)
+    }
+}
+
+impl From<ValidationErrors> for Problem {
+    fn from(e: ValidationErrors) -> Self {
+        Self::validation(e.0)
+    }
+}
+
+/// A payload that can check itself.
+pub trait Validate {
+    /// Every failed check, or `Ok` when the payload is valid.
+    fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+/// Localizes validation messages.
+///
+/// Provide it as an `Extension<Arc<dyn Translator>>`; closures
+/// `Fn(&Locale, &FieldError) -> Option<String>` implement it.
+pub trait Translator: Send + Sync {
+    /// Message for `error` in `locale`, or `None` to keep the built-in text.
+    fn translate(&self, locale: &Locale, error: &FieldError) -> Option<String>;
+}
+
+impl<F> Translator for F
+where
+    F: Fn(&Locale, &FieldError) -> Option<String> + Send + Sync,
+{
+    fn translate(&self, locale: &Locale, error: &FieldError) -> Option<String> {
+        self(locale, error)
+    }
+}
+
+/// Collects failed checks.
+#[derive(Debug, Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fails when `value` is blank.
+    pub fn required(self, field: &str, value: &str) -> Self {
+        let ok = !value.trim().is_empty();
+        self.check(ok, || {
+            FieldError::new(field, "is required").code("required")
+        })
+    }
+
+    /// Fails when `value` has fewer than `min` or more than `max`
+    /// characters.
+    pub fn length(self, field: &str, value: &str, min: usize, max: usize) -> Self {
+        let len = value.trim().chars().count();
+        if len < min {
+            let message = format!("must be at least {min} characters");
+            return self.fail(
+                FieldError::new(field, message)
+                    .code("too_short")
+                    .param("min", min),
+            );
+        }
+        self.check(len <= max, || {
+            FieldError::new(field, format!("must be at most {max} characters"))
+                .code("too_long")
+                .param("max", max)
+        })
+    }
+
+    /// Fails when `value` is not a plausible email address.
+    pub fn email(self, field: &str, value: &str) -> Self {
+        self.check(is_plausible_email(value), || {
+            FieldError::new(field, "is not a valid email address").code("invalid_email")
+        })
+    }
+
+    /// Fails when `value` is outside `min..=max`.
+    pub fn range<T: PartialOrd + Display>(self, field: &str, value: T, min: T, max: T) -> Self {
+        let ok = value >= min && value <= max;
+        self.check(ok, || {
+            FieldError::new(field, format!("must be between {min} and {max}"))
+                .code("out_of_range")
+                .param("min", &min)
+                .param("max", &max)
+        })
+    }
+
+    /// Adds `error` unless `ok`.
+    pub fn check(self, ok: bool, error: impl FnOnce() -> FieldError) -> Self {
+        if ok {
+            self
+        } else {
+            self.fail(error())
+        }
+    }
+
+    /// Validates a nested payload, prefixing its fields with `field.`.
+    pub fn nested(mut self, field: &str, value: &impl Validate) -> Self {
+        if let Err(e) = value.validate() {
+            self.errors.extend(e.0.into_iter().map(|mut error| {
+                error.field = format!("{field}.{}", error.field);
+                error
+            }));
+        }
+        self
+    }
+
+    /// Adds a failed check.
+    pub fn fail(mut self, error: FieldError) -> Self {
+        self.errors.push(error);
+        self
+    }
+
+    pub fn finish(self) -> Result<(), ValidationErrors> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(self.errors))
+        }
+    }
+}
+
+/// JSON extractor that also runs [`Validate`].
+///
+/// Malformed JSON is rejected like [`Json`] (`400`/`415`/`422` with the
+/// parser's message); failed checks are rejected with `422` and the invalid
+/// fields, translated when a [`Translator`] extension is present.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let (mut parts, body) = req.into_parts();
+        let locale = match Locale::from_request_parts(&mut parts, state).await {
+            Ok(locale) => locale,
+            Err(never) => match never {},
+        };
+        let translator = parts.extensions.get::<Arc<dyn Translator>>().cloned();
+
+        let Json(value) = Json::<T>::from_request(Request::from_parts(parts, body), state)
+            .await
+            .map_err(|e| Problem::new(e.status()).detail(e.body_text()))?;
+        match (value.validate(), translator) {
+            (Ok(()), _) => Ok(Self(value)),
+            (Err(e), Some(t)) => Err(e.translate(t.as_ref(), &locale).into()),
+            (Err(e), None) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{
+        body::Body,
+        http::{header::CONTENT_TYPE, StatusCode},
+        routing::post,
+        Extension, Router,
+    };
+    use http_body_util::BodyExt;
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    #[derive(Deserialize)]
+    struct Address {
+        city: String,
+    }
+
+    impl Validate for Address {
+        fn validate(&self) -> Result<(), ValidationErrors> {
+            Validator::new().required("city", &self.city).finish()
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct SignUp {
+        email: String,
+        name: String,
+        age: u32,
+        address: Address,
+    }
+
+    impl Validate for SignUp {
+        fn validate(&self) -> Result<(), ValidationErrors> {
+            Validator::new()
+                .email("email", &self.email)
+                .length("name", &self.name, 2, 5)
+                .range("age", self.age, 18, 130)
+                .nested("address", &self.address)
+                .finish()
+        }
+    }
+
+    fn errors(body: &str) -> Vec<FieldError> {
+        let form: SignUp = serde_json::from_str(body).unwrap();
+        form.validate().err().unwrap_or_default().0
+    }
+
+    #[test]
+    fn collects_every_failed_check() {
+        let ok = r#"{"email":"a@b.io","name":"Ann","age":30,"address":{"city":"Oslo"}}"#;
+        assert!(errors(ok).is_empty());
+
+        let bad = r#"{"email":"nope","name":"Annabel","age":7,"address":{"city":" "}}"#;
+        let fields: Vec<(String, Option<String>)> =
+            errors(bad).into_iter().map(|e| (e.field, e.code)).collect();
+        assert_eq!(
+            fields,
+            [
+                ("email".into(), Some("invalid_email".into())),
+                ("name".into(), Some("too_long".into())),
+                ("age".into(), Some("out_of_range".into())),
+                ("address.city".into(), Some("required".into())),
+            ]
+        );
+
+        let short = errors(r#"{"email":"a@b.io","name":"A","age":18,"address":{"city":"x"}}"#);
+        assert_eq!(short[0].message, "must be at least 2 characters");
+        assert_eq!(short[0].params["min"], "2");
+    }
+
+    async fn post_json(app: Router, body: &str, lang: &str) -> (StatusCode, Value) {
+        let req = Request::post("/")
+            .header(CONTENT_TYPE, "application/json")
+            .header("accept-language", lang)
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        let status = res.status();
+        let bytes = res.into_body().collect().await.unwrap().to_bytes();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    fn app() -> Router {
+        let translator = |locale: &Locale, e: &FieldError| match (locale.language(), &e.code) {
+            ("ja", Some(code)) if code == "out_of_range" => Some(format!(
+                "{}〜{}の範囲で入力してください",
+                e.params["min"], e.params["max"]
+            )),
+            _ => None,
+        };
+        Router::new()
+            .route(
+                "/",
+                post(|ValidatedJson(f): ValidatedJson<SignUp>| async move { f.name }),
+            )
+            .layer(Extension(Arc::new(translator) as Arc<dyn Translator>))
+    }
+
+    #[tokio::test]
+    async fn extractor_rejects_with_field_errors() {
+        let body = r#"{"email":"a@b.io","name":"Ann","age":3,"address":{"city":"Oslo"}}"#;
+        let (status, json) = post_json(app(), body, "ja").await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            json["errors"],
+            json!([{
+                "field": "age",
+                "message": "18〜130の範囲で入力してください",
+                "code": "out_of_range",
+                "params": { "min": "18", "max": "130" },
+            }])
+        );
+
+        let (_, json) = post_json(app(), body, "en").await;
+        assert_eq!(json["errors"][0]["message"], "must be between 18 and 130");
+
+        let ok = r#"{"email":"a@b.io","name":"Ann","age":30,"address":{"city":"Oslo"}}"#;
+        assert_eq!(post_json(app(), ok, "en").await.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn malformed_json_is_a_problem() {
+        let (status, json) = post_json(app(), "{", "en").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["status"], 400);
+        assert!(json["detail"].as_str().is_some());
+    }
+}
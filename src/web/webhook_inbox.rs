@@ -0,0 +1,12 @@
+//! # Webhook Inbox
+//!
+//! Generic intake for third-party webhooks.
+//!
+//! - [`signature`] — [`SignatureVerifier`](signature::SignatureVerifier)
+//!   implementations for plain HMAC, GitHub-style and Stripe-style signatures
+//! - [`inbox`] — the [`WebhookInbox`](inbox::WebhookInbox) handler: raw-body
+//!   capture, verification, replay protection and dispatch to a
+//!   [`WebhookDispatcher`](inbox::WebhookDispatcher)
+
+pub mod inbox;
+pub mod signature;
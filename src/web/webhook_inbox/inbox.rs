@@ -0,0 +1,319 @@
+//! # Webhook Inbox Handler
+//!
+//! [`webhook_inbox_handler`] receives a webhook as raw bytes, verifies it
+//! with a [`SignatureVerifier`], drops replays and hands the delivery to a
+//! [`WebhookDispatcher`].
+//!
+//! | Outcome | Response |
+//! |---------|----------|
+//! | Dispatched | `200 OK` |
+//! | Already seen (replay) | `200 OK` (so the provider stops retrying) |
+//! | Bad or missing signature | `401 Unauthorized` |
+//! | Dispatcher error | `500 Internal Server Error` (replay key released so the retry is accepted) |
+//!
+//! The crate has no shared cache or event bus yet, so replay protection is a
+//! [`ReplayGuard`] port (with the in-process [`MemoryReplayGuard`]) and
+//! dispatch is the [`WebhookDispatcher`] port.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//! use axum::{routing::post, Router};
+//! use wzs_web::async_trait::async_trait;
+//! use wzs_web::web::webhook_inbox::inbox::{
+//!     webhook_inbox_handler, MemoryReplayGuard, WebhookDispatcher, WebhookEvent, WebhookInbox,
+//! };
+//! use wzs_web::web::webhook_inbox::signature::StripeVerifier;
+//!
+//! struct Billing;
+//!
+//! #[async_trait]
+//! impl WebhookDispatcher for Billing {
+//!     async fn dispatch(&self, event: WebhookEvent) -> anyhow::Result<()> {
+//!         println!("{} {} bytes", event.provider, event.body.len());
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let inbox = WebhookInbox::new(
+//!     "stripe",
+//!     Arc::new(StripeVerifier::new("whsec_...")),
+//!     Arc::new(Billing),
+//! )
+//! .replay_guard(Arc::new(MemoryReplayGuard::new(Duration::from_secs(24 * 3600))));
+//!
+//! let app: Router = Router::new()
+//!     .route("/webhooks/stripe", post(webhook_inbox_handler))
+//!     .with_state(Arc::new(inbox));
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::error::app::AppError;
+use crate::web::webhook_inbox::signature::SignatureVerifier;
+
+/// A verified webhook delivery.
+#[derive(Clone, Debug)]
+pub struct WebhookEvent {
+    /// Provider name given to [`WebhookInbox::new`].
+    pub provider: String,
+    /// Replay key returned by the verifier.
+    pub delivery_id: String,
+    /// Request headers.
+    pub headers: HeaderMap,
+    /// Raw request body, exactly as signed.
+    pub body: Bytes,
+}
+
+/// Receives verified deliveries.
+#[async_trait]
+pub trait WebhookDispatcher: Send + Sync {
+    async fn dispatch(&self, event: WebhookEvent) -> Result<()>;
+}
+
+/// Remembers delivery keys to reject replays.
+pub trait ReplayGuard: Send + Sync {
+    /// Records `key`; returns `false` if it was already recorded.
+    fn first_seen(&self, key: &str) -> bool;
+
+    /// Forgets `key` so a retry is accepted.
+    fn release(&self, key: &str);
+}
+
+/// In-process [`ReplayGuard`] keeping keys for `ttl`.
+pub struct MemoryReplayGuard {
+    ttl: Duration,
+    seen: Mutex<HashMap<String, Instant>>,
+}
+
+impl MemoryReplayGuard {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl ReplayGuard for MemoryReplayGuard {
+    fn first_seen(&self, key: &str) -> bool {
+        let mut seen = self.seen.lock().unwrap();
+        seen.retain(|_, at| at.elapsed() < self.ttl);
+        if seen.contains_key(key) {
+            return false;
+        }
+        seen.insert(key.to_string(), Instant::now());
+        true
+    }
+
+    fn release(&self, key: &str) {
+        self.seen.lock().unwrap().remove(key);
+    }
+}
+
+/// Configuration for one webhook endpoint, used as handler state.
+pub struct WebhookInbox {
+    provider: String,
+    verifier: Arc<dyn SignatureVerifier>,
+    dispatcher: Arc<dyn WebhookDispatcher>,
+    replay: Option<Arc<dyn ReplayGuard>>,
+}
+
+impl WebhookInbox {
+    /// Creates an inbox without replay protection.
+    pub fn new(
+        provider: impl Into<String>,
+        verifier: Arc<dyn SignatureVerifier>,
+        dispatcher: Arc<dyn WebhookDispatcher>,
+    ) -> Self {
+        Self {
+            provider: provider.into(),
+            verifier,
+            dispatcher,
+            replay: None,
+        }
+    }
+
+    /// Enables replay protection.
+    pub fn replay_guard(mut self, guard: Arc<dyn ReplayGuard>) -> Self {
+        self.replay = Some(guard);
+        self
+    }
+
+    /// Verifies and dispatches one delivery at `now_unix`.
+    pub async fn receive(&self, headers: HeaderMap, body: Bytes, now_unix: i64) -> Response {
+        let delivery_id = match self.verifier.verify(&headers, &body, now_unix) {
+            Ok(id) => id,
+            Err(e) => {
+                tracing::warn!(provider = %self.provider, error = %e, "webhook rejected");
+                return AppError::unauthorized("invalid signature").into_response();
+            }
+        };
+
+        let replay_key = format!("{}:{delivery_id}", self.provider);
+        let duplicate = self
+            .replay
+            .as_ref()
+            .is_some_and(|guard| !guard.first_seen(&replay_key));
+        if duplicate {
+            tracing::info!(provider = %self.provider, %delivery_id, "webhook replay ignored");
+            return (StatusCode::OK, "duplicate").into_response();
+        }
+
+        let event = WebhookEvent {
+            provider: self.provider.clone(),
+            delivery_id,
+            headers,
+            body,
+        };
+        if let Err(e) = self.dispatcher.dispatch(event).await {
+            if let Some(guard) = &self.replay {
+                guard.release(&replay_key);
+            }
+            let e = e.context(format!("{} webhook dispatch failed", self.provider));
+            return AppError::Internal(e).into_response();
+        }
+
+        (StatusCode::OK, "ok").into_response()
+    }
+}
+
+/// Axum handler for a [`WebhookInbox`] passed as `State<Arc<WebhookInbox>>`.
+///
+/// The body is taken as raw [`Bytes`] so the signature is checked against
+/// exactly what the provider sent.
+pub async fn webhook_inbox_handler(
+    State(inbox): State<Arc<WebhookInbox>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    inbox.receive(headers, body, now).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::webhook_inbox::signature::GithubVerifier;
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Mutex<Vec<WebhookEvent>>,
+        fail: std::sync::atomic::AtomicBool,
+    }
+
+    #[async_trait]
+    impl WebhookDispatcher for Recorder {
+        async fn dispatch(&self, event: WebhookEvent) -> Result<()> {
+            if self.fail.swap(false, std::sync::atomic::Ordering::SeqCst) {
+                anyhow::bail!("downstream unavailable");
+            }
+            self.events.lock().unwrap().push(event);
+            Ok(())
+        }
+    }
+
+    fn inbox(recorder: Arc<Recorder>) -> WebhookInbox {
+        WebhookInbox::new("github", Arc::new(GithubVerifier::new("s")), recorder)
+            .replay_guard(Arc::new(MemoryReplayGuard::new(Duration::from_secs(60))))
+    }
+
+    fn signed(body: &'static [u8], delivery: &str) -> (HeaderMap, Bytes) {
+        let mut h = HeaderMap::new();
+        let sig = GithubVerifier::new("s").sign(body);
+        h.insert("x-hub-signature-256", sig.parse().unwrap());
+        h.insert("x-github-delivery", delivery.parse().unwrap());
+        (h, Bytes::from_static(body))
+    }
+
+    #[tokio::test]
+    async fn dispatches_verified_delivery_once() {
+        let recorder = Arc::new(Recorder::default());
+        let inbox = inbox(recorder.clone());
+
+        let (h, b) = signed(b"{\"a\":1}", "d1");
+        assert_eq!(
+            inbox.receive(h.clone(), b.clone(), 0).await.status(),
+            StatusCode::OK
+        );
+        assert_eq!(inbox.receive(h, b, 0).await.status(), StatusCode::OK);
+
+        let events = recorder.events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].provider, "github");
+        assert_eq!(
+            format!("sha256={}", events[0].delivery_id),
+            GithubVerifier::new("s").sign(b"{\"a\":1}")
+        );
+        assert_eq!(&events[0].body[..], b"{\"a\":1}");
+    }
+
+    #[tokio::test]
+    async fn replays_under_a_new_delivery_id_are_ignored() {
+        let recorder = Arc::new(Recorder::default());
+        let inbox = inbox(recorder.clone());
+
+        let (h, b) = signed(b"{\"a\":1}", "d1");
+        assert_eq!(inbox.receive(h, b, 0).await.status(), StatusCode::OK);
+        let (h, b) = signed(b"{\"a\":1}", "d2");
+        assert_eq!(inbox.receive(h, b, 0).await.status(), StatusCode::OK);
+        assert_eq!(recorder.events.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_signature() {
+        let recorder = Arc::new(Recorder::default());
+        let (h, _) = signed(b"{}", "d1");
+
+        let res = inbox(recorder.clone())
+            .receive(h, Bytes::from_static(b"{\"forged\":true}"), 0)
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert!(recorder.events.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_dispatch_allows_retry() {
+        let recorder = Arc::new(Recorder::default());
+        recorder
+            .fail
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        let inbox = inbox(recorder.clone());
+
+        let (h, b) = signed(b"{}", "d2");
+        assert_eq!(
+            inbox.receive(h.clone(), b.clone(), 0).await.status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(inbox.receive(h, b, 0).await.status(), StatusCode::OK);
+        assert_eq!(recorder.events.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn memory_guard_expires_keys() {
+        let guard = MemoryReplayGuard::new(Duration::ZERO);
+        assert!(guard.first_seen("k"));
+        assert!(guard.first_seen("k"));
+
+        let guard = MemoryReplayGuard::new(Duration::from_secs(60));
+        assert!(guard.first_seen("k"));
+        assert!(!guard.first_seen("k"));
+        guard.release("k");
+        assert!(guard.first_seen("k"));
+    }
+}
@@ -0,0 +1,325 @@
+//! # Webhook Signature Verification
+//!
+//! Each verifier checks the raw request body against a shared secret and
+//! returns a *replay key* that identifies the delivery:
+//!
+//! | Verifier | Header | Signed payload | Replay key |
+//! |----------|--------|----------------|------------|
+//! | [`HmacVerifier`] | configurable, hex digest with optional prefix | body | digest |
+//! | [`GithubVerifier`] | `X-Hub-Signature-256: sha256=<hex>` | body | digest |
+//! | [`StripeVerifier`] | `Stripe-Signature: t=<unix>,v1=<hex>` | `"{t}.{body}"` | `t` and digest |
+//!
+//! Digests are HMAC-SHA256 and compared in constant time. Replay keys are
+//! always derived from the verified digest, never from an unsigned header
+//! such as `X-GitHub-Delivery`, so a captured delivery cannot be replayed
+//! under a new delivery id.
+//!
+//! # Example
+//! ```rust
+//! use axum::http::HeaderMap;
+//! use wzs_web::web::webhook_inbox::signature::{GithubVerifier, SignatureVerifier};
+//!
+//! let verifier = GithubVerifier::new("secret");
+//! let body = br#"{"action":"opened"}"#;
+//!
+//! let mut headers = HeaderMap::new();
+//! headers.insert("x-hub-signature-256", verifier.sign(body).parse().unwrap());
+//! headers.insert("x-github-delivery", "d-1".parse().unwrap());
+//!
+//! let key = verifier.verify(&headers, body, 0).unwrap();
+//! assert_eq!(format!("sha256={key}"), verifier.sign(body));
+//! ```
+
+use std::fmt::Write;
+
+use axum::http::HeaderMap;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Reasons a webhook signature is rejected.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SignatureError {
+    #[error("missing header: {0}")]
+    MissingHeader(String),
+    #[error("malformed signature header")]
+    Malformed,
+    #[error("signature mismatch")]
+    Mismatch,
+    #[error("signature timestamp outside tolerance")]
+    Expired,
+}
+
+/// Verifies a webhook delivery.
+pub trait SignatureVerifier: Send + Sync {
+    /// Checks `body` against the signature in `headers`.
+    ///
+    /// `now_unix` is the current Unix time in seconds, used by verifiers
+    /// with timestamp tolerance.
+    ///
+    /// Returns the replay key for the delivery.
+    fn verify(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        now_unix: i64,
+    ) -> Result<String, SignatureError>;
+}
+
+/// Plain HMAC-SHA256 over the body, hex encoded in a configurable header.
+#[derive(Clone)]
+pub struct HmacVerifier {
+    secret: Vec<u8>,
+    header: String,
+    prefix: String,
+}
+
+impl HmacVerifier {
+    /// Verifies `header: <hex digest>`.
+    pub fn new(secret: impl AsRef<[u8]>, header: impl Into<String>) -> Self {
+        Self {
+            secret: secret.as_ref().to_vec(),
+            header: header.into().to_ascii_lowercase(),
+            prefix: String::new(),
+        }
+    }
+
+    /// Expects the digest after `prefix` (e.g. `"sha256="`).
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Returns the header value for `body` (useful in tests and clients).
+    pub fn sign(&self, body: &[u8]) -> String {
+        format!("{}{}", self.prefix, hmac_hex(&self.secret, &[body]))
+    }
+}
+
+impl SignatureVerifier for HmacVerifier {
+    fn verify(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        _now: i64,
+    ) -> Result<String, SignatureError> {
+        let value = header(headers, &self.header)?;
+        let given = value
+            .strip_prefix(self.prefix.as_str())
+            .ok_or(SignatureError::Malformed)?;
+        let expected = hmac_hex(&self.secret, &[body]);
+        if !hex_eq(given, &expected) {
+            return Err(SignatureError::Mismatch);
+        }
+        Ok(expected)
+    }
+}
+
+/// GitHub-style signatures (`X-Hub-Signature-256: sha256=<hex>`).
+#[derive(Clone)]
+pub struct GithubVerifier {
+    hmac: HmacVerifier,
+}
+
+impl GithubVerifier {
+    pub const SIGNATURE_HEADER: &'static str = "x-hub-signature-256";
+    /// Delivery id header; not signed, so not used as the replay key.
+    pub const DELIVERY_HEADER: &'static str = "x-github-delivery";
+
+    /// Creates a verifier for the webhook `secret`.
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        Self {
+            hmac: HmacVerifier::new(secret, Self::SIGNATURE_HEADER).prefix("sha256="),
+        }
+    }
+
+    /// Returns the `X-Hub-Signature-256` value for `body`.
+    pub fn sign(&self, body: &[u8]) -> String {
+        self.hmac.sign(body)
+    }
+}
+
+impl SignatureVerifier for GithubVerifier {
+    fn verify(&self, headers: &HeaderMap, body: &[u8], now: i64) -> Result<String, SignatureError> {
+        self.hmac.verify(headers, body, now)
+    }
+}
+
+/// Stripe-style signatures (`Stripe-Signature: t=<unix>,v1=<hex>[,v1=...]`).
+#[derive(Clone)]
+pub struct StripeVerifier {
+    secret: Vec<u8>,
+    tolerance_secs: i64,
+}
+
+impl StripeVerifier {
+    pub const SIGNATURE_HEADER: &'static str = "stripe-signature";
+
+    /// Creates a verifier with the default 300-second tolerance.
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        Self {
+            secret: secret.as_ref().to_vec(),
+            tolerance_secs: 300,
+        }
+    }
+
+    /// Sets how far the signed timestamp may be from `now`.
+    pub fn tolerance_secs(mut self, secs: i64) -> Self {
+        self.tolerance_secs = secs;
+        self
+    }
+
+    /// Returns the `Stripe-Signature` value for `body` at `timestamp`.
+    pub fn sign(&self, body: &[u8], timestamp: i64) -> String {
+        let t = timestamp.to_string();
+        let v1 = hmac_hex(&self.secret, &[t.as_bytes(), b".", body]);
+        format!("t={t},v1={v1}")
+    }
+}
+
+impl SignatureVerifier for StripeVerifier {
+    fn verify(&self, headers: &HeaderMap, body: &[u8], now: i64) -> Result<String, SignatureError> {
+        let value = header(headers, Self::SIGNATURE_HEADER)?;
+
+        let mut timestamp = None;
+        let mut candidates = Vec::new();
+        for part in value.split(',') {
+            match part.trim().split_once('=') {
+                Some(("t", t)) => timestamp = Some(t),
+                Some(("v1", sig)) => candidates.push(sig),
+                _ => {}
+            }
+        }
+        let t = timestamp.ok_or(SignatureError::Malformed)?;
+        let ts: i64 = t.parse().map_err(|_| SignatureError::Malformed)?;
+        if candidates.is_empty() {
+            return Err(SignatureError::Malformed);
+        }
+
+        let expected = hmac_hex(&self.secret, &[t.as_bytes(), b".", body]);
+        if !candidates.iter().any(|c| hex_eq(c, &expected)) {
+            return Err(SignatureError::Mismatch);
+        }
+        if (now - ts).abs() > self.tolerance_secs {
+            return Err(SignatureError::Expired);
+        }
+        Ok(format!("{t}.{expected}"))
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, SignatureError> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .ok_or_else(|| SignatureError::MissingHeader(name.to_string()))
+}
+
+fn hmac_hex(secret: &[u8], parts: &[&[u8]]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC can take key of any size");
+    for p in parts {
+        mac.update(p);
+    }
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .fold(String::with_capacity(64), |mut s, b| {
+            let _ = write!(s, "{b:02x}");
+            s
+        })
+}
+
+fn hex_eq(given: &str, expected: &str) -> bool {
+    given
+        .to_ascii_lowercase()
+        .as_bytes()
+        .ct_eq(expected.as_bytes())
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut h = HeaderMap::new();
+        for (k, v) in pairs {
+            h.insert(*k, v.parse().unwrap());
+        }
+        h
+    }
+
+    #[test]
+    fn hmac_verifier_checks_digest_and_prefix() {
+        let v = HmacVerifier::new("s", "X-Signature").prefix("sha256=");
+        let sig = v.sign(b"body");
+
+        let key = v
+            .verify(&headers(&[("x-signature", &sig)]), b"body", 0)
+            .unwrap();
+        assert_eq!(format!("sha256={key}"), sig);
+
+        let upper = format!("sha256={}", key.to_uppercase());
+        assert!(v
+            .verify(&headers(&[("x-signature", &upper)]), b"body", 0)
+            .is_ok());
+
+        assert_eq!(
+            v.verify(&headers(&[("x-signature", &sig)]), b"tampered", 0),
+            Err(SignatureError::Mismatch)
+        );
+        assert_eq!(
+            v.verify(&headers(&[("x-signature", &key)]), b"body", 0),
+            Err(SignatureError::Malformed)
+        );
+        assert_eq!(
+            v.verify(&HeaderMap::new(), b"body", 0),
+            Err(SignatureError::MissingHeader("x-signature".into()))
+        );
+    }
+
+    #[test]
+    fn github_verifier_keys_replays_on_the_digest() {
+        // Example from GitHub's webhook documentation.
+        let v = GithubVerifier::new("It's a Secret to Everybody");
+        let sig = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+        assert_eq!(v.sign(b"Hello, World!"), sig);
+
+        let h = headers(&[("x-hub-signature-256", sig), ("x-github-delivery", "abc")]);
+        assert_eq!(v.verify(&h, b"Hello, World!", 0).unwrap(), &sig[7..]);
+
+        let h = headers(&[("x-hub-signature-256", sig), ("x-github-delivery", "xyz")]);
+        assert_eq!(v.verify(&h, b"Hello, World!", 0).unwrap(), &sig[7..]);
+
+        let h = headers(&[("x-hub-signature-256", sig)]);
+        assert_eq!(v.verify(&h, b"Hello, World!", 0).unwrap(), &sig[7..]);
+    }
+
+    #[test]
+    fn stripe_verifier_checks_timestamp_and_any_v1() {
+        let v = StripeVerifier::new("whsec_test");
+        let body = br#"{"id":"evt_1"}"#;
+        let sig = v.sign(body, 1_700_000_000);
+        let with_rotated = format!("{sig},v1=deadbeef,v0=ignored");
+
+        let h = headers(&[("stripe-signature", &with_rotated)]);
+        let key = v.verify(&h, body, 1_700_000_100).unwrap();
+        assert!(key.starts_with("1700000000."));
+
+        assert_eq!(
+            v.verify(&h, body, 1_700_000_301),
+            Err(SignatureError::Expired)
+        );
+        assert_eq!(
+            v.verify(&h, b"{}", 1_700_000_000),
+            Err(SignatureError::Mismatch)
+        );
+        assert_eq!(
+            v.verify(&headers(&[("stripe-signature", "v1=ab")]), body, 0),
+            Err(SignatureError::Malformed)
+        );
+    }
+}
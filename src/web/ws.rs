@@ -0,0 +1,565 @@
+//! # WebSockets
+//!
+//! Authenticated WebSocket endpoints with typed messages and server push:
+//!
+//! - [`ws_handler`] — upgrade handler authenticating the handshake like
+//!   [`graphql_post_handler`](crate::graphql::handler::graphql_post_handler)
+//!   (JWT cookie, `Authorization: Bearer` when enabled, revoked tokens
+//!   ignored) and running the [`WsEndpoint`]'s socket handler with a
+//!   [`WsContext`] holding the `CurrentUser`.
+//! - [`Envelope`] — JSON message `{"type": "...", "data": ...}`.
+//! - [`Hub`] — broadcast channel for pushing envelopes to every connected
+//!   socket, or to the sockets of one user.
+//!
+//! Browsers cannot attach headers to a WebSocket handshake, so CSRF tokens
+//! do not apply. Instead, cookie-authenticated handshakes must carry an
+//! `Origin` that is the request's own host or one of
+//! [`CsrfConfig::allowed_origins`] (when a `CsrfConfig` extension is
+//! present); a missing or other origin gets `403`. This keeps other sites
+//! from opening sockets with the user's cookie. Bearer-authenticated
+//! handshakes are not checked.
+//!
+//! # Example
+//! ```rust,no_run
+//! use axum::extract::ws::WebSocket;
+//! use axum::{routing::get, Extension, Router};
+//! use wzs_web::graphql::config::GraphqlAuthConfig;
+//! use wzs_web::web::ws::{ws_handler, Envelope, Hub, WsContext, WsEndpoint};
+//!
+//! async fn notifications(mut socket: WebSocket, ctx: WsContext, hub: Hub) {
+//!     let mut feed = hub.subscribe(&ctx);
+//!     loop {
+//!         tokio::select! {
+//!             Some(msg) = feed.recv() => {
+//!                 if socket.send(msg).await.is_err() {
+//!                     break;
+//!                 }
+//!             }
+//!             incoming = socket.recv() => match incoming {
+//!                 Some(Ok(msg)) => {
+//!                     if let Some(Ok(ping)) = Envelope::<String>::from_message(&msg) {
+//!                         let _ = hub.broadcast(&Envelope::new("echo", ping.data));
+//!                     }
+//!                 }
+//!                 _ => break,
+//!             },
+//!         }
+//!     }
+//! }
+//!
+//! let hub = Hub::new(64);
+//! let endpoint = WsEndpoint::new({
+//!     let hub = hub.clone();
+//!     move |socket, ctx| notifications(socket, ctx, hub.clone())
+//! });
+//!
+//! let app: Router = Router::new()
+//!     .route("/ws", get(ws_handler))
+//!     .layer(Extension(endpoint))
+//!     .layer(Extension(Some("jwt-secret".to_string())))
+//!     .layer(Extension(GraphqlAuthConfig::new("app_token")));
+//!
+//! // Elsewhere: push to one user's sockets.
+//! hub.send_to("42", &Envelope::new("invoice.paid", 1234)).unwrap();
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+use axum::{
+    extract::ws::{Message, Utf8Bytes, WebSocket, WebSocketUpgrade},
+    http::{header::ORIGIN, Extensions, HeaderMap},
+    response::Response,
+    Extension,
+};
+use axum_extra::extract::cookie::CookieJar;
+use futures_util::future::BoxFuture;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::auth::revocation::{unless_revoked, TokenRevocationStore};
+use crate::auth::CurrentUser;
+use crate::config::csrf::CsrfConfig;
+use crate::error::app::AppError;
+use crate::graphql::config::GraphqlAuthConfig;
+use crate::graphql::context::{extract_bearer_user, extract_cookie_user};
+use crate::web::csrf::origin_matches;
+use crate::web::middleware::request_id::RequestId;
+
+type SocketHandler = Arc<dyn Fn(WebSocket, WsContext) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Request-scoped values handed to a socket handler.
+#[derive(Clone, Debug, Default)]
+pub struct WsContext {
+    /// The authenticated principal; `None` only for
+    /// [anonymous](WsEndpoint::allow_anonymous) endpoints.
+    pub user: Option<CurrentUser>,
+    /// [`RequestId`] of the handshake, if the middleware ran.
+    pub request_id: Option<String>,
+}
+
+impl WsContext {
+    /// Subject of the authenticated user.
+    pub fn subject(&self) -> Option<&str> {
+        self.user.as_ref().map(|u| u.subject.as_str())
+    }
+}
+
+/// A WebSocket endpoint served by [`ws_handler`].
+#[derive(Clone)]
+pub struct WsEndpoint {
+    handler: SocketHandler,
+    allow_anonymous: bool,
+    max_message_size: Option<usize>,
+}
+
+impl fmt::Debug for WsEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WsEndpoint")
+            .field("allow_anonymous", &self.allow_anonymous)
+            .field("max_message_size", &self.max_message_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WsEndpoint {
+    /// Runs `handler` for every accepted socket; unauthenticated handshakes
+    /// are refused.
+    pub fn new<F, Fut>(handler: F) -> Self
+    where
+        F: Fn(WebSocket, WsContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        Self {
+            handler: Arc::new(move |socket, ctx| Box::pin(handler(socket, ctx))),
+            allow_anonymous: false,
+            max_message_size: None,
+        }
+    }
+
+    /// Also accepts handshakes without a valid token (`ctx.user` is `None`).
+    pub fn allow_anonymous(mut self, allow: bool) -> Self {
+        self.allow_anonymous = allow;
+        self
+    }
+
+    /// Largest message (in bytes) accepted from the client.
+    pub fn max_message_size(mut self, bytes: usize) -> Self {
+        self.max_message_size = Some(bytes);
+        self
+    }
+}
+
+/// WebSocket upgrade handler; see the [module docs](self).
+///
+/// # Required Extensions
+/// - `WsEndpoint`
+/// - `Option<String>` (JWT secret) and `GraphqlAuthConfig`, as for the
+///   GraphQL handler
+/// - `CsrfConfig` and `Arc<dyn TokenRevocationStore>` (optional)
+///
+/// # Returns
+/// - `101 SWITCHING PROTOCOLS` when the socket is accepted
+/// - `401 UNAUTHORIZED` without a valid token (unless anonymous sockets are
+///   allowed)
+/// - `403 FORBIDDEN` for a cookie-authenticated handshake without an
+///   `Origin`, or from an origin other than the host or the allowlist
+#[allow(clippy::too_many_arguments)]
+pub async fn ws_handler(
+    Extension(endpoint): Extension<WsEndpoint>,
+    Extension(jwt_secret): Extension<Option<String>>,
+    Extension(auth_cfg): Extension<GraphqlAuthConfig>,
+    csrf: Option<Extension<CsrfConfig>>,
+    revocation: Option<Extension<Arc<dyn TokenRevocationStore>>>,
+    headers: HeaderMap,
+    extensions: Extensions,
+    ws: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    let bearer_user = auth_cfg
+        .bearer_enabled()
+        .then(|| extract_bearer_user(&headers, jwt_secret.as_deref()))
+        .flatten();
+    let user = match bearer_user {
+        Some(user) => Some(user),
+        None => {
+            let jar = CookieJar::from_headers(&headers);
+            let user = extract_cookie_user(&jar, jwt_secret.as_deref(), &auth_cfg.jwt_cookie_name);
+            let allowed = csrf
+                .as_ref()
+                .and_then(|Extension(cfg)| cfg.allowed_origins.as_deref());
+            if user.is_some() && !handshake_origin_allowed(&headers, allowed.unwrap_or_default()) {
+                return Err(AppError::forbidden("origin not allowed"));
+            }
+            user
+        }
+    };
+    let user = unless_revoked(user, revocation.map(|Extension(store)| store)).await;
+    if user.is_none() && !endpoint.allow_anonymous {
+        return Err(AppError::unauthorized("authentication required"));
+    }
+
+    let ctx = WsContext {
+        user,
+        request_id: extensions.get::<RequestId>().map(|id| id.0.clone()),
+    };
+    let ws = match endpoint.max_message_size {
+        Some(bytes) => ws.max_message_size(bytes),
+        None => ws,
+    };
+    let handler = endpoint.handler.clone();
+    Ok(ws.on_upgrade(move |socket| handler(socket, ctx)))
+}
+
+/// Whether the handshake's `Origin` is its own host or one of `allowed`.
+///
+/// Browsers always send `Origin` with WebSocket handshakes, so unlike
+/// [`origin_allowed`](crate::web::csrf::origin_allowed) a missing one is
+/// refused and there is no `Referer` fallback.
+fn handshake_origin_allowed(headers: &HeaderMap, allowed: &[String]) -> bool {
+    headers
+        .get(ORIGIN)
+        .and_then(|v| v.to_str().ok())
+        .filter(|o| !o.is_empty() && *o != "null")
+        .map(|o| o.trim_end_matches('/').to_ascii_lowercase())
+        .is_some_and(|origin| origin_matches(headers, &origin, allowed))
+}
+
+/// A typed message: `{"type": "<kind>", "data": <data>}`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Envelope<T = serde_json::Value> {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub data: T,
+}
+
+impl<T> Envelope<T> {
+    pub fn new(kind: impl Into<String>, data: T) -> Self {
+        Self {
+            kind: kind.into(),
+            data,
+        }
+    }
+}
+
+impl<T: Serialize> Envelope<T> {
+    /// JSON text message.
+    pub fn to_message(&self) -> Result<Message, serde_json::Error> {
+        Ok(Message::Text(serde_json::to_string(self)?.into()))
+    }
+}
+
+impl<T: DeserializeOwned> Envelope<T> {
+    /// Parses a text or binary message; `None` for control frames.
+    pub fn from_message(msg: &Message) -> Option<Result<Self, serde_json::Error>> {
+        match msg {
+            Message::Text(text) => Some(serde_json::from_str(text.as_str())),
+            Message::Binary(bytes) => Some(serde_json::from_slice(bytes)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Push {
+    /// Subject the message is for; `None` for everyone.
+    to: Option<String>,
+    text: Utf8Bytes,
+}
+
+/// Fan-out of server-pushed messages to connected sockets.
+///
+/// Cloning is cheap; clones share the channel. Subscribers that fall more
+/// than `capacity` messages behind skip the oldest ones.
+#[derive(Clone, Debug)]
+pub struct Hub {
+    tx: broadcast::Sender<Arc<Push>>,
+}
+
+impl Hub {
+    /// Hub buffering up to `capacity` messages per subscriber.
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity.max(1));
+        Self { tx }
+    }
+
+    /// Pushes `envelope` to every subscriber; returns how many there are.
+    pub fn broadcast<T: Serialize>(&self, envelope: &Envelope<T>) -> serde_json::Result<usize> {
+        self.push(None, envelope)
+    }
+
+    /// Pushes `envelope` to the subscribers authenticated as `subject`.
+    pub fn send_to<T: Serialize>(
+        &self,
+        subject: &str,
+        envelope: &Envelope<T>,
+    ) -> serde_json::Result<usize> {
+        self.push(Some(subject.to_string()), envelope)
+    }
+
+    /// Subscribes the socket of `ctx` (broadcasts plus messages for its
+    /// user).
+    pub fn subscribe(&self, ctx: &WsContext) -> Subscription {
+        Subscription {
+            rx: self.tx.subscribe(),
+            subject: ctx.subject().map(str::to_string),
+        }
+    }
+
+    /// Number of live subscriptions.
+    pub fn subscribers(&self) -> usize {
+        self.tx.receiver_count()
+    }
+
+    fn push<T: Serialize>(
+        &self,
+        to: Option<String>,
+        envelope: &Envelope<T>,
+    ) -> serde_json::Result<usize> {
+        let text = serde_json::to_string(envelope)?.into();
+        Ok(self.tx.send(Arc::new(Push { to, text })).unwrap_or(0))
+    }
+}
+
+/// Messages a [`Hub`] pushes to one socket.
+#[derive(Debug)]
+pub struct Subscription {
+    rx: broadcast::Receiver<Arc<Push>>,
+    subject: Option<String>,
+}
+
+impl Subscription {
+    /// Next message for this socket; `None` once the hub is gone.
+    pub async fn recv(&mut self) -> Option<Message> {
+        loop {
+            match self.rx.recv().await {
+                Ok(push) => {
+                    let for_me = match &push.to {
+                        None => true,
+                        Some(to) => self.subject.as_ref() == Some(to),
+                    };
+                    if for_me {
+                        return Some(Message::Text(push.text.clone()));
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "websocket subscriber lagged; messages dropped");
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::SocketAddr;
+
+    use axum::{routing::get, Router};
+    use futures_util::{SinkExt, StreamExt};
+    use serde_json::json;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest};
+
+    use crate::auth::jwt::create_jwt;
+
+    const SECRET: &str = "ws-secret";
+
+    fn ctx(subject: Option<&str>) -> WsContext {
+        WsContext {
+            user: subject.map(CurrentUser::new),
+            request_id: None,
+        }
+    }
+
+    #[test]
+    fn envelopes_round_trip_as_json() {
+        let env = Envelope::new("chat", json!({ "text": "hi" }));
+        let msg = env.to_message().unwrap();
+        assert_eq!(
+            msg.to_text().unwrap(),
+            r#"{"type":"chat","data":{"text":"hi"}}"#
+        );
+        assert_eq!(Envelope::from_message(&msg).unwrap().unwrap(), env);
+
+        let typed = Envelope::<u32>::from_message(&Message::Binary(
+            br#"{"type":"n","data":7}"#.to_vec().into(),
+        ));
+        assert_eq!(typed.unwrap().unwrap(), Envelope::new("n", 7));
+        assert!(Envelope::<u32>::from_message(&Message::Text("{}".into()))
+            .unwrap()
+            .is_err());
+        assert!(Envelope::<u32>::from_message(&Message::Ping(Default::default())).is_none());
+    }
+
+    #[tokio::test]
+    async fn hub_pushes_broadcasts_and_targeted_messages() {
+        let hub = Hub::new(8);
+        assert_eq!(hub.broadcast(&Envelope::new("none", 0)).unwrap(), 0);
+
+        let mut alice = hub.subscribe(&ctx(Some("alice")));
+        let mut guest = hub.subscribe(&ctx(None));
+        assert_eq!(hub.subscribers(), 2);
+
+        hub.send_to("alice", &Envelope::new("private", 1)).unwrap();
+        hub.broadcast(&Envelope::new("public", 2)).unwrap();
+
+        let text = |m: Option<Message>| m.unwrap().into_text().unwrap().to_string();
+        assert_eq!(text(alice.recv().await), r#"{"type":"private","data":1}"#);
+        assert_eq!(text(alice.recv().await), r#"{"type":"public","data":2}"#);
+        assert_eq!(text(guest.recv().await), r#"{"type":"public","data":2}"#);
+
+        drop(hub);
+        assert!(guest.recv().await.is_none());
+    }
+
+    async fn serve(endpoint: WsEndpoint, csrf: Option<CsrfConfig>) -> SocketAddr {
+        let mut app = Router::new()
+            .route("/ws", get(ws_handler))
+            .layer(Extension(endpoint))
+            .layer(Extension(Some(SECRET.to_string())))
+            .layer(Extension(
+                GraphqlAuthConfig::new("auth").accept_bearer(true),
+            ));
+        if let Some(cfg) = csrf {
+            app = app.layer(Extension(cfg));
+        }
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        addr
+    }
+
+    fn greeter() -> WsEndpoint {
+        WsEndpoint::new(|mut socket: WebSocket, ctx: WsContext| async move {
+            let hello = Envelope::new("hello", ctx.subject().unwrap_or("anonymous"));
+            let _ = socket.send(hello.to_message().unwrap()).await;
+        })
+    }
+
+    async fn connect(addr: SocketAddr, headers: &[(&'static str, String)]) -> Result<String, u16> {
+        let mut req = format!("ws://{addr}/ws").into_client_request().unwrap();
+        for (name, value) in headers {
+            req.headers_mut().insert(*name, value.parse().unwrap());
+        }
+        match tokio_tungstenite::connect_async(req).await {
+            Ok((mut socket, _)) => {
+                let msg = socket.next().await.unwrap().unwrap();
+                let _ = socket.close(None).await;
+                Ok(msg.into_text().unwrap().to_string())
+            }
+            Err(tungstenite::Error::Http(res)) => Err(res.status().as_u16()),
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    fn cookie() -> (&'static str, String) {
+        let token = create_jwt(42, SECRET).unwrap();
+        ("cookie", format!("auth={}", json!({ "token": token })))
+    }
+
+    fn same_origin(addr: SocketAddr) -> (&'static str, String) {
+        ("origin", format!("http://{addr}"))
+    }
+
+    #[tokio::test]
+    async fn authenticates_the_handshake_like_graphql() {
+        let addr = serve(greeter(), None).await;
+        assert_eq!(connect(addr, &[]).await, Err(401));
+        assert_eq!(
+            connect(addr, &[cookie(), same_origin(addr)]).await.unwrap(),
+            r#"{"type":"hello","data":"42"}"#
+        );
+
+        let bearer = format!("Bearer {}", create_jwt(7, SECRET).unwrap());
+        assert_eq!(
+            connect(addr, &[("authorization", bearer)]).await.unwrap(),
+            r#"{"type":"hello","data":"7"}"#
+        );
+        let forged = format!("Bearer {}", create_jwt(7, "other").unwrap());
+        assert_eq!(connect(addr, &[("authorization", forged)]).await, Err(401));
+
+        let anonymous = serve(greeter().allow_anonymous(true), None).await;
+        assert_eq!(
+            connect(anonymous, &[]).await.unwrap(),
+            r#"{"type":"hello","data":"anonymous"}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn cookie_handshakes_from_other_origins_are_refused() {
+        let csrf = CsrfConfig {
+            allowed_origins: Some(vec!["https://app.example".into()]),
+            ..CsrfConfig::from_env_with(|_| None)
+        };
+        let addr = serve(greeter(), Some(csrf)).await;
+
+        let evil = ("origin", "https://evil.example".to_string());
+        assert_eq!(connect(addr, &[cookie(), evil.clone()]).await, Err(403));
+        let app = ("origin", "https://app.example".to_string());
+        assert!(connect(addr, &[cookie(), app]).await.is_ok());
+
+        let bearer = format!("Bearer {}", create_jwt(7, SECRET).unwrap());
+        assert!(connect(addr, &[("authorization", bearer), evil])
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn cookie_handshakes_need_a_same_origin_by_default() {
+        let addr = serve(greeter(), None).await;
+
+        assert_eq!(connect(addr, &[cookie()]).await, Err(403));
+        let evil = ("origin", "https://evil.example".to_string());
+        assert_eq!(connect(addr, &[cookie(), evil]).await, Err(403));
+        assert!(connect(addr, &[cookie(), same_origin(addr)]).await.is_ok());
+
+        let csrf = CsrfConfig::from_env_with(|_| None);
+        assert!(csrf.allowed_origins.is_none());
+        let addr = serve(greeter(), Some(csrf)).await;
+        assert_eq!(connect(addr, &[cookie()]).await, Err(403));
+        assert!(connect(addr, &[cookie(), same_origin(addr)]).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn hub_messages_reach_connected_sockets() {
+        let hub = Hub::new(8);
+        let endpoint = WsEndpoint::new({
+            let hub = hub.clone();
+            move |mut socket: WebSocket, ctx: WsContext| {
+                let mut feed = hub.subscribe(&ctx);
+                async move {
+                    let _ = socket.send(Message::Text("ready".into())).await;
+                    while let Some(msg) = feed.recv().await {
+                        if socket.send(msg).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        let addr = serve(endpoint, None).await;
+
+        let mut req = format!("ws://{addr}/ws").into_client_request().unwrap();
+        for (name, value) in [cookie(), same_origin(addr)] {
+            req.headers_mut().insert(name, value.parse().unwrap());
+        }
+        let (mut socket, _) = tokio_tungstenite::connect_async(req).await.unwrap();
+        assert_eq!(
+            socket.next().await.unwrap().unwrap().to_text().unwrap(),
+            "ready"
+        );
+
+        hub.send_to("someone-else", &Envelope::new("skip", 0))
+            .unwrap();
+        hub.send_to("42", &Envelope::new("paid", 1234)).unwrap();
+        let msg = socket.next().await.unwrap().unwrap();
+        assert_eq!(msg.to_text().unwrap(), r#"{"type":"paid","data":1234}"#);
+        socket
+            .send(tungstenite::Message::Close(None))
+            .await
+            .unwrap();
+    }
+}